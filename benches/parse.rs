@@ -0,0 +1,137 @@
+//! End-to-end benchmarks of the built binary on synthetic inputs. The large object
+//! checks that header-only work stays independent of file size (the file is mapped,
+//! never read as a whole); the directory benchmark covers many small files.
+//!
+//! Sizes can be changed through the environment:
+//! `ELFHP_BENCH_MB` (default 256), `ELFHP_BENCH_SECTIONS` (2000),
+//! `ELFHP_BENCH_SYMBOLS` (200000) and `ELFHP_BENCH_FILES` (500).
+
+#[path = "../src/synthetic.rs"]
+mod fixture;
+
+use std::env;
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+
+use fixture::Spec;
+
+fn knob(name: &str, default: usize) -> usize {
+    env::var(name)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(default)
+}
+
+fn fixture_dir() -> PathBuf {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("bench-fixtures");
+    fs::create_dir_all(&dir).expect("cannot create the fixture directory");
+    dir
+}
+
+/// Generates `path` unless an earlier run already did. The name encodes the
+/// parameters, and the file is renamed into place only once it is complete.
+fn generate(path: &Path, spec: &Spec) {
+    if path.exists() {
+        return;
+    }
+    let partial = path.with_extension("partial");
+    fixture::write_elf(&partial, spec).expect("cannot write a fixture");
+    fs::rename(&partial, path).expect("cannot move a fixture into place");
+}
+
+fn large_object() -> PathBuf {
+    let megabytes = knob("ELFHP_BENCH_MB", 256);
+    let sections = knob("ELFHP_BENCH_SECTIONS", 2000);
+    let symbols = knob("ELFHP_BENCH_SYMBOLS", 200_000);
+    let path = fixture_dir().join(format!(
+        "large-{}mb-{}sec-{}sym.o",
+        megabytes, sections, symbols
+    ));
+    generate(
+        &path,
+        &Spec {
+            executable: false,
+            sections,
+            symbols,
+            padding: (megabytes as u64) << 20,
+        },
+    );
+    path
+}
+
+fn executable_tree() -> (PathBuf, usize) {
+    let files = knob("ELFHP_BENCH_FILES", 500);
+    let dir = fixture_dir().join(format!("tree-{}", files));
+    for index in 0..files {
+        let subdir = dir.join(format!("d{}", index % 16));
+        fs::create_dir_all(&subdir).expect("cannot create the fixture directory");
+        generate(
+            &subdir.join(format!("bin{}", index)),
+            &Spec {
+                executable: true,
+                sections: 16,
+                symbols: 64,
+                padding: 4096,
+            },
+        );
+    }
+    (dir, files)
+}
+
+/// Runs the binary with `args`, discarding the report, and expects it to succeed.
+fn run<S: AsRef<OsStr>>(args: &[S]) {
+    let status = Command::new(env!("CARGO_BIN_EXE_elf-header-parser"))
+        .args(args)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .expect("cannot run elf-header-parser");
+    assert!(status.success(), "elf-header-parser failed: {}", status);
+}
+
+fn large_file(c: &mut Criterion) {
+    let path = large_object();
+    let path = path.as_os_str();
+    let mut group = c.benchmark_group("large_object");
+    group.sample_size(10);
+    group.bench_function("header_only", |b| {
+        b.iter(|| run(&[OsStr::new("--format"), OsStr::new("quiet"), path]))
+    });
+    group.bench_function("sections_and_symbols", |b| {
+        b.iter(|| {
+            run(&[
+                OsStr::new("obj-summary"),
+                OsStr::new("--format"),
+                OsStr::new("quiet"),
+                path,
+            ])
+        })
+    });
+    group.finish();
+}
+
+fn directory_scan(c: &mut Criterion) {
+    let (dir, files) = executable_tree();
+    let dir = dir.as_os_str();
+    let mut group = c.benchmark_group("directory_scan");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(files as u64));
+    group.bench_function("linkage", |b| {
+        b.iter(|| {
+            run(&[
+                OsStr::new("linkage"),
+                OsStr::new("--format"),
+                OsStr::new("quiet"),
+                dir,
+            ])
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, large_file, directory_scan);
+criterion_main!(benches);
@@ -0,0 +1,90 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+/// The commit being built, or `unknown` outside a git checkout such as a
+/// release tarball. `ELFHP_GIT_HASH` overrides it for packagers.
+fn git_hash() -> String {
+    println!("cargo:rerun-if-env-changed=ELFHP_GIT_HASH");
+    if let Ok(hash) = env::var("ELFHP_GIT_HASH") {
+        return hash;
+    }
+    // HEAD names the branch; the branch's ref file changes with each commit,
+    // or packed-refs once the refs are packed. Cargo reruns the script on
+    // every build for a path that does not exist, so only those that do are
+    // watched.
+    let mut watched = vec![".git/HEAD".to_string(), ".git/packed-refs".to_string()];
+    if let Some(branch) = fs::read_to_string(".git/HEAD").ok().and_then(|head| {
+        head.strip_prefix("ref: ")
+            .map(|name| name.trim().to_string())
+    }) {
+        watched.push(format!(".git/{}", branch));
+    }
+    for path in watched.iter().filter(|path| Path::new(path).exists()) {
+        println!("cargo:rerun-if-changed={}", path);
+    }
+    Command::new("git")
+        .args(["rev-parse", "--short=12", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Records what the binary was built from for `--version` and the reports:
+/// the commit, the target triple and the enabled features.
+fn build_info() {
+    println!("cargo:rustc-env=ELFHP_GIT_HASH={}", git_hash());
+    println!(
+        "cargo:rustc-env=ELFHP_TARGET={}",
+        env::var("TARGET").unwrap_or_default()
+    );
+    let mut features: Vec<String> = env::vars()
+        .filter_map(|(name, _)| {
+            let feature = name.strip_prefix("CARGO_FEATURE_")?;
+            Some(feature.to_lowercase().replace('_', "-"))
+        })
+        .collect();
+    features.sort();
+    println!("cargo:rustc-env=ELFHP_FEATURES={}", features.join(","));
+}
+
+/// Generates the e_machine lookup table from `machines.tsv`.
+fn main() {
+    build_info();
+    println!("cargo:rerun-if-changed=machines.tsv");
+    let registry = fs::read_to_string("machines.tsv").expect("cannot read machines.tsv");
+    let mut table = String::from("pub const MACHINES: &[Machine] = &[\n");
+    let mut previous = None;
+    for (number, line) in registry.lines().enumerate() {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = line.split('\t').collect();
+        let [id, constant, name] = fields[..] else {
+            panic!(
+                "machines.tsv:{}: expected three tab-separated fields",
+                number + 1
+            );
+        };
+        let id: u16 = id
+            .parse()
+            .unwrap_or_else(|_| panic!("machines.tsv:{}: invalid value {}", number + 1, id));
+        if previous.is_some_and(|previous| previous >= id) {
+            panic!(
+                "machines.tsv:{}: values must be sorted and unique",
+                number + 1
+            );
+        }
+        previous = Some(id);
+        table.push_str(&format!(
+            "    Machine {{ id: {}, constant: {:?}, name: {:?} }},\n",
+            id, constant, name
+        ));
+    }
+    table.push_str("];\n");
+    let out = Path::new(&env::var("OUT_DIR").unwrap()).join("machines.rs");
+    fs::write(out, table).expect("cannot write the machine table");
+}
@@ -0,0 +1,39 @@
+//! Writes a synthetic x86-64 object for profiling outside the benchmarks:
+//!
+//! `cargo run --release --example synthetic_elf -- <OUT> [MB] [SECTIONS] [SYMBOLS] [--exec]`
+
+#[path = "../src/synthetic.rs"]
+mod fixture;
+
+use std::env;
+use std::path::Path;
+use std::process;
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let executable = args.iter().any(|arg| arg == "--exec");
+    let args: Vec<&String> = args.iter().filter(|arg| *arg != "--exec").collect();
+    let Some(out) = args.first() else {
+        eprintln!("usage: synthetic_elf <OUT> [MB] [SECTIONS] [SYMBOLS] [--exec]");
+        process::exit(2);
+    };
+    let number = |index: usize, default: usize| -> usize {
+        match args.get(index) {
+            Some(arg) => arg.parse().unwrap_or_else(|_| {
+                eprintln!("not a number: {}", arg);
+                process::exit(2);
+            }),
+            None => default,
+        }
+    };
+    let spec = fixture::Spec {
+        executable,
+        padding: (number(1, 256) as u64) << 20,
+        sections: number(2, 2000),
+        symbols: number(3, 200_000),
+    };
+    if let Err(error) = fixture::write_elf(Path::new(out.as_str()), &spec) {
+        eprintln!("{}: {}", out, error);
+        process::exit(1);
+    }
+}
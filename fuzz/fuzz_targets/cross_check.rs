@@ -0,0 +1,24 @@
+//! Parses each input with this crate and with `object`, failing on any field
+//! the two read differently:
+//!
+//! `cargo +nightly fuzz run cross_check`
+
+#![no_main]
+
+use elf_header_parser::crosscheck;
+use elf_header_parser::Loader;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let loader = Loader::from_bytes(data.to_vec());
+    // Inputs object rejects have nothing to compare.
+    if let Ok(differences) = crosscheck::differences(&loader) {
+        if let Some(first) = differences.first() {
+            panic!(
+                "{} field(s) read differently, first {}",
+                differences.len(),
+                first
+            );
+        }
+    }
+});
@@ -0,0 +1,172 @@
+/* Generated by cbindgen from src/ffi.rs, do not edit. */
+
+#ifndef ELFHP_H
+#define ELFHP_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * An open ELF file.
+ */
+typedef struct ElfhpFile ElfhpFile;
+
+/**
+ * Walks the section headers, section 0 included.
+ */
+typedef struct ElfhpSectionIter ElfhpSectionIter;
+
+/**
+ * Walks `.symtab` or `.dynsym` in table order.
+ */
+typedef struct ElfhpSymbolIter ElfhpSymbolIter;
+
+/**
+ * The ELF header as stored in the file, widened to the ELF64 field sizes.
+ */
+typedef struct ElfhpHeader {
+  /**
+   * EI_CLASS: 1 for ELF32, 2 for ELF64.
+   */
+  uint8_t ei_class;
+  /**
+   * EI_DATA: 1 for little endian, 2 for big endian.
+   */
+  uint8_t ei_data;
+  uint8_t ei_version;
+  uint8_t ei_osabi;
+  uint16_t e_type;
+  uint16_t e_machine;
+  uint32_t e_version;
+  uint64_t e_entry;
+  uint64_t e_phoff;
+  uint64_t e_shoff;
+  uint32_t e_flags;
+  uint16_t e_ehsize;
+  uint16_t e_phentsize;
+  uint16_t e_phnum;
+  uint16_t e_shentsize;
+  uint16_t e_shnum;
+  uint16_t e_shstrndx;
+} ElfhpHeader;
+
+/**
+ * One section header. `name` stays valid until the iterator is freed.
+ */
+typedef struct ElfhpSection {
+  uint32_t index;
+  const char *name;
+  uint32_t sh_type;
+  uint64_t sh_flags;
+  uint64_t sh_addr;
+  uint64_t sh_offset;
+  uint64_t sh_size;
+  uint32_t sh_link;
+  uint32_t sh_info;
+  uint64_t sh_addralign;
+  uint64_t sh_entsize;
+} ElfhpSection;
+
+/**
+ * One symbol table entry. `name` stays valid until the iterator is freed.
+ */
+typedef struct ElfhpSymbol {
+  const char *name;
+  uint64_t st_value;
+  uint64_t st_size;
+  uint8_t st_info;
+  uint8_t st_other;
+  uint16_t st_shndx;
+} ElfhpSymbol;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * The message of the last call on this thread that failed, or NULL.
+ */
+const char *elfhp_last_error(void);
+
+/**
+ * Maps the ELF file at `path`. Returns NULL if it cannot be read, is not ELF or
+ * its header is truncated.
+ *
+ * # Safety
+ * `path` must be NULL or a NUL-terminated string.
+ */
+ElfhpFile *elfhp_open(const char *path);
+
+/**
+ * Unmaps a file from `elfhp_open`. NULL is ignored.
+ *
+ * # Safety
+ * `file` must be NULL or come from `elfhp_open`, and not be used afterwards.
+ */
+void elfhp_close(ElfhpFile *file);
+
+/**
+ * Fills `out` with the ELF header of `file`.
+ *
+ * # Safety
+ * `file` must come from `elfhp_open`; `out` must point to writable memory.
+ */
+bool elfhp_header(const ElfhpFile *file, ElfhpHeader *out);
+
+/**
+ * Starts a walk over the section headers of `file`. The iterator owns what it
+ * hands out, so `file` may be closed first. Free it with `elfhp_sections_iter_free`.
+ *
+ * # Safety
+ * `file` must come from `elfhp_open`.
+ */
+ElfhpSectionIter *elfhp_sections_iter(const ElfhpFile *file);
+
+/**
+ * Fills `out` with the next section header; returns false after the last one.
+ *
+ * # Safety
+ * `iter` must come from `elfhp_sections_iter`; `out` must point to writable memory.
+ */
+bool elfhp_sections_next(ElfhpSectionIter *iter, ElfhpSection *out);
+
+/**
+ * Frees a section iterator and the names it handed out. NULL is ignored.
+ *
+ * # Safety
+ * `iter` must be NULL or come from `elfhp_sections_iter`, and not be used afterwards.
+ */
+void elfhp_sections_iter_free(ElfhpSectionIter *iter);
+
+/**
+ * Starts a walk over `.dynsym` if `dynamic` is true, else `.symtab`. A file
+ * without that table gives an empty walk. Free it with `elfhp_symbols_iter_free`.
+ *
+ * # Safety
+ * `file` must come from `elfhp_open`.
+ */
+ElfhpSymbolIter *elfhp_symbols_iter(const ElfhpFile *file, bool dynamic);
+
+/**
+ * Fills `out` with the next symbol; returns false after the last one.
+ *
+ * # Safety
+ * `iter` must come from `elfhp_symbols_iter`; `out` must point to writable memory.
+ */
+bool elfhp_symbols_next(ElfhpSymbolIter *iter, ElfhpSymbol *out);
+
+/**
+ * Frees a symbol iterator and the names it handed out. NULL is ignored.
+ *
+ * # Safety
+ * `iter` must be NULL or come from `elfhp_symbols_iter`, and not be used afterwards.
+ */
+void elfhp_symbols_iter_free(ElfhpSymbolIter *iter);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif // __cplusplus
+
+#endif  /* ELFHP_H */
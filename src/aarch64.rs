@@ -0,0 +1,65 @@
+use crate::arch::{bit_name, ArchExt, TextModel};
+use crate::Loader;
+
+pub(crate) const EM_AARCH64: u16 = 183;
+
+/// The HWCAP_* bits of arch/arm64/include/uapi/asm/hwcap.h.
+const AARCH64_HWCAP: [&str; 32] = [
+    "fp", "asimd", "evtstrm", "aes", "pmull", "sha1", "sha2", "crc32", "atomics", "fphp",
+    "asimdhp", "cpuid", "asimdrdm", "jscvt", "fcma", "lrcpc", "dcpop", "sha3", "sm3", "sm4",
+    "asimddp", "sha512", "sve", "asimdfhm", "dit", "uscat", "ilrcpc", "flagm", "ssbs", "sb",
+    "paca", "pacg",
+];
+
+pub(crate) const GNU_PROPERTY_AARCH64_FEATURE_1_AND: u32 = 0xc000_0000;
+
+pub(crate) const GNU_PROPERTY_AARCH64_FEATURE_1_BTI: u32 = 1;
+const GNU_PROPERTY_AARCH64_FEATURE_1_PAC: u32 = 2;
+
+pub struct Aarch64;
+
+impl ArchExt for Aarch64 {
+    fn machines(&self) -> &'static [u16] {
+        &[EM_AARCH64]
+    }
+
+    fn hwcap_name(&self, bit: u32) -> Option<&'static str> {
+        bit_name(&AARCH64_HWCAP, bit)
+    }
+
+    fn describe_property(&self, pr_type: u32, value: u32) -> Option<String> {
+        if pr_type != GNU_PROPERTY_AARCH64_FEATURE_1_AND {
+            return None;
+        }
+        let mut features = Vec::new();
+        if value & GNU_PROPERTY_AARCH64_FEATURE_1_BTI != 0 {
+            features.push("BTI");
+        }
+        if value & GNU_PROPERTY_AARCH64_FEATURE_1_PAC != 0 {
+            features.push("PAC");
+        }
+        Some(format!("AArch64 feature: {}", features.join(", ")))
+    }
+
+    /// Instructions are little-endian even in big-endian files.
+    fn text_model(&self, _loader: &Loader) -> Option<TextModel> {
+        Some(TextModel {
+            average_length: 4.0,
+            alignment: 4,
+            padding: &[&[0x1f, 0x20, 0x03, 0xd5]],
+        })
+    }
+
+    fn relocation_name(&self, r_type: u32) -> Option<&'static str> {
+        match r_type {
+            257 => Some("R_AARCH64_ABS64"),
+            1024 => Some("R_AARCH64_COPY"),
+            1025 => Some("R_AARCH64_GLOB_DAT"),
+            1026 => Some("R_AARCH64_JUMP_SLOT"),
+            1027 => Some("R_AARCH64_RELATIVE"),
+            1030 => Some("R_AARCH64_TLS_TPREL64"),
+            1032 => Some("R_AARCH64_IRELATIVE"),
+            _ => None,
+        }
+    }
+}
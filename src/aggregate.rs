@@ -0,0 +1,104 @@
+use std::collections::{BTreeMap, HashSet};
+
+use crate::checksec::RelroLevel;
+use crate::linkage::Linkage;
+use crate::output;
+use crate::segment::PT_INTERP;
+use crate::{display_line, Loader};
+
+const ET_EXEC: u16 = 2;
+
+/// Prints `label` as a count followed by the files it applies to.
+fn display_files(label: &str, files: &[&str]) {
+    display_line(label, &files.len().to_string());
+    for file in files {
+        output::item(1, file);
+    }
+}
+
+/// One report for a set of ELF files that ship together, such as the contents of
+/// a container image or a package: how they were linked, which of them lack the
+/// usual hardening, and the shared libraries they need, including the ones no
+/// file in the set provides.
+pub fn display_aggregate(title: &str, files: &[(String, Loader)]) {
+    let mut counts = [0usize; 3];
+    let (mut executables, mut libraries) = (0, 0);
+    let mut no_relro = Vec::new();
+    let mut partial_relro = Vec::new();
+    let mut no_nx = Vec::new();
+    let mut no_pie = Vec::new();
+    let mut textrel = Vec::new();
+    let mut no_canary = Vec::new();
+    let mut needed: BTreeMap<String, usize> = BTreeMap::new();
+    let mut provided = HashSet::new();
+
+    for (path, loader) in files {
+        let file_name = path.rsplit('/').next().unwrap_or(path);
+        provided.insert(file_name.to_string());
+        if let Some(soname) = loader.soname() {
+            provided.insert(soname);
+        }
+        let Some(linkage) = loader.linkage() else {
+            continue;
+        };
+        counts[linkage as usize] += 1;
+        let executable = loader.raw_e_type() == ET_EXEC
+            || linkage == Linkage::StaticPie
+            || loader.program_header_by_type(PT_INTERP).is_some();
+        if executable {
+            executables += 1;
+            if loader.raw_e_type() == ET_EXEC {
+                no_pie.push(path.as_str());
+            }
+        } else {
+            libraries += 1;
+        }
+        if linkage == Linkage::Dynamic {
+            match loader.relro_report().level {
+                RelroLevel::None => no_relro.push(path.as_str()),
+                RelroLevel::Partial => partial_relro.push(path.as_str()),
+                RelroLevel::Full => {}
+            }
+            if !loader.has_stack_canary() {
+                no_canary.push(path.as_str());
+            }
+        }
+        if !loader.has_nx() {
+            no_nx.push(path.as_str());
+        }
+        if loader.has_textrel_flag() || !loader.text_relocations().is_empty() {
+            textrel.push(path.as_str());
+        }
+        for library in loader.needed_libraries() {
+            *needed.entry(library).or_default() += 1;
+        }
+    }
+
+    output::begin(title);
+    display_line("ELF Files", &files.len().to_string());
+    display_line("Executables", &executables.to_string());
+    display_line("Shared Libraries", &libraries.to_string());
+    display_line("Static", &counts[Linkage::Static as usize].to_string());
+    display_line(
+        "Static PIE",
+        &counts[Linkage::StaticPie as usize].to_string(),
+    );
+    display_line("Dynamic", &counts[Linkage::Dynamic as usize].to_string());
+    display_files("No RELRO", &no_relro);
+    display_files("Partial RELRO", &partial_relro);
+    display_files("NX Disabled", &no_nx);
+    display_files("Executables Without PIE", &no_pie);
+    display_files("TEXTREL", &textrel);
+    display_files("No Stack Canary", &no_canary);
+    display_line("Needed Libraries", &needed.len().to_string());
+    for (library, users) in &needed {
+        output::item(1, &format!("{} ({} user(s))", library, users));
+    }
+    let missing: Vec<&str> = needed
+        .keys()
+        .filter(|library| !provided.contains(*library))
+        .map(String::as_str)
+        .collect();
+    display_files("Needed Libraries Not Provided", &missing);
+    output::end();
+}
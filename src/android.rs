@@ -0,0 +1,98 @@
+use crate::note::desc_str;
+use crate::output;
+use crate::segment::PT_LOAD;
+use crate::{display_line, Loader};
+
+const NT_ANDROID_TYPE_IDENT: u32 = 1;
+const ANDROID_NDK_STRING_SIZE: usize = 64;
+
+/// Devices with 16 KB pages need every PT_LOAD aligned to at least this much.
+const ANDROID_16K_PAGE_SIZE: u64 = 0x4000;
+
+/// Android refuses to load text relocations from this API level (Marshmallow) on.
+const ANDROID_TEXTREL_BANNED_API: u32 = 23;
+
+pub struct AndroidIdent {
+    pub api_level: u32,
+    pub ndk_version: Option<String>,
+    pub ndk_build_number: Option<String>,
+}
+
+impl Loader {
+    /// Decodes the `.note.android.ident` note the NDK's crtbegin objects emit.
+    pub fn android_ident(&self) -> Option<AndroidIdent> {
+        let note = self.find_note("Android", NT_ANDROID_TYPE_IDENT)?;
+        Some(AndroidIdent {
+            api_level: self.desc_u32(&note.desc, 0)?,
+            ndk_version: desc_str(&note.desc, 4, ANDROID_NDK_STRING_SIZE),
+            ndk_build_number: desc_str(
+                &note.desc,
+                4 + ANDROID_NDK_STRING_SIZE,
+                ANDROID_NDK_STRING_SIZE,
+            ),
+        })
+    }
+
+    /// PT_LOAD segments whose alignment is too small for 16 KB page kernels.
+    pub fn misaligned_for_16k(&self) -> Vec<(u64, u64)> {
+        self.program_headers()
+            .iter()
+            .filter(|header| header.p_type == PT_LOAD)
+            .filter(|header| header.p_align < ANDROID_16K_PAGE_SIZE)
+            .map(|header| (header.p_vaddr, header.p_align))
+            .collect()
+    }
+}
+
+pub fn display_android(path: &str, loader: &Loader) {
+    output::begin(path);
+    let misaligned = loader.misaligned_for_16k();
+    if misaligned.is_empty() {
+        display_line("16 KB Page Size Compatible", "Yes");
+    } else {
+        display_line("16 KB Page Size Compatible", "No");
+        for (vaddr, align) in &misaligned {
+            output::item(
+                1,
+                &format!("PT_LOAD at {:#x} is aligned to {:#x}", vaddr, align),
+            );
+        }
+    }
+
+    let ident = loader.android_ident();
+    match &ident {
+        Some(ident) => {
+            display_line(".note.android.ident", "Present");
+            display_line("Minimum SDK (API level)", &ident.api_level.to_string());
+            if let Some(version) = ident.ndk_version.as_deref().filter(|v| !v.is_empty()) {
+                let build = ident.ndk_build_number.as_deref().unwrap_or("");
+                if build.is_empty() {
+                    display_line("NDK Version", version);
+                } else {
+                    display_line("NDK Version", &format!("{} (build {})", version, build));
+                }
+            }
+        }
+        None => display_line(".note.android.ident", "Missing"),
+    }
+
+    let text_relocations = loader.text_relocations().len();
+    if text_relocations == 0 && !loader.has_textrel_flag() {
+        display_line("Text Relocations", "None");
+    } else {
+        let verdict = match &ident {
+            Some(ident) if ident.api_level >= ANDROID_TEXTREL_BANNED_API => {
+                "rejected by the loader at this API level".to_string()
+            }
+            _ => format!(
+                "rejected by the loader from API level {}",
+                ANDROID_TEXTREL_BANNED_API
+            ),
+        };
+        display_line(
+            "Text Relocations",
+            &format!("{} ({})", text_relocations, verdict),
+        );
+    }
+    output::end();
+}
@@ -0,0 +1,146 @@
+use crate::arch::{ArchExt, AttributeSection};
+use crate::attributes::AttributeValue;
+use crate::Loader;
+
+const EM_ARC_COMPACT: u16 = 93;
+const EM_ARC_COMPACT2: u16 = 195;
+
+const EF_ARC_MACH_MSK: u32 = 0xff;
+const EF_ARC_OSABI_MSK: u32 = 0xf00;
+
+const ARC_ATTRIBUTES_SECTION: &str = ".ARC.attributes";
+
+const TAG_ARC_CPU_NAME: u64 = 7;
+const TAG_ARC_ISA_CONFIG: u64 = 16;
+const TAG_ARC_ISA_APEX: u64 = 17;
+const TAG_ARC_ISA_MPY_OPTION: u64 = 18;
+
+/// In the `ARC` vendor subsection the CPU name and ISA strings are text and the
+/// other known tags numbers; unknown tags follow the odd-is-string rule.
+fn is_text_attribute(tag: u64) -> bool {
+    match tag {
+        TAG_ARC_CPU_NAME | TAG_ARC_ISA_CONFIG | TAG_ARC_ISA_APEX => true,
+        tag if tag <= TAG_ARC_ISA_MPY_OPTION => false,
+        tag => tag % 2 == 1,
+    }
+}
+
+/// Describes the ARC e_flags: the CPU family in the low byte and the OS ABI above it.
+fn describe_flags(flags: u32) -> Vec<String> {
+    let cpu = match flags & EF_ARC_MACH_MSK {
+        0x0 => "generic ARC CPU".to_string(),
+        0x2 => "ARC600".to_string(),
+        0x3 => "ARC700".to_string(),
+        0x4 => "ARC601".to_string(),
+        0x5 => "ARC EM (ARCv2)".to_string(),
+        0x6 => "ARC HS (ARCv2)".to_string(),
+        cpu => format!("unknown CPU {:#x}", cpu),
+    };
+    let abi = match flags & EF_ARC_OSABI_MSK {
+        0x000 => "legacy OS ABI".to_string(),
+        0x200 => "OS ABI v2".to_string(),
+        0x300 => "OS ABI v3 (no legacy syscalls)".to_string(),
+        0x400 => "OS ABI v4".to_string(),
+        abi => format!("unknown OS ABI {:#x}", abi >> 8),
+    };
+    vec![cpu, abi]
+}
+
+fn describe_attribute(tag: u64, value: &AttributeValue) -> (String, String) {
+    let number = match value {
+        AttributeValue::Text(text) => {
+            let name = match tag {
+                TAG_ARC_CPU_NAME => "Tag_ARC_CPU_name".to_string(),
+                TAG_ARC_ISA_CONFIG => "Tag_ARC_ISA_config".to_string(),
+                TAG_ARC_ISA_APEX => "Tag_ARC_ISA_apex".to_string(),
+                _ => format!("Tag_{}", tag),
+            };
+            return (name, format!("\"{}\"", text));
+        }
+        AttributeValue::Number(number) => *number,
+    };
+    let (name, value) = match tag {
+        4 => (
+            "Tag_ARC_PCS_config",
+            match number {
+                1 => "Bare metal/mwdt".to_string(),
+                2 => "Bare metal/newlib".to_string(),
+                3 => "Linux/uclibc".to_string(),
+                4 => "Linux/glibc".to_string(),
+                _ => "Absent/Non standard".to_string(),
+            },
+        ),
+        5 => (
+            "Tag_ARC_CPU_base",
+            match number {
+                1 => "ARC6xx".to_string(),
+                2 => "ARC7xx".to_string(),
+                3 => "ARCEM".to_string(),
+                4 => "ARCHS".to_string(),
+                _ => "Absent".to_string(),
+            },
+        ),
+        6 => ("Tag_ARC_CPU_variation", number.to_string()),
+        8 => (
+            "Tag_ARC_ABI_rf16",
+            if number == 0 { "no" } else { "yes" }.to_string(),
+        ),
+        9 => ("Tag_ARC_ABI_osver", format!("v{}", number)),
+        10 => ("Tag_ARC_ABI_sda", number.to_string()),
+        11 => ("Tag_ARC_ABI_pic", number.to_string()),
+        12 => ("Tag_ARC_ABI_tls", number.to_string()),
+        13 => ("Tag_ARC_ABI_enumsize", number.to_string()),
+        14 => ("Tag_ARC_ABI_exceptions", number.to_string()),
+        15 => ("Tag_ARC_ABI_double_size", format!("{}-byte", number)),
+        TAG_ARC_ISA_MPY_OPTION => ("Tag_ARC_ISA_mpy_option", number.to_string()),
+        20 => ("Tag_ARC_ATR_version", number.to_string()),
+        _ => return (format!("Tag_{}", tag), number.to_string()),
+    };
+    (name.to_string(), value)
+}
+
+pub struct ArcCompact;
+
+impl ArchExt for ArcCompact {
+    fn machines(&self) -> &'static [u16] {
+        &[EM_ARC_COMPACT, EM_ARC_COMPACT2]
+    }
+
+    fn describe_flags(&self, _loader: &Loader, flags: u32) -> Vec<String> {
+        describe_flags(flags)
+    }
+
+    fn attribute_section(&self) -> Option<AttributeSection> {
+        Some(AttributeSection {
+            name: ARC_ATTRIBUTES_SECTION,
+            is_text: is_text_attribute,
+            describe: describe_attribute,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{describe_flags, is_text_attribute};
+
+    #[test]
+    fn flags() {
+        assert_eq!(
+            describe_flags(0x306),
+            ["ARC HS (ARCv2)", "OS ABI v3 (no legacy syscalls)"]
+        );
+        assert_eq!(
+            describe_flags(0x1f1),
+            ["unknown CPU 0xf1", "unknown OS ABI 0x1"]
+        );
+    }
+
+    /// The known string tags are not all odd, so they are listed.
+    #[test]
+    fn attribute_kinds() {
+        assert!(is_text_attribute(16));
+        assert!(!is_text_attribute(15));
+        assert!(is_text_attribute(19));
+        assert!(!is_text_attribute(20));
+    }
+}
@@ -0,0 +1,143 @@
+use crate::aarch64::Aarch64;
+use crate::arc::ArcCompact;
+use crate::arm::Arm;
+use crate::attributes::AttributeValue;
+use crate::loongarch::LoongArch;
+use crate::mips::Mips;
+use crate::output;
+use crate::ppc64::Ppc64;
+use crate::riscv::Riscv;
+use crate::x86::{I386, X86_64};
+use crate::{display_line, Loader};
+
+/// A build attributes section (`.ARM.attributes` and friends) and how to read its tags.
+pub struct AttributeSection {
+    pub name: &'static str,
+    /// Whether a tag carries a NUL-terminated string rather than a ULEB128 number.
+    pub is_text: fn(u64) -> bool,
+    /// Names a tag and renders its value.
+    pub describe: fn(u64, &AttributeValue) -> (String, String),
+}
+
+/// How `--text-stats` estimates a family's code without decoding it.
+pub struct TextModel {
+    /// Average instruction length in bytes of typical compiled code.
+    pub average_length: f64,
+    /// Instructions start at multiples of this, so padding is searched there.
+    pub alignment: usize,
+    /// Encodings compilers and linkers fill gaps between functions with.
+    pub padding: &'static [&'static [u8]],
+}
+
+/// What the tool knows about one processor family beyond the generic ELF layout.
+/// To support a new architecture, implement this in its own module and add it
+/// to [`ARCHITECTURES`].
+pub trait ArchExt: Sync {
+    /// The e_machine values this implementation covers.
+    fn machines(&self) -> &'static [u16];
+
+    /// Describes the processor-specific e_flags bits, one line each.
+    fn describe_flags(&self, _loader: &Loader, _flags: u32) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// The `R_*` name of a relocation type.
+    fn relocation_name(&self, _r_type: u32) -> Option<&'static str> {
+        None
+    }
+
+    /// The kernel's name for a bit of AT_HWCAP in this family's auxiliary vector.
+    fn hwcap_name(&self, _bit: u32) -> Option<&'static str> {
+        None
+    }
+
+    /// Describes a processor-specific GNU property holding a 32-bit value.
+    fn describe_property(&self, _pr_type: u32, _value: u32) -> Option<String> {
+        None
+    }
+
+    fn text_model(&self, _loader: &Loader) -> Option<TextModel> {
+        None
+    }
+
+    fn attribute_section(&self) -> Option<AttributeSection> {
+        None
+    }
+
+    /// Prints whatever else `--arch-info` shows for this family, after the flags
+    /// and the build attributes.
+    fn display_details(
+        &self,
+        _loader: &Loader,
+        _flags: u32,
+        _attributes: &[(u64, AttributeValue)],
+    ) {
+    }
+}
+
+pub static ARCHITECTURES: &[&dyn ArchExt] = &[
+    &I386,
+    &X86_64,
+    &Arm,
+    &Aarch64,
+    &Riscv,
+    &Mips,
+    &Ppc64,
+    &ArcCompact,
+    &LoongArch,
+];
+
+/// The name of `bit` in a table of bit names, where unnamed bits are empty.
+pub fn bit_name(names: &[&'static str], bit: u32) -> Option<&'static str> {
+    names
+        .get(bit as usize)
+        .copied()
+        .filter(|name| !name.is_empty())
+}
+
+/// The extension registered for `machine`, if any.
+pub fn arch_ext(machine: u16) -> Option<&'static dyn ArchExt> {
+    ARCHITECTURES
+        .iter()
+        .find(|arch| arch.machines().contains(&machine))
+        .copied()
+}
+
+/// Prints the file attributes of a build attributes section and returns them.
+fn display_attributes(loader: &Loader, section: &AttributeSection) -> Vec<(u64, AttributeValue)> {
+    let Some(header) = loader.section_by_name(section.name) else {
+        display_line("Build Attributes", "None");
+        return Vec::new();
+    };
+    let mut all = Vec::new();
+    for subsection in loader.attribute_subsections(&header, section.is_text) {
+        display_line(
+            &format!("Build Attributes ({})", subsection.vendor),
+            &subsection.attributes.len().to_string(),
+        );
+        for (tag, value) in &subsection.attributes {
+            let (name, value) = (section.describe)(*tag, value);
+            output::item(1, &format!("{:<32} {}", name, value));
+        }
+        all.extend(subsection.attributes);
+    }
+    all
+}
+
+pub fn display_arch_info(path: &str, loader: &Loader) {
+    let flags = loader.raw_e_flags();
+    output::begin(path);
+    display_line("Machine", &loader.machine_name());
+    display_line("Flags", &format!("{:#x}", flags));
+    if let Some(arch) = arch_ext(loader.raw_e_machine()) {
+        for description in arch.describe_flags(loader, flags) {
+            output::item(1, &description);
+        }
+        let attributes = match arch.attribute_section() {
+            Some(section) => display_attributes(loader, &section),
+            None => Vec::new(),
+        };
+        arch.display_details(loader, flags, &attributes);
+    }
+    output::end();
+}
@@ -0,0 +1,183 @@
+use flate2::read::GzDecoder;
+use lzma_rust2::XzReader;
+use ruzstd::decoding::StreamingDecoder;
+use std::collections::HashMap;
+use std::io::{self, Read};
+use tar::EntryType;
+
+use crate::HEADER_MAGIC;
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+const XZ_MAGIC: &[u8] = &[0xfd, b'7', b'z', b'X', b'Z', 0];
+
+/// Whether `bytes` start like a compressed stream `decompressed` can undo.
+pub fn is_compressed(bytes: &[u8]) -> bool {
+    bytes.starts_with(GZIP_MAGIC) || bytes.starts_with(ZSTD_MAGIC) || bytes.starts_with(XZ_MAGIC)
+}
+
+/// A reader over `bytes` that undoes gzip, zstd or xz compression, recognised by
+/// its magic number; anything else is read as it is.
+pub fn decompressed(bytes: &[u8]) -> io::Result<Box<dyn Read + '_>> {
+    if bytes.starts_with(GZIP_MAGIC) {
+        Ok(Box::new(GzDecoder::new(bytes)))
+    } else if bytes.starts_with(XZ_MAGIC) {
+        Ok(Box::new(XzReader::new(bytes, true)))
+    } else if bytes.starts_with(ZSTD_MAGIC) {
+        let decoder = StreamingDecoder::new(bytes)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error.to_string()))?;
+        Ok(Box::new(decoder))
+    } else {
+        Ok(Box::new(bytes))
+    }
+}
+
+/// What a tar member is, as far as finding ELF files goes.
+pub enum Member {
+    /// A regular file starting with the ELF magic, with its contents.
+    Elf(Vec<u8>),
+    /// A hard link to the member at this path.
+    Link(String),
+    /// Any other file, symbolic link or device; directories are not reported.
+    Other,
+}
+
+/// `./usr/bin/ls` and `/usr/bin/ls` both become `usr/bin/ls`.
+pub fn normalize(path: &str) -> String {
+    path.trim_start_matches("./")
+        .trim_start_matches('/')
+        .trim_end_matches('/')
+        .to_string()
+}
+
+/// Reads the rest of a file if it starts with the ELF magic; otherwise leaves it
+/// after at most four bytes.
+fn read_elf(file: &mut impl Read) -> io::Result<Option<Vec<u8>>> {
+    let mut magic = [0; 4];
+    let mut read = 0;
+    while read < magic.len() {
+        match file.read(&mut magic[read..])? {
+            0 => break,
+            count => read += count,
+        }
+    }
+    if magic != HEADER_MAGIC {
+        return Ok(None);
+    }
+    let mut contents = magic.to_vec();
+    file.read_to_end(&mut contents)?;
+    Ok(Some(contents))
+}
+
+/// Calls `visit` with the normalized path of every member of the tar stream.
+/// Only ELF files are read in full; the rest is skipped.
+pub fn walk_tar(reader: impl Read, mut visit: impl FnMut(String, Member)) -> io::Result<()> {
+    let mut archive = tar::Archive::new(reader);
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let path = normalize(&String::from_utf8_lossy(&entry.path_bytes()));
+        let member = match entry.header().entry_type() {
+            EntryType::Directory => continue,
+            EntryType::Link => match entry.link_name_bytes() {
+                Some(target) => Member::Link(normalize(&String::from_utf8_lossy(&target))),
+                None => Member::Other,
+            },
+            EntryType::Regular | EntryType::Continuous => match read_elf(&mut entry)? {
+                Some(contents) => Member::Elf(contents),
+                None => Member::Other,
+            },
+            _ => Member::Other,
+        };
+        visit(path, member);
+    }
+    Ok(())
+}
+
+const CPIO_HEADER_LEN: usize = 110;
+const S_IFMT: u32 = 0o170000;
+const S_IFREG: u32 = 0o100000;
+const S_IFDIR: u32 = 0o040000;
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Skips `count` bytes of `reader`.
+fn skip(reader: &mut impl Read, count: u64) -> io::Result<()> {
+    let skipped = io::copy(&mut reader.take(count), &mut io::sink())?;
+    if skipped < count {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(())
+}
+
+/// How far `offset` is from the next multiple of four, which aligns every name
+/// and file in a newc archive.
+fn padding(offset: u64) -> u64 {
+    (4 - offset % 4) % 4
+}
+
+/// Like `walk_tar`, for an SVR4 (`newc`) cpio stream as found in RPM payloads.
+/// Hard links there carry their data on the last name only, so the earlier
+/// names are reported as links to it once it has been seen.
+pub fn walk_cpio(mut reader: impl Read, mut visit: impl FnMut(String, Member)) -> io::Result<()> {
+    // Names waiting for the data of the file they link to, by device and inode.
+    let mut pending: HashMap<(u32, u32, u32), Vec<String>> = HashMap::new();
+    let mut offset = 0u64;
+    loop {
+        let mut header = [0; CPIO_HEADER_LEN];
+        reader.read_exact(&mut header)?;
+        if &header[..5] != b"07070" || !matches!(header[5], b'1' | b'2') {
+            return Err(invalid("not a newc cpio archive"));
+        }
+        let field = |index: usize| {
+            let start = 6 + index * 8;
+            std::str::from_utf8(&header[start..start + 8])
+                .ok()
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .ok_or_else(|| invalid("malformed cpio header"))
+        };
+        let (inode, mode, links, size) = (field(0)?, field(1)?, field(4)?, field(6)?);
+        let device = (field(7)?, field(8)?);
+        let name_len = field(11)? as usize;
+        let mut name = vec![0; name_len];
+        reader.read_exact(&mut name)?;
+        offset += (CPIO_HEADER_LEN + name_len) as u64;
+        skip(&mut reader, padding(offset))?;
+        offset += padding(offset);
+        let name = String::from_utf8_lossy(name.strip_suffix(&[0]).unwrap_or(&name)).into_owned();
+        if name == "TRAILER!!!" {
+            return Ok(());
+        }
+        let path = normalize(&name);
+
+        let mut file = (&mut reader).take(size as u64);
+        let member = match mode & S_IFMT {
+            S_IFDIR => None,
+            S_IFREG if size == 0 && links > 1 => {
+                let key = (device.0, device.1, inode);
+                pending.entry(key).or_default().push(path.clone());
+                None
+            }
+            S_IFREG => Some(match read_elf(&mut file)? {
+                Some(contents) => Member::Elf(contents),
+                None => Member::Other,
+            }),
+            _ => Some(Member::Other),
+        };
+        io::copy(&mut file, &mut io::sink())?;
+        offset += size as u64;
+        skip(&mut reader, padding(offset))?;
+        offset += padding(offset);
+
+        if let Some(member) = member {
+            let links = pending
+                .remove(&(device.0, device.1, inode))
+                .unwrap_or_default();
+            visit(path.clone(), member);
+            for link in links {
+                visit(link, Member::Link(path.clone()));
+            }
+        }
+    }
+}
@@ -0,0 +1,342 @@
+use crate::arch::{bit_name, ArchExt, AttributeSection, TextModel};
+use crate::attributes::AttributeValue;
+use crate::Loader;
+
+const EM_ARM: u16 = 40;
+
+const EF_ARM_EABIMASK: u32 = 0xff00_0000;
+const EF_ARM_BE8: u32 = 0x0080_0000;
+const EF_ARM_ABI_FLOAT_SOFT: u32 = 0x200;
+const EF_ARM_ABI_FLOAT_HARD: u32 = 0x400;
+
+const ARM_ATTRIBUTES_SECTION: &str = ".ARM.attributes";
+
+const TAG_CPU_RAW_NAME: u64 = 4;
+const TAG_CPU_NAME: u64 = 5;
+const TAG_COMPATIBILITY: u64 = 32;
+const TAG_ALSO_COMPATIBLE_WITH: u64 = 65;
+const TAG_CONFORMANCE: u64 = 67;
+
+/// In the `aeabi` vendor subsection, tags below 32 are numbers except the two CPU
+/// names; from 32 on, odd tags are strings and even tags numbers.
+fn is_text_attribute(tag: u64) -> bool {
+    match tag {
+        TAG_CPU_RAW_NAME | TAG_CPU_NAME | TAG_ALSO_COMPATIBLE_WITH | TAG_CONFORMANCE => true,
+        TAG_COMPATIBILITY => false,
+        tag if tag < 32 => false,
+        tag => tag % 2 == 1,
+    }
+}
+
+/// Describes the ARM-specific e_flags bits.
+fn describe_flags(flags: u32) -> Vec<String> {
+    let mut described = Vec::new();
+    let eabi = (flags & EF_ARM_EABIMASK) >> 24;
+    if eabi == 0 {
+        described.push("GNU/legacy ABI (no EABI version)".to_string());
+    } else {
+        described.push(format!("EABI version {}", eabi));
+    }
+    if flags & EF_ARM_ABI_FLOAT_HARD != 0 {
+        described.push("hard-float ABI (arguments in VFP registers)".to_string());
+    }
+    if flags & EF_ARM_ABI_FLOAT_SOFT != 0 {
+        described.push("soft-float ABI".to_string());
+    }
+    if flags & EF_ARM_BE8 != 0 {
+        described.push("BE8 (byte-invariant big-endian)".to_string());
+    }
+    described
+}
+
+fn cpu_arch(value: u64) -> Option<&'static str> {
+    const NAMES: &[&str] = &[
+        "Pre-v4",
+        "v4",
+        "v4T",
+        "v5T",
+        "v5TE",
+        "v5TEJ",
+        "v6",
+        "v6KZ",
+        "v6T2",
+        "v6K",
+        "v7",
+        "v6-M",
+        "v6S-M",
+        "v7E-M",
+        "v8-A",
+        "v8-R",
+        "v8-M.baseline",
+        "v8-M.mainline",
+        "v8.1-A",
+        "v8.2-A",
+        "v8.3-A",
+        "v8.1-M.mainline",
+        "v9-A",
+    ];
+    NAMES.get(value as usize).copied()
+}
+
+fn lookup(value: u64, names: &[&'static str]) -> Option<&'static str> {
+    names.get(value as usize).copied()
+}
+
+/// Names an `aeabi` attribute and renders its value the way the ABI document does.
+fn describe_attribute(tag: u64, value: &AttributeValue) -> (String, String) {
+    let number = match value {
+        AttributeValue::Number(number) => *number,
+        AttributeValue::Text(text) => {
+            let name = match tag {
+                TAG_CPU_RAW_NAME => "Tag_CPU_raw_name",
+                TAG_CPU_NAME => "Tag_CPU_name",
+                TAG_ALSO_COMPATIBLE_WITH => "Tag_also_compatible_with",
+                TAG_CONFORMANCE => "Tag_conformance",
+                _ => return (format!("Tag_{}", tag), format!("\"{}\"", text)),
+            };
+            return (name.to_string(), format!("\"{}\"", text));
+        }
+    };
+    let (name, decoded) = match tag {
+        6 => ("Tag_CPU_arch", cpu_arch(number)),
+        7 => (
+            "Tag_CPU_arch_profile",
+            match number as u8 {
+                0 => Some("None"),
+                b'A' => Some("Application"),
+                b'R' => Some("Realtime"),
+                b'M' => Some("Microcontroller"),
+                b'S' => Some("Application or Realtime"),
+                _ => None,
+            },
+        ),
+        8 => ("Tag_ARM_ISA_use", lookup(number, &["No", "Yes"])),
+        9 => (
+            "Tag_THUMB_ISA_use",
+            lookup(number, &["No", "Thumb-1", "Thumb-2", "Yes"]),
+        ),
+        10 => (
+            "Tag_FP_arch",
+            lookup(
+                number,
+                &[
+                    "No",
+                    "VFPv1",
+                    "VFPv2",
+                    "VFPv3",
+                    "VFPv3-D16",
+                    "VFPv4",
+                    "VFPv4-D16",
+                    "FP for ARMv8",
+                    "FPv5/FP-D16 for ARMv8",
+                ],
+            ),
+        ),
+        11 => ("Tag_WMMX_arch", lookup(number, &["No", "WMMXv1", "WMMXv2"])),
+        12 => (
+            "Tag_Advanced_SIMD_arch",
+            lookup(
+                number,
+                &[
+                    "No",
+                    "NEONv1",
+                    "NEONv1 with Fused-MAC",
+                    "NEON for ARMv8",
+                    "NEON for ARMv8.1",
+                ],
+            ),
+        ),
+        14 => (
+            "Tag_ABI_PCS_R9_use",
+            lookup(number, &["V6", "SB", "TLS", "Unused"]),
+        ),
+        15 => (
+            "Tag_ABI_PCS_RW_data",
+            lookup(number, &["Absolute", "PC-relative", "SB-relative", "None"]),
+        ),
+        16 => (
+            "Tag_ABI_PCS_RO_data",
+            lookup(number, &["Absolute", "PC-relative", "None"]),
+        ),
+        17 => (
+            "Tag_ABI_PCS_GOT_use",
+            lookup(number, &["None", "direct", "GOT-indirect"]),
+        ),
+        18 => (
+            "Tag_ABI_PCS_wchar_t",
+            match number {
+                0 => Some("None"),
+                2 => Some("2 bytes"),
+                4 => Some("4 bytes"),
+                _ => None,
+            },
+        ),
+        20 => (
+            "Tag_ABI_FP_denormal",
+            lookup(number, &["Unused", "Needed", "Sign only"]),
+        ),
+        21 => (
+            "Tag_ABI_FP_exceptions",
+            lookup(number, &["Unused", "Needed"]),
+        ),
+        22 => (
+            "Tag_ABI_FP_user_exceptions",
+            lookup(number, &["Unused", "Needed"]),
+        ),
+        23 => (
+            "Tag_ABI_FP_number_model",
+            lookup(number, &["Unused", "Finite", "RTABI", "IEEE 754"]),
+        ),
+        24 => (
+            "Tag_ABI_align_needed",
+            lookup(number, &["None", "8-byte", "4-byte"]),
+        ),
+        25 => (
+            "Tag_ABI_align_preserved",
+            lookup(number, &["None", "8-byte", "8-byte, except leaf SP"]),
+        ),
+        26 => (
+            "Tag_ABI_enum_size",
+            lookup(number, &["Unused", "small", "int", "forced to int"]),
+        ),
+        27 => (
+            "Tag_ABI_HardFP_use",
+            lookup(
+                number,
+                &["As Tag_FP_arch", "SP only", "DP only", "SP and DP"],
+            ),
+        ),
+        28 => (
+            "Tag_ABI_VFP_args",
+            lookup(
+                number,
+                &[
+                    "AAPCS (soft-float)",
+                    "VFP registers",
+                    "custom",
+                    "compatible",
+                ],
+            ),
+        ),
+        30 => (
+            "Tag_ABI_optimization_goals",
+            lookup(
+                number,
+                &[
+                    "None",
+                    "Prefer Speed",
+                    "Aggressive Speed",
+                    "Prefer Size",
+                    "Aggressive Size",
+                    "Prefer Debug",
+                    "Aggressive Debug",
+                ],
+            ),
+        ),
+        34 => ("Tag_CPU_unaligned_access", lookup(number, &["None", "v6"])),
+        36 => (
+            "Tag_FP_HP_extension",
+            lookup(number, &["Not Allowed", "Allowed"]),
+        ),
+        38 => (
+            "Tag_ABI_FP_16bit_format",
+            lookup(number, &["None", "IEEE 754", "Alternative Format"]),
+        ),
+        42 => (
+            "Tag_MPextension_use",
+            lookup(number, &["Not Allowed", "Allowed"]),
+        ),
+        44 => (
+            "Tag_DIV_use",
+            lookup(
+                number,
+                &[
+                    "Allowed in Thumb-ISA, v7-R or v7-M",
+                    "Not allowed",
+                    "Allowed in v7-A with integer division extension",
+                ],
+            ),
+        ),
+        46 => (
+            "Tag_DSP_extension",
+            lookup(number, &["Follow architecture", "Allowed"]),
+        ),
+        68 => (
+            "Tag_Virtualization_use",
+            lookup(
+                number,
+                &[
+                    "Not Allowed",
+                    "TrustZone",
+                    "Virtualization Extensions",
+                    "TrustZone and Virtualization Extensions",
+                ],
+            ),
+        ),
+        _ => return (format!("Tag_{}", tag), number.to_string()),
+    };
+    let value = match decoded {
+        Some(text) => text.to_string(),
+        None => format!("Unknown ({})", number),
+    };
+    (name.to_string(), value)
+}
+
+/// The HWCAP_* bits of arch/arm/include/uapi/asm/hwcap.h.
+const ARM_HWCAP: [&str; 23] = [
+    "swp", "half", "thumb", "26bit", "fastmult", "fpa", "vfp", "edsp", "java", "iwmmxt", "crunch",
+    "thumbee", "neon", "vfpv3", "vfpv3d16", "tls", "vfpv4", "idiva", "idivt", "vfpd32", "lpae",
+    "evtstrm", "fphp",
+];
+
+pub struct Arm;
+
+impl ArchExt for Arm {
+    fn machines(&self) -> &'static [u16] {
+        &[EM_ARM]
+    }
+
+    fn hwcap_name(&self, bit: u32) -> Option<&'static str> {
+        bit_name(&ARM_HWCAP, bit)
+    }
+
+    fn describe_flags(&self, _loader: &Loader, flags: u32) -> Vec<String> {
+        describe_flags(flags)
+    }
+
+    /// Most Linux code is Thumb-2, which mixes 2 and 4 byte instructions. The
+    /// padding is the ARM and Thumb NOPs and the `mov r0, r0` and `mov r8, r8`
+    /// older toolchains use, as stored in little-endian and BE8 files.
+    fn text_model(&self, _loader: &Loader) -> Option<TextModel> {
+        Some(TextModel {
+            average_length: 3.0,
+            alignment: 2,
+            padding: &[
+                &[0x00, 0xf0, 0x20, 0xe3],
+                &[0x00, 0x00, 0xa0, 0xe1],
+                &[0x00, 0xbf],
+                &[0xc0, 0x46],
+            ],
+        })
+    }
+
+    fn relocation_name(&self, r_type: u32) -> Option<&'static str> {
+        match r_type {
+            2 => Some("R_ARM_ABS32"),
+            19 => Some("R_ARM_TLS_TPOFF32"),
+            20 => Some("R_ARM_COPY"),
+            21 => Some("R_ARM_GLOB_DAT"),
+            22 => Some("R_ARM_JUMP_SLOT"),
+            23 => Some("R_ARM_RELATIVE"),
+            _ => None,
+        }
+    }
+
+    fn attribute_section(&self) -> Option<AttributeSection> {
+        Some(AttributeSection {
+            name: ARM_ATTRIBUTES_SECTION,
+            is_text: is_text_attribute,
+            describe: describe_attribute,
+        })
+    }
+}
@@ -0,0 +1,106 @@
+use crate::section::SectionHeader;
+use crate::Loader;
+
+/// First byte of an attributes section: format version 'A'.
+const ATTRIBUTES_FORMAT_A: u8 = b'A';
+/// Sub-subsection tag for attributes that apply to the whole file.
+const TAG_FILE: u64 = 1;
+
+#[derive(Clone, Debug)]
+pub enum AttributeValue {
+    Number(u64),
+    Text(String),
+}
+
+/// The file-scope attributes of one vendor subsection (`aeabi`, `riscv`, ...).
+pub struct AttributeSubsection {
+    pub vendor: String,
+    pub attributes: Vec<(u64, AttributeValue)>,
+}
+
+/// Decodes an unsigned LEB128 number, returning it with the number of bytes it took.
+pub fn read_uleb128(data: &[u8]) -> Option<(u64, usize)> {
+    let mut value = 0u64;
+    for (index, byte) in data.iter().enumerate().take(10) {
+        value |= u64::from(byte & 0x7f) << (7 * index);
+        if byte & 0x80 == 0 {
+            return Some((value, index + 1));
+        }
+    }
+    None
+}
+
+fn read_ntbs(data: &[u8]) -> Option<(String, usize)> {
+    let end = data.iter().position(|&byte| byte == 0)?;
+    Some((String::from_utf8_lossy(&data[..end]).to_string(), end + 1))
+}
+
+impl Loader {
+    /// Parses a build attributes section as used by ARM and RISC-V. Whether a tag
+    /// carries a number or a NUL-terminated string is vendor specific, so the caller
+    /// says so through `is_text`. Section and symbol scoped attributes are skipped.
+    pub fn attribute_subsections(
+        &self,
+        section: &SectionHeader,
+        is_text: fn(u64) -> bool,
+    ) -> Vec<AttributeSubsection> {
+        let data = self.section_data(section);
+        let mut subsections = Vec::new();
+        if data.first() != Some(&ATTRIBUTES_FORMAT_A) {
+            return subsections;
+        }
+        let mut offset = 1;
+        while offset + 4 <= data.len() {
+            let Some(length) = self.read_u32(section.sh_offset as usize + offset) else {
+                break;
+            };
+            let end = offset.saturating_add(length as usize).min(data.len());
+            if length < 4 {
+                break;
+            }
+            let Some((vendor, vendor_length)) = read_ntbs(&data[offset + 4..end]) else {
+                break;
+            };
+            let mut attributes = Vec::new();
+            let mut position = offset + 4 + vendor_length;
+            while position < end {
+                let Some((tag, tag_length)) = read_uleb128(&data[position..end]) else {
+                    break;
+                };
+                let Some(size) = self.read_u32(section.sh_offset as usize + position + tag_length)
+                else {
+                    break;
+                };
+                let scope_end = position.saturating_add(size as usize).min(end);
+                if tag == TAG_FILE {
+                    let mut cursor = position + tag_length + 4;
+                    while cursor < scope_end {
+                        let Some((attribute, used)) = read_uleb128(&data[cursor..scope_end]) else {
+                            break;
+                        };
+                        cursor += used;
+                        let value = if is_text(attribute) {
+                            read_ntbs(&data[cursor..scope_end])
+                                .map(|(text, used)| (AttributeValue::Text(text), used))
+                        } else {
+                            read_uleb128(&data[cursor..scope_end])
+                                .map(|(number, used)| (AttributeValue::Number(number), used))
+                        };
+                        let Some((value, used)) = value else {
+                            break;
+                        };
+                        cursor += used;
+                        attributes.push((attribute, value));
+                    }
+                }
+                if size == 0 {
+                    break;
+                }
+                position = scope_end;
+            }
+            subsections.push(AttributeSubsection { vendor, attributes });
+            offset = end;
+        }
+        subsections
+    }
+}
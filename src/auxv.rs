@@ -0,0 +1,141 @@
+use crate::arch::arch_ext;
+use crate::note::hex_string;
+use crate::Loader;
+
+pub(crate) const NT_AUXV: u32 = 6;
+
+const AT_NULL: u64 = 0;
+const AT_PHDR: u64 = 3;
+const AT_PHENT: u64 = 4;
+const AT_PHNUM: u64 = 5;
+const AT_PAGESZ: u64 = 6;
+const AT_BASE: u64 = 7;
+const AT_FLAGS: u64 = 8;
+const AT_ENTRY: u64 = 9;
+const AT_UID: u64 = 11;
+const AT_EUID: u64 = 12;
+const AT_GID: u64 = 13;
+const AT_EGID: u64 = 14;
+const AT_PLATFORM: u64 = 15;
+const AT_HWCAP: u64 = 16;
+const AT_CLKTCK: u64 = 17;
+const AT_SECURE: u64 = 23;
+const AT_BASE_PLATFORM: u64 = 24;
+const AT_RANDOM: u64 = 25;
+const AT_HWCAP2: u64 = 26;
+const AT_RSEQ_FEATURE_SIZE: u64 = 27;
+const AT_RSEQ_ALIGN: u64 = 28;
+const AT_HWCAP3: u64 = 29;
+const AT_HWCAP4: u64 = 30;
+const AT_EXECFN: u64 = 31;
+const AT_SYSINFO: u64 = 32;
+const AT_SYSINFO_EHDR: u64 = 33;
+const AT_MINSIGSTKSZ: u64 = 51;
+
+/// The bytes the kernel puts at AT_RANDOM.
+const RANDOM_LEN: usize = 16;
+
+fn entry_name(a_type: u64) -> Option<&'static str> {
+    Some(match a_type {
+        AT_PHDR => "AT_PHDR",
+        AT_PHENT => "AT_PHENT",
+        AT_PHNUM => "AT_PHNUM",
+        AT_PAGESZ => "AT_PAGESZ",
+        AT_BASE => "AT_BASE",
+        AT_FLAGS => "AT_FLAGS",
+        AT_ENTRY => "AT_ENTRY",
+        AT_UID => "AT_UID",
+        AT_EUID => "AT_EUID",
+        AT_GID => "AT_GID",
+        AT_EGID => "AT_EGID",
+        AT_PLATFORM => "AT_PLATFORM",
+        AT_HWCAP => "AT_HWCAP",
+        AT_CLKTCK => "AT_CLKTCK",
+        AT_SECURE => "AT_SECURE",
+        AT_BASE_PLATFORM => "AT_BASE_PLATFORM",
+        AT_RANDOM => "AT_RANDOM",
+        AT_HWCAP2 => "AT_HWCAP2",
+        AT_RSEQ_FEATURE_SIZE => "AT_RSEQ_FEATURE_SIZE",
+        AT_RSEQ_ALIGN => "AT_RSEQ_ALIGN",
+        AT_HWCAP3 => "AT_HWCAP3",
+        AT_HWCAP4 => "AT_HWCAP4",
+        AT_EXECFN => "AT_EXECFN",
+        AT_SYSINFO => "AT_SYSINFO",
+        AT_SYSINFO_EHDR => "AT_SYSINFO_EHDR",
+        AT_MINSIGSTKSZ => "AT_MINSIGSTKSZ",
+        _ => return None,
+    })
+}
+
+impl Loader {
+    /// The (a_type, a_val) pairs of an NT_AUXV descriptor, up to AT_NULL.
+    pub fn auxv_entries(&self, desc: &[u8]) -> Vec<(u64, u64)> {
+        let word = if self.is_64bit() { 8 } else { 4 };
+        let mut entries = Vec::new();
+        let mut offset = 0;
+        while let (Some(a_type), Some(a_val)) = (
+            self.desc_word(desc, offset),
+            self.desc_word(desc, offset + word),
+        ) {
+            if a_type == AT_NULL {
+                break;
+            }
+            entries.push((a_type, a_val));
+            offset += word * 2;
+        }
+        entries
+    }
+
+    /// The NUL-terminated string at `vaddr` in the dumped memory.
+    fn memory_string(&self, vaddr: u64) -> Option<String> {
+        let memory = self.memory_from(vaddr)?;
+        let end = memory.iter().position(|&byte| byte == 0)?;
+        Some(String::from_utf8_lossy(&memory[..end]).into_owned())
+    }
+
+    /// AT_HWCAP with the names of its bits for the dump's architecture.
+    fn describe_hwcap(&self, value: u64) -> String {
+        let arch = arch_ext(self.raw_e_machine());
+        let names: Vec<String> = (0..64)
+            .filter(|bit| value & (1 << bit) != 0)
+            .map(|bit| match arch.and_then(|arch| arch.hwcap_name(bit)) {
+                Some(name) => name.to_string(),
+                None => format!("bit {}", bit),
+            })
+            .collect();
+        if names.is_empty() {
+            format!("{:#x}", value)
+        } else {
+            format!("{:#x} ({})", value, names.join(" "))
+        }
+    }
+
+    /// Names an auxiliary vector entry and renders its value. Strings and the
+    /// AT_RANDOM bytes are read from the process memory kept in the core dump.
+    pub fn describe_auxv_entry(&self, a_type: u64, a_val: u64) -> (String, String) {
+        let name = match entry_name(a_type) {
+            Some(name) => name.to_string(),
+            None => format!("type {}", a_type),
+        };
+        let value = match a_type {
+            AT_PHENT | AT_PHNUM | AT_PAGESZ | AT_UID | AT_EUID | AT_GID | AT_EGID | AT_CLKTCK
+            | AT_SECURE | AT_RSEQ_FEATURE_SIZE | AT_RSEQ_ALIGN | AT_MINSIGSTKSZ => {
+                a_val.to_string()
+            }
+            AT_HWCAP => self.describe_hwcap(a_val),
+            AT_PLATFORM | AT_BASE_PLATFORM | AT_EXECFN => match self.memory_string(a_val) {
+                Some(text) => format!("{:#x} \"{}\"", a_val, text),
+                None => format!("{:#x}", a_val),
+            },
+            AT_RANDOM => match self
+                .memory_from(a_val)
+                .and_then(|bytes| bytes.get(..RANDOM_LEN))
+            {
+                Some(bytes) => format!("{:#x} ({})", a_val, hex_string(bytes)),
+                None => format!("{:#x}", a_val),
+            },
+            _ => format!("{:#x}", a_val),
+        };
+        (name, value)
+    }
+}
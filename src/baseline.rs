@@ -0,0 +1,247 @@
+use std::fs;
+use std::path::Path;
+
+use serde_json::{json, Map, Value};
+
+use crate::buildinfo;
+use crate::checksec::RelroLevel;
+use crate::finding::{self, Finding, Severity};
+use crate::output;
+use crate::patch::write_atomic;
+use crate::{display_line, Loader};
+
+/// Growth in percent of a section or the whole file that counts as a
+/// regression when `--max-growth` is not given.
+pub const DEFAULT_MAX_GROWTH: f64 = 5.0;
+
+/// The checks behind the `--report` findings of `--baseline`.
+const BASELINE_CHECKS: &[&str] = &["baseline/size", "baseline/hardening"];
+
+/// The hardening properties kept in a baseline, with whether `true` is the
+/// hardened state.
+const HARDENING: &[(&str, bool)] = &[
+    ("nx", true),
+    ("pie", true),
+    ("bind_now", true),
+    ("stack_canary", true),
+    ("textrel", false),
+];
+
+/// The RELRO levels from weakest to strongest.
+const RELRO_LEVELS: &[&str] = &["none", "partial", "full"];
+
+/// The key of a file in the baseline: its file name, so that a release in
+/// another directory still finds its entries.
+pub(crate) fn key(label: &str) -> String {
+    Path::new(label)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| label.to_string())
+}
+
+/// What a baseline records about one file: its size, the size of each section
+/// (summed over sections sharing a name) and its hardening.
+pub(crate) fn entry(loader: &Loader) -> Value {
+    let mut sections = Map::new();
+    for section in loader.section_headers() {
+        if section.index == 0 || section.name.is_empty() {
+            continue;
+        }
+        let size = sections
+            .get(&section.name)
+            .and_then(Value::as_u64)
+            .unwrap_or(0);
+        sections.insert(section.name.clone(), json!(size + section.sh_size));
+    }
+    let relro = match loader.relro_report().level {
+        RelroLevel::None => "none",
+        RelroLevel::Partial => "partial",
+        RelroLevel::Full => "full",
+    };
+    json!({
+        "size": loader.bytes().len(),
+        "sections": sections,
+        "hardening": {
+            "relro": relro,
+            "nx": loader.has_nx(),
+            "pie": loader.is_pie(),
+            "bind_now": loader.is_bind_now(),
+            "stack_canary": loader.has_stack_canary(),
+            "textrel": loader.has_textrel_flag() || !loader.text_relocations().is_empty(),
+        },
+    })
+}
+
+/// `--write-baseline FILE`: records the size, sections and hardening of every
+/// target, keyed by file name, for later runs to compare against.
+pub fn write_baseline(path: &str, targets: &[(String, Loader)]) -> Result<(), String> {
+    let files: Map<String, Value> = targets
+        .iter()
+        .map(|(label, loader)| (key(label), entry(loader)))
+        .collect();
+    let baseline = json!({ "tool": buildinfo::tool(), "files": files });
+    write_atomic(path, format!("{:#}\n", baseline))?;
+    output::message(&format!(
+        "Wrote the baseline of {} file(s) to {}",
+        targets.len(),
+        path
+    ));
+    Ok(())
+}
+
+/// Reads a baseline written by `--write-baseline`, as its entries by file name.
+pub fn load_baseline(path: &str) -> Result<Map<String, Value>, String> {
+    let text = fs::read_to_string(path).map_err(|error| error.to_string())?;
+    let baseline: Value = serde_json::from_str(&text).map_err(|error| error.to_string())?;
+    match baseline.get("files") {
+        Some(Value::Object(files)) => Ok(files.clone()),
+        _ => Err("not a baseline: expected an object under `files`".to_string()),
+    }
+}
+
+/// `old -> new (+delta, +percent)`, and whether it grew by more than `max_growth`
+/// percent. Sizes that were 0 have no percentage and never count as growth.
+fn size_change(old: u64, new: u64, max_growth: f64) -> (String, bool) {
+    let delta = new as i64 - old as i64;
+    if old == 0 {
+        return (format!("{} -> {} ({:+})", old, new, delta), false);
+    }
+    let percent = delta as f64 * 100.0 / old as f64;
+    (
+        format!("{} -> {} ({:+}, {:+.2}%)", old, new, delta, percent),
+        percent > max_growth,
+    )
+}
+
+/// Compares one file with its baseline entry, printing the changes, and returns
+/// the regressions as findings.
+fn compare(old: &Value, new: &Value, max_growth: f64) -> Vec<Finding> {
+    let mut regressions = Vec::new();
+    let mut regression = |check: &str, message: String| {
+        regressions.push(Finding {
+            severity: Severity::Error,
+            check: format!("baseline/{}", check),
+            message,
+        })
+    };
+    let size = |entry: &Value| entry.get("size").and_then(Value::as_u64).unwrap_or(0);
+    let (text, grew) = size_change(size(old), size(new), max_growth);
+    display_line("File Size", &text);
+    if grew {
+        regression("size", format!("the file grew {}", text));
+    }
+
+    let sections = |entry: &Value| {
+        entry
+            .get("sections")
+            .and_then(Value::as_object)
+            .cloned()
+            .unwrap_or_default()
+    };
+    let (old_sections, new_sections) = (sections(old), sections(new));
+    let mut changes = Vec::new();
+    for (name, value) in &new_sections {
+        let new_size = value.as_u64().unwrap_or(0);
+        match old_sections.get(name).and_then(Value::as_u64) {
+            None => changes.push(format!("{:<24} added ({} bytes)", name, new_size)),
+            Some(old_size) if old_size != new_size => {
+                let (text, grew) = size_change(old_size, new_size, max_growth);
+                if grew {
+                    regression("size", format!("{} grew {}", name, text));
+                    changes.push(format!("{:<24} {}  regression", name, text));
+                } else {
+                    changes.push(format!("{:<24} {}", name, text));
+                }
+            }
+            Some(_) => {}
+        }
+    }
+    for (name, value) in &old_sections {
+        if !new_sections.contains_key(name) {
+            changes.push(format!(
+                "{:<24} removed (was {} bytes)",
+                name,
+                value.as_u64().unwrap_or(0)
+            ));
+        }
+    }
+    display_line("Changed Sections", &changes.len().to_string());
+    for change in &changes {
+        output::item(1, change);
+    }
+
+    let hardening = |entry: &Value, name: &str| {
+        entry
+            .get("hardening")
+            .and_then(|hardening| hardening.get(name))
+            .cloned()
+            .unwrap_or(Value::Null)
+    };
+    let mut changes = Vec::new();
+    let rank = |value: &Value| {
+        RELRO_LEVELS
+            .iter()
+            .position(|level| value.as_str() == Some(*level))
+    };
+    let (old_relro, new_relro) = (hardening(old, "relro"), hardening(new, "relro"));
+    if old_relro != new_relro {
+        let text = format!(
+            "relro {} -> {}",
+            old_relro.as_str().unwrap_or("unknown"),
+            new_relro.as_str().unwrap_or("unknown")
+        );
+        if rank(&new_relro) < rank(&old_relro) {
+            regression("hardening", format!("RELRO downgraded: {}", text));
+            changes.push(format!("{}  regression", text));
+        } else {
+            changes.push(text);
+        }
+    }
+    for &(name, hardened) in HARDENING {
+        let (old_value, new_value) = (hardening(old, name), hardening(new, name));
+        if old_value == new_value {
+            continue;
+        }
+        let text = format!("{} {} -> {}", name, old_value, new_value);
+        if old_value.as_bool() == Some(hardened) {
+            regression("hardening", format!("{} regressed: {}", name, text));
+            changes.push(format!("{}  regression", text));
+        } else {
+            changes.push(text);
+        }
+    }
+    display_line("Hardening Changes", &changes.len().to_string());
+    for change in &changes {
+        output::item(1, change);
+    }
+    display_line("Regressions", &regressions.len().to_string());
+    regressions
+}
+
+/// `--baseline FILE`: the changes of every target since the baseline, failing
+/// the files that grew by more than `max_growth` percent or lost hardening.
+pub fn display_baseline(
+    targets: &[(String, Loader)],
+    baseline: &Map<String, Value>,
+    max_growth: f64,
+) -> Vec<(String, String)> {
+    let mut failures = Vec::new();
+    for (label, loader) in targets {
+        output::begin(label);
+        let Some(old) = baseline.get(&key(label)) else {
+            display_line("Baseline", "Not in the baseline");
+            output::end();
+            continue;
+        };
+        let regressions = compare(old, &entry(loader), max_growth);
+        output::end();
+        finding::record(label, BASELINE_CHECKS, &regressions);
+        if !regressions.is_empty() {
+            failures.push((
+                label.clone(),
+                format!("{} regression(s) since the baseline", regressions.len()),
+            ));
+        }
+    }
+    failures
+}
@@ -0,0 +1,485 @@
+use memchr::memmem;
+
+use crate::output;
+use crate::section::SHT_NOBITS;
+use crate::{display_line, Loader};
+
+const MULTIBOOT_MAGIC: u32 = 0x1bad_b002;
+const MULTIBOOT2_MAGIC: u32 = 0xe852_50d6;
+
+/// How far into the image boot loaders look for each header, and its alignment.
+const MULTIBOOT_SEARCH: usize = 8192;
+const MULTIBOOT_ALIGN: usize = 4;
+const MULTIBOOT2_SEARCH: usize = 32768;
+const MULTIBOOT2_ALIGN: usize = 8;
+
+/// Multiboot header flags: bit 16 asks for the address fields to be used
+/// instead of the ELF program headers, bit 2 for a video mode.
+const MULTIBOOT_FLAGS: &[(u32, &str)] = &[
+    (1 << 0, "page-align"),
+    (1 << 1, "memory-info"),
+    (1 << 2, "video-mode"),
+    (1 << 16, "address fields"),
+];
+const MULTIBOOT_VIDEO_MODE: u32 = 1 << 2;
+const MULTIBOOT_AOUT_KLUDGE: u32 = 1 << 16;
+
+/// Multiboot2 header tags by type.
+const MULTIBOOT2_TAG_END: u16 = 0;
+const MULTIBOOT2_TAG_INFORMATION_REQUEST: u16 = 1;
+const MULTIBOOT2_TAG_ADDRESS: u16 = 2;
+const MULTIBOOT2_TAG_ENTRY_ADDRESS: u16 = 3;
+const MULTIBOOT2_TAG_CONSOLE_FLAGS: u16 = 4;
+const MULTIBOOT2_TAG_FRAMEBUFFER: u16 = 5;
+const MULTIBOOT2_TAG_MODULE_ALIGN: u16 = 6;
+const MULTIBOOT2_TAG_EFI_BS: u16 = 7;
+const MULTIBOOT2_TAG_ENTRY_ADDRESS_EFI32: u16 = 8;
+const MULTIBOOT2_TAG_ENTRY_ADDRESS_EFI64: u16 = 9;
+const MULTIBOOT2_TAG_RELOCATABLE: u16 = 10;
+/// A tag the boot loader may ignore when it does not support it.
+const MULTIBOOT2_TAG_OPTIONAL: u16 = 1 << 0;
+
+/// `HdrS`, the signature of the x86 Linux setup header, and where it is
+/// relative to the header's first field, `setup_sects` at 0x1f1.
+const LINUX_SIGNATURE: &[u8] = b"HdrS";
+const LINUX_SIGNATURE_OFFSET: usize = 0x202 - 0x1f1;
+/// The setup header as `arch/x86/boot/setup.ld` places it, in a `.header`
+/// section.
+const LINUX_HEADER_SECTION: &str = ".header";
+
+const LINUX_LOADFLAGS: &[(u8, &str)] = &[
+    (1 << 0, "LOADED_HIGH"),
+    (1 << 1, "KASLR_FLAG"),
+    (1 << 5, "QUIET_FLAG"),
+    (1 << 6, "KEEP_SEGMENTS"),
+    (1 << 7, "CAN_USE_HEAP"),
+];
+const LINUX_XLOADFLAGS: &[(u16, &str)] = &[
+    (1 << 0, "XLF_KERNEL_64"),
+    (1 << 1, "XLF_CAN_BE_LOADED_ABOVE_4G"),
+    (1 << 2, "XLF_EFI_HANDOVER_32"),
+    (1 << 3, "XLF_EFI_HANDOVER_64"),
+    (1 << 4, "XLF_EFI_KEXEC"),
+    (1 << 5, "XLF_5LEVEL"),
+    (1 << 6, "XLF_5LEVEL_ENABLED"),
+    (1 << 7, "XLF_MEM_ENCRYPTION"),
+];
+
+/// A boot header and where the file has it.
+pub struct Found<T> {
+    pub offset: usize,
+    /// The section holding it, if any.
+    pub section: Option<String>,
+    pub header: T,
+}
+
+pub struct Multiboot {
+    pub flags: u32,
+    /// `header_addr`, `load_addr`, `load_end_addr`, `bss_end_addr` and
+    /// `entry_addr`, when the flags ask for them.
+    pub addresses: Option<[u32; 5]>,
+    /// `mode_type`, `width`, `height` and `depth`, when the flags ask for a
+    /// video mode.
+    pub video: Option<[u32; 4]>,
+}
+
+pub struct Multiboot2 {
+    pub architecture: u32,
+    pub header_length: u32,
+    /// The tags after the fixed part, described one per line.
+    pub tags: Vec<String>,
+}
+
+/// The fields of the x86 Linux setup header the boot loader acts on, those
+/// newer than the header's protocol version left out.
+pub struct LinuxSetup {
+    pub version: u16,
+    pub fields: Vec<(&'static str, String)>,
+}
+
+fn flag_names<T>(value: T, names: &[(T, &str)]) -> String
+where
+    T: Copy + std::ops::BitAnd<Output = T> + PartialEq + Default,
+{
+    let set: Vec<&str> = names
+        .iter()
+        .filter(|(bit, _)| value & *bit != T::default())
+        .map(|(_, name)| *name)
+        .collect();
+    if set.is_empty() {
+        "none".to_string()
+    } else {
+        set.join(", ")
+    }
+}
+
+/// The section whose contents hold file offset `offset`.
+fn section_at(loader: &Loader, offset: usize) -> Option<String> {
+    loader
+        .section_headers()
+        .into_iter()
+        .find(|section| {
+            let start = section.sh_offset as usize;
+            section.index != 0
+                && section.sh_type != SHT_NOBITS
+                && (start..start.saturating_add(section.sh_size as usize)).contains(&offset)
+        })
+        .map(|section| section.name)
+}
+
+/// The offsets up to `limit` and aligned to `align` holding `magic` in the
+/// file's byte order.
+fn candidates(loader: &Loader, magic: u32, limit: usize, align: usize) -> Vec<usize> {
+    let bytes = loader.bytes();
+    let window = &bytes[..limit.min(bytes.len())];
+    let pattern = if loader.is_big_endian() {
+        magic.to_be_bytes()
+    } else {
+        magic.to_le_bytes()
+    };
+    memmem::find_iter(window, &pattern)
+        .filter(|offset| offset % align == 0)
+        .collect()
+}
+
+/// The Multiboot header: a magic in the first 8 KiB whose checksum adds up.
+pub fn multiboot(loader: &Loader) -> Option<Found<Multiboot>> {
+    candidates(loader, MULTIBOOT_MAGIC, MULTIBOOT_SEARCH, MULTIBOOT_ALIGN)
+        .into_iter()
+        .find_map(|offset| {
+            let flags = loader.read_u32(offset + 4)?;
+            let checksum = loader.read_u32(offset + 8)?;
+            if MULTIBOOT_MAGIC.wrapping_add(flags).wrapping_add(checksum) != 0 {
+                return None;
+            }
+            let words = |start: usize, count: usize| -> Option<Vec<u32>> {
+                (0..count)
+                    .map(|index| loader.read_u32(offset + start + 4 * index))
+                    .collect()
+            };
+            let addresses = if flags & MULTIBOOT_AOUT_KLUDGE != 0 {
+                words(12, 5).and_then(|words| words.try_into().ok())
+            } else {
+                None
+            };
+            let video = if flags & MULTIBOOT_VIDEO_MODE != 0 {
+                words(32, 4).and_then(|words| words.try_into().ok())
+            } else {
+                None
+            };
+            Some(Found {
+                offset,
+                section: section_at(loader, offset),
+                header: Multiboot {
+                    flags,
+                    addresses,
+                    video,
+                },
+            })
+        })
+}
+
+/// One Multiboot2 header tag as a line, `None` for one cut short.
+fn multiboot2_tag(loader: &Loader, offset: usize, tag_type: u16, size: usize) -> Option<String> {
+    let word = |at: usize| loader.read_u32(offset + at);
+    Some(match tag_type {
+        MULTIBOOT2_TAG_INFORMATION_REQUEST => {
+            let requests: Option<Vec<String>> = (8..size)
+                .step_by(4)
+                .map(|at| word(at).map(|request| request.to_string()))
+                .collect();
+            format!("information request: {}", requests?.join(" "))
+        }
+        MULTIBOOT2_TAG_ADDRESS => format!(
+            "address: header_addr {:#x}, load_addr {:#x}, load_end_addr {:#x}, bss_end_addr {:#x}",
+            word(8)?,
+            word(12)?,
+            word(16)?,
+            word(20)?
+        ),
+        MULTIBOOT2_TAG_ENTRY_ADDRESS => format!("entry address: {:#x}", word(8)?),
+        MULTIBOOT2_TAG_ENTRY_ADDRESS_EFI32 => format!("EFI i386 entry address: {:#x}", word(8)?),
+        MULTIBOOT2_TAG_ENTRY_ADDRESS_EFI64 => {
+            format!("EFI amd64 entry address: {:#x}", word(8)?)
+        }
+        MULTIBOOT2_TAG_CONSOLE_FLAGS => format!("console flags: {:#x}", word(8)?),
+        MULTIBOOT2_TAG_FRAMEBUFFER => {
+            format!("framebuffer: {}x{}x{}", word(8)?, word(12)?, word(16)?)
+        }
+        MULTIBOOT2_TAG_MODULE_ALIGN => "module alignment: page aligned".to_string(),
+        MULTIBOOT2_TAG_EFI_BS => "EFI boot services: left running".to_string(),
+        MULTIBOOT2_TAG_RELOCATABLE => {
+            let preference = match word(20)? {
+                0 => "none",
+                1 => "lowest",
+                2 => "highest",
+                _ => "unknown",
+            };
+            format!(
+                "relocatable: {:#x}-{:#x}, align {:#x}, preference {}",
+                word(8)?,
+                word(12)?,
+                word(16)?,
+                preference
+            )
+        }
+        other => format!("unknown tag {}", other),
+    })
+}
+
+/// The Multiboot2 header: a magic in the first 32 KiB whose checksum adds up,
+/// with its tags up to the end tag.
+pub fn multiboot2(loader: &Loader) -> Option<Found<Multiboot2>> {
+    candidates(
+        loader,
+        MULTIBOOT2_MAGIC,
+        MULTIBOOT2_SEARCH,
+        MULTIBOOT2_ALIGN,
+    )
+    .into_iter()
+    .find_map(|offset| {
+        let architecture = loader.read_u32(offset + 4)?;
+        let header_length = loader.read_u32(offset + 8)?;
+        let checksum = loader.read_u32(offset + 12)?;
+        let sum = MULTIBOOT2_MAGIC
+            .wrapping_add(architecture)
+            .wrapping_add(header_length)
+            .wrapping_add(checksum);
+        if sum != 0 {
+            return None;
+        }
+        let end = offset.saturating_add(header_length as usize);
+        let mut tags = Vec::new();
+        let mut tag = offset + 16;
+        while tag + 8 <= end {
+            let (Some(tag_type), Some(tag_flags), Some(size)) = (
+                loader.read_u16(tag),
+                loader.read_u16(tag + 2),
+                loader.read_u32(tag + 4),
+            ) else {
+                break;
+            };
+            let size = size as usize;
+            if tag_type == MULTIBOOT2_TAG_END || size < 8 {
+                break;
+            }
+            let Some(mut line) = multiboot2_tag(loader, tag, tag_type, size) else {
+                tags.push(format!("tag {} cut short", tag_type));
+                break;
+            };
+            if tag_flags & MULTIBOOT2_TAG_OPTIONAL != 0 {
+                line.push_str(" (optional)");
+            }
+            tags.push(line);
+            tag += size.next_multiple_of(8);
+        }
+        Some(Found {
+            offset,
+            section: section_at(loader, offset),
+            header: Multiboot2 {
+                architecture,
+                header_length,
+                tags,
+            },
+        })
+    })
+}
+
+fn le16(bytes: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(bytes.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn le32(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn le64(bytes: &[u8], at: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(bytes.get(at..at + 8)?.try_into().ok()?))
+}
+
+/// The fields of the setup header at the start of `bytes`, `setup_sects` at
+/// 0; offsets below are those of `Documentation/arch/x86/boot.rst` less 0x1f1.
+fn linux_setup_fields(bytes: &[u8]) -> Option<LinuxSetup> {
+    let version = le16(bytes, 0x206 - 0x1f1)?;
+    let at = |offset: usize| offset - 0x1f1;
+    let hex32 = |offset: usize| le32(bytes, at(offset)).map(|value| format!("{:#x}", value));
+    let mut fields = vec![
+        ("setup_sects", bytes.first()?.to_string()),
+        ("boot_flag", format!("{:#06x}", le16(bytes, at(0x1fe))?)),
+    ];
+    let loadflags = *bytes.get(at(0x211))?;
+    fields.push((
+        "loadflags",
+        format!(
+            "{:#04x} ({})",
+            loadflags,
+            flag_names(loadflags, LINUX_LOADFLAGS)
+        ),
+    ));
+    fields.push(("code32_start", hex32(0x214)?));
+    // Each field exists from the protocol version given with it.
+    let newer: [(u16, &str, Option<String>); 9] = [
+        (0x203, "initrd_addr_max", hex32(0x22c)),
+        (0x205, "kernel_alignment", hex32(0x230)),
+        (
+            0x205,
+            "relocatable_kernel",
+            bytes
+                .get(at(0x234))
+                .map(|&value| if value != 0 { "yes" } else { "no" }.to_string()),
+        ),
+        (
+            0x20a,
+            "min_alignment",
+            bytes
+                .get(at(0x235))
+                .map(|&shift| format!("{:#x}", 1u64 << (shift & 63))),
+        ),
+        (
+            0x20c,
+            "xloadflags",
+            le16(bytes, at(0x236))
+                .map(|value| format!("{:#06x} ({})", value, flag_names(value, LINUX_XLOADFLAGS))),
+        ),
+        (
+            0x206,
+            "cmdline_size",
+            le32(bytes, at(0x238)).map(|value| value.to_string()),
+        ),
+        (
+            0x20a,
+            "pref_address",
+            le64(bytes, at(0x258)).map(|value| format!("{:#x}", value)),
+        ),
+        (0x20a, "init_size", hex32(0x260)),
+        (0x20b, "handover_offset", hex32(0x264)),
+    ];
+    for (since, name, value) in newer {
+        if version >= since {
+            fields.push((name, value?));
+        }
+    }
+    Some(LinuxSetup { version, fields })
+}
+
+/// The x86 Linux setup header, looked for in a `.header` section, as the
+/// `setup.elf` of a kernel build has it, or at 0x1f1 into the file.
+pub fn linux_setup(loader: &Loader) -> Option<Found<LinuxSetup>> {
+    let bytes = loader.bytes();
+    let mut starts = Vec::new();
+    if let Some(section) = loader.section_by_name(LINUX_HEADER_SECTION) {
+        let start = section.sh_offset as usize;
+        let end = start
+            .saturating_add(section.sh_size as usize)
+            .min(bytes.len());
+        if let Some(contents) = bytes.get(start..end) {
+            starts.extend(
+                memmem::find_iter(contents, LINUX_SIGNATURE)
+                    .filter_map(|at| (start + at).checked_sub(LINUX_SIGNATURE_OFFSET)),
+            );
+        }
+    }
+    starts.push(0x1f1);
+    starts.into_iter().find_map(|offset| {
+        let setup = bytes.get(offset..)?;
+        if setup.get(LINUX_SIGNATURE_OFFSET..LINUX_SIGNATURE_OFFSET + 4)? != LINUX_SIGNATURE {
+            return None;
+        }
+        Some(Found {
+            offset,
+            section: section_at(loader, offset),
+            header: linux_setup_fields(setup)?,
+        })
+    })
+}
+
+fn location<T>(found: &Found<T>) -> String {
+    match &found.section {
+        Some(section) => format!("at file offset {:#x} in {}", found.offset, section),
+        None => format!("at file offset {:#x}", found.offset),
+    }
+}
+
+/// `--boot`: the Multiboot, Multiboot2 and x86 Linux boot protocol headers a
+/// kernel carries for its boot loader, with the load addresses and flags they
+/// ask for.
+pub fn display_boot(path: &str, loader: &Loader) {
+    output::begin(path);
+    match multiboot(loader) {
+        Some(found) => {
+            let header = &found.header;
+            display_line("Multiboot", &location(&found));
+            output::item(
+                1,
+                &format!(
+                    "flags {:#010x} ({})",
+                    header.flags,
+                    flag_names(header.flags, MULTIBOOT_FLAGS)
+                ),
+            );
+            match header.addresses {
+                Some([header_addr, load_addr, load_end_addr, bss_end_addr, entry_addr]) => {
+                    output::item(
+                        1,
+                        &format!(
+                            "header_addr {:#x}, load_addr {:#x}, load_end_addr {:#x}, bss_end_addr {:#x}",
+                            header_addr, load_addr, load_end_addr, bss_end_addr
+                        ),
+                    );
+                    output::item(1, &format!("entry_addr {:#x}", entry_addr));
+                }
+                None => output::item(1, "loaded by the ELF program headers"),
+            }
+            if let Some([mode_type, width, height, depth]) = header.video {
+                let mode = if mode_type == 0 { "linear" } else { "text" };
+                output::item(
+                    1,
+                    &format!("video mode {} {}x{}x{}", mode, width, height, depth),
+                );
+            }
+        }
+        None => display_line("Multiboot", "none"),
+    }
+    match multiboot2(loader) {
+        Some(found) => {
+            let header = &found.header;
+            let architecture = match header.architecture {
+                0 => "i386".to_string(),
+                4 => "MIPS".to_string(),
+                other => format!("architecture {}", other),
+            };
+            display_line(
+                "Multiboot2",
+                &format!(
+                    "{}, {}, {} bytes",
+                    location(&found),
+                    architecture,
+                    header.header_length
+                ),
+            );
+            for tag in &header.tags {
+                output::item(1, tag);
+            }
+        }
+        None => display_line("Multiboot2", "none"),
+    }
+    match linux_setup(loader) {
+        Some(found) => {
+            let header = &found.header;
+            display_line(
+                "Linux Boot Protocol",
+                &format!(
+                    "{}.{:02} {}",
+                    header.version >> 8,
+                    header.version & 0xff,
+                    location(&found)
+                ),
+            );
+            for (name, value) in &header.fields {
+                output::item(1, &format!("{} {}", name, value));
+            }
+        }
+        None => display_line("Linux Boot Protocol", "none"),
+    }
+    output::end();
+}
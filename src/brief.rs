@@ -0,0 +1,151 @@
+use crate::linkage::FileKind;
+use crate::note::NT_GNU_ABI_TAG;
+use crate::output;
+use crate::segment::{PT_DYNAMIC, PT_INTERP};
+use crate::Loader;
+
+const ET_REL: u16 = 1;
+const ET_EXEC: u16 = 2;
+const ET_DYN: u16 = 3;
+const ET_CORE: u16 = 4;
+
+/// How `file(1)` names the machines it is most often asked about; the rest use
+/// the registry's name.
+const FILE_MACHINES: &[(u16, &str)] = &[
+    (2, "SPARC"),
+    (3, "Intel 80386"),
+    (8, "MIPS"),
+    (20, "PowerPC or cisco 4500"),
+    (21, "64-bit PowerPC or cisco 7500"),
+    (22, "IBM S/390"),
+    (40, "ARM"),
+    (43, "SPARC V9"),
+    (62, "x86-64"),
+    (183, "ARM aarch64"),
+    (243, "UCB RISC-V"),
+    (258, "LoongArch"),
+];
+
+fn kind(loader: &Loader) -> String {
+    match loader.raw_e_type() {
+        ET_REL => "relocatable".to_string(),
+        ET_EXEC => "executable".to_string(),
+        ET_DYN if loader.file_kind().is_pie() => "pie executable".to_string(),
+        ET_DYN => "shared object".to_string(),
+        ET_CORE => "core file".to_string(),
+        other => format!("unknown type {:#x}", other),
+    }
+}
+
+fn osabi(loader: &Loader) -> String {
+    match loader.raw_ei_osabi() {
+        0 => "SYSV".to_string(),
+        3 => "GNU/Linux".to_string(),
+        _ => loader.get_ei_osabi(),
+    }
+}
+
+/// The program interpreter named by PT_INTERP.
+pub(crate) fn interpreter(loader: &Loader) -> Option<String> {
+    let segment = loader.program_header_by_type(PT_INTERP)?;
+    let start = segment.p_offset as usize;
+    let data = loader
+        .bytes()
+        .get(start..start.saturating_add(segment.p_filesz as usize))?;
+    let end = data
+        .iter()
+        .position(|&byte| byte == 0)
+        .unwrap_or(data.len());
+    Some(String::from_utf8_lossy(&data[..end]).into_owned())
+}
+
+/// `for GNU/Linux 3.2.0`, from the NT_GNU_ABI_TAG note.
+fn abi_tag(loader: &Loader) -> Option<String> {
+    let note = loader.find_note("GNU", NT_GNU_ABI_TAG)?;
+    let word = |offset| loader.desc_u32(&note.desc, offset);
+    let os = match word(0)? {
+        0 => "GNU/Linux",
+        1 => "GNU/Hurd",
+        2 => "Solaris",
+        3 => "FreeBSD",
+        _ => return None,
+    };
+    Some(format!(
+        "for {} {}.{}.{}",
+        os,
+        word(4)?,
+        word(8)?,
+        word(12)?
+    ))
+}
+
+/// The one-line description `file(1)` gives of an ELF file, such as
+/// `ELF 64-bit LSB pie executable, x86-64, version 1 (SYSV), dynamically linked,
+/// interpreter /lib64/ld-linux-x86-64.so.2, BuildID[sha1]=..., not stripped`.
+pub fn describe(loader: &Loader) -> String {
+    let id = loader.raw_e_machine();
+    let machine = FILE_MACHINES
+        .iter()
+        .find(|(known, _)| *known == id)
+        .map(|(_, name)| name.to_string())
+        .unwrap_or_else(|| loader.machine_name());
+    let mut parts = vec![
+        format!(
+            "ELF {}-bit {} {}",
+            if loader.is_64bit() { 64 } else { 32 },
+            if loader.is_big_endian() { "MSB" } else { "LSB" },
+            kind(loader)
+        ),
+        machine,
+        format!("version {} ({})", loader.bytes()[6], osabi(loader)),
+    ];
+    let e_type = loader.raw_e_type();
+    if e_type == ET_CORE {
+        return parts.join(", ");
+    }
+    if matches!(e_type, ET_EXEC | ET_DYN) {
+        let linking = if loader.file_kind() == FileKind::StaticPie {
+            "static-pie linked"
+        } else if loader.program_header_by_type(PT_DYNAMIC).is_some() {
+            "dynamically linked"
+        } else {
+            "statically linked"
+        };
+        parts.push(linking.to_string());
+    }
+    if let Some(interpreter) = interpreter(loader) {
+        parts.push(format!("interpreter {}", interpreter));
+    }
+    if let Some(id) = loader.go_build_id() {
+        parts.push(format!("Go BuildID={}", id));
+    }
+    let facts = loader.debug_facts();
+    if let Some(id) = &facts.build_id {
+        let hash = match id.len() {
+            20 => "sha1",
+            16 => "md5",
+            8 => "xxHash",
+            _ => "uuid",
+        };
+        let hex: String = id.iter().map(|byte| format!("{:02x}", byte)).collect();
+        parts.push(format!("BuildID[{}]={}", hash, hex));
+    }
+    parts.extend(abi_tag(loader));
+    if facts.dwarf.iter().any(|name| name.ends_with("debug_info")) {
+        parts.push("with debug_info".to_string());
+    }
+    parts.push(
+        if facts.stripped() {
+            "stripped"
+        } else {
+            "not stripped"
+        }
+        .to_string(),
+    );
+    parts.join(", ")
+}
+
+/// `--brief`: one line per file in the style of `file(1)`.
+pub fn display_brief(path: &str, loader: &Loader) {
+    output::message(&format!("{}: {}", path, describe(loader)));
+}
@@ -0,0 +1,55 @@
+//! What the binary was built from, recorded by `build.rs`: shown by
+//! `--version` and kept in the structured reports, so that a report can be
+//! traced back to the build that wrote it.
+
+use serde_json::{json, Value};
+
+pub const NAME: &str = env!("CARGO_PKG_NAME");
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+/// The short commit hash, or `unknown` for builds outside a git checkout.
+pub const GIT_HASH: &str = env!("ELFHP_GIT_HASH");
+/// The target triple, such as `x86_64-unknown-linux-gnu`.
+pub const TARGET: &str = env!("ELFHP_TARGET");
+const FEATURES: &str = env!("ELFHP_FEATURES");
+
+/// The Cargo features the binary was built with, sorted.
+pub fn features() -> Vec<&'static str> {
+    FEATURES
+        .split(',')
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+/// The build as the `tool` block of the JSON files written, such as
+/// baselines and manifests.
+pub fn tool() -> Value {
+    json!({
+        "name": NAME,
+        "version": VERSION,
+        "git": GIT_HASH,
+        "target": TARGET,
+        "features": features(),
+    })
+}
+
+/// `--version`: the name and version, with the commit.
+pub fn version() -> String {
+    format!("{} {} ({})", NAME, VERSION, GIT_HASH)
+}
+
+/// `--version --verbose`: one line per fact.
+pub fn version_verbose() -> String {
+    let features = features();
+    format!(
+        "{}\nversion: {}\ncommit: {}\ntarget: {}\nfeatures: {}\n",
+        NAME,
+        VERSION,
+        GIT_HASH,
+        TARGET,
+        if features.is_empty() {
+            "none".to_string()
+        } else {
+            features.join(", ")
+        }
+    )
+}
@@ -0,0 +1,192 @@
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use crate::output;
+use crate::{display_line, Loader};
+
+/// `AI` and the type from `e_ident[EI_ABIVERSION]`, as the AppImage runtime marks itself.
+const APPIMAGE_MAGIC: [u8; 2] = *b"AI";
+const APPIMAGE_MAGIC_OFFSET: usize = 8;
+
+const SQUASHFS_MAGIC: [u8; 4] = *b"hsqs";
+/// Offset of `bytes_used` in the squashfs superblock.
+const SQUASHFS_BYTES_USED: usize = 40;
+/// Offset of the compression id in the squashfs superblock.
+const SQUASHFS_COMPRESSION: usize = 20;
+
+/// What an AppImage keeps besides its runtime.
+pub struct AppImage {
+    /// 1 for an ISO 9660 payload, 2 for squashfs.
+    pub kind: u8,
+    /// The end of the runtime, where the payload starts.
+    pub payload_offset: usize,
+    /// The size of a type 2 squashfs payload, from its superblock.
+    pub payload_size: Option<u64>,
+    pub compression: Option<&'static str>,
+    /// The `.upd_info` string `appimageupdatetool` reads, if set.
+    pub update_info: Option<String>,
+    /// Whether `.sha256_sig` holds a signature.
+    pub signed: bool,
+}
+
+fn compression_name(id: u16) -> &'static str {
+    match id {
+        1 => "gzip",
+        2 => "lzma",
+        3 => "lzo",
+        4 => "xz",
+        5 => "lz4",
+        6 => "zstd",
+        _ => "unknown",
+    }
+}
+
+impl Loader {
+    /// The AppImage type marked in `e_ident` by its runtime.
+    pub(crate) fn appimage_kind(&self) -> Option<u8> {
+        let magic = self
+            .bytes()
+            .get(APPIMAGE_MAGIC_OFFSET..APPIMAGE_MAGIC_OFFSET + 3)?;
+        (magic[..2] == APPIMAGE_MAGIC && matches!(magic[2], 1 | 2)).then_some(magic[2])
+    }
+
+    /// The AppImage this runtime starts, or `None` for any other ELF file.
+    pub fn appimage(&self) -> Option<AppImage> {
+        let kind = self.appimage_kind()?;
+        let bytes = self.bytes();
+        // The runtime ends with its section header table, the payload follows.
+        let payload_offset = self.image_size();
+        let superblock = bytes
+            .get(payload_offset..)
+            .filter(|payload| payload.starts_with(&SQUASHFS_MAGIC));
+        let payload_size = superblock
+            .and_then(|payload| payload.get(SQUASHFS_BYTES_USED..SQUASHFS_BYTES_USED + 8))
+            .map(|used| u64::from_le_bytes(used.try_into().unwrap()));
+        let compression = superblock
+            .and_then(|payload| payload.get(SQUASHFS_COMPRESSION..SQUASHFS_COMPRESSION + 2))
+            .map(|id| compression_name(u16::from_le_bytes([id[0], id[1]])));
+        let section = |name: &str| {
+            self.section_by_name(name)
+                .map(|section| self.section_data(&section).to_vec())
+                .unwrap_or_default()
+        };
+        let update_info = section(".upd_info");
+        let end = update_info
+            .iter()
+            .position(|&byte| byte == 0)
+            .unwrap_or(update_info.len());
+        let update_info = String::from_utf8_lossy(&update_info[..end]).into_owned();
+        Some(AppImage {
+            kind,
+            payload_offset,
+            payload_size,
+            compression,
+            update_info: (!update_info.is_empty()).then_some(update_info),
+            signed: section(".sha256_sig").iter().any(|&byte| byte != 0),
+        })
+    }
+}
+
+/// `--appimage-runtime`: the runtime of an AppImage on its own, without the
+/// payload behind it.
+pub fn appimage_runtime(loader: &Loader) -> Option<Loader> {
+    let appimage = loader.appimage()?;
+    Some(Loader::from_bytes(
+        loader.bytes()[..appimage.payload_offset].to_vec(),
+    ))
+}
+
+/// Where a file sits in a Flatpak installation:
+/// `.../flatpak/{app,runtime}/ID/ARCH/BRANCH/COMMIT/files/...`.
+pub struct FlatpakDeploy {
+    /// `app` or `runtime`.
+    pub kind: String,
+    pub id: String,
+    pub arch: String,
+    pub branch: String,
+    /// The deploy directory, which holds the `metadata` file.
+    pub directory: PathBuf,
+}
+
+/// The Flatpak deploy `path` lies in, if any.
+pub fn flatpak_deploy(path: &str) -> Option<FlatpakDeploy> {
+    let path = fs::canonicalize(path).unwrap_or_else(|_| PathBuf::from(path));
+    let parts: Vec<&str> = path
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(part) => part.to_str(),
+            _ => None,
+        })
+        .collect();
+    let start = parts.iter().position(|&part| part == "flatpak")?;
+    let [kind, id, arch, branch, commit, ..] = parts.get(start + 1..)? else {
+        return None;
+    };
+    if !matches!(*kind, "app" | "runtime") {
+        return None;
+    }
+    let directory = Path::new("/")
+        .join(parts[..start + 1].join("/"))
+        .join([*kind, *id, *arch, *branch, *commit].join("/"));
+    Some(FlatpakDeploy {
+        kind: kind.to_string(),
+        id: id.to_string(),
+        arch: arch.to_string(),
+        branch: branch.to_string(),
+        directory,
+    })
+}
+
+/// A `key=value` of the deploy's `metadata` keyfile, e.g. `runtime` or `sdk`.
+fn metadata_value(metadata: &str, key: &str) -> Option<String> {
+    metadata.lines().find_map(|line| {
+        let (name, value) = line.split_once('=')?;
+        (name.trim() == key).then(|| value.trim().to_string())
+    })
+}
+
+/// `--bundle`: the AppImage a runtime starts, with its payload offset, size and
+/// compression, update information and signature, or the Flatpak the file was
+/// installed by, with the runtime it runs on.
+pub fn display_bundle(path: &str, loader: &Loader) {
+    output::begin(path);
+    if let Some(appimage) = loader.appimage() {
+        display_line("Format", &format!("AppImage type {}", appimage.kind));
+        display_line(
+            "Runtime Size",
+            &format!("{} bytes", appimage.payload_offset),
+        );
+        // A type 1 AppImage is an ISO 9660 image the runtime was written into.
+        let payload = if appimage.kind == 1 {
+            "ISO 9660 around the runtime".to_string()
+        } else {
+            format!("squashfs at {:#x}", appimage.payload_offset)
+        };
+        display_line("Payload", &payload);
+        if let Some(size) = appimage.payload_size {
+            display_line("Payload Size", &format!("{} bytes", size));
+        }
+        if let Some(compression) = appimage.compression {
+            display_line("Compression", compression);
+        }
+        display_line(
+            "Update Information",
+            appimage.update_info.as_deref().unwrap_or("None"),
+        );
+        display_line("Signed", if appimage.signed { "Yes" } else { "No" });
+    } else if let Some(deploy) = flatpak_deploy(path) {
+        display_line("Format", &format!("Flatpak {}", deploy.kind));
+        display_line("ID", &deploy.id);
+        display_line("Arch", &deploy.arch);
+        display_line("Branch", &deploy.branch);
+        let metadata = fs::read_to_string(deploy.directory.join("metadata")).unwrap_or_default();
+        for key in ["runtime", "sdk"] {
+            if let Some(value) = metadata_value(&metadata, key) {
+                display_line(if key == "sdk" { "SDK" } else { "Runtime" }, &value);
+            }
+        }
+    } else {
+        display_line("Format", "None");
+    }
+    output::end();
+}
@@ -0,0 +1,222 @@
+use log::info;
+use sha2::{Digest, Sha256};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::output::{self, Record};
+use crate::Loader;
+
+/// What makes two inputs the same binary. The build-id alone is not enough: a
+/// stripped copy keeps it but reports fewer symbols, so the size goes with it.
+/// Files without a build-id are hashed.
+#[derive(PartialEq, Eq, Hash)]
+enum ContentKey {
+    BuildId(Vec<u8>, usize),
+    Sha256([u8; 32]),
+}
+
+impl ContentKey {
+    fn of(loader: &Loader) -> ContentKey {
+        match loader.build_id() {
+            Some(id) => ContentKey::BuildId(id, loader.bytes().len()),
+            None => ContentKey::Sha256(Sha256::digest(loader.bytes()).into()),
+        }
+    }
+}
+
+/// The report function, the content and, for reports that look around the
+/// input, its path.
+type ReportKey = (usize, ContentKey, Option<PathBuf>);
+
+/// A report made once, with the label it was made for.
+struct Cached {
+    label: String,
+    records: Vec<Record>,
+}
+
+/// Cleared by `--no-cache` for the whole process, `serve` threads included.
+static ENABLED: AtomicBool = AtomicBool::new(true);
+
+thread_local! {
+    /// Keyed by the report function as well, since one run can make several kinds.
+    static REPORTS: RefCell<HashMap<ReportKey, Cached>> = RefCell::new(HashMap::new());
+}
+
+/// `--no-cache`: report every input on its own.
+pub fn disable() {
+    ENABLED.store(false, Ordering::Relaxed);
+}
+
+/// `records`, made for `from`, retitled for `to`. Reports are titled with the
+/// label and messages start with it; nothing else in them names the file.
+fn relabel(records: &[Record], from: &str, to: &str) -> Vec<Record> {
+    records
+        .iter()
+        .map(|record| match record {
+            Record::Report { title, entries } if title == from => Record::Report {
+                title: to.to_string(),
+                entries: entries.clone(),
+            },
+            Record::Message(text) => match text.strip_prefix(from) {
+                Some(rest) => Record::Message(format!("{}{}", to, rest)),
+                None => record.clone(),
+            },
+            _ => record.clone(),
+        })
+        .collect()
+}
+
+/// Runs `display` for `label`, unless a binary with the same content was already
+/// reported this run, in which case that report is printed again under `label`.
+pub fn display_once(label: &str, loader: &Loader, display: fn(&str, &Loader)) {
+    display_cached(label, loader, display, None);
+}
+
+/// [`display_once`] for reports that read the files next to the input or
+/// depend on its directory, such as the debug file of `--debug-info`: a
+/// report is only reused for the same content at the same place.
+pub fn display_once_at(label: &str, loader: &Loader, display: fn(&str, &Loader)) {
+    let path = fs::canonicalize(label).unwrap_or_else(|_| PathBuf::from(label));
+    display_cached(label, loader, display, Some(path));
+}
+
+fn display_cached(label: &str, loader: &Loader, display: fn(&str, &Loader), path: Option<PathBuf>) {
+    if !ENABLED.load(Ordering::Relaxed) {
+        display(label, loader);
+        return;
+    }
+    let key = (display as usize, ContentKey::of(loader), path);
+    let hit = REPORTS.with(|reports| {
+        reports.borrow().get(&key).map(|cached| {
+            info!("{}: same content as {}, report reused", label, cached.label);
+            relabel(&cached.records, &cached.label, label)
+        })
+    });
+    let records = match hit {
+        Some(records) => records,
+        None => {
+            let records = output::collect(|| display(label, loader));
+            REPORTS.with(|reports| {
+                reports.borrow_mut().insert(
+                    key,
+                    Cached {
+                        label: label.to_string(),
+                        records: records.clone(),
+                    },
+                )
+            });
+            records
+        }
+    };
+    output::replay(&records);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::path::Path;
+    use std::process;
+
+    use super::display_once_at;
+    use crate::checksum::crc32;
+    use crate::debuginfo;
+    use crate::output::{self, Entry, Record};
+    use crate::Loader;
+
+    fn u64_at(bytes: &[u8], at: usize) -> usize {
+        u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap()) as usize
+    }
+
+    fn u16_at(bytes: &[u8], at: usize) -> usize {
+        u16::from_le_bytes(bytes[at..at + 2].try_into().unwrap()) as usize
+    }
+
+    /// `elf`, an ELF64 little-endian file, with a `.gnu_debuglink` to `name`
+    /// added behind everything else, the way `objcopy --add-gnu-debuglink`
+    /// would.
+    fn with_debuglink(mut elf: Vec<u8>, name: &str, debug: &[u8]) -> Vec<u8> {
+        let (shoff, shnum, shstrndx) = (u64_at(&elf, 40), u16_at(&elf, 60), u16_at(&elf, 62));
+        let mut headers = elf[shoff..shoff + shnum * 64].to_vec();
+        let names = &headers[shstrndx * 64..shstrndx * 64 + 64];
+        let (names_offset, names_size) = (u64_at(names, 24), u64_at(names, 32));
+        let mut names = elf[names_offset..names_offset + names_size].to_vec();
+        let name_index = names.len() as u32;
+        names.extend_from_slice(b".gnu_debuglink\0");
+
+        let mut link = name.as_bytes().to_vec();
+        link.resize(name.len() + 4 - name.len() % 4, 0);
+        link.extend_from_slice(&crc32(debug).to_le_bytes());
+        elf.resize(elf.len().next_multiple_of(8), 0);
+        let link_offset = elf.len();
+        elf.extend_from_slice(&link);
+        let names_offset = elf.len();
+        elf.extend_from_slice(&names);
+        elf.resize(elf.len().next_multiple_of(8), 0);
+
+        let shstrtab = &mut headers[shstrndx * 64..shstrndx * 64 + 64];
+        shstrtab[24..32].copy_from_slice(&(names_offset as u64).to_le_bytes());
+        shstrtab[32..40].copy_from_slice(&(names.len() as u64).to_le_bytes());
+        let mut header = [0u8; 64];
+        header[0..4].copy_from_slice(&name_index.to_le_bytes());
+        header[4..8].copy_from_slice(&1u32.to_le_bytes());
+        header[24..32].copy_from_slice(&(link_offset as u64).to_le_bytes());
+        header[32..40].copy_from_slice(&(link.len() as u64).to_le_bytes());
+        header[48..56].copy_from_slice(&4u64.to_le_bytes());
+        headers.extend_from_slice(&header);
+        let shoff = elf.len() as u64;
+        elf.extend_from_slice(&headers);
+        elf[40..48].copy_from_slice(&shoff.to_le_bytes());
+        elf[60..62].copy_from_slice(&(shnum as u16 + 1).to_le_bytes());
+        elf
+    }
+
+    fn debug_file(records: &[Record], title: &str) -> String {
+        records
+            .iter()
+            .find_map(|record| match record {
+                Record::Report {
+                    title: found,
+                    entries,
+                } if found == title => entries.iter().find_map(|entry| match entry {
+                    Entry::Field { key, value } if key == "Debug File" => Some(value.clone()),
+                    _ => None,
+                }),
+                _ => None,
+            })
+            .unwrap_or_default()
+    }
+
+    /// Two copies of one binary in different directories, only one of them
+    /// with its debug file alongside: the copies share content, not reports.
+    #[test]
+    fn copies_in_other_directories_find_their_own_debug_file() {
+        let dir = env::temp_dir().join(format!("elf-header-parser-cache-{}", process::id()));
+        let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/x86_64-shared");
+        let debug = b"not really debug info";
+        let elf = with_debuglink(fs::read(fixture).unwrap(), "prog.debug", debug);
+        for copy in ["a", "b"] {
+            fs::create_dir_all(dir.join(copy)).unwrap();
+            fs::write(dir.join(copy).join("prog"), &elf).unwrap();
+        }
+        fs::write(dir.join("a/prog.debug"), debug).unwrap();
+
+        let labels: Vec<String> = ["a", "b"]
+            .iter()
+            .map(|copy| dir.join(copy).join("prog").display().to_string())
+            .collect();
+        let records = output::collect(|| {
+            for label in &labels {
+                let loader = Loader::open(label).unwrap();
+                display_once_at(label, &loader, debuginfo::display_debug_info);
+            }
+        });
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(debug_file(&records, &labels[0]).contains("a/prog.debug"));
+        assert_eq!(debug_file(&records, &labels[1]), "Not found");
+    }
+}
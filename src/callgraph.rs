@@ -0,0 +1,201 @@
+use log::debug;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+
+use crate::dynamic::DT_JMPREL;
+use crate::graph::Graph;
+use crate::linkage::walk;
+use crate::section::{SHT_REL, SHT_RELA};
+use crate::symbol::SHN_UNDEF;
+use crate::{display_line, output, Loader};
+
+/// A function one binary calls through its PLT, and the library expected to
+/// provide it.
+pub struct Edge {
+    pub function: String,
+    /// The DT_NEEDED name of the library, or `None` when none of the binaries
+    /// scanned defines it and no symbol version names it.
+    pub library: Option<String>,
+}
+
+impl Edge {
+    fn target(&self) -> String {
+        format!(
+            "{}@{}",
+            self.function,
+            self.library.as_deref().unwrap_or("?")
+        )
+    }
+}
+
+impl Loader {
+    /// The functions bound through the PLT: the symbols of the DT_JMPREL table,
+    /// with the library their symbol version is required from, if any.
+    pub fn plt_imports(&self) -> Vec<(String, Option<String>)> {
+        let Some(jmprel) = self.dynamic_value(DT_JMPREL) else {
+            return Vec::new();
+        };
+        let sections = self.section_headers();
+        let versions = self.dynamic_symbol_versions();
+        let mut seen = HashSet::new();
+        sections
+            .iter()
+            .filter(|table| table.sh_type == SHT_REL || table.sh_type == SHT_RELA)
+            .filter(|table| table.sh_addr == jmprel)
+            .flat_map(|table| self.relocations_in(table, &sections))
+            .filter(|relocation| relocation.r_sym != 0 && !relocation.symbol.is_empty())
+            .filter(|relocation| seen.insert(relocation.symbol.clone()))
+            .map(|relocation| {
+                let file = versions
+                    .get(relocation.r_sym as usize)
+                    .cloned()
+                    .flatten()
+                    .and_then(|version| version.file);
+                (relocation.symbol, file)
+            })
+            .collect()
+    }
+}
+
+/// The name other binaries list in DT_NEEDED for `path`: its soname, or its file
+/// name when it has none.
+fn needed_name(path: &str, loader: &Loader) -> String {
+    loader.soname().unwrap_or_else(|| {
+        Path::new(path)
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    })
+}
+
+/// The PLT imports of every binary, with unversioned imports attributed to the
+/// binary itself or the first DT_NEEDED library among the scanned ones that
+/// defines the function.
+fn edges(binaries: &[(String, Loader)]) -> Vec<(String, Vec<Edge>)> {
+    let mut exports: HashMap<String, HashSet<String>> = HashMap::new();
+    for (path, loader) in binaries {
+        let defined = loader
+            .dynamic_symbols()
+            .into_iter()
+            .filter(|symbol| symbol.st_shndx != SHN_UNDEF && !symbol.name.is_empty())
+            .map(|symbol| symbol.name);
+        exports
+            .entry(needed_name(path, loader))
+            .or_default()
+            .extend(defined);
+    }
+    binaries
+        .iter()
+        .map(|(path, loader)| {
+            // Calls a library makes to its own exported functions go through the
+            // PLT as well, so that they can be interposed.
+            let mut needed = vec![needed_name(path, loader)];
+            needed.extend(loader.needed_libraries());
+            let edges = loader
+                .plt_imports()
+                .into_iter()
+                .map(|(function, library)| {
+                    let library = library.or_else(|| {
+                        needed
+                            .iter()
+                            .find(|name| {
+                                exports
+                                    .get(*name)
+                                    .is_some_and(|defined| defined.contains(&function))
+                            })
+                            .cloned()
+                    });
+                    Edge { function, library }
+                })
+                .collect();
+            (path.clone(), edges)
+        })
+        .collect()
+}
+
+/// The graph of binaries and the functions they import, grouped by library.
+fn call_graph(graph: &[(String, Vec<Edge>)]) -> Graph {
+    let mut call_graph = Graph::default();
+    for (binary, edges) in graph.iter().filter(|(_, edges)| !edges.is_empty()) {
+        let caller = call_graph.node(binary, binary, None);
+        for edge in edges {
+            let library = edge.library.as_deref().unwrap_or("?");
+            let callee = call_graph.node(&edge.target(), &edge.function, Some(library));
+            call_graph.edge(caller, callee);
+        }
+    }
+    call_graph
+}
+
+/// `call-graph PATH...`: the `binary -> function@library` edges of every dynamic
+/// binary given or found under the directories, from their PLT relocations.
+/// Printed as a report per binary and a per-library summary, or with `--graph` as
+/// one graph. Returns the paths that could not be read.
+pub fn display_call_graph(paths: &[String], graph_format: Option<&str>) -> Vec<(String, String)> {
+    let mut files = Vec::new();
+    let mut errors = Vec::new();
+    for path in paths {
+        if Path::new(path).is_dir() {
+            walk(Path::new(path), &mut files, &mut errors);
+        } else {
+            files.push(path.clone());
+        }
+    }
+    let mut binaries = Vec::new();
+    for path in files {
+        match Loader::open(&path) {
+            Ok(loader) if loader.is_elf() && loader.header_truncation().is_none() => {
+                binaries.push((path, loader))
+            }
+            Ok(_) => debug!("{}: not a complete ELF file, skipped", path),
+            Err(error) => errors.push((path, error.to_string())),
+        }
+    }
+    let graph = edges(&binaries);
+
+    if let Some(format) = graph_format {
+        call_graph(&graph).print(format);
+        return errors;
+    }
+    let mut callers: BTreeMap<&str, (usize, usize)> = BTreeMap::new();
+    for (binary, edges) in &graph {
+        if edges.is_empty() {
+            continue;
+        }
+        output::begin(binary);
+        display_line("Imported Functions", &edges.len().to_string());
+        for edge in edges {
+            output::item(1, &edge.target());
+        }
+        let mut used = HashSet::new();
+        for edge in edges {
+            let library = edge.library.as_deref().unwrap_or("?");
+            let entry = callers.entry(library).or_default();
+            entry.1 += 1;
+            if used.insert(library) {
+                entry.0 += 1;
+            }
+        }
+    }
+    output::begin("");
+    display_line(
+        "Edges",
+        &graph
+            .iter()
+            .map(|(_, edges)| edges.len())
+            .sum::<usize>()
+            .to_string(),
+    );
+    display_line("Libraries", &callers.len().to_string());
+    for (library, (binaries, functions)) in &callers {
+        output::item(
+            1,
+            &format!(
+                "{}: {} function(s), {} caller(s)",
+                library, functions, binaries
+            ),
+        );
+    }
+    output::end();
+    errors
+}
@@ -0,0 +1,80 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::container::EmbeddedElf;
+use crate::Loader;
+use crate::{output, patch};
+
+impl Loader {
+    /// A short `file(1)`-like summary of the image, used to label carved candidates.
+    pub fn summary(&self) -> String {
+        let class = if self.is_64bit() { "ELF64" } else { "ELF32" };
+        let data = if self.is_big_endian() { "MSB" } else { "LSB" };
+        format!(
+            "{} {} {}, {}",
+            class,
+            data,
+            self.get_e_type(),
+            self.machine_name()
+        )
+    }
+}
+
+fn extract(
+    loader: &Loader,
+    image: &EmbeddedElf,
+    path: &str,
+    directory: &str,
+) -> io::Result<String> {
+    let name = Path::new(path)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| "blob".to_string());
+    let output = Path::new(directory).join(format!("{}-{:#x}.elf", name, image.offset));
+    let data = loader.file_bytes();
+    let end = image.offset.saturating_add(image.size).min(data.len());
+    let contents = &data[image.offset..end];
+    fs::create_dir_all(directory)?;
+    patch::write_atomic(&output, contents).map_err(io::Error::other)?;
+    Ok(output.to_string_lossy().to_string())
+}
+
+/// Lists (and with `extract_to`, writes out) every plausible ELF image inside a
+/// blob, given the images and rejected magic occurrences of `Loader::scan`.
+pub fn display_carve(
+    path: &str,
+    loader: &Loader,
+    (images, rejected): (Vec<EmbeddedElf>, usize),
+    extract_to: Option<&str>,
+) {
+    output::begin(path);
+    output::item(
+        1,
+        &format!(
+            "{} ELF image(s) found, {} magic occurrence(s) rejected",
+            images.len(),
+            rejected
+        ),
+    );
+    for image in &images {
+        let summary = loader
+            .at(image.offset)
+            .map(|embedded| embedded.summary())
+            .unwrap_or_default();
+        output::item(
+            1,
+            &format!(
+                "{:>#10x} {:>10} bytes  {}",
+                image.offset, image.size, summary
+            ),
+        );
+        if let Some(directory) = extract_to {
+            match extract(loader, image, path, directory) {
+                Ok(written) => output::item(2, &format!("extracted to {}", written)),
+                Err(error) => output::item(2, &format!("could not extract: {}", error)),
+            }
+        }
+    }
+    output::end();
+}
@@ -0,0 +1,258 @@
+use crate::finding::{Finding, Severity};
+use crate::linkage::FileKind;
+use crate::output;
+use crate::section::{SectionHeader, SHF_ALLOC, SHF_WRITE};
+use crate::segment::{PF_X, PT_GNU_RELRO, PT_GNU_STACK};
+use crate::{display_line, Loader};
+
+const ET_EXEC: u16 = 2;
+const ET_DYN: u16 = 3;
+
+/// The checks behind the `--report` findings of `--checksec`.
+const CHECKSEC_CHECKS: &[&str] = &[
+    "checksec/relro",
+    "checksec/nx",
+    "checksec/pie",
+    "checksec/textrel",
+    "checksec/stack-canary",
+];
+
+pub enum RelroLevel {
+    None,
+    Partial,
+    Full,
+}
+
+impl RelroLevel {
+    pub(crate) fn as_str(&self) -> &str {
+        match *self {
+            RelroLevel::None => "No RELRO",
+            RelroLevel::Partial => "Partial RELRO",
+            RelroLevel::Full => "Full RELRO",
+        }
+    }
+}
+
+pub struct GotEntry {
+    pub address: u64,
+    pub section: String,
+    pub symbol: String,
+    pub r_type: String,
+}
+
+/// What the PT_GNU_RELRO segment protects once the dynamic linker has run.
+pub struct RelroReport {
+    pub level: RelroLevel,
+    /// Start and end (exclusive) of the read-only-after-relocation region.
+    pub region: Option<(u64, u64)>,
+    pub covered: Vec<String>,
+    pub partially_covered: Vec<String>,
+    pub writable_got: Vec<GotEntry>,
+}
+
+fn is_got_section(section: &SectionHeader) -> bool {
+    section.name == ".got" || section.name.starts_with(".got.")
+}
+
+impl Loader {
+    pub fn relro_report(&self) -> RelroReport {
+        let region = self
+            .program_header_by_type(PT_GNU_RELRO)
+            .map(|relro| (relro.p_vaddr, relro.end_vaddr()));
+        let level = match region {
+            None => RelroLevel::None,
+            Some(_) if self.is_bind_now() => RelroLevel::Full,
+            Some(_) => RelroLevel::Partial,
+        };
+        let in_region = |addr: u64| region.is_some_and(|(start, end)| addr >= start && addr < end);
+
+        let sections = self.section_headers();
+        let mut covered = Vec::new();
+        let mut partially_covered = Vec::new();
+        if let Some((start, end)) = region {
+            for section in sections
+                .iter()
+                .filter(|section| section.sh_flags & SHF_ALLOC != 0 && section.sh_size != 0)
+            {
+                if section.sh_addr >= start && section.end_addr() <= end {
+                    covered.push(section.name.clone());
+                } else if section.sh_addr < end && section.end_addr() > start {
+                    partially_covered.push(section.name.clone());
+                }
+            }
+        }
+
+        let writable_got = self
+            .relocations()
+            .into_iter()
+            .filter(|reloc| !in_region(reloc.r_offset))
+            .filter_map(|reloc| {
+                let got = sections.iter().find(|section| {
+                    is_got_section(section)
+                        && section.sh_flags & SHF_WRITE != 0
+                        && section.contains_addr(reloc.r_offset)
+                })?;
+                Some(GotEntry {
+                    address: reloc.r_offset,
+                    section: got.name.clone(),
+                    symbol: reloc.symbol,
+                    r_type: self.relocation_type_name(reloc.r_type),
+                })
+            })
+            .collect();
+
+        RelroReport {
+            level,
+            region,
+            covered,
+            partially_covered,
+            writable_got,
+        }
+    }
+
+    pub fn has_nx(&self) -> bool {
+        self.program_header_by_type(PT_GNU_STACK)
+            .is_some_and(|stack| stack.p_flags & PF_X == 0)
+    }
+
+    /// Whether the file is a PIE, one with an interpreter or a static-pie.
+    pub fn is_pie(&self) -> bool {
+        self.file_kind().is_pie()
+    }
+
+    pub fn has_stack_canary(&self) -> bool {
+        self.dynamic_symbols()
+            .iter()
+            .chain(self.static_symbols().iter())
+            .any(|symbol| symbol.name.starts_with("__stack_chk_fail"))
+    }
+
+    /// The checks [`Loader::checksec_findings`] makes of this file.
+    pub fn checksec_checks(&self) -> &'static [&'static str] {
+        if matches!(self.raw_e_type(), ET_EXEC | ET_DYN) {
+            CHECKSEC_CHECKS
+        } else {
+            &[]
+        }
+    }
+
+    /// The hardening `--checksec` finds missing, as findings for `--report`.
+    /// Only executables and shared objects are judged.
+    pub fn checksec_findings(&self) -> Vec<Finding> {
+        if !matches!(self.raw_e_type(), ET_EXEC | ET_DYN) {
+            return Vec::new();
+        }
+        let mut findings = Vec::new();
+        let mut add = |severity, check: &str, message: &str| {
+            findings.push(Finding {
+                severity,
+                check: format!("checksec/{}", check),
+                message: message.to_string(),
+            })
+        };
+        match self.relro_report().level {
+            RelroLevel::None => add(Severity::Warning, "relro", "no RELRO segment"),
+            RelroLevel::Partial => add(
+                Severity::Note,
+                "relro",
+                "partial RELRO, the PLT GOT stays writable",
+            ),
+            RelroLevel::Full => {}
+        }
+        if !self.has_nx() {
+            add(Severity::Error, "nx", "the stack is executable");
+        }
+        // Shared libraries are position independent without being PIEs.
+        let is_library = self.file_kind() == FileKind::SharedLibrary;
+        if !self.is_pie() && !is_library {
+            add(
+                Severity::Warning,
+                "pie",
+                "not a position-independent executable",
+            );
+        }
+        if self.has_textrel_flag() || !self.text_relocations().is_empty() {
+            add(Severity::Warning, "textrel", "text relocations are present");
+        }
+        if !self.has_stack_canary() {
+            add(
+                Severity::Note,
+                "stack-canary",
+                "no stack protector symbol found",
+            );
+        }
+        findings
+    }
+}
+
+pub fn display_checksec(path: &str, loader: &Loader) {
+    let relro = loader.relro_report();
+    output::begin(path);
+    display_line("RELRO", relro.level.as_str());
+    match relro.region {
+        Some((start, end)) => display_line(
+            "RELRO Region",
+            &format!("{:#x} - {:#x} ({} bytes)", start, end, end - start),
+        ),
+        None => display_line("RELRO Region", "-"),
+    }
+    if relro.region.is_some() {
+        display_line("Sections in RELRO", &relro.covered.join(" "));
+        if !relro.partially_covered.is_empty() {
+            display_line(
+                "Sections partially in RELRO",
+                &relro.partially_covered.join(" "),
+            );
+        }
+    }
+    display_line(
+        "Writable GOT entries after relocation",
+        &relro.writable_got.len().to_string(),
+    );
+    for entry in &relro.writable_got {
+        let symbol = if entry.symbol.is_empty() {
+            "-"
+        } else {
+            &entry.symbol
+        };
+        output::item(
+            1,
+            &format!(
+                "{:#x} {:<30} {} ({})",
+                entry.address, symbol, entry.section, entry.r_type
+            ),
+        );
+    }
+    display_line(
+        "NX",
+        if loader.has_nx() {
+            "Enabled"
+        } else {
+            "Disabled"
+        },
+    );
+    display_line(
+        "PIE",
+        if loader.is_pie() {
+            "Enabled"
+        } else {
+            "Disabled"
+        },
+    );
+    display_line(
+        "TEXTREL",
+        &match loader.text_relocations().len() {
+            0 if !loader.has_textrel_flag() => "None".to_string(),
+            count => format!("Present ({} relocations)", count),
+        },
+    );
+    display_line(
+        "Stack Canary",
+        if loader.has_stack_canary() {
+            "Found"
+        } else {
+            "Not found"
+        },
+    );
+    output::end();
+}
@@ -0,0 +1,178 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+use flate2::Crc;
+use sha2::{Digest, Sha256};
+
+use crate::debuginfo::debuglink_candidates;
+use crate::finding::{self, Finding, Severity};
+use crate::note::hex_string;
+use crate::output;
+use crate::{display_line, Loader};
+
+/// The checks behind the `--report` findings of `--verify-checksums`.
+const CHECKSUM_CHECKS: &[&str] = &["checksum/debuglink", "checksum/modversions"];
+
+/// The size of a `__versions` entry: the CRC as an `unsigned long`, then the
+/// NUL-padded symbol name filling the rest.
+const MODVERSION_ENTRY_SIZE: usize = 64;
+
+/// The CRC-32 `.gnu_debuglink` records, the one zlib computes.
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = Crc::new();
+    crc.update(data);
+    crc.sum()
+}
+
+/// The SHA-256 of `data` in hexadecimal, as manifests and journals record it.
+pub fn sha256(data: &[u8]) -> String {
+    hex_string(&Sha256::digest(data))
+}
+
+/// The first debug file found for `path`, with its CRC-32.
+fn find_debuglink(path: &str, name: &str) -> Option<(PathBuf, u32)> {
+    debuglink_candidates(path, name)
+        .into_iter()
+        .find_map(|candidate| {
+            let data = fs::read(&candidate).ok()?;
+            Some((candidate, crc32(&data)))
+        })
+}
+
+impl Loader {
+    /// The symbol CRCs a kernel module was built against, from `__versions`.
+    pub fn modversions(&self) -> Vec<(String, u64)> {
+        let Some(section) = self.section_by_name("__versions") else {
+            return Vec::new();
+        };
+        let word = if self.is_64bit() { 8 } else { 4 };
+        self.section_data(&section)
+            .chunks_exact(MODVERSION_ENTRY_SIZE)
+            .map(|entry| {
+                let crc = entry[..word]
+                    .iter()
+                    .enumerate()
+                    .fold(0u64, |crc, (index, &byte)| {
+                        let shift = if self.is_big_endian() {
+                            8 * (word - 1 - index)
+                        } else {
+                            8 * index
+                        };
+                        crc | (byte as u64) << shift
+                    });
+                let name = &entry[word..];
+                let end = name
+                    .iter()
+                    .position(|&byte| byte == 0)
+                    .unwrap_or(name.len());
+                (String::from_utf8_lossy(&name[..end]).into_owned(), crc)
+            })
+            .collect()
+    }
+}
+
+/// Reads the CRCs of a kernel's `Module.symvers`, by symbol.
+pub fn load_symvers(path: &str) -> Result<HashMap<String, u64>, String> {
+    let text = fs::read_to_string(path).map_err(|error| error.to_string())?;
+    Ok(text
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\t');
+            let crc = fields.next()?.trim_start_matches("0x");
+            let symbol = fields.next()?;
+            Some((symbol.to_string(), u64::from_str_radix(crc, 16).ok()?))
+        })
+        .collect())
+}
+
+/// Checks one file, printing what was compared, and returns the mismatches.
+fn verify(path: &str, loader: &Loader, symvers: Option<&HashMap<String, u64>>) -> Vec<Finding> {
+    let mut mismatches = Vec::new();
+    let mut mismatch = |check: &str, message: String| {
+        mismatches.push(Finding {
+            severity: Severity::Error,
+            check: format!("checksum/{}", check),
+            message,
+        })
+    };
+    match loader.debug_facts().debuglink {
+        Some((name, expected)) => {
+            display_line("Debug Link", &format!("{} (crc {:#010x})", name, expected));
+            match find_debuglink(path, &name) {
+                Some((file, crc)) if crc == expected => {
+                    display_line("Debug File", &format!("{}, CRC matches", file.display()));
+                }
+                Some((file, crc)) => {
+                    let message = format!(
+                        "{} has crc {:#010x}, the debug link expects {:#010x}",
+                        file.display(),
+                        crc,
+                        expected
+                    );
+                    display_line("Debug File", &format!("{}  mismatch", message));
+                    mismatch("debuglink", format!("stale debug file: {}", message));
+                }
+                None => display_line("Debug File", "Not found"),
+            }
+        }
+        None => display_line("Debug Link", "None"),
+    }
+
+    let versions = loader.modversions();
+    if versions.is_empty() {
+        display_line("Module Versions", "None");
+        return mismatches;
+    }
+    display_line(
+        "Module Versions",
+        &format!("{} symbol CRC(s)", versions.len()),
+    );
+    let Some(symvers) = symvers else {
+        for (symbol, crc) in &versions {
+            output::item(1, &format!("{:#010x} {}", crc, symbol));
+        }
+        return mismatches;
+    };
+    let mut unknown = 0;
+    for (symbol, crc) in &versions {
+        match symvers.get(symbol) {
+            Some(expected) if expected == crc => {}
+            Some(expected) => {
+                let message = format!(
+                    "{} has crc {:#010x}, Module.symvers has {:#010x}",
+                    symbol, crc, expected
+                );
+                output::item(1, &format!("{}  mismatch", message));
+                mismatch("modversions", message);
+            }
+            None => unknown += 1,
+        }
+    }
+    display_line("Not In Module.symvers", &unknown.to_string());
+    mismatches
+}
+
+/// `--verify-checksums`: recomputes the CRC-32 of the debug file a
+/// `.gnu_debuglink` names and, with `--symvers`, compares the symbol CRCs of
+/// kernel modules with the kernel's. Files with a mismatch fail.
+pub fn display_checksums(
+    targets: &[(String, Loader)],
+    symvers: Option<&HashMap<String, u64>>,
+) -> Vec<(String, String)> {
+    let mut failures = Vec::new();
+    for (label, loader) in targets {
+        output::begin(label);
+        let mismatches = verify(label, loader, symvers);
+        display_line("Mismatches", &mismatches.len().to_string());
+        output::end();
+        finding::record(label, CHECKSUM_CHECKS, &mismatches);
+        if !mismatches.is_empty() {
+            failures.push((
+                label.clone(),
+                format!("{} checksum mismatch(es)", mismatches.len()),
+            ));
+        }
+    }
+    failures
+}
@@ -0,0 +1,1395 @@
+use std::env;
+use std::ops::Range;
+
+use crate::grep::GrepPattern;
+use crate::i18n::LANGUAGES;
+use crate::limits::Limits;
+use crate::machine;
+use crate::output::FORMATS;
+use crate::query::Query;
+
+pub const BIN_NAME: &str = "elf-header-parser";
+
+/// One-line summary used by `--help-full` and the NAME section of the man page.
+pub const ABOUT: &str = "inspect ELF headers and hardening properties";
+
+/// Longer description used by `--help-full` and the DESCRIPTION section of the man page.
+pub const DESCRIPTION: &str =
+    "Prints the ELF header of every FILE as a table. The options below switch \
+to more specific reports; each report starts with the path of the file it describes and \
+ends with a blank line. Debian and RPM packages among the FILEs are unpacked in memory and \
+summarised like container images.";
+
+/// Example invocations and what they do, shown by `--help-full` and in the man page.
+pub const EXAMPLES: &[(&str, &str)] = &[
+    (
+        "elf-header-parser /bin/ls /bin/cat",
+        "Compare the headers of two executables",
+    ),
+    (
+        "elf-header-parser --checksec /usr/lib/*.so",
+        "Audit the hardening of shared libraries",
+    ),
+    (
+        "elf-header-parser --offset auto firmware.bin",
+        "Inspect ELF images embedded in a blob",
+    ),
+    (
+        "elf-header-parser --watch-diff build/app",
+        "Show what changes in the header on every rebuild",
+    ),
+];
+
+/// Environment variables read as defaults, which the options override; shown
+/// by `--help-full` and in the man page.
+pub const ENVIRONMENT: &[(&str, &str)] = &[
+    ("ELFHP_FORMAT", "The output format when --format is not given"),
+    (
+        "ELFHP_COLOR",
+        "auto, always or never: whether diagnostics on stderr are colored when --color is not given",
+    ),
+    (
+        "ELFHP_STRICT",
+        "1, true, yes or on for --strict; 0, false, no or off for the default",
+    ),
+    (
+        "DEBUGINFOD_URLS",
+        "Space-separated debuginfod servers to fetch debug files from when --debuginfod is not given",
+    ),
+    (
+        "NO_COLOR",
+        "When set and not empty, diagnostics are not colored unless color is asked for",
+    ),
+];
+
+/// What `--color` and `ELFHP_COLOR` accept.
+pub const COLOR_CHOICES: &[&str] = &["auto", "always", "never"];
+
+/// What kind of value an option expects; used for parsing and for shell completion hints.
+#[derive(Clone, Copy, PartialEq)]
+pub enum ValueKind {
+    None,
+    Directory,
+    /// One or more files, up to the next option.
+    Files,
+    /// Free-form text such as a pattern.
+    Text,
+    /// One of a fixed set of words.
+    Choice(&'static [&'static str]),
+}
+
+pub struct Flag {
+    pub long: &'static str,
+    pub value_name: &'static str,
+    pub kind: ValueKind,
+    pub help: &'static str,
+}
+
+pub struct Subcommand {
+    pub name: &'static str,
+    pub usage: &'static str,
+    pub help: &'static str,
+    /// Choices completed for the first positional argument.
+    pub choices: &'static [&'static str],
+    /// Left out of `--help` and the shell completions; only `--help-full` lists it.
+    pub hidden: bool,
+}
+
+pub const SHELLS: &[&str] = &["bash", "zsh", "fish"];
+
+/// Graph languages `--graph` can print.
+pub const GRAPH_FORMATS: &[&str] = &["dot", "mermaid"];
+
+/// Report files `--report` can write.
+pub const REPORT_KINDS: &[&str] = &["sarif", "junit", "markdown"];
+
+/// Every option the tool understands. Parsing, `--help` and the generated shell
+/// completions all read this table, so a new option only has to be added here.
+pub const FLAGS: &[Flag] = &[
+    Flag {
+        long: "--help",
+        value_name: "",
+        kind: ValueKind::None,
+        help: "Print usage information",
+    },
+    Flag {
+        long: "--help-full",
+        value_name: "",
+        kind: ValueKind::None,
+        help: "Print usage information with a description, hidden commands and examples",
+    },
+    Flag {
+        long: "--version",
+        value_name: "",
+        kind: ValueKind::None,
+        help: "Print the version (-V); with --verbose also the commit, target and features",
+    },
+    Flag {
+        long: "--checksec",
+        value_name: "",
+        kind: ValueKind::None,
+        help: "Report RELRO, NX, PIE, TEXTREL and stack canary status",
+    },
+    Flag {
+        long: "--textrel",
+        value_name: "",
+        kind: ValueKind::None,
+        help: "List text relocations and the objects that introduced them",
+    },
+    Flag {
+        long: "--android",
+        value_name: "",
+        kind: ValueKind::None,
+        help: "Check page alignment, NDK ident note and text relocations for Android",
+    },
+    Flag {
+        long: "--notes",
+        value_name: "",
+        kind: ValueKind::None,
+        help: "Decode ELF notes according to the target OS",
+    },
+    Flag {
+        long: "--functions",
+        value_name: "",
+        kind: ValueKind::None,
+        help: "List function starts and sizes from symbols and .eh_frame",
+    },
+    Flag {
+        long: "--dlopen",
+        value_name: "",
+        kind: ValueKind::None,
+        help: "Check whether shared objects can be loaded with dlopen",
+    },
+    Flag {
+        long: "--stack-sizes",
+        value_name: "",
+        kind: ValueKind::None,
+        help: "Report per-function stack usage from .stack_sizes sections",
+    },
+    Flag {
+        long: "--max-stack",
+        value_name: "BYTES",
+        kind: ValueKind::Text,
+        help: "With --stack-sizes, fail when a function uses more stack than BYTES",
+    },
+    Flag {
+        long: "--split-dwarf",
+        value_name: "",
+        kind: ValueKind::None,
+        help: "List the .dwo files of skeleton units and check they are present and match",
+    },
+    Flag {
+        long: "--size",
+        value_name: "",
+        kind: ValueKind::None,
+        help: "Show the bytes of code, read-only data, data and bss, and the sections by address",
+    },
+    Flag {
+        long: "--config",
+        value_name: "FILE",
+        kind: ValueKind::Text,
+        help: "Read settings from FILE instead of ~/.config/elf-header-parser/config.toml",
+    },
+    Flag {
+        long: "--mitigations",
+        value_name: "",
+        kind: ValueKind::None,
+        help: "Show BIND_NOW and full RELRO binding, retpoline thunks and ENDBR at function entries",
+    },
+    Flag {
+        long: "--boot",
+        value_name: "",
+        kind: ValueKind::None,
+        help: "Show the Multiboot, Multiboot2 and x86 Linux boot protocol headers of kernels",
+    },
+    Flag {
+        long: "--ima",
+        value_name: "",
+        kind: ValueKind::None,
+        help: "Show the IMA and EVM attributes of files and check IMA digests against them (Linux)",
+    },
+    Flag {
+        long: "--query",
+        value_name: "QUERY",
+        kind: ValueKind::Text,
+        help: "Print what a jq-like query such as '.sections[] | .name' gives for each file",
+    },
+    #[cfg(feature = "templates")]
+    Flag {
+        long: "--template",
+        value_name: "FILE",
+        kind: ValueKind::Text,
+        help: "Render the files through a minijinja template, such as an HTML or Markdown report",
+    },
+    Flag {
+        long: "--normalized",
+        value_name: "",
+        kind: ValueKind::None,
+        help: "Show format, architecture, entry point and hardening in one shape for every format",
+    },
+    Flag {
+        long: "--source-paths",
+        value_name: "",
+        kind: ValueKind::None,
+        help: "List the source paths in the debug info and warn about home directories leaking",
+    },
+    Flag {
+        long: "--with-debug",
+        value_name: "",
+        kind: ValueKind::None,
+        help: "Merge the symbols of the separate debug file found by build-id or .gnu_debuglink",
+    },
+    Flag {
+        long: "--debuginfod",
+        value_name: "URLS",
+        kind: ValueKind::Text,
+        help: "Space-separated debuginfod servers to fetch debug files from when none is installed",
+    },
+    Flag {
+        long: "--verify-checksums",
+        value_name: "",
+        kind: ValueKind::None,
+        help: "Recompute the CRC of the .gnu_debuglink debug file and check module symbol CRCs",
+    },
+    Flag {
+        long: "--symvers",
+        value_name: "FILE",
+        kind: ValueKind::Text,
+        help: "With --verify-checksums, the Module.symvers to check kernel module CRCs against",
+    },
+    Flag {
+        long: "--baseline",
+        value_name: "FILE",
+        kind: ValueKind::Text,
+        help: "Report size and hardening changes since a baseline and fail on regressions",
+    },
+    Flag {
+        long: "--write-baseline",
+        value_name: "FILE",
+        kind: ValueKind::Text,
+        help: "Record the section sizes and hardening of the files as a baseline in FILE",
+    },
+    Flag {
+        long: "--max-growth",
+        value_name: "PERCENT",
+        kind: ValueKind::Text,
+        help: "With --baseline, the growth of a section or file that is a regression (default 5)",
+    },
+    Flag {
+        long: "--grep-bytes",
+        value_name: "HEX",
+        kind: ValueKind::Text,
+        help: "Find a byte pattern such as '48 8b ?? 24' and report the section of each match",
+    },
+    Flag {
+        long: "--grep-regex",
+        value_name: "REGEX",
+        kind: ValueKind::Text,
+        help: "Find strings matching REGEX and report the section of each match",
+    },
+    Flag {
+        long: "--strings-scan",
+        value_name: "",
+        kind: ValueKind::None,
+        help: "List printable strings with their section, memory protection and nearest symbol",
+    },
+    Flag {
+        long: "--classify",
+        value_name: "",
+        kind: ValueKind::None,
+        help: "Tell static, static-pie, dynamic and dynamic pie executables, shared libraries and Go binaries apart",
+    },
+    Flag {
+        long: "--bundle",
+        value_name: "",
+        kind: ValueKind::None,
+        help: "Show the AppImage payload, update information and signature, or the Flatpak a file belongs to",
+    },
+    Flag {
+        long: "--brief",
+        value_name: "",
+        kind: ValueKind::None,
+        help: "Describe each file on one line, as file(1) does",
+    },
+    Flag {
+        long: "--debug-info",
+        value_name: "",
+        kind: ValueKind::None,
+        help: "Report whether files are stripped and what symbol, DWARF, debug link and build-id data they keep",
+    },
+    Flag {
+        long: "--explain",
+        value_name: "FIELD",
+        kind: ValueKind::Text,
+        help: "Describe a header field such as e_shstrndx and how each file's value is read",
+    },
+    Flag {
+        long: "--text-stats",
+        value_name: "",
+        kind: ValueKind::None,
+        help: "Report code size, instruction count and padding ratio",
+    },
+    Flag {
+        long: "--arch-info",
+        value_name: "",
+        kind: ValueKind::None,
+        help: "Decode architecture specific e_flags and build attributes",
+    },
+    Flag {
+        long: "--list-machines",
+        value_name: "",
+        kind: ValueKind::None,
+        help: "List the e_machine values the tool can name and exit",
+    },
+    Flag {
+        long: "--groups",
+        value_name: "",
+        kind: ValueKind::None,
+        help: "List section groups (COMDAT) with their signature and member sections",
+    },
+    Flag {
+        long: "--verbose",
+        value_name: "",
+        kind: ValueKind::None,
+        help: "Trace parsing decisions on stderr (-v); repeat or use -vv for table-level detail",
+    },
+    Flag {
+        long: "--timings",
+        value_name: "",
+        kind: ValueKind::None,
+        help: "Print per-file and per-phase timings to stderr",
+    },
+    Flag {
+        long: "--format",
+        value_name: "table|json|yaml|ndjson|quiet",
+        kind: ValueKind::Choice(FORMATS),
+        help: "Output format; quiet prints nothing and only sets the exit status",
+    },
+    Flag {
+        long: "--color",
+        value_name: "auto|always|never",
+        kind: ValueKind::Choice(COLOR_CHOICES),
+        help: "Color the warnings and errors on stderr; auto does when it is a terminal",
+    },
+    Flag {
+        long: "--lang",
+        value_name: "en|ja",
+        kind: ValueKind::Choice(LANGUAGES),
+        help: "Language of the field labels in the table output",
+    },
+    Flag {
+        long: "--report",
+        value_name: "KIND=FILE",
+        kind: ValueKind::Text,
+        help: "Also write the checks of the run and their findings as a sarif, junit or markdown report to FILE",
+    },
+    Flag {
+        long: "--fail-on",
+        value_name: "warning|error|never",
+        kind: ValueKind::Choice(&["warning", "error", "never"]),
+        help: "Exit with a failure status on warnings too, on errors (the default) or never",
+    },
+    Flag {
+        long: "--expect-machine",
+        value_name: "MACHINE",
+        kind: ValueKind::Text,
+        help: "Fail files whose e_machine is not MACHINE, given as a number, EM_ constant or name",
+    },
+    Flag {
+        long: "--strict",
+        value_name: "",
+        kind: ValueKind::None,
+        help: "Treat ELF specification deviations in the header as errors instead of warnings",
+    },
+    Flag {
+        long: "--offset",
+        value_name: "auto|N",
+        kind: ValueKind::Choice(&["auto"]),
+        help: "Parse the ELF image at offset N, or find embedded images with auto",
+    },
+    Flag {
+        long: "--scan-for-magic",
+        value_name: "",
+        kind: ValueKind::None,
+        help: "Skip a prefix such as a script or runtime header and parse the first valid ELF header",
+    },
+    Flag {
+        long: "--appimage-runtime",
+        value_name: "",
+        kind: ValueKind::None,
+        help: "Parse only the runtime of AppImages, leaving out the squashfs or ISO payload",
+    },
+    Flag {
+        long: "--carve",
+        value_name: "",
+        kind: ValueKind::None,
+        help: "Scan arbitrary blobs for embedded ELF images",
+    },
+    Flag {
+        long: "--extract",
+        value_name: "DIR",
+        kind: ValueKind::Directory,
+        help: "With --carve, write every image found into DIR",
+    },
+    Flag {
+        long: "--with",
+        value_name: "LIB...",
+        kind: ValueKind::Files,
+        help: "With resolve-undefs, the libraries to resolve against",
+    },
+    Flag {
+        long: "--golden",
+        value_name: "FILE",
+        kind: ValueKind::Text,
+        help: "With verify, the reference image to compare against",
+    },
+    Flag {
+        long: "--mask",
+        value_name: "START-END",
+        kind: ValueKind::Text,
+        help: "With verify, addresses to leave out, as START-END or START+LEN (repeatable)",
+    },
+    Flag {
+        long: "--graph",
+        value_name: "dot|mermaid",
+        kind: ValueKind::Choice(GRAPH_FORMATS),
+        help: "With call-graph or deps, print a Graphviz or Mermaid graph instead of the reports",
+    },
+    Flag {
+        long: "--allow-export",
+        value_name: "GLOB",
+        kind: ValueKind::Text,
+        help: "With lint-exports, a pattern exported symbols must match (repeatable)",
+    },
+    Flag {
+        long: "--version-script",
+        value_name: "required|forbidden",
+        kind: ValueKind::Choice(&["required", "forbidden"]),
+        help: "With lint-exports, whether libraries must or must not use a version script",
+    },
+    #[cfg(feature = "cross-check")]
+    Flag {
+        long: "--cross-check",
+        value_name: "",
+        kind: ValueKind::None,
+        help: "Parse with the object crate too and list the fields it reads differently",
+    },
+    #[cfg(feature = "signing")]
+    Flag {
+        long: "--key",
+        value_name: "FILE",
+        kind: ValueKind::Text,
+        help: "With sign, the Ed25519 secret key, as 32 bytes or 64 hexadecimal digits",
+    },
+    #[cfg(feature = "signing")]
+    Flag {
+        long: "--public-key",
+        value_name: "FILE",
+        kind: ValueKind::Text,
+        help: "With verify, check the signature notes against this Ed25519 public key",
+    },
+    Flag {
+        long: "--in-place",
+        value_name: "",
+        kind: ValueKind::None,
+        help: "With sign or unpatch, replace each file with its new copy, keeping owner, mode and xattrs",
+    },
+    Flag {
+        long: "--output",
+        value_name: "FILE",
+        kind: ValueKind::Text,
+        help: "With sign or unpatch, write the patched or restored copy of the one file to FILE",
+    },
+    Flag {
+        long: "--journal",
+        value_name: "FILE",
+        kind: ValueKind::Text,
+        help: "With sign, record the bytes replaced in FILE; with unpatch, restore them from it",
+    },
+    #[cfg(feature = "plugins")]
+    Flag {
+        long: "--plugin",
+        value_name: "FILE...",
+        kind: ValueKind::Files,
+        help: "Run the checks of shared library or WASM plugins (repeatable)",
+    },
+    Flag {
+        long: "--watch",
+        value_name: "",
+        kind: ValueKind::None,
+        help: "Print the output again whenever an input file changes",
+    },
+    Flag {
+        long: "--watch-diff",
+        value_name: "",
+        kind: ValueKind::None,
+        help: "Like --watch, but print only the lines that changed",
+    },
+    Flag {
+        long: "--bias",
+        value_name: "N",
+        kind: ValueKind::Text,
+        help: "With translate, the load bias to subtract from the addresses",
+    },
+    Flag {
+        long: "--maps",
+        value_name: "LINE",
+        kind: ValueKind::Text,
+        help: "With translate, a /proc/PID/maps line of the module to take the load bias from",
+    },
+    Flag {
+        long: "--pid",
+        value_name: "PID",
+        kind: ValueKind::Text,
+        help:
+            "Report the executable and mapped libraries of a running process with their load bias",
+    },
+    Flag {
+        long: "--daemon",
+        value_name: "",
+        kind: ValueKind::None,
+        help:
+            "Keep rescanning the FILEs and directories, reusing cached reports of unchanged files",
+    },
+    Flag {
+        long: "--no-cache",
+        value_name: "",
+        kind: ValueKind::None,
+        help: "Analyse copies of a file already reported again and leave the daemon cache alone",
+    },
+    Flag {
+        long: "--max-sections",
+        value_name: "N",
+        kind: ValueKind::Text,
+        help: "Refuse files with more than N section headers (default 1048576)",
+    },
+    Flag {
+        long: "--max-symbols",
+        value_name: "N",
+        kind: ValueKind::Text,
+        help: "Refuse files with a symbol table of more than N entries (default 16777216)",
+    },
+    Flag {
+        long: "--max-note-size",
+        value_name: "BYTES",
+        kind: ValueKind::Text,
+        help: "Refuse files with a note larger than BYTES (default 64 MiB)",
+    },
+    Flag {
+        long: "--max-depth",
+        value_name: "N",
+        kind: ValueKind::Text,
+        help: "Stop deps from following libraries more than N levels down (default 64)",
+    },
+    Flag {
+        long: "--sandbox",
+        value_name: "",
+        kind: ValueKind::None,
+        help: "Drop filesystem, network and exec access once the inputs are open (Linux)",
+    },
+];
+
+pub const SUBCOMMANDS: &[Subcommand] = &[
+    Subcommand {
+        name: "completions",
+        usage: "completions <bash|zsh|fish>",
+        help: "Print a shell completion script",
+        choices: SHELLS,
+        hidden: false,
+    },
+    Subcommand {
+        name: "obj-summary",
+        usage: "obj-summary <FILE>...",
+        help: "Summarise relocatable objects for linking",
+        choices: &[],
+        hidden: false,
+    },
+    Subcommand {
+        name: "resolve-preview",
+        usage: "resolve-preview <OBJ>...",
+        help: "Preview how the linker resolves strong, weak and common symbols",
+        choices: &[],
+        hidden: false,
+    },
+    Subcommand {
+        name: "resolve-undefs",
+        usage: "resolve-undefs <APP> --with <LIB>...",
+        help: "Check that the libraries given with --with define every symbol APP imports",
+        choices: &[],
+        hidden: false,
+    },
+    Subcommand {
+        name: "lint-exports",
+        usage: "lint-exports <LIB>...",
+        help: "Check the symbols shared libraries export against a policy",
+        choices: &[],
+        hidden: false,
+    },
+    Subcommand {
+        name: "explain",
+        usage: "explain [FIELD] [FILE]...",
+        help: "Describe an ELF header field and how each file's value is read",
+        choices: &[],
+        hidden: false,
+    },
+    Subcommand {
+        name: "policy",
+        usage: "policy <RULES> <FILE>...",
+        help: "Check files against the rules of a TOML or YAML policy file",
+        choices: &[],
+        hidden: false,
+    },
+    Subcommand {
+        name: "linkage",
+        usage: "linkage <DIR>...",
+        help: "Classify every binary under DIR as static, static-pie or dynamic",
+        choices: &[],
+        hidden: false,
+    },
+    Subcommand {
+        name: "deps",
+        usage: "deps <FILE>...",
+        help: "Show the tree of shared libraries each file loads and where they are found",
+        choices: &[],
+        hidden: false,
+    },
+    Subcommand {
+        name: "call-graph",
+        usage: "call-graph <PATH>...",
+        help: "List the functions each binary imports through its PLT and their libraries",
+        choices: &[],
+        hidden: false,
+    },
+    Subcommand {
+        name: "scan-image",
+        usage: "scan-image <IMAGE>...",
+        help: "Summarise hardening and dependencies of the ELF files in OCI or docker-save images",
+        choices: &[],
+        hidden: false,
+    },
+    Subcommand {
+        name: "translate",
+        usage: "translate <FILE> <ADDRESS>...",
+        help: "Map runtime addresses to file addresses, sections and symbols with --bias or --maps",
+        choices: &[],
+        hidden: false,
+    },
+    Subcommand {
+        name: "dry-load",
+        usage: "dry-load <FILE>...",
+        help: "Simulate the kernel loading each file: its mappings, interpreter and why exec would fail",
+        choices: &[],
+        hidden: false,
+    },
+    Subcommand {
+        name: "manifest",
+        usage: "manifest <create|verify> <MANIFEST> <FILE>...",
+        help: "Record the SHA-256 of files and their sections, or check files against it",
+        choices: &["create", "verify"],
+        hidden: false,
+    },
+    Subcommand {
+        name: "verify",
+        usage: "verify --golden <REF> <FILE>... [--mask START-END]...",
+        help: "Compare the LOAD segments of firmware images byte by byte with a golden image",
+        choices: &[],
+        hidden: false,
+    },
+    #[cfg(feature = "signing")]
+    Subcommand {
+        name: "sign",
+        usage: "sign --key <KEY> (--in-place | --output <OUT>) <FILE>...",
+        help: "Sign files with Ed25519 in a note; verify --public-key <KEY> checks them",
+        choices: &[],
+        hidden: false,
+    },
+    Subcommand {
+        name: "unpatch",
+        usage: "unpatch --journal <JOURNAL> (--in-place | --output <OUT>) [FILE]...",
+        help: "Put back the bytes a patch replaced, from the journal it wrote with --journal",
+        choices: &[],
+        hidden: false,
+    },
+    Subcommand {
+        name: "core-check",
+        usage: "core-check <CORE> [FILE]...",
+        help: "Check that the binaries a core dump references are present with matching build-ids",
+        choices: &[],
+        hidden: false,
+    },
+    Subcommand {
+        name: "serve",
+        usage: "serve <ADDR> [DIR]...",
+        help: "Answer HTTP requests with JSON reports of uploaded files or files under DIR",
+        choices: &[],
+        hidden: false,
+    },
+    #[cfg(feature = "fetch-fixtures")]
+    Subcommand {
+        name: "fetch-fixtures",
+        usage: "fetch-fixtures [DIR]",
+        help: "Download hello-world binaries for ten architectures into DIR or the user cache",
+        choices: &[],
+        hidden: false,
+    },
+    Subcommand {
+        name: "selftest",
+        usage: "selftest",
+        help: "Write synthetic ELF files to a temporary directory and check every report reads them right",
+        choices: &[],
+        hidden: false,
+    },
+    Subcommand {
+        name: "gen-man",
+        usage: "gen-man",
+        help: "Print a roff man page, for packagers",
+        choices: &[],
+        hidden: true,
+    },
+];
+
+/// What `lint-exports` expects of a library's symbol versioning.
+#[derive(Clone, Copy)]
+pub enum VersionScript {
+    Required,
+    Forbidden,
+}
+
+/// What `--fail-on` makes the run exit with a failure status for.
+#[derive(Clone, Copy, Default, PartialEq)]
+pub enum FailOn {
+    Warning,
+    #[default]
+    Error,
+    Never,
+}
+
+/// Where the ELF image starts inside each input file.
+#[derive(Clone, Copy)]
+pub enum Offset {
+    /// Look for FatELF records, zip members or concatenated images.
+    Auto,
+    Fixed(usize),
+}
+
+#[derive(Default)]
+pub struct Options {
+    pub help: bool,
+    /// `--help-full`: also print the description, hidden commands and examples.
+    pub help_full: bool,
+    pub version: bool,
+    /// The subcommand named by the first argument, if any.
+    pub subcommand: Option<&'static str>,
+    pub checksec: bool,
+    pub textrel: bool,
+    pub android: bool,
+    pub notes: bool,
+    pub groups: bool,
+    pub functions: bool,
+    pub verify_checksums: bool,
+    pub split_dwarf: bool,
+    pub source_paths: bool,
+    pub mitigations: bool,
+    pub boot: bool,
+    /// The same fields for ELF, Mach-O, PE and WebAssembly inputs.
+    pub normalized: bool,
+    /// The `security.ima` and `security.evm` attributes of each input.
+    pub ima: bool,
+    /// The `--query` run over the parsed document of each input.
+    pub query: Option<Query>,
+    /// The minijinja template `--template` renders the inputs with.
+    pub template: Option<String>,
+    pub size: bool,
+    /// The settings file given by `--config`.
+    pub config: Option<String>,
+    /// Attach the separate debug file of each input, for its symbols.
+    pub with_debug: bool,
+    /// The debuginfod servers debug files are fetched from when none is found.
+    pub debuginfod: Vec<String>,
+    /// The kernel's `Module.symvers`, for `--verify-checksums`.
+    pub symvers: Option<String>,
+    /// Baseline `--baseline` compares the files with.
+    pub baseline: Option<String>,
+    /// Where `--write-baseline` records the files.
+    pub write_baseline: Option<String>,
+    /// With `--baseline`, the growth in percent that is a regression.
+    pub max_growth: Option<f64>,
+    /// The pattern `--grep-bytes` or `--grep-regex` searches for.
+    pub grep: Option<GrepPattern>,
+    pub strings_scan: bool,
+    pub text_stats: bool,
+    pub debug_info: bool,
+    pub brief: bool,
+    pub bundle: bool,
+    pub classify: bool,
+    /// The header field `--explain` describes.
+    pub explain: Option<String>,
+    /// Compare each file with the `object` crate's reading of it.
+    pub cross_check: bool,
+    /// Plugins `--plugin` runs over every file.
+    pub plugins: Vec<String>,
+    pub dlopen: bool,
+    pub arch_info: bool,
+    pub list_machines: bool,
+    pub stack_sizes: bool,
+    /// Stack usage in bytes above which `--stack-sizes` fails the run.
+    pub max_stack: Option<u64>,
+    pub watch: bool,
+    /// With `watch`, print only the lines that changed since the previous run.
+    pub watch_diff: bool,
+    /// The arguments minus the watch flags, used to re-run the parser on every change.
+    pub forwarded: Vec<String>,
+    /// With `translate`, what to subtract from runtime addresses.
+    pub bias: Option<u64>,
+    /// With `translate`, the maps line the load bias is worked out from.
+    pub maps_line: Option<String>,
+    /// Process whose executable and mapped files are reported instead of FILEs.
+    pub pid: Option<u32>,
+    /// Rescan the inputs forever, keeping reports of unchanged files in an on-disk cache.
+    pub daemon: bool,
+    /// Do not reuse reports of identical files or the daemon's on-disk cache.
+    pub no_cache: bool,
+    /// Confine the process with Landlock and seccomp once the inputs are open.
+    pub sandbox: bool,
+    /// How much a file may make the parser walk.
+    pub limits: Limits,
+    /// Skip files whose header deviates from the specification instead of warning.
+    pub strict: bool,
+    pub fail_on: FailOn,
+    /// The e_machine every file is expected to have.
+    pub expect_machine: Option<u16>,
+    pub timings: bool,
+    /// How many `-v`/`--verbose` were given.
+    pub verbose: u8,
+    /// One of `output::FORMATS`; the aligned table when not given.
+    pub format: Option<String>,
+    /// One of `COLOR_CHOICES`; `auto` when not given.
+    pub color: Option<String>,
+    /// One of `i18n::LANGUAGES`; English when not given.
+    pub lang: Option<String>,
+    pub offset: Option<Offset>,
+    /// Parse files that do not start with ELF from their first plausible header.
+    pub scan_for_magic: bool,
+    pub appimage_runtime: bool,
+    pub carve: bool,
+    /// Directory that carved images are written to.
+    pub extract: Option<String>,
+    /// Libraries given to `resolve-undefs`.
+    pub with: Vec<String>,
+    /// The reference image of `verify`.
+    pub golden: Option<String>,
+    /// Address ranges `verify` does not compare.
+    pub masks: Vec<Range<u64>>,
+    /// The Ed25519 secret key file of `sign`.
+    pub key: Option<String>,
+    /// The Ed25519 public key file `verify` checks signature notes against.
+    pub public_key: Option<String>,
+    /// Subcommands that patch files write over their inputs.
+    pub in_place: bool,
+    /// Where the patched copy of the single input goes instead.
+    pub output: Option<String>,
+    /// The undo journal patches write and `unpatch` reads.
+    pub journal: Option<String>,
+    /// With `call-graph` or `deps`, one of `GRAPH_FORMATS` to print instead of the reports.
+    pub graph: Option<String>,
+    /// The `--report` files to write, as (one of `REPORT_KINDS`, path).
+    pub reports: Vec<(String, String)>,
+    /// Patterns every exported symbol has to match for `lint-exports`.
+    pub allow_exports: Vec<String>,
+    pub version_script: Option<VersionScript>,
+    pub paths: Vec<String>,
+}
+
+/// Parses a decimal or `0x`-prefixed hexadecimal number.
+pub fn parse_number(value: &str) -> Option<usize> {
+    match value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        Some(hex) => usize::from_str_radix(hex, 16).ok(),
+        None => value.parse().ok(),
+    }
+}
+
+/// Parses an address range as `START-END`, the end excluded, or `START+LEN`.
+fn parse_range(value: &str) -> Option<Range<u64>> {
+    if let Some((start, end)) = value.split_once('-') {
+        let range = parse_number(start)? as u64..parse_number(end)? as u64;
+        return (range.start < range.end).then_some(range);
+    }
+    let (start, len) = value.split_once('+')?;
+    let start = parse_number(start)? as u64;
+    let end = start.checked_add(parse_number(len)? as u64)?;
+    (start < end).then_some(start..end)
+}
+
+impl Options {
+    fn apply(&mut self, flag: &str, value: Option<String>) -> Result<(), String> {
+        match flag {
+            "--help" => self.help = true,
+            "--help-full" => self.help_full = true,
+            "--version" => self.version = true,
+            "--checksec" => self.checksec = true,
+            "--textrel" => self.textrel = true,
+            "--android" => self.android = true,
+            "--notes" => self.notes = true,
+            "--groups" => self.groups = true,
+            "--functions" => self.functions = true,
+            "--grep-bytes" => self.grep = Some(GrepPattern::hex(&value.unwrap_or_default())?),
+            "--grep-regex" => self.grep = Some(GrepPattern::regex(&value.unwrap_or_default())?),
+            "--strings-scan" => self.strings_scan = true,
+            "--text-stats" => self.text_stats = true,
+            "--debug-info" => self.debug_info = true,
+            "--brief" => self.brief = true,
+            "--bundle" => self.bundle = true,
+            "--classify" => self.classify = true,
+            "--explain" => self.explain = value,
+            "--dlopen" => self.dlopen = true,
+            "--arch-info" => self.arch_info = true,
+            "--list-machines" => self.list_machines = true,
+            "--stack-sizes" => self.stack_sizes = true,
+            "--max-stack" => {
+                let value = value.unwrap_or_default();
+                self.stack_sizes = true;
+                self.max_stack = Some(
+                    parse_number(&value).ok_or_else(|| format!("invalid stack size: {}", value))?
+                        as u64,
+                );
+            }
+            "--verify-checksums" => self.verify_checksums = true,
+            "--split-dwarf" => self.split_dwarf = true,
+            "--source-paths" => self.source_paths = true,
+            "--mitigations" => self.mitigations = true,
+            "--boot" => self.boot = true,
+            "--normalized" => self.normalized = true,
+            "--ima" => self.ima = true,
+            "--query" => self.query = Some(Query::parse(&value.unwrap_or_default())?),
+            "--template" => self.template = value,
+            "--size" => self.size = true,
+            "--config" => self.config = value,
+            "--with-debug" => self.with_debug = true,
+            "--debuginfod" => {
+                self.debuginfod = value
+                    .unwrap_or_default()
+                    .split_whitespace()
+                    .map(str::to_string)
+                    .collect()
+            }
+            "--symvers" => self.symvers = value,
+            "--baseline" => self.baseline = value,
+            "--write-baseline" => self.write_baseline = value,
+            "--max-growth" => {
+                let value = value.unwrap_or_default();
+                self.max_growth = Some(
+                    value
+                        .trim_end_matches('%')
+                        .parse()
+                        .ok()
+                        .filter(|percent: &f64| *percent >= 0.0)
+                        .ok_or_else(|| format!("invalid percentage: {}", value))?,
+                );
+            }
+            "--watch" => self.watch = true,
+            "--watch-diff" => {
+                self.watch = true;
+                self.watch_diff = true;
+            }
+            "--bias" => {
+                let value = value.unwrap_or_default();
+                self.bias = Some(
+                    parse_number(&value).ok_or_else(|| format!("invalid load bias: {}", value))?
+                        as u64,
+                );
+            }
+            "--maps" => self.maps_line = value,
+            "--pid" => {
+                let value = value.unwrap_or_default();
+                self.pid = Some(
+                    value
+                        .parse()
+                        .map_err(|_| format!("invalid process id: {}", value))?,
+                );
+            }
+            "--daemon" => self.daemon = true,
+            "--no-cache" => self.no_cache = true,
+            "--sandbox" => self.sandbox = true,
+            "--max-sections" | "--max-symbols" | "--max-note-size" | "--max-depth" => {
+                let value = value.unwrap_or_default();
+                let limit =
+                    parse_number(&value).ok_or_else(|| format!("invalid {}: {}", flag, value))?;
+                match flag {
+                    "--max-sections" => self.limits.max_sections = limit,
+                    "--max-symbols" => self.limits.max_symbols = limit,
+                    "--max-note-size" => self.limits.max_note_size = limit as u64,
+                    _ => self.limits.max_depth = limit,
+                }
+            }
+            "--strict" => self.strict = true,
+            "--fail-on" => {
+                self.fail_on = match value.as_deref() {
+                    Some("warning") => FailOn::Warning,
+                    Some("error") => FailOn::Error,
+                    Some("never") => FailOn::Never,
+                    _ => return Err("--fail-on expects warning, error or never".to_string()),
+                }
+            }
+            "--expect-machine" => {
+                let value = value.unwrap_or_default();
+                self.expect_machine = Some(
+                    machine::parse(&value).ok_or_else(|| format!("unknown machine: {}", value))?,
+                );
+            }
+            "--timings" => self.timings = true,
+            "--verbose" => self.verbose = self.verbose.saturating_add(1),
+            "--format" => {
+                let value = value.unwrap_or_default();
+                if !FORMATS.contains(&value.as_str()) {
+                    return Err(format!("--format expects one of: {}", FORMATS.join(", ")));
+                }
+                self.format = Some(value);
+            }
+            "--color" => {
+                let value = value.unwrap_or_default();
+                if !COLOR_CHOICES.contains(&value.as_str()) {
+                    return Err(format!(
+                        "--color expects one of: {}",
+                        COLOR_CHOICES.join(", ")
+                    ));
+                }
+                self.color = Some(value);
+            }
+            "--lang" => {
+                let value = value.unwrap_or_default();
+                if !LANGUAGES.contains(&value.as_str()) {
+                    return Err(format!("--lang expects one of: {}", LANGUAGES.join(", ")));
+                }
+                self.lang = Some(value);
+            }
+            "--carve" => self.carve = true,
+            "--extract" => self.extract = value,
+            "--with" => self.with.extend(value),
+            "--golden" => self.golden = value,
+            "--key" => self.key = value,
+            "--public-key" => self.public_key = value,
+            "--in-place" => self.in_place = true,
+            "--output" => self.output = value,
+            "--journal" => self.journal = value,
+            "--mask" => {
+                let value = value.unwrap_or_default();
+                self.masks.push(
+                    parse_range(&value)
+                        .ok_or_else(|| format!("invalid address range: {}", value))?,
+                );
+            }
+            "--cross-check" => self.cross_check = true,
+            "--plugin" => self.plugins.extend(value),
+            "--graph" => {
+                let value = value.unwrap_or_default();
+                if !GRAPH_FORMATS.contains(&value.as_str()) {
+                    return Err(format!(
+                        "--graph expects one of: {}",
+                        GRAPH_FORMATS.join(", ")
+                    ));
+                }
+                self.graph = Some(value);
+            }
+            "--report" => {
+                let value = value.unwrap_or_default();
+                match value.split_once('=') {
+                    Some((kind, path)) if REPORT_KINDS.contains(&kind) && !path.is_empty() => {
+                        self.reports.push((kind.to_string(), path.to_string()))
+                    }
+                    _ => {
+                        return Err(format!(
+                            "--report expects KIND=FILE with KIND one of: {}",
+                            REPORT_KINDS.join(", ")
+                        ))
+                    }
+                }
+            }
+            "--allow-export" => self.allow_exports.extend(value),
+            "--version-script" => {
+                self.version_script = match value.as_deref() {
+                    Some("required") => Some(VersionScript::Required),
+                    Some("forbidden") => Some(VersionScript::Forbidden),
+                    _ => return Err("--version-script expects required or forbidden".to_string()),
+                }
+            }
+            "--scan-for-magic" => self.scan_for_magic = true,
+            "--appimage-runtime" => self.appimage_runtime = true,
+            "--offset" => {
+                let value = value.unwrap_or_default();
+                self.offset = Some(if value == "auto" {
+                    Offset::Auto
+                } else {
+                    Offset::Fixed(
+                        parse_number(&value).ok_or_else(|| format!("invalid offset: {}", value))?,
+                    )
+                });
+            }
+            _ => return Err(format!("unknown option: {}", flag)),
+        }
+        Ok(())
+    }
+
+    /// What reads files other than the inputs once they are open, so that it
+    /// cannot run under `--sandbox`.
+    pub fn sandbox_conflict(&self) -> Option<&'static str> {
+        match self.subcommand {
+            None
+            | Some("obj-summary" | "resolve-preview" | "lint-exports" | "policy" | "explain") => {}
+            Some(subcommand) => return Some(subcommand),
+        }
+        [
+            (self.watch, "--watch"),
+            (self.daemon, "--daemon"),
+            (self.pid.is_some(), "--pid"),
+            (self.with_debug, "--with-debug"),
+            (!self.plugins.is_empty(), "--plugin"),
+            (self.template.is_some(), "--template"),
+        ]
+        .into_iter()
+        .find_map(|(set, flag)| set.then_some(flag))
+    }
+
+    /// The first two report modes given, when there is more than one: each
+    /// mode is a report of its own and the rest would be dropped.
+    pub fn mode_conflict(&self) -> Option<(&'static str, &'static str)> {
+        let mut modes = [
+            (self.carve, "--carve"),
+            (self.checksec, "--checksec"),
+            (self.textrel, "--textrel"),
+            (self.android, "--android"),
+            (self.notes, "--notes"),
+            (self.classify, "--classify"),
+            (self.bundle, "--bundle"),
+            (self.brief, "--brief"),
+            (self.debug_info, "--debug-info"),
+            (self.arch_info, "--arch-info"),
+            (self.dlopen, "--dlopen"),
+            (self.baseline.is_some(), "--baseline"),
+            (
+                self.baseline.is_none() && self.write_baseline.is_some(),
+                "--write-baseline",
+            ),
+            (self.size, "--size"),
+            (self.mitigations, "--mitigations"),
+            (self.boot, "--boot"),
+            (self.normalized, "--normalized"),
+            (self.ima, "--ima"),
+            (self.query.is_some(), "--query"),
+            (self.template.is_some(), "--template"),
+            (self.source_paths, "--source-paths"),
+            (self.split_dwarf, "--split-dwarf"),
+            (self.verify_checksums, "--verify-checksums"),
+            (self.stack_sizes, "--stack-sizes"),
+            (self.cross_check, "--cross-check"),
+            (!self.plugins.is_empty(), "--plugin"),
+            (self.groups, "--groups"),
+            (
+                self.grep.as_ref().is_some_and(|pattern| pattern.hex),
+                "--grep-bytes",
+            ),
+            (
+                self.grep.as_ref().is_some_and(|pattern| !pattern.hex),
+                "--grep-regex",
+            ),
+            (self.strings_scan, "--strings-scan"),
+            (self.explain.is_some(), "--explain"),
+            (self.text_stats, "--text-stats"),
+            (self.functions, "--functions"),
+        ]
+        .into_iter()
+        .filter_map(|(set, flag)| set.then_some(flag));
+        Some((modes.next()?, modes.next()?))
+    }
+
+    /// Whether only the default ELF header table is printed, which needs nothing
+    /// past the first 64 bytes of each input.
+    pub fn header_only(&self) -> bool {
+        self.subcommand.is_none()
+            && self.offset.is_none()
+            && !self.scan_for_magic
+            && !self.appimage_runtime
+            && !(self.carve
+                || self.checksec
+                || self.textrel
+                || self.android
+                || self.notes
+                || self.groups
+                || self.functions
+                || self.grep.is_some()
+                || self.strings_scan
+                || self.text_stats
+                || self.debug_info
+                || self.brief
+                || self.bundle
+                || self.classify
+                || self.explain.is_some()
+                || !self.plugins.is_empty()
+                || self.cross_check
+                || self.dlopen
+                || self.arch_info
+                || self.stack_sizes
+                || self.baseline.is_some()
+                || self.verify_checksums
+                || self.split_dwarf
+                || self.source_paths
+                || self.mitigations
+                || self.boot
+                || self.normalized
+                || self.ima
+                || self.query.is_some()
+                || self.template.is_some()
+                || self.size
+                || self.with_debug
+                || self.write_baseline.is_some())
+    }
+
+    /// Applies the `ENVIRONMENT` variables that are set. It runs before the
+    /// options are read, so that they override the variables.
+    fn apply_environment(&mut self) -> Result<(), String> {
+        let var = |name: &str| env::var(name).ok().filter(|value| !value.is_empty());
+        for (name, flag) in [("ELFHP_FORMAT", "--format"), ("ELFHP_COLOR", "--color")] {
+            if let Some(value) = var(name) {
+                self.apply(flag, Some(value))
+                    .map_err(|error| format!("{}: {}", name, error))?;
+            }
+        }
+        if let Some(value) = var("ELFHP_STRICT") {
+            self.strict = match value.to_ascii_lowercase().as_str() {
+                "1" | "true" | "yes" | "on" => true,
+                "0" | "false" | "no" | "off" => false,
+                _ => {
+                    return Err(format!(
+                        "ELFHP_STRICT expects 1, true, yes or on, or 0, false, no or off, not {}",
+                        value
+                    ))
+                }
+            };
+        }
+        if let Some(value) = var("DEBUGINFOD_URLS") {
+            self.apply("--debuginfod", Some(value))?;
+        }
+        Ok(())
+    }
+
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Options, String> {
+        let args: Vec<String> = args.into_iter().collect();
+        let mut options = Options {
+            forwarded: args
+                .iter()
+                .filter(|arg| *arg != "--watch" && *arg != "--watch-diff")
+                .cloned()
+                .collect(),
+            ..Options::default()
+        };
+        options.apply_environment()?;
+        let mut args = args.into_iter().peekable();
+        if let Some(subcommand) = args
+            .peek()
+            .and_then(|first| SUBCOMMANDS.iter().find(|sub| sub.name == first))
+        {
+            options.subcommand = Some(subcommand.name);
+            args.next();
+        }
+        while let Some(arg) = args.next() {
+            if arg == "-h" {
+                options.help = true;
+                continue;
+            }
+            if arg == "-V" {
+                options.version = true;
+                continue;
+            }
+            if arg == "-v" || arg == "-vv" {
+                options.verbose = options.verbose.saturating_add(arg.len() as u8 - 1);
+                continue;
+            }
+            if !arg.starts_with("--") {
+                options.paths.push(arg);
+                continue;
+            }
+            let flag = FLAGS
+                .iter()
+                .find(|flag| flag.long == arg)
+                .ok_or_else(|| format!("unknown option: {}", arg))?;
+            match flag.kind {
+                ValueKind::None => options.apply(flag.long, None)?,
+                ValueKind::Files => {
+                    let mut values = 0;
+                    while let Some(value) = args.next_if(|next| !next.starts_with("--")) {
+                        options.apply(flag.long, Some(value))?;
+                        values += 1;
+                    }
+                    if values == 0 {
+                        return Err(format!("{} requires a value", flag.long));
+                    }
+                }
+                _ => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| format!("{} requires a value", flag.long))?;
+                    options.apply(flag.long, Some(value))?;
+                }
+            }
+        }
+        if let Some(conflict) = options
+            .sandbox
+            .then(|| options.sandbox_conflict())
+            .flatten()
+        {
+            return Err(format!("--sandbox cannot be combined with {}", conflict));
+        }
+        if let Some((first, second)) = options.mode_conflict() {
+            return Err(format!("{} cannot be combined with {}", first, second));
+        }
+        Ok(options)
+    }
+}
+
+fn render_usage(full: bool) -> String {
+    let mut text = String::new();
+    if full {
+        text.push_str(&format!("{} - {}\n\n{}\n\n", BIN_NAME, ABOUT, DESCRIPTION));
+    }
+    text.push_str(&format!(
+        "Usage: {} [OPTIONS] <FILE>...\n       {} <COMMAND> [ARGS]...\n\nCommands:\n",
+        BIN_NAME, BIN_NAME
+    ));
+    for subcommand in SUBCOMMANDS.iter().filter(|sub| full || !sub.hidden) {
+        text.push_str(&format!("  {:<28} {}\n", subcommand.usage, subcommand.help));
+    }
+    text.push_str("\nOptions:\n");
+    for flag in FLAGS {
+        text.push_str(&format!("  {:<28} {}\n", flag_synopsis(flag), flag.help));
+    }
+    if full {
+        text.push_str("\nEnvironment:\n");
+        for (name, description) in ENVIRONMENT {
+            text.push_str(&format!("  {:<28} {}\n", name, description));
+        }
+        text.push_str("\nExamples:\n");
+        for (command, description) in EXAMPLES {
+            text.push_str(&format!("  {}\n      {}\n", command, description));
+        }
+    }
+    text
+}
+
+/// The option as it is written on the command line, e.g. `--extract <DIR>`.
+fn flag_synopsis(flag: &Flag) -> String {
+    if flag.value_name.is_empty() {
+        flag.long.to_string()
+    } else {
+        format!("{} <{}>", flag.long, flag.value_name)
+    }
+}
+
+pub fn usage() -> String {
+    render_usage(false)
+}
+
+pub fn usage_full() -> String {
+    render_usage(true)
+}
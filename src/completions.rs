@@ -0,0 +1,216 @@
+use crate::cli::{Subcommand, ValueKind, BIN_NAME, FLAGS, SUBCOMMANDS};
+
+/// Hidden subcommands are for packagers and are not offered to interactive shells.
+fn visible_subcommands() -> impl Iterator<Item = &'static Subcommand> {
+    SUBCOMMANDS.iter().filter(|sub| !sub.hidden)
+}
+
+fn function_name() -> String {
+    format!("_{}", BIN_NAME.replace('-', "_"))
+}
+
+fn bash() -> String {
+    let flags: Vec<&str> = FLAGS.iter().map(|flag| flag.long).collect();
+    let subcommands: Vec<&str> = visible_subcommands().map(|sub| sub.name).collect();
+    let mut value_cases = String::new();
+    for flag in FLAGS {
+        match flag.kind {
+            ValueKind::None => {}
+            ValueKind::Directory => value_cases.push_str(&format!(
+                "        {})\n            COMPREPLY=($(compgen -d -- \"$cur\"))\n            return ;;\n",
+                flag.long
+            )),
+            ValueKind::Files | ValueKind::Text => {}
+            ValueKind::Choice(choices) => value_cases.push_str(&format!(
+                "        {})\n            COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))\n            return ;;\n",
+                flag.long,
+                choices.join(" ")
+            )),
+        }
+    }
+    let mut subcommand_cases = String::new();
+    for subcommand in visible_subcommands().filter(|sub| !sub.choices.is_empty()) {
+        subcommand_cases.push_str(&format!(
+            "    if [[ $COMP_CWORD -eq 2 && ${{COMP_WORDS[1]}} == {} ]]; then\n        COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))\n        return\n    fi\n",
+            subcommand.name,
+            subcommand.choices.join(" ")
+        ));
+    }
+    format!(
+        r#"{name}() {{
+    local cur prev
+    cur="${{COMP_WORDS[COMP_CWORD]}}"
+    prev="${{COMP_WORDS[COMP_CWORD-1]}}"
+    case "$prev" in
+{value_cases}    esac
+{subcommand_cases}    if [[ "$cur" == -* ]]; then
+        COMPREPLY=($(compgen -W "{flags}" -- "$cur"))
+    elif [[ $COMP_CWORD -eq 1 ]]; then
+        COMPREPLY=($(compgen -W "{subcommands}" -- "$cur") $(compgen -f -- "$cur"))
+    else
+        COMPREPLY=($(compgen -f -- "$cur"))
+    fi
+}}
+complete -o filenames -F {name} {bin}
+"#,
+        name = function_name(),
+        value_cases = value_cases,
+        subcommand_cases = subcommand_cases,
+        flags = flags.join(" "),
+        subcommands = subcommands.join(" "),
+        bin = BIN_NAME,
+    )
+}
+
+fn zsh_escape(text: &str) -> String {
+    text.replace('\'', "'\\''")
+        .replace('[', "\\[")
+        .replace(']', "\\]")
+        .replace(':', "\\:")
+}
+
+fn zsh() -> String {
+    let mut specs = String::new();
+    for flag in FLAGS {
+        let action = match flag.kind {
+            ValueKind::None => String::new(),
+            ValueKind::Directory => format!(":{}:_files -/", flag.value_name),
+            ValueKind::Files => format!(":{}:_files", flag.value_name),
+            ValueKind::Text => format!(":{}: ", flag.value_name),
+            ValueKind::Choice(choices) => {
+                format!(":{}:({})", flag.value_name, choices.join(" "))
+            }
+        };
+        specs.push_str(&format!(
+            "        '{}[{}]{}' \\\n",
+            flag.long,
+            zsh_escape(flag.help),
+            action
+        ));
+    }
+    let mut subcommands = String::new();
+    for subcommand in visible_subcommands() {
+        subcommands.push_str(&format!(
+            "        '{}:{}'\n",
+            subcommand.name,
+            zsh_escape(subcommand.help)
+        ));
+    }
+    let mut subcommand_cases = String::new();
+    for subcommand in visible_subcommands().filter(|sub| !sub.choices.is_empty()) {
+        subcommand_cases.push_str(&format!(
+            "    if [[ $CURRENT -eq 3 && $words[2] == {} ]]; then\n        _values 'value' {}\n        return\n    fi\n",
+            subcommand.name,
+            subcommand.choices.join(" ")
+        ));
+    }
+    format!(
+        r#"#compdef {bin}
+
+{name}() {{
+    local -a commands
+    commands=(
+{subcommands}    )
+{subcommand_cases}    if [[ $CURRENT -eq 2 && $words[2] != -* ]]; then
+        _describe 'command' commands
+    fi
+    _arguments -s \
+{specs}        '*:file:_files'
+}}
+
+{name} "$@"
+"#,
+        bin = BIN_NAME,
+        name = function_name(),
+        subcommands = subcommands,
+        subcommand_cases = subcommand_cases,
+        specs = specs,
+    )
+}
+
+fn fish() -> String {
+    let mut script = String::new();
+    let no_subcommand = format!(
+        "not __fish_seen_subcommand_from {}",
+        visible_subcommands()
+            .map(|sub| sub.name)
+            .collect::<Vec<_>>()
+            .join(" ")
+    );
+    for subcommand in visible_subcommands() {
+        script.push_str(&format!(
+            "complete -c {} -n '{}' -a {} -d '{}'\n",
+            BIN_NAME,
+            no_subcommand,
+            subcommand.name,
+            subcommand.help.replace('\'', "\\'")
+        ));
+        if !subcommand.choices.is_empty() {
+            script.push_str(&format!(
+                "complete -c {} -n '__fish_seen_subcommand_from {}' -f -a '{}'\n",
+                BIN_NAME,
+                subcommand.name,
+                subcommand.choices.join(" ")
+            ));
+        }
+    }
+    for flag in FLAGS {
+        let long = flag.long.trim_start_matches("--");
+        let value = match flag.kind {
+            ValueKind::None => String::new(),
+            ValueKind::Directory => " -r -a '(__fish_complete_directories)'".to_string(),
+            ValueKind::Files => " -r -F".to_string(),
+            ValueKind::Text => " -r -f".to_string(),
+            ValueKind::Choice(choices) => format!(" -r -a '{}'", choices.join(" ")),
+        };
+        script.push_str(&format!(
+            "complete -c {} -l {}{} -d '{}'\n",
+            BIN_NAME,
+            long,
+            value,
+            flag.help.replace('\'', "\\'")
+        ));
+    }
+    script
+}
+
+/// Renders the completion script for `shell`, or `None` for an unsupported shell.
+pub fn completion_script(shell: &str) -> Option<String> {
+    match shell {
+        "bash" => Some(bash()),
+        "zsh" => Some(zsh()),
+        "fish" => Some(fish()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{completion_script, visible_subcommands};
+    use crate::cli::FLAGS;
+
+    /// Every option and visible command is offered by every shell.
+    #[test]
+    fn scripts_offer_every_flag_and_command() {
+        for shell in ["bash", "zsh", "fish"] {
+            let script = completion_script(shell).unwrap();
+            for flag in FLAGS {
+                let offered = match shell {
+                    "fish" => format!("-l {}", flag.long.trim_start_matches("--")),
+                    _ => flag.long.to_string(),
+                };
+                assert!(script.contains(&offered), "{} lacks {}", shell, flag.long);
+            }
+            for subcommand in visible_subcommands() {
+                assert!(
+                    script.contains(subcommand.name),
+                    "{} lacks {}",
+                    shell,
+                    subcommand.name
+                );
+            }
+            assert!(!script.contains("gen-man"), "{} offers gen-man", shell);
+        }
+        assert!(completion_script("tcsh").is_none());
+    }
+}
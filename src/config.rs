@@ -0,0 +1,86 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{PoisonError, RwLock};
+
+use log::info;
+use toml::Value;
+
+use crate::sizes::SectionClass;
+
+/// User settings from `config.toml`.
+#[derive(Clone, Default)]
+pub struct Config {
+    /// `[[section]]` tables: glob patterns over section names and the class
+    /// `--size` puts the sections they match in, first match first.
+    pub sections: Vec<(String, SectionClass)>,
+}
+
+/// The settings of the run, seen by every thread; `None` until they are read.
+static CONFIG: RwLock<Option<Config>> = RwLock::new(None);
+
+pub fn set(config: Config) {
+    *CONFIG.write().unwrap_or_else(PoisonError::into_inner) = Some(config);
+}
+
+pub fn get() -> Config {
+    CONFIG
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+        .unwrap_or_default()
+}
+
+/// `$XDG_CONFIG_HOME/elf-header-parser/config.toml`, by default under `~/.config`.
+pub fn default_path() -> Option<PathBuf> {
+    let base = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+    Some(base.join("elf-header-parser").join("config.toml"))
+}
+
+fn parse(text: &str) -> Result<Config, String> {
+    let document: Value = toml::from_str(text).map_err(|error| error.to_string())?;
+    let mut config = Config::default();
+    let Some(sections) = document.get("section") else {
+        return Ok(config);
+    };
+    let sections = sections
+        .as_array()
+        .ok_or("`section` is not a list of [[section]] tables")?;
+    for (index, section) in sections.iter().enumerate() {
+        let text = |name: &str| {
+            section
+                .get(name)
+                .and_then(Value::as_str)
+                .ok_or_else(|| format!("section {}: no {}", index + 1, name))
+        };
+        let class = text("class")?;
+        let class = SectionClass::parse(class).ok_or_else(|| {
+            format!(
+                "section {}: unknown class {}, expected one of: {}",
+                index + 1,
+                class,
+                SectionClass::ALL.map(SectionClass::as_str).join(", ")
+            )
+        })?;
+        config.sections.push((text("name")?.to_string(), class));
+    }
+    Ok(config)
+}
+
+/// Reads `--config FILE`, or else the default file when there is one.
+pub fn load(path: Option<&str>) -> Result<Config, String> {
+    let path = match path {
+        Some(path) => PathBuf::from(path),
+        None => match default_path().filter(|path| path.is_file()) {
+            Some(path) => path,
+            None => return Ok(Config::default()),
+        },
+    };
+    info!("reading settings from {}", path.display());
+    let text =
+        fs::read_to_string(&path).map_err(|error| format!("{}: {}", path.display(), error))?;
+    parse(&text).map_err(|error| format!("{}: {}", path.display(), error))
+}
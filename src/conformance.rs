@@ -0,0 +1,237 @@
+use crate::Loader;
+
+const EI_VERSION: usize = 6;
+const EI_PAD: usize = 9;
+const EI_NIDENT: usize = 16;
+const E_VERSION: usize = 20;
+const E_EHSIZE_ELF32: usize = 40;
+const E_EHSIZE_ELF64: usize = 52;
+const EV_CURRENT: u32 = 1;
+const ELF32_EHDR_SIZE: u16 = 52;
+const ELF64_EHDR_SIZE: u16 = 64;
+const ELF32_PHDR_SIZE: u32 = 32;
+const ELF64_PHDR_SIZE: u32 = 56;
+const ELF32_SHDR_SIZE: u32 = 40;
+const ELF64_SHDR_SIZE: u32 = 64;
+const ET_EXEC: u16 = 2;
+const ET_DYN: u16 = 3;
+const SHN_XINDEX: u32 = 0xffff;
+
+/// A header value worth pointing out, found by [`Loader::header_anomalies`].
+pub struct Anomaly {
+    /// The header field, named as in the header view (`E_EHSIZE`).
+    pub field: &'static str,
+    /// The full sentence, as `--strict` and the warnings print it.
+    pub message: String,
+    /// The short form shown next to the value.
+    pub note: String,
+    /// Whether the value breaks the specification, as opposed to being unusual.
+    pub deviation: bool,
+    /// Whether the value suggests a broken or hand-crafted file.
+    pub suspicious: bool,
+}
+
+impl Anomaly {
+    /// The note as it is appended to the value, `[! ...]` when suspicious.
+    pub fn annotation(&self) -> String {
+        if self.suspicious {
+            format!("[! {}]", self.note)
+        } else {
+            format!("[{}]", self.note)
+        }
+    }
+}
+
+impl Loader {
+    /// Header fields that break the ELF specification without stopping the header
+    /// from being parsed. Loaders mostly tolerate these, but they often point at a
+    /// corrupted or hand-crafted file.
+    pub fn spec_deviations(&self) -> Vec<String> {
+        self.header_anomalies()
+            .into_iter()
+            .filter(|anomaly| anomaly.deviation)
+            .map(|anomaly| anomaly.message)
+            .collect()
+    }
+
+    /// Everything unusual about the header: the deviations from the specification,
+    /// and values that are valid but worth a note, such as a missing section
+    /// header table.
+    pub fn header_anomalies(&self) -> Vec<Anomaly> {
+        let mut anomalies = Vec::new();
+        let mut add = |field, message: String, note: String, deviation, suspicious| {
+            anomalies.push(Anomaly {
+                field,
+                message,
+                note,
+                deviation,
+                suspicious,
+            })
+        };
+        let class = if self.is_64bit() { 64 } else { 32 };
+
+        let ei_version = self.read_u8(EI_VERSION).unwrap_or(0);
+        if u32::from(ei_version) != EV_CURRENT {
+            add(
+                "EI_VERSION",
+                format!("EI_VERSION is {}, expected {}", ei_version, EV_CURRENT),
+                format!("expected {}", EV_CURRENT),
+                true,
+                true,
+            );
+        }
+
+        // The AppImage runtime marks itself with `AI` and its type from EI_ABIVERSION.
+        let padding_start = if self.appimage_kind().is_some() {
+            EI_PAD + 2
+        } else {
+            EI_PAD
+        };
+        let padding = &self.bytes()[padding_start..EI_NIDENT];
+        if padding.iter().any(|&byte| byte != 0) {
+            let hex: Vec<String> = padding.iter().map(|byte| format!("{:02x}", byte)).collect();
+            add(
+                "EI_PAD",
+                format!(
+                    "reserved e_ident bytes {}..{} are not zero: {}",
+                    padding_start,
+                    EI_NIDENT,
+                    hex.join(" ")
+                ),
+                "reserved bytes are not zero".to_string(),
+                true,
+                true,
+            );
+        }
+
+        let e_version = self.read_u32(E_VERSION).unwrap_or(0);
+        if e_version != EV_CURRENT {
+            add(
+                "E_VERSION",
+                format!("e_version is {}, expected {}", e_version, EV_CURRENT),
+                format!("expected {}", EV_CURRENT),
+                true,
+                true,
+            );
+        }
+
+        let (ehsize, expected) = if self.is_64bit() {
+            (self.read_u16(E_EHSIZE_ELF64), ELF64_EHDR_SIZE)
+        } else {
+            (self.read_u16(E_EHSIZE_ELF32), ELF32_EHDR_SIZE)
+        };
+        let ehsize = ehsize.unwrap_or(0);
+        if ehsize != expected {
+            add(
+                "E_EHSIZE",
+                format!(
+                    "e_ehsize is {}, but an ELF{} header is {} bytes",
+                    ehsize, class, expected
+                ),
+                format!("expected {} for ELF{}", expected, class),
+                true,
+                true,
+            );
+        }
+
+        let len = self.bytes().len() as u64;
+        let (phoff, shoff) = (self.get_e_phoff(), self.get_e_shoff());
+        let executable = matches!(self.raw_e_type(), ET_EXEC | ET_DYN);
+        if phoff == 0 && executable {
+            let message = "an executable or shared object without program headers";
+            add(
+                "E_PHOFF",
+                message.to_string(),
+                "cannot be loaded".to_string(),
+                false,
+                true,
+            );
+        }
+        if shoff == 0 {
+            let message = "no section header table";
+            add(
+                "E_SHOFF",
+                message.to_string(),
+                "stripped".to_string(),
+                false,
+                false,
+            );
+        }
+        for (field, offset, table) in [("E_PHOFF", phoff, "program"), ("E_SHOFF", shoff, "section")]
+        {
+            if offset >= len {
+                add(
+                    field,
+                    format!(
+                        "the {} header table at {:#x} starts past the end of the file",
+                        table, offset
+                    ),
+                    "past the end of the file".to_string(),
+                    false,
+                    true,
+                );
+            }
+        }
+        if self.raw_e_type() == ET_EXEC && self.get_e_entry() == 0 {
+            add(
+                "E_ENTRY",
+                "an executable without an entry point".to_string(),
+                "no entry point".to_string(),
+                false,
+                true,
+            );
+        }
+
+        let entry_sizes = [
+            (
+                "E_PHENTSIZE",
+                self.get_e_phentsize(),
+                self.get_e_phnum(),
+                phoff,
+                ELF32_PHDR_SIZE,
+                ELF64_PHDR_SIZE,
+            ),
+            (
+                "E_SHENTSIZE",
+                self.get_e_shentsize(),
+                self.get_e_shnum(),
+                shoff,
+                ELF32_SHDR_SIZE,
+                ELF64_SHDR_SIZE,
+            ),
+        ];
+        for (field, size, count, offset, elf32, elf64) in entry_sizes {
+            let expected = if self.is_64bit() { elf64 } else { elf32 };
+            if offset != 0 && count != 0 && size != expected {
+                add(
+                    field,
+                    format!(
+                        "{} is {}, but an ELF{} entry is {} bytes",
+                        field.to_ascii_lowercase(),
+                        size,
+                        class,
+                        expected
+                    ),
+                    format!("expected {} for ELF{}", expected, class),
+                    false,
+                    true,
+                );
+            }
+        }
+
+        let (shnum, shstrndx) = (self.get_e_shnum(), self.get_e_shstrndx());
+        if shnum != 0 && shstrndx != SHN_XINDEX && shstrndx >= shnum {
+            add(
+                "E_SHSTRNDX",
+                format!(
+                    "e_shstrndx is {}, past the last of {} sections",
+                    shstrndx, shnum
+                ),
+                "past the last section".to_string(),
+                false,
+                true,
+            );
+        }
+        anomalies
+    }
+}
@@ -0,0 +1,308 @@
+use std::ops::Range;
+use std::thread;
+
+use log::info;
+use memchr::memmem;
+
+use crate::section::SHT_NOBITS;
+use crate::{Loader, HEADER_MAGIC};
+
+const FATELF_MAGIC: u32 = 0x1f0e_70fa;
+const FATELF_HEADER_SIZE: usize = 8;
+const FATELF_RECORD_SIZE: usize = 24;
+
+const ZIP_LOCAL_FILE_HEADER: [u8; 4] = *b"PK\x03\x04";
+const ZIP_LOCAL_FILE_HEADER_SIZE: usize = 30;
+const ZIP_METHOD_STORED: u16 = 0;
+const ZIP_FLAG_DATA_DESCRIPTOR: u16 = 0x8;
+
+/// Files from this size on are searched for the magic by several threads.
+const PARALLEL_SCAN_MIN: usize = 64 << 20;
+
+/// An ELF image found somewhere other than offset 0 of its file.
+pub struct EmbeddedElf {
+    pub offset: usize,
+    pub size: usize,
+    /// Where the image came from: a FatELF record, a zip member or a raw scan hit.
+    pub origin: String,
+}
+
+fn le_u16(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(
+        data.get(offset..offset + 2)?.try_into().ok()?,
+    ))
+}
+
+fn le_u32(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        data.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+fn le_u64(data: &[u8], offset: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(
+        data.get(offset..offset + 8)?.try_into().ok()?,
+    ))
+}
+
+impl Loader {
+    /// Whether the identification bytes and e_ehsize look like a real ELF header,
+    /// rather than a stray occurrence of the magic number.
+    pub fn is_plausible_header(&self) -> bool {
+        let bytes = self.bytes();
+        if !bytes.starts_with(&HEADER_MAGIC) || bytes.len() < 52 {
+            return false;
+        }
+        let ehsize_offset = match bytes[4] {
+            1 => 40,
+            2 => 52,
+            _ => return false,
+        };
+        matches!(bytes[5], 1 | 2)
+            && bytes[6] == 1
+            && matches!(
+                (bytes[4], self.read_u16(ehsize_offset)),
+                (1, Some(52)) | (2, Some(64))
+            )
+    }
+
+    /// Size of the ELF image, measured as the furthest byte referenced by its headers.
+    pub fn image_size(&self) -> usize {
+        let ehsize = if self.is_64bit() { 64 } else { 52 };
+        let segments = self
+            .program_headers()
+            .iter()
+            .map(|header| header.p_offset.saturating_add(header.p_filesz) as usize)
+            .max()
+            .unwrap_or(0);
+        let sections = self
+            .section_headers()
+            .iter()
+            .filter(|section| section.sh_type != SHT_NOBITS)
+            .map(|section| section.sh_offset.saturating_add(section.sh_size) as usize)
+            .max()
+            .unwrap_or(0);
+        let tables = self.program_table_end().max(self.section_table_end());
+        ehsize
+            .max(segments)
+            .max(sections)
+            .max(tables)
+            .min(self.bytes().len())
+    }
+
+    fn fatelf_records(&self) -> Option<Vec<EmbeddedElf>> {
+        let data = self.file_bytes();
+        if le_u32(data, 0)? != FATELF_MAGIC {
+            return None;
+        }
+        let count = *data.get(6)? as usize;
+        let records = (0..count)
+            .filter_map(|index| {
+                let record = FATELF_HEADER_SIZE + index * FATELF_RECORD_SIZE;
+                let machine = le_u16(data, record)?;
+                let offset = le_u64(data, record + 8)? as usize;
+                let size = le_u64(data, record + 16)? as usize;
+                Some(EmbeddedElf {
+                    offset,
+                    size,
+                    origin: format!("FatELF record {} (e_machine {})", index, machine),
+                })
+            })
+            .collect();
+        Some(records)
+    }
+
+    /// Members of a zip archive (e.g. an APK) that are stored uncompressed and start with
+    /// the ELF magic. Android requires native libraries to be stored this way to load them
+    /// straight from the archive.
+    fn zip_members(&self) -> Vec<EmbeddedElf> {
+        let data = self.file_bytes();
+        let mut members = Vec::new();
+        let mut position = 0;
+        while data
+            .get(position..position + 4)
+            .is_some_and(|magic| magic == ZIP_LOCAL_FILE_HEADER)
+        {
+            let (Some(flags), Some(method), Some(compressed), Some(uncompressed)) = (
+                le_u16(data, position + 6),
+                le_u16(data, position + 8),
+                le_u32(data, position + 18),
+                le_u32(data, position + 22),
+            ) else {
+                break;
+            };
+            let (Some(name_len), Some(extra_len)) =
+                (le_u16(data, position + 26), le_u16(data, position + 28))
+            else {
+                break;
+            };
+            let name_start = position + ZIP_LOCAL_FILE_HEADER_SIZE;
+            let content = name_start + name_len as usize + extra_len as usize;
+            // Sizes live in a trailing data descriptor we cannot find without the central
+            // directory, so the walk has to stop here.
+            if flags & ZIP_FLAG_DATA_DESCRIPTOR != 0 {
+                break;
+            }
+            if method == ZIP_METHOD_STORED
+                && data
+                    .get(content..)
+                    .is_some_and(|member| member.starts_with(&HEADER_MAGIC))
+            {
+                let name = data
+                    .get(name_start..name_start + name_len as usize)
+                    .map(String::from_utf8_lossy)
+                    .unwrap_or_default();
+                members.push(EmbeddedElf {
+                    offset: content,
+                    size: uncompressed as usize,
+                    origin: name.to_string(),
+                });
+            }
+            position = content + compressed as usize;
+        }
+        members
+    }
+
+    /// Scans the whole file for ELF headers laid out back to back or behind arbitrary data.
+    pub fn scan_for_headers(&self) -> Vec<EmbeddedElf> {
+        self.scan().0
+    }
+
+    /// Like `scan_for_headers`, also counting magic occurrences rejected as implausible.
+    pub fn scan(&self) -> (Vec<EmbeddedElf>, usize) {
+        self.scan_with(scan_threads())
+    }
+
+    fn scan_with(&self, threads: usize) -> (Vec<EmbeddedElf>, usize) {
+        let mut found = Vec::new();
+        let mut rejected = 0;
+        let mut position = 0;
+        for (offset, plausible) in self.magic_candidates(threads) {
+            // Occurrences inside an image already found belong to it.
+            if offset < position {
+                continue;
+            }
+            if plausible {
+                let size = self.at(offset).map_or(0, |image| image.image_size());
+                found.push(EmbeddedElf {
+                    offset,
+                    size,
+                    origin: "concatenated image".to_string(),
+                });
+                position = offset + size.max(HEADER_MAGIC.len());
+            } else {
+                rejected += 1;
+                position = offset + 1;
+            }
+        }
+        (found, rejected)
+    }
+
+    /// Every occurrence of the ELF magic in the file with whether a plausible
+    /// header starts there, in file order. Large files are split into one
+    /// chunk per thread.
+    fn magic_candidates(&self, threads: usize) -> Vec<(usize, bool)> {
+        let len = self.file_bytes().len();
+        if threads < 2 || len < PARALLEL_SCAN_MIN {
+            return self.magic_candidates_in(0..len);
+        }
+        let chunk = len.div_ceil(threads);
+        thread::scope(|scope| {
+            let workers: Vec<_> = (0..len)
+                .step_by(chunk)
+                .map(|start| {
+                    let range = start..(start + chunk).min(len);
+                    scope.spawn(move || self.magic_candidates_in(range))
+                })
+                .collect();
+            workers
+                .into_iter()
+                .flat_map(|worker| worker.join().expect("scan thread panicked"))
+                .collect()
+        })
+    }
+
+    /// The magic occurrences starting within `range`.
+    fn magic_candidates_in(&self, range: Range<usize>) -> Vec<(usize, bool)> {
+        let data = self.file_bytes();
+        // A match starting near the end of the range reaches into the next one.
+        let end = (range.end + HEADER_MAGIC.len() - 1).min(data.len());
+        memmem::find_iter(&data[range.start..end], &HEADER_MAGIC)
+            .map(|hit| {
+                let offset = range.start + hit;
+                (offset, self.is_plausible_header_at(offset))
+            })
+            .collect()
+    }
+
+    fn is_plausible_header_at(&self, offset: usize) -> bool {
+        self.at(offset)
+            .is_some_and(|image| image.is_plausible_header())
+    }
+
+    /// The offset of the first plausible ELF header, for images behind a prefix
+    /// such as a shell script or a runtime stub.
+    pub fn first_header(&self) -> Option<usize> {
+        memmem::find_iter(self.file_bytes(), &HEADER_MAGIC)
+            .find(|&offset| self.is_plausible_header_at(offset))
+    }
+
+    /// Finds every ELF image in a container file, trying FatELF, zip and plain concatenation.
+    pub fn embedded_images(&self) -> Vec<EmbeddedElf> {
+        if let Some(records) = self.fatelf_records() {
+            info!("FatELF container with {} record(s)", records.len());
+            return records;
+        }
+        let members = self.zip_members();
+        if !members.is_empty() {
+            info!("zip archive with {} ELF member(s)", members.len());
+            return members;
+        }
+        let (images, rejected) = self.scan();
+        info!(
+            "not a FatELF or zip container, scanning for headers: {} found, {} magic occurrence(s) rejected",
+            images.len(),
+            rejected
+        );
+        images
+    }
+}
+
+fn scan_threads() -> usize {
+    thread::available_parallelism().map_or(1, |threads| threads.get())
+}
+
+/// `Loader::scan` of every input, in input order. Small files are scanned side
+/// by side, one per thread; large ones one after another, each split among
+/// the threads.
+pub fn scan_all(inputs: &[(String, Loader)]) -> Vec<(Vec<EmbeddedElf>, usize)> {
+    let threads = scan_threads();
+    let mut results: Vec<Option<(Vec<EmbeddedElf>, usize)>> = Vec::new();
+    results.resize_with(inputs.len(), || None);
+    let small: Vec<usize> = (0..inputs.len())
+        .filter(|&index| inputs[index].1.file_bytes().len() < PARALLEL_SCAN_MIN)
+        .collect();
+    thread::scope(|scope| {
+        let workers: Vec<_> = small
+            .chunks(small.len().div_ceil(threads).max(1))
+            .map(|indices| {
+                scope.spawn(move || {
+                    indices
+                        .iter()
+                        .map(|&index| (index, inputs[index].1.scan_with(1)))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        for worker in workers {
+            for (index, scanned) in worker.join().expect("scan thread panicked") {
+                results[index] = Some(scanned);
+            }
+        }
+    });
+    inputs
+        .iter()
+        .zip(results)
+        .map(|((_, loader), scanned)| scanned.unwrap_or_else(|| loader.scan_with(threads)))
+        .collect()
+}
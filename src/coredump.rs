@@ -0,0 +1,242 @@
+use log::debug;
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::filetype;
+use crate::note::hex_string;
+use crate::output;
+use crate::segment::PT_LOAD;
+use crate::{display_line, Loader};
+
+const ET_CORE: u16 = 4;
+const NT_FILE: u32 = 0x4649_4c45;
+
+/// One entry of the NT_FILE note: a file mapped into the crashed process.
+pub struct MappedFile {
+    pub start: u64,
+    pub end: u64,
+    /// Offset into the file, in bytes.
+    pub offset: u64,
+    pub path: String,
+}
+
+impl Loader {
+    pub(crate) fn is_core(&self) -> bool {
+        self.raw_e_type() == ET_CORE
+    }
+
+    /// The files mapped into the process, from the NT_FILE note: a count and a page
+    /// size, one (start, end, page offset) triple per mapping, then the paths.
+    pub fn mapped_files(&self) -> Option<Vec<MappedFile>> {
+        let desc = self.find_note("CORE", NT_FILE)?.desc;
+        let word = if self.is_64bit() { 8 } else { 4 };
+        let count = self.desc_word(&desc, 0)? as usize;
+        let page_size = self.desc_word(&desc, word)?;
+        let names_start = word * 2 + count.checked_mul(word * 3)?;
+        let mut names = desc
+            .get(names_start..)?
+            .split(|&byte| byte == 0)
+            .map(|name| String::from_utf8_lossy(name).into_owned());
+        let mut files = Vec::with_capacity(count);
+        for index in 0..count {
+            let entry = word * 2 + index * word * 3;
+            files.push(MappedFile {
+                start: self.desc_word(&desc, entry)?,
+                end: self.desc_word(&desc, entry + word)?,
+                offset: self
+                    .desc_word(&desc, entry + word * 2)?
+                    .wrapping_mul(page_size),
+                path: names.next()?,
+            });
+        }
+        Some(files)
+    }
+
+    /// The bytes the core dump holds for memory from `vaddr` to the end of the
+    /// PT_LOAD segment containing it.
+    pub fn memory_from(&self, vaddr: u64) -> Option<&[u8]> {
+        let segment = self.program_headers().into_iter().find(|header| {
+            header.p_type == PT_LOAD
+                && vaddr >= header.p_vaddr
+                && vaddr < header.p_vaddr.saturating_add(header.p_filesz)
+        })?;
+        let start = segment.p_offset.checked_add(vaddr - segment.p_vaddr)? as usize;
+        let end = segment.p_offset.checked_add(segment.p_filesz)? as usize;
+        self.bytes().get(start..end)
+    }
+}
+
+/// A module of the crashed process: a file with a mapping of its first page.
+struct Module {
+    path: String,
+    start: u64,
+}
+
+/// The files whose ELF header was mapped, in address order.
+fn modules(files: &[MappedFile]) -> Vec<Module> {
+    let mut modules: Vec<Module> = Vec::new();
+    for file in files.iter().filter(|file| file.offset == 0) {
+        match modules.iter_mut().find(|module| module.path == file.path) {
+            Some(module) => module.start = module.start.min(file.start),
+            None => modules.push(Module {
+                path: file.path.clone(),
+                start: file.start,
+            }),
+        }
+    }
+    modules.sort_by_key(|module| module.start);
+    modules
+}
+
+/// The build-id of the module mapped at `start`, read from the memory the core
+/// dump kept. Kernels dump the first page of every mapped ELF file by default,
+/// and the build-id note sits right after the program headers in it.
+fn module_build_id(core: &Loader, start: u64) -> Option<Vec<u8>> {
+    let memory = core.memory_from(start)?;
+    // The memory of the first segment is the start of the file, so the note's
+    // file offset can be read in it directly.
+    let image = Loader::from_bytes(memory.to_vec());
+    if !image.is_elf() {
+        return None;
+    }
+    image.build_id()
+}
+
+/// What became of one module.
+enum Status {
+    Ok,
+    Missing,
+    Mismatch(Option<Vec<u8>>),
+    /// The core dump kept no build-id to check the file against.
+    Unverified,
+}
+
+/// `core-check CORE [FILE]...`: checks that the binaries a core dump was made
+/// with are at hand, as a pre-flight for a debugger. Each module from NT_FILE is
+/// looked for among the FILEs (by build-id, then by file name) and then at its
+/// own path, and its build-id compared with the one in the dump. The
+/// debuginfod-find calls that would fetch what is missing are listed at the end.
+/// Returns the dump when it cannot be read or modules are missing or differ.
+pub fn display_core_check(paths: &[String]) -> Vec<(String, String)> {
+    let Some((core_path, candidates)) = paths.split_first() else {
+        return vec![(
+            "core-check".to_string(),
+            "expects a core file and optionally the binaries it used".to_string(),
+        )];
+    };
+    let mut failures = Vec::new();
+    let core = match Loader::open(core_path) {
+        Ok(core) if core.header_truncation().is_none() && core.is_core() => core,
+        Ok(_) => return vec![(core_path.clone(), "not an ELF core file".to_string())],
+        Err(error) => return vec![(core_path.clone(), error.to_string())],
+    };
+    let Some(files) = core.mapped_files() else {
+        return vec![(core_path.clone(), "no NT_FILE note".to_string())];
+    };
+
+    let mut by_build_id = HashMap::new();
+    let mut by_name = HashMap::new();
+    for candidate in candidates {
+        match Loader::open(candidate) {
+            Ok(loader) if loader.is_elf() && loader.header_truncation().is_none() => {
+                if let Some(id) = loader.build_id() {
+                    by_build_id.insert(id, candidate.clone());
+                }
+                let name = Path::new(candidate).file_name().map(|name| name.to_owned());
+                by_name.insert(name, candidate.clone());
+            }
+            Ok(loader) => failures.push((candidate.clone(), filetype::not_elf(loader.bytes()))),
+            Err(error) => failures.push((candidate.clone(), error.to_string())),
+        }
+    }
+
+    output::begin(core_path);
+    let modules = modules(&files);
+    let mut checked = 0;
+    let (mut missing, mut mismatched) = (0, 0);
+    let mut queries = Vec::new();
+    for module in &modules {
+        let core_id = module_build_id(&core, module.start);
+        let name = Path::new(&module.path)
+            .file_name()
+            .map(|name| name.to_owned());
+        let local = core_id
+            .as_ref()
+            .and_then(|id| by_build_id.get(id))
+            .or_else(|| by_name.get(&name))
+            .cloned()
+            .unwrap_or_else(|| module.path.clone());
+        let file = Loader::open(&local)
+            .ok()
+            .filter(|loader| loader.is_elf() && loader.header_truncation().is_none());
+        if core_id.is_none() && file.is_none() {
+            // Data files and devices mapped at offset 0 look the same in NT_FILE.
+            debug!(
+                "{}: neither dumped nor on disk as ELF, skipped",
+                module.path
+            );
+            continue;
+        }
+        checked += 1;
+        let status = match (&file, &core_id) {
+            (None, _) => Status::Missing,
+            (Some(_), None) => Status::Unverified,
+            (Some(file), Some(id)) => match file.build_id() {
+                Some(file_id) if &file_id == id => Status::Ok,
+                other => Status::Mismatch(other),
+            },
+        };
+        let text = match &status {
+            Status::Ok => "ok".to_string(),
+            Status::Missing => "missing".to_string(),
+            Status::Mismatch(Some(id)) => format!("build-id mismatch, file has {}", hex_string(id)),
+            Status::Mismatch(None) => "build-id mismatch, file has none".to_string(),
+            Status::Unverified => "not verified, no build-id in the core dump".to_string(),
+        };
+        display_line(&module.path, &text);
+        if local != module.path {
+            output::item(1, &format!("file {}", local));
+        }
+        if let Some(id) = &core_id {
+            output::item(1, &format!("build-id {}", hex_string(id)));
+        }
+        let id = core_id.as_deref().map(hex_string);
+        match (&status, id) {
+            (Status::Missing | Status::Mismatch(_), Some(id)) => {
+                if matches!(status, Status::Missing) {
+                    missing += 1;
+                } else {
+                    mismatched += 1;
+                }
+                queries.push(format!("debuginfod-find executable {}", id));
+                queries.push(format!("debuginfod-find debuginfo {}", id));
+            }
+            (Status::Missing, None) => missing += 1,
+            (Status::Ok, Some(id)) => {
+                let stripped = file.as_ref().is_some_and(|file| {
+                    file.section_by_name(".debug_info").is_none()
+                        && file.section_by_name(".zdebug_info").is_none()
+                });
+                if stripped {
+                    queries.push(format!("debuginfod-find debuginfo {}", id));
+                }
+            }
+            _ => {}
+        }
+    }
+    display_line("Modules", &checked.to_string());
+    display_line("Missing", &missing.to_string());
+    display_line("Mismatched", &mismatched.to_string());
+    display_line("Debuginfod Queries", &queries.len().to_string());
+    for query in &queries {
+        output::item(1, query);
+    }
+    output::end();
+    if missing + mismatched > 0 {
+        failures.push((
+            core_path.clone(),
+            format!("{} module(s) missing, {} mismatched", missing, mismatched),
+        ));
+    }
+    failures
+}
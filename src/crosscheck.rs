@@ -0,0 +1,242 @@
+//! `--cross-check`, built with the `cross-check` feature: parses each input
+//! with the `object` crate as well and lists the fields the two read
+//! differently. `fuzz/` runs the same comparison on generated inputs.
+
+use object::elf::{FileHeader32, FileHeader64, SHT_DYNSYM, SHT_SYMTAB};
+use object::read::elf::{FileHeader, ProgramHeader as _, SectionHeader as _, Sym as _};
+use object::Endianness;
+
+use crate::output;
+use crate::{display_line, Loader};
+
+/// A field this crate and `object` read differently.
+#[derive(Debug)]
+pub struct Difference {
+    /// Where the field is, e.g. `e_entry`, `phdr[2].p_vaddr` or `.dynsym[5].name`.
+    pub field: String,
+    pub ours: String,
+    pub object: String,
+}
+
+impl std::fmt::Display for Difference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: {} here, {} in object",
+            self.field, self.ours, self.object
+        )
+    }
+}
+
+/// Records the fields of `fields`, named with `prefix`, whose values differ.
+fn compare(differences: &mut Vec<Difference>, prefix: &str, fields: &[(&str, u64, u64)]) {
+    for &(name, ours, object) in fields {
+        if ours != object {
+            differences.push(Difference {
+                field: format!("{}{}", prefix, name),
+                ours: format!("{:#x}", ours),
+                object: format!("{:#x}", object),
+            });
+        }
+    }
+}
+
+fn compare_name(differences: &mut Vec<Difference>, field: String, ours: &str, object: &[u8]) {
+    let object = String::from_utf8_lossy(object);
+    if ours != object {
+        differences.push(Difference {
+            field,
+            ours: format!("{:?}", ours),
+            object: format!("{:?}", object),
+        });
+    }
+}
+
+fn differences_of<Elf: FileHeader<Endian = Endianness>>(
+    loader: &Loader,
+) -> Result<Vec<Difference>, String> {
+    let data = loader.bytes();
+    let header = Elf::parse(data).map_err(|error| error.to_string())?;
+    let endian = header.endian().map_err(|error| error.to_string())?;
+    let mut differences = Vec::new();
+    let out = &mut differences;
+
+    compare(
+        out,
+        "",
+        &[
+            (
+                "e_type",
+                loader.raw_e_type().into(),
+                header.e_type(endian).into(),
+            ),
+            (
+                "e_machine",
+                loader.raw_e_machine().into(),
+                header.e_machine(endian).into(),
+            ),
+            (
+                "e_entry",
+                loader.raw_e_entry(),
+                header.e_entry(endian).into(),
+            ),
+            (
+                "e_phoff",
+                loader.get_e_phoff(),
+                header.e_phoff(endian).into(),
+            ),
+            (
+                "e_shoff",
+                loader.get_e_shoff(),
+                header.e_shoff(endian).into(),
+            ),
+            (
+                "e_flags",
+                loader.raw_e_flags().into(),
+                header.e_flags(endian).into(),
+            ),
+        ],
+    );
+
+    // Tables object refuses, for a bad entry size or a range past the end,
+    // are not compared: this crate reads what it can of those instead.
+    if let Ok(theirs) = header.program_headers(endian, data) {
+        let ours = loader.program_headers();
+        compare(
+            out,
+            "",
+            &[("e_phnum", ours.len() as u64, theirs.len() as u64)],
+        );
+        for (index, (ours, theirs)) in ours.iter().zip(theirs).enumerate() {
+            compare(
+                out,
+                &format!("phdr[{}].", index),
+                &[
+                    ("p_type", ours.p_type.into(), theirs.p_type(endian).into()),
+                    (
+                        "p_flags",
+                        ours.p_flags.into(),
+                        theirs.p_flags(endian).into(),
+                    ),
+                    ("p_offset", ours.p_offset, theirs.p_offset(endian).into()),
+                    ("p_vaddr", ours.p_vaddr, theirs.p_vaddr(endian).into()),
+                    ("p_filesz", ours.p_filesz, theirs.p_filesz(endian).into()),
+                    ("p_memsz", ours.p_memsz, theirs.p_memsz(endian).into()),
+                    ("p_align", ours.p_align, theirs.p_align(endian).into()),
+                ],
+            );
+        }
+    }
+
+    let Ok(sections) = header.sections(endian, data) else {
+        return Ok(differences);
+    };
+    let ours = loader.section_headers();
+    compare(
+        out,
+        "",
+        &[("e_shnum", ours.len() as u64, sections.len() as u64)],
+    );
+    for (ours, theirs) in ours.iter().zip(sections.iter()) {
+        let prefix = format!("shdr[{}].", ours.index);
+        if let Ok(name) = sections.section_name(endian, theirs) {
+            compare_name(out, format!("{}name", prefix), &ours.name, name);
+        }
+        compare(
+            out,
+            &prefix,
+            &[
+                (
+                    "sh_type",
+                    ours.sh_type.into(),
+                    theirs.sh_type(endian).into(),
+                ),
+                ("sh_flags", ours.sh_flags, theirs.sh_flags(endian).into()),
+                ("sh_addr", ours.sh_addr, theirs.sh_addr(endian).into()),
+                ("sh_offset", ours.sh_offset, theirs.sh_offset(endian).into()),
+                ("sh_size", ours.sh_size, theirs.sh_size(endian).into()),
+                (
+                    "sh_link",
+                    ours.sh_link.into(),
+                    theirs.sh_link(endian).into(),
+                ),
+                (
+                    "sh_info",
+                    ours.sh_info.into(),
+                    theirs.sh_info(endian).into(),
+                ),
+            ],
+        );
+    }
+
+    for (sh_type, table_name) in [(SHT_SYMTAB, ".symtab"), (SHT_DYNSYM, ".dynsym")] {
+        let Ok(table) = sections.symbols(endian, data, sh_type) else {
+            continue;
+        };
+        let ours = loader.symbols_of_type(sh_type);
+        compare(
+            out,
+            table_name,
+            &[(" entries", ours.len() as u64, table.len() as u64)],
+        );
+        for (index, (ours, theirs)) in ours.iter().zip(table.iter()).enumerate() {
+            let prefix = format!("{}[{}].", table_name, index);
+            if let Ok(name) = table.symbol_name(endian, theirs) {
+                compare_name(out, format!("{}name", prefix), &ours.name, name);
+            }
+            compare(
+                out,
+                &prefix,
+                &[
+                    ("st_value", ours.st_value, theirs.st_value(endian).into()),
+                    ("st_size", ours.st_size, theirs.st_size(endian).into()),
+                    ("st_info", ours.st_info.into(), theirs.st_info().into()),
+                    ("st_other", ours.st_other.into(), theirs.st_other().into()),
+                    (
+                        "st_shndx",
+                        ours.st_shndx.into(),
+                        theirs.st_shndx(endian).into(),
+                    ),
+                ],
+            );
+        }
+    }
+    Ok(differences)
+}
+
+/// The fields of the ELF header, program and section headers and symbol
+/// tables that `object` reads differently, or why `object` cannot parse the
+/// file at all.
+pub fn differences(loader: &Loader) -> Result<Vec<Difference>, String> {
+    if loader.is_64bit() {
+        differences_of::<FileHeader64<Endianness>>(loader)
+    } else {
+        differences_of::<FileHeader32<Endianness>>(loader)
+    }
+}
+
+/// `--cross-check`: the disagreements with `object`, each file failing that
+/// has any.
+pub fn display_cross_check(targets: &[(String, Loader)]) -> Vec<(String, String)> {
+    let mut failures = Vec::new();
+    for (label, loader) in targets {
+        output::begin(label);
+        match differences(loader) {
+            Ok(differences) => {
+                display_line("Disagreements", &differences.len().to_string());
+                for difference in &differences {
+                    output::item(1, &difference.to_string());
+                }
+                if !differences.is_empty() {
+                    failures.push((
+                        label.clone(),
+                        format!("{} field(s) read differently by object", differences.len()),
+                    ));
+                }
+            }
+            Err(error) => display_line("Disagreements", &format!("object cannot parse: {}", error)),
+        }
+        output::end();
+    }
+    failures
+}
@@ -0,0 +1,376 @@
+use log::{info, warn};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, UNIX_EPOCH};
+
+use crate::cli::Options;
+use crate::fingerprint::Fingerprint;
+use crate::output::{self, Record};
+use crate::{linkage, open_inputs, patch, report, user_cache_dir};
+
+const RESCAN_INTERVAL: Duration = Duration::from_secs(2);
+const CACHE_MAGIC: &str = "elf-header-parser daemon cache 1";
+
+/// What identifies one version of a file: its modification time (seconds and
+/// nanoseconds since the epoch) and size.
+type Stamp = (u64, u32, u64);
+
+fn stamp(path: &str) -> Option<Stamp> {
+    let metadata = fs::metadata(path).ok()?;
+    let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+    Some((modified.as_secs(), modified.subsec_nanos(), metadata.len()))
+}
+
+/// The report of every file seen so far, keyed by path and only valid for the
+/// stamp it was made at.
+#[derive(Default)]
+struct Cache {
+    files: HashMap<String, (Stamp, Vec<Record>)>,
+    /// The regions of each file when it was last parsed this run, so that a
+    /// change outside what the report reads keeps the report.
+    fingerprints: HashMap<String, Fingerprint>,
+}
+
+impl Cache {
+    /// Reads a cache written by `save` for the same report options. A missing,
+    /// damaged or differently configured cache gives an empty one.
+    fn load(path: &Path, key: &str) -> Cache {
+        let mut cache = Cache::default();
+        let Ok(text) = fs::read_to_string(path) else {
+            return cache;
+        };
+        let mut lines = text.lines();
+        if lines.next() != Some(CACHE_MAGIC) || lines.next() != Some(key) {
+            info!(
+                "{}: written for other options, starting afresh",
+                path.display()
+            );
+            return cache;
+        }
+        while let Some(header) = lines.next() {
+            let fields: Vec<&str> = header.split('\t').collect();
+            let parsed = match fields[..] {
+                ["file", path, secs, nanos, size, count] => (|| {
+                    let stamp = (secs.parse().ok()?, nanos.parse().ok()?, size.parse().ok()?);
+                    let count: usize = count.parse().ok()?;
+                    let records = output::decode(lines.by_ref().take(count))?;
+                    Some((output::unescape(path), stamp, records))
+                })(),
+                _ => None,
+            };
+            let Some((path, stamp, records)) = parsed else {
+                warn!("daemon cache is damaged, dropping the rest of it");
+                break;
+            };
+            cache.files.insert(path, (stamp, records));
+        }
+        info!("{}: {} cached report(s)", path.display(), cache.files.len());
+        cache
+    }
+
+    /// Writes the cache next to its final place and renames it over, so a crash
+    /// mid-write leaves the previous cache intact.
+    fn save(&self, path: &Path, key: &str) -> io::Result<()> {
+        let mut text = format!("{}\n{}\n", CACHE_MAGIC, key);
+        for (file, ((secs, nanos, size), records)) in &self.files {
+            let lines = output::encode(records);
+            text.push_str(&format!(
+                "file\t{}\t{}\t{}\t{}\t{}\n",
+                output::escape(file),
+                secs,
+                nanos,
+                size,
+                lines.len()
+            ));
+            for line in lines {
+                text.push_str(&line);
+                text.push('\n');
+            }
+        }
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        patch::write_atomic(path, text)
+            .map(drop)
+            .map_err(io::Error::other)
+    }
+}
+
+/// What a scan did with one file.
+#[derive(Debug, PartialEq)]
+enum Refresh {
+    /// Unchanged since its cached report.
+    Reused,
+    /// Changed outside the regions its report reads, so the report stands.
+    Kept,
+    /// New or changed, and reported again.
+    Parsed,
+}
+
+impl Cache {
+    /// Brings the report of `path`, now at `current`, up to date, printing it
+    /// when it is parsed again.
+    fn refresh(&mut self, path: &str, current: Stamp, options: &Options) -> Refresh {
+        if let Some((cached, _)) = self.files.get(path) {
+            if *cached == current {
+                return Refresh::Reused;
+            }
+        }
+        let fingerprint = Fingerprint::of(path).ok();
+        let unaffected = self.files.contains_key(path)
+            && self
+                .fingerprints
+                .get(path)
+                .zip(fingerprint.as_ref())
+                .is_some_and(|(old, new)| new.changed_since(old, options.header_only()).is_empty());
+        match fingerprint {
+            Some(fingerprint) => self.fingerprints.insert(path.to_string(), fingerprint),
+            None => self.fingerprints.remove(path),
+        };
+        if unaffected {
+            info!("{}: changed outside the regions its report reads", path);
+            if let Some((stamp, _)) = self.files.get_mut(path) {
+                *stamp = current;
+            }
+            return Refresh::Kept;
+        }
+        // Failures are part of the report, so an unreadable file is
+        // not retried until it changes either.
+        let records = output::collect(|| {
+            let mut failures = Vec::new();
+            report(
+                open_inputs(&[path.to_string()], options.header_only(), &mut failures),
+                options,
+                &mut failures,
+            );
+            for (label, problem) in &failures {
+                output::message(&format!("{}: {}", label, problem));
+            }
+        });
+        output::replay(&records);
+        self.files.insert(path.to_string(), (current, records));
+        Refresh::Parsed
+    }
+}
+
+/// Arguments that change how reports are printed or traced but not what they
+/// contain, so the cache outlives them.
+const PRESENTATION_FLAGS: &[&str] = &["--daemon", "--verbose", "-v", "-vv", "--timings"];
+
+/// The arguments that decide what a report contains: everything but the inputs
+/// and `PRESENTATION_FLAGS`. A cache made with other options is not reused.
+fn options_key(options: &Options) -> String {
+    let mut args = Vec::new();
+    let mut forwarded = options.forwarded.iter();
+    while let Some(arg) = forwarded.next() {
+        if arg == "--format" {
+            forwarded.next();
+        } else if !PRESENTATION_FLAGS.contains(&arg.as_str()) && !options.paths.contains(arg) {
+            args.push(arg.as_str());
+        }
+    }
+    format!("options\t{}", args.join(" "))
+}
+
+/// The input files, with directories replaced by the regular files below them.
+fn input_files(paths: &[String]) -> Vec<String> {
+    let mut files = Vec::new();
+    let mut errors = Vec::new();
+    for path in paths {
+        if Path::new(path).is_dir() {
+            linkage::walk(Path::new(path), &mut files, &mut errors);
+        } else {
+            files.push(path.clone());
+        }
+    }
+    for (path, error) in errors {
+        warn!("{}: {}", path, error);
+    }
+    files
+}
+
+/// Reports every input, then rescans them every few seconds and reports only the
+/// files that are new or changed, and the ones that went away. Reports are kept
+/// in `~/.cache/elf-header-parser/daemon.cache` keyed by path, modification time
+/// and size, so unchanged files are never parsed twice, even across restarts.
+/// With the default header table, a file whose header regions, as hashed by
+/// [`Fingerprint`] when it was last parsed this run, did not change keeps its
+/// report too; other reports parse every changed file again.
+/// Never returns unless there is nothing to scan.
+pub fn daemon(options: &Options) -> Vec<(String, String)> {
+    if options.paths.is_empty() {
+        return vec![(
+            "--daemon".to_string(),
+            "needs files or directories to scan".to_string(),
+        )];
+    }
+    let key = options_key(options);
+    let cache_path: Option<PathBuf> = if options.no_cache {
+        None
+    } else {
+        user_cache_dir().map(|dir| dir.join("daemon.cache"))
+    };
+    let mut cache = match &cache_path {
+        Some(path) => Cache::load(path, &key),
+        None if options.no_cache => Cache::default(),
+        None => {
+            warn!(
+                "no cache directory (set HOME or XDG_CACHE_HOME), reports are kept in memory only"
+            );
+            Cache::default()
+        }
+    };
+
+    let mut first_pass = true;
+    loop {
+        let files = input_files(&options.paths);
+        let (mut reused, mut kept, mut parsed) = (0, 0, 0);
+        for path in &files {
+            let Some(current) = stamp(path) else {
+                continue;
+            };
+            match cache.refresh(path, current, options) {
+                Refresh::Reused => {
+                    if first_pass {
+                        output::replay(&cache.files[path].1);
+                    }
+                    reused += 1;
+                }
+                Refresh::Kept => kept += 1,
+                Refresh::Parsed => parsed += 1,
+            }
+        }
+        let before = cache.files.len();
+        let present: HashSet<&String> = files.iter().collect();
+        cache.files.retain(|path, _| {
+            let present = present.contains(path);
+            if !present {
+                output::message(&format!("{}: removed", path));
+            }
+            present
+        });
+        let removed = before - cache.files.len();
+        cache
+            .fingerprints
+            .retain(|path, _| cache.files.contains_key(path));
+        if parsed > 0 || kept > 0 || removed > 0 {
+            if let Some(path) = &cache_path {
+                if let Err(error) = cache.save(path, &key) {
+                    warn!("{}: cannot write the cache: {}", path.display(), error);
+                }
+            }
+        }
+        if first_pass || parsed > 0 || removed > 0 {
+            info!(
+                "scanned {} file(s): {} from the cache, {} changed elsewhere than their report reads, {} parsed, {} removed",
+                files.len(),
+                reused,
+                kept,
+                parsed,
+                removed
+            );
+            output::finish();
+        }
+        first_pass = false;
+        thread::sleep(RESCAN_INTERVAL);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::path::Path;
+    use std::process;
+
+    use super::{options_key, Cache, Refresh};
+    use crate::cli::Options;
+    use crate::output::{self, Record};
+
+    fn options(args: &[&str]) -> Options {
+        Options::parse(args.iter().map(|arg| arg.to_string())).unwrap()
+    }
+
+    fn scratch(name: &str) -> std::path::PathBuf {
+        env::temp_dir().join(format!(
+            "elf-header-parser-daemon-{}-{}",
+            process::id(),
+            name
+        ))
+    }
+
+    #[test]
+    fn saved_cache_loads_back() {
+        let path = scratch("cache");
+        let mut cache = Cache::default();
+        let records = vec![Record::Message("a\tb: report".to_string())];
+        cache
+            .files
+            .insert("dir/with\ttab".to_string(), ((1, 2, 3), records));
+        cache.save(&path, "options\t--checksec").unwrap();
+
+        let loaded = Cache::load(&path, "options\t--checksec");
+        let other = Cache::load(&path, "options\t--notes");
+        fs::remove_file(&path).unwrap();
+        let (stamp, records) = &loaded.files["dir/with\ttab"];
+        assert_eq!(*stamp, (1, 2, 3));
+        assert!(matches!(&records[..], [Record::Message(text)] if text == "a\tb: report"));
+        assert!(other.files.is_empty());
+    }
+
+    #[test]
+    fn options_key_leaves_out_presentation_and_inputs() {
+        let key = options_key(&options(&[
+            "--format",
+            "json",
+            "-v",
+            "--checksec",
+            "a.out",
+            "b.so",
+        ]));
+        assert_eq!(key, options_key(&options(&["--checksec", "c.out"])));
+        assert_eq!(key, "options\t--checksec");
+        assert_ne!(key, options_key(&options(&["--notes", "a.out"])));
+    }
+
+    /// A change the header table does not read keeps the report; one it reads
+    /// parses the file again.
+    #[test]
+    fn change_outside_the_header_keeps_the_report() {
+        let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/x86_64-shared");
+        let path = scratch("shared");
+        let label = path.to_str().unwrap();
+        let mut elf = fs::read(fixture).unwrap();
+        fs::write(&path, &elf).unwrap();
+        let options = options(&[label]);
+        let mut cache = Cache::default();
+
+        let mut outcomes = Vec::new();
+        output::collect(|| {
+            outcomes.push(cache.refresh(label, (1, 0, 0), &options));
+            outcomes.push(cache.refresh(label, (1, 0, 0), &options));
+            // A byte of .text.
+            elf[0x320] ^= 0xff;
+            fs::write(&path, &elf).unwrap();
+            outcomes.push(cache.refresh(label, (2, 0, 0), &options));
+            // e_flags.
+            elf[0x30] ^= 0xff;
+            fs::write(&path, &elf).unwrap();
+            outcomes.push(cache.refresh(label, (3, 0, 0), &options));
+        });
+        fs::remove_file(&path).unwrap();
+        assert_eq!(
+            outcomes,
+            [
+                Refresh::Parsed,
+                Refresh::Reused,
+                Refresh::Kept,
+                Refresh::Parsed
+            ]
+        );
+        assert_eq!(cache.files[label].0, (3, 0, 0));
+    }
+}
@@ -0,0 +1,338 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, PoisonError, RwLock};
+
+use log::info;
+
+use crate::checksum::crc32;
+use crate::note::hex_string;
+use crate::output;
+use crate::patch;
+use crate::remote;
+use crate::section::SHT_SYMTAB;
+use crate::{display_line, user_cache_dir, Loader};
+
+/// Where distributions install separate debug files, GDB's default
+/// `debug-file-directory`.
+const DEBUG_DIR: &str = "/usr/lib/debug";
+
+/// The servers of `--debuginfod` or `DEBUGINFOD_URLS`, for every thread.
+static DEBUGINFOD: RwLock<Vec<String>> = RwLock::new(Vec::new());
+
+pub fn set_debuginfod(urls: Vec<String>) {
+    *DEBUGINFOD.write().unwrap_or_else(PoisonError::into_inner) = urls;
+}
+
+/// What a file keeps for debuggers and symbolizers, as `file(1)` sums it up.
+pub struct DebugFacts {
+    /// The number of entries of `.symtab`, if the file has one.
+    pub symtab: Option<usize>,
+    /// The DWARF sections, `.debug_*` or compressed `.zdebug_*`.
+    pub dwarf: Vec<String>,
+    /// The file name and CRC-32 `.gnu_debuglink` points to.
+    pub debuglink: Option<(String, u32)>,
+    /// The file name and build-id of the supplementary file `dwz` shares
+    /// debug info through, from `.gnu_debugaltlink`.
+    pub debugaltlink: Option<(String, Vec<u8>)>,
+    pub build_id: Option<Vec<u8>>,
+}
+
+impl DebugFacts {
+    /// Without a static symbol table, as `file(1)` and `strip` mean it.
+    pub fn stripped(&self) -> bool {
+        self.symtab.is_none()
+    }
+
+    /// The facts as one line, e.g. `not stripped, DWARF, no debuglink, build-id`.
+    pub fn summary(&self) -> String {
+        let mut parts = vec![if self.stripped() {
+            "stripped"
+        } else {
+            "not stripped"
+        }];
+        parts.push(if self.dwarf.is_empty() {
+            "no DWARF"
+        } else {
+            "DWARF"
+        });
+        parts.push(if self.debuglink.is_some() {
+            "debuglink"
+        } else {
+            "no debuglink"
+        });
+        parts.push(if self.build_id.is_some() {
+            "build-id"
+        } else {
+            "no build-id"
+        });
+        parts.join(", ")
+    }
+}
+
+impl Loader {
+    pub fn debug_facts(&self) -> DebugFacts {
+        let sections = self.section_headers();
+        let symtab = sections
+            .iter()
+            .find(|section| section.sh_type == SHT_SYMTAB)
+            .map(|_| self.symbols_of_type(SHT_SYMTAB).len());
+        let dwarf = sections
+            .iter()
+            .filter(|section| {
+                section.name.starts_with(".debug_") || section.name.starts_with(".zdebug_")
+            })
+            .map(|section| section.name.clone())
+            .collect();
+        // The file name, NUL-padded to 4 bytes, then the CRC-32 of the debug file.
+        let debuglink = sections
+            .iter()
+            .find(|section| section.name == ".gnu_debuglink")
+            .and_then(|section| {
+                let data = self.section_data(section);
+                let end = data.iter().position(|&byte| byte == 0)?;
+                let name = String::from_utf8_lossy(&data[..end]).into_owned();
+                let crc_offset = (end + 4) & !3;
+                let crc: [u8; 4] = data.get(crc_offset..crc_offset + 4)?.try_into().ok()?;
+                let crc = if self.is_big_endian() {
+                    u32::from_be_bytes(crc)
+                } else {
+                    u32::from_le_bytes(crc)
+                };
+                Some((name, crc))
+            });
+        // The file name, NUL-terminated, then the build-id of the file.
+        let debugaltlink = sections
+            .iter()
+            .find(|section| section.name == ".gnu_debugaltlink")
+            .and_then(|section| {
+                let data = self.section_data(section);
+                let end = data.iter().position(|&byte| byte == 0)?;
+                let name = String::from_utf8_lossy(&data[..end]).into_owned();
+                Some((name, data[end + 1..].to_vec()))
+            });
+        DebugFacts {
+            symtab,
+            dwarf,
+            debuglink,
+            debugaltlink,
+            build_id: self.build_id(),
+        }
+    }
+
+    /// The separate debug file attached by `--with-debug`, if any.
+    pub fn debug_file(&self) -> Option<&Loader> {
+        self.debug.as_deref()
+    }
+
+    /// Attaches a separate debug file, whose symbol table then stands in for
+    /// the stripped one.
+    pub fn with_debug_file(mut self, debug: Loader) -> Loader {
+        self.debug = Some(Arc::new(debug));
+        self
+    }
+}
+
+/// A separate debug file found for a binary.
+pub struct DebugFile {
+    pub path: PathBuf,
+    /// `build-id`, `debuglink` or `debuginfod`, the way it was found.
+    pub via: &'static str,
+    /// Whether its build-id, or for a debug link its CRC-32, is the one the
+    /// binary records.
+    pub matches: bool,
+}
+
+/// `/usr/lib/debug/.build-id/ab/cdef....debug`, where a file with this
+/// build-id keeps its debug info.
+fn build_id_path(id: &[u8]) -> Option<PathBuf> {
+    let (first, rest) = id.split_first()?;
+    Some(
+        Path::new(DEBUG_DIR)
+            .join(".build-id")
+            .join(format!("{:02x}", first))
+            .join(format!("{}.debug", hex_string(rest))),
+    )
+}
+
+/// Where GDB looks for the debug file a `.gnu_debuglink` names: next to the
+/// file, in its `.debug` directory and under `/usr/lib/debug` at the file's
+/// own directory.
+pub(crate) fn debuglink_candidates(path: &str, name: &str) -> Vec<PathBuf> {
+    let path = fs::canonicalize(path).unwrap_or_else(|_| PathBuf::from(path));
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let global = Path::new(DEBUG_DIR).join(dir.strip_prefix("/").unwrap_or(dir));
+    [
+        dir.join(name),
+        dir.join(".debug").join(name),
+        global.join(name),
+    ]
+    .into_iter()
+    .filter(|candidate| *candidate != path)
+    .collect()
+}
+
+/// Whether the file at `path` is an ELF file with this build-id.
+fn has_build_id(path: &Path, id: &[u8]) -> bool {
+    Loader::open(&path.to_string_lossy())
+        .is_ok_and(|loader| loader.is_elf() && loader.build_id().as_deref() == Some(id))
+}
+
+/// The debug file with this build-id from the first debuginfod server that
+/// has it, kept in the cache directory so that it is fetched once.
+fn fetch_debuginfod(id: &[u8]) -> Option<PathBuf> {
+    let urls = DEBUGINFOD
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone();
+    if urls.is_empty() {
+        return None;
+    }
+    let id = hex_string(id);
+    let cached = user_cache_dir()?
+        .join("debuginfod")
+        .join(format!("{}.debug", id));
+    if cached.is_file() {
+        return Some(cached);
+    }
+    for url in &urls {
+        let url = format!("{}/buildid/{}/debuginfo", url.trim_end_matches('/'), id);
+        match remote::curl(&url, None) {
+            Ok(bytes) => {
+                let written = cached
+                    .parent()
+                    .map_or(Ok(()), fs::create_dir_all)
+                    .and_then(|()| {
+                        patch::write_atomic(&cached, bytes)
+                            .map(drop)
+                            .map_err(io::Error::other)
+                    });
+                if let Err(error) = written {
+                    info!("{}: not cached: {}", cached.display(), error);
+                    return None;
+                }
+                return Some(cached);
+            }
+            Err(error) => info!("{}: {}", url, error),
+        }
+    }
+    None
+}
+
+/// The separate debug file of the binary at `path`, looked up the way GDB
+/// does: by build-id first, then by the debug link, and last from the
+/// debuginfod servers.
+pub fn find_debug_file(path: &str, facts: &DebugFacts) -> Option<DebugFile> {
+    if let Some(id) = &facts.build_id {
+        if let Some(candidate) = build_id_path(id).filter(|candidate| candidate.is_file()) {
+            return Some(DebugFile {
+                matches: has_build_id(&candidate, id),
+                path: candidate,
+                via: "build-id",
+            });
+        }
+    }
+    let linked = facts.debuglink.as_ref().and_then(|(name, expected)| {
+        debuglink_candidates(path, name)
+            .into_iter()
+            .find_map(|candidate| {
+                let data = fs::read(&candidate).ok()?;
+                Some(DebugFile {
+                    matches: crc32(&data) == *expected,
+                    path: candidate,
+                    via: "debuglink",
+                })
+            })
+    });
+    linked.or_else(|| {
+        let id = facts.build_id.as_ref()?;
+        let fetched = fetch_debuginfod(id)?;
+        Some(DebugFile {
+            matches: has_build_id(&fetched, id),
+            path: fetched,
+            via: "debuginfod",
+        })
+    })
+}
+
+/// The file `.gnu_debugaltlink` names, relative names taken from the binary's
+/// directory, or else found by its build-id, with whether the build-id matches.
+pub fn find_alt_file(path: &str, facts: &DebugFacts) -> Option<(PathBuf, bool)> {
+    let (name, id) = facts.debugaltlink.as_ref()?;
+    let dir = Path::new(path).parent().unwrap_or(Path::new("."));
+    [Some(dir.join(name)), build_id_path(id)]
+        .into_iter()
+        .flatten()
+        .find(|candidate| candidate.is_file())
+        .map(|candidate| {
+            let matches = has_build_id(&candidate, id);
+            (candidate, matches)
+        })
+}
+
+/// `--with-debug`: the binary with its separate debug file attached, when one
+/// is found and matches.
+pub fn attach_debug_file(path: &str, loader: Loader) -> Loader {
+    let found = find_debug_file(path, &loader.debug_facts())
+        .filter(|found| found.matches)
+        .and_then(|found| Loader::open(&found.path.to_string_lossy()).ok());
+    match found {
+        Some(debug) => loader.with_debug_file(debug),
+        None => loader,
+    }
+}
+
+/// `--debug-info`: whether the file is stripped, its symbol table, DWARF
+/// sections, debug links and build-id, with a one-line summary, and the
+/// separate debug file found for it.
+pub fn display_debug_info(path: &str, loader: &Loader) {
+    let facts = loader.debug_facts();
+    output::begin(path);
+    display_line("Summary", &facts.summary());
+    display_line("Stripped", if facts.stripped() { "Yes" } else { "No" });
+    match facts.symtab {
+        Some(count) => display_line("Symbol Table", &format!(".symtab ({} symbols)", count)),
+        None => display_line("Symbol Table", "None"),
+    }
+    if facts.dwarf.is_empty() {
+        display_line("DWARF", "None");
+    } else {
+        display_line("DWARF", &facts.dwarf.join(" "));
+    }
+    match &facts.debuglink {
+        Some((name, crc)) => display_line("Debug Link", &format!("{} (crc {:#010x})", name, crc)),
+        None => display_line("Debug Link", "None"),
+    }
+    if let Some((name, id)) = &facts.debugaltlink {
+        let found = match find_alt_file(path, &facts) {
+            Some((file, true)) => format!(", found at {}", file.display()),
+            Some((file, false)) => format!(", {} has another build-id", file.display()),
+            None => ", not found".to_string(),
+        };
+        display_line(
+            "Debug Alt Link",
+            &format!("{} (build-id {}){}", name, hex_string(id), found),
+        );
+    }
+    if facts.debuglink.is_some() || facts.build_id.is_some() {
+        let found = match find_debug_file(path, &facts) {
+            Some(file) => format!(
+                "{} (by {}){}",
+                file.path.display(),
+                file.via,
+                if file.matches {
+                    ""
+                } else {
+                    "  [! does not match]"
+                }
+            ),
+            None => "Not found".to_string(),
+        };
+        display_line("Debug File", &found);
+    }
+    match &facts.build_id {
+        Some(id) => display_line("Build ID", &hex_string(id)),
+        None => display_line("Build ID", "None"),
+    }
+    output::end();
+}
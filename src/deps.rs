@@ -0,0 +1,248 @@
+use log::debug;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::dynamic::{DT_RPATH, DT_RUNPATH};
+use crate::filetype;
+use crate::graph::Graph;
+use crate::limits::{self, LimitExceeded};
+use crate::{display_line, output, Loader};
+
+/// Directories the dynamic linker falls back to, after the multiarch ones.
+const DEFAULT_DIRS: &[&str] = &["/lib64", "/usr/lib64", "/lib", "/usr/lib"];
+
+impl Loader {
+    /// The directories of DT_RPATH or DT_RUNPATH, split at the colons.
+    fn search_dirs(&self, d_tag: u64) -> Vec<String> {
+        self.dynamic_value(d_tag)
+            .and_then(|offset| self.dynamic_string(offset))
+            .map(|dirs| {
+                dirs.split(':')
+                    .filter(|dir| !dir.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// The multiarch directories such as /usr/lib/x86_64-linux-gnu, then the
+/// traditional ones.
+fn default_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    for base in ["/lib", "/usr/lib"] {
+        let Ok(entries) = fs::read_dir(base) else {
+            continue;
+        };
+        let mut multiarch: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_name().to_string_lossy().contains("-linux-"))
+            .map(|entry| entry.path())
+            .filter(|path| path.is_dir())
+            .collect();
+        multiarch.sort();
+        dirs.extend(multiarch);
+    }
+    dirs.extend(DEFAULT_DIRS.iter().map(PathBuf::from));
+    dirs
+}
+
+/// Substitutes the dynamic string tokens the tool can know: $ORIGIN, the
+/// directory of the object, and $LIB.
+fn expand(dir: &str, origin: &Path, is_64bit: bool) -> PathBuf {
+    let lib = if is_64bit { "lib64" } else { "lib" };
+    let origin = origin.display().to_string();
+    PathBuf::from(
+        dir.replace("${ORIGIN}", &origin)
+            .replace("$ORIGIN", &origin)
+            .replace("${LIB}", lib)
+            .replace("$LIB", lib),
+    )
+}
+
+/// One line of a dependency tree.
+struct Dependency {
+    depth: usize,
+    name: String,
+    /// Where the library was found, if anywhere.
+    path: Option<String>,
+    /// Whether it was listed, with its own dependencies, higher up.
+    repeated: bool,
+}
+
+/// Finds libraries the way the dynamic linker does, without the environment and
+/// the ld.so cache, and records the tree under one executable or library.
+struct Resolver<'a> {
+    defaults: &'a [PathBuf],
+    is_64bit: bool,
+    machine: u16,
+    /// DT_RPATH of the objects on the way down, which apply to everything they load.
+    inherited_rpath: Vec<PathBuf>,
+    seen: HashSet<String>,
+    lines: Vec<Dependency>,
+    /// Set when a library lies deeper than `--max-depth`.
+    too_deep: Option<LimitExceeded>,
+    graph: &'a mut Graph,
+}
+
+impl Resolver<'_> {
+    /// Whether a candidate can be loaded by the root: same class and machine.
+    fn compatible(&self, loader: &Loader) -> bool {
+        loader.is_elf()
+            && loader.header_truncation().is_none()
+            && loader.is_64bit() == self.is_64bit
+            && loader.raw_e_machine() == self.machine
+    }
+
+    fn find(&self, name: &str, object: &Loader, origin: &Path) -> Option<(String, Loader)> {
+        if name.contains('/') {
+            let loader = Loader::open(name).ok()?;
+            return self.compatible(&loader).then(|| (name.to_string(), loader));
+        }
+        let runpath = object.search_dirs(DT_RUNPATH);
+        let mut dirs: Vec<PathBuf> = Vec::new();
+        // DT_RUNPATH makes the linker ignore DT_RPATH, its own and the loaders'.
+        if runpath.is_empty() {
+            dirs.extend(
+                object
+                    .search_dirs(DT_RPATH)
+                    .iter()
+                    .map(|dir| expand(dir, origin, self.is_64bit)),
+            );
+            dirs.extend(self.inherited_rpath.iter().cloned());
+        } else {
+            dirs.extend(runpath.iter().map(|dir| expand(dir, origin, self.is_64bit)));
+        }
+        dirs.extend(self.defaults.iter().cloned());
+        dirs.iter().find_map(|dir| {
+            let candidate = dir.join(name).display().to_string();
+            let loader = Loader::open(&candidate).ok()?;
+            if !self.compatible(&loader) {
+                debug!("{}: skipped, not for the same machine", candidate);
+                return None;
+            }
+            Some((candidate, loader))
+        })
+    }
+
+    /// Adds the DT_NEEDED libraries of `object`, and theirs, below `parent`.
+    fn visit(&mut self, object: &Loader, path: &str, parent: usize, depth: usize) {
+        let origin = Path::new(path).parent().unwrap_or(Path::new("."));
+        let rpath: Vec<PathBuf> = object
+            .search_dirs(DT_RPATH)
+            .iter()
+            .map(|dir| expand(dir, origin, self.is_64bit))
+            .collect();
+        for name in object.needed_libraries() {
+            let found = self.find(&name, object, origin);
+            let key = match &found {
+                Some((path, _)) => path.clone(),
+                None => format!("{} (not found)", name),
+            };
+            let node = self.graph.node(&key, &name, None);
+            self.graph.edge(parent, node);
+            let repeated = !self.seen.insert(key);
+            self.lines.push(Dependency {
+                depth,
+                name,
+                path: found.as_ref().map(|(path, _)| path.clone()),
+                repeated,
+            });
+            if let (Some((path, library)), false) = (found, repeated) {
+                let max_depth = limits::get().max_depth;
+                if depth >= max_depth && !library.needed_libraries().is_empty() {
+                    self.too_deep.get_or_insert(LimitExceeded {
+                        limit: "--max-depth",
+                        subject: format!("the dependency chain through {}", path),
+                        found: depth as u64 + 1,
+                        max: max_depth as u64,
+                        unit: "levels",
+                    });
+                    continue;
+                }
+                let inherited = self.inherited_rpath.len();
+                self.inherited_rpath.extend(rpath.iter().cloned());
+                self.visit(&library, &path, node, depth + 1);
+                self.inherited_rpath.truncate(inherited);
+            }
+        }
+    }
+}
+
+/// `deps FILE...`: the tree of shared libraries each FILE loads, found through
+/// DT_RUNPATH, DT_RPATH and the default directories like the dynamic linker
+/// would, with libraries met before marked rather than expanded again. With
+/// `--graph` all trees are printed as one graph instead. Returns the files that
+/// cannot be read or miss libraries.
+pub fn display_deps(paths: &[String], graph_format: Option<&str>) -> Vec<(String, String)> {
+    let defaults = default_dirs();
+    let mut failures = Vec::new();
+    let mut graph = Graph::default();
+    for path in paths {
+        let loader = match Loader::open(path) {
+            Ok(loader) if loader.is_elf() && loader.header_truncation().is_none() => loader,
+            Ok(loader) => {
+                failures.push((path.clone(), filetype::not_elf(loader.bytes())));
+                continue;
+            }
+            Err(error) => {
+                failures.push((path.clone(), error.to_string()));
+                continue;
+            }
+        };
+        let label = Path::new(path)
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.clone());
+        let root = graph.node(path, &label, None);
+        let mut resolver = Resolver {
+            defaults: &defaults,
+            is_64bit: loader.is_64bit(),
+            machine: loader.raw_e_machine(),
+            inherited_rpath: Vec::new(),
+            seen: HashSet::new(),
+            lines: Vec::new(),
+            too_deep: None,
+            graph: &mut graph,
+        };
+        resolver.visit(&loader, path, root, 1);
+        if let Some(exceeded) = resolver.too_deep {
+            failures.push((path.clone(), exceeded.to_string()));
+        }
+        let lines = resolver.lines;
+        let missing = lines.iter().filter(|line| line.path.is_none()).count();
+        if missing > 0 {
+            failures.push((
+                path.clone(),
+                format!("{} needed libraries not found", missing),
+            ));
+        }
+        if graph_format.is_some() {
+            continue;
+        }
+        output::begin(path);
+        display_line(
+            "Libraries",
+            &lines
+                .iter()
+                .filter(|line| line.path.is_some() && !line.repeated)
+                .count()
+                .to_string(),
+        );
+        display_line("Not Found", &missing.to_string());
+        for line in &lines {
+            let location = match (&line.path, line.repeated) {
+                (None, _) => "not found".to_string(),
+                (Some(path), false) => path.clone(),
+                (Some(path), true) => format!("{} (listed above)", path),
+            };
+            output::item(line.depth, &format!("{} => {}", line.name, location));
+        }
+        output::end();
+    }
+    if let Some(format) = graph_format {
+        graph.print(format);
+    }
+    failures
+}
@@ -0,0 +1,101 @@
+use crate::dynamic::{DF_1_NOOPEN, DF_1_PIE, DF_STATIC_TLS, DT_FLAGS, DT_FLAGS_1};
+use crate::output;
+use crate::section::SHF_ALLOC;
+use crate::segment::PT_INTERP;
+use crate::{display_line, Loader};
+
+const ET_EXEC: u16 = 2;
+const ET_DYN: u16 = 3;
+
+/// Relocations that resolve a TLS variable to a fixed offset from the thread pointer
+/// (the initial-exec model), which needs room in the static TLS block.
+fn is_static_tls_relocation(name: &str) -> bool {
+    name.contains("_TPOFF") || name.contains("_TPREL")
+}
+
+impl Loader {
+    /// Names of the dynamic relocations that use the initial-exec TLS model.
+    pub fn static_tls_relocations(&self) -> Vec<String> {
+        let sections = self.section_headers();
+        let mut found: Vec<String> = self
+            .relocations()
+            .into_iter()
+            .filter(|reloc| {
+                sections.iter().any(|section| {
+                    section.name == reloc.section && section.sh_flags & SHF_ALLOC != 0
+                })
+            })
+            .map(|reloc| self.relocation_type_name(reloc.r_type))
+            .filter(|name| is_static_tls_relocation(name))
+            .collect();
+        found.sort();
+        found.dedup();
+        found
+    }
+
+    /// Reasons `dlopen` would refuse this object, or may fail on it at run time.
+    /// The first list holds certain failures, the second likely ones.
+    pub fn dlopen_problems(&self) -> (Vec<String>, Vec<String>) {
+        let mut fatal = Vec::new();
+        let mut risky = Vec::new();
+        let flags = self.dynamic_value(DT_FLAGS).unwrap_or(0);
+        let flags_1 = self.dynamic_value(DT_FLAGS_1).unwrap_or(0);
+
+        match self.raw_e_type() {
+            ET_DYN => {}
+            ET_EXEC => fatal.push("not a shared object (ET_EXEC executable)".to_string()),
+            other => fatal.push(format!("not a shared object (e_type {})", other)),
+        }
+        if self.dynamic_entries().is_empty() {
+            fatal.push("no dynamic section".to_string());
+        }
+        if flags_1 & DF_1_NOOPEN != 0 {
+            fatal.push("DF_1_NOOPEN is set, dlopen refuses the object".to_string());
+        }
+        if flags_1 & DF_1_PIE != 0 || self.program_header_by_type(PT_INTERP).is_some() {
+            fatal.push(
+                "has an interpreter (PT_INTERP) or DF_1_PIE, so it is a position-independent \
+                 executable; glibc 2.30 and later refuse to dlopen those"
+                    .to_string(),
+            );
+        }
+
+        let tls = self.static_tls_relocations();
+        if flags & DF_STATIC_TLS != 0 || !tls.is_empty() {
+            let detail = if tls.is_empty() {
+                String::new()
+            } else {
+                format!(" ({})", tls.join(", "))
+            };
+            risky.push(format!(
+                "uses initial-exec TLS{}; loading it late can fail with \
+                 \"cannot allocate memory in static TLS block\"",
+                detail
+            ));
+        }
+        if self.has_textrel_flag() {
+            risky.push("has text relocations, refused where W^X is enforced".to_string());
+        }
+        (fatal, risky)
+    }
+}
+
+pub fn display_dlopen(path: &str, loader: &Loader) {
+    let (fatal, risky) = loader.dlopen_problems();
+    output::begin(path);
+    let verdict = if !fatal.is_empty() {
+        "No"
+    } else if !risky.is_empty() {
+        "Yes, with risks"
+    } else {
+        "Yes"
+    };
+    display_line("dlopen-able", verdict);
+    for problem in &fatal {
+        output::item(1, &format!("error: {}", problem));
+    }
+    for problem in &risky {
+        output::item(1, &format!("warning: {}", problem));
+    }
+    output::end();
+}
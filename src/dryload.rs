@@ -0,0 +1,519 @@
+use std::fs;
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+
+use crate::brief::interpreter;
+use crate::finding::{self, Finding, Severity};
+use crate::output;
+use crate::segment::{ProgramHeader, PF_W, PF_X, PT_GNU_STACK, PT_INTERP, PT_LOAD};
+use crate::{display_line, Loader};
+
+/// The checks behind the `--report` findings of `dry-load`.
+const DRY_LOAD_CHECKS: &[&str] = &["dry-load/exec", "dry-load/layout"];
+
+const ET_EXEC: u16 = 2;
+const ET_DYN: u16 = 3;
+
+/// `ELF_MIN_ALIGN`, the page size the kernel maps segments with. Kernels with
+/// larger pages, as some arm64 and ppc64 ones have, are stricter.
+const PAGE_SIZE: u64 = 0x1000;
+
+/// The largest program header table `elf_read_phdrs` accepts.
+const MAX_PHDR_TABLE: usize = 65536;
+
+/// `PATH_MAX`, the longest PT_INTERP the kernel reads.
+const PATH_MAX: u64 = 4096;
+
+/// The default `vm.mmap_min_addr`, used when `/proc` does not say.
+const MMAP_MIN_ADDR: u64 = 0x10000;
+
+/// The end of user space, with 4-level page tables on 64-bit kernels and for
+/// compat tasks on 32-bit ones.
+const TASK_SIZE_64: u64 = (1 << 47) - PAGE_SIZE;
+const TASK_SIZE_32: u64 = 0xffff_e000;
+
+/// The e_machine the running kernel executes natively, by `std::env::consts::ARCH`.
+const HOST_MACHINES: &[(&str, u16)] = &[
+    ("x86", 3),
+    ("x86_64", 62),
+    ("arm", 40),
+    ("aarch64", 183),
+    ("mips", 8),
+    ("mips64", 8),
+    ("powerpc", 20),
+    ("powerpc64", 21),
+    ("s390x", 22),
+    ("riscv64", 243),
+    ("loongarch64", 258),
+];
+
+/// A step of `execve` the file does not get past, with the errno it returns.
+/// Steps past the point of no return, where the old program is gone, cannot
+/// return one and kill the new process with `SIGSEGV` instead.
+pub struct ExecError {
+    pub errno: &'static str,
+    pub reason: String,
+}
+
+/// A mapping the kernel makes for a PT_LOAD segment: the file-backed pages,
+/// or the anonymous zero pages of its bss.
+pub struct Mapping {
+    pub start: u64,
+    pub end: u64,
+    pub permissions: String,
+    /// The page-aligned file offset mapped, `None` for anonymous memory.
+    pub offset: Option<u64>,
+}
+
+/// What the kernel's ELF loader would do with a file.
+pub struct DryLoad {
+    /// The reasons exec fails, the first being the one it returns.
+    pub errors: Vec<ExecError>,
+    /// What loads but is likely wrong, such as an entry point that is not
+    /// executable.
+    pub warnings: Vec<String>,
+    pub mappings: Vec<Mapping>,
+    /// Where the heap starts, past the last segment.
+    pub brk: Option<u64>,
+    /// The interpreter and how it was resolved.
+    pub interpreter: Option<(String, String)>,
+}
+
+/// A step failing with `errno` after the point of no return.
+fn past_no_return(errno: &str, reason: String) -> ExecError {
+    ExecError {
+        errno: "SIGSEGV",
+        reason: format!("{} after the point of no return: {}", errno, reason),
+    }
+}
+
+fn page_down(address: u64) -> u64 {
+    address & !(PAGE_SIZE - 1)
+}
+
+fn page_up(address: u64) -> u64 {
+    page_down(address.saturating_add(PAGE_SIZE - 1))
+}
+
+/// `vm.mmap_min_addr` of this machine, below which fixed mappings are refused.
+fn mmap_min_addr() -> u64 {
+    fs::read_to_string("/proc/sys/vm/mmap_min_addr")
+        .ok()
+        .and_then(|text| text.trim().parse().ok())
+        .unwrap_or(MMAP_MIN_ADDR)
+}
+
+fn host_machine() -> Option<u16> {
+    HOST_MACHINES
+        .iter()
+        .find(|(arch, _)| *arch == std::env::consts::ARCH)
+        .map(|&(_, machine)| machine)
+}
+
+/// Whether the file at `path` may be executed, when it is a local file.
+#[cfg(unix)]
+fn is_executable(path: &str) -> Option<bool> {
+    let metadata = fs::metadata(path).ok()?;
+    Some(metadata.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(_path: &str) -> Option<bool> {
+    None
+}
+
+/// What `load_elf_interp` checks of the interpreter, resolved on this machine.
+fn check_interpreter(loader: &Loader, name: &str, errors: &mut Vec<ExecError>) -> String {
+    let mut error = |errno, reason: String| errors.push(ExecError { errno, reason });
+    if !Path::new(name).is_file() {
+        error(
+            "ENOENT",
+            format!(
+                "the interpreter {} does not exist, which exec reports as if the file itself were missing",
+                name
+            ),
+        );
+        return "not found".to_string();
+    }
+    let interp = match Loader::open(name) {
+        Ok(interp) if interp.is_elf() && interp.header_truncation().is_none() => interp,
+        Ok(_) => {
+            error(
+                "ELIBBAD",
+                format!("the interpreter {} is not an ELF file", name),
+            );
+            return "not an ELF file".to_string();
+        }
+        Err(open_error) => {
+            error(
+                "EACCES",
+                format!("the interpreter {}: {}", name, open_error),
+            );
+            return open_error.to_string();
+        }
+    };
+    if interp.raw_e_machine() != loader.raw_e_machine() || interp.is_64bit() != loader.is_64bit() {
+        error(
+            "ELIBBAD",
+            format!(
+                "the interpreter {} is for {} {}-bit, the file for {} {}-bit",
+                name,
+                interp.machine_name(),
+                if interp.is_64bit() { 64 } else { 32 },
+                loader.machine_name(),
+                if loader.is_64bit() { 64 } else { 32 }
+            ),
+        );
+        return "for another machine".to_string();
+    }
+    // load_elf_interp runs once the old program is gone.
+    if !matches!(interp.raw_e_type(), ET_EXEC | ET_DYN) {
+        errors.push(past_no_return(
+            "EINVAL",
+            format!("the interpreter {} is neither ET_EXEC nor ET_DYN", name),
+        ));
+        return "not loadable".to_string();
+    }
+    let span = interp
+        .program_headers()
+        .iter()
+        .filter(|header| header.p_type == PT_LOAD)
+        .map(|header| page_up(header.end_vaddr()))
+        .max()
+        .unwrap_or(0);
+    format!("found, {:#x} bytes mapped", span)
+}
+
+/// Checks the PT_LOAD segments the way `load_elf_binary` maps them and
+/// returns the mappings made.
+fn map_segments(
+    loader: &Loader,
+    loads: &[ProgramHeader],
+    errors: &mut Vec<ExecError>,
+    warnings: &mut Vec<String>,
+) -> Vec<Mapping> {
+    let task_size = if loader.is_64bit() {
+        TASK_SIZE_64
+    } else {
+        TASK_SIZE_32
+    };
+    let min_addr = mmap_min_addr();
+    let mut mappings = Vec::new();
+    for (index, segment) in loads.iter().enumerate() {
+        let name = format!("PT_LOAD #{} at {:#x}", index, segment.p_vaddr);
+        let mut error = |errno, reason: String| errors.push(past_no_return(errno, reason));
+        if segment.p_filesz > segment.p_memsz {
+            error(
+                "EINVAL",
+                format!(
+                    "{} has p_filesz {:#x} larger than p_memsz {:#x}",
+                    name, segment.p_filesz, segment.p_memsz
+                ),
+            );
+        }
+        if segment.p_memsz > task_size || task_size - segment.p_memsz < segment.p_vaddr {
+            error(
+                "EINVAL",
+                format!("{} ends past the end of user space {:#x}", name, task_size),
+            );
+        }
+        // mmap needs the file offset on a page boundary once the address is.
+        if segment.p_vaddr % PAGE_SIZE != segment.p_offset % PAGE_SIZE {
+            error(
+                "EINVAL",
+                format!(
+                    "{} has p_offset {:#x} and p_vaddr {:#x} at different page offsets",
+                    name, segment.p_offset, segment.p_vaddr
+                ),
+            );
+        }
+        if loader.raw_e_type() == ET_EXEC && page_down(segment.p_vaddr) < min_addr {
+            error(
+                "EPERM",
+                format!("{} is fixed below vm.mmap_min_addr {:#x}", name, min_addr),
+            );
+        }
+        if segment.p_align > 1 && !segment.p_align.is_power_of_two() {
+            warnings.push(format!(
+                "{} has p_align {:#x}, not a power of two, which the kernel ignores",
+                name, segment.p_align
+            ));
+        }
+        if segment.p_flags & (PF_W | PF_X) == PF_W | PF_X {
+            warnings.push(format!(
+                "{} is writable and executable, which SELinux execmem and PaX refuse",
+                name
+            ));
+        }
+        if index > 0 && segment.p_vaddr < loads[index - 1].p_vaddr {
+            warnings.push(format!(
+                "{} comes after a segment at a higher address; the kernel sizes the mapping \
+                 from the first and last PT_LOAD",
+                name
+            ));
+        }
+        if let Some(previous) = mappings.last().map(|mapping: &Mapping| mapping.end) {
+            if page_down(segment.p_vaddr) < previous {
+                warnings.push(format!(
+                    "{} shares a page with the segment before it, whose permissions are overwritten",
+                    name
+                ));
+            }
+        }
+
+        let file_end = page_up(segment.p_vaddr.saturating_add(segment.p_filesz));
+        let memory_end = page_up(segment.end_vaddr());
+        if segment.p_filesz > 0 {
+            mappings.push(Mapping {
+                start: page_down(segment.p_vaddr),
+                end: file_end,
+                permissions: segment.permissions(),
+                offset: Some(page_down(segment.p_offset)),
+            });
+        }
+        if memory_end > file_end || segment.p_filesz == 0 {
+            mappings.push(Mapping {
+                start: if segment.p_filesz == 0 {
+                    page_down(segment.p_vaddr)
+                } else {
+                    file_end
+                },
+                end: memory_end,
+                permissions: segment.permissions(),
+                offset: None,
+            });
+        }
+    }
+    mappings
+}
+
+/// The checks of the ELF header and program header table, after which the
+/// kernel reads the segments. Returns whether the file gets that far.
+fn check_header(loader: &Loader, errors: &mut Vec<ExecError>) -> bool {
+    let mut error = |errno, reason: String| {
+        errors.push(ExecError { errno, reason });
+        false
+    };
+    match loader.raw_e_type() {
+        ET_EXEC | ET_DYN => {}
+        1 => {
+            return error(
+                "ENOEXEC",
+                "relocatable objects are linked, not executed".to_string(),
+            )
+        }
+        4 => return error("ENOEXEC", "core files cannot be executed".to_string()),
+        e_type => return error("ENOEXEC", format!("e_type {} is not loadable", e_type)),
+    }
+    let phentsize = if loader.is_64bit() { 56 } else { 32 };
+    if loader.phentsize() != phentsize {
+        return error(
+            "ENOEXEC",
+            format!(
+                "e_phentsize is {}, the kernel expects {}",
+                loader.phentsize(),
+                phentsize
+            ),
+        );
+    }
+    // The kernel takes e_phnum as is, without the PN_XNUM escape.
+    let table = loader.raw_e_phnum() * loader.phentsize();
+    if table == 0 || table > MAX_PHDR_TABLE {
+        return error(
+            "ENOEXEC",
+            format!(
+                "the program header table is {} bytes, the kernel reads 1 to {}",
+                table, MAX_PHDR_TABLE
+            ),
+        );
+    }
+    if loader.program_table_end() > loader.bytes().len() {
+        return error(
+            "EIO",
+            "the program header table runs past the end of the file".to_string(),
+        );
+    }
+    true
+}
+
+/// Walks the checks of the kernel's `load_elf_binary` over the file at `path`.
+pub fn dry_load(path: &str, loader: &Loader) -> DryLoad {
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
+    if is_executable(path) == Some(false) {
+        errors.push(ExecError {
+            errno: "EACCES",
+            reason: "the file has no execute permission".to_string(),
+        });
+    }
+    if !check_header(loader, &mut errors) {
+        return DryLoad {
+            errors,
+            warnings,
+            mappings: Vec::new(),
+            brk: None,
+            interpreter: None,
+        };
+    }
+    let mut error = |errno, reason: &str| {
+        errors.push(ExecError {
+            errno,
+            reason: reason.to_string(),
+        })
+    };
+
+    let headers = loader.program_headers();
+    let mut interp = None;
+    if let Some(segment) = headers.iter().find(|header| header.p_type == PT_INTERP) {
+        let end = segment.p_offset.saturating_add(segment.p_filesz);
+        let data = loader
+            .bytes()
+            .get(segment.p_offset as usize..end.min(usize::MAX as u64) as usize);
+        if segment.p_filesz > PATH_MAX || segment.p_filesz < 2 {
+            error(
+                "ENOEXEC",
+                &format!(
+                    "PT_INTERP is {} bytes, not 2 to {}",
+                    segment.p_filesz, PATH_MAX
+                ),
+            );
+        } else if data.is_none() {
+            error("EIO", "PT_INTERP runs past the end of the file");
+        } else if data.and_then(|data| data.last()) != Some(&0) {
+            error("ENOEXEC", "PT_INTERP is not NUL-terminated");
+        } else if let Some(name) = interpreter(loader) {
+            let found = check_interpreter(loader, &name, &mut errors);
+            interp = Some((name, found));
+        }
+    }
+
+    let loads: Vec<ProgramHeader> = headers
+        .iter()
+        .filter(|header| header.p_type == PT_LOAD)
+        .cloned()
+        .collect();
+    let mappings = map_segments(loader, &loads, &mut errors, &mut warnings);
+    if matches!(loader.raw_e_type(), ET_EXEC | ET_DYN) {
+        let entry = loader.raw_e_entry();
+        match loads
+            .iter()
+            .find(|segment| entry >= segment.p_vaddr && entry < segment.end_vaddr())
+        {
+            None => warnings.push(format!(
+                "e_entry {:#x} is in no PT_LOAD: exec succeeds, then the process gets SIGSEGV",
+                entry
+            )),
+            Some(segment) if segment.p_flags & PF_X == 0 => warnings.push(format!(
+                "e_entry {:#x} is in a {} segment: exec succeeds, then the process gets SIGSEGV",
+                entry,
+                segment.permissions()
+            )),
+            Some(_) => {}
+        }
+    }
+    if let Some(stack) = loader.program_header_by_type(PT_GNU_STACK) {
+        if stack.p_flags & PF_X != 0 {
+            warnings.push("PT_GNU_STACK asks for an executable stack".to_string());
+        }
+    }
+    if host_machine().is_some_and(|machine| machine != loader.raw_e_machine()) {
+        warnings.push(format!(
+            "this {} host runs {} files only through binfmt_misc, such as qemu-user",
+            std::env::consts::ARCH,
+            loader.machine_name()
+        ));
+    }
+
+    DryLoad {
+        brk: mappings.iter().map(|mapping| mapping.end).max(),
+        errors,
+        warnings,
+        mappings,
+        interpreter: interp,
+    }
+}
+
+/// `dry-load`: how the kernel would map each file and start it, or the errno
+/// `execve` would fail with and why. Files that would not exec fail.
+pub fn display_dry_load(targets: &[(String, Loader)]) -> Vec<(String, String)> {
+    let mut failures = Vec::new();
+    for (label, loader) in targets {
+        let load = dry_load(label, loader);
+        output::begin(label);
+        match load.errors.first() {
+            Some(first) if first.errno == "SIGSEGV" => {
+                display_line("Exec", "Succeeds, then SIGSEGV while loading")
+            }
+            Some(first) => display_line("Exec", &format!("Fails with {}", first.errno)),
+            None => display_line("Exec", "Succeeds"),
+        }
+        for error in &load.errors {
+            output::item(1, &format!("{:<8} {}", error.errno, error.reason));
+        }
+        let bias = match loader.raw_e_type() {
+            ET_EXEC => "None, ET_EXEC maps at its own addresses",
+            ET_DYN if load.interpreter.is_some() => {
+                "Randomized from ELF_ET_DYN_BASE, addresses below are relative"
+            }
+            ET_DYN => "Randomized in the mmap area, addresses below are relative",
+            _ => "-",
+        };
+        display_line("Load Bias", bias);
+        display_line("Mappings", &load.mappings.len().to_string());
+        for mapping in &load.mappings {
+            let backing = match mapping.offset {
+                Some(offset) => format!("file offset {:#x}", offset),
+                None => "anonymous (bss)".to_string(),
+            };
+            output::item(
+                1,
+                &format!(
+                    "{:#014x}-{:#014x} {} {}",
+                    mapping.start, mapping.end, mapping.permissions, backing
+                ),
+            );
+        }
+        match load.brk {
+            Some(brk) => display_line("Program Break", &format!("{:#x}", brk)),
+            None => display_line("Program Break", "-"),
+        }
+        match &load.interpreter {
+            Some((name, found)) => display_line("Interpreter", &format!("{} ({})", name, found)),
+            None => display_line("Interpreter", "None"),
+        }
+        display_line("Entry Point", &format!("{:#x}", loader.raw_e_entry()));
+        display_line("Warnings", &load.warnings.len().to_string());
+        for warning in &load.warnings {
+            output::item(1, warning);
+        }
+        output::end();
+
+        let findings: Vec<Finding> = load
+            .errors
+            .iter()
+            .map(|error| Finding {
+                severity: Severity::Error,
+                check: "dry-load/exec".to_string(),
+                message: format!("{}: {}", error.errno, error.reason),
+            })
+            .chain(load.warnings.iter().map(|warning| Finding {
+                severity: Severity::Warning,
+                check: "dry-load/layout".to_string(),
+                message: warning.clone(),
+            }))
+            .collect();
+        finding::record(label, DRY_LOAD_CHECKS, &findings);
+        if let Some(first) = load.errors.first() {
+            failures.push((
+                label.clone(),
+                if first.errno == "SIGSEGV" {
+                    "the new process would get SIGSEGV while loading".to_string()
+                } else {
+                    format!("execve would fail with {}", first.errno)
+                },
+            ));
+        }
+    }
+    failures
+}
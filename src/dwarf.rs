@@ -0,0 +1,502 @@
+use std::borrow::Cow;
+use std::io::Read;
+
+use flate2::read::ZlibDecoder;
+use log::debug;
+use ruzstd::decoding::StreamingDecoder;
+
+use crate::attributes::read_uleb128;
+use crate::Loader;
+
+/// Set on sections whose contents start with an `Elf_Chdr`.
+const SHF_COMPRESSED: u64 = 0x800;
+const ELFCOMPRESS_ZLIB: u32 = 1;
+const ELFCOMPRESS_ZSTD: u32 = 2;
+
+pub const DW_UT_COMPILE: u8 = 1;
+pub const DW_UT_TYPE: u8 = 2;
+pub const DW_UT_SKELETON: u8 = 4;
+pub const DW_UT_SPLIT_COMPILE: u8 = 5;
+pub const DW_UT_SPLIT_TYPE: u8 = 6;
+
+pub const DW_AT_NAME: u64 = 0x03;
+pub const DW_AT_COMP_DIR: u64 = 0x1b;
+const DW_AT_STR_OFFSETS_BASE: u64 = 0x72;
+pub const DW_AT_DWO_NAME: u64 = 0x76;
+pub const DW_AT_GNU_DWO_NAME: u64 = 0x2130;
+pub const DW_AT_GNU_DWO_ID: u64 = 0x2131;
+
+const DW_FORM_ADDR: u64 = 0x01;
+const DW_FORM_BLOCK2: u64 = 0x03;
+const DW_FORM_BLOCK4: u64 = 0x04;
+const DW_FORM_DATA2: u64 = 0x05;
+const DW_FORM_DATA4: u64 = 0x06;
+const DW_FORM_DATA8: u64 = 0x07;
+const DW_FORM_STRING: u64 = 0x08;
+const DW_FORM_BLOCK: u64 = 0x09;
+const DW_FORM_BLOCK1: u64 = 0x0a;
+const DW_FORM_DATA1: u64 = 0x0b;
+const DW_FORM_FLAG: u64 = 0x0c;
+const DW_FORM_SDATA: u64 = 0x0d;
+const DW_FORM_STRP: u64 = 0x0e;
+const DW_FORM_UDATA: u64 = 0x0f;
+const DW_FORM_REF_ADDR: u64 = 0x10;
+const DW_FORM_REF1: u64 = 0x11;
+const DW_FORM_REF2: u64 = 0x12;
+const DW_FORM_REF4: u64 = 0x13;
+const DW_FORM_REF8: u64 = 0x14;
+const DW_FORM_REF_UDATA: u64 = 0x15;
+const DW_FORM_INDIRECT: u64 = 0x16;
+const DW_FORM_SEC_OFFSET: u64 = 0x17;
+const DW_FORM_EXPRLOC: u64 = 0x18;
+const DW_FORM_FLAG_PRESENT: u64 = 0x19;
+const DW_FORM_STRX: u64 = 0x1a;
+const DW_FORM_ADDRX: u64 = 0x1b;
+const DW_FORM_REF_SUP4: u64 = 0x1c;
+const DW_FORM_STRP_SUP: u64 = 0x1d;
+const DW_FORM_DATA16: u64 = 0x1e;
+const DW_FORM_LINE_STRP: u64 = 0x1f;
+const DW_FORM_REF_SIG8: u64 = 0x20;
+const DW_FORM_IMPLICIT_CONST: u64 = 0x21;
+const DW_FORM_LOCLISTX: u64 = 0x22;
+const DW_FORM_RNGLISTX: u64 = 0x23;
+const DW_FORM_REF_SUP8: u64 = 0x24;
+const DW_FORM_STRX1: u64 = 0x25;
+const DW_FORM_STRX2: u64 = 0x26;
+const DW_FORM_STRX3: u64 = 0x27;
+const DW_FORM_STRX4: u64 = 0x28;
+const DW_FORM_ADDRX1: u64 = 0x29;
+const DW_FORM_ADDRX2: u64 = 0x2a;
+const DW_FORM_ADDRX3: u64 = 0x2b;
+const DW_FORM_ADDRX4: u64 = 0x2c;
+const DW_FORM_GNU_ADDR_INDEX: u64 = 0x1f01;
+const DW_FORM_GNU_STR_INDEX: u64 = 0x1f02;
+const DW_FORM_GNU_REF_ALT: u64 = 0x1f20;
+const DW_FORM_GNU_STRP_ALT: u64 = 0x1f21;
+
+/// The value of an attribute of a unit's root DIE, as far as the reports need it.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AttributeValue {
+    Text(String),
+    Number(u64),
+    /// A string kept elsewhere: in a `.dwo` (`DW_FORM_GNU_str_index`) or the
+    /// `dwz` supplementary file.
+    Unresolved,
+    /// Blocks, expressions and references.
+    Other,
+}
+
+/// The header of a unit in `.debug_info` and the attributes of its root DIE.
+pub struct Unit {
+    pub offset: usize,
+    pub version: u16,
+    /// A `DW_UT_*`; DWARF 4 compile units count as `DW_UT_COMPILE`.
+    pub unit_type: u8,
+    /// The split unit this skeleton stands for, or the id of a split unit,
+    /// from the DWARF 5 header.
+    pub dwo_id: Option<u64>,
+    pub attributes: Vec<(u64, AttributeValue)>,
+}
+
+impl Unit {
+    pub fn attribute(&self, at: u64) -> Option<&AttributeValue> {
+        self.attributes
+            .iter()
+            .find(|(name, _)| *name == at)
+            .map(|(_, value)| value)
+    }
+
+    /// A string attribute, if it was resolved.
+    pub fn text(&self, at: u64) -> Option<&str> {
+        match self.attribute(at)? {
+            AttributeValue::Text(text) => Some(text),
+            _ => None,
+        }
+    }
+}
+
+/// Reads the sections in the byte order of the file.
+struct Reader<'a> {
+    data: &'a [u8],
+    position: usize,
+    big_endian: bool,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+        let bytes = self
+            .data
+            .get(self.position..self.position.checked_add(len)?)?;
+        self.position += len;
+        Some(bytes)
+    }
+
+    fn unsigned(&mut self, len: usize) -> Option<u64> {
+        let bytes = self.bytes(len)?;
+        let fold = |value: u64, byte: &u8| value << 8 | u64::from(*byte);
+        Some(if self.big_endian {
+            bytes.iter().fold(0, fold)
+        } else {
+            bytes.iter().rev().fold(0, fold)
+        })
+    }
+
+    fn uleb(&mut self) -> Option<u64> {
+        let (value, len) = read_uleb128(self.data.get(self.position..)?)?;
+        self.position += len;
+        Some(value)
+    }
+
+    fn cstr(&mut self) -> Option<String> {
+        let rest = self.data.get(self.position..)?;
+        let end = rest.iter().position(|&byte| byte == 0)?;
+        self.position += end + 1;
+        Some(String::from_utf8_lossy(&rest[..end]).into_owned())
+    }
+}
+
+/// A string at `offset` of a string section.
+fn string_at(section: &[u8], offset: u64) -> Option<String> {
+    let rest = section.get(usize::try_from(offset).ok()?..)?;
+    let end = rest.iter().position(|&byte| byte == 0)?;
+    Some(String::from_utf8_lossy(&rest[..end]).into_owned())
+}
+
+/// An attribute as read, before string offsets are resolved.
+enum Raw {
+    Value(AttributeValue),
+    Strp(u64),
+    LineStrp(u64),
+    Strx(u64),
+}
+
+/// A unit as read, with the attributes that need a string section still raw.
+struct RawUnit {
+    unit: Unit,
+    raw: Vec<(u64, Raw)>,
+    /// The offset of the next unit.
+    next: usize,
+    /// 4 for 32-bit DWARF, 8 for 64-bit DWARF.
+    offset_size: usize,
+}
+
+impl Loader {
+    /// The contents of a DWARF section, decompressed from `SHF_COMPRESSED` or
+    /// the older `.zdebug_*` form when needed.
+    pub fn debug_section(&self, name: &str) -> Option<Cow<'_, [u8]>> {
+        if let Some(section) = self.section_by_name(name) {
+            let data = self.section_data(&section);
+            if section.sh_flags & SHF_COMPRESSED == 0 {
+                return Some(Cow::Borrowed(data));
+            }
+            let mut reader = Reader {
+                data,
+                position: 0,
+                big_endian: self.is_big_endian(),
+            };
+            let ch_type = reader.unsigned(4)?;
+            // Elf64_Chdr has a reserved word, then 64-bit size and alignment.
+            let header = if self.is_64bit() { 24 } else { 12 };
+            return decompress(ch_type as u32, data.get(header..)?, name).map(Cow::Owned);
+        }
+        let zname = format!(".z{}", name.strip_prefix('.')?);
+        let section = self.section_by_name(&zname)?;
+        // `ZLIB`, the size as a big-endian 64-bit number, then the zlib stream.
+        let data = self.section_data(&section);
+        if !data.starts_with(b"ZLIB") {
+            return None;
+        }
+        decompress(ELFCOMPRESS_ZLIB, data.get(12..)?, &zname).map(Cow::Owned)
+    }
+
+    /// The units of `.debug_info` (or `.debug_info.dwo` in split DWARF files)
+    /// with the attributes of their root DIE.
+    pub fn units(&self) -> Vec<Unit> {
+        let info_name = if self.section_by_name(".debug_info.dwo").is_some() {
+            ".debug_info.dwo"
+        } else {
+            ".debug_info"
+        };
+        let dwo = info_name.ends_with(".dwo");
+        let section = |name: &str| {
+            let name = if dwo {
+                format!("{}.dwo", name)
+            } else {
+                name.to_string()
+            };
+            self.debug_section(&name).unwrap_or_default()
+        };
+        let Some(info) = self.debug_section(info_name) else {
+            return Vec::new();
+        };
+        let abbrev = section(".debug_abbrev");
+        let strings = section(".debug_str");
+        let str_offsets = section(".debug_str_offsets");
+        let line_strings = self.debug_section(".debug_line_str").unwrap_or_default();
+        let mut units = Vec::new();
+        let mut offset = 0;
+        while offset < info.len() {
+            let mut reader = Reader {
+                data: &info,
+                position: offset,
+                big_endian: self.is_big_endian(),
+            };
+            let Some(RawUnit {
+                mut unit,
+                raw,
+                next,
+                offset_size,
+            }) = read_unit(&mut reader, &abbrev)
+            else {
+                debug!("{}: unreadable unit at {:#x}", info_name, offset);
+                break;
+            };
+            let base = unit
+                .attributes
+                .iter()
+                .find(|(at, _)| *at == DW_AT_STR_OFFSETS_BASE)
+                .and_then(|(_, value)| match value {
+                    AttributeValue::Number(base) => Some(*base),
+                    _ => None,
+                })
+                // Split units have no base; their offsets table starts after its header.
+                .unwrap_or(if unit.version >= 5 { 8 } else { 0 });
+            for (at, raw) in raw {
+                let value = match raw {
+                    Raw::Value(value) => value,
+                    Raw::Strp(offset) => string_at(&strings, offset)
+                        .map_or(AttributeValue::Unresolved, AttributeValue::Text),
+                    Raw::LineStrp(offset) => string_at(&line_strings, offset)
+                        .map_or(AttributeValue::Unresolved, AttributeValue::Text),
+                    Raw::Strx(index) => {
+                        let mut entry = Reader {
+                            data: &str_offsets,
+                            position: (base + index * offset_size as u64) as usize,
+                            big_endian: self.is_big_endian(),
+                        };
+                        entry
+                            .unsigned(offset_size)
+                            .and_then(|offset| string_at(&strings, offset))
+                            .map_or(AttributeValue::Unresolved, AttributeValue::Text)
+                    }
+                };
+                unit.attributes.push((at, value));
+            }
+            unit.offset = offset;
+            units.push(unit);
+            if next <= offset {
+                break;
+            }
+            offset = next;
+        }
+        units
+    }
+}
+
+fn decompress(ch_type: u32, data: &[u8], name: &str) -> Option<Vec<u8>> {
+    let mut contents = Vec::new();
+    let result = match ch_type {
+        ELFCOMPRESS_ZLIB => ZlibDecoder::new(data).read_to_end(&mut contents),
+        ELFCOMPRESS_ZSTD => match StreamingDecoder::new(data) {
+            Ok(mut decoder) => decoder.read_to_end(&mut contents),
+            Err(error) => {
+                debug!("{}: {}", name, error);
+                return None;
+            }
+        },
+        _ => {
+            debug!("{}: unknown compression type {}", name, ch_type);
+            return None;
+        }
+    };
+    match result {
+        Ok(_) => Some(contents),
+        Err(error) => {
+            debug!("{}: {}", name, error);
+            None
+        }
+    }
+}
+
+/// Reads a unit header and its root DIE.
+fn read_unit(reader: &mut Reader, abbrev: &[u8]) -> Option<RawUnit> {
+    let mut length = reader.unsigned(4)?;
+    let mut offset_size = 4;
+    if length == 0xffff_ffff {
+        length = reader.unsigned(8)?;
+        offset_size = 8;
+    }
+    let next = reader.position.checked_add(usize::try_from(length).ok()?)?;
+    let version = reader.unsigned(2)? as u16;
+    let (unit_type, address_size, abbrev_offset) = if version >= 5 {
+        let unit_type = reader.unsigned(1)? as u8;
+        let address_size = reader.unsigned(1)? as usize;
+        (unit_type, address_size, reader.unsigned(offset_size)?)
+    } else {
+        let abbrev_offset = reader.unsigned(offset_size)?;
+        (DW_UT_COMPILE, reader.unsigned(1)? as usize, abbrev_offset)
+    };
+    let mut dwo_id = None;
+    match unit_type {
+        DW_UT_SKELETON | DW_UT_SPLIT_COMPILE => dwo_id = Some(reader.unsigned(8)?),
+        DW_UT_TYPE | DW_UT_SPLIT_TYPE => {
+            reader.unsigned(8)?;
+            reader.unsigned(offset_size)?;
+        }
+        _ => {}
+    }
+    let mut unit = Unit {
+        offset: 0,
+        version,
+        unit_type,
+        dwo_id,
+        attributes: Vec::new(),
+    };
+    let code = reader.uleb()?;
+    let specs = abbreviation(abbrev, abbrev_offset, code, reader.big_endian)?;
+    let mut raw = Vec::new();
+    for (at, form, implicit) in specs {
+        let value = read_form(reader, form, implicit, offset_size, address_size, version)?;
+        match value {
+            Raw::Value(value) => unit.attributes.push((at, value)),
+            other => raw.push((at, other)),
+        }
+    }
+    if unit.dwo_id.is_none() {
+        if let Some(AttributeValue::Number(id)) = unit.attribute(DW_AT_GNU_DWO_ID) {
+            unit.dwo_id = Some(*id);
+        }
+    }
+    Some(RawUnit {
+        unit,
+        raw,
+        next,
+        offset_size,
+    })
+}
+
+/// The attribute specifications of abbreviation `code` in the table at `offset`.
+fn abbreviation(
+    abbrev: &[u8],
+    offset: u64,
+    code: u64,
+    big_endian: bool,
+) -> Option<Vec<(u64, u64, i64)>> {
+    let mut reader = Reader {
+        data: abbrev,
+        position: usize::try_from(offset).ok()?,
+        big_endian,
+    };
+    loop {
+        let entry = reader.uleb()?;
+        if entry == 0 {
+            return None;
+        }
+        let _tag = reader.uleb()?;
+        reader.unsigned(1)?;
+        let mut specs = Vec::new();
+        loop {
+            let (at, form) = (reader.uleb()?, reader.uleb()?);
+            if at == 0 && form == 0 {
+                break;
+            }
+            let implicit = if form == DW_FORM_IMPLICIT_CONST {
+                let (value, len) = read_uleb128(reader.data.get(reader.position..)?)?;
+                reader.position += len;
+                // Sign-extend the SLEB128 value.
+                let bits = 7 * len as u32;
+                if bits < 64 && value & (1 << (bits - 1)) != 0 {
+                    (value | !0 << bits) as i64
+                } else {
+                    value as i64
+                }
+            } else {
+                0
+            };
+            specs.push((at, form, implicit));
+        }
+        if entry == code {
+            return Some(specs);
+        }
+    }
+}
+
+fn read_form(
+    reader: &mut Reader,
+    form: u64,
+    implicit: i64,
+    offset_size: usize,
+    address_size: usize,
+    version: u16,
+) -> Option<Raw> {
+    let number = |value: u64| Some(Raw::Value(AttributeValue::Number(value)));
+    let skip = |reader: &mut Reader, len: usize| {
+        reader.bytes(len)?;
+        Some(Raw::Value(AttributeValue::Other))
+    };
+    match form {
+        DW_FORM_ADDR => number(reader.unsigned(address_size)?),
+        DW_FORM_DATA1 | DW_FORM_REF1 | DW_FORM_FLAG => number(reader.unsigned(1)?),
+        DW_FORM_DATA2 | DW_FORM_REF2 => number(reader.unsigned(2)?),
+        DW_FORM_DATA4 | DW_FORM_REF4 | DW_FORM_REF_SUP4 => number(reader.unsigned(4)?),
+        DW_FORM_DATA8 | DW_FORM_REF8 | DW_FORM_REF_SIG8 | DW_FORM_REF_SUP8 => {
+            number(reader.unsigned(8)?)
+        }
+        DW_FORM_DATA16 => skip(reader, 16),
+        DW_FORM_UDATA
+        | DW_FORM_REF_UDATA
+        | DW_FORM_ADDRX
+        | DW_FORM_LOCLISTX
+        | DW_FORM_RNGLISTX
+        | DW_FORM_GNU_ADDR_INDEX => number(reader.uleb()?),
+        DW_FORM_SDATA => {
+            reader.uleb()?;
+            Some(Raw::Value(AttributeValue::Other))
+        }
+        DW_FORM_STRING => Some(Raw::Value(AttributeValue::Text(reader.cstr()?))),
+        DW_FORM_STRP => Some(Raw::Strp(reader.unsigned(offset_size)?)),
+        DW_FORM_LINE_STRP => Some(Raw::LineStrp(reader.unsigned(offset_size)?)),
+        DW_FORM_STRX => Some(Raw::Strx(reader.uleb()?)),
+        DW_FORM_STRX1 => Some(Raw::Strx(reader.unsigned(1)?)),
+        DW_FORM_STRX2 => Some(Raw::Strx(reader.unsigned(2)?)),
+        DW_FORM_STRX3 => Some(Raw::Strx(reader.unsigned(3)?)),
+        DW_FORM_STRX4 => Some(Raw::Strx(reader.unsigned(4)?)),
+        DW_FORM_ADDRX1 => number(reader.unsigned(1)?),
+        DW_FORM_ADDRX2 => number(reader.unsigned(2)?),
+        DW_FORM_ADDRX3 => number(reader.unsigned(3)?),
+        DW_FORM_ADDRX4 => number(reader.unsigned(4)?),
+        DW_FORM_SEC_OFFSET => number(reader.unsigned(offset_size)?),
+        DW_FORM_REF_ADDR if version <= 2 => number(reader.unsigned(address_size)?),
+        DW_FORM_REF_ADDR | DW_FORM_STRP_SUP | DW_FORM_GNU_REF_ALT | DW_FORM_GNU_STRP_ALT => {
+            reader.unsigned(offset_size)?;
+            Some(Raw::Value(AttributeValue::Unresolved))
+        }
+        DW_FORM_GNU_STR_INDEX => {
+            reader.uleb()?;
+            Some(Raw::Value(AttributeValue::Unresolved))
+        }
+        DW_FORM_BLOCK1 => {
+            let len = reader.unsigned(1)? as usize;
+            skip(reader, len)
+        }
+        DW_FORM_BLOCK2 => {
+            let len = reader.unsigned(2)? as usize;
+            skip(reader, len)
+        }
+        DW_FORM_BLOCK4 => {
+            let len = reader.unsigned(4)? as usize;
+            skip(reader, len)
+        }
+        DW_FORM_BLOCK | DW_FORM_EXPRLOC => {
+            let len = reader.uleb()? as usize;
+            skip(reader, len)
+        }
+        DW_FORM_FLAG_PRESENT => number(1),
+        DW_FORM_IMPLICIT_CONST => number(implicit as u64),
+        DW_FORM_INDIRECT => {
+            let form = reader.uleb()?;
+            read_form(reader, form, implicit, offset_size, address_size, version)
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,183 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use crate::dwarf::{
+    DW_AT_COMP_DIR, DW_AT_DWO_NAME, DW_AT_GNU_DWO_NAME, DW_UT_SKELETON, DW_UT_SPLIT_COMPILE,
+};
+use crate::finding::{self, Finding, Severity};
+use crate::output;
+use crate::{display_line, Loader};
+
+/// The check behind the `--report` findings of `--split-dwarf`.
+const SPLIT_DWARF_CHECKS: &[&str] = &["split-dwarf/dwo"];
+
+/// A skeleton unit: the part of a compile unit left in the binary when its
+/// debug info went to a `.dwo` file.
+pub struct Skeleton {
+    pub dwo_name: String,
+    pub comp_dir: Option<String>,
+    pub dwo_id: Option<u64>,
+}
+
+impl Skeleton {
+    /// Where the `.dwo` is looked for: the name as given when absolute, else
+    /// under the compilation directory, else next to the binary.
+    fn candidates(&self, binary: &str) -> Vec<PathBuf> {
+        let name = Path::new(&self.dwo_name);
+        if name.is_absolute() {
+            return vec![name.to_path_buf()];
+        }
+        let beside = Path::new(binary)
+            .parent()
+            .unwrap_or(Path::new("."))
+            .join(name);
+        match &self.comp_dir {
+            Some(dir) => vec![Path::new(dir).join(name), beside],
+            None => vec![beside],
+        }
+    }
+}
+
+impl Loader {
+    /// The skeleton units of the file: DWARF 5 `DW_UT_skeleton` units and
+    /// DWARF 4 compile units with `DW_AT_GNU_dwo_name`.
+    pub fn skeleton_units(&self) -> Vec<Skeleton> {
+        self.units()
+            .into_iter()
+            .filter_map(|unit| {
+                let name = unit
+                    .text(DW_AT_DWO_NAME)
+                    .or_else(|| unit.text(DW_AT_GNU_DWO_NAME))?;
+                if unit.unit_type != DW_UT_SKELETON && unit.version >= 5 {
+                    return None;
+                }
+                Some(Skeleton {
+                    dwo_name: name.to_string(),
+                    comp_dir: unit.text(DW_AT_COMP_DIR).map(str::to_string),
+                    dwo_id: unit.dwo_id,
+                })
+            })
+            .collect()
+    }
+
+    /// The ids of the split compile units of a `.dwo` file.
+    fn split_unit_ids(&self) -> Vec<u64> {
+        self.units()
+            .into_iter()
+            .filter(|unit| unit.unit_type == DW_UT_SPLIT_COMPILE || unit.version < 5)
+            .filter_map(|unit| unit.dwo_id)
+            .collect()
+    }
+
+    /// The unit signatures in the hash table of a `.dwp`'s `.debug_cu_index`.
+    pub fn dwp_unit_ids(&self) -> HashSet<u64> {
+        let Some(index) = self.debug_section(".debug_cu_index") else {
+            return HashSet::new();
+        };
+        let word = |offset: usize| {
+            let bytes: [u8; 4] = index.get(offset..offset + 4)?.try_into().ok()?;
+            Some(if self.is_big_endian() {
+                u32::from_be_bytes(bytes)
+            } else {
+                u32::from_le_bytes(bytes)
+            })
+        };
+        // Version, column count, unit count and slot count, then the signatures.
+        let slots = word(12).unwrap_or(0) as usize;
+        (0..slots)
+            .filter_map(|slot| {
+                let offset = 16 + slot * 8;
+                let bytes: [u8; 8] = index.get(offset..offset + 8)?.try_into().ok()?;
+                Some(if self.is_big_endian() {
+                    u64::from_be_bytes(bytes)
+                } else {
+                    u64::from_le_bytes(bytes)
+                })
+            })
+            .filter(|&signature| signature != 0)
+            .collect()
+    }
+}
+
+/// What became of one skeleton's `.dwo`.
+fn locate(binary: &str, skeleton: &Skeleton, dwp: &HashSet<u64>) -> Result<String, String> {
+    if skeleton.dwo_id.is_some_and(|id| dwp.contains(&id)) {
+        return Ok("in the .dwp".to_string());
+    }
+    let Some(path) = skeleton
+        .candidates(binary)
+        .into_iter()
+        .find(|candidate| candidate.is_file())
+    else {
+        return Err("missing".to_string());
+    };
+    let ids = Loader::open(&path.to_string_lossy())
+        .map(|dwo| dwo.split_unit_ids())
+        .unwrap_or_default();
+    match skeleton.dwo_id {
+        Some(id) if !ids.contains(&id) => Err(format!(
+            "{} does not hold unit {:#018x} (stale?)",
+            path.display(),
+            id
+        )),
+        _ => Ok(format!("found at {}", path.display())),
+    }
+}
+
+/// `--split-dwarf`: the `.dwo` files the skeleton units of each file refer
+/// to, whether each is present with a matching DWO id, either on its own or
+/// in the `<file>.dwp` package. Files with missing or stale `.dwo`s fail.
+pub fn display_split_dwarf(targets: &[(String, Loader)]) -> Vec<(String, String)> {
+    let mut failures = Vec::new();
+    for (label, loader) in targets {
+        let skeletons = loader.skeleton_units();
+        output::begin(label);
+        display_line("Skeleton Units", &skeletons.len().to_string());
+        if skeletons.is_empty() {
+            output::end();
+            finding::record(label, SPLIT_DWARF_CHECKS, &[] as &[Finding]);
+            continue;
+        }
+        let dwp_path = format!("{}.dwp", label);
+        let dwp = Loader::open(&dwp_path)
+            .map(|dwp| dwp.dwp_unit_ids())
+            .unwrap_or_default();
+        display_line(
+            "DWP",
+            &if Path::new(&dwp_path).is_file() {
+                format!("{} ({} units)", dwp_path, dwp.len())
+            } else {
+                "None".to_string()
+            },
+        );
+        let mut problems = Vec::new();
+        for skeleton in &skeletons {
+            let id = skeleton
+                .dwo_id
+                .map(|id| format!("{:#018x}", id))
+                .unwrap_or_else(|| "no id".to_string());
+            let status = match locate(label, skeleton, &dwp) {
+                Ok(status) => status,
+                Err(problem) => {
+                    problems.push(Finding {
+                        severity: Severity::Error,
+                        check: SPLIT_DWARF_CHECKS[0].to_string(),
+                        message: format!("{}: {}", skeleton.dwo_name, problem),
+                    });
+                    format!("{}  [!]", problem)
+                }
+            };
+            output::item(1, &format!("{} {}  {}", id, skeleton.dwo_name, status));
+        }
+        display_line("Missing Or Stale", &problems.len().to_string());
+        output::end();
+        finding::record(label, SPLIT_DWARF_CHECKS, &problems);
+        if !problems.is_empty() {
+            failures.push((
+                label.clone(),
+                format!("{} missing or stale .dwo file(s)", problems.len()),
+            ));
+        }
+    }
+    failures
+}
@@ -0,0 +1,93 @@
+use log::{debug, trace};
+
+use crate::segment::PT_DYNAMIC;
+use crate::Loader;
+
+pub const DT_NULL: u64 = 0;
+pub const DT_NEEDED: u64 = 1;
+pub const DT_STRTAB: u64 = 5;
+pub const DT_SONAME: u64 = 14;
+pub const DT_RPATH: u64 = 15;
+pub const DT_TEXTREL: u64 = 22;
+pub const DT_JMPREL: u64 = 23;
+pub const DT_BIND_NOW: u64 = 24;
+pub const DT_RUNPATH: u64 = 29;
+pub const DT_FLAGS: u64 = 30;
+pub const DT_FLAGS_1: u64 = 0x6fff_fffb;
+
+pub const DF_TEXTREL: u64 = 0x4;
+pub const DF_BIND_NOW: u64 = 0x8;
+pub const DF_STATIC_TLS: u64 = 0x10;
+pub const DF_1_NOW: u64 = 0x1;
+pub const DF_1_NOOPEN: u64 = 0x40;
+pub const DF_1_PIE: u64 = 0x0800_0000;
+
+#[derive(Clone, Copy, Debug)]
+pub struct DynamicEntry {
+    pub d_tag: u64,
+    pub d_val: u64,
+}
+
+impl Loader {
+    /// Returns the entries of the dynamic section, stopping at DT_NULL.
+    pub fn dynamic_entries(&self) -> Vec<DynamicEntry> {
+        let Some(dynamic) = self.program_header_by_type(PT_DYNAMIC) else {
+            trace!("no PT_DYNAMIC segment");
+            return Vec::new();
+        };
+        let entry_size = self.addr_size() * 2;
+        let count = dynamic.p_filesz as usize / entry_size;
+        let mut entries = Vec::new();
+        for index in 0..count {
+            let base = dynamic.p_offset as usize + index * entry_size;
+            let (Some(d_tag), Some(d_val)) = (
+                self.read_addr(base),
+                self.read_addr(base + self.addr_size()),
+            ) else {
+                debug!(
+                    "dynamic section is truncated after {} of {} entries",
+                    index, count
+                );
+                break;
+            };
+            if d_tag == DT_NULL {
+                break;
+            }
+            entries.push(DynamicEntry { d_tag, d_val });
+        }
+        entries
+    }
+
+    pub fn dynamic_value(&self, d_tag: u64) -> Option<u64> {
+        self.dynamic_entries()
+            .iter()
+            .find(|entry| entry.d_tag == d_tag)
+            .map(|entry| entry.d_val)
+    }
+
+    /// Reads a string from the dynamic string table (DT_STRTAB) at `offset`.
+    pub fn dynamic_string(&self, offset: u64) -> Option<String> {
+        let strtab = self.vaddr_to_offset(self.dynamic_value(DT_STRTAB)?)?;
+        self.read_cstr(strtab + offset as usize).map(str::to_string)
+    }
+
+    /// The DT_NEEDED libraries, in load order.
+    pub fn needed_libraries(&self) -> Vec<String> {
+        self.dynamic_entries()
+            .iter()
+            .filter(|entry| entry.d_tag == DT_NEEDED)
+            .filter_map(|entry| self.dynamic_string(entry.d_val))
+            .collect()
+    }
+
+    pub fn soname(&self) -> Option<String> {
+        self.dynamic_string(self.dynamic_value(DT_SONAME)?)
+    }
+
+    /// Whether the dynamic linker resolves every symbol at load time (`-z now`).
+    pub fn is_bind_now(&self) -> bool {
+        self.dynamic_value(DT_BIND_NOW).is_some()
+            || self.dynamic_value(DT_FLAGS).unwrap_or(0) & DF_BIND_NOW != 0
+            || self.dynamic_value(DT_FLAGS_1).unwrap_or(0) & DF_1_NOW != 0
+    }
+}
@@ -0,0 +1,257 @@
+use log::debug;
+use std::collections::HashMap;
+
+use crate::attributes::read_uleb128;
+use crate::section::SHT_NOBITS;
+use crate::segment::{PT_GNU_EH_FRAME, PT_LOAD};
+use crate::Loader;
+
+const EH_FRAME_SECTION: &str = ".eh_frame";
+const ET_REL: u16 = 1;
+
+/// A 32-bit record length of all ones announces the 64-bit DWARF format.
+const DWARF64_LENGTH: u32 = 0xffff_ffff;
+
+const DW_EH_PE_ABSPTR: u8 = 0x00;
+const DW_EH_PE_ULEB128: u8 = 0x01;
+const DW_EH_PE_UDATA2: u8 = 0x02;
+const DW_EH_PE_UDATA4: u8 = 0x03;
+const DW_EH_PE_UDATA8: u8 = 0x04;
+const DW_EH_PE_SLEB128: u8 = 0x09;
+const DW_EH_PE_SDATA2: u8 = 0x0a;
+const DW_EH_PE_SDATA4: u8 = 0x0b;
+const DW_EH_PE_SDATA8: u8 = 0x0c;
+const DW_EH_PE_PCREL: u8 = 0x10;
+const DW_EH_PE_INDIRECT: u8 = 0x80;
+const DW_EH_PE_OMIT: u8 = 0xff;
+
+/// The address range one FDE describes unwinding for.
+pub struct Fde {
+    pub start: u64,
+    pub size: u64,
+}
+
+/// Where the unwind tables are: file offset, size and address of the contents.
+struct EhFrame {
+    offset: usize,
+    size: usize,
+    addr: u64,
+}
+
+/// Decodes a signed LEB128 number, returning it with the number of bytes it took.
+fn read_sleb128(data: &[u8]) -> Option<(i64, usize)> {
+    let (value, length) = read_uleb128(data)?;
+    let bits = 7 * length as u32;
+    if bits < 64 && value & (1 << (bits - 1)) != 0 {
+        return Some(((value | (u64::MAX << bits)) as i64, length));
+    }
+    Some((value as i64, length))
+}
+
+impl Loader {
+    /// The `.eh_frame` section, or for files without section headers the tables
+    /// PT_GNU_EH_FRAME points to, read up to the end of their segment.
+    fn eh_frame(&self) -> Option<EhFrame> {
+        if let Some(section) = self
+            .section_by_name(EH_FRAME_SECTION)
+            .filter(|section| section.sh_type != SHT_NOBITS)
+        {
+            return Some(EhFrame {
+                offset: section.sh_offset as usize,
+                size: section.sh_size as usize,
+                addr: section.sh_addr,
+            });
+        }
+        let header = self.program_header_by_type(PT_GNU_EH_FRAME)?;
+        // version, eh_frame_ptr encoding, fde_count encoding, table encoding
+        let encoding = self.read_u8(header.p_offset as usize + 1)?;
+        let (addr, _) =
+            self.read_encoded(header.p_offset as usize + 4, header.p_vaddr + 4, encoding)?;
+        let offset = self.vaddr_to_offset(addr)?;
+        let segment = self.program_headers().into_iter().find(|segment| {
+            segment.p_type == PT_LOAD
+                && addr >= segment.p_vaddr
+                && addr < segment.p_vaddr + segment.p_filesz
+        })?;
+        Some(EhFrame {
+            offset,
+            size: (segment.p_offset + segment.p_filesz) as usize - offset,
+            addr,
+        })
+    }
+
+    /// Reads a pointer in one of the DW_EH_PE_* encodings at file `offset`, which
+    /// is loaded at `addr`. Returns the value and the bytes it took; indirect and
+    /// data- or text-relative pointers are not resolved.
+    fn read_encoded(&self, offset: usize, addr: u64, encoding: u8) -> Option<(u64, usize)> {
+        let (value, length) = match encoding & 0x0f {
+            DW_EH_PE_ABSPTR => (self.read_addr(offset)?, self.addr_size()),
+            DW_EH_PE_ULEB128 => read_uleb128(self.bytes().get(offset..)?)?,
+            DW_EH_PE_UDATA2 => (u64::from(self.read_u16(offset)?), 2),
+            DW_EH_PE_UDATA4 => (u64::from(self.read_u32(offset)?), 4),
+            DW_EH_PE_UDATA8 | DW_EH_PE_SDATA8 => (self.read_u64(offset)?, 8),
+            DW_EH_PE_SLEB128 => {
+                let (value, length) = read_sleb128(self.bytes().get(offset..)?)?;
+                (value as u64, length)
+            }
+            DW_EH_PE_SDATA2 => (self.read_u16(offset)? as i16 as u64, 2),
+            DW_EH_PE_SDATA4 => (self.read_u32(offset)? as i32 as u64, 4),
+            _ => return None,
+        };
+        let value = match encoding & 0x70 {
+            0 => value,
+            DW_EH_PE_PCREL => value.wrapping_add(addr),
+            _ => return None,
+        };
+        if encoding & DW_EH_PE_INDIRECT != 0 {
+            return None;
+        }
+        Some(if self.is_64bit() {
+            (value, length)
+        } else {
+            (value & u64::from(u32::MAX), length)
+        })
+    }
+
+    /// The FDE pointer encoding of the CIE whose contents (after the length and
+    /// the id) start at file offset `content`, from the `R` entry of its
+    /// augmentation data.
+    fn cie_fde_encoding(
+        &self,
+        content: usize,
+        end: usize,
+        addr_of: impl Fn(usize) -> u64,
+    ) -> Option<u8> {
+        let data = self.bytes().get(..end)?;
+        let version = *data.get(content)?;
+        let augmentation_start = content + 1;
+        let augmentation_len = data
+            .get(augmentation_start..)?
+            .iter()
+            .position(|&byte| byte == 0)?;
+        let augmentation = data.get(augmentation_start..augmentation_start + augmentation_len)?;
+        let mut position = augmentation_start + augmentation_len + 1;
+        if augmentation.starts_with(b"eh") {
+            position += self.addr_size();
+        }
+        // Code alignment, data alignment (signed, but only its length matters here)
+        // and the return address register.
+        for field in 0..3 {
+            if field == 2 && version == 1 {
+                position += 1;
+                continue;
+            }
+            position += read_uleb128(data.get(position..)?)?.1;
+        }
+        if augmentation.first() != Some(&b'z') {
+            return Some(DW_EH_PE_ABSPTR);
+        }
+        position += read_uleb128(data.get(position..)?)?.1;
+        for letter in &augmentation[1..] {
+            match letter {
+                b'R' => return data.get(position).copied(),
+                b'L' => position += 1,
+                b'P' => {
+                    let encoding = *data.get(position)?;
+                    let (_, length) =
+                        self.read_encoded(position + 1, addr_of(position + 1), encoding)?;
+                    position += 1 + length;
+                }
+                b'S' | b'B' | b'G' => {}
+                _ => return None,
+            }
+        }
+        Some(DW_EH_PE_ABSPTR)
+    }
+
+    /// The code ranges of the FDEs in the unwind tables, which stripped binaries
+    /// keep so that exceptions and backtraces work. Relocatable objects are left
+    /// out, as their FDE addresses are only filled in by relocations.
+    pub fn fde_ranges(&self) -> Vec<Fde> {
+        if self.raw_e_type() == ET_REL {
+            return Vec::new();
+        }
+        let Some(frame) = self.eh_frame() else {
+            return Vec::new();
+        };
+        let start = frame.offset;
+        let end = frame
+            .offset
+            .saturating_add(frame.size)
+            .min(self.bytes().len());
+        let addr_of = |offset: usize| frame.addr + (offset - start) as u64;
+        let mut encodings: HashMap<usize, Option<u8>> = HashMap::new();
+        let mut fdes = Vec::new();
+        let mut position = start;
+        while position + 4 <= end {
+            let Some(length) = self.read_u32(position) else {
+                break;
+            };
+            if length == 0 {
+                break;
+            }
+            let (content, id_size, length) = if length == DWARF64_LENGTH {
+                let Some(length) = self.read_u64(position + 4) else {
+                    break;
+                };
+                (position + 12, 8, length as usize)
+            } else {
+                (position + 4, 4, length as usize)
+            };
+            let record_end = content.saturating_add(length);
+            if record_end > end {
+                debug!(
+                    "{}: record at {:#x} runs past the end",
+                    EH_FRAME_SECTION, position
+                );
+                break;
+            }
+            let id = if id_size == 8 {
+                self.read_u64(content)
+            } else {
+                self.read_u32(content).map(u64::from)
+            };
+            match id {
+                Some(0) | None => {}
+                Some(id) => {
+                    // The CIE pointer counts back from the field itself.
+                    let cie = (content as u64)
+                        .checked_sub(id)
+                        .map(|cie| cie as usize)
+                        .filter(|&cie| cie >= start);
+                    let encoding = cie.and_then(|cie| {
+                        *encodings.entry(cie).or_insert_with(|| {
+                            let cie_content = if self.read_u32(cie) == Some(DWARF64_LENGTH) {
+                                cie + 20
+                            } else {
+                                cie + 8
+                            };
+                            self.cie_fde_encoding(cie_content, end, addr_of)
+                        })
+                    });
+                    let fields = content + id_size;
+                    let range = encoding
+                        .filter(|&encoding| encoding != DW_EH_PE_OMIT)
+                        .and_then(|encoding| {
+                            let (begin, length) =
+                                self.read_encoded(fields, addr_of(fields), encoding)?;
+                            let next = fields + length;
+                            let (size, _) =
+                                self.read_encoded(next, addr_of(next), encoding & 0x0f)?;
+                            Some(Fde { start: begin, size })
+                        });
+                    match range {
+                        Some(fde) if fde.size > 0 => fdes.push(fde),
+                        Some(_) => {}
+                        None => debug!(
+                            "{}: cannot read the FDE at {:#x}",
+                            EH_FRAME_SECTION, position
+                        ),
+                    }
+                }
+            }
+            position = record_end;
+        }
+        fdes
+    }
+}
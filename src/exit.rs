@@ -0,0 +1,42 @@
+use std::cell::RefCell;
+
+use crate::cli::FailOn;
+
+/// A check failed: a policy rule, a regression, an error finding and the like.
+pub const CHECK_FAILED: i32 = 1;
+/// The command line could not be understood.
+pub const USAGE: i32 = 2;
+/// A file could not be read or parsed; takes precedence over failed checks.
+pub const PARSE_ERROR: i32 = 3;
+/// With `--fail-on warning`, nothing failed but something was warned about.
+pub const WARNINGS: i32 = 4;
+
+thread_local! {
+    /// Inputs that could not be read or parsed, as opposed to failing a check.
+    static UNPARSED: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Marks the failure of `path` as a parse error.
+pub fn unparsed(path: &str) {
+    UNPARSED.with(|unparsed| unparsed.borrow_mut().push(path.to_string()));
+}
+
+/// The exit status of a run with these failures and warnings.
+pub fn status(failures: &[(String, String)], warnings: usize, fail_on: FailOn) -> i32 {
+    if fail_on == FailOn::Never {
+        return 0;
+    }
+    let unparsed = UNPARSED.with(|unparsed| {
+        let unparsed = unparsed.borrow();
+        failures.iter().any(|(path, _)| unparsed.contains(path))
+    });
+    if unparsed {
+        PARSE_ERROR
+    } else if !failures.is_empty() {
+        CHECK_FAILED
+    } else if warnings > 0 && fail_on == FailOn::Warning {
+        WARNINGS
+    } else {
+        0
+    }
+}
@@ -0,0 +1,378 @@
+use crate::machine;
+use crate::output;
+use crate::{display_line, Loader};
+
+/// Section index that says the real one is kept in section 0 (`sh_link`).
+const SHN_XINDEX: u32 = 0xffff;
+/// Program header count that says the real one is kept in section 0 (`sh_info`).
+const PN_XNUM: u32 = 0xffff;
+
+/// One field of the ELF header, as `explain` describes it.
+struct Field {
+    name: &'static str,
+    /// Offset in an ELF32 and an ELF64 file.
+    offsets: (usize, usize),
+    /// Size in an ELF32 and an ELF64 file.
+    sizes: (usize, usize),
+    description: &'static str,
+    valid: &'static str,
+}
+
+const GLOSSARY: &[Field] = &[
+    Field {
+        name: "ei_mag",
+        offsets: (0, 0),
+        sizes: (4, 4),
+        description: "The magic number that marks the file as ELF",
+        valid: "7f 45 4c 46 (\\x7fELF)",
+    },
+    Field {
+        name: "ei_class",
+        offsets: (4, 4),
+        sizes: (1, 1),
+        description: "Whether the file uses 32-bit or 64-bit structures; decides the layout of the rest of the header",
+        valid: "1 (ELFCLASS32) or 2 (ELFCLASS64)",
+    },
+    Field {
+        name: "ei_data",
+        offsets: (5, 5),
+        sizes: (1, 1),
+        description: "The byte order of every multi-byte field after e_ident",
+        valid: "1 (ELFDATA2LSB, little endian) or 2 (ELFDATA2MSB, big endian)",
+    },
+    Field {
+        name: "ei_version",
+        offsets: (6, 6),
+        sizes: (1, 1),
+        description: "The version of the ELF identification",
+        valid: "1 (EV_CURRENT)",
+    },
+    Field {
+        name: "ei_osabi",
+        offsets: (7, 7),
+        sizes: (1, 1),
+        description: "The operating system or ABI extensions the file relies on",
+        valid: "0 (System V) for most Linux files, 3 (GNU) when GNU extensions such as IFUNC are used",
+    },
+    Field {
+        name: "ei_abiversion",
+        offsets: (8, 8),
+        sizes: (1, 1),
+        description: "The version of the ABI given by ei_osabi",
+        valid: "Usually 0; its meaning depends on ei_osabi",
+    },
+    Field {
+        name: "e_type",
+        offsets: (16, 16),
+        sizes: (2, 2),
+        description: "What kind of object the file is",
+        valid: "1 (ET_REL), 2 (ET_EXEC), 3 (ET_DYN), 4 (ET_CORE), or an OS or processor specific value from 0xfe00",
+    },
+    Field {
+        name: "e_machine",
+        offsets: (18, 18),
+        sizes: (2, 2),
+        description: "The instruction set the file targets",
+        valid: "An EM_ constant from the registry, see --list-machines",
+    },
+    Field {
+        name: "e_version",
+        offsets: (20, 20),
+        sizes: (4, 4),
+        description: "The version of the object file format",
+        valid: "1 (EV_CURRENT)",
+    },
+    Field {
+        name: "e_entry",
+        offsets: (24, 24),
+        sizes: (4, 8),
+        description: "The virtual address execution starts at",
+        valid: "0 when there is no entry point, as in relocatable files and most shared libraries",
+    },
+    Field {
+        name: "e_phoff",
+        offsets: (28, 32),
+        sizes: (4, 8),
+        description: "The file offset of the program header table",
+        valid: "0 when there is no table, otherwise within the file",
+    },
+    Field {
+        name: "e_shoff",
+        offsets: (32, 40),
+        sizes: (4, 8),
+        description: "The file offset of the section header table",
+        valid: "0 when there is no table, otherwise within the file",
+    },
+    Field {
+        name: "e_flags",
+        offsets: (36, 48),
+        sizes: (4, 4),
+        description: "Processor-specific flags, such as the ABI variant or the ISA extensions used",
+        valid: "Depends on e_machine, see --arch-info",
+    },
+    Field {
+        name: "e_ehsize",
+        offsets: (40, 52),
+        sizes: (2, 2),
+        description: "The size of this ELF header",
+        valid: "52 for ELF32, 64 for ELF64",
+    },
+    Field {
+        name: "e_phentsize",
+        offsets: (42, 54),
+        sizes: (2, 2),
+        description: "The size of one entry of the program header table",
+        valid: "32 for ELF32, 56 for ELF64, or 0 without a table",
+    },
+    Field {
+        name: "e_phnum",
+        offsets: (44, 56),
+        sizes: (2, 2),
+        description: "The number of entries in the program header table",
+        valid: "0 to 0xfffe; 0xffff (PN_XNUM) moves the count to sh_info of section 0",
+    },
+    Field {
+        name: "e_shentsize",
+        offsets: (46, 58),
+        sizes: (2, 2),
+        description: "The size of one entry of the section header table",
+        valid: "40 for ELF32, 64 for ELF64, or 0 without a table",
+    },
+    Field {
+        name: "e_shnum",
+        offsets: (48, 60),
+        sizes: (2, 2),
+        description: "The number of entries in the section header table",
+        valid: "0 to 0xfeff; 0 with a table moves the count to sh_size of section 0",
+    },
+    Field {
+        name: "e_shstrndx",
+        offsets: (50, 62),
+        sizes: (2, 2),
+        description: "The index of the section holding the section names",
+        valid: "0 (SHN_UNDEF) without names, below e_shnum, or 0xffff (SHN_XINDEX) to move the index to sh_link of section 0",
+    },
+];
+
+/// Looks a field up by name, ignoring case.
+fn lookup(name: &str) -> Option<&'static Field> {
+    GLOSSARY
+        .iter()
+        .find(|field| field.name.eq_ignore_ascii_case(name))
+}
+
+/// The value of the field in the file and how it was interpreted.
+fn interpret(field: &Field, loader: &Loader) -> (String, String) {
+    let elf64 = loader.is_64bit();
+    let expect = |value: u32, elf32: u32, elf64_value: u32, what: &str| {
+        let expected = if elf64 { elf64_value } else { elf32 };
+        if value == expected {
+            format!("the {} size for this class", what)
+        } else if value == 0 {
+            format!("no {} table", what)
+        } else {
+            format!("expected {} for this class", expected)
+        }
+    };
+    let offset = |value: u64, what: &str| {
+        if value == 0 {
+            format!("there is no {} table", what)
+        } else if value >= loader.bytes().len() as u64 {
+            format!("past the end of the {}-byte file", loader.bytes().len())
+        } else {
+            format!("the {} table starts {} bytes into the file", what, value)
+        }
+    };
+    match field.name {
+        "ei_mag" => {
+            let magic: Vec<String> = loader.bytes()[..4]
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect();
+            (magic.join(" "), "an ELF file".to_string())
+        }
+        "ei_class" => (
+            loader.bytes()[4].to_string(),
+            loader.get_ei_class().to_string(),
+        ),
+        "ei_data" => (
+            loader.bytes()[5].to_string(),
+            loader.get_ei_data().to_string(),
+        ),
+        "ei_version" => {
+            let version = loader.get_ei_version();
+            let meaning = if version == 1 {
+                "the current version"
+            } else {
+                "not the current version, which is 1"
+            };
+            (version.to_string(), meaning.to_string())
+        }
+        "ei_osabi" => (loader.raw_ei_osabi().to_string(), loader.get_ei_osabi()),
+        "ei_abiversion" => (
+            loader.bytes()[8].to_string(),
+            format!("version {} of the ABI", loader.bytes()[8]),
+        ),
+        "e_type" => (
+            format!("{:#x}", loader.raw_e_type()),
+            loader.get_e_type().to_string(),
+        ),
+        "e_machine" => {
+            let id = loader.raw_e_machine();
+            let meaning = match machine::lookup(id) {
+                Some(machine) => format!("{} ({})", machine.name, machine.constant),
+                None => "not in the registry".to_string(),
+            };
+            (id.to_string(), meaning)
+        }
+        "e_version" => {
+            let version = loader.get_e_version();
+            let meaning = if version == 1 {
+                "the current version"
+            } else {
+                "not the current version, which is 1"
+            };
+            (version.to_string(), meaning.to_string())
+        }
+        "e_entry" => {
+            let entry = loader.get_e_entry();
+            let meaning = if entry == 0 {
+                "no entry point".to_string()
+            } else {
+                let section = loader.section_headers().into_iter().find(|section| {
+                    section.sh_addr != 0
+                        && entry >= section.sh_addr
+                        && entry < section.sh_addr + section.sh_size
+                });
+                match section {
+                    Some(section) => format!("execution starts in {}", section.name),
+                    None => "execution starts here, outside any section".to_string(),
+                }
+            };
+            (format!("{:#x}", entry), meaning)
+        }
+        "e_phoff" => {
+            let value = loader.get_e_phoff();
+            (format!("{:#x}", value), offset(value, "program header"))
+        }
+        "e_shoff" => {
+            let value = loader.get_e_shoff();
+            (format!("{:#x}", value), offset(value, "section header"))
+        }
+        "e_flags" => (
+            format!("{:#x}", loader.get_e_flags()),
+            "decoded by --arch-info".to_string(),
+        ),
+        "e_ehsize" => {
+            let value = loader.get_e_ehsize();
+            (value.to_string(), expect(value, 52, 64, "ELF header"))
+        }
+        "e_phentsize" => {
+            let value = loader.get_e_phentsize();
+            (value.to_string(), expect(value, 32, 56, "program header"))
+        }
+        "e_shentsize" => {
+            let value = loader.get_e_shentsize();
+            (value.to_string(), expect(value, 40, 64, "section header"))
+        }
+        "e_phnum" => {
+            let value = loader.get_e_phnum();
+            let meaning = if value == PN_XNUM {
+                format!(
+                    "PN_XNUM, the table has {} entries",
+                    loader.program_headers().len()
+                )
+            } else {
+                format!("{} program header(s)", value)
+            };
+            (value.to_string(), meaning)
+        }
+        "e_shnum" => {
+            let value = loader.get_e_shnum();
+            let count = loader.section_headers().len();
+            let meaning = if value == 0 && count > 0 {
+                format!(
+                    "extended numbering, the table has {} entries (sh_size of section 0)",
+                    count
+                )
+            } else {
+                format!("{} section header(s), including the null section 0", value)
+            };
+            (value.to_string(), meaning)
+        }
+        "e_shstrndx" => {
+            let value = loader.get_e_shstrndx();
+            let sections = loader.section_headers();
+            let meaning = if value == 0 {
+                "SHN_UNDEF, the sections have no names".to_string()
+            } else if value == SHN_XINDEX {
+                "SHN_XINDEX, the index is in sh_link of section 0".to_string()
+            } else {
+                match sections.get(value as usize) {
+                    Some(section) => {
+                        format!("section {} ({}) holds the names", value, section.name)
+                    }
+                    None => format!("past the {} sections of the file", sections.len()),
+                }
+            };
+            (value.to_string(), meaning)
+        }
+        _ => (String::new(), String::new()),
+    }
+}
+
+/// Describes a field: what it holds, where it sits and what values are valid.
+fn display_field(field: &Field) {
+    output::begin(field.name);
+    display_line("Description", field.description);
+    let place = |(elf32, elf64): (usize, usize)| {
+        if elf32 == elf64 {
+            format!("{:#x}", elf32)
+        } else {
+            format!("{:#x} (ELF32), {:#x} (ELF64)", elf32, elf64)
+        }
+    };
+    display_line("Offset", &place(field.offsets));
+    let size = match field.sizes {
+        (elf32, elf64) if elf32 == elf64 => format!("{} bytes", elf32),
+        (elf32, elf64) => format!("{} bytes (ELF32), {} bytes (ELF64)", elf32, elf64),
+    };
+    display_line("Size", &size);
+    display_line("Valid Values", field.valid);
+    output::end();
+}
+
+/// Lists every field `explain` knows with its description.
+fn display_glossary() {
+    output::begin("ELF header fields");
+    for field in GLOSSARY {
+        output::item(1, &format!("{:<16} {}", field.name, field.description));
+    }
+    output::end();
+}
+
+/// `explain [FIELD] [FILE...]` and `--explain FIELD`: describes a header field
+/// and, for each file, its value there and how it was read. Without a field,
+/// lists the fields. Unknown fields are returned as a failure.
+pub fn display_explain(field: Option<&str>, targets: &[(String, Loader)]) -> Vec<(String, String)> {
+    let Some(name) = field else {
+        display_glossary();
+        return Vec::new();
+    };
+    let Some(field) = lookup(name) else {
+        let known: Vec<&str> = GLOSSARY.iter().map(|field| field.name).collect();
+        return vec![(
+            name.to_string(),
+            format!("unknown field, expected one of: {}", known.join(", ")),
+        )];
+    };
+    display_field(field);
+    for (label, loader) in targets {
+        let (value, meaning) = interpret(field, loader);
+        output::begin(label);
+        display_line(field.name, &value);
+        display_line("Interpretation", &meaning);
+        output::end();
+    }
+    Vec::new()
+}
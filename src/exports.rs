@@ -0,0 +1,177 @@
+use crate::cli::{Options, VersionScript};
+use crate::finding::{Finding, Severity};
+use crate::output;
+use crate::symbol::{SHN_UNDEF, STB_GLOBAL, STB_WEAK};
+use crate::version::VER_NDX_GLOBAL;
+use crate::{display_line, Loader};
+
+/// Symbols the linker adds to every shared object; a version script hides them, but
+/// their presence says nothing about the library's own API.
+const LINKER_GENERATED: &[&str] = &["_init", "_fini", "_edata", "_end", "__bss_start"];
+
+/// The checks `lint-exports` makes, as named in its findings.
+pub const LINT_CHECKS: &[&str] = &[
+    "lint-exports/allowed-exports",
+    "lint-exports/std-internal",
+    "lint-exports/version-script",
+];
+
+/// Matches `*` (any run of characters) and `?` (one character) against the whole text.
+pub(crate) fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            p = star + 1;
+            t = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Whether a mangled name belongs to namespace std (`St`, or one of the `Sa`, `Sb`,
+/// `Ss`, `Si`, `So`, `Sd` abbreviations), including its vtables and typeinfo.
+fn is_cxx_std_internal(name: &str) -> bool {
+    let Some(mut rest) = name.strip_prefix("_Z") else {
+        return false;
+    };
+    for special in ["TV", "TI", "TS", "TT"] {
+        rest = rest.strip_prefix(special).unwrap_or(rest);
+    }
+    rest = rest.strip_prefix('N').unwrap_or(rest);
+    rest = rest.trim_start_matches(['r', 'V', 'K']);
+    ["St", "Sa", "Sb", "Ss", "Si", "So", "Sd"]
+        .iter()
+        .any(|prefix| rest.starts_with(prefix))
+}
+
+impl Loader {
+    /// Names of the dynamic symbols this object defines and exports, leaving out the
+    /// absolute symbols the linker emits for each version node.
+    pub fn exported_symbols(&self) -> Vec<String> {
+        let version_nodes: Vec<String> = self.version_definitions().into_values().collect();
+        self.dynamic_symbols()
+            .into_iter()
+            .filter(|symbol| symbol.st_shndx != SHN_UNDEF && !symbol.name.is_empty())
+            .filter(|symbol| matches!(symbol.bind(), STB_GLOBAL | STB_WEAK))
+            .filter(|symbol| !LINKER_GENERATED.contains(&symbol.name.as_str()))
+            .filter(|symbol| !version_nodes.contains(&symbol.name))
+            .map(|symbol| symbol.name)
+            .collect()
+    }
+
+    /// Version nodes other than the base one the linker always emits for the soname,
+    /// which only exist when the library was linked with a version script.
+    pub fn version_script_nodes(&self) -> Vec<String> {
+        let mut nodes: Vec<(u16, String)> = self
+            .version_definitions()
+            .into_iter()
+            .filter(|(index, _)| *index > VER_NDX_GLOBAL)
+            .collect();
+        nodes.sort();
+        nodes.into_iter().map(|(_, name)| name).collect()
+    }
+}
+
+/// Prints the policy violations of one library and returns them.
+pub fn display_export_lint(path: &str, loader: &Loader, options: &Options) -> Vec<Finding> {
+    let exported = loader.exported_symbols();
+    let outside: Vec<&String> = if options.allow_exports.is_empty() {
+        Vec::new()
+    } else {
+        exported
+            .iter()
+            .filter(|name| {
+                !options
+                    .allow_exports
+                    .iter()
+                    .any(|pattern| glob_match(pattern, name))
+            })
+            .collect()
+    };
+    let std_internals: Vec<&String> = exported
+        .iter()
+        .filter(|name| is_cxx_std_internal(name))
+        .collect();
+    let nodes = loader.version_script_nodes();
+    let version_script_violation = match options.version_script {
+        Some(VersionScript::Required) => nodes.is_empty(),
+        Some(VersionScript::Forbidden) => !nodes.is_empty(),
+        None => false,
+    };
+
+    output::begin(path);
+    display_line("Exported Symbols", &exported.len().to_string());
+    if !options.allow_exports.is_empty() {
+        display_line(
+            &format!("Outside {}", options.allow_exports.join(", ")),
+            &outside.len().to_string(),
+        );
+        for name in &outside {
+            output::item(1, name);
+        }
+    }
+    display_line(
+        "C++ Standard Library Internals",
+        &std_internals.len().to_string(),
+    );
+    for name in &std_internals {
+        output::item(1, name);
+    }
+    let mut version_script = if nodes.is_empty() {
+        "Not used".to_string()
+    } else {
+        format!("Used ({})", nodes.join(", "))
+    };
+    if version_script_violation {
+        version_script.push_str(match options.version_script {
+            Some(VersionScript::Required) => ", but the policy requires one",
+            _ => ", but the policy forbids one",
+        });
+    }
+    display_line("Version Script", &version_script);
+    output::end();
+
+    let violation = |check: &str, message: String| Finding {
+        severity: Severity::Error,
+        check: format!("lint-exports/{}", check),
+        message,
+    };
+    let mut violations: Vec<Finding> = outside
+        .iter()
+        .map(|name| {
+            violation(
+                "allowed-exports",
+                format!("{} matches none of the allowed patterns", name),
+            )
+        })
+        .collect();
+    violations.extend(std_internals.iter().map(|name| {
+        violation(
+            "std-internal",
+            format!("{} is a C++ standard library internal", name),
+        )
+    }));
+    if version_script_violation {
+        violations.push(violation(
+            "version-script",
+            match options.version_script {
+                Some(VersionScript::Required) => "no version script, but the policy requires one",
+                _ => "a version script, but the policy forbids one",
+            }
+            .to_string(),
+        ));
+    }
+    violations
+}
@@ -0,0 +1,167 @@
+use log::info;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::machine;
+use crate::output;
+use crate::remote::run_tool;
+use crate::{display_line, user_cache_dir, Loader};
+
+/// Debian's `hello` package from a fixed point of snapshot.debian.org, so the
+/// files behind these URLs never change.
+const POOL: &str = "https://snapshot.debian.org/archive/debian/20210801T000000Z/pool/main/h/hello";
+const VERSION: &str = "2.10-2";
+
+/// A Debian architecture and the e_machine its binary must have.
+struct Fixture {
+    arch: &'static str,
+    machine: u16,
+}
+
+const FIXTURES: &[Fixture] = &[
+    Fixture {
+        arch: "amd64",
+        machine: 62,
+    },
+    Fixture {
+        arch: "i386",
+        machine: 3,
+    },
+    Fixture {
+        arch: "arm64",
+        machine: 183,
+    },
+    Fixture {
+        arch: "armel",
+        machine: 40,
+    },
+    Fixture {
+        arch: "armhf",
+        machine: 40,
+    },
+    Fixture {
+        arch: "mips",
+        machine: 8,
+    },
+    Fixture {
+        arch: "mipsel",
+        machine: 8,
+    },
+    Fixture {
+        arch: "mips64el",
+        machine: 8,
+    },
+    Fixture {
+        arch: "ppc64el",
+        machine: 21,
+    },
+    Fixture {
+        arch: "s390x",
+        machine: 22,
+    },
+];
+
+/// Checks that `path` is an ELF file for the fixture's machine, removing it if not
+/// so the next run downloads it again.
+fn validate(path: &Path, fixture: &Fixture) -> Result<(), String> {
+    let loader = Loader::open(&path.display().to_string()).map_err(|error| error.to_string())?;
+    let problem = if !loader.is_elf() {
+        Some("not an ELF file".to_string())
+    } else if loader.raw_e_machine() != fixture.machine {
+        Some(format!(
+            "machine is {}, expected {}",
+            loader.machine_name(),
+            machine::lookup(fixture.machine).map_or("?", |machine| machine.name)
+        ))
+    } else {
+        None
+    };
+    match problem {
+        Some(problem) => {
+            let _ = fs::remove_file(path);
+            Err(problem)
+        }
+        None => Ok(()),
+    }
+}
+
+/// Downloads the package with curl and takes `/usr/bin/hello` out of it with ar
+/// and tar, working in a scratch directory next to the result.
+fn download(fixture: &Fixture, target: &Path, dir: &Path) -> Result<(), String> {
+    let work = dir.join(format!(".work-{}", fixture.arch));
+    fs::create_dir_all(&work).map_err(|error| error.to_string())?;
+    let url = format!("{}/hello_{}_{}.deb", POOL, VERSION, fixture.arch);
+    info!("{}: downloading {}", fixture.arch, url);
+    let result = (|| {
+        run_tool(
+            Command::new("curl")
+                .args(["--fail", "--silent", "--show-error", "--location"])
+                .arg("--output")
+                .arg(work.join("hello.deb"))
+                .arg(&url),
+        )?;
+        let members = run_tool(
+            Command::new("ar")
+                .args(["t", "hello.deb"])
+                .current_dir(&work),
+        )?;
+        let members = String::from_utf8_lossy(&members);
+        let data = members
+            .lines()
+            .find(|member| member.starts_with("data.tar"))
+            .ok_or("package has no data archive")?;
+        run_tool(
+            Command::new("ar")
+                .args(["x", "hello.deb", data])
+                .current_dir(&work),
+        )?;
+        run_tool(
+            Command::new("tar")
+                .args(["-xf", data, "./usr/bin/hello"])
+                .current_dir(&work),
+        )?;
+        fs::rename(work.join("usr/bin/hello"), target).map_err(|error| error.to_string())
+    })();
+    let _ = fs::remove_dir_all(&work);
+    result
+}
+
+/// Fetches every fixture into `dir` (the user cache directory by default),
+/// skipping the ones already there. Returns the architectures that failed.
+pub fn fetch_fixtures(dir: Option<&String>) -> Vec<(String, String)> {
+    let Some(dir) = dir
+        .map(PathBuf::from)
+        .or_else(|| user_cache_dir().map(|cache| cache.join("fixtures")))
+    else {
+        return vec![(
+            "fetch-fixtures".to_string(),
+            "no cache directory: set HOME or XDG_CACHE_HOME, or give DIR".to_string(),
+        )];
+    };
+    if let Err(error) = fs::create_dir_all(&dir) {
+        return vec![(dir.display().to_string(), error.to_string())];
+    }
+
+    let mut failures = Vec::new();
+    output::begin(&dir.display().to_string());
+    for fixture in FIXTURES {
+        let target = dir.join(format!("hello-{}", fixture.arch));
+        let status = if target.exists() {
+            validate(&target, fixture).map(|()| "cached")
+        } else {
+            download(fixture, &target, &dir)
+                .and_then(|()| validate(&target, fixture))
+                .map(|()| "downloaded")
+        };
+        match status {
+            Ok(status) => display_line(fixture.arch, &format!("{} ({})", target.display(), status)),
+            Err(error) => {
+                display_line(fixture.arch, &format!("Failed: {}", error));
+                failures.push((fixture.arch.to_string(), error));
+            }
+        }
+    }
+    output::end();
+    failures
+}
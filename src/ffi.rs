@@ -0,0 +1,316 @@
+//! C interface, built into the cdylib with the `ffi` feature. `include/elfhp.h`
+//! declares it and is regenerated with
+//! `cbindgen --config cbindgen.toml --output include/elfhp.h`.
+//!
+//! Functions that can fail return NULL or false and leave a message for
+//! `elfhp_last_error`. Strings handed out belong to the object they came from.
+
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::ptr;
+
+use crate::section::SectionHeader;
+use crate::symbol::Symbol;
+use crate::Loader;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_error(message: &str) {
+    LAST_ERROR.with(|error| *error.borrow_mut() = Some(c_string(message)));
+}
+
+/// Names come from NUL-terminated tables, so an interior NUL cannot occur.
+fn c_string(text: &str) -> CString {
+    CString::new(text).unwrap_or_default()
+}
+
+/// An open ELF file.
+pub struct ElfhpFile {
+    loader: Loader,
+}
+
+/// The ELF header as stored in the file, widened to the ELF64 field sizes.
+#[repr(C)]
+pub struct ElfhpHeader {
+    /// EI_CLASS: 1 for ELF32, 2 for ELF64.
+    pub ei_class: u8,
+    /// EI_DATA: 1 for little endian, 2 for big endian.
+    pub ei_data: u8,
+    pub ei_version: u8,
+    pub ei_osabi: u8,
+    pub e_type: u16,
+    pub e_machine: u16,
+    pub e_version: u32,
+    pub e_entry: u64,
+    pub e_phoff: u64,
+    pub e_shoff: u64,
+    pub e_flags: u32,
+    pub e_ehsize: u16,
+    pub e_phentsize: u16,
+    pub e_phnum: u16,
+    pub e_shentsize: u16,
+    pub e_shnum: u16,
+    pub e_shstrndx: u16,
+}
+
+/// One section header. `name` stays valid until the iterator is freed.
+#[repr(C)]
+pub struct ElfhpSection {
+    pub index: u32,
+    pub name: *const c_char,
+    pub sh_type: u32,
+    pub sh_flags: u64,
+    pub sh_addr: u64,
+    pub sh_offset: u64,
+    pub sh_size: u64,
+    pub sh_link: u32,
+    pub sh_info: u32,
+    pub sh_addralign: u64,
+    pub sh_entsize: u64,
+}
+
+/// One symbol table entry. `name` stays valid until the iterator is freed.
+#[repr(C)]
+pub struct ElfhpSymbol {
+    pub name: *const c_char,
+    pub st_value: u64,
+    pub st_size: u64,
+    pub st_info: u8,
+    pub st_other: u8,
+    pub st_shndx: u16,
+}
+
+/// Walks the section headers, section 0 included.
+pub struct ElfhpSectionIter {
+    sections: Vec<(SectionHeader, CString)>,
+    next: usize,
+}
+
+/// Walks `.symtab` or `.dynsym` in table order.
+pub struct ElfhpSymbolIter {
+    symbols: Vec<(Symbol, CString)>,
+    next: usize,
+}
+
+/// The message of the last call on this thread that failed, or NULL.
+#[no_mangle]
+pub extern "C" fn elfhp_last_error() -> *const c_char {
+    LAST_ERROR.with(|error| {
+        error
+            .borrow()
+            .as_ref()
+            .map_or(ptr::null(), |message| message.as_ptr())
+    })
+}
+
+/// Maps the ELF file at `path`. Returns NULL if it cannot be read, is not ELF or
+/// its header is truncated.
+///
+/// # Safety
+/// `path` must be NULL or a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn elfhp_open(path: *const c_char) -> *mut ElfhpFile {
+    if path.is_null() {
+        set_error("path is NULL");
+        return ptr::null_mut();
+    }
+    let Ok(path) = CStr::from_ptr(path).to_str() else {
+        set_error("path is not valid UTF-8");
+        return ptr::null_mut();
+    };
+    let loader = match Loader::open(path) {
+        Ok(loader) => loader,
+        Err(error) => {
+            set_error(&format!("{}: {}", path, error));
+            return ptr::null_mut();
+        }
+    };
+    if !loader.is_elf() {
+        set_error(&format!("{} is not an ELF file", path));
+        return ptr::null_mut();
+    }
+    if let Some(problem) = loader.header_truncation() {
+        set_error(&format!("{}: {}", path, problem));
+        return ptr::null_mut();
+    }
+    Box::into_raw(Box::new(ElfhpFile { loader }))
+}
+
+/// Unmaps a file from `elfhp_open`. NULL is ignored.
+///
+/// # Safety
+/// `file` must be NULL or come from `elfhp_open`, and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn elfhp_close(file: *mut ElfhpFile) {
+    if !file.is_null() {
+        drop(Box::from_raw(file));
+    }
+}
+
+/// Fills `out` with the ELF header of `file`.
+///
+/// # Safety
+/// `file` must come from `elfhp_open`; `out` must point to writable memory.
+#[no_mangle]
+pub unsafe extern "C" fn elfhp_header(file: *const ElfhpFile, out: *mut ElfhpHeader) -> bool {
+    let (Some(file), Some(out)) = (file.as_ref(), out.as_mut()) else {
+        set_error("file or out is NULL");
+        return false;
+    };
+    let loader = &file.loader;
+    *out = ElfhpHeader {
+        ei_class: loader.read_u8(4).unwrap_or(0),
+        ei_data: loader.read_u8(5).unwrap_or(0),
+        ei_version: loader.get_ei_version(),
+        ei_osabi: loader.read_u8(7).unwrap_or(0),
+        e_type: loader.raw_e_type(),
+        e_machine: loader.raw_e_machine(),
+        e_version: loader.get_e_version() as u32,
+        e_entry: loader.get_e_entry(),
+        e_phoff: loader.get_e_phoff(),
+        e_shoff: loader.get_e_shoff(),
+        e_flags: loader.get_e_flags(),
+        e_ehsize: loader.get_e_ehsize() as u16,
+        e_phentsize: loader.get_e_phentsize() as u16,
+        e_phnum: loader.get_e_phnum() as u16,
+        e_shentsize: loader.get_e_shentsize() as u16,
+        e_shnum: loader.get_e_shnum() as u16,
+        e_shstrndx: loader.get_e_shstrndx() as u16,
+    };
+    true
+}
+
+/// Starts a walk over the section headers of `file`. The iterator owns what it
+/// hands out, so `file` may be closed first. Free it with `elfhp_sections_iter_free`.
+///
+/// # Safety
+/// `file` must come from `elfhp_open`.
+#[no_mangle]
+pub unsafe extern "C" fn elfhp_sections_iter(file: *const ElfhpFile) -> *mut ElfhpSectionIter {
+    let Some(file) = file.as_ref() else {
+        set_error("file is NULL");
+        return ptr::null_mut();
+    };
+    let sections = file
+        .loader
+        .section_headers()
+        .into_iter()
+        .map(|section| {
+            let name = c_string(&section.name);
+            (section, name)
+        })
+        .collect();
+    Box::into_raw(Box::new(ElfhpSectionIter { sections, next: 0 }))
+}
+
+/// Fills `out` with the next section header; returns false after the last one.
+///
+/// # Safety
+/// `iter` must come from `elfhp_sections_iter`; `out` must point to writable memory.
+#[no_mangle]
+pub unsafe extern "C" fn elfhp_sections_next(
+    iter: *mut ElfhpSectionIter,
+    out: *mut ElfhpSection,
+) -> bool {
+    let (Some(iter), Some(out)) = (iter.as_mut(), out.as_mut()) else {
+        return false;
+    };
+    let Some((section, name)) = iter.sections.get(iter.next) else {
+        return false;
+    };
+    iter.next += 1;
+    *out = ElfhpSection {
+        index: section.index as u32,
+        name: name.as_ptr(),
+        sh_type: section.sh_type,
+        sh_flags: section.sh_flags,
+        sh_addr: section.sh_addr,
+        sh_offset: section.sh_offset,
+        sh_size: section.sh_size,
+        sh_link: section.sh_link,
+        sh_info: section.sh_info,
+        sh_addralign: section.sh_addralign,
+        sh_entsize: section.sh_entsize,
+    };
+    true
+}
+
+/// Frees a section iterator and the names it handed out. NULL is ignored.
+///
+/// # Safety
+/// `iter` must be NULL or come from `elfhp_sections_iter`, and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn elfhp_sections_iter_free(iter: *mut ElfhpSectionIter) {
+    if !iter.is_null() {
+        drop(Box::from_raw(iter));
+    }
+}
+
+/// Starts a walk over `.dynsym` if `dynamic` is true, else `.symtab`. A file
+/// without that table gives an empty walk. Free it with `elfhp_symbols_iter_free`.
+///
+/// # Safety
+/// `file` must come from `elfhp_open`.
+#[no_mangle]
+pub unsafe extern "C" fn elfhp_symbols_iter(
+    file: *const ElfhpFile,
+    dynamic: bool,
+) -> *mut ElfhpSymbolIter {
+    let Some(file) = file.as_ref() else {
+        set_error("file is NULL");
+        return ptr::null_mut();
+    };
+    let symbols = if dynamic {
+        file.loader.dynamic_symbols()
+    } else {
+        file.loader.static_symbols()
+    };
+    let symbols = symbols
+        .into_iter()
+        .map(|symbol| {
+            let name = c_string(&symbol.name);
+            (symbol, name)
+        })
+        .collect();
+    Box::into_raw(Box::new(ElfhpSymbolIter { symbols, next: 0 }))
+}
+
+/// Fills `out` with the next symbol; returns false after the last one.
+///
+/// # Safety
+/// `iter` must come from `elfhp_symbols_iter`; `out` must point to writable memory.
+#[no_mangle]
+pub unsafe extern "C" fn elfhp_symbols_next(
+    iter: *mut ElfhpSymbolIter,
+    out: *mut ElfhpSymbol,
+) -> bool {
+    let (Some(iter), Some(out)) = (iter.as_mut(), out.as_mut()) else {
+        return false;
+    };
+    let Some((symbol, name)) = iter.symbols.get(iter.next) else {
+        return false;
+    };
+    iter.next += 1;
+    *out = ElfhpSymbol {
+        name: name.as_ptr(),
+        st_value: symbol.st_value,
+        st_size: symbol.st_size,
+        st_info: symbol.st_info,
+        st_other: symbol.st_other,
+        st_shndx: symbol.st_shndx,
+    };
+    true
+}
+
+/// Frees a symbol iterator and the names it handed out. NULL is ignored.
+///
+/// # Safety
+/// `iter` must be NULL or come from `elfhp_symbols_iter`, and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn elfhp_symbols_iter_free(iter: *mut ElfhpSymbolIter) {
+    if !iter.is_null() {
+        drop(Box::from_raw(iter));
+    }
+}
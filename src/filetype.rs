@@ -0,0 +1,174 @@
+//! What a file that is not ELF is instead, from its magic number, so that the
+//! message for it can say more than that it is not an ELF file.
+
+use crate::pe;
+
+/// `MH_MAGIC`, `MH_MAGIC_64` and the two byte-swapped.
+pub const MACH_O_MAGICS: [[u8; 4]; 4] = [
+    [0xfe, 0xed, 0xfa, 0xce],
+    [0xfe, 0xed, 0xfa, 0xcf],
+    [0xce, 0xfa, 0xed, 0xfe],
+    [0xcf, 0xfa, 0xed, 0xfe],
+];
+/// Universal Mach-O binaries and Java class files share this magic; the
+/// former follow it with a small count of architectures, the latter with
+/// their version.
+pub const FAT_MAGIC: &[u8] = &[0xca, 0xfe, 0xba, 0xbe];
+pub const FAT_MAX_ARCHS: u32 = 20;
+pub const FAT_ARCH_SIZE: usize = 20;
+
+const WASM_MAGIC: &[u8] = b"\0asm";
+const WASM_COMPONENT_VERSION: u32 = 0x1_000d;
+
+/// Magic numbers that say all there is to say, with the phrase for them.
+const SIMPLE_MAGICS: &[(&[u8], &str)] = &[
+    (b"!<arch>\n", "an ar archive, such as a static library"),
+    (b"!<thin>\n", "a thin ar archive"),
+    (&[0x1f, 0x8b], "gzip compressed data"),
+    (&[0xfd, b'7', b'z', b'X', b'Z', 0], "xz compressed data"),
+    (&[0x28, 0xb5, 0x2f, 0xfd], "zstd compressed data"),
+    (b"BZh", "bzip2 compressed data"),
+    (&[0x04, 0x22, 0x4d, 0x18], "LZ4 compressed data"),
+    (b"PK\x03\x04", "a zip archive"),
+    (b"7z\xbc\xaf\x27\x1c", "a 7-zip archive"),
+    (b"BC\xc0\xde", "LLVM bitcode"),
+    (&[0xde, 0xc0, 0x17, 0x0b], "LLVM bitcode in a wrapper"),
+    (b"hsqs", "a squashfs filesystem"),
+    (b"070701", "a cpio archive"),
+    (b"070707", "a cpio archive"),
+    (&[0x27, 0x05, 0x19, 0x56], "a U-Boot legacy image"),
+    (&[0xd0, 0x0d, 0xfe, 0xed], "a flattened device tree blob"),
+];
+
+/// How many leading bytes are looked at to call a file text.
+const TEXT_PROBE: usize = 512;
+
+fn be_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(
+        bytes.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+pub fn mach_o_cpu(cputype: u32) -> String {
+    match cputype {
+        7 => "i386".to_string(),
+        0x0100_0007 => "x86_64".to_string(),
+        12 => "arm".to_string(),
+        0x0100_000c => "arm64".to_string(),
+        0x0200_000c => "arm64_32".to_string(),
+        18 => "ppc".to_string(),
+        0x0100_0012 => "ppc64".to_string(),
+        other => format!("CPU type {:#x}", other),
+    }
+}
+
+/// The kind of Mach-O file a `filetype` stands for.
+pub fn mach_o_file_type(filetype: u32) -> &'static str {
+    match filetype {
+        1 => "object file",
+        2 => "executable",
+        3 => "fixed VM shared library",
+        4 => "core dump",
+        5 => "preloaded executable",
+        6 => "dynamic library",
+        7 => "dynamic linker",
+        8 => "bundle",
+        9 => "dynamic library stub",
+        10 => "debug symbols file",
+        11 => "kext bundle",
+        12 => "fileset",
+        _ => "binary",
+    }
+}
+
+fn mach_o(bytes: &[u8]) -> Option<String> {
+    let magic: [u8; 4] = bytes.get(..4)?.try_into().ok()?;
+    if !MACH_O_MAGICS.contains(&magic) {
+        return None;
+    }
+    // The first two magics are big-endian files, the others little-endian.
+    let little = magic[0] != 0xfe;
+    let read = |offset: usize| -> Option<u32> {
+        let raw: [u8; 4] = bytes.get(offset..offset + 4)?.try_into().ok()?;
+        Some(if little {
+            u32::from_le_bytes(raw)
+        } else {
+            u32::from_be_bytes(raw)
+        })
+    };
+    let kind = read(12).map_or("binary", mach_o_file_type);
+    let cpu = read(4).map_or_else(|| "truncated".to_string(), mach_o_cpu);
+    Some(format!("a Mach-O {} {}", cpu, kind))
+}
+
+fn fat(bytes: &[u8]) -> Option<String> {
+    if !bytes.starts_with(FAT_MAGIC) {
+        return None;
+    }
+    let count = be_u32(bytes, 4)?;
+    if count == 0 || count >= FAT_MAX_ARCHS {
+        return Some("a Java class file".to_string());
+    }
+    let cpus: Vec<String> = (0..count as usize)
+        .map_while(|index| be_u32(bytes, 8 + index * FAT_ARCH_SIZE))
+        .map(mach_o_cpu)
+        .collect();
+    Some(format!("a universal Mach-O binary ({})", cpus.join(", ")))
+}
+
+fn wasm(bytes: &[u8]) -> Option<String> {
+    if !bytes.starts_with(WASM_MAGIC) {
+        return None;
+    }
+    let version = u32::from_le_bytes(bytes.get(4..8)?.try_into().ok()?);
+    Some(if version == WASM_COMPONENT_VERSION {
+        "a WebAssembly component".to_string()
+    } else {
+        format!("a WebAssembly module (version {})", version)
+    })
+}
+
+fn script(bytes: &[u8]) -> Option<String> {
+    let line = bytes.strip_prefix(b"#!")?;
+    let line = line.split(|&byte| byte == b'\n').next()?;
+    let interpreter = String::from_utf8_lossy(line).trim().to_string();
+    Some(format!("a script for {}", interpreter))
+}
+
+fn is_text(bytes: &[u8]) -> bool {
+    let probe = &bytes[..bytes.len().min(TEXT_PROBE)];
+    !probe.is_empty()
+        && probe
+            .iter()
+            .all(|&byte| byte.is_ascii_graphic() || byte.is_ascii_whitespace())
+}
+
+/// What `bytes` look like as a phrase, e.g. `a Mach-O arm64 executable`, or
+/// `None` for a format this does not know.
+pub fn identify(bytes: &[u8]) -> Option<String> {
+    if let Some(pe) = pe::parse(bytes) {
+        return Some(format!("a {}", pe.describe(bytes)));
+    }
+    if bytes.starts_with(b"MZ") {
+        return Some("a DOS executable".to_string());
+    }
+    mach_o(bytes)
+        .or_else(|| fat(bytes))
+        .or_else(|| wasm(bytes))
+        .or_else(|| {
+            SIMPLE_MAGICS
+                .iter()
+                .find(|(magic, _)| bytes.starts_with(magic))
+                .map(|(_, phrase)| phrase.to_string())
+        })
+        .or_else(|| script(bytes))
+        .or_else(|| is_text(bytes).then(|| "a text file".to_string()))
+}
+
+/// Why a file cannot be read as ELF, naming what it looks like when known.
+pub fn not_elf(bytes: &[u8]) -> String {
+    match identify(bytes) {
+        Some(kind) => format!("not an ELF file, it looks like {}", kind),
+        None => "not an ELF file".to_string(),
+    }
+}
@@ -0,0 +1,136 @@
+use std::cell::RefCell;
+
+thread_local! {
+    /// Findings of the run, by file, for the `--report` files; `None` until a
+    /// report is asked for, so that nothing piles up otherwise.
+    static RECORDED: RefCell<Option<Vec<Checked>>> = const { RefCell::new(None) };
+}
+
+/// How serious a finding is; the names are SARIF's result levels.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+impl Severity {
+    pub fn parse(text: &str) -> Option<Severity> {
+        match text {
+            "error" => Some(Severity::Error),
+            "warning" => Some(Severity::Warning),
+            "note" => Some(Severity::Note),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Note => "note",
+        }
+    }
+}
+
+/// One result of a policy rule or plugin check about a file.
+#[derive(Clone)]
+pub struct Finding {
+    pub severity: Severity,
+    /// The name of the rule or check, such as `policy/pie` or `plugin/soname`.
+    pub check: String,
+    pub message: String,
+}
+
+impl Finding {
+    /// The table line `--plugin` and `policy` print for the finding.
+    pub fn describe(&self) -> String {
+        format!(
+            "{:<7} {}: {}",
+            self.severity.as_str(),
+            self.check,
+            self.message
+        )
+    }
+}
+
+/// The checks run on one file and what they found, for the reports.
+#[derive(Clone)]
+pub struct Checked {
+    pub path: String,
+    /// The names of the checks, passed or not, in the order they were run.
+    pub checks: Vec<String>,
+    pub findings: Vec<Finding>,
+}
+
+/// How many of the findings are errors.
+pub fn errors(findings: &[Finding]) -> usize {
+    findings
+        .iter()
+        .filter(|finding| finding.severity == Severity::Error)
+        .count()
+}
+
+/// Starts keeping findings for the reports.
+pub fn enable() {
+    RECORDED.with(|recorded| *recorded.borrow_mut() = Some(Vec::new()));
+}
+
+pub fn is_enabled() -> bool {
+    RECORDED.with(|recorded| recorded.borrow().is_some())
+}
+
+/// Keeps the checks run on `path` and their findings for the reports written
+/// at the end of the run. Checks that found something are added to `checks`.
+pub fn record<I>(path: &str, checks: I, findings: &[Finding])
+where
+    I: IntoIterator,
+    I::Item: ToString,
+{
+    RECORDED.with(|recorded| {
+        let mut recorded = recorded.borrow_mut();
+        let Some(recorded) = recorded.as_mut() else {
+            return;
+        };
+        let index = match recorded.iter().position(|checked| checked.path == path) {
+            Some(index) => index,
+            None => {
+                recorded.push(Checked {
+                    path: path.to_string(),
+                    checks: Vec::new(),
+                    findings: Vec::new(),
+                });
+                recorded.len() - 1
+            }
+        };
+        let checked = &mut recorded[index];
+        let names = checks
+            .into_iter()
+            .map(|name| name.to_string())
+            .chain(findings.iter().map(|finding| finding.check.clone()));
+        for name in names {
+            if !checked.checks.contains(&name) {
+                checked.checks.push(name);
+            }
+        }
+        checked.findings.extend_from_slice(findings);
+    });
+}
+
+/// How many warnings were recorded.
+pub fn warnings() -> usize {
+    RECORDED.with(|recorded| {
+        recorded.borrow().as_ref().map_or(0, |recorded| {
+            recorded
+                .iter()
+                .flat_map(|checked| &checked.findings)
+                .filter(|finding| finding.severity == Severity::Warning)
+                .count()
+        })
+    })
+}
+
+/// The files checked so far, in the order they were first checked.
+pub fn recorded() -> Vec<Checked> {
+    RECORDED.with(|recorded| recorded.borrow().clone().unwrap_or_default())
+}
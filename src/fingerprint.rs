@@ -0,0 +1,149 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::ops::Range;
+
+use crate::ranged::{self, read_range};
+use crate::section::SHT_NOBITS;
+
+/// Sections up to `BLOCK * SAMPLES` bytes are hashed whole, larger ones by
+/// `SAMPLES` blocks spread over them. The regions of the header table are
+/// always hashed whole.
+const BLOCK: usize = 4096;
+const SAMPLES: usize = 256;
+
+/// The regions the default header table reads.
+const HEADER_REGIONS: &[&str] = &["ELF header", "program headers", "section headers"];
+/// What a change the sampled hashes did not place is reported as.
+const UNSAMPLED: &str = "bytes outside the sampled blocks";
+
+/// One version of a file as hashes of the regions the reports read: the ELF
+/// header, the header tables and each section, so that a change can be traced
+/// to the regions it touched. Only the regions of the header table are hashed
+/// in full, so only a report of the header table can rely on an unchanged
+/// fingerprint. Files are read, not mapped, since a file being relinked can
+/// shrink under a mapping.
+pub struct Fingerprint {
+    regions: HashMap<String, u64>,
+    /// The region of the section holding the section names.
+    shstrtab: Option<String>,
+}
+
+/// A hash of `range` of `file` reading at most `BLOCK * SAMPLES` bytes of it,
+/// unless it has to be `whole`. A change outside the blocks it reads goes
+/// unnoticed unless the length changes too.
+fn sampled_hash(file: &mut File, range: Range<usize>, whole: bool) -> io::Result<u64> {
+    let mut hasher = DefaultHasher::new();
+    range.len().hash(&mut hasher);
+    if range.len() <= BLOCK * SAMPLES || whole {
+        read_range(file, range)?.hash(&mut hasher);
+    } else {
+        let last = range.len() - BLOCK;
+        for sample in 0..SAMPLES {
+            let start = range.start + sample * last / (SAMPLES - 1);
+            read_range(file, start..start + BLOCK)?.hash(&mut hasher);
+        }
+    }
+    Ok(hasher.finish())
+}
+
+impl Fingerprint {
+    pub fn of(path: &str) -> io::Result<Fingerprint> {
+        let mut file = File::open(path)?;
+        let len = file.metadata()?.len() as usize;
+        let mut regions = HashMap::new();
+        let headers = ranged::open_headers(path)?;
+        if !headers.is_elf() || headers.header_truncation().is_some() {
+            regions.insert(
+                "contents".to_string(),
+                sampled_hash(&mut file, 0..len, false)?,
+            );
+            return Ok(Fingerprint {
+                regions,
+                shstrtab: None,
+            });
+        }
+        let ehsize = if headers.is_64bit() { 64 } else { 52 };
+        for (name, range) in
+            HEADER_REGIONS
+                .iter()
+                .zip([0..ehsize, headers.program_table(), headers.section_table()])
+        {
+            let range = range.start.min(len)..range.end.min(len);
+            regions.insert(name.to_string(), sampled_hash(&mut file, range, true)?);
+        }
+
+        // The image holds the headers only, the section names are read here.
+        let sections = headers.section_headers();
+        let shstrndx = headers.shstrndx();
+        let names = match sections.get(shstrndx) {
+            Some(strtab) => {
+                let start = strtab.sh_offset as usize;
+                read_range(
+                    &mut file,
+                    start..start.saturating_add(strtab.sh_size as usize),
+                )?
+            }
+            None => Vec::new(),
+        };
+        let mut shstrtab = None;
+        for section in &sections {
+            if section.sh_type == SHT_NOBITS {
+                continue;
+            }
+            let name = names
+                .get(section.sh_name as usize..)
+                .and_then(|tail| tail.split(|&byte| byte == 0).next())
+                .map(String::from_utf8_lossy)
+                .unwrap_or_default();
+            // Relocatable files can have several sections of one name.
+            let name = match &*name {
+                "" => format!("section {}", section.index),
+                name if regions.contains_key(name) => format!("{} [{}]", name, section.index),
+                name => name.to_string(),
+            };
+            let start = (section.sh_offset as usize).min(len);
+            let end = start.saturating_add(section.sh_size as usize).min(len);
+            let whole = section.index == shstrndx;
+            regions.insert(name.clone(), sampled_hash(&mut file, start..end, whole)?);
+            if section.index == shstrndx {
+                shstrtab = Some(name);
+            }
+        }
+        Ok(Fingerprint { regions, shstrtab })
+    }
+
+    /// The regions that differ from `previous`, sorted, those in only one of
+    /// the two included, for a file whose modification time or size changed.
+    /// With `header_only`, only the regions the default header table reads,
+    /// which are hashed whole: none means the table is as it was. Other reports
+    /// read sampled regions too, so a change the samples miss is still a change.
+    pub fn changed_since(&self, previous: &Fingerprint, header_only: bool) -> Vec<String> {
+        let mut changed: Vec<String> = self
+            .regions
+            .iter()
+            .filter(|(name, hash)| previous.regions.get(*name) != Some(hash))
+            .map(|(name, _)| name.clone())
+            .chain(
+                previous
+                    .regions
+                    .keys()
+                    .filter(|name| !self.regions.contains_key(*name))
+                    .cloned(),
+            )
+            .filter(|name| {
+                !header_only
+                    || HEADER_REGIONS.contains(&name.as_str())
+                    || name == "contents"
+                    || self.shstrtab.as_ref() == Some(name)
+            })
+            .collect();
+        changed.sort();
+        if changed.is_empty() && !header_only {
+            changed.push(UNSAMPLED.to_string());
+        }
+        changed
+    }
+}
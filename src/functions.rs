@@ -0,0 +1,111 @@
+use crate::output;
+use crate::symbol::{SHN_UNDEF, STT_FUNC};
+use crate::{display_line, Loader};
+
+/// Where a function boundary was learnt from.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Source {
+    Symbol,
+    EhFrame,
+    Both,
+}
+
+impl Source {
+    fn as_str(&self) -> &str {
+        match *self {
+            Source::Symbol => "symbol",
+            Source::EhFrame => "eh_frame",
+            Source::Both => "symbol+eh_frame",
+        }
+    }
+}
+
+pub struct Function {
+    pub start: u64,
+    pub size: u64,
+    pub name: Option<String>,
+    pub source: Source,
+}
+
+impl Loader {
+    /// Function start addresses and sizes from the FUNC symbols merged with the
+    /// FDE ranges of the unwind tables. An FDE starting at a symbol confirms it and
+    /// gives the size when the symbol has none; one starting inside a symbol's
+    /// range is part of that function. The rest, such as every function of a
+    /// stripped binary, are listed without a name.
+    pub fn functions(&self) -> Vec<Function> {
+        let mut symbols = self.static_symbols();
+        if symbols.is_empty() {
+            symbols = self.dynamic_symbols();
+        }
+        let mut defined: Vec<_> = symbols
+            .into_iter()
+            .filter(|symbol| symbol.kind() == STT_FUNC && symbol.st_shndx != SHN_UNDEF)
+            .collect();
+        // Aliases share an address; the first name stands for them. In relocatable
+        // objects addresses are per section, so the section tells them apart.
+        defined.sort_by_key(|symbol| (symbol.st_value, symbol.st_shndx));
+        defined.dedup_by_key(|symbol| (symbol.st_value, symbol.st_shndx));
+        let mut functions: Vec<Function> = defined
+            .into_iter()
+            .map(|symbol| Function {
+                start: symbol.st_value,
+                size: symbol.st_size,
+                name: Some(symbol.name),
+                source: Source::Symbol,
+            })
+            .collect();
+
+        let mut unnamed = Vec::new();
+        for fde in self.fde_ranges() {
+            let index = functions.partition_point(|function| function.start <= fde.start);
+            match index.checked_sub(1).map(|index| &mut functions[index]) {
+                Some(function) if function.start == fde.start => {
+                    function.source = Source::Both;
+                    if function.size == 0 {
+                        function.size = fde.size;
+                    }
+                }
+                Some(function) if fde.start < function.start.saturating_add(function.size) => {}
+                _ => unnamed.push(Function {
+                    start: fde.start,
+                    size: fde.size,
+                    name: None,
+                    source: Source::EhFrame,
+                }),
+            }
+        }
+        functions.extend(unnamed);
+        functions.sort_by_key(|function| function.start);
+        functions
+    }
+}
+
+/// `--functions`: one line per function with its start address, size, where it
+/// was found and its name, in address order.
+pub fn display_functions(path: &str, loader: &Loader) {
+    let functions = loader.functions();
+    let count = |source| {
+        functions
+            .iter()
+            .filter(|function| function.source == source)
+            .count()
+    };
+    output::begin(path);
+    display_line("Functions", &functions.len().to_string());
+    display_line("Symbol Only", &count(Source::Symbol).to_string());
+    display_line("eh_frame Only", &count(Source::EhFrame).to_string());
+    for function in &functions {
+        output::item(
+            1,
+            &format!(
+                "{:#018x} {:>8} {:<15} {}",
+                function.start,
+                function.size,
+                function.source.as_str(),
+                function.name.as_deref().unwrap_or("-")
+            ),
+        );
+    }
+    output::end();
+}
@@ -0,0 +1,221 @@
+//! `verify`: compares the PT_LOAD segments of firmware images with those of a
+//! golden reference image, byte by byte, leaving out the address ranges given
+//! with `--mask`, such as calibration data or a build stamp.
+
+use std::ops::Range;
+
+use crate::checksum::sha256;
+use crate::note::hex_string;
+use crate::output;
+use crate::segment::{ProgramHeader, PT_LOAD};
+use crate::{display_line, Loader};
+
+/// The differing runs listed per segment; the byte count covers all of them.
+const MAX_RUNS: usize = 16;
+/// The bytes of each image shown for a run.
+const SHOWN_BYTES: usize = 8;
+
+fn load_segments(loader: &Loader) -> Vec<ProgramHeader> {
+    loader
+        .program_headers()
+        .into_iter()
+        .filter(|header| header.p_type == PT_LOAD)
+        .collect()
+}
+
+/// The file contents of a segment, cut short where the file ends.
+fn contents<'a>(loader: &'a Loader, segment: &ProgramHeader) -> &'a [u8] {
+    let bytes = loader.bytes();
+    let start = (segment.p_offset as usize).min(bytes.len());
+    let end = start
+        .saturating_add(segment.p_filesz as usize)
+        .min(bytes.len());
+    &bytes[start..end]
+}
+
+fn is_masked(address: u64, masks: &[Range<u64>]) -> bool {
+    masks.iter().any(|mask| mask.contains(&address))
+}
+
+/// SHA-256 of a segment's contents with the masked bytes read as zero, so
+/// that images differing only there have the same digest.
+fn digest(contents: &[u8], vaddr: u64, masks: &[Range<u64>]) -> String {
+    let mut contents = contents.to_vec();
+    let end = vaddr.saturating_add(contents.len() as u64);
+    for mask in masks {
+        let start = mask.start.clamp(vaddr, end) - vaddr;
+        let stop = mask.end.clamp(vaddr, end) - vaddr;
+        contents[start as usize..stop as usize].fill(0);
+    }
+    sha256(&contents)
+}
+
+/// The offsets into the segment, as ranges, where the two contents differ
+/// outside the masks. Bytes only one of them has differ too.
+fn differing_runs(
+    golden: &[u8],
+    target: &[u8],
+    vaddr: u64,
+    masks: &[Range<u64>],
+) -> Vec<Range<usize>> {
+    let mut runs: Vec<Range<usize>> = Vec::new();
+    for index in 0..golden.len().max(target.len()) {
+        if golden.get(index) == target.get(index)
+            || is_masked(vaddr.wrapping_add(index as u64), masks)
+        {
+            continue;
+        }
+        match runs.last_mut() {
+            Some(run) if run.end == index => run.end += 1,
+            _ => runs.push(index..index + 1),
+        }
+    }
+    runs
+}
+
+/// Up to `SHOWN_BYTES` of `contents` from `run`, `-` when it has none there.
+fn shown(contents: &[u8], run: &Range<usize>) -> String {
+    let end = run.end.min(run.start + SHOWN_BYTES).min(contents.len());
+    match contents.get(run.start..end) {
+        Some(bytes) if !bytes.is_empty() => {
+            let ellipsis = if end < run.end { "..." } else { "" };
+            format!("{}{}", hex_string(bytes), ellipsis)
+        }
+        _ => "-".to_string(),
+    }
+}
+
+/// Compares one segment of the target with the golden one at the same index,
+/// listing what differs and returning whether anything does. Addresses and
+/// masks are those of the golden segment.
+fn compare_segment(
+    index: usize,
+    golden: (&ProgramHeader, &[u8]),
+    target: (&ProgramHeader, &[u8]),
+    masks: &[Range<u64>],
+) -> bool {
+    let (golden_header, golden_contents) = golden;
+    let (target_header, target_contents) = target;
+    let fields = [
+        ("p_vaddr", golden_header.p_vaddr, target_header.p_vaddr),
+        ("p_filesz", golden_header.p_filesz, target_header.p_filesz),
+        ("p_memsz", golden_header.p_memsz, target_header.p_memsz),
+    ];
+    let mut changes: Vec<String> = fields
+        .iter()
+        .filter(|(_, golden, target)| golden != target)
+        .map(|(name, golden, target)| format!("{} {:#x} -> {:#x}", name, golden, target))
+        .collect();
+    if golden_header.p_flags != target_header.p_flags {
+        changes.push(format!(
+            "p_flags {} -> {}",
+            golden_header.permissions(),
+            target_header.permissions()
+        ));
+    }
+    let vaddr = golden_header.p_vaddr;
+    let runs = differing_runs(golden_contents, target_contents, vaddr, masks);
+    let differing: usize = runs.iter().map(|run| run.len()).sum();
+
+    let status = if changes.is_empty() && runs.is_empty() {
+        "matches".to_string()
+    } else {
+        format!("{} byte(s) differ in {} run(s)", differing, runs.len())
+    };
+    output::item(
+        1,
+        &format!(
+            "LOAD[{}] {:#010x} {} {} bytes: {}, sha256 {}",
+            index,
+            vaddr,
+            target_header.permissions(),
+            target_contents.len(),
+            status,
+            digest(target_contents, vaddr, masks)
+        ),
+    );
+    for change in &changes {
+        output::item(2, change);
+    }
+    for run in runs.iter().take(MAX_RUNS) {
+        output::item(
+            2,
+            &format!(
+                "{:#010x}..{:#010x}: {} -> {}",
+                vaddr.wrapping_add(run.start as u64),
+                vaddr.wrapping_add(run.end as u64),
+                shown(golden_contents, run),
+                shown(target_contents, run)
+            ),
+        );
+    }
+    if runs.len() > MAX_RUNS {
+        output::item(2, &format!("... {} more run(s)", runs.len() - MAX_RUNS));
+    }
+    !(changes.is_empty() && runs.is_empty())
+}
+
+fn format_mask(mask: &Range<u64>) -> String {
+    format!("{:#x}-{:#x}", mask.start, mask.end)
+}
+
+/// `verify --golden`: each target's LOAD segments against the golden image's,
+/// paired by their order. A target fails when any segment differs outside
+/// the masks, or when the two have different numbers of segments.
+pub fn display_verify(
+    golden: &(String, Loader),
+    targets: &[(String, Loader)],
+    masks: &[Range<u64>],
+) -> Vec<(String, String)> {
+    let (golden_label, golden_loader) = golden;
+    let golden_segments = load_segments(golden_loader);
+    let mut failures = Vec::new();
+    for (label, loader) in targets {
+        let segments = load_segments(loader);
+        output::begin(label);
+        display_line("Golden", golden_label);
+        let masked: Vec<String> = masks.iter().map(format_mask).collect();
+        display_line(
+            "Masked",
+            &if masked.is_empty() {
+                "none".to_string()
+            } else {
+                masked.join(", ")
+            },
+        );
+        display_line(
+            "LOAD Segments",
+            &format!("{} (golden {})", segments.len(), golden_segments.len()),
+        );
+        let mut differing = 0;
+        for (index, (golden, target)) in golden_segments.iter().zip(&segments).enumerate() {
+            let golden = (golden, contents(golden_loader, golden));
+            let target = (target, contents(loader, target));
+            if compare_segment(index, golden, target, masks) {
+                differing += 1;
+            }
+        }
+        let paired = golden_segments.len().min(segments.len());
+        for (index, segment) in golden_segments.iter().enumerate().skip(paired) {
+            output::item(
+                1,
+                &format!("LOAD[{}] {:#010x}: only in golden", index, segment.p_vaddr),
+            );
+        }
+        for (index, segment) in segments.iter().enumerate().skip(paired) {
+            output::item(
+                1,
+                &format!("LOAD[{}] {:#010x}: only in target", index, segment.p_vaddr),
+            );
+        }
+        differing += golden_segments.len().max(segments.len()) - paired;
+        output::end();
+        if differing > 0 {
+            failures.push((
+                label.clone(),
+                format!("{} LOAD segment(s) differ from {}", differing, golden_label),
+            ));
+        }
+    }
+    failures
+}
@@ -0,0 +1,132 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::output;
+
+/// A directed graph printed as Graphviz DOT or Mermaid by `--graph`. Nodes are
+/// keyed by a unique text and may belong to a group, drawn as a cluster.
+#[derive(Default)]
+pub struct Graph {
+    nodes: Vec<Node>,
+    index: HashMap<String, usize>,
+    edges: Vec<(usize, usize)>,
+    seen_edges: HashSet<(usize, usize)>,
+}
+
+struct Node {
+    key: String,
+    label: String,
+    group: Option<String>,
+}
+
+/// Quotes a DOT identifier.
+fn dot_id(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Quotes a Mermaid label; Mermaid has no escapes, only entity codes.
+fn mermaid_label(text: &str) -> String {
+    format!("[\"{}\"]", text.replace('"', "#quot;"))
+}
+
+impl Graph {
+    /// Adds a node unless one with the same key exists, and returns its index.
+    pub fn node(&mut self, key: &str, label: &str, group: Option<&str>) -> usize {
+        if let Some(&index) = self.index.get(key) {
+            return index;
+        }
+        self.nodes.push(Node {
+            key: key.to_string(),
+            label: label.to_string(),
+            group: group.map(str::to_string),
+        });
+        self.index.insert(key.to_string(), self.nodes.len() - 1);
+        self.nodes.len() - 1
+    }
+
+    /// Adds an edge between two nodes, once.
+    pub fn edge(&mut self, from: usize, to: usize) {
+        if self.seen_edges.insert((from, to)) {
+            self.edges.push((from, to));
+        }
+    }
+
+    /// Node indices by group, in group name order, and the ungrouped ones.
+    fn groups(&self) -> (BTreeMap<&str, Vec<usize>>, Vec<usize>) {
+        let mut groups: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+        let mut ungrouped = Vec::new();
+        for (index, node) in self.nodes.iter().enumerate() {
+            match &node.group {
+                Some(group) => groups.entry(group).or_default().push(index),
+                None => ungrouped.push(index),
+            }
+        }
+        (groups, ungrouped)
+    }
+
+    fn dot_lines(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        let node = |index: usize| {
+            let node = &self.nodes[index];
+            format!("{} [label={}];", dot_id(&node.key), dot_id(&node.label))
+        };
+        let (groups, ungrouped) = self.groups();
+        lines.push("digraph {".to_string());
+        lines.push("  rankdir=LR;".to_string());
+        lines.push("  node [shape=box];".to_string());
+        for (number, (group, members)) in groups.iter().enumerate() {
+            lines.push(format!("  subgraph cluster_{} {{", number));
+            lines.push(format!("    label={};", dot_id(group)));
+            for &index in members {
+                lines.push(format!("    {}", node(index)));
+            }
+            lines.push("  }".to_string());
+        }
+        for index in ungrouped {
+            lines.push(format!("  {}", node(index)));
+        }
+        for &(from, to) in &self.edges {
+            lines.push(format!(
+                "  {} -> {};",
+                dot_id(&self.nodes[from].key),
+                dot_id(&self.nodes[to].key)
+            ));
+        }
+        lines.push("}".to_string());
+        lines
+    }
+
+    fn mermaid_lines(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        let node = |index: usize| format!("n{}{}", index, mermaid_label(&self.nodes[index].label));
+        let (groups, ungrouped) = self.groups();
+        lines.push("graph LR".to_string());
+        for (number, (group, members)) in groups.iter().enumerate() {
+            lines.push(format!("  subgraph g{}{}", number, mermaid_label(group)));
+            for &index in members {
+                lines.push(format!("    {}", node(index)));
+            }
+            lines.push("  end".to_string());
+        }
+        for index in ungrouped {
+            lines.push(format!("  {}", node(index)));
+        }
+        for &(from, to) in &self.edges {
+            lines.push(format!("  n{} --> n{}", from, to));
+        }
+        lines
+    }
+
+    /// Prints the graph in one of `cli::GRAPH_FORMATS`, one item per line, so
+    /// that `--format json` carries the same text.
+    pub fn print(&self, format: &str) {
+        let lines = match format {
+            "mermaid" => self.mermaid_lines(),
+            _ => self.dot_lines(),
+        };
+        output::begin("");
+        for line in lines {
+            output::item(0, &line);
+        }
+        output::end();
+    }
+}
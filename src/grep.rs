@@ -0,0 +1,116 @@
+use regex::bytes::{Regex, RegexBuilder};
+
+use crate::output;
+use crate::section::SectionHeader;
+use crate::segment::{ProgramHeader, PT_LOAD};
+use crate::{display_line, Loader};
+
+/// Bytes of a match shown before it is cut short.
+const SHOWN_BYTES: usize = 16;
+
+/// What `--grep-bytes` or `--grep-regex` looks for.
+pub struct GrepPattern {
+    pub regex: Regex,
+    /// Whether the pattern was hex, so matches are shown as bytes, not text.
+    pub hex: bool,
+}
+
+impl GrepPattern {
+    /// A hex pattern such as `48 8b ?? 24`: pairs of hex digits, `??` for any
+    /// byte, spaces ignored.
+    pub fn hex(pattern: &str) -> Result<GrepPattern, String> {
+        let digits: Vec<char> = pattern.chars().filter(|c| !c.is_whitespace()).collect();
+        if digits.is_empty() || !digits.len().is_multiple_of(2) {
+            return Err(format!("invalid hex pattern: {}", pattern));
+        }
+        let mut source = String::from("(?s-u)");
+        for pair in digits.chunks(2) {
+            let pair: String = pair.iter().collect();
+            if pair == "??" {
+                source.push('.');
+            } else if u8::from_str_radix(&pair, 16).is_ok() {
+                source.push_str(&format!("\\x{}", pair));
+            } else {
+                return Err(format!("invalid hex pattern: {}", pattern));
+            }
+        }
+        let regex = Regex::new(&source).map_err(|error| error.to_string())?;
+        Ok(GrepPattern { regex, hex: true })
+    }
+
+    /// A regular expression over the file's bytes, for strings.
+    pub fn regex(pattern: &str) -> Result<GrepPattern, String> {
+        let regex = RegexBuilder::new(pattern)
+            .unicode(false)
+            .build()
+            .map_err(|error| format!("invalid regular expression: {}", error))?;
+        Ok(GrepPattern { regex, hex: false })
+    }
+
+    fn show(&self, bytes: &[u8]) -> String {
+        let shown = &bytes[..bytes.len().min(SHOWN_BYTES)];
+        let more = if bytes.len() > SHOWN_BYTES {
+            " ..."
+        } else {
+            ""
+        };
+        if self.hex {
+            let hex: Vec<String> = shown.iter().map(|byte| format!("{:02x}", byte)).collect();
+            format!("{}{}", hex.join(" "), more)
+        } else {
+            format!("{:?}{}", String::from_utf8_lossy(shown), more)
+        }
+    }
+}
+
+/// The section and the PT_LOAD segment a file offset lies in.
+pub fn locate<'a>(
+    sections: &'a [SectionHeader],
+    segments: &'a [ProgramHeader],
+    offset: u64,
+) -> (Option<&'a SectionHeader>, Option<&'a ProgramHeader>) {
+    let section = sections
+        .iter()
+        .find(|section| section.index != 0 && section.contains_offset(offset));
+    let segment = segments.iter().find(|segment| {
+        segment.p_type == PT_LOAD
+            && offset >= segment.p_offset
+            && offset < segment.p_offset.saturating_add(segment.p_filesz)
+    });
+    (section, segment)
+}
+
+/// Prints every match of the pattern in the file with its offset, section and,
+/// when it is loaded, segment permissions and address.
+pub fn display_grep(path: &str, loader: &Loader, pattern: &GrepPattern) {
+    let sections = loader.section_headers();
+    let segments = loader.program_headers();
+    let matches: Vec<_> = pattern.regex.find_iter(loader.bytes()).collect();
+    output::begin(path);
+    display_line("Matches", &matches.len().to_string());
+    for found in &matches {
+        let offset = found.start() as u64;
+        let (section, segment) = locate(&sections, &segments, offset);
+        let mut place = match section {
+            Some(section) => section.name.clone(),
+            None => "-".to_string(),
+        };
+        if let Some(segment) = segment {
+            place.push_str(&format!(
+                " LOAD {} {:#x}",
+                segment.permissions(),
+                segment.p_vaddr + (offset - segment.p_offset)
+            ));
+        }
+        output::item(
+            1,
+            &format!(
+                "{:#010x} {:<32} {}",
+                offset,
+                place,
+                pattern.show(found.as_bytes())
+            ),
+        );
+    }
+    output::end();
+}
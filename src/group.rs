@@ -0,0 +1,90 @@
+use crate::output;
+use crate::section::{SectionHeader, SHT_GROUP};
+use crate::symbol::STT_SECTION;
+use crate::{display_line, Loader};
+
+/// Set in the first word of a SHT_GROUP section when the linker should keep only
+/// one group with this signature across all input objects.
+pub const GRP_COMDAT: u32 = 0x1;
+
+pub struct SectionGroup {
+    /// Name of the SHT_GROUP section itself, usually `.group`.
+    pub section: String,
+    /// Name of the signature symbol the linker deduplicates groups by.
+    pub signature: String,
+    pub flags: u32,
+    pub members: Vec<SectionHeader>,
+}
+
+impl SectionGroup {
+    pub fn is_comdat(&self) -> bool {
+        self.flags & GRP_COMDAT != 0
+    }
+}
+
+impl Loader {
+    /// Reads every SHT_GROUP section: a flag word followed by the member section indices.
+    pub fn section_groups(&self) -> Vec<SectionGroup> {
+        let sections = self.section_headers();
+        let mut groups = Vec::new();
+        for group in sections
+            .iter()
+            .filter(|section| section.sh_type == SHT_GROUP)
+        {
+            let Some(flags) = self.read_u32(group.sh_offset as usize) else {
+                continue;
+            };
+            let members = (1..group.sh_size as usize / 4)
+                .filter_map(|word| self.read_u32(group.sh_offset as usize + word * 4))
+                .filter_map(|index| sections.get(index as usize).cloned())
+                .collect();
+            groups.push(SectionGroup {
+                section: group.name.clone(),
+                signature: self.group_signature(group, &sections),
+                flags,
+                members,
+            });
+        }
+        groups
+    }
+
+    /// sh_link names the symbol table and sh_info the signature symbol in it. A
+    /// section symbol has no name of its own, so the section's name stands in.
+    fn group_signature(&self, group: &SectionHeader, sections: &[SectionHeader]) -> String {
+        let Some(table) = sections.get(group.sh_link as usize) else {
+            return String::new();
+        };
+        let symbols = self.symbols_in(table, sections);
+        match symbols.get(group.sh_info as usize) {
+            Some(symbol) if symbol.kind() == STT_SECTION => sections
+                .get(symbol.st_shndx as usize)
+                .map(|section| section.name.clone())
+                .unwrap_or_default(),
+            Some(symbol) => symbol.name.clone(),
+            None => String::new(),
+        }
+    }
+}
+
+pub fn display_groups(path: &str, loader: &Loader) {
+    let groups = loader.section_groups();
+    output::begin(path);
+    display_line("Section Groups", &groups.len().to_string());
+    for group in &groups {
+        let kind = if group.is_comdat() { "COMDAT" } else { "group" };
+        output::item(
+            1,
+            &format!(
+                "{} {} ({}, {} section(s))",
+                kind,
+                group.signature,
+                group.section,
+                group.members.len()
+            ),
+        );
+        for member in &group.members {
+            output::item(2, &format!("[{:>2}] {}", member.index, member.name));
+        }
+    }
+    output::end();
+}
@@ -0,0 +1,73 @@
+use std::sync::{OnceLock, PoisonError, RwLock};
+
+/// The languages `--lang` accepts; English is the labels as the code writes them.
+pub const LANGUAGES: &[&str] = &["en", "ja"];
+
+/// The Japanese catalog, as `English<TAB>Japanese` lines.
+const JA: &str = include_str!("../locales/ja.tsv");
+
+/// The catalog labels are translated with, on every thread; `None` for English.
+static CATALOG: RwLock<Option<&'static [(&'static str, &'static str)]>> = RwLock::new(None);
+
+/// Reads a catalog, skipping comments and blank lines.
+fn parse(catalog: &'static str) -> Vec<(&'static str, &'static str)> {
+    catalog
+        .lines()
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.split_once('\t'))
+        .collect()
+}
+
+/// Translates the labels of the table output from now on.
+pub fn set_language(language: &str) {
+    static JA_CATALOG: OnceLock<Vec<(&str, &str)>> = OnceLock::new();
+    let catalog = match language {
+        "ja" => Some(JA_CATALOG.get_or_init(|| parse(JA)).as_slice()),
+        _ => None,
+    };
+    *CATALOG.write().unwrap_or_else(PoisonError::into_inner) = catalog;
+}
+
+/// The label in the selected language, or as given when the catalog lacks it.
+pub fn label(key: &str) -> &str {
+    let catalog = *CATALOG.read().unwrap_or_else(PoisonError::into_inner);
+    catalog
+        .and_then(|catalog| {
+            catalog
+                .iter()
+                .find(|(english, _)| *english == key)
+                .map(|&(_, translated)| translated)
+        })
+        .unwrap_or(key)
+}
+
+/// Columns a character takes in a terminal: two for CJK and full-width forms.
+fn char_width(c: char) -> usize {
+    match c as u32 {
+        0x1100..=0x115f | 0x2e80..=0xa4cf | 0xac00..=0xd7a3 | 0xf900..=0xfaff => 2,
+        0xfe30..=0xfe4f | 0xff00..=0xff60 | 0xffe0..=0xffe6 => 2,
+        _ => 1,
+    }
+}
+
+fn spare(label: &str, width: usize) -> usize {
+    let used: usize = label.chars().map(char_width).sum();
+    width.saturating_sub(used)
+}
+
+/// The label padded with spaces to `width` terminal columns, as `{:<width}`
+/// would for ASCII.
+pub fn pad(label: &str, width: usize) -> String {
+    format!("{}{}", label, " ".repeat(spare(label, width)))
+}
+
+/// The label centred in `width` terminal columns, as `{:^width}` would for ASCII.
+pub fn center(label: &str, width: usize) -> String {
+    let spare = spare(label, width);
+    format!(
+        "{}{}{}",
+        " ".repeat(spare / 2),
+        label,
+        " ".repeat(spare - spare / 2)
+    )
+}
@@ -0,0 +1,176 @@
+//! `--ima`: the `security.ima` and `security.evm` extended attributes that
+//! IMA appraisal checks files against, and whether an IMA digest still
+//! matches the file, so that a binary rejected at exec on a locked-down host
+//! can be told apart from one that will run.
+
+use std::path::Path;
+
+use sha2::{Digest, Sha224, Sha256, Sha384, Sha512};
+
+use crate::note::hex_string;
+use crate::xattr;
+use crate::{display_line, output, Loader};
+
+pub const XATTR_IMA: &str = "security.ima";
+pub const XATTR_EVM: &str = "security.evm";
+
+/// The first byte of the attributes, from `enum evm_ima_xattr_type`.
+const IMA_XATTR_DIGEST: u8 = 0x01;
+const EVM_XATTR_HMAC: u8 = 0x02;
+const EVM_IMA_XATTR_DIGSIG: u8 = 0x03;
+const IMA_XATTR_DIGEST_NG: u8 = 0x04;
+const EVM_XATTR_PORTABLE_DIGSIG: u8 = 0x05;
+const IMA_VERITY_DIGSIG: u8 = 0x06;
+
+/// Indexes of `enum hash_algo` in include/uapi/linux/hash_info.h.
+const HASH_ALGO_SHA1: u8 = 2;
+const HASH_ALGO_SHA256: u8 = 4;
+const HASH_ALGO_SHA384: u8 = 5;
+const HASH_ALGO_SHA512: u8 = 6;
+const HASH_ALGO_SHA224: u8 = 7;
+
+fn hash_name(algorithm: u8) -> String {
+    match algorithm {
+        0 => "md4".to_string(),
+        1 => "md5".to_string(),
+        HASH_ALGO_SHA1 => "sha1".to_string(),
+        3 => "rmd160".to_string(),
+        HASH_ALGO_SHA256 => "sha256".to_string(),
+        HASH_ALGO_SHA384 => "sha384".to_string(),
+        HASH_ALGO_SHA512 => "sha512".to_string(),
+        HASH_ALGO_SHA224 => "sha224".to_string(),
+        17 => "sm3".to_string(),
+        other => format!("hash algorithm {}", other),
+    }
+}
+
+/// The digest of `bytes` with one of the `hash_info.h` algorithms, or
+/// `None` for those not built in. SHA-1 is not, as IMA has long defaulted to
+/// SHA-256.
+fn file_digest(algorithm: u8, bytes: &[u8]) -> Option<Vec<u8>> {
+    Some(match algorithm {
+        HASH_ALGO_SHA256 => Sha256::digest(bytes).to_vec(),
+        HASH_ALGO_SHA384 => Sha384::digest(bytes).to_vec(),
+        HASH_ALGO_SHA512 => Sha512::digest(bytes).to_vec(),
+        HASH_ALGO_SHA224 => Sha224::digest(bytes).to_vec(),
+        _ => return None,
+    })
+}
+
+/// The `signature_v2_hdr` of a signature attribute: the hash algorithm and
+/// key ID it names.
+fn signature(value: &[u8]) -> String {
+    match value {
+        [_, 2, algorithm, key @ ..] if key.len() >= 4 => format!(
+            "{}, key ID {}",
+            hash_name(*algorithm),
+            hex_string(&key[..4])
+        ),
+        [_, version, ..] => format!("signature version {}", version),
+        _ => "truncated".to_string(),
+    }
+}
+
+/// Prints `security.ima` and returns why it does not match the file, if it
+/// holds a digest that does not.
+fn display_ima_xattr(value: &[u8], bytes: &[u8]) -> Option<String> {
+    let (algorithm, stored) = match value {
+        [IMA_XATTR_DIGEST, digest @ ..] => (HASH_ALGO_SHA1, digest),
+        [IMA_XATTR_DIGEST_NG, algorithm, digest @ ..] => (*algorithm, digest),
+        [EVM_IMA_XATTR_DIGSIG, ..] => {
+            display_line(
+                "IMA",
+                &format!("Signature ({}), not checked", signature(value)),
+            );
+            return None;
+        }
+        [IMA_VERITY_DIGSIG, ..] => {
+            display_line(
+                "IMA",
+                &format!("fs-verity signature ({}), not checked", signature(value)),
+            );
+            return None;
+        }
+        [other, ..] => {
+            display_line("IMA", &format!("Unknown type {:#x}", other));
+            return None;
+        }
+        [] => {
+            display_line("IMA", "Empty");
+            return None;
+        }
+    };
+    display_line(
+        "IMA",
+        &format!("{} digest {}", hash_name(algorithm), hex_string(stored)),
+    );
+    match file_digest(algorithm, bytes) {
+        Some(digest) if digest == stored => {
+            display_line("IMA Digest", "Matches the file");
+            None
+        }
+        Some(digest) => {
+            display_line("IMA Digest", "Does not match the file");
+            output::item(1, &format!("file {}", hex_string(&digest)));
+            Some(format!(
+                "the {} digest in {} does not match the file",
+                hash_name(algorithm),
+                XATTR_IMA
+            ))
+        }
+        None => {
+            display_line("IMA Digest", "Not checked, unsupported algorithm");
+            None
+        }
+    }
+}
+
+fn evm_kind(value: &[u8]) -> String {
+    match value.first() {
+        Some(&EVM_XATTR_HMAC) => "HMAC, checked by the kernel only".to_string(),
+        Some(&EVM_IMA_XATTR_DIGSIG) => format!("Signature ({}), not checked", signature(value)),
+        Some(&EVM_XATTR_PORTABLE_DIGSIG) => {
+            format!("Portable signature ({}), not checked", signature(value))
+        }
+        Some(other) => format!("Unknown type {:#x}", other),
+        None => "Empty".to_string(),
+    }
+}
+
+/// `--ima`: the IMA and EVM attributes of every target, failing the files
+/// whose IMA digest no longer matches their contents.
+pub fn display_ima(targets: &[(String, Loader)]) -> Vec<(String, String)> {
+    let mut failures = Vec::new();
+    for (label, loader) in targets {
+        let path = Path::new(label);
+        if !path.is_file() {
+            failures.push((
+                label.clone(),
+                "not a file with attributes of its own".to_string(),
+            ));
+            continue;
+        }
+        output::begin(label);
+        let problem = match xattr::read(path, XATTR_IMA) {
+            Ok(Some(value)) => display_ima_xattr(&value, loader.file_bytes()),
+            Ok(None) => {
+                display_line("IMA", "None");
+                None
+            }
+            Err(error) => {
+                display_line("IMA", &format!("Unreadable: {}", error));
+                Some(format!("cannot read {}: {}", XATTR_IMA, error))
+            }
+        };
+        match xattr::read(path, XATTR_EVM) {
+            Ok(Some(value)) => display_line("EVM", &evm_kind(&value)),
+            Ok(None) => display_line("EVM", "None"),
+            Err(error) => display_line("EVM", &format!("Unreadable: {}", error)),
+        }
+        output::end();
+        if let Some(problem) = problem {
+            failures.push((label.clone(), problem));
+        }
+    }
+    failures
+}
@@ -0,0 +1,282 @@
+use log::{debug, info};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::io::Read;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use crate::aggregate::display_aggregate;
+use crate::archive::{self, Member};
+use crate::Loader;
+
+/// Where an image's blobs come from: an OCI layout directory, or a tarball of an
+/// OCI layout or of a `docker save` archive, whose members are read in place.
+enum Source {
+    Directory(PathBuf),
+    Tarball {
+        file: Loader,
+        members: HashMap<String, Range<usize>>,
+    },
+}
+
+impl Source {
+    fn open(path: &str) -> Result<Source, String> {
+        if Path::new(path).is_dir() {
+            return Ok(Source::Directory(PathBuf::from(path)));
+        }
+        let mut file = Loader::open(path).map_err(|error| error.to_string())?;
+        // `docker save | gzip` output is unpacked into memory; plain tarballs are read in place.
+        if archive::is_compressed(file.file_bytes()) {
+            let mut bytes = Vec::new();
+            archive::decompressed(file.file_bytes())
+                .and_then(|mut reader| reader.read_to_end(&mut bytes))
+                .map_err(|error| format!("cannot decompress: {}", error))?;
+            file = Loader::from_bytes(bytes);
+        }
+        let not_tar = |_| "not an OCI layout or image tarball".to_string();
+        let mut members = HashMap::new();
+        let mut archive = tar::Archive::new(file.file_bytes());
+        for entry in archive.entries().map_err(not_tar)? {
+            let entry = entry.map_err(not_tar)?;
+            if entry.header().entry_type().is_file() {
+                let start = entry.raw_file_position() as usize;
+                let path = String::from_utf8_lossy(&entry.path_bytes()).into_owned();
+                members.insert(
+                    archive::normalize(&path),
+                    start..start + entry.size() as usize,
+                );
+            }
+        }
+        Ok(Source::Tarball { file, members })
+    }
+
+    fn exists(&self, name: &str) -> bool {
+        match self {
+            Source::Directory(dir) => dir.join(name).is_file(),
+            Source::Tarball { members, .. } => members.contains_key(name),
+        }
+    }
+
+    /// Runs `f` on the contents of the blob or member `name`.
+    fn with_blob<T>(&self, name: &str, f: impl FnOnce(&[u8]) -> T) -> Result<T, String> {
+        match self {
+            Source::Directory(dir) => {
+                let path = dir.join(name);
+                let blob = Loader::open(&path.display().to_string())
+                    .map_err(|error| format!("{}: {}", name, error))?;
+                Ok(f(blob.file_bytes()))
+            }
+            Source::Tarball { file, members } => {
+                let range = members
+                    .get(name)
+                    .ok_or_else(|| format!("{} is missing from the archive", name))?;
+                let bytes = file
+                    .file_bytes()
+                    .get(range.clone())
+                    .ok_or_else(|| format!("{} is truncated", name))?;
+                Ok(f(bytes))
+            }
+        }
+    }
+
+    fn json(&self, name: &str) -> Result<Value, String> {
+        self.with_blob(name, |bytes| serde_json::from_slice::<Value>(bytes))?
+            .map_err(|error| format!("{}: {}", name, error))
+    }
+}
+
+/// One image in the archive: a display name and its layers, lowest first.
+struct Image {
+    name: String,
+    layers: Vec<String>,
+}
+
+/// `sha256:abc...` as a path below an OCI layout.
+fn blob_path(digest: &str) -> Option<String> {
+    let (algorithm, hex) = digest.split_once(':')?;
+    Some(format!("blobs/{}/{}", algorithm, hex))
+}
+
+fn strings(value: &Value) -> Vec<String> {
+    value
+        .as_array()
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|item| item.as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// The images `docker save` lists in `manifest.json`.
+fn docker_images(manifest: &Value) -> Vec<Image> {
+    manifest
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|image| {
+            let tags = strings(&image["RepoTags"]);
+            let name = if tags.is_empty() {
+                image["Config"].as_str().unwrap_or("untagged").to_string()
+            } else {
+                tags.join(", ")
+            };
+            Image {
+                name,
+                layers: strings(&image["Layers"]),
+            }
+        })
+        .collect()
+}
+
+/// The images an OCI index points at, following nested indexes (multi-platform
+/// images) down to the manifests.
+fn oci_images(
+    source: &Source,
+    index: &Value,
+    name: &str,
+    images: &mut Vec<Image>,
+) -> Result<(), String> {
+    for descriptor in index["manifests"].as_array().into_iter().flatten() {
+        let digest = descriptor["digest"].as_str().unwrap_or_default();
+        let path = blob_path(digest).ok_or_else(|| format!("bad digest {:?}", digest))?;
+        let annotations = &descriptor["annotations"];
+        let mut name = annotations["io.containerd.image.name"]
+            .as_str()
+            .or(annotations["org.opencontainers.image.ref.name"].as_str())
+            .unwrap_or(name)
+            .to_string();
+        if name.is_empty() {
+            name = digest.to_string();
+        }
+        let platform = &descriptor["platform"];
+        if let (Some(os), Some(arch)) = (platform["os"].as_str(), platform["architecture"].as_str())
+        {
+            name = format!("{} ({}/{})", name, os, arch);
+        }
+        // Indexes can name blobs that were not exported, such as other platforms.
+        if !source.exists(&path) {
+            debug!("{}: {} not in the layout, skipped", name, digest);
+            continue;
+        }
+        let blob = source.json(&path)?;
+        if blob.get("manifests").is_some() {
+            oci_images(source, &blob, &name, images)?;
+        } else if let Some(layers) = blob["layers"].as_array() {
+            let layers = layers
+                .iter()
+                .filter_map(|layer| layer["digest"].as_str().and_then(blob_path))
+                .collect();
+            images.push(Image { name, layers });
+        }
+    }
+    Ok(())
+}
+
+fn find_images(source: &Source) -> Result<Vec<Image>, String> {
+    if source.exists("manifest.json") {
+        return Ok(docker_images(&source.json("manifest.json")?));
+    }
+    if source.exists("index.json") {
+        let mut images = Vec::new();
+        oci_images(source, &source.json("index.json")?, "", &mut images)?;
+        return Ok(images);
+    }
+    Err("neither an OCI layout (index.json) nor a docker archive (manifest.json)".to_string())
+}
+
+/// Drops `path` and everything below it.
+fn remove_tree(files: &mut BTreeMap<String, Loader>, path: &str) {
+    let prefix = format!("{}/", path);
+    files.retain(|file, _| file != path && !file.starts_with(&prefix));
+}
+
+/// Stacks the layers the way a container runtime does, honouring whiteouts, and
+/// returns the ELF files of the resulting filesystem.
+fn flatten(source: &Source, image: &Image) -> Result<BTreeMap<String, Loader>, String> {
+    let mut files: BTreeMap<String, Loader> = BTreeMap::new();
+    for layer in &image.layers {
+        let mut upper: BTreeMap<String, Loader> = BTreeMap::new();
+        // Paths this layer deletes or covers, and directories it makes opaque.
+        let mut removed = Vec::new();
+        let mut opaque = Vec::new();
+        source
+            .with_blob(layer, |bytes| {
+                archive::walk_tar(archive::decompressed(bytes)?, |path, member| {
+                    let (dir, name) = path.rsplit_once('/').unwrap_or(("", &path));
+                    if name == ".wh..wh..opq" {
+                        opaque.push(dir.to_string());
+                    } else if let Some(hidden) = name.strip_prefix(".wh.") {
+                        let hidden = if dir.is_empty() {
+                            hidden.to_string()
+                        } else {
+                            format!("{}/{}", dir, hidden)
+                        };
+                        removed.push(hidden);
+                    } else {
+                        match member {
+                            Member::Elf(bytes) => {
+                                upper.insert(path, Loader::from_bytes(bytes));
+                            }
+                            Member::Link(target) => {
+                                match upper.get(&target).or(files.get(&target)) {
+                                    Some(loader) => {
+                                        let link = loader.at(0).expect("offset 0 is always valid");
+                                        upper.insert(path, link);
+                                    }
+                                    None => removed.push(path),
+                                }
+                            }
+                            Member::Other => removed.push(path),
+                        }
+                    }
+                })
+            })?
+            .map_err(|error| format!("{}: {}", layer, error))?;
+        for dir in &opaque {
+            if dir.is_empty() {
+                files.clear();
+            } else {
+                remove_tree(&mut files, dir);
+            }
+        }
+        for path in &removed {
+            remove_tree(&mut files, path);
+        }
+        files.append(&mut upper);
+    }
+    Ok(files)
+}
+
+/// Reports every image in each OCI layout or (possibly compressed) image tarball: one aggregated
+/// hardening and dependency report per image, over the files a container would
+/// see. Returns the archives that could not be read.
+pub fn scan_images(paths: &[String]) -> Vec<(String, String)> {
+    let mut failures = Vec::new();
+    for path in paths {
+        let result = Source::open(path).and_then(|source| {
+            let images = find_images(&source)?;
+            info!("{}: {} image(s)", path, images.len());
+            for image in &images {
+                let files: Vec<(String, Loader)> = flatten(&source, image)?
+                    .into_iter()
+                    .filter(|(_, loader)| loader.header_truncation().is_none())
+                    .map(|(file, loader)| (format!("/{}", file), loader))
+                    .collect();
+                info!(
+                    "{}: {} layer(s), {} ELF file(s)",
+                    image.name,
+                    image.layers.len(),
+                    files.len()
+                );
+                display_aggregate(&format!("{}: {}", path, image.name), &files);
+            }
+            Ok(())
+        });
+        if let Err(error) = result {
+            failures.push((path.clone(), error));
+        }
+    }
+    failures
+}
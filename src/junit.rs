@@ -0,0 +1,153 @@
+use crate::buildinfo;
+use crate::finding::{Checked, Finding, Severity};
+
+/// The test case failed files are reported under when none of their checks ran.
+const UNCHECKED: &str = "elf/valid";
+
+/// Escapes text for XML attributes and character data.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // XML 1.0 cannot carry other control characters, even escaped.
+            c if c.is_control() && !matches!(c, '\t' | '\n' | '\r') => escaped.push('?'),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// The `<properties>` of every suite: the build that wrote the report.
+fn properties() -> String {
+    let properties = [
+        ("version", buildinfo::VERSION.to_string()),
+        ("git", buildinfo::GIT_HASH.to_string()),
+        ("target", buildinfo::TARGET.to_string()),
+        ("features", buildinfo::features().join(",")),
+    ];
+    let mut text = String::from("    <properties>\n");
+    for (name, value) in properties {
+        text.push_str(&format!(
+            "      <property name=\"{}\" value=\"{}\"/>\n",
+            name,
+            escape(&value)
+        ));
+    }
+    text.push_str("    </properties>\n");
+    text
+}
+
+/// One `<testcase>`: failed by findings of error severity, with the others
+/// kept as its output.
+fn test_case(path: &str, check: &str, findings: &[&Finding]) -> String {
+    let errors: Vec<&str> = findings
+        .iter()
+        .filter(|finding| finding.severity == Severity::Error)
+        .map(|finding| finding.message.as_str())
+        .collect();
+    let others: Vec<String> = findings
+        .iter()
+        .filter(|finding| finding.severity != Severity::Error)
+        .map(|finding| format!("{}: {}", finding.severity.as_str(), finding.message))
+        .collect();
+    let mut case = format!(
+        "    <testcase classname=\"{}\" name=\"{}\"",
+        escape(path),
+        escape(check)
+    );
+    if errors.is_empty() && others.is_empty() {
+        case.push_str("/>\n");
+        return case;
+    }
+    case.push_str(">\n");
+    if !errors.is_empty() {
+        case.push_str(&format!(
+            "      <failure message=\"{}\">{}</failure>\n",
+            escape(errors[0]),
+            escape(&errors.join("\n"))
+        ));
+    }
+    if !others.is_empty() {
+        case.push_str(&format!(
+            "      <system-out>{}</system-out>\n",
+            escape(&others.join("\n"))
+        ));
+    }
+    case.push_str("    </testcase>\n");
+    case
+}
+
+/// `--report junit=FILE`: the checks of the run as a JUnit XML report, which CI
+/// systems show as test results. Every file is a test suite and every check run
+/// on it a test case, failed when it found an error. Failures of files no check
+/// ran on, such as unreadable ones, are test errors.
+pub fn junit(checked: &[Checked], failures: &[(String, String)]) -> String {
+    let mut suites = String::new();
+    let (mut tests, mut failed, mut errors) = (0, 0, 0);
+    for file in checked {
+        let mut cases = String::new();
+        let mut failed_here = 0;
+        for check in &file.checks {
+            let findings: Vec<&Finding> = file
+                .findings
+                .iter()
+                .filter(|finding| finding.check == *check)
+                .collect();
+            if findings
+                .iter()
+                .any(|finding| finding.severity == Severity::Error)
+            {
+                failed_here += 1;
+            }
+            cases.push_str(&test_case(&file.path, check, &findings));
+        }
+        suites.push_str(&format!(
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"0\">\n{}{}  </testsuite>\n",
+            escape(&file.path),
+            file.checks.len(),
+            failed_here,
+            properties(),
+            cases
+        ));
+        tests += file.checks.len();
+        failed += failed_here;
+    }
+    for (path, reason) in failures {
+        if checked.iter().any(|file| file.path == *path) {
+            continue;
+        }
+        suites.push_str(&format!(
+            concat!(
+                "  <testsuite name=\"{path}\" tests=\"1\" failures=\"0\" errors=\"1\">\n",
+                "{properties}",
+                "    <testcase classname=\"{path}\" name=\"{check}\">\n",
+                "      <error message=\"{reason}\"/>\n",
+                "    </testcase>\n",
+                "  </testsuite>\n"
+            ),
+            path = escape(path),
+            properties = properties(),
+            check = UNCHECKED,
+            reason = escape(reason)
+        ));
+        tests += 1;
+        errors += 1;
+    }
+    format!(
+        concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+            "<testsuites name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\">\n",
+            "{}</testsuites>\n"
+        ),
+        buildinfo::NAME,
+        tests,
+        failed,
+        errors,
+        suites
+    )
+}
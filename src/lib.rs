@@ -0,0 +1,1637 @@
+mod aarch64;
+mod aggregate;
+mod android;
+mod arc;
+mod arch;
+mod archive;
+mod arm;
+mod attributes;
+mod auxv;
+mod baseline;
+mod boot;
+mod brief;
+mod buildinfo;
+mod bundle;
+mod cache;
+mod callgraph;
+mod carve;
+mod checksec;
+mod checksum;
+mod cli;
+mod completions;
+mod config;
+mod conformance;
+mod container;
+mod coredump;
+#[cfg(feature = "cross-check")]
+pub mod crosscheck;
+mod daemon;
+mod debuginfo;
+mod deps;
+mod dlopen;
+mod dryload;
+mod dwarf;
+mod dwo;
+mod dynamic;
+mod eh_frame;
+mod exit;
+mod explain;
+mod exports;
+#[cfg(feature = "fetch-fixtures")]
+mod fetch;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod filetype;
+mod finding;
+mod fingerprint;
+mod functions;
+mod golden;
+mod graph;
+mod grep;
+mod group;
+mod i18n;
+mod ima;
+mod image;
+mod junit;
+mod limits;
+mod linkage;
+mod logging;
+mod loongarch;
+mod machine;
+#[cfg(feature = "macho")]
+mod macho;
+mod man;
+mod manifest;
+mod markdown;
+mod mips;
+mod mitigations;
+mod model;
+mod normalized;
+mod note;
+mod objsummary;
+mod output;
+mod package;
+mod patch;
+mod pe;
+#[cfg(feature = "pe")]
+mod peheader;
+mod pid;
+#[cfg(feature = "plugins")]
+mod plugin;
+mod policy;
+mod ppc64;
+mod property;
+#[cfg(feature = "python")]
+mod python;
+mod query;
+mod ranged;
+pub mod raw;
+mod reloc;
+mod remote;
+mod resolve;
+mod riscv;
+#[cfg(target_os = "linux")]
+mod sandbox;
+mod sarif;
+mod scratch;
+mod section;
+mod segment;
+mod selftest;
+mod serve;
+#[cfg(feature = "signing")]
+mod sign;
+mod sizes;
+mod sourcepath;
+mod stack;
+mod strings;
+mod symbol;
+mod synthetic;
+#[cfg(feature = "templates")]
+mod template;
+mod textrel;
+mod textstats;
+mod timings;
+mod translate;
+mod undefs;
+mod version;
+#[cfg(feature = "wasm")]
+mod wasm;
+#[cfg(feature = "wasm-summary")]
+mod wasmsummary;
+mod watch;
+mod x86;
+mod xattr;
+
+use cli::{FailOn, Offset, Options};
+use core::mem;
+use finding::{Finding, Severity};
+use log::info;
+#[cfg(not(target_arch = "wasm32"))]
+use memmap::Mmap;
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fs;
+#[cfg(unix)]
+use std::os::unix::fs::FileTypeExt;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::Arc;
+use timings::Phase;
+
+const HEADER_MAGIC: [u8; 4] = [0x7f, 0x45, 0x4c, 0x46];
+const EI_NIDENT: usize = 16;
+
+const ELF64_ADDR_SIZE: usize = mem::size_of::<u64>();
+const ELF64_OFF_SIZE: usize = mem::size_of::<u64>();
+const ELF64_WORD_SIZE: usize = mem::size_of::<u32>();
+const ELF64_HALF_SIZE: usize = mem::size_of::<u16>();
+
+const E_TYPE_START_BYTE: usize = 16;
+const E_TYPE_SIZE_BYTE: usize = ELF64_HALF_SIZE;
+const E_MACHINE_START_BYTE: usize = E_TYPE_START_BYTE + E_TYPE_SIZE_BYTE;
+const E_MACHINE_SIZE_BYTE: usize = ELF64_HALF_SIZE;
+const E_VERSION_START_BYTE: usize = E_MACHINE_START_BYTE + E_MACHINE_SIZE_BYTE;
+const E_VERSION_SIZE_BYTE: usize = ELF64_WORD_SIZE;
+const E_ENTRY_START_BYTE: usize = E_VERSION_START_BYTE + E_VERSION_SIZE_BYTE;
+const E_ENTRY_SIZE_BYTE: usize = ELF64_ADDR_SIZE;
+const E_PHOFF_START_BYTE: usize = E_ENTRY_START_BYTE + E_ENTRY_SIZE_BYTE;
+const E_PHOFF_SIZE_BYTE: usize = ELF64_OFF_SIZE;
+const E_SHOFF_START_BYTE: usize = E_PHOFF_START_BYTE + E_PHOFF_SIZE_BYTE;
+const E_SHOFF_SIZE_BYTE: usize = ELF64_OFF_SIZE;
+const E_FLAGS_START_BYTE: usize = E_SHOFF_START_BYTE + E_SHOFF_SIZE_BYTE;
+const E_FLAGS_SIZE_BYTE: usize = ELF64_WORD_SIZE;
+const E_EHSIZE_START_BYTE: usize = E_FLAGS_START_BYTE + E_FLAGS_SIZE_BYTE;
+const E_EHSIZE_SIZE_BYTE: usize = ELF64_HALF_SIZE;
+const E_PHENTSIZE_START_BYTE: usize = E_EHSIZE_START_BYTE + E_EHSIZE_SIZE_BYTE;
+const E_PHENTSIZE_SIZE_BYTE: usize = ELF64_HALF_SIZE;
+const E_PHNUM_START_BYTE: usize = E_PHENTSIZE_START_BYTE + E_PHENTSIZE_SIZE_BYTE;
+const E_PHNUM_SIZE_BYTE: usize = ELF64_HALF_SIZE;
+const E_SHENTSIZE_START_BYTE: usize = E_PHNUM_START_BYTE + E_PHNUM_SIZE_BYTE;
+const E_SHENTSIZE_SIZE_BYTE: usize = ELF64_HALF_SIZE;
+const E_SHNUM_START_BYTE: usize = E_SHENTSIZE_START_BYTE + E_SHENTSIZE_SIZE_BYTE;
+const E_SHNUM_SIZE_BYTE: usize = ELF64_HALF_SIZE;
+const E_SHSTRNDX_START_BYTE: usize = E_SHNUM_START_BYTE + E_SHNUM_SIZE_BYTE;
+
+/// The fields after e_ident in file order with their sizes; `None` marks the
+/// address/offset sized fields whose width depends on EI_CLASS.
+const HEADER_FIELDS: &[(&str, Option<usize>)] = &[
+    ("e_type", Some(ELF64_HALF_SIZE)),
+    ("e_machine", Some(ELF64_HALF_SIZE)),
+    ("e_version", Some(ELF64_WORD_SIZE)),
+    ("e_entry", None),
+    ("e_phoff", None),
+    ("e_shoff", None),
+    ("e_flags", Some(ELF64_WORD_SIZE)),
+    ("e_ehsize", Some(ELF64_HALF_SIZE)),
+    ("e_phentsize", Some(ELF64_HALF_SIZE)),
+    ("e_phnum", Some(ELF64_HALF_SIZE)),
+    ("e_shentsize", Some(ELF64_HALF_SIZE)),
+    ("e_shnum", Some(ELF64_HALF_SIZE)),
+    ("e_shstrndx", Some(ELF64_HALF_SIZE)),
+];
+
+fn too_small_message(len: usize) -> String {
+    format!("file too small to be ELF ({} bytes)", len)
+}
+
+/// The bytes behind a loader: a mapped file, or a buffer from a caller with no
+/// file to map, such as the wasm build.
+enum Image {
+    #[cfg(not(target_arch = "wasm32"))]
+    Mapped(Mmap),
+    Owned(Vec<u8>),
+}
+
+impl std::ops::Deref for Image {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            #[cfg(not(target_arch = "wasm32"))]
+            Image::Mapped(map) => map,
+            Image::Owned(bytes) => bytes,
+        }
+    }
+}
+
+impl Image {
+    fn open(path: &str) -> std::io::Result<Image> {
+        Image::map(&fs::File::open(path)?)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn map(file: &fs::File) -> std::io::Result<Image> {
+        timings::measure(Phase::Mmap, || unsafe { Mmap::map(file) }).map(Image::Mapped)
+    }
+
+    /// There is no mmap on wasm32; read the file where a filesystem exists at all.
+    #[cfg(target_arch = "wasm32")]
+    fn map(mut file: &fs::File) -> std::io::Result<Image> {
+        use std::io::Read;
+        let mut bytes = Vec::new();
+        timings::measure(Phase::Mmap, || file.read_to_end(&mut bytes))?;
+        Ok(Image::Owned(bytes))
+    }
+}
+
+pub struct Loader {
+    file: Arc<Image>,
+    /// Offset of the ELF image inside the mapped file; non-zero for bundled images.
+    base: usize,
+    /// The separate debug file attached by `--with-debug`, whose symbols stand in
+    /// for the ones stripped from this file.
+    debug: Option<Arc<Loader>>,
+}
+
+impl Loader {
+    pub fn open(path: &str) -> std::io::Result<Loader> {
+        Loader::open_at(path, 0)
+    }
+
+    /// A loader over a file already open, such as one no longer linked.
+    pub fn from_file(file: &fs::File) -> std::io::Result<Loader> {
+        Ok(Loader {
+            file: Arc::new(Image::map(file)?),
+            base: 0,
+            debug: None,
+        })
+    }
+
+    /// A loader over bytes already in memory.
+    pub fn from_bytes(bytes: Vec<u8>) -> Loader {
+        Loader {
+            file: Arc::new(Image::Owned(bytes)),
+            base: 0,
+            debug: None,
+        }
+    }
+
+    pub fn open_at(path: &str, base: usize) -> std::io::Result<Loader> {
+        let file = Arc::new(Image::open(path)?);
+        Loader {
+            file,
+            base: 0,
+            debug: None,
+        }
+        .at(base)
+        .ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("offset {:#x} is past the end of the file", base),
+            )
+        })
+    }
+
+    /// Returns a loader over the same mapping whose ELF image starts at `base`.
+    pub(crate) fn at(&self, base: usize) -> Option<Loader> {
+        if base > self.file.len() {
+            return None;
+        }
+        Some(Loader {
+            file: Arc::clone(&self.file),
+            base,
+            debug: None,
+        })
+    }
+
+    pub(crate) fn is_elf(&self) -> bool {
+        self.bytes().starts_with(&HEADER_MAGIC)
+    }
+
+    /// The ELF image, starting at its own offset 0.
+    pub(crate) fn bytes(&self) -> &[u8] {
+        &self.file[self.base..]
+    }
+
+    /// The whole mapped file, including anything in front of the ELF image.
+    pub(crate) fn file_bytes(&self) -> &[u8] {
+        &self.file[..]
+    }
+
+    pub(crate) fn is_64bit(&self) -> bool {
+        self.read_u8(4) == Some(2)
+    }
+
+    pub(crate) fn is_big_endian(&self) -> bool {
+        self.read_u8(5) == Some(2)
+    }
+
+    pub(crate) fn read_u8(&self, offset: usize) -> Option<u8> {
+        self.bytes().get(offset).copied()
+    }
+
+    pub(crate) fn read_u16(&self, offset: usize) -> Option<u16> {
+        let raw: [u8; 2] = self.bytes().get(offset..offset + 2)?.try_into().ok()?;
+        Some(if self.is_big_endian() {
+            u16::from_be_bytes(raw)
+        } else {
+            u16::from_le_bytes(raw)
+        })
+    }
+
+    pub(crate) fn read_u32(&self, offset: usize) -> Option<u32> {
+        let raw: [u8; 4] = self.bytes().get(offset..offset + 4)?.try_into().ok()?;
+        Some(if self.is_big_endian() {
+            u32::from_be_bytes(raw)
+        } else {
+            u32::from_le_bytes(raw)
+        })
+    }
+
+    pub(crate) fn read_u64(&self, offset: usize) -> Option<u64> {
+        let raw: [u8; 8] = self.bytes().get(offset..offset + 8)?.try_into().ok()?;
+        Some(if self.is_big_endian() {
+            u64::from_be_bytes(raw)
+        } else {
+            u64::from_le_bytes(raw)
+        })
+    }
+
+    /// Reads an address/offset sized field (Elf32_Addr or Elf64_Addr) depending on EI_CLASS.
+    pub(crate) fn read_addr(&self, offset: usize) -> Option<u64> {
+        if self.is_64bit() {
+            self.read_u64(offset)
+        } else {
+            self.read_u32(offset).map(u64::from)
+        }
+    }
+
+    pub(crate) fn addr_size(&self) -> usize {
+        if self.is_64bit() {
+            ELF64_ADDR_SIZE
+        } else {
+            mem::size_of::<u32>()
+        }
+    }
+
+    /// Reads a NUL-terminated string starting at `offset`.
+    pub(crate) fn read_cstr(&self, offset: usize) -> Option<&str> {
+        let tail = self.bytes().get(offset..)?;
+        let end = tail.iter().position(|&b| b == 0)?;
+        std::str::from_utf8(&tail[..end]).ok()
+    }
+
+    /// Describes why the ELF header cannot be read in full, naming the first field
+    /// that runs past the end of the image, or `None` when the header is complete.
+    pub(crate) fn header_truncation(&self) -> Option<String> {
+        let len = self.bytes().len();
+        if len < EI_NIDENT {
+            return Some(too_small_message(len));
+        }
+        let mut offset = EI_NIDENT;
+        for (name, size) in HEADER_FIELDS {
+            let size = size.unwrap_or_else(|| self.addr_size());
+            if offset + size > len {
+                return Some(format!(
+                    "truncated ELF header ({} bytes), cannot read {} at {:#x}..{:#x}",
+                    len,
+                    name,
+                    offset,
+                    offset + size
+                ));
+            }
+            offset += size;
+        }
+        None
+    }
+
+    pub(crate) fn raw_e_type(&self) -> u16 {
+        self.read_u16(E_TYPE_START_BYTE).unwrap_or(0)
+    }
+
+    pub(crate) fn raw_e_machine(&self) -> u16 {
+        self.read_u16(E_MACHINE_START_BYTE).unwrap_or(0)
+    }
+
+    pub(crate) fn raw_e_entry(&self) -> u64 {
+        self.read_addr(E_ENTRY_START_BYTE).unwrap_or(0)
+    }
+
+    /// e_flags, which sits after the class-sized entry and table offsets.
+    pub(crate) fn raw_e_flags(&self) -> u32 {
+        let offset = E_VERSION_START_BYTE + E_VERSION_SIZE_BYTE + 3 * self.addr_size();
+        self.read_u32(offset).unwrap_or(0)
+    }
+
+    fn get_ei_class(&self) -> &str {
+        match self.bytes()[4] {
+            1 => "32bit architecture",
+            2 => "64bit architecture",
+            _ => "Invalid class",
+        }
+    }
+
+    fn get_ei_data(&self) -> &str {
+        match self.bytes()[5] {
+            1 => "Little endian",
+            2 => "Big endian",
+            _ => "Invalid data",
+        }
+    }
+
+    fn get_ei_version(&self) -> u8 {
+        self.bytes()[6]
+    }
+
+    fn get_e_type(&self) -> &str {
+        match self.raw_e_type() {
+            0 => "No file type",
+            1 => "Relocatable file",
+            2 => "Executable file",
+            3 => "Shared object file",
+            4 => "Core file",
+            0xfe00 | 0xfeff => "Operating system-specific",
+            0xff00 | 0xffff => "Processor-specific",
+            _ => "Invalid type",
+        }
+    }
+
+    /// Moves the offset of a field after e_shoff from the ELF64 layout to this
+    /// file's class: e_entry, e_phoff and e_shoff are 4 bytes each in ELF32.
+    fn class_offset(&self, elf64_offset: usize) -> usize {
+        elf64_offset - 3 * (ELF64_ADDR_SIZE - self.addr_size())
+    }
+
+    fn get_e_version(&self) -> u64 {
+        self.read_u32(E_VERSION_START_BYTE).unwrap_or(0).into()
+    }
+
+    fn get_e_entry(&self) -> u64 {
+        self.raw_e_entry()
+    }
+
+    fn get_e_phoff(&self) -> u64 {
+        self.read_addr(E_ENTRY_START_BYTE + self.addr_size())
+            .unwrap_or(0)
+    }
+
+    fn get_e_shoff(&self) -> u64 {
+        self.read_addr(E_ENTRY_START_BYTE + 2 * self.addr_size())
+            .unwrap_or(0)
+    }
+
+    fn get_e_flags(&self) -> u32 {
+        self.raw_e_flags()
+    }
+
+    fn get_e_ehsize(&self) -> u32 {
+        self.read_u16(self.class_offset(E_EHSIZE_START_BYTE))
+            .unwrap_or(0)
+            .into()
+    }
+
+    fn get_e_phentsize(&self) -> u32 {
+        self.read_u16(self.class_offset(E_PHENTSIZE_START_BYTE))
+            .unwrap_or(0)
+            .into()
+    }
+
+    fn get_e_phnum(&self) -> u32 {
+        self.read_u16(self.class_offset(E_PHNUM_START_BYTE))
+            .unwrap_or(0)
+            .into()
+    }
+
+    fn get_e_shentsize(&self) -> u32 {
+        self.read_u16(self.class_offset(E_SHENTSIZE_START_BYTE))
+            .unwrap_or(0)
+            .into()
+    }
+
+    fn get_e_shnum(&self) -> u32 {
+        self.read_u16(self.class_offset(E_SHNUM_START_BYTE))
+            .unwrap_or(0)
+            .into()
+    }
+
+    fn get_e_shstrndx(&self) -> u32 {
+        self.read_u16(self.class_offset(E_SHSTRNDX_START_BYTE))
+            .unwrap_or(0)
+            .into()
+    }
+}
+
+/// Renders one header field for every file, in hex and/or with a unit suffix.
+fn format_elem(values: &[String], hex: bool, suffix: &str) -> Vec<String> {
+    values
+        .iter()
+        .map(|string| {
+            let value = if hex {
+                match string.parse::<u64>() {
+                    Ok(parsed_int) => format!("{:#x}", parsed_int),
+                    Err(_) => panic!("Illegal instruction"),
+                }
+            } else {
+                string.clone()
+            };
+            value + suffix
+        })
+        .collect()
+}
+
+pub(crate) fn display_line(key: &str, value: &str) {
+    output::field(key, value);
+}
+
+/// `$XDG_CACHE_HOME/elf-header-parser`, or the same under `~/.cache`.
+pub(crate) fn user_cache_dir() -> Option<PathBuf> {
+    let base = env::var_os("XDG_CACHE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".cache")))?;
+    Some(base.join("elf-header-parser"))
+}
+
+fn display_each(targets: &[(String, Loader)], display: fn(&str, &Loader)) {
+    for (label, loader) in targets {
+        timings::for_file(label, || cache::display_once(label, loader, display));
+    }
+}
+
+/// [`display_each`] for reports that look at the files around each input.
+fn display_each_at(targets: &[(String, Loader)], display: fn(&str, &Loader)) {
+    for (label, loader) in targets {
+        timings::for_file(label, || cache::display_once_at(label, loader, display));
+    }
+}
+
+/// What a file that is neither regular nor a directory is, for the error message.
+#[cfg(unix)]
+fn special_file_kind(file_type: &fs::FileType) -> &'static str {
+    if file_type.is_fifo() {
+        "a FIFO"
+    } else if file_type.is_char_device() {
+        "a character device"
+    } else if file_type.is_block_device() {
+        "a block device"
+    } else if file_type.is_socket() {
+        "a socket"
+    } else {
+        "not a regular file"
+    }
+}
+
+#[cfg(not(unix))]
+fn special_file_kind(_: &fs::FileType) -> &'static str {
+    "not a regular file"
+}
+
+/// Rejects directories, FIFOs, devices and sockets before they are opened: mapping
+/// them fails in confusing ways and opening a FIFO blocks until a writer shows up.
+/// Returns the file size.
+fn regular_file_len(path: &str) -> std::io::Result<u64> {
+    let metadata = fs::metadata(path)?;
+    let file_type = metadata.file_type();
+    if file_type.is_file() {
+        return Ok(metadata.len());
+    }
+    let kind = if file_type.is_dir() {
+        "a directory"
+    } else {
+        special_file_kind(&file_type)
+    };
+    Err(std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        format!("is {}", kind),
+    ))
+}
+
+/// Opens every input path, reporting the ones that cannot be read instead of aborting.
+/// URLs are downloaded into memory. With `header_only`, only the ELF header and
+/// the header tables are read, from local files and HTTP servers alike.
+fn open_inputs(
+    paths: &[String],
+    header_only: bool,
+    failures: &mut Vec<(String, String)>,
+) -> Vec<(String, Loader)> {
+    let mut loaders = Vec::new();
+    for path in paths {
+        if remote::is_url(path) {
+            match timings::for_file(path, || remote::fetch(path, header_only)) {
+                Ok(loader) => loaders.push((path.clone(), loader)),
+                Err(error) => {
+                    eprintln!("{}: skipped, {}", path, error);
+                    exit::unparsed(path);
+                    failures.push((path.clone(), error));
+                }
+            }
+            continue;
+        }
+        let len = match regular_file_len(path) {
+            Ok(len) => len,
+            Err(error) => {
+                eprintln!("{}: skipped, {}", path, error);
+                exit::unparsed(path);
+                failures.push((path.clone(), error.to_string()));
+                continue;
+            }
+        };
+        if len < EI_NIDENT as u64 {
+            let problem = too_small_message(len as usize);
+            output::message(&format!("{}: {}", path, problem));
+            exit::unparsed(path);
+            failures.push((path.clone(), problem));
+            continue;
+        }
+        let opened = timings::for_file(path, || {
+            if header_only {
+                // Files that do not start with ELF are mapped whole, to tell
+                // what they are.
+                ranged::open_headers(path).and_then(|headers| {
+                    if headers.is_elf() {
+                        Ok(headers)
+                    } else {
+                        Loader::open(path)
+                    }
+                })
+            } else {
+                let loader = Loader::open(path);
+                info!("{}: mapped {} bytes", path, len);
+                loader
+            }
+        });
+        match opened {
+            Ok(loader) => {
+                loaders.push((path.clone(), loader));
+            }
+            Err(error) => {
+                eprintln!("{}: skipped, {}", path, error);
+                exit::unparsed(path);
+                failures.push((path.clone(), error.to_string()));
+            }
+        }
+    }
+    loaders
+}
+
+/// The `--report` check that a file parses as ELF.
+const VALID_ELF: &str = "elf/valid";
+/// The `--report` check of `--expect-machine`.
+const EXPECTED_MACHINE: &str = "elf/machine";
+
+/// Records that `path` failed the valid ELF check for the reports, and fails
+/// the run with it as an input that did not parse.
+fn record_invalid(path: &str, problem: &str, failures: &mut Vec<(String, String)>) {
+    exit::unparsed(path);
+    failures.push((path.to_string(), problem.to_string()));
+    let finding = Finding {
+        severity: Severity::Error,
+        check: VALID_ELF.to_string(),
+        message: problem.to_string(),
+    };
+    finding::record(path, [VALID_ELF], &[finding]);
+}
+
+/// Resolves every opened input into the ELF images to inspect, honouring `--offset`.
+/// Header deviations from the specification are warnings, or with `--strict` errors
+/// that drop the image and are recorded in `failures`.
+fn collect_targets(
+    inputs: Vec<(String, Loader)>,
+    options: &Options,
+    failures: &mut Vec<(String, String)>,
+) -> Vec<(String, Loader)> {
+    let mut targets = Vec::new();
+    let mut push = |label: String, image: Loader| {
+        let checked = timings::for_file(&label, || {
+            timings::measure(Phase::Header, || match image.header_truncation() {
+                Some(problem) => Err(problem),
+                None => Ok((image.spec_deviations(), image.summary())),
+            })
+        });
+        let (deviations, summary) = match checked {
+            Ok(checked) => checked,
+            Err(problem) => {
+                record_invalid(&label, &problem, failures);
+                output::message(&format!("{}: {}", label, problem));
+                return;
+            }
+        };
+        // The default table reads nothing the limits are about.
+        if !options.header_only() {
+            if let Err(exceeded) = image.check_limits() {
+                let problem = exceeded.to_string();
+                eprintln!("error: {}: {}", label, problem);
+                record_invalid(&label, &problem, failures);
+                return;
+            }
+        }
+        if options.strict && !deviations.is_empty() {
+            for deviation in &deviations {
+                eprintln!("error: {}: {}", label, deviation);
+            }
+            record_invalid(&label, &deviations.join("; "), failures);
+            return;
+        }
+        for deviation in &deviations {
+            eprintln!("warning: {}: {}", label, deviation);
+        }
+        let warnings: Vec<Finding> = deviations
+            .iter()
+            .map(|deviation| Finding {
+                severity: Severity::Warning,
+                check: VALID_ELF.to_string(),
+                message: deviation.clone(),
+            })
+            .collect();
+        finding::record(&label, [VALID_ELF], &warnings);
+        if let Some(expected) = options.expect_machine {
+            let mut mismatch = Vec::new();
+            if image.raw_e_machine() != expected {
+                let name = machine::lookup(expected)
+                    .map(|machine| machine.name.to_string())
+                    .unwrap_or_else(|| format!("EM_{}", expected));
+                mismatch.push(Finding {
+                    severity: Severity::Error,
+                    check: EXPECTED_MACHINE.to_string(),
+                    message: format!("machine is {}, expected {}", image.machine_name(), name),
+                });
+            }
+            finding::record(&label, [EXPECTED_MACHINE], &mismatch);
+            if let Some(mismatch) = mismatch.first() {
+                eprintln!("error: {}: {}", label, mismatch.message);
+                failures.push((label.clone(), mismatch.message.clone()));
+            }
+        }
+        info!("{}: {}", label, summary);
+        targets.push((label, image));
+    };
+    // Inputs that are not ELF, kept apart while `push` holds `failures`.
+    let mut invalid = Vec::new();
+    for (path, loader) in inputs {
+        match options.offset {
+            _ if options.appimage_runtime && loader.appimage_kind().is_some() => {
+                match bundle::appimage_runtime(&loader) {
+                    Some(runtime) => {
+                        output::message(&format!(
+                            "{}: AppImage runtime of {} bytes, payload left out",
+                            path,
+                            runtime.bytes().len()
+                        ));
+                        push(format!("{}#runtime", path), runtime);
+                    }
+                    None => push(path, loader),
+                }
+            }
+            _ if loader.is_elf() => push(path, loader),
+            None if options.scan_for_magic => {
+                match loader
+                    .first_header()
+                    .and_then(|offset| Some((offset, loader.at(offset)?)))
+                {
+                    Some((offset, image)) => {
+                        output::message(&format!(
+                            "{}: ELF image after a {}-byte prefix",
+                            path, offset
+                        ));
+                        push(format!("{}@{:#x}", path, offset), image);
+                    }
+                    None => {
+                        record_invalid(&path, "no ELF header found", &mut invalid);
+                        output::message(&format!("{}: no ELF header found", path));
+                    }
+                }
+            }
+            #[cfg(feature = "wasm-summary")]
+            None if wasmsummary::is_wasm(loader.bytes()) => {
+                if options.normalized {
+                    normalized::display_normalized(&path, &wasmsummary::normalized());
+                } else {
+                    wasmsummary::display_wasm_summary(&path, loader.bytes());
+                }
+            }
+            #[cfg(feature = "macho")]
+            None if macho::is_macho(loader.bytes()) => {
+                macho::display_macho(&path, loader.bytes(), options.normalized)
+            }
+            // PE images with an ELF image inside are EFI stub kernels, below.
+            #[cfg(feature = "pe")]
+            None if peheader::parse(loader.bytes()).is_some()
+                && loader.first_header().is_none() =>
+            {
+                if let Some(header) = peheader::parse(loader.bytes()) {
+                    if options.normalized {
+                        normalized::display_normalized(&path, &header.normalized());
+                    } else {
+                        peheader::display_pe(&path, &header);
+                    }
+                }
+            }
+            None => match pe::parse(loader.bytes()) {
+                // EFI stub kernels start with a PE header, and some have the
+                // ELF kernel further on.
+                Some(pe) => {
+                    let description = pe.describe(loader.bytes());
+                    match loader
+                        .first_header()
+                        .and_then(|offset| Some((offset, loader.at(offset)?)))
+                    {
+                        Some((offset, image)) => {
+                            output::message(&format!(
+                                "{}: {}, with an ELF image at offset {:#x}",
+                                path, description, offset
+                            ));
+                            push(format!("{}@{:#x}", path, offset), image);
+                        }
+                        None => {
+                            let problem = format!("{}, with no ELF image inside", description);
+                            record_invalid(&path, &problem, &mut invalid);
+                            output::message(&format!("{} is a {}", path, problem));
+                        }
+                    }
+                }
+                None => {
+                    info!(
+                        "{}: no ELF magic at offset 0, try --offset auto for containers",
+                        path
+                    );
+                    let problem = filetype::not_elf(loader.bytes());
+                    record_invalid(&path, &problem, &mut invalid);
+                    output::message(&format!("{} is {}", path, problem))
+                }
+            },
+            Some(Offset::Fixed(offset)) => match loader.at(offset).filter(|image| image.is_elf()) {
+                Some(image) => push(format!("{}@{:#x}", path, offset), image),
+                None => {
+                    let problem = format!("no ELF header at offset {:#x}", offset);
+                    record_invalid(&path, &problem, &mut invalid);
+                    output::message(&format!("{} has {}", path, problem));
+                }
+            },
+            Some(Offset::Auto) => {
+                let images = loader.embedded_images();
+                if images.is_empty() {
+                    let problem = filetype::not_elf(loader.bytes());
+                    record_invalid(&path, &problem, &mut invalid);
+                    output::message(&format!("{} is {}", path, problem));
+                    continue;
+                }
+                output::begin(&format!(
+                    "{} contains {} embedded ELF image(s)",
+                    path,
+                    images.len()
+                ));
+                for embedded in &images {
+                    output::item(
+                        1,
+                        &format!(
+                            "{:>#10x} {:>10} bytes  {}",
+                            embedded.offset, embedded.size, embedded.origin
+                        ),
+                    );
+                }
+                output::end();
+                for embedded in images {
+                    if let Some(image) = loader.at(embedded.offset).filter(|image| image.is_elf()) {
+                        push(format!("{}@{:#x}", path, embedded.offset), image);
+                    }
+                }
+            }
+        }
+    }
+    failures.extend(invalid);
+    if options.with_debug {
+        targets = targets
+            .into_iter()
+            .map(|(label, loader)| {
+                let loader = debuginfo::attach_debug_file(&label, loader);
+                (label, loader)
+            })
+            .collect();
+    }
+    targets
+}
+
+/// The command line tool: parses the arguments, runs the report and exits with
+/// its status.
+pub fn cli_main() {
+    let options = match Options::parse(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}", message);
+            process::exit(exit::USAGE);
+        }
+    };
+
+    if options.help_full {
+        print!("{}", cli::usage_full());
+        return;
+    }
+    if options.help {
+        print!("{}", cli::usage());
+        return;
+    }
+    if options.version {
+        if options.verbose > 0 {
+            print!("{}", buildinfo::version_verbose());
+        } else {
+            println!("{}", buildinfo::version());
+        }
+        return;
+    }
+    if options.subcommand == Some("gen-man") {
+        print!("{}", man::man_page());
+        return;
+    }
+    if options.subcommand == Some("completions") {
+        let shell = options.paths.first().map(String::as_str).unwrap_or("");
+        match completions::completion_script(shell) {
+            Some(script) => print!("{}", script),
+            None => {
+                eprintln!(
+                    "unsupported shell '{}', expected one of: bash, zsh, fish",
+                    shell
+                );
+                process::exit(exit::USAGE);
+            }
+        }
+        return;
+    }
+
+    if options.watch {
+        watch::watch(
+            &options.paths,
+            &options.forwarded,
+            options.watch_diff,
+            options.header_only(),
+        );
+    }
+
+    logging::init(
+        options.verbose,
+        logging::use_color(options.color.as_deref()),
+    );
+    if options.timings {
+        timings::enable();
+    }
+    if let Some(format) = &options.format {
+        output::set_format(format);
+    }
+    if options.list_machines {
+        machine::display_machines();
+        output::finish();
+        return;
+    }
+    if let Some(language) = &options.lang {
+        i18n::set_language(language);
+    }
+    if options.no_cache {
+        cache::disable();
+    }
+    limits::set(options.limits);
+    debuginfo::set_debuginfod(options.debuginfod.clone());
+    match config::load(options.config.as_deref()) {
+        Ok(config) => config::set(config),
+        Err(error) => {
+            eprintln!("{}", error);
+            process::exit(exit::USAGE);
+        }
+    }
+    if !options.reports.is_empty() || options.fail_on == FailOn::Warning {
+        finding::enable();
+    }
+    if options.reports.iter().any(|(kind, _)| kind == "markdown") {
+        markdown::enable();
+    }
+    let mut failures = Vec::new();
+    let processed = run(&options, &mut failures);
+    write_reports(&options, &mut failures);
+    timings::measure(Phase::Output, output::finish);
+    timings::report();
+    if !failures.is_empty() {
+        eprintln!();
+        eprintln!(
+            "{} of {} file(s) failed:",
+            failures.len(),
+            // Images, --pid and the reports written afterwards can fail more
+            // files than were processed.
+            processed.max(failures.len())
+        );
+        for (path, reason) in &failures {
+            eprintln!("    {}: {}", path, reason);
+        }
+    }
+    let warnings = finding::warnings();
+    if failures.is_empty() && warnings > 0 && options.fail_on == FailOn::Warning {
+        eprintln!();
+        eprintln!("{} warning(s)", warnings);
+    }
+    let status = exit::status(&failures, warnings, options.fail_on);
+    if status != 0 {
+        process::exit(status);
+    }
+}
+
+/// Writes the `--report` files from the checks the run recorded.
+fn write_reports(options: &Options, failures: &mut Vec<(String, String)>) {
+    let checked = finding::recorded();
+    let failed = failures.clone();
+    for (kind, path) in &options.reports {
+        let text = match kind.as_str() {
+            "sarif" => sarif::sarif(&checked),
+            "junit" => junit::junit(&checked, &failed),
+            "markdown" => markdown::markdown(&checked, &failed),
+            _ => continue,
+        };
+        if let Err(error) = patch::write_atomic(path, text) {
+            failures.push((path.clone(), error));
+        }
+    }
+}
+
+/// `--sandbox`: confines the process once its inputs are open, keeping the
+/// files `--baseline` and `--symvers` read and the places reports and
+/// extracted files are written to.
+fn enter_sandbox(options: &Options) {
+    if !options.sandbox {
+        return;
+    }
+    #[cfg(target_os = "linux")]
+    let result = {
+        let readable: Vec<&Path> = [&options.baseline, &options.symvers]
+            .into_iter()
+            .flatten()
+            .map(Path::new)
+            .collect();
+        let writable: Vec<&Path> = options
+            .reports
+            .iter()
+            .map(|(_, path)| path)
+            .chain(&options.write_baseline)
+            .map(|path| match Path::new(path).parent() {
+                Some(dir) if !dir.as_os_str().is_empty() => dir,
+                _ => Path::new("."),
+            })
+            .chain(options.extract.as_deref().map(Path::new))
+            .collect();
+        sandbox::enter(&readable, &writable)
+    };
+    #[cfg(not(target_os = "linux"))]
+    let result: Result<(), String> = Err("--sandbox is only supported on Linux".to_string());
+    if let Err(error) = result {
+        eprintln!("{}", error);
+        process::exit(exit::USAGE);
+    }
+}
+
+/// Runs the selected report over every input, recording unreadable files in `failures`.
+fn run(options: &Options, failures: &mut Vec<(String, String)>) -> usize {
+    if options.subcommand == Some("linkage") {
+        failures.extend(linkage::display_linkage(&options.paths));
+        return options.paths.len();
+    }
+    if options.subcommand == Some("call-graph") {
+        failures.extend(callgraph::display_call_graph(
+            &options.paths,
+            options.graph.as_deref(),
+        ));
+        return options.paths.len();
+    }
+    if options.subcommand == Some("deps") {
+        failures.extend(deps::display_deps(&options.paths, options.graph.as_deref()));
+        return options.paths.len();
+    }
+    if options.subcommand == Some("core-check") {
+        failures.extend(coredump::display_core_check(&options.paths));
+        return options.paths.len();
+    }
+    if options.subcommand == Some("policy") {
+        let Some((rules, paths)) = options.paths.split_first() else {
+            failures.push((
+                "policy".to_string(),
+                "expects a rules file and the files to check".to_string(),
+            ));
+            return 0;
+        };
+        match policy::load_rules(rules) {
+            Ok(rules) => {
+                let inputs = open_inputs(paths, false, failures);
+                enter_sandbox(options);
+                let targets = collect_targets(inputs, options, failures);
+                failures.extend(policy::display_policy(&targets, &rules));
+            }
+            Err(error) => failures.push((rules.clone(), error)),
+        }
+        return paths.len();
+    }
+    if options.subcommand == Some("manifest") {
+        let (action, manifest, paths) = match options.paths.as_slice() {
+            [action, manifest, paths @ ..] if action == "create" || action == "verify" => {
+                (action.as_str(), manifest, paths)
+            }
+            _ => {
+                failures.push((
+                    "manifest".to_string(),
+                    "expects create or verify, a manifest file and the files".to_string(),
+                ));
+                return 0;
+            }
+        };
+        let inputs = open_inputs(paths, false, failures);
+        let targets = collect_targets(inputs, options, failures);
+        if action == "create" {
+            if let Err(error) = manifest::write_manifest(manifest, &targets) {
+                failures.push((manifest.clone(), error));
+            }
+            return paths.len();
+        }
+        match manifest::load_manifest(manifest) {
+            Ok(files) => failures.extend(manifest::display_verify(&targets, &files)),
+            Err(error) => failures.push((manifest.clone(), error)),
+        }
+        return paths.len();
+    }
+    if options.subcommand == Some("unpatch") {
+        failures.extend(patch::unpatch(options));
+        return options.paths.len();
+    }
+    if options.subcommand == Some("explain") {
+        let (field, paths) = match options.paths.split_first() {
+            Some((field, paths)) => (Some(field.as_str()), paths),
+            None => (None, &[][..]),
+        };
+        let inputs = open_inputs(paths, false, failures);
+        enter_sandbox(options);
+        let targets = collect_targets(inputs, options, failures);
+        failures.extend(explain::display_explain(field, &targets));
+        return paths.len();
+    }
+    if options.subcommand == Some("translate") {
+        failures.extend(translate::translate(options));
+        return options.paths.len().min(1);
+    }
+    if options.subcommand == Some("scan-image") {
+        failures.extend(image::scan_images(&options.paths));
+        return options.paths.len();
+    }
+    if options.subcommand == Some("selftest") {
+        failures.extend(selftest::selftest());
+        return 0;
+    }
+    #[cfg(feature = "fetch-fixtures")]
+    if options.subcommand == Some("fetch-fixtures") {
+        failures.extend(fetch::fetch_fixtures(options.paths.first()));
+        return 0;
+    }
+
+    if options.subcommand == Some("serve") {
+        failures.extend(serve::serve(options));
+        return 0;
+    }
+
+    if let Some(pid) = options.pid {
+        failures.extend(pid::display_process(pid));
+        return 0;
+    }
+
+    if options.daemon {
+        failures.extend(daemon::daemon(options));
+        return 0;
+    }
+
+    // Packages get one summary each; everything else is reported as usual.
+    let (packages, paths): (Vec<String>, Vec<String>) = options
+        .paths
+        .iter()
+        .cloned()
+        .partition(|path| package::is_package(path));
+    // Everything is opened first, so that the archives are unpacked and the
+    // inputs parsed inside the sandbox.
+    let opened = package::open_packages(&packages, failures);
+    let inputs = open_inputs(&paths, options.header_only(), failures);
+    enter_sandbox(options);
+    failures.extend(package::scan_packages(opened));
+    let unopened = paths.len() - inputs.len();
+    if paths.is_empty() && !packages.is_empty() {
+        return packages.len();
+    }
+    packages.len() + unopened + report(inputs, options, failures)
+}
+
+/// Runs the selected report over inputs that are already open. Returns how
+/// many files it processed, those that were not ELF included.
+pub(crate) fn report(
+    inputs: Vec<(String, Loader)>,
+    options: &Options,
+    failures: &mut Vec<(String, String)>,
+) -> usize {
+    if options.carve {
+        let scans = container::scan_all(&inputs);
+        for ((path, loader), scanned) in inputs.iter().zip(scans) {
+            timings::for_file(path, || {
+                carve::display_carve(path, loader, scanned, options.extract.as_deref())
+            });
+        }
+        return inputs.len();
+    }
+
+    let before = failures.len();
+    let targets = collect_targets(inputs, options, failures);
+    // An input can turn into several images, or into none.
+    let dropped: HashSet<&str> = failures[before..]
+        .iter()
+        .map(|(label, _)| label.as_str())
+        .filter(|label| !targets.iter().any(|(target, _)| target == label))
+        .collect();
+    let processed = targets.len() + dropped.len();
+    markdown::record(&targets, options.baseline.as_deref());
+    report_targets(targets, options, failures);
+    processed
+}
+
+/// Runs the selected report over the ELF images collected from the inputs.
+fn report_targets(
+    targets: Vec<(String, Loader)>,
+    options: &Options,
+    failures: &mut Vec<(String, String)>,
+) {
+    if options.subcommand == Some("obj-summary") {
+        display_each(&targets, objsummary::display_obj_summary);
+        return;
+    }
+    if options.subcommand == Some("dry-load") {
+        failures.extend(dryload::display_dry_load(&targets));
+        return;
+    }
+    #[cfg(feature = "signing")]
+    if options.subcommand == Some("sign") {
+        let Some(key) = &options.key else {
+            eprintln!("sign needs the secret key file after --key");
+            failures.push(("sign".to_string(), "no --key".to_string()));
+            return;
+        };
+        let destination = match patch::Destination::from_options(options, targets.len()) {
+            Ok(destination) => destination,
+            Err(error) => {
+                eprintln!("sign: {}", error);
+                failures.push(("sign".to_string(), error));
+                return;
+            }
+        };
+        let secret = match sign::read_key(key) {
+            Ok(secret) => secret,
+            Err(error) => {
+                failures.push((key.clone(), error));
+                return;
+            }
+        };
+        let mut journal = patch::Journal::default();
+        failures.extend(sign::display_sign(
+            &targets,
+            &secret,
+            &destination,
+            &mut journal,
+        ));
+        if let Some(path) = &options.journal {
+            if let Err(error) = journal.write(path) {
+                failures.push((path.clone(), error));
+            }
+        }
+        return;
+    }
+    #[cfg(feature = "signing")]
+    if let (Some("verify"), Some(key)) = (options.subcommand, &options.public_key) {
+        match sign::read_key(key) {
+            Ok(public) => failures.extend(sign::display_verify(&targets, &public)),
+            Err(error) => failures.push((key.clone(), error)),
+        }
+        return;
+    }
+    if options.subcommand == Some("verify") {
+        let Some(golden) = &options.golden else {
+            eprintln!("verify needs the reference image after --golden");
+            failures.push(("verify".to_string(), "no --golden image".to_string()));
+            return;
+        };
+        let mut golden = collect_targets(
+            open_inputs(std::slice::from_ref(golden), false, failures),
+            options,
+            failures,
+        );
+        if let Some(golden) = golden.pop() {
+            failures.extend(golden::display_verify(&golden, &targets, &options.masks));
+        }
+        return;
+    }
+    if options.subcommand == Some("resolve-undefs") {
+        let libraries = collect_targets(
+            open_inputs(&options.with, false, failures),
+            options,
+            failures,
+        );
+        if libraries.is_empty() {
+            eprintln!("resolve-undefs needs at least one library after --with");
+        }
+        for (label, app) in &targets {
+            let missing = timings::for_file(label, || {
+                undefs::display_resolve_undefs(label, app, &libraries)
+            });
+            if missing > 0 {
+                failures.push((label.clone(), format!("{} unresolved symbol(s)", missing)));
+            }
+        }
+        return;
+    }
+    if options.subcommand == Some("lint-exports") {
+        for (label, library) in &targets {
+            let violations = timings::for_file(label, || {
+                exports::display_export_lint(label, library, options)
+            });
+            finding::record(label, exports::LINT_CHECKS, &violations);
+            if !violations.is_empty() {
+                failures.push((
+                    label.clone(),
+                    format!("{} policy violation(s)", violations.len()),
+                ));
+            }
+        }
+        return;
+    }
+    if options.subcommand == Some("resolve-preview") {
+        resolve::display_resolution(&targets);
+        return;
+    }
+    if options.checksec {
+        display_each(&targets, checksec::display_checksec);
+        if finding::is_enabled() {
+            for (label, loader) in &targets {
+                finding::record(label, loader.checksec_checks(), &loader.checksec_findings());
+            }
+        }
+        return;
+    }
+    if options.textrel {
+        display_each(&targets, textrel::display_textrel);
+        return;
+    }
+    if options.android {
+        display_each(&targets, android::display_android);
+        return;
+    }
+    if options.notes {
+        display_each(&targets, note::display_notes);
+        return;
+    }
+    if options.classify {
+        display_each(&targets, linkage::display_classify);
+        return;
+    }
+    if options.bundle {
+        display_each_at(&targets, bundle::display_bundle);
+        return;
+    }
+    if options.brief {
+        display_each(&targets, brief::display_brief);
+        return;
+    }
+    if options.debug_info {
+        display_each_at(&targets, debuginfo::display_debug_info);
+        return;
+    }
+    if options.arch_info {
+        display_each(&targets, arch::display_arch_info);
+        return;
+    }
+    if options.dlopen {
+        display_each(&targets, dlopen::display_dlopen);
+        return;
+    }
+    if options.baseline.is_some() || options.write_baseline.is_some() {
+        if let Some(path) = &options.baseline {
+            match baseline::load_baseline(path) {
+                Ok(files) => failures.extend(baseline::display_baseline(
+                    &targets,
+                    &files,
+                    options.max_growth.unwrap_or(baseline::DEFAULT_MAX_GROWTH),
+                )),
+                Err(error) => failures.push((path.clone(), error)),
+            }
+        }
+        // Comparing first lets one run check a release and then adopt it.
+        if let Some(path) = &options.write_baseline {
+            if let Err(error) = baseline::write_baseline(path, &targets) {
+                failures.push((path.clone(), error));
+            }
+        }
+        return;
+    }
+    if options.size {
+        display_each(&targets, sizes::display_size);
+        return;
+    }
+    if options.mitigations {
+        display_each(&targets, mitigations::display_mitigations);
+        return;
+    }
+    if options.boot {
+        display_each(&targets, boot::display_boot);
+        return;
+    }
+    if options.normalized {
+        display_each(&targets, normalized::display_normalized_elf);
+        return;
+    }
+    if options.ima {
+        failures.extend(ima::display_ima(&targets));
+        return;
+    }
+    if let Some(query) = &options.query {
+        failures.extend(query::display_query(&targets, query));
+        return;
+    }
+    #[cfg(feature = "templates")]
+    if let Some(path) = &options.template {
+        failures.extend(template::display_template(&targets, path));
+        return;
+    }
+    if options.source_paths {
+        display_each(&targets, sourcepath::display_source_paths);
+        return;
+    }
+    if options.split_dwarf {
+        failures.extend(dwo::display_split_dwarf(&targets));
+        return;
+    }
+    if options.verify_checksums {
+        let symvers = match options.symvers.as_deref().map(checksum::load_symvers) {
+            Some(Ok(symvers)) => Some(symvers),
+            Some(Err(error)) => {
+                failures.push((options.symvers.clone().unwrap_or_default(), error));
+                return;
+            }
+            None => None,
+        };
+        failures.extend(checksum::display_checksums(&targets, symvers.as_ref()));
+        return;
+    }
+    if options.stack_sizes {
+        for (label, loader) in &targets {
+            let over = timings::for_file(label, || {
+                stack::display_stack_sizes(label, loader, options.max_stack)
+            });
+            if over > 0 {
+                failures.push((
+                    label.clone(),
+                    format!("{} function(s) over the stack limit", over),
+                ));
+            }
+        }
+        return;
+    }
+    #[cfg(feature = "cross-check")]
+    if options.cross_check {
+        failures.extend(crosscheck::display_cross_check(&targets));
+        return;
+    }
+    #[cfg(feature = "plugins")]
+    if !options.plugins.is_empty() {
+        failures.extend(plugin::display_plugins(&targets, &options.plugins));
+        return;
+    }
+    if options.groups {
+        display_each(&targets, group::display_groups);
+        return;
+    }
+    if let Some(pattern) = &options.grep {
+        for (label, loader) in &targets {
+            timings::for_file(label, || grep::display_grep(label, loader, pattern));
+        }
+        return;
+    }
+    if options.strings_scan {
+        display_each(&targets, strings::display_strings);
+        return;
+    }
+    if let Some(field) = &options.explain {
+        failures.extend(explain::display_explain(Some(field), &targets));
+        return;
+    }
+    if options.text_stats {
+        display_each(&targets, textstats::display_text_stats);
+        return;
+    }
+    if options.functions {
+        display_each(&targets, functions::display_functions);
+        return;
+    }
+
+    if targets.is_empty() {
+        return;
+    }
+
+    let mut results: HashMap<String, Vec<String>> = HashMap::new();
+
+    for (label, loader) in &targets {
+        timings::for_file(label, || {
+            timings::measure(Phase::Header, || {
+                results
+                    .entry("EI_CLASS".to_string())
+                    .or_default()
+                    .push(loader.get_ei_class().to_string());
+                results
+                    .entry("EI_DATA".to_string())
+                    .or_default()
+                    .push(loader.get_ei_data().to_string());
+                results
+                    .entry("EI_VERSION".to_string())
+                    .or_default()
+                    .push(loader.get_ei_version().to_string());
+                results
+                    .entry("E_TYPE".to_string())
+                    .or_default()
+                    .push(loader.get_e_type().to_string());
+
+                results
+                    .entry("E_MACHINE".to_string())
+                    .or_default()
+                    .push(loader.machine_name());
+
+                results
+                    .entry("E_VERSION".to_string())
+                    .or_default()
+                    .push(loader.get_e_version().to_string());
+                results
+                    .entry("E_ENTRY".to_string())
+                    .or_default()
+                    .push(loader.get_e_entry().to_string());
+                results
+                    .entry("E_PHOFF".to_string())
+                    .or_default()
+                    .push(loader.get_e_phoff().to_string());
+                results
+                    .entry("E_SHOFF".to_string())
+                    .or_default()
+                    .push(loader.get_e_shoff().to_string());
+                results
+                    .entry("E_FLAGS".to_string())
+                    .or_default()
+                    .push(loader.get_e_flags().to_string());
+                results
+                    .entry("E_EHSIZE".to_string())
+                    .or_default()
+                    .push(loader.get_e_ehsize().to_string());
+                results
+                    .entry("E_PHENTSIZE".to_string())
+                    .or_default()
+                    .push(loader.get_e_phentsize().to_string());
+                results
+                    .entry("E_PHNUM".to_string())
+                    .or_default()
+                    .push(loader.get_e_phnum().to_string());
+                results
+                    .entry("E_SHENTSIZE".to_string())
+                    .or_default()
+                    .push(loader.get_e_shentsize().to_string());
+                results
+                    .entry("E_SHNUM".to_string())
+                    .or_default()
+                    .push(loader.get_e_shnum().to_string());
+                results
+                    .entry("E_SHSTRNDX".to_string())
+                    .or_default()
+                    .push(loader.get_e_shstrndx().to_string());
+            })
+        });
+    }
+
+    // Unusual values are annotated in place, such as `[! expected 64 for ELF64]`.
+    let anomalies: Vec<_> = targets
+        .iter()
+        .map(|(_, loader)| loader.header_anomalies())
+        .collect();
+    let rows = [
+        ("Architecture", "EI_CLASS", false, ""),
+        ("Endian", "EI_DATA", false, ""),
+        ("ELF Header Version", "EI_VERSION", false, ""),
+        ("File Type", "E_TYPE", false, ""),
+        ("Machine Type", "E_MACHINE", false, ""),
+        ("Object File Version", "E_VERSION", true, ""),
+        ("Entry Point", "E_ENTRY", true, ""),
+        ("Program Header Offset", "E_PHOFF", true, ""),
+        ("Section Header Offset", "E_SHOFF", true, ""),
+        ("Flags", "E_FLAGS", false, ""),
+        ("Header's Size", "E_EHSIZE", false, " bytes"),
+        ("Per Program Header's Size", "E_PHENTSIZE", false, " bytes"),
+        ("Program Header's Number", "E_PHNUM", false, ""),
+        ("Per Section Header's Size", "E_SHENTSIZE", false, " bytes"),
+        ("Section Header's Number", "E_SHNUM", false, ""),
+        ("Entry Index", "E_SHSTRNDX", false, ""),
+    ]
+    .into_iter()
+    .map(|(name, key, hex, suffix)| {
+        let mut values = format_elem(&results[key], hex, suffix);
+        for (value, anomalies) in values.iter_mut().zip(&anomalies) {
+            for anomaly in anomalies {
+                if anomaly.field == key {
+                    value.push_str("  ");
+                    value.push_str(&anomaly.annotation());
+                }
+            }
+        }
+        (name.to_string(), values)
+    })
+    .collect();
+    output::comparison(
+        targets.iter().map(|(label, _)| label.clone()).collect(),
+        rows,
+    );
+}
@@ -0,0 +1,121 @@
+use std::fmt;
+use std::sync::{PoisonError, RwLock};
+
+use crate::section::{SHT_DYNSYM, SHT_SYMTAB};
+use crate::Loader;
+
+/// How much a file may make the parser walk, so that crafted counts and sizes
+/// cannot take unbounded time or memory. The defaults are well above what
+/// real binaries, kernels and browsers included, need.
+#[derive(Clone, Copy)]
+pub struct Limits {
+    /// Section headers, extended numbering included.
+    pub max_sections: usize,
+    /// Entries of one symbol table.
+    pub max_symbols: usize,
+    /// Name and descriptor of one note, in bytes.
+    pub max_note_size: u64,
+    /// Levels of libraries loading libraries in `deps`.
+    pub max_depth: usize,
+}
+
+pub const DEFAULT: Limits = Limits {
+    max_sections: 1 << 20,
+    max_symbols: 1 << 24,
+    max_note_size: 64 << 20,
+    max_depth: 64,
+};
+
+impl Default for Limits {
+    fn default() -> Limits {
+        DEFAULT
+    }
+}
+
+/// For the whole process, so that the threads of `serve` and of the container
+/// scans parse with the limits of the command line too.
+static LIMITS: RwLock<Limits> = RwLock::new(DEFAULT);
+
+/// Sets the limits from `--max-sections`, `--max-symbols`, `--max-note-size`
+/// and `--max-depth` for the rest of the run.
+pub fn set(limits: Limits) {
+    *LIMITS.write().unwrap_or_else(PoisonError::into_inner) = limits;
+}
+
+pub fn get() -> Limits {
+    *LIMITS.read().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// A file asking for more than a limit allows.
+pub struct LimitExceeded {
+    /// The option setting the limit, e.g. `--max-symbols`.
+    pub limit: &'static str,
+    /// What is too large, e.g. `.symtab`.
+    pub subject: String,
+    pub found: u64,
+    pub max: u64,
+    /// What `found` and `max` count, e.g. `symbols`.
+    pub unit: &'static str,
+}
+
+impl fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "limits exceeded: {} has {} {}, more than {} {}",
+            self.subject, self.found, self.unit, self.limit, self.max
+        )
+    }
+}
+
+impl Loader {
+    /// Checks the counts and sizes the headers claim against the limits before
+    /// anything walks them.
+    pub fn check_limits(&self) -> Result<(), LimitExceeded> {
+        let limits = get();
+        let sections = self.shnum();
+        if sections > limits.max_sections {
+            return Err(LimitExceeded {
+                limit: "--max-sections",
+                subject: "the section header table".to_string(),
+                found: sections as u64,
+                max: limits.max_sections as u64,
+                unit: "entries",
+            });
+        }
+        for table in self
+            .section_headers()
+            .iter()
+            .filter(|section| matches!(section.sh_type, SHT_SYMTAB | SHT_DYNSYM))
+        {
+            let entry_size = match table.sh_entsize {
+                0 if self.is_64bit() => 24,
+                0 => 16,
+                size => size,
+            };
+            let symbols = table.sh_size / entry_size;
+            if symbols > limits.max_symbols as u64 {
+                return Err(LimitExceeded {
+                    limit: "--max-symbols",
+                    subject: table.name.clone(),
+                    found: symbols,
+                    max: limits.max_symbols as u64,
+                    unit: "symbols",
+                });
+            }
+        }
+        if let Some((location, size)) = self
+            .largest_note()
+            .filter(|(_, size)| *size > limits.max_note_size)
+        {
+            return Err(LimitExceeded {
+                limit: "--max-note-size",
+                subject: format!("a note in {}", location),
+                found: size,
+                max: limits.max_note_size,
+                unit: "bytes",
+            });
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,324 @@
+use std::fs;
+use std::path::Path;
+
+use log::debug;
+
+use crate::attributes::read_uleb128;
+use crate::brief;
+use crate::dynamic::{DF_1_PIE, DT_FLAGS_1};
+use crate::output;
+use crate::segment::{PT_DYNAMIC, PT_INTERP};
+use crate::symbol::SHN_UNDEF;
+use crate::{display_line, Loader};
+
+const ET_REL: u16 = 1;
+const ET_EXEC: u16 = 2;
+const ET_DYN: u16 = 3;
+const ET_CORE: u16 = 4;
+
+/// The owner and type of the note the Go linker writes its build ID into.
+const GO_NOTE: &str = "Go";
+const NT_GO_BUILD_ID: u32 = 4;
+/// The start of `.go.buildinfo`; the pointer size and flags follow.
+const GO_BUILDINFO_MAGIC: &[u8] = b"\xff Go buildinf:";
+/// Set in the flags when the version string is inline rather than behind a pointer.
+const GO_BUILDINFO_INLINE: u8 = 0x2;
+const GO_BUILDINFO_HEADER: usize = 32;
+
+/// Libraries that are commonly linked statically into otherwise dynamic binaries: the
+/// name reported, the soname stem of the real library, and a symbol only it defines.
+const BUNDLED_LIBRARIES: &[(&str, &str, &str)] = &[
+    ("zlib", "libz.", "zlibVersion"),
+    ("bzip2", "libbz2.", "BZ2_bzlibVersion"),
+    ("xz/liblzma", "liblzma.", "lzma_version_string"),
+    ("zstd", "libzstd.", "ZSTD_versionNumber"),
+    ("OpenSSL libcrypto", "libcrypto.", "OpenSSL_version"),
+    ("OpenSSL libssl", "libssl.", "SSL_CTX_new"),
+    ("libpng", "libpng", "png_get_libpng_ver"),
+    ("libjpeg", "libjpeg.", "jpeg_CreateDecompress"),
+    ("SQLite", "libsqlite3.", "sqlite3_libversion"),
+    ("libcurl", "libcurl.", "curl_version"),
+    ("libxml2", "libxml2.", "xmlCheckVersion"),
+];
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Linkage {
+    Static,
+    StaticPie,
+    Dynamic,
+}
+
+impl Linkage {
+    fn as_str(&self) -> &str {
+        match self {
+            Linkage::Static => "static",
+            Linkage::StaticPie => "static-pie",
+            Linkage::Dynamic => "dynamic",
+        }
+    }
+}
+
+/// What a file is, told apart further than e_type does: ET_DYN is a shared
+/// library as well as a PIE, and a PIE may carry its own loader.
+#[derive(Clone, Copy, PartialEq)]
+pub enum FileKind {
+    Relocatable,
+    /// ET_EXEC without a dynamic section.
+    Static,
+    /// ET_DYN that relocates itself at startup, without an interpreter.
+    StaticPie,
+    /// ET_EXEC loaded by the dynamic linker.
+    Dynamic,
+    /// ET_DYN with an interpreter or DF_1_PIE.
+    DynamicPie,
+    SharedLibrary,
+    Core,
+    Other,
+}
+
+impl FileKind {
+    pub fn as_str(&self) -> &str {
+        match self {
+            FileKind::Relocatable => "relocatable object",
+            FileKind::Static => "static executable",
+            FileKind::StaticPie => "static-pie executable",
+            FileKind::Dynamic => "dynamic executable",
+            FileKind::DynamicPie => "dynamic pie executable",
+            FileKind::SharedLibrary => "shared library",
+            FileKind::Core => "core file",
+            FileKind::Other => "other",
+        }
+    }
+
+    pub fn is_pie(&self) -> bool {
+        matches!(self, FileKind::StaticPie | FileKind::DynamicPie)
+    }
+}
+
+/// A binary built by the Go toolchain, whose own linker lays out segments
+/// unlike ld: text from 0x401000 without PT_INTERP unless cgo is used.
+pub struct GoBuild {
+    /// The toolchain version from `.go.buildinfo`, such as `go1.22.1`.
+    pub version: Option<String>,
+    /// Linked against C code through cgo, and with it usually dynamically.
+    pub cgo: bool,
+}
+
+impl Loader {
+    /// The kind of file, from e_type, the interpreter and the dynamic section.
+    pub fn file_kind(&self) -> FileKind {
+        match self.raw_e_type() {
+            ET_REL => FileKind::Relocatable,
+            ET_CORE => FileKind::Core,
+            ET_EXEC if self.program_header_by_type(PT_DYNAMIC).is_none() => FileKind::Static,
+            ET_EXEC => FileKind::Dynamic,
+            ET_DYN => match self.linkage() {
+                Some(Linkage::StaticPie) => FileKind::StaticPie,
+                _ if self.program_header_by_type(PT_INTERP).is_some()
+                    || self.dynamic_value(DT_FLAGS_1).unwrap_or(0) & DF_1_PIE != 0 =>
+                {
+                    FileKind::DynamicPie
+                }
+                _ => FileKind::SharedLibrary,
+            },
+            _ => FileKind::Other,
+        }
+    }
+
+    /// The Go build, recognised by the Go build ID note or `.go.buildinfo`.
+    pub fn go_build(&self) -> Option<GoBuild> {
+        let buildinfo = self
+            .section_by_name(".go.buildinfo")
+            .map(|section| self.section_data(&section).to_vec());
+        if buildinfo.is_none()
+            && self.find_note(GO_NOTE, NT_GO_BUILD_ID).is_none()
+            && self.section_by_name(".gopclntab").is_none()
+        {
+            return None;
+        }
+        let version = buildinfo
+            .filter(|data| {
+                data.starts_with(GO_BUILDINFO_MAGIC)
+                    && data
+                        .get(15)
+                        .is_some_and(|flags| flags & GO_BUILDINFO_INLINE != 0)
+            })
+            .and_then(|data| {
+                let rest = data.get(GO_BUILDINFO_HEADER..)?;
+                // The length goes in front as an unsigned LEB128.
+                let (len, skip) = read_uleb128(rest)?;
+                let version = rest.get(skip..skip.checked_add(len as usize)?)?;
+                Some(String::from_utf8_lossy(version).into_owned())
+            });
+        let mut symbols = self.static_symbols();
+        symbols.extend(self.dynamic_symbols());
+        let cgo = symbols
+            .iter()
+            .any(|symbol| matches!(symbol.name.as_str(), "_cgo_init" | "x_cgo_init"))
+            || self.program_header_by_type(PT_INTERP).is_some();
+        Some(GoBuild { version, cgo })
+    }
+
+    /// The build ID the Go linker notes, a string unlike the GNU one.
+    pub fn go_build_id(&self) -> Option<String> {
+        let note = self.find_note(GO_NOTE, NT_GO_BUILD_ID)?;
+        let end = note
+            .desc
+            .iter()
+            .position(|&byte| byte == 0)
+            .unwrap_or(note.desc.len());
+        Some(String::from_utf8_lossy(&note.desc[..end]).into_owned())
+    }
+
+    /// How the file was linked, or `None` for objects that are not linked at all.
+    pub fn linkage(&self) -> Option<Linkage> {
+        let e_type = self.raw_e_type();
+        if e_type != ET_EXEC && e_type != ET_DYN {
+            return None;
+        }
+        if self.program_header_by_type(PT_DYNAMIC).is_none() {
+            return Some(Linkage::Static);
+        }
+        if self.program_header_by_type(PT_INTERP).is_some() || !self.needed_libraries().is_empty() {
+            return Some(Linkage::Dynamic);
+        }
+        // Without an interpreter or dependencies, a PIE relocates itself at startup;
+        // anything else is a shared library that simply needs nothing else.
+        if self.dynamic_value(DT_FLAGS_1).unwrap_or(0) & DF_1_PIE != 0 {
+            Some(Linkage::StaticPie)
+        } else {
+            Some(Linkage::Dynamic)
+        }
+    }
+
+    /// Common libraries whose code is linked into this file instead of loaded from
+    /// their shared library, recognised by a symbol only that library defines.
+    pub fn bundled_libraries(&self) -> Vec<&'static str> {
+        let soname = self.soname().unwrap_or_default();
+        let mut symbols = self.static_symbols();
+        symbols.extend(self.dynamic_symbols());
+        BUNDLED_LIBRARIES
+            .iter()
+            .filter(|(_, stem, _)| !soname.starts_with(stem))
+            .filter(|(_, _, marker)| {
+                symbols
+                    .iter()
+                    .any(|symbol| symbol.name == *marker && symbol.st_shndx != SHN_UNDEF)
+            })
+            .map(|(name, _, _)| *name)
+            .collect()
+    }
+}
+
+/// Every regular file below `dir`. Symbolic links are not followed, so a link cycle
+/// or a link out of the tree cannot make the walk loop or escape.
+pub(crate) fn walk(dir: &Path, files: &mut Vec<String>, errors: &mut Vec<(String, String)>) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(error) => {
+            errors.push((dir.display().to_string(), error.to_string()));
+            return;
+        }
+    };
+    let mut paths: Vec<_> = entries.filter_map(|entry| entry.ok()).collect();
+    paths.sort_by_key(|entry| entry.path());
+    for entry in paths {
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() {
+            walk(&entry.path(), files, errors);
+        } else if file_type.is_file() && entry.metadata().is_ok_and(|meta| meta.len() > 0) {
+            files.push(entry.path().display().to_string());
+        }
+    }
+}
+
+/// Classifies every ELF executable and shared library under `dirs`. Returns the
+/// directories or files that could not be read.
+pub fn display_linkage(dirs: &[String]) -> Vec<(String, String)> {
+    let mut files = Vec::new();
+    let mut errors = Vec::new();
+    for dir in dirs {
+        walk(Path::new(dir), &mut files, &mut errors);
+    }
+
+    let mut counts = [0usize; 3];
+    let mut bundling = Vec::new();
+    output::begin(&format!("{:<12} File", "Linkage"));
+    for path in &files {
+        let loader = match Loader::open(path) {
+            Ok(loader) => loader,
+            Err(error) => {
+                errors.push((path.clone(), error.to_string()));
+                continue;
+            }
+        };
+        if !loader.is_elf() || loader.header_truncation().is_some() {
+            debug!("{}: not a complete ELF file, skipped", path);
+            continue;
+        }
+        let Some(linkage) = loader.linkage() else {
+            continue;
+        };
+        counts[linkage as usize] += 1;
+        output::item(0, &format!("{:<12} {}", linkage.as_str(), path));
+        if linkage == Linkage::Dynamic {
+            let bundled = loader.bundled_libraries();
+            if !bundled.is_empty() {
+                bundling.push((path.clone(), bundled));
+            }
+        }
+    }
+
+    output::begin("");
+    display_line("Static", &counts[Linkage::Static as usize].to_string());
+    display_line(
+        "Static PIE",
+        &counts[Linkage::StaticPie as usize].to_string(),
+    );
+    display_line("Dynamic", &counts[Linkage::Dynamic as usize].to_string());
+    display_line(
+        "Dynamic With Bundled Libraries",
+        &bundling.len().to_string(),
+    );
+    for (path, bundled) in &bundling {
+        output::item(1, &format!("{}: {}", path, bundled.join(", ")));
+    }
+    output::end();
+    errors
+}
+
+/// `--classify`: what the file is beyond e_type, with the facts the kind was
+/// told from and whether the Go toolchain built it.
+pub fn display_classify(path: &str, loader: &Loader) {
+    output::begin(path);
+    display_line("Kind", loader.file_kind().as_str());
+    display_line(
+        "Interpreter",
+        &brief::interpreter(loader).unwrap_or_else(|| "None".to_string()),
+    );
+    let yes_no = |value: bool| if value { "Yes" } else { "No" };
+    display_line(
+        "Dynamic Section",
+        yes_no(loader.program_header_by_type(PT_DYNAMIC).is_some()),
+    );
+    display_line(
+        "DF_1_PIE",
+        yes_no(loader.dynamic_value(DT_FLAGS_1).unwrap_or(0) & DF_1_PIE != 0),
+    );
+    display_line(
+        "Needed Libraries",
+        &loader.needed_libraries().len().to_string(),
+    );
+    let go = match loader.go_build() {
+        Some(go) => format!(
+            "{}{}",
+            go.version.as_deref().unwrap_or("unknown version"),
+            if go.cgo { ", cgo" } else { ", pure Go" }
+        ),
+        None => "No".to_string(),
+    };
+    display_line("Go", &go);
+    output::end();
+}
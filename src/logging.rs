@@ -0,0 +1,66 @@
+use std::env;
+use std::io::{self, IsTerminal};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+/// Whether the level prefixes are colored, set once by `init`.
+static COLOR: AtomicBool = AtomicBool::new(false);
+
+/// Writes diagnostics to stderr, prefixed with their level, so they never end up
+/// in the report on stdout.
+struct StderrLogger;
+
+impl Log for StderrLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            let level = record.level().as_str().to_lowercase();
+            let color = match record.level() {
+                Level::Error => "1;31",
+                Level::Warn => "1;33",
+                _ => "1;36",
+            };
+            if COLOR.load(Ordering::Relaxed) {
+                eprintln!("\x1b[{}m{}\x1b[0m: {}", color, level, record.args());
+            } else {
+                eprintln!("{}: {}", level, record.args());
+            }
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: StderrLogger = StderrLogger;
+
+/// Whether to color diagnostics for `--color` or `ELFHP_COLOR`: `auto`, the
+/// default, does when stderr is a terminal and `NO_COLOR` is not set.
+pub fn use_color(choice: Option<&str>) -> bool {
+    match choice {
+        Some("always") => true,
+        Some("never") => false,
+        _ => {
+            env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+                && io::stderr().is_terminal()
+        }
+    }
+}
+
+/// Installs the logger. `verbosity` is the number of `-v` given: one traces the
+/// decisions taken per file, two also how the tables inside it were read.
+pub fn init(verbosity: u8, color: bool) {
+    COLOR.store(color, Ordering::Relaxed);
+    let level = match verbosity {
+        0 => LevelFilter::Warn,
+        1 => LevelFilter::Info,
+        2 => LevelFilter::Debug,
+        _ => LevelFilter::Trace,
+    };
+    if log::set_logger(&LOGGER).is_ok() {
+        log::set_max_level(level);
+    }
+}
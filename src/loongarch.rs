@@ -0,0 +1,77 @@
+use crate::arch::{ArchExt, TextModel};
+use crate::Loader;
+
+const EM_LOONGARCH: u16 = 258;
+
+const EF_LOONGARCH_ABI_MODIFIER_MASK: u32 = 0x7;
+const EF_LOONGARCH_ABI_SOFT_FLOAT: u32 = 0x1;
+const EF_LOONGARCH_ABI_SINGLE_FLOAT: u32 = 0x2;
+const EF_LOONGARCH_ABI_DOUBLE_FLOAT: u32 = 0x3;
+const EF_LOONGARCH_OBJABI_MASK: u32 = 0xc0;
+const EF_LOONGARCH_OBJABI_V1: u32 = 0x40;
+
+/// Describes the LoongArch e_flags. The base ABI name combines the file class
+/// (lp64 or ilp32) with the float modifier in the low bits.
+fn describe_flags(flags: u32, is_64: bool) -> Vec<String> {
+    let base = if is_64 { "lp64" } else { "ilp32" };
+    let abi = match flags & EF_LOONGARCH_ABI_MODIFIER_MASK {
+        EF_LOONGARCH_ABI_SOFT_FLOAT => format!("{}s ABI (soft-float)", base),
+        EF_LOONGARCH_ABI_SINGLE_FLOAT => format!("{}f ABI (single-float)", base),
+        EF_LOONGARCH_ABI_DOUBLE_FLOAT => format!("{}d ABI (double-float)", base),
+        modifier => format!("{} ABI with reserved float modifier {:#x}", base, modifier),
+    };
+    let objabi = match flags & EF_LOONGARCH_OBJABI_MASK {
+        0 => "object ABI v0 (stack-based relocations)".to_string(),
+        EF_LOONGARCH_OBJABI_V1 => "object ABI v1".to_string(),
+        version => format!("reserved object ABI {:#x}", version >> 6),
+    };
+    vec![abi, objabi]
+}
+
+pub struct LoongArch;
+
+impl ArchExt for LoongArch {
+    fn machines(&self) -> &'static [u16] {
+        &[EM_LOONGARCH]
+    }
+
+    fn describe_flags(&self, loader: &Loader, flags: u32) -> Vec<String> {
+        describe_flags(flags, loader.is_64bit())
+    }
+
+    /// The NOP is `andi $zero, $zero, 0`.
+    fn text_model(&self, _loader: &Loader) -> Option<TextModel> {
+        Some(TextModel {
+            average_length: 4.0,
+            alignment: 4,
+            padding: &[&[0x00, 0x00, 0x40, 0x03]],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::describe_flags;
+
+    #[test]
+    fn flags() {
+        assert_eq!(
+            describe_flags(0x43, true),
+            ["lp64d ABI (double-float)", "object ABI v1"]
+        );
+        assert_eq!(
+            describe_flags(0x1, false),
+            [
+                "ilp32s ABI (soft-float)",
+                "object ABI v0 (stack-based relocations)"
+            ]
+        );
+        assert_eq!(
+            describe_flags(0xc4, true),
+            [
+                "lp64 ABI with reserved float modifier 0x4",
+                "reserved object ABI 0x3"
+            ]
+        );
+    }
+}
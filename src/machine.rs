@@ -0,0 +1,60 @@
+use crate::{output, Loader};
+
+/// One entry of the e_machine registry.
+pub struct Machine {
+    pub id: u16,
+    pub constant: &'static str,
+    pub name: &'static str,
+}
+
+// Generated by build.rs from machines.tsv.
+include!(concat!(env!("OUT_DIR"), "/machines.rs"));
+
+pub fn lookup(id: u16) -> Option<&'static Machine> {
+    MACHINES
+        .binary_search_by_key(&id, |machine| machine.id)
+        .ok()
+        .map(|index| &MACHINES[index])
+}
+
+/// The e_machine value named by its number, its constant with or without `EM_`,
+/// or its name, ignoring case.
+pub fn parse(text: &str) -> Option<u16> {
+    if let Some(id) = crate::cli::parse_number(text) {
+        return u16::try_from(id).ok();
+    }
+    let constant = text.to_ascii_uppercase();
+    let constant = constant.strip_prefix("EM_").unwrap_or(&constant);
+    MACHINES
+        .iter()
+        .find(|machine| {
+            machine.constant.strip_prefix("EM_") == Some(constant)
+                || machine.name.eq_ignore_ascii_case(text)
+        })
+        .map(|machine| machine.id)
+}
+
+impl Loader {
+    /// The machine name, or `EM_<n>` for machines the registry does not know yet.
+    pub fn machine_name(&self) -> String {
+        let id = self.raw_e_machine();
+        lookup(id)
+            .map(|machine| machine.name.to_string())
+            .unwrap_or_else(|| format!("EM_{}", id))
+    }
+}
+
+/// Prints every e_machine value the tool can name.
+pub fn display_machines() {
+    output::begin("");
+    for machine in MACHINES {
+        output::item(
+            0,
+            &format!(
+                "{:>5}  {:<20} {}",
+                machine.id, machine.constant, machine.name
+            ),
+        );
+    }
+    output::end();
+}
@@ -0,0 +1,296 @@
+//! The Mach-O header, built with the `macho` feature, for teams that want one
+//! tool over ELF and Apple binaries alike. Only the header is read: the magic,
+//! CPU type, file type, load command count and flags; ELF stays what the rest
+//! of the reports are about.
+
+use crate::filetype::{
+    mach_o_cpu, mach_o_file_type, FAT_ARCH_SIZE, FAT_MAGIC, FAT_MAX_ARCHS, MACH_O_MAGICS,
+};
+use crate::normalized::{Kind, NormalizedReport};
+use crate::{display_line, output};
+
+const MH_MAGIC_64: u32 = 0xfeed_facf;
+
+const MH_OBJECT: u32 = 1;
+const MH_EXECUTE: u32 = 2;
+const MH_FVMLIB: u32 = 3;
+const MH_CORE: u32 = 4;
+const MH_PRELOAD: u32 = 5;
+const MH_DYLIB: u32 = 6;
+const MH_BUNDLE: u32 = 8;
+
+const MH_ALLOW_STACK_EXECUTION: u32 = 0x20000;
+const MH_PIE: u32 = 0x200000;
+
+const LC_SEGMENT: u32 = 0x1;
+const LC_SEGMENT_64: u32 = 0x19;
+const LC_MAIN: u32 = 0x8000_0028;
+
+const MH_FLAGS: &[(u32, &str)] = &[
+    (0x1, "NOUNDEFS"),
+    (0x2, "INCRLINK"),
+    (0x4, "DYLDLINK"),
+    (0x8, "BINDATLOAD"),
+    (0x10, "PREBOUND"),
+    (0x20, "SPLIT_SEGS"),
+    (0x80, "TWOLEVEL"),
+    (0x100, "FORCE_FLAT"),
+    (0x200, "NOMULTIDEFS"),
+    (0x400, "NOFIXPREBINDING"),
+    (0x800, "PREBINDABLE"),
+    (0x1000, "ALLMODSBOUND"),
+    (0x2000, "SUBSECTIONS_VIA_SYMBOLS"),
+    (0x4000, "CANONICAL"),
+    (0x8000, "WEAK_DEFINES"),
+    (0x10000, "BINDS_TO_WEAK"),
+    (0x20000, "ALLOW_STACK_EXECUTION"),
+    (0x40000, "ROOT_SAFE"),
+    (0x80000, "SETUID_SAFE"),
+    (0x100000, "NO_REEXPORTED_DYLIBS"),
+    (0x200000, "PIE"),
+    (0x400000, "DEAD_STRIPPABLE_DYLIB"),
+    (0x800000, "HAS_TLV_DESCRIPTORS"),
+    (0x1000000, "NO_HEAP_EXECUTION"),
+    (0x2000000, "APP_EXTENSION_SAFE"),
+    (0x4000000, "NLIST_OUTOFSYNC_WITH_DYLDINFO"),
+    (0x8000000, "SIM_SUPPORT"),
+    (0x80000000, "DYLIB_IN_CACHE"),
+];
+
+/// The fields of `mach_header` and `mach_header_64` but `reserved`.
+pub struct MachHeader {
+    pub magic: u32,
+    pub big_endian: bool,
+    pub cputype: u32,
+    pub cpusubtype: u32,
+    pub filetype: u32,
+    pub ncmds: u32,
+    pub sizeofcmds: u32,
+    pub flags: u32,
+}
+
+fn u32_at(bytes: &[u8], offset: usize, big_endian: bool) -> Option<u32> {
+    let raw: [u8; 4] = bytes.get(offset..offset + 4)?.try_into().ok()?;
+    Some(if big_endian {
+        u32::from_be_bytes(raw)
+    } else {
+        u32::from_le_bytes(raw)
+    })
+}
+
+fn u64_at(bytes: &[u8], offset: usize, big_endian: bool) -> Option<u64> {
+    let first = u64::from(u32_at(bytes, offset, big_endian)?);
+    let second = u64::from(u32_at(bytes, offset + 4, big_endian)?);
+    Some(if big_endian {
+        first << 32 | second
+    } else {
+        second << 32 | first
+    })
+}
+
+/// Whether `bytes` start with a Mach-O or universal binary header; Java class
+/// files, which share the universal magic, are not.
+pub fn is_macho(bytes: &[u8]) -> bool {
+    parse(bytes).is_some() || !fat_slices(bytes).is_empty()
+}
+
+pub fn parse(bytes: &[u8]) -> Option<MachHeader> {
+    let magic: [u8; 4] = bytes.get(..4)?.try_into().ok()?;
+    if !MACH_O_MAGICS.contains(&magic) {
+        return None;
+    }
+    let big_endian = magic[0] == 0xfe;
+    let field = |index: usize| u32_at(bytes, 4 * index, big_endian);
+    Some(MachHeader {
+        magic: field(0)?,
+        big_endian,
+        cputype: field(1)?,
+        cpusubtype: field(2)?,
+        filetype: field(3)?,
+        ncmds: field(4)?,
+        sizeofcmds: field(5)?,
+        flags: field(6)?,
+    })
+}
+
+impl MachHeader {
+    fn is_64bit(&self) -> bool {
+        self.magic == MH_MAGIC_64 || self.magic == MH_MAGIC_64.swap_bytes()
+    }
+
+    /// The `(cmd, offset)` of each load command in `bytes`.
+    fn load_commands(&self, bytes: &[u8]) -> Vec<(u32, usize)> {
+        let mut commands = Vec::new();
+        let mut at = if self.is_64bit() { 32 } else { 28 };
+        for _ in 0..self.ncmds {
+            let (Some(cmd), Some(size)) = (
+                u32_at(bytes, at, self.big_endian),
+                u32_at(bytes, at + 4, self.big_endian),
+            ) else {
+                break;
+            };
+            commands.push((cmd, at));
+            if size < 8 {
+                break;
+            }
+            at += size as usize;
+        }
+        commands
+    }
+
+    /// The address of `LC_MAIN`'s entry point: its offset into the file
+    /// added to where the `__TEXT` segment, which maps the start of the file,
+    /// is loaded.
+    fn entry(&self, bytes: &[u8]) -> Option<u64> {
+        let commands = self.load_commands(bytes);
+        let text = commands.iter().find_map(|&(cmd, at)| {
+            let name = bytes.get(at + 8..at + 24)?;
+            if !name.starts_with(b"__TEXT\0") {
+                return None;
+            }
+            match cmd {
+                LC_SEGMENT_64 => u64_at(bytes, at + 24, self.big_endian),
+                LC_SEGMENT => u32_at(bytes, at + 24, self.big_endian).map(u64::from),
+                _ => None,
+            }
+        })?;
+        let entryoff = commands
+            .iter()
+            .find(|&&(cmd, _)| cmd == LC_MAIN)
+            .and_then(|&(_, at)| u64_at(bytes, at + 8, self.big_endian))?;
+        Some(text.wrapping_add(entryoff))
+    }
+
+    /// The header in the shape `--normalized` gives every format. RELRO has
+    /// no Mach-O counterpart, and canaries and pointer authentication are
+    /// not told by the header.
+    fn normalized(&self, bytes: &[u8]) -> NormalizedReport {
+        let kind = match self.filetype {
+            MH_OBJECT => Kind::Object,
+            MH_EXECUTE | MH_PRELOAD => Kind::Executable,
+            MH_FVMLIB | MH_DYLIB | MH_BUNDLE => Kind::SharedLibrary,
+            MH_CORE => Kind::Core,
+            _ => Kind::Other,
+        };
+        let mut report = NormalizedReport::new("Mach-O", mach_o_cpu(self.cputype), kind);
+        report.bits = Some(if self.is_64bit() { 64 } else { 32 });
+        report.big_endian = Some(self.big_endian);
+        report.entry = self.entry(bytes);
+        match kind {
+            Kind::Executable => {
+                report.nx = Some(self.flags & MH_ALLOW_STACK_EXECUTION == 0);
+                report.aslr = Some(self.flags & MH_PIE != 0);
+            }
+            Kind::SharedLibrary => {
+                report.nx = Some(self.flags & MH_ALLOW_STACK_EXECUTION == 0);
+                report.aslr = Some(true);
+            }
+            _ => {}
+        }
+        report
+    }
+}
+
+/// The offsets of the slices of a universal binary, with their CPU types.
+pub fn fat_slices(bytes: &[u8]) -> Vec<(usize, u32)> {
+    if !bytes.starts_with(FAT_MAGIC) {
+        return Vec::new();
+    }
+    let count = match u32_at(bytes, 4, true) {
+        Some(count) if count > 0 && count < FAT_MAX_ARCHS => count as usize,
+        _ => return Vec::new(),
+    };
+    (0..count)
+        .map_while(|index| {
+            let entry = 8 + index * FAT_ARCH_SIZE;
+            let cputype = u32_at(bytes, entry, true)?;
+            let offset = u32_at(bytes, entry + 8, true)? as usize;
+            Some((offset, cputype))
+        })
+        .collect()
+}
+
+fn flag_names(flags: u32) -> String {
+    let names: Vec<&str> = MH_FLAGS
+        .iter()
+        .filter(|(bit, _)| flags & bit != 0)
+        .map(|(_, name)| *name)
+        .collect();
+    if names.is_empty() {
+        "none".to_string()
+    } else {
+        names.join(", ")
+    }
+}
+
+fn display_header(label: &str, _bytes: &[u8], header: &MachHeader) {
+    output::begin(label);
+    display_line("Magic", &format!("{:#010x}", header.magic));
+    display_line(
+        "Architecture",
+        if header.is_64bit() {
+            "64bit architecture"
+        } else {
+            "32bit architecture"
+        },
+    );
+    display_line(
+        "Endian",
+        if header.big_endian {
+            "Big endian"
+        } else {
+            "Little endian"
+        },
+    );
+    display_line("CPU Type", &mach_o_cpu(header.cputype));
+    display_line("CPU Subtype", &format!("{:#x}", header.cpusubtype));
+    display_line(
+        "File Type",
+        &format!(
+            "{} ({})",
+            mach_o_file_type(header.filetype),
+            header.filetype
+        ),
+    );
+    display_line("Load Commands", &header.ncmds.to_string());
+    display_line(
+        "Size of Load Commands",
+        &format!("{} (bytes)", header.sizeofcmds),
+    );
+    display_line(
+        "Flags",
+        &format!("{:#x} ({})", header.flags, flag_names(header.flags)),
+    );
+    output::end();
+}
+
+fn display_normalized(label: &str, bytes: &[u8], header: &MachHeader) {
+    crate::normalized::display_normalized(label, &header.normalized(bytes));
+}
+
+/// The header of a thin Mach-O binary, or of each slice of a universal one,
+/// labelled `<file>@<offset>`; with `normalized`, in the `--normalized`
+/// shape.
+pub fn display_macho(path: &str, bytes: &[u8], normalized: bool) {
+    let display = if normalized {
+        display_normalized
+    } else {
+        display_header
+    };
+    if let Some(header) = parse(bytes) {
+        display(path, bytes, &header);
+        return;
+    }
+    for (offset, cputype) in fat_slices(bytes) {
+        let label = format!("{}@{:#x}", path, offset);
+        let slice = &bytes[offset.min(bytes.len())..];
+        match parse(slice) {
+            Some(header) => display(&label, slice, &header),
+            None => output::message(&format!(
+                "{}: no Mach-O header for the {} slice",
+                label,
+                mach_o_cpu(cputype)
+            )),
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use crate::cli::{ABOUT, BIN_NAME, DESCRIPTION, ENVIRONMENT, EXAMPLES, FLAGS, SUBCOMMANDS};
+
+/// Escapes text for roff: backslashes, hyphens (so they are not typeset as
+/// hyphenation points) and a leading dot or quote that would start a request.
+fn roff_escape(text: &str) -> String {
+    let escaped = text.replace('\\', "\\e").replace('-', "\\-");
+    if escaped.starts_with('.') || escaped.starts_with('\'') {
+        format!("\\&{}", escaped)
+    } else {
+        escaped
+    }
+}
+
+/// Renders the man page (section 1) from the same tables that drive parsing and `--help`.
+pub fn man_page() -> String {
+    let title = BIN_NAME.to_uppercase();
+    let name = roff_escape(BIN_NAME);
+    let mut page = format!(
+        ".TH {} 1 \"\" \"{} {}\" \"User Commands\"\n",
+        title,
+        name,
+        env!("CARGO_PKG_VERSION")
+    );
+
+    page.push_str(&format!(".SH NAME\n{} \\- {}\n", name, roff_escape(ABOUT)));
+
+    page.push_str(".SH SYNOPSIS\n");
+    page.push_str(&format!(
+        ".B {}\n[\\fIOPTIONS\\fR] \\fIFILE\\fR...\n.br\n",
+        name
+    ));
+    page.push_str(&format!(".B {}\n\\fICOMMAND\\fR [\\fIARGS\\fR]...\n", name));
+
+    page.push_str(&format!(".SH DESCRIPTION\n{}\n", roff_escape(DESCRIPTION)));
+
+    page.push_str(".SH COMMANDS\n");
+    for subcommand in SUBCOMMANDS {
+        page.push_str(&format!(
+            ".TP\n.B {}\n{}\n",
+            roff_escape(subcommand.usage),
+            roff_escape(subcommand.help)
+        ));
+    }
+
+    page.push_str(".SH OPTIONS\n");
+    for flag in FLAGS {
+        let synopsis = if flag.value_name.is_empty() {
+            format!("\\fB{}\\fR", roff_escape(flag.long))
+        } else {
+            format!(
+                "\\fB{}\\fR \\fI{}\\fR",
+                roff_escape(flag.long),
+                roff_escape(flag.value_name)
+            )
+        };
+        page.push_str(&format!(".TP\n{}\n{}\n", synopsis, roff_escape(flag.help)));
+    }
+
+    page.push_str(".SH ENVIRONMENT\n");
+    for (name, description) in ENVIRONMENT {
+        page.push_str(&format!(".TP\n.B {}\n{}\n", name, roff_escape(description)));
+    }
+
+    page.push_str(".SH EXIT STATUS\n");
+    page.push_str("0 on success, 1 when some input files could not be read, 2 when the command line cannot be parsed.\n");
+
+    page.push_str(".SH EXAMPLES\n");
+    for (command, description) in EXAMPLES {
+        page.push_str(&format!(
+            ".TP\n.B {}\n{}\n",
+            roff_escape(command),
+            roff_escape(description)
+        ));
+    }
+    page
+}
@@ -0,0 +1,180 @@
+//! `manifest create` and `manifest verify`: the SHA-256 of every file and of
+//! each of its sections, written once at release time and checked later
+//! against what is deployed, so that a binary patched after deployment is
+//! caught and the changed sections named.
+
+use std::collections::HashMap;
+use std::fs;
+
+use serde_json::{json, Map, Value};
+
+use crate::baseline::key;
+use crate::buildinfo;
+use crate::checksum::sha256;
+use crate::finding::{self, Finding, Severity};
+use crate::patch::write_atomic;
+use crate::section::SHT_NOBITS;
+use crate::{display_line, output, Loader};
+
+/// The check behind the `--report` findings of `manifest verify`.
+const MANIFEST_CHECKS: &[&str] = &["manifest/tampered"];
+
+/// The sections with contents in the file, keyed by name; a name used again
+/// gets `#2`, `#3` and so on, as relocatable objects repeat them.
+fn sections(loader: &Loader) -> Map<String, Value> {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    let mut sections = Map::new();
+    for section in loader.section_headers() {
+        if section.index == 0 || section.sh_type == SHT_NOBITS {
+            continue;
+        }
+        let count = seen.entry(section.name.clone()).or_insert(0);
+        *count += 1;
+        let name = if *count == 1 {
+            section.name.clone()
+        } else {
+            format!("{}#{}", section.name, count)
+        };
+        sections.insert(
+            name,
+            json!({
+                "offset": section.sh_offset,
+                "size": section.sh_size,
+                "sha256": sha256(loader.section_data(&section)),
+            }),
+        );
+    }
+    sections
+}
+
+fn entry(loader: &Loader) -> Value {
+    json!({
+        "size": loader.bytes().len(),
+        "sha256": sha256(loader.bytes()),
+        "sections": sections(loader),
+    })
+}
+
+/// `manifest create`: writes the digests of every target, keyed by file name
+/// as `--write-baseline` does. The keys are sorted and the layout fixed, so
+/// the file can be signed as it is with any detached signature tool.
+pub fn write_manifest(path: &str, targets: &[(String, Loader)]) -> Result<(), String> {
+    let files: Map<String, Value> = targets
+        .iter()
+        .map(|(label, loader)| (key(label), entry(loader)))
+        .collect();
+    let manifest = json!({ "tool": buildinfo::tool(), "files": files });
+    write_atomic(path, format!("{:#}\n", manifest))?;
+    output::message(&format!(
+        "Wrote the manifest of {} file(s) to {}",
+        targets.len(),
+        path
+    ));
+    Ok(())
+}
+
+/// Reads a manifest written by `manifest create`, as its entries by file name.
+pub fn load_manifest(path: &str) -> Result<Map<String, Value>, String> {
+    let text = fs::read_to_string(path).map_err(|error| error.to_string())?;
+    let manifest: Value = serde_json::from_str(&text).map_err(|error| error.to_string())?;
+    match manifest.get("files") {
+        Some(Value::Object(files)) => Ok(files.clone()),
+        _ => Err("not a manifest: expected an object under `files`".to_string()),
+    }
+}
+
+fn digest_of(entry: &Value) -> &str {
+    entry.get("sha256").and_then(Value::as_str).unwrap_or("")
+}
+
+/// The sections that differ from the manifest, one line each.
+fn changed_sections(old: &Value, new: &Value) -> Vec<String> {
+    let sections = |entry: &Value| {
+        entry
+            .get("sections")
+            .and_then(Value::as_object)
+            .cloned()
+            .unwrap_or_default()
+    };
+    let (old_sections, new_sections) = (sections(old), sections(new));
+    let mut changes = Vec::new();
+    for (name, section) in &new_sections {
+        let size = section.get("size").and_then(Value::as_u64).unwrap_or(0);
+        match old_sections.get(name) {
+            None => changes.push(format!("{:<24} added ({} bytes)", name, size)),
+            Some(old) if digest_of(old) != digest_of(section) => {
+                let old_size = old.get("size").and_then(Value::as_u64).unwrap_or(0);
+                if old_size == size {
+                    changes.push(format!("{:<24} modified", name));
+                } else {
+                    changes.push(format!(
+                        "{:<24} modified ({} -> {} bytes)",
+                        name, old_size, size
+                    ));
+                }
+            }
+            Some(_) => {}
+        }
+    }
+    for name in old_sections.keys() {
+        if !new_sections.contains_key(name) {
+            changes.push(format!("{:<24} removed", name));
+        }
+    }
+    changes
+}
+
+/// Prints how a file compares with its manifest entry, and returns why it
+/// fails if it changed.
+fn compare(old: &Value, new: &Value) -> Option<String> {
+    let intact = digest_of(old) == digest_of(new);
+    display_line("SHA-256", digest_of(new));
+    let changes = changed_sections(old, new);
+    display_line("Changed Sections", &changes.len().to_string());
+    for change in &changes {
+        output::item(1, change);
+    }
+    if !intact && changes.is_empty() {
+        output::item(1, "bytes outside the sections differ, such as the headers");
+    }
+    display_line("Result", if intact { "Intact" } else { "Tampered" });
+    (!intact).then(|| {
+        format!(
+            "the file differs from the manifest in {} section(s)",
+            changes.len()
+        )
+    })
+}
+
+/// `manifest verify`: checks every target against its manifest entry,
+/// failing the files that changed or that the manifest does not list.
+pub fn display_verify(
+    targets: &[(String, Loader)],
+    manifest: &Map<String, Value>,
+) -> Vec<(String, String)> {
+    let mut failures = Vec::new();
+    for (label, loader) in targets {
+        output::begin(label);
+        let problem = match manifest.get(&key(label)) {
+            Some(old) => compare(old, &entry(loader)),
+            None => {
+                display_line("Manifest", "Not in the manifest");
+                Some("not in the manifest".to_string())
+            }
+        };
+        output::end();
+        let findings: Vec<Finding> = problem
+            .map(|message| Finding {
+                severity: Severity::Error,
+                check: MANIFEST_CHECKS[0].to_string(),
+                message,
+            })
+            .into_iter()
+            .collect();
+        finding::record(label, MANIFEST_CHECKS, &findings);
+        if let Some(finding) = findings.first() {
+            failures.push((label.clone(), finding.message.clone()));
+        }
+    }
+    failures
+}
@@ -0,0 +1,230 @@
+//! `--report markdown=FILE`: a short Markdown summary of the run, meant to be
+//! posted as a pull request comment by CI. It has a table of the size,
+//! hardening and dependencies of every file, the changes since `--baseline`
+//! when one is given, the findings of the checks that ran, and a last line
+//! naming the build that wrote it.
+
+use std::cell::RefCell;
+
+use serde_json::{Map, Value};
+
+use crate::baseline::{self, key};
+use crate::buildinfo;
+use crate::finding::{Checked, Severity};
+use crate::Loader;
+
+thread_local! {
+    /// The files of the run, for the report; `None` unless it was asked for.
+    static SUMMARIES: RefCell<Option<Vec<Summary>>> = const { RefCell::new(None) };
+}
+
+/// The hardening columns of the table, as (baseline name, heading).
+const HARDENING_COLUMNS: &[(&str, &str)] = &[
+    ("pie", "PIE"),
+    ("nx", "NX"),
+    ("relro", "RELRO"),
+    ("bind_now", "BIND_NOW"),
+    ("stack_canary", "Canary"),
+];
+
+/// What the table shows about one file.
+#[derive(Clone)]
+struct Summary {
+    path: String,
+    /// The file's `--write-baseline` entry: size, sections and hardening.
+    entry: Value,
+    needed: Vec<String>,
+    /// With `--baseline`, the file's entry there, if it has one.
+    old: Option<Option<Value>>,
+}
+
+/// Starts keeping the files of the run for the report.
+pub fn enable() {
+    SUMMARIES.with(|summaries| *summaries.borrow_mut() = Some(Vec::new()));
+}
+
+/// Keeps what the report shows about every target, and its entry in the
+/// baseline at `baseline_path`. A baseline that cannot be read is left to
+/// `--baseline` to report.
+pub fn record(targets: &[(String, Loader)], baseline_path: Option<&str>) {
+    SUMMARIES.with(|summaries| {
+        let mut summaries = summaries.borrow_mut();
+        let Some(summaries) = summaries.as_mut() else {
+            return;
+        };
+        let baseline: Option<Map<String, Value>> =
+            baseline_path.map(|path| baseline::load_baseline(path).unwrap_or_default());
+        for (label, loader) in targets {
+            let summary = Summary {
+                path: label.clone(),
+                entry: baseline::entry(loader),
+                needed: loader.needed_libraries(),
+                old: baseline
+                    .as_ref()
+                    .map(|baseline| baseline.get(&key(label)).cloned()),
+            };
+            match summaries.iter_mut().find(|kept| kept.path == *label) {
+                Some(kept) => *kept = summary,
+                None => summaries.push(summary),
+            }
+        }
+    });
+}
+
+/// Makes text safe inside a table cell.
+fn cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+fn hardening(entry: &Value, name: &str) -> Value {
+    entry
+        .get("hardening")
+        .and_then(|hardening| hardening.get(name))
+        .cloned()
+        .unwrap_or(Value::Null)
+}
+
+fn show(value: &Value) -> String {
+    match value {
+        Value::Bool(true) => "✅".to_string(),
+        Value::Bool(false) => "❌".to_string(),
+        Value::String(text) => text.clone(),
+        _ => "?".to_string(),
+    }
+}
+
+fn size(entry: &Value) -> u64 {
+    entry.get("size").and_then(Value::as_u64).unwrap_or(0)
+}
+
+/// The change in size since the baseline, such as `+512 (+1.25%)`.
+fn size_delta(old: Option<&Value>, new: &Value) -> String {
+    let Some(old) = old else {
+        return "new".to_string();
+    };
+    let (old, new) = (size(old), size(new));
+    let delta = new as i64 - old as i64;
+    match delta {
+        0 => "±0".to_string(),
+        _ if old == 0 => format!("{:+}", delta),
+        _ => format!("{:+} ({:+.2}%)", delta, delta as f64 * 100.0 / old as f64),
+    }
+}
+
+fn table(summaries: &[Summary], text: &mut String) {
+    let with_baseline = summaries.iter().any(|summary| summary.old.is_some());
+    let mut headings = vec!["File", "Size"];
+    let mut alignments = vec![":---", "---:"];
+    if with_baseline {
+        headings.push("vs Baseline");
+        alignments.push("---:");
+    }
+    for (_, heading) in HARDENING_COLUMNS {
+        headings.push(heading);
+        alignments.push(":---:");
+    }
+    headings.push("Needed");
+    alignments.push(":---");
+    text.push_str(&format!("| {} |\n", headings.join(" | ")));
+    text.push_str(&format!("|{}|\n", alignments.join("|")));
+    for summary in summaries {
+        let mut cells = vec![
+            format!("`{}`", cell(&summary.path)),
+            size(&summary.entry).to_string(),
+        ];
+        if with_baseline {
+            cells.push(match &summary.old {
+                Some(old) => size_delta(old.as_ref(), &summary.entry),
+                None => String::new(),
+            });
+        }
+        for (name, _) in HARDENING_COLUMNS {
+            cells.push(show(&hardening(&summary.entry, name)));
+        }
+        cells.push(if summary.needed.is_empty() {
+            "-".to_string()
+        } else {
+            cell(&summary.needed.join(", "))
+        });
+        text.push_str(&format!("| {} |\n", cells.join(" | ")));
+    }
+}
+
+/// The hardening that changed since the baseline, one line per change.
+fn hardening_changes(summaries: &[Summary]) -> Vec<String> {
+    let mut changes = Vec::new();
+    for summary in summaries {
+        let Some(Some(old)) = &summary.old else {
+            continue;
+        };
+        for (name, heading) in HARDENING_COLUMNS.iter().chain(&[("textrel", "TEXTREL")]) {
+            let (old_value, new_value) = (hardening(old, name), hardening(&summary.entry, name));
+            if old_value != new_value {
+                changes.push(format!(
+                    "- `{}`: {} {} → {}",
+                    summary.path,
+                    heading,
+                    show(&old_value),
+                    show(&new_value)
+                ));
+            }
+        }
+    }
+    changes
+}
+
+/// `--report markdown=FILE`: the report from the files kept by [`record`], the
+/// findings of the run and the files that failed.
+pub fn markdown(checked: &[Checked], failures: &[(String, String)]) -> String {
+    let summaries = SUMMARIES.with(|summaries| summaries.borrow().clone().unwrap_or_default());
+    let mut text = format!("### {} report\n\n", buildinfo::NAME);
+    if !summaries.is_empty() {
+        table(&summaries, &mut text);
+        text.push('\n');
+    }
+    let changes = hardening_changes(&summaries);
+    if !changes.is_empty() {
+        text.push_str("#### Hardening changes since the baseline\n\n");
+        text.push_str(&changes.join("\n"));
+        text.push_str("\n\n");
+    }
+    let mut findings: Vec<String> = checked
+        .iter()
+        .flat_map(|file| {
+            file.findings
+                .iter()
+                .filter(|finding| finding.severity != Severity::Note)
+                .map(|finding| {
+                    let mark = match finding.severity {
+                        Severity::Error => "❌",
+                        _ => "⚠️",
+                    };
+                    format!(
+                        "- {} `{}` **{}**: {}",
+                        mark, file.path, finding.check, finding.message
+                    )
+                })
+        })
+        .collect();
+    findings.extend(
+        failures
+            .iter()
+            .filter(|(path, _)| !checked.iter().any(|file| file.path == *path))
+            .map(|(path, reason)| format!("- ❌ `{}`: {}", path, reason)),
+    );
+    if findings.is_empty() {
+        text.push_str("No findings.\n");
+    } else {
+        text.push_str(&format!("#### Findings ({})\n\n", findings.len()));
+        text.push_str(&findings.join("\n"));
+        text.push('\n');
+    }
+    text.push_str(&format!(
+        "\n<sub>{} {} ({}, {})</sub>\n",
+        buildinfo::NAME,
+        buildinfo::VERSION,
+        buildinfo::GIT_HASH,
+        buildinfo::TARGET
+    ));
+    text
+}
@@ -0,0 +1,375 @@
+use crate::arch::{ArchExt, TextModel};
+use crate::attributes::AttributeValue;
+use crate::output;
+use crate::{display_line, Loader};
+
+const EM_MIPS: u16 = 8;
+
+const EF_MIPS_NOREORDER: u32 = 0x1;
+const EF_MIPS_PIC: u32 = 0x2;
+const EF_MIPS_CPIC: u32 = 0x4;
+const EF_MIPS_XGOT: u32 = 0x8;
+const EF_MIPS_ABI2: u32 = 0x20;
+const EF_MIPS_32BITMODE: u32 = 0x100;
+const EF_MIPS_FP64: u32 = 0x200;
+const EF_MIPS_NAN2008: u32 = 0x400;
+const EF_MIPS_ABI: u32 = 0x0000_f000;
+const EF_MIPS_ARCH: u32 = 0xf000_0000;
+
+const DT_MIPS_RLD_VERSION: u64 = 0x7000_0001;
+const DT_MIPS_FLAGS: u64 = 0x7000_0005;
+const DT_MIPS_BASE_ADDRESS: u64 = 0x7000_0006;
+const DT_MIPS_LOCAL_GOTNO: u64 = 0x7000_000a;
+const DT_MIPS_SYMTABNO: u64 = 0x7000_0011;
+const DT_MIPS_UNREFEXTNO: u64 = 0x7000_0012;
+const DT_MIPS_GOTSYM: u64 = 0x7000_0013;
+const DT_MIPS_HIPAGENO: u64 = 0x7000_0014;
+const DT_MIPS_RLD_MAP: u64 = 0x7000_0016;
+const DT_MIPS_RLD_MAP_REL: u64 = 0x7000_0035;
+
+const REGINFO_SECTION: &str = ".reginfo";
+const ABIFLAGS_SECTION: &str = ".MIPS.abiflags";
+
+fn describe_flags(flags: u32) -> Vec<String> {
+    let arch = match (flags & EF_MIPS_ARCH) >> 28 {
+        0 => "MIPS I",
+        1 => "MIPS II",
+        2 => "MIPS III",
+        3 => "MIPS IV",
+        4 => "MIPS V",
+        5 => "MIPS32",
+        6 => "MIPS64",
+        7 => "MIPS32r2",
+        8 => "MIPS64r2",
+        9 => "MIPS32r6",
+        10 => "MIPS64r6",
+        _ => "unknown architecture",
+    };
+    let abi = match flags & EF_MIPS_ABI {
+        0x1000 => "O32",
+        0x2000 => "O64",
+        0x3000 => "EABI32",
+        0x4000 => "EABI64",
+        _ if flags & EF_MIPS_ABI2 != 0 => "N32",
+        _ => "unspecified (N64 for ELF64)",
+    };
+    let mut described = vec![arch.to_string(), format!("ABI {}", abi)];
+    for (bit, name) in [
+        (EF_MIPS_NOREORDER, "noreorder"),
+        (EF_MIPS_PIC, "PIC"),
+        (EF_MIPS_CPIC, "CPIC (calls PIC code)"),
+        (EF_MIPS_XGOT, "XGOT (large GOT)"),
+        (EF_MIPS_32BITMODE, "32-bit mode"),
+        (EF_MIPS_FP64, "FP64"),
+        (EF_MIPS_NAN2008, "NaN 2008"),
+    ] {
+        if flags & bit != 0 {
+            described.push(name.to_string());
+        }
+    }
+    described
+}
+
+/// The register usage record in `.reginfo` (Elf32_RegInfo).
+pub struct RegInfo {
+    pub gpr_mask: u32,
+    pub cpr_masks: [u32; 4],
+    pub gp_value: i32,
+}
+
+/// The `.MIPS.abiflags` record (Elf_MIPS_ABIFlags_v0).
+pub struct AbiFlags {
+    pub version: u16,
+    pub isa_level: u8,
+    pub isa_rev: u8,
+    pub gpr_size: u8,
+    pub cpr1_size: u8,
+    pub cpr2_size: u8,
+    pub fp_abi: u8,
+    pub isa_ext: u32,
+    pub ases: u32,
+    pub flags1: u32,
+}
+
+fn register_size(code: u8) -> &'static str {
+    match code {
+        0 => "none",
+        1 => "32-bit",
+        2 => "64-bit",
+        3 => "128-bit",
+        _ => "unknown",
+    }
+}
+
+fn fp_abi_name(fp_abi: u8) -> &'static str {
+    match fp_abi {
+        0 => "any",
+        1 => "hard-float, double precision",
+        2 => "hard-float, single precision",
+        3 => "soft-float",
+        4 => "hard-float, 64-bit FPRs (MIPS32r2 only, deprecated)",
+        5 => "hard-float, FPXX (32- or 64-bit FPRs)",
+        6 => "hard-float, 64-bit FPRs",
+        7 => "hard-float, 64-bit FPRs, odd singles unused",
+        _ => "unknown",
+    }
+}
+
+const ASE_NAMES: &[(u32, &str)] = &[
+    (0x1, "DSP"),
+    (0x2, "DSPR2"),
+    (0x4, "EVA"),
+    (0x8, "MCU"),
+    (0x10, "MDMX"),
+    (0x20, "MIPS-3D"),
+    (0x40, "MT"),
+    (0x80, "SmartMIPS"),
+    (0x100, "VZ"),
+    (0x200, "MSA"),
+    (0x400, "MIPS16"),
+    (0x800, "microMIPS"),
+    (0x1000, "XPA"),
+    (0x2000, "DSPR3"),
+    (0x4000, "MIPS16e2"),
+    (0x8000, "CRC"),
+    (0x10000, "GINV"),
+    (0x20000, "Loongson MMI"),
+    (0x40000, "Loongson CAM"),
+    (0x80000, "Loongson EXT"),
+    (0x100000, "Loongson EXT2"),
+];
+
+impl AbiFlags {
+    pub fn describe(&self) -> Vec<(String, String)> {
+        let isa = if self.isa_rev > 1 {
+            format!("MIPS{}r{}", self.isa_level, self.isa_rev)
+        } else {
+            format!("MIPS{}", self.isa_level)
+        };
+        let ases: Vec<&str> = ASE_NAMES
+            .iter()
+            .filter(|(bit, _)| self.ases & bit != 0)
+            .map(|(_, name)| *name)
+            .collect();
+        vec![
+            ("Version".to_string(), self.version.to_string()),
+            ("ISA".to_string(), isa),
+            (
+                "GPR size".to_string(),
+                register_size(self.gpr_size).to_string(),
+            ),
+            (
+                "CPR1 size".to_string(),
+                register_size(self.cpr1_size).to_string(),
+            ),
+            (
+                "CPR2 size".to_string(),
+                register_size(self.cpr2_size).to_string(),
+            ),
+            ("FP ABI".to_string(), fp_abi_name(self.fp_abi).to_string()),
+            ("ISA extension".to_string(), format!("{:#x}", self.isa_ext)),
+            (
+                "ASEs".to_string(),
+                if ases.is_empty() {
+                    "None".to_string()
+                } else {
+                    ases.join(", ")
+                },
+            ),
+            (
+                "Flags 1".to_string(),
+                if self.flags1 & 1 != 0 {
+                    "ODDSPREG".to_string()
+                } else {
+                    format!("{:#x}", self.flags1)
+                },
+            ),
+        ]
+    }
+}
+
+impl Loader {
+    pub fn mips_reginfo(&self) -> Option<RegInfo> {
+        let section = self.section_by_name(REGINFO_SECTION)?;
+        let base = section.sh_offset as usize;
+        // ELF64 objects use a 32-byte Elf64_RegInfo with padding before ri_gp_value.
+        let gp_offset = if self.is_64bit() { 24 } else { 20 };
+        Some(RegInfo {
+            gpr_mask: self.read_u32(base)?,
+            cpr_masks: [
+                self.read_u32(base + 4)?,
+                self.read_u32(base + 8)?,
+                self.read_u32(base + 12)?,
+                self.read_u32(base + 16)?,
+            ],
+            gp_value: if self.is_64bit() {
+                self.read_u64(base + gp_offset)? as i32
+            } else {
+                self.read_u32(base + gp_offset)? as i32
+            },
+        })
+    }
+
+    pub fn mips_abiflags(&self) -> Option<AbiFlags> {
+        let section = self.section_by_name(ABIFLAGS_SECTION)?;
+        let base = section.sh_offset as usize;
+        Some(AbiFlags {
+            version: self.read_u16(base)?,
+            isa_level: self.read_u8(base + 2)?,
+            isa_rev: self.read_u8(base + 3)?,
+            gpr_size: self.read_u8(base + 4)?,
+            cpr1_size: self.read_u8(base + 5)?,
+            cpr2_size: self.read_u8(base + 6)?,
+            fp_abi: self.read_u8(base + 7)?,
+            isa_ext: self.read_u32(base + 8)?,
+            ases: self.read_u32(base + 12)?,
+            flags1: self.read_u32(base + 16)?,
+        })
+    }
+
+    /// The MIPS-specific dynamic tags, named, plus the GOT layout they describe:
+    /// DT_MIPS_LOCAL_GOTNO local entries followed by one global entry per dynamic
+    /// symbol from DT_MIPS_GOTSYM on.
+    pub fn mips_dynamic_tags(&self) -> Vec<(String, String)> {
+        let mut described = Vec::new();
+        for entry in self.dynamic_entries() {
+            let name = match entry.d_tag {
+                DT_MIPS_RLD_VERSION => "DT_MIPS_RLD_VERSION",
+                DT_MIPS_FLAGS => "DT_MIPS_FLAGS",
+                DT_MIPS_BASE_ADDRESS => "DT_MIPS_BASE_ADDRESS",
+                DT_MIPS_LOCAL_GOTNO => "DT_MIPS_LOCAL_GOTNO",
+                DT_MIPS_SYMTABNO => "DT_MIPS_SYMTABNO",
+                DT_MIPS_UNREFEXTNO => "DT_MIPS_UNREFEXTNO",
+                DT_MIPS_GOTSYM => "DT_MIPS_GOTSYM",
+                DT_MIPS_HIPAGENO => "DT_MIPS_HIPAGENO",
+                DT_MIPS_RLD_MAP => "DT_MIPS_RLD_MAP",
+                DT_MIPS_RLD_MAP_REL => "DT_MIPS_RLD_MAP_REL",
+                _ => continue,
+            };
+            let value = match entry.d_tag {
+                DT_MIPS_BASE_ADDRESS | DT_MIPS_RLD_MAP | DT_MIPS_RLD_MAP_REL => {
+                    format!("{:#x}", entry.d_val)
+                }
+                _ => entry.d_val.to_string(),
+            };
+            described.push((name.to_string(), value));
+        }
+        if let (Some(local), Some(symtabno), Some(gotsym)) = (
+            self.dynamic_value(DT_MIPS_LOCAL_GOTNO),
+            self.dynamic_value(DT_MIPS_SYMTABNO),
+            self.dynamic_value(DT_MIPS_GOTSYM),
+        ) {
+            described.push((
+                "GOT Layout".to_string(),
+                format!(
+                    "{} local + {} global entries",
+                    local,
+                    symtabno.saturating_sub(gotsym)
+                ),
+            ));
+        }
+        described
+    }
+}
+
+pub struct Mips;
+
+impl ArchExt for Mips {
+    fn machines(&self) -> &'static [u16] {
+        &[EM_MIPS]
+    }
+
+    fn describe_flags(&self, _loader: &Loader, flags: u32) -> Vec<String> {
+        describe_flags(flags)
+    }
+
+    /// The NOP, `sll $0, $0, 0`, is all zeros in either byte order.
+    fn text_model(&self, _loader: &Loader) -> Option<TextModel> {
+        Some(TextModel {
+            average_length: 4.0,
+            alignment: 4,
+            padding: &[&[0x00, 0x00, 0x00, 0x00]],
+        })
+    }
+
+    fn display_details(&self, loader: &Loader, _flags: u32, _attributes: &[(u64, AttributeValue)]) {
+        match loader.mips_reginfo() {
+            Some(reginfo) => {
+                display_line(".reginfo GPR Mask", &format!("{:#010x}", reginfo.gpr_mask));
+                let masks: Vec<String> = reginfo
+                    .cpr_masks
+                    .iter()
+                    .map(|mask| format!("{:#010x}", mask))
+                    .collect();
+                display_line(".reginfo CPR Masks", &masks.join(" "));
+                display_line(".reginfo GP Value", &format!("{:#x}", reginfo.gp_value));
+            }
+            None => display_line(".reginfo", "None"),
+        }
+        match loader.mips_abiflags() {
+            Some(abiflags) => {
+                display_line(".MIPS.abiflags", "Present");
+                for (name, value) in abiflags.describe() {
+                    output::item(1, &format!("{:<32} {}", name, value));
+                }
+            }
+            None => display_line(".MIPS.abiflags", "None"),
+        }
+        for (name, value) in loader.mips_dynamic_tags() {
+            display_line(&name, &value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{describe_flags, AbiFlags};
+
+    #[test]
+    fn flags() {
+        // MIPS64r2, N32, PIC, CPIC, XGOT, NaN 2008.
+        assert_eq!(
+            describe_flags(0x8000_042e),
+            [
+                "MIPS64r2",
+                "ABI N32",
+                "PIC",
+                "CPIC (calls PIC code)",
+                "XGOT (large GOT)",
+                "NaN 2008"
+            ]
+        );
+        assert_eq!(
+            describe_flags(0xa000_0200),
+            ["MIPS64r6", "ABI unspecified (N64 for ELF64)", "FP64"]
+        );
+    }
+
+    #[test]
+    fn abiflags() {
+        let abiflags = AbiFlags {
+            version: 0,
+            isa_level: 64,
+            isa_rev: 6,
+            gpr_size: 2,
+            cpr1_size: 2,
+            cpr2_size: 0,
+            fp_abi: 6,
+            isa_ext: 0,
+            ases: 0x200 | 0x8000,
+            flags1: 0,
+        };
+        let described = abiflags.describe();
+        let value = |key: &str| {
+            described
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value.as_str())
+        };
+        assert_eq!(value("ISA"), Some("MIPS64r6"));
+        assert_eq!(value("GPR size"), Some("64-bit"));
+        assert_eq!(value("FP ABI"), Some("hard-float, 64-bit FPRs"));
+        assert_eq!(value("ASEs"), Some("MSA, CRC"));
+        assert_eq!(value("Flags 1"), Some("0x0"));
+    }
+}
@@ -0,0 +1,215 @@
+use crate::checksec::RelroLevel;
+use crate::output;
+use crate::x86::{
+    EM_386, EM_X86_64, GNU_PROPERTY_X86_FEATURE_1_AND, GNU_PROPERTY_X86_FEATURE_1_IBT,
+    GNU_PROPERTY_X86_FEATURE_1_SHSTK,
+};
+use crate::{display_line, Loader};
+
+/// `endbr64` and `endbr32`, the landing pads IBT lets indirect branches reach.
+const ENDBR64: [u8; 4] = [0xf3, 0x0f, 0x1e, 0xfa];
+const ENDBR32: [u8; 4] = [0xf3, 0x0f, 0x1e, 0xfb];
+
+/// The thunks GCC (`-mindirect-branch=thunk`) and LLVM (`-mretpoline`) route
+/// indirect calls and jumps through.
+const RETPOLINE_PREFIXES: &[&str] = &["__x86_indirect_thunk", "__llvm_retpoline_"];
+
+/// The sections lazy binding and `-fno-plt` show up in.
+const PLT_SECTIONS: &[&str] = &[".plt", ".plt.sec", ".plt.got"];
+
+/// The hardening of a file beyond what `--checksec` reports.
+pub struct Mitigations {
+    pub relro: RelroLevel,
+    pub bind_now: bool,
+    /// The PLT slots the dynamic linker fills on first call unless bound now.
+    pub plt_slots: usize,
+    /// The PLT sections with their sizes.
+    pub plt_sections: Vec<(String, u64)>,
+    /// The retpoline thunks defined or imported, for x86 files.
+    pub retpoline_thunks: Vec<String>,
+    /// Whether the GNU property note marks the file IBT and SHSTK compatible.
+    pub ibt: bool,
+    pub shstk: bool,
+    /// The functions starting with an ENDBR, out of those whose first bytes
+    /// are in the file, for x86 files.
+    pub endbr: Option<(usize, usize)>,
+}
+
+impl Mitigations {
+    /// Every PLT slot resolved before the program runs and read-only after:
+    /// `-z now` with `-z relro`, so no writable code pointers stay in the GOT.
+    /// Files without PLT slots, such as those built with `-fno-plt`, have none
+    /// to protect.
+    pub fn plt_free(&self) -> bool {
+        self.plt_slots == 0 || matches!(self.relro, RelroLevel::Full) && self.bind_now
+    }
+
+    /// The posture as one line, e.g. `full RELRO, bind-now, IBT, SHSTK, retpoline`.
+    pub fn summary(&self) -> String {
+        let mut parts = vec![match self.relro {
+            RelroLevel::None => "no RELRO",
+            RelroLevel::Partial => "partial RELRO",
+            RelroLevel::Full => "full RELRO",
+        }];
+        if self.bind_now {
+            parts.push("bind-now");
+        }
+        parts.push(if self.plt_slots == 0 {
+            "no PLT slots"
+        } else if self.plt_free() {
+            "PLT-free"
+        } else {
+            "lazy PLT"
+        });
+        if self.ibt {
+            parts.push("IBT");
+        }
+        if self.shstk {
+            parts.push("SHSTK");
+        }
+        if !self.retpoline_thunks.is_empty() {
+            parts.push("retpoline");
+        }
+        parts.join(", ")
+    }
+}
+
+impl Loader {
+    fn is_x86(&self) -> bool {
+        matches!(self.raw_e_machine(), EM_386 | EM_X86_64)
+    }
+
+    pub fn mitigations(&self) -> Mitigations {
+        let plt_slots = self
+            .relocations()
+            .into_iter()
+            .filter(|reloc| {
+                let name = self.relocation_type_name(reloc.r_type);
+                name.ends_with("_JUMP_SLOT") || name.ends_with("_JMP_SLOT")
+            })
+            .count();
+        let plt_sections = self
+            .section_headers()
+            .into_iter()
+            .filter(|section| PLT_SECTIONS.contains(&section.name.as_str()))
+            .map(|section| (section.name, section.sh_size))
+            .collect();
+
+        let mut retpoline_thunks = Vec::new();
+        if self.is_x86() {
+            retpoline_thunks = self
+                .static_symbols()
+                .into_iter()
+                .chain(self.dynamic_symbols())
+                .map(|symbol| symbol.name)
+                .filter(|name| {
+                    RETPOLINE_PREFIXES
+                        .iter()
+                        .any(|prefix| name.starts_with(prefix))
+                })
+                .collect();
+            retpoline_thunks.sort();
+            retpoline_thunks.dedup();
+        }
+
+        let features = self
+            .property_u32(GNU_PROPERTY_X86_FEATURE_1_AND)
+            .filter(|_| self.is_x86())
+            .unwrap_or(0);
+        // Functions the loader does not map, those of relocatable objects
+        // among them, are left out.
+        let endbr = self.is_x86().then(|| {
+            let bytes = self.bytes();
+            let entries: Vec<&[u8]> = self
+                .functions()
+                .iter()
+                .filter_map(|function| {
+                    let offset = self.vaddr_to_offset(function.start)?;
+                    bytes.get(offset..offset + 4)
+                })
+                .collect();
+            let marked = entries
+                .iter()
+                .filter(|entry| **entry == ENDBR64 || **entry == ENDBR32)
+                .count();
+            (marked, entries.len())
+        });
+
+        Mitigations {
+            relro: self.relro_report().level,
+            bind_now: self.is_bind_now(),
+            plt_slots,
+            plt_sections,
+            retpoline_thunks,
+            ibt: features & GNU_PROPERTY_X86_FEATURE_1_IBT != 0,
+            shstk: features & GNU_PROPERTY_X86_FEATURE_1_SHSTK != 0,
+            endbr,
+        }
+    }
+}
+
+/// `--mitigations`: whether every PLT slot is bound at load and protected by
+/// full RELRO, the retpoline thunks indirect branches go through, and how many
+/// functions start with the ENDBR that IBT needs, next to what the CET
+/// property note claims.
+pub fn display_mitigations(path: &str, loader: &Loader) {
+    let mitigations = loader.mitigations();
+    output::begin(path);
+    display_line("Summary", &mitigations.summary());
+    display_line("RELRO", mitigations.relro.as_str());
+    display_line("BIND_NOW", if mitigations.bind_now { "Yes" } else { "No" });
+    display_line("PLT Slots", &mitigations.plt_slots.to_string());
+    if mitigations.plt_sections.is_empty() {
+        display_line("PLT Sections", "None");
+    } else {
+        let sections: Vec<String> = mitigations
+            .plt_sections
+            .iter()
+            .map(|(name, size)| format!("{} ({} bytes)", name, size))
+            .collect();
+        display_line("PLT Sections", &sections.join(" "));
+    }
+    let plt_free = if mitigations.plt_slots == 0 {
+        "Yes (no PLT slots)"
+    } else if mitigations.plt_free() {
+        "Yes"
+    } else if mitigations.bind_now {
+        "No (bound now, but the GOT stays writable without full RELRO)"
+    } else {
+        "No (lazy binding leaves the PLT slots writable)"
+    };
+    display_line("PLT-Free Binding", plt_free);
+
+    if !loader.is_x86() {
+        output::end();
+        return;
+    }
+    display_line(
+        "Retpoline Thunks",
+        &mitigations.retpoline_thunks.len().to_string(),
+    );
+    for thunk in &mitigations.retpoline_thunks {
+        output::item(1, thunk);
+    }
+    let marked = |flag: bool| if flag { "Marked" } else { "Not marked" };
+    display_line("IBT", marked(mitigations.ibt));
+    display_line("SHSTK", marked(mitigations.shstk));
+    if let Some((endbr, functions)) = mitigations.endbr {
+        let share = (endbr * 100)
+            .checked_div(functions)
+            .map(|percent| format!(" ({}%)", percent))
+            .unwrap_or_default();
+        display_line(
+            "ENDBR at Entry",
+            &format!("{} of {} functions{}", endbr, functions, share),
+        );
+        if mitigations.ibt && endbr < functions {
+            output::item(
+                1,
+                "note: functions only called directly need no ENDBR, so a marked file \
+                 may leave some out",
+            );
+        }
+    }
+    output::end();
+}
@@ -0,0 +1,105 @@
+//! The parsed file as one JSON document, for plugins and `--query`.
+
+use serde_json::{json, Value};
+
+use crate::Loader;
+
+/// The `sh_flags` bits readelf shows as letters, in its order.
+const SECTION_FLAG_LETTERS: &[(u64, char)] = &[
+    (0x1, 'W'),
+    (0x2, 'A'),
+    (0x4, 'X'),
+    (0x10, 'M'),
+    (0x20, 'S'),
+    (0x40, 'I'),
+    (0x80, 'L'),
+    (0x100, 'O'),
+    (0x200, 'G'),
+    (0x400, 'T'),
+    (0x800, 'C'),
+];
+
+/// `sh_flags` as readelf's `Flg` column spells it, such as `AX`.
+fn flag_letters(flags: u64) -> String {
+    SECTION_FLAG_LETTERS
+        .iter()
+        .filter(|(bit, _)| flags & bit != 0)
+        .map(|(_, letter)| letter)
+        .collect()
+}
+
+/// The `sh_flags` bits of `letters` as readelf spells them, such as `AX`, or
+/// `None` for a letter it does not use.
+pub(crate) fn flag_bits(letters: &str) -> Option<u64> {
+    letters.chars().try_fold(0, |bits, letter| {
+        let (bit, _) = SECTION_FLAG_LETTERS
+            .iter()
+            .find(|(_, known)| *known == letter)?;
+        Some(bits | bit)
+    })
+}
+
+/// The document plugins analyze and `--query` runs over: the header fields,
+/// the section and program headers, the dynamic dependencies and the dynamic
+/// symbols.
+pub fn parsed_elf(path: &str, loader: &Loader) -> Value {
+    let sections: Vec<Value> = loader
+        .section_headers()
+        .iter()
+        .map(|section| {
+            json!({
+                "name": section.name,
+                "type": section.sh_type,
+                "flags": section.sh_flags,
+                "flag_letters": flag_letters(section.sh_flags),
+                "addr": section.sh_addr,
+                "offset": section.sh_offset,
+                "size": section.sh_size,
+            })
+        })
+        .collect();
+    let segments: Vec<Value> = loader
+        .program_headers()
+        .iter()
+        .map(|segment| {
+            json!({
+                "type": segment.p_type,
+                "flags": segment.p_flags,
+                "offset": segment.p_offset,
+                "vaddr": segment.p_vaddr,
+                "filesz": segment.p_filesz,
+                "memsz": segment.p_memsz,
+            })
+        })
+        .collect();
+    let symbols: Vec<Value> = loader
+        .dynamic_symbols()
+        .iter()
+        .filter(|symbol| !symbol.name.is_empty())
+        .map(|symbol| {
+            json!({
+                "name": symbol.name,
+                "value": symbol.st_value,
+                "size": symbol.st_size,
+                "bind": symbol.bind(),
+                "type": symbol.kind(),
+                "shndx": symbol.st_shndx,
+            })
+        })
+        .collect();
+    json!({
+        "path": path,
+        "class": if loader.is_64bit() { 64 } else { 32 },
+        "big_endian": loader.is_big_endian(),
+        "type": loader.raw_e_type(),
+        "machine": loader.raw_e_machine(),
+        "machine_name": loader.machine_name(),
+        "entry": loader.raw_e_entry(),
+        "flags": loader.raw_e_flags(),
+        "sections": sections,
+        "segments": segments,
+        "needed": loader.needed_libraries(),
+        "soname": loader.soname(),
+        "dynamic_symbols": symbols,
+    })
+}
@@ -0,0 +1,175 @@
+//! `--normalized`: one record per input with the same fields whatever its
+//! container format, so that the JSON of a release tree mixing ELF with the
+//! Mach-O, PE and WebAssembly files the `macho`, `pe` and `wasm-summary`
+//! features read can be compared field by field. Fields a format has no
+//! notion of, or that this tool cannot tell for it, are `n/a`.
+
+use crate::aarch64::{
+    EM_AARCH64, GNU_PROPERTY_AARCH64_FEATURE_1_AND, GNU_PROPERTY_AARCH64_FEATURE_1_BTI,
+};
+use crate::checksec::RelroLevel;
+use crate::linkage::FileKind;
+use crate::x86::{
+    EM_386, EM_X86_64, GNU_PROPERTY_X86_FEATURE_1_AND, GNU_PROPERTY_X86_FEATURE_1_IBT,
+};
+use crate::{display_line, output, Loader};
+
+/// What a file is for, in the words every format shares.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Executable,
+    SharedLibrary,
+    /// An object file still to be linked.
+    Object,
+    Core,
+    /// A WebAssembly module.
+    Module,
+    Other,
+}
+
+impl Kind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Kind::Executable => "executable",
+            Kind::SharedLibrary => "shared library",
+            Kind::Object => "object",
+            Kind::Core => "core",
+            Kind::Module => "module",
+            Kind::Other => "other",
+        }
+    }
+}
+
+/// One file's report in the normalized shape.
+pub struct NormalizedReport {
+    /// `ELF`, `Mach-O`, `PE` or `WebAssembly`.
+    pub format: &'static str,
+    pub arch: String,
+    pub bits: Option<u32>,
+    pub big_endian: Option<bool>,
+    pub kind: Kind,
+    /// The entry point as a virtual address.
+    pub entry: Option<u64>,
+    /// Data and stack not executable.
+    pub nx: Option<bool>,
+    /// Loaded at a random address: a PIE, a shared library or a PE image
+    /// with `DYNAMIC_BASE`.
+    pub aslr: Option<bool>,
+    pub relro: Option<&'static str>,
+    pub stack_canary: Option<bool>,
+    /// Indirect branches checked: IBT, BTI or Control Flow Guard.
+    pub cfi: Option<bool>,
+}
+
+impl NormalizedReport {
+    /// A report with the format and architecture only, the rest unknown.
+    pub fn new(format: &'static str, arch: String, kind: Kind) -> NormalizedReport {
+        NormalizedReport {
+            format,
+            arch,
+            bits: None,
+            big_endian: None,
+            kind,
+            entry: None,
+            nx: None,
+            aslr: None,
+            relro: None,
+            stack_canary: None,
+            cfi: None,
+        }
+    }
+}
+
+fn flag(value: Option<bool>) -> &'static str {
+    match value {
+        Some(true) => "yes",
+        Some(false) => "no",
+        None => "n/a",
+    }
+}
+
+fn or_na(value: Option<String>) -> String {
+    value.unwrap_or_else(|| "n/a".to_string())
+}
+
+pub fn display_normalized(label: &str, report: &NormalizedReport) {
+    output::begin(label);
+    display_line("Format", report.format);
+    display_line("Architecture", &report.arch);
+    display_line("Bits", &or_na(report.bits.map(|bits| bits.to_string())));
+    display_line(
+        "Endian",
+        match report.big_endian {
+            Some(true) => "big",
+            Some(false) => "little",
+            None => "n/a",
+        },
+    );
+    display_line("Kind", report.kind.as_str());
+    display_line("Relocatable", flag(Some(report.kind == Kind::Object)));
+    display_line(
+        "Entry Point",
+        &or_na(report.entry.map(|entry| format!("{:#x}", entry))),
+    );
+    display_line("NX", flag(report.nx));
+    display_line("ASLR", flag(report.aslr));
+    display_line("RELRO", report.relro.unwrap_or("n/a"));
+    display_line("Stack Canary", flag(report.stack_canary));
+    display_line("CFI", flag(report.cfi));
+    output::end();
+}
+
+impl Loader {
+    /// Whether the GNU property note marks the file IBT (x86) or BTI (arm64)
+    /// compatible, or `None` for other machines.
+    fn branch_protection(&self) -> Option<bool> {
+        let (pr_type, bit) = match self.raw_e_machine() {
+            EM_386 | EM_X86_64 => (
+                GNU_PROPERTY_X86_FEATURE_1_AND,
+                GNU_PROPERTY_X86_FEATURE_1_IBT,
+            ),
+            EM_AARCH64 => (
+                GNU_PROPERTY_AARCH64_FEATURE_1_AND,
+                GNU_PROPERTY_AARCH64_FEATURE_1_BTI,
+            ),
+            _ => return None,
+        };
+        Some(self.property_u32(pr_type).unwrap_or(0) & bit != 0)
+    }
+
+    pub fn normalized(&self) -> NormalizedReport {
+        let file_kind = self.file_kind();
+        let kind = match file_kind {
+            FileKind::Relocatable => Kind::Object,
+            FileKind::Static | FileKind::StaticPie | FileKind::Dynamic | FileKind::DynamicPie => {
+                Kind::Executable
+            }
+            FileKind::SharedLibrary => Kind::SharedLibrary,
+            FileKind::Core => Kind::Core,
+            FileKind::Other => Kind::Other,
+        };
+        let mut report = NormalizedReport::new("ELF", self.machine_name(), kind);
+        report.bits = Some(if self.is_64bit() { 64 } else { 32 });
+        report.big_endian = Some(self.is_big_endian());
+        let entry = self.raw_e_entry();
+        report.entry = (entry != 0).then_some(entry);
+        // Hardening is decided at link time; objects and cores have none yet.
+        if matches!(kind, Kind::Executable | Kind::SharedLibrary) {
+            report.nx = Some(self.has_nx());
+            report.aslr = Some(file_kind.is_pie() || kind == Kind::SharedLibrary);
+            report.relro = Some(match self.relro_report().level {
+                RelroLevel::None => "none",
+                RelroLevel::Partial => "partial",
+                RelroLevel::Full => "full",
+            });
+            report.stack_canary = Some(self.has_stack_canary());
+            report.cfi = self.branch_protection();
+        }
+        report
+    }
+}
+
+/// `--normalized` for an ELF file.
+pub fn display_normalized_elf(path: &str, loader: &Loader) {
+    display_normalized(path, &loader.normalized());
+}
@@ -0,0 +1,644 @@
+use log::debug;
+use std::ops::Range;
+
+use crate::auxv::NT_AUXV;
+use crate::limits;
+use crate::output;
+use crate::section::SHT_NOTE;
+use crate::segment::PT_NOTE;
+use crate::{display_line, Loader};
+
+const NOTE_HEADER_SIZE: usize = 12;
+
+#[derive(Clone, Debug)]
+pub struct Note {
+    /// The note's namespace, e.g. "GNU" or "Android".
+    pub owner: String,
+    pub n_type: u32,
+    pub desc: Vec<u8>,
+    /// Name of the section (or "PT_NOTE" segment) the note was read from.
+    pub location: String,
+}
+
+/// Where one note lies in the file.
+struct NoteEntry {
+    n_type: u32,
+    name: Range<usize>,
+    desc: Range<usize>,
+}
+
+impl Loader {
+    /// The notes packed in `size` bytes at `offset`, up to the first that does
+    /// not fit.
+    fn note_entries(&self, offset: usize, size: usize, align: u64) -> Vec<NoteEntry> {
+        // Notes are packed on 4-byte boundaries unless the container asks for 8.
+        let align = if align == 8 { 8 } else { 4 };
+        let end = offset.saturating_add(size);
+        let mut entries = Vec::new();
+        let mut position = offset;
+        // Fuzzed offsets and sizes can run past the end of the address space,
+        // which ends the walk like any other note that does not fit.
+        while position
+            .checked_add(NOTE_HEADER_SIZE)
+            .is_some_and(|header_end| header_end <= end)
+        {
+            let (Some(namesz), Some(descsz), Some(n_type)) = (
+                self.read_u32(position),
+                self.read_u32(position + 4),
+                self.read_u32(position + 8),
+            ) else {
+                break;
+            };
+            let name_start = position + NOTE_HEADER_SIZE;
+            let Some(name_end) = name_start.checked_add(namesz as usize) else {
+                break;
+            };
+            let Some(desc_end) = name_end
+                .checked_next_multiple_of(align)
+                .and_then(|desc_start| desc_start.checked_add(descsz as usize))
+            else {
+                break;
+            };
+            let desc_start = desc_end - descsz as usize;
+            if desc_end > end {
+                break;
+            }
+            entries.push(NoteEntry {
+                n_type,
+                name: name_start..name_end,
+                desc: desc_start..desc_end,
+            });
+            let Some(next) = desc_end.checked_next_multiple_of(align) else {
+                break;
+            };
+            position = next;
+        }
+        entries
+    }
+
+    fn notes_in(&self, offset: usize, size: usize, align: u64, location: &str) -> Vec<Note> {
+        let max_size = limits::get().max_note_size;
+        self.note_entries(offset, size, align)
+            .into_iter()
+            .filter(|entry| {
+                let size = (entry.name.len() + entry.desc.len()) as u64;
+                if size > max_size {
+                    debug!(
+                        "{}: skipping a note of {} bytes, more than --max-note-size {}",
+                        location, size, max_size
+                    );
+                }
+                size <= max_size
+            })
+            .map(|entry| {
+                let owner = self
+                    .bytes()
+                    .get(entry.name)
+                    .map(|name| {
+                        String::from_utf8_lossy(name)
+                            .trim_end_matches('\0')
+                            .to_string()
+                    })
+                    .unwrap_or_default();
+                let desc = self
+                    .bytes()
+                    .get(entry.desc)
+                    .map(<[u8]>::to_vec)
+                    .unwrap_or_default();
+                Note {
+                    owner,
+                    n_type: entry.n_type,
+                    desc,
+                    location: location.to_string(),
+                }
+            })
+            .collect()
+    }
+
+    /// Where the notes are kept: the SHT_NOTE sections or, for files without
+    /// section headers, the PT_NOTE segments. Each with its offset, size,
+    /// alignment and name.
+    fn note_containers(&self) -> Vec<(usize, usize, u64, String)> {
+        let sections: Vec<_> = self
+            .section_headers()
+            .into_iter()
+            .filter(|section| section.sh_type == SHT_NOTE)
+            .map(|section| {
+                (
+                    section.sh_offset as usize,
+                    section.sh_size as usize,
+                    section.sh_addralign,
+                    section.name,
+                )
+            })
+            .collect();
+        if !sections.is_empty() {
+            return sections;
+        }
+        self.program_headers()
+            .iter()
+            .filter(|header| header.p_type == PT_NOTE)
+            .map(|header| {
+                (
+                    header.p_offset as usize,
+                    header.p_filesz as usize,
+                    header.p_align,
+                    "PT_NOTE".to_string(),
+                )
+            })
+            .collect()
+    }
+
+    /// Returns every note of the file, read from SHT_NOTE sections or, for files without
+    /// section headers, from PT_NOTE segments.
+    pub fn notes(&self) -> Vec<Note> {
+        self.note_containers()
+            .iter()
+            .flat_map(|(offset, size, align, location)| {
+                self.notes_in(*offset, *size, *align, location)
+            })
+            .collect()
+    }
+
+    /// The name and size of the largest note, with where it is kept.
+    pub(crate) fn largest_note(&self) -> Option<(String, u64)> {
+        self.note_containers()
+            .into_iter()
+            .flat_map(|(offset, size, align, location)| {
+                self.note_entries(offset, size, align)
+                    .into_iter()
+                    .map(move |entry| {
+                        let size = (entry.name.len() + entry.desc.len()) as u64;
+                        (location.clone(), size)
+                    })
+            })
+            .max_by_key(|(_, size)| *size)
+    }
+
+    pub fn find_note(&self, owner: &str, n_type: u32) -> Option<Note> {
+        self.notes()
+            .into_iter()
+            .find(|note| note.owner == owner && note.n_type == n_type)
+    }
+
+    /// The descriptor of the NT_GNU_BUILD_ID note, if the file has one.
+    pub fn build_id(&self) -> Option<Vec<u8>> {
+        self.find_note("GNU", NT_GNU_BUILD_ID)
+            .map(|note| note.desc)
+            .filter(|desc| !desc.is_empty())
+    }
+
+    /// Reads a 32-bit value from a note descriptor in the file's byte order.
+    pub fn desc_u32(&self, desc: &[u8], offset: usize) -> Option<u32> {
+        let raw: [u8; 4] = desc.get(offset..offset + 4)?.try_into().ok()?;
+        Some(if self.is_big_endian() {
+            u32::from_be_bytes(raw)
+        } else {
+            u32::from_le_bytes(raw)
+        })
+    }
+
+    /// Reads an address-sized value (4 or 8 bytes by class) from a note descriptor.
+    pub fn desc_word(&self, desc: &[u8], offset: usize) -> Option<u64> {
+        if !self.is_64bit() {
+            return self.desc_u32(desc, offset).map(u64::from);
+        }
+        let raw: [u8; 8] = desc.get(offset..offset + 8)?.try_into().ok()?;
+        Some(if self.is_big_endian() {
+            u64::from_be_bytes(raw)
+        } else {
+            u64::from_le_bytes(raw)
+        })
+    }
+}
+
+/// Reads a NUL-padded string out of a fixed-size descriptor field.
+pub fn desc_str(desc: &[u8], offset: usize, len: usize) -> Option<String> {
+    let field = desc.get(offset..offset + len)?;
+    let end = field.iter().position(|&b| b == 0).unwrap_or(field.len());
+    Some(String::from_utf8_lossy(&field[..end]).to_string())
+}
+
+const ELFOSABI_NONE: u8 = 0;
+const ELFOSABI_NETBSD: u8 = 2;
+const ELFOSABI_GNU: u8 = 3;
+const ELFOSABI_SOLARIS: u8 = 6;
+const ELFOSABI_FREEBSD: u8 = 9;
+const ELFOSABI_OPENBSD: u8 = 12;
+
+pub(crate) const NT_GNU_ABI_TAG: u32 = 1;
+const NT_GNU_HWCAP: u32 = 2;
+const NT_GNU_BUILD_ID: u32 = 3;
+const NT_GNU_GOLD_VERSION: u32 = 4;
+pub(crate) const NT_GNU_PROPERTY_TYPE_0: u32 = 5;
+
+const NT_FREEBSD_ABI_TAG: u32 = 1;
+const NT_FREEBSD_NOINIT_TAG: u32 = 2;
+const NT_FREEBSD_ARCH_TAG: u32 = 3;
+const NT_FREEBSD_FEATURE_CTL: u32 = 4;
+
+const NT_NETBSD_IDENT: u32 = 1;
+const NT_NETBSD_EMULATION: u32 = 2;
+const NT_NETBSD_PAX: u32 = 3;
+const NT_NETBSD_MARCH: u32 = 5;
+
+const NT_OPENBSD_IDENT: u32 = 1;
+
+const NT_SOLARIS_PAGESIZE_HINT: u32 = 1;
+
+const NT_ANDROID_TYPE_IDENT: u32 = 1;
+
+/// Operating systems whose notes carry OS-specific meaning.
+#[derive(Clone, Copy, PartialEq)]
+pub enum NoteOs {
+    Gnu,
+    FreeBsd,
+    NetBsd,
+    OpenBsd,
+    Solaris,
+}
+
+impl NoteOs {
+    fn from_owner(owner: &str) -> Option<NoteOs> {
+        match owner {
+            "GNU" => Some(NoteOs::Gnu),
+            "FreeBSD" => Some(NoteOs::FreeBsd),
+            "NetBSD" | "PaX" => Some(NoteOs::NetBsd),
+            "OpenBSD" => Some(NoteOs::OpenBsd),
+            "SUNW Solaris" => Some(NoteOs::Solaris),
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &str {
+        match *self {
+            NoteOs::Gnu => "GNU/Linux",
+            NoteOs::FreeBsd => "FreeBSD",
+            NoteOs::NetBsd => "NetBSD",
+            NoteOs::OpenBsd => "OpenBSD",
+            NoteOs::Solaris => "Solaris",
+        }
+    }
+
+    fn from_osabi(osabi: u8) -> Option<NoteOs> {
+        match osabi {
+            ELFOSABI_GNU => Some(NoteOs::Gnu),
+            ELFOSABI_FREEBSD => Some(NoteOs::FreeBsd),
+            ELFOSABI_NETBSD => Some(NoteOs::NetBsd),
+            ELFOSABI_OPENBSD => Some(NoteOs::OpenBsd),
+            ELFOSABI_SOLARIS => Some(NoteOs::Solaris),
+            _ => None,
+        }
+    }
+}
+
+fn flag_names(value: u32, names: &[(u32, &str)]) -> String {
+    let set: Vec<&str> = names
+        .iter()
+        .filter(|(bit, _)| value & bit != 0)
+        .map(|(_, name)| *name)
+        .collect();
+    if set.is_empty() {
+        format!("{:#x}", value)
+    } else {
+        set.join(" | ")
+    }
+}
+
+pub(crate) fn hex_string(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// The bytes `hex_string` gives `text` for, if it is an even number of
+/// hexadecimal digits.
+pub(crate) fn from_hex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) || !text.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|at| u8::from_str_radix(&text[at..at + 2], 16).ok())
+        .collect()
+}
+
+impl Loader {
+    pub fn raw_ei_osabi(&self) -> u8 {
+        self.read_u8(7).unwrap_or(ELFOSABI_NONE)
+    }
+
+    pub fn get_ei_osabi(&self) -> String {
+        match self.raw_ei_osabi() {
+            ELFOSABI_NONE => "UNIX System V".to_string(),
+            ELFOSABI_NETBSD => "NetBSD".to_string(),
+            ELFOSABI_GNU => "GNU/Linux".to_string(),
+            ELFOSABI_SOLARIS => "Solaris".to_string(),
+            ELFOSABI_FREEBSD => "FreeBSD".to_string(),
+            ELFOSABI_OPENBSD => "OpenBSD".to_string(),
+            other => format!("OSABI {}", other),
+        }
+    }
+
+    /// The OS whose note decoders apply. A specific EI_OSABI wins; binaries tagged
+    /// ELFOSABI_NONE (as NetBSD and most Linux ones are) are identified by their notes.
+    pub(crate) fn note_os(&self, notes: &[Note]) -> Option<NoteOs> {
+        NoteOs::from_osabi(self.raw_ei_osabi()).or_else(|| {
+            notes
+                .iter()
+                .filter(|note| note.owner != "GNU" || note.n_type == NT_GNU_ABI_TAG)
+                .find_map(|note| NoteOs::from_owner(&note.owner))
+        })
+    }
+
+    /// Names a note's type and decodes its descriptor into a human-readable value.
+    pub fn describe_note(&self, note: &Note, os: Option<NoteOs>) -> (String, String) {
+        let owner_os = NoteOs::from_owner(&note.owner);
+        // GNU build-id and property notes are toolchain notes every OS emits.
+        let toolchain_note = note.owner == "GNU" && note.n_type != NT_GNU_ABI_TAG;
+        if let Some(os) = os.filter(|os| !toolchain_note && owner_os.is_some_and(|o| o != *os)) {
+            return (
+                format!("type {}", note.n_type),
+                format!("foreign note, not decoded for a {} binary", os.name()),
+            );
+        }
+        let desc = &note.desc;
+        let word = |offset| self.desc_u32(desc, offset);
+        let (name, value) = match (owner_os, note.n_type) {
+            (Some(NoteOs::Gnu), NT_GNU_ABI_TAG) => {
+                let os_name = match word(0) {
+                    Some(0) => "Linux",
+                    Some(1) => "Hurd",
+                    Some(2) => "Solaris",
+                    Some(3) => "FreeBSD",
+                    _ => "Unknown OS",
+                };
+                let version = match (word(4), word(8), word(12)) {
+                    (Some(major), Some(minor), Some(sub)) => {
+                        format!("{}.{}.{}", major, minor, sub)
+                    }
+                    _ => "?".to_string(),
+                };
+                ("NT_GNU_ABI_TAG", format!("{} ABI {}", os_name, version))
+            }
+            (Some(NoteOs::Gnu), NT_GNU_HWCAP) => ("NT_GNU_HWCAP", hex_string(desc)),
+            (Some(NoteOs::Gnu), NT_GNU_BUILD_ID) => ("NT_GNU_BUILD_ID", hex_string(desc)),
+            (Some(NoteOs::Gnu), NT_GNU_GOLD_VERSION) => (
+                "NT_GNU_GOLD_VERSION",
+                desc_str(desc, 0, desc.len()).unwrap_or_default(),
+            ),
+            (Some(NoteOs::Gnu), NT_GNU_PROPERTY_TYPE_0) => {
+                let properties: Vec<String> = self
+                    .properties_in(desc)
+                    .iter()
+                    .map(|property| self.describe_property(property))
+                    .collect();
+                ("NT_GNU_PROPERTY_TYPE_0", properties.join("; "))
+            }
+            (Some(NoteOs::FreeBsd), NT_FREEBSD_ABI_TAG) => {
+                // __FreeBSD_version is encoded as MMmmXXX.
+                let version = word(0).unwrap_or(0);
+                (
+                    "NT_FREEBSD_ABI_TAG",
+                    format!(
+                        "FreeBSD {}.{} ({})",
+                        version / 100_000,
+                        version / 1_000 % 100,
+                        version
+                    ),
+                )
+            }
+            (Some(NoteOs::FreeBsd), NT_FREEBSD_NOINIT_TAG) => (
+                "NT_FREEBSD_NOINIT_TAG",
+                "crt does not call init".to_string(),
+            ),
+            (Some(NoteOs::FreeBsd), NT_FREEBSD_ARCH_TAG) => (
+                "NT_FREEBSD_ARCH_TAG",
+                desc_str(desc, 0, desc.len()).unwrap_or_default(),
+            ),
+            (Some(NoteOs::FreeBsd), NT_FREEBSD_FEATURE_CTL) => (
+                "NT_FREEBSD_FEATURE_CTL",
+                flag_names(
+                    word(0).unwrap_or(0),
+                    &[
+                        (0x1, "ASLR_DISABLE"),
+                        (0x2, "PROTMAX_DISABLE"),
+                        (0x4, "STKGAP_DISABLE"),
+                        (0x8, "WXNEEDED"),
+                        (0x10, "LA48"),
+                    ],
+                ),
+            ),
+            (Some(NoteOs::NetBsd), NT_NETBSD_IDENT) if note.owner == "NetBSD" => {
+                // __NetBSD_Version__ is encoded as MMmmrrpp00.
+                let version = word(0).unwrap_or(0);
+                (
+                    "NT_NETBSD_IDENT",
+                    format!(
+                        "NetBSD {}.{} ({})",
+                        version / 100_000_000,
+                        version / 1_000_000 % 100,
+                        version
+                    ),
+                )
+            }
+            (Some(NoteOs::NetBsd), NT_NETBSD_EMULATION) => (
+                "NT_NETBSD_EMULATION",
+                desc_str(desc, 0, desc.len()).unwrap_or_default(),
+            ),
+            (Some(NoteOs::NetBsd), NT_NETBSD_PAX) => (
+                "NT_NETBSD_PAX",
+                flag_names(
+                    word(0).unwrap_or(0),
+                    &[
+                        (0x01, "MPROTECT"),
+                        (0x02, "NOMPROTECT"),
+                        (0x04, "GUARD"),
+                        (0x08, "NOGUARD"),
+                        (0x10, "ASLR"),
+                        (0x20, "NOASLR"),
+                    ],
+                ),
+            ),
+            (Some(NoteOs::NetBsd), NT_NETBSD_MARCH) => (
+                "NT_NETBSD_MARCH",
+                desc_str(desc, 0, desc.len()).unwrap_or_default(),
+            ),
+            (Some(NoteOs::OpenBsd), NT_OPENBSD_IDENT) => {
+                ("NT_OPENBSD_IDENT", format!("{:#x}", word(0).unwrap_or(0)))
+            }
+            (None, NT_AUXV) if note.owner == "CORE" && self.is_core() => (
+                "NT_AUXV",
+                format!("{} entries", self.auxv_entries(desc).len()),
+            ),
+            (None, NT_ANDROID_TYPE_IDENT) if note.owner == "Android" => (
+                "NT_ANDROID_TYPE_IDENT",
+                format!("API level {}", word(0).unwrap_or(0)),
+            ),
+            (Some(NoteOs::Solaris), NT_SOLARIS_PAGESIZE_HINT) => (
+                "NT_SOLARIS_PAGESIZE_HINT",
+                format!("{:#x}", word(0).unwrap_or(0)),
+            ),
+            _ => {
+                return (
+                    format!("type {}", note.n_type),
+                    format!("{} bytes: {}", desc.len(), hex_string(desc)),
+                )
+            }
+        };
+        (name.to_string(), value)
+    }
+}
+
+pub fn display_notes(path: &str, loader: &Loader) {
+    let notes = loader.notes();
+    let os = loader.note_os(&notes);
+    output::begin(path);
+    display_line("OS ABI", &loader.get_ei_osabi());
+    for note in &notes {
+        let (name, value) = loader.describe_note(note, os);
+        display_line(
+            &format!("{} [{}] {}", note.location, note.owner, name),
+            &value,
+        );
+        if note.owner == "CORE" && note.n_type == NT_AUXV && loader.is_core() {
+            for (a_type, a_val) in loader.auxv_entries(&note.desc) {
+                let (name, value) = loader.describe_auxv_entry(a_type, a_val);
+                output::item(1, &format!("{:<20} {}", name, value));
+            }
+        }
+    }
+    output::end();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Loader;
+
+    /// An ELF64 relocatable file whose only section is an SHT_NOTE at
+    /// `sh_offset`, `sh_size` bytes long.
+    fn with_note_section(sh_offset: u64, sh_size: u64) -> Loader {
+        Loader::from_bytes(note_file(sh_offset, sh_size))
+    }
+
+    fn note_file(sh_offset: u64, sh_size: u64) -> Vec<u8> {
+        let mut bytes = vec![0u8; 64 + 2 * 64];
+        bytes[..7].copy_from_slice(&[0x7f, b'E', b'L', b'F', 2, 1, 1]);
+        bytes[16..18].copy_from_slice(&1u16.to_le_bytes());
+        bytes[18..20].copy_from_slice(&62u16.to_le_bytes());
+        bytes[20..24].copy_from_slice(&1u32.to_le_bytes());
+        bytes[40..48].copy_from_slice(&64u64.to_le_bytes());
+        bytes[52..54].copy_from_slice(&64u16.to_le_bytes());
+        bytes[58..60].copy_from_slice(&64u16.to_le_bytes());
+        bytes[60..62].copy_from_slice(&2u16.to_le_bytes());
+        let note = &mut bytes[128..192];
+        note[4..8].copy_from_slice(&super::SHT_NOTE.to_le_bytes());
+        note[24..32].copy_from_slice(&sh_offset.to_le_bytes());
+        note[32..40].copy_from_slice(&sh_size.to_le_bytes());
+        bytes
+    }
+
+    /// A file tagged with `osabi` whose note section holds `notes`, as
+    /// (owner, type, descriptor).
+    fn with_notes(osabi: u8, notes: &[(&str, u32, &[u8])]) -> Loader {
+        let mut section = Vec::new();
+        for (owner, n_type, desc) in notes {
+            for word in [owner.len() as u32 + 1, desc.len() as u32, *n_type] {
+                section.extend_from_slice(&word.to_le_bytes());
+            }
+            section.extend_from_slice(owner.as_bytes());
+            section.resize((section.len() + 1).next_multiple_of(4), 0);
+            section.extend_from_slice(desc);
+            section.resize(section.len().next_multiple_of(4), 0);
+        }
+        let mut bytes = note_file(192, section.len() as u64);
+        bytes[7] = osabi;
+        bytes.extend(section);
+        Loader::from_bytes(bytes)
+    }
+
+    /// The decoded notes of `loader`, as (type name, value).
+    fn described(loader: &Loader) -> Vec<(String, String)> {
+        let notes = loader.notes();
+        let os = loader.note_os(&notes);
+        notes
+            .iter()
+            .map(|note| loader.describe_note(note, os))
+            .collect()
+    }
+
+    #[test]
+    fn freebsd_notes_by_osabi() {
+        let loader = with_notes(
+            super::ELFOSABI_FREEBSD,
+            &[
+                ("FreeBSD", 1, &1_400_097u32.to_le_bytes()),
+                ("FreeBSD", 4, &0x9u32.to_le_bytes()),
+            ],
+        );
+        assert_eq!(loader.get_ei_osabi(), "FreeBSD");
+        assert_eq!(
+            described(&loader),
+            [
+                (
+                    "NT_FREEBSD_ABI_TAG".to_string(),
+                    "FreeBSD 14.0 (1400097)".to_string()
+                ),
+                (
+                    "NT_FREEBSD_FEATURE_CTL".to_string(),
+                    "ASLR_DISABLE | WXNEEDED".to_string()
+                ),
+            ]
+        );
+    }
+
+    /// NetBSD binaries keep ELFOSABI_NONE and are known by their notes.
+    #[test]
+    fn netbsd_notes_by_owner() {
+        let loader = with_notes(
+            0,
+            &[
+                ("NetBSD", 1, &1_000_000_000u32.to_le_bytes()),
+                ("PaX", 3, &0x11u32.to_le_bytes()),
+            ],
+        );
+        assert_eq!(
+            described(&loader),
+            [
+                (
+                    "NT_NETBSD_IDENT".to_string(),
+                    "NetBSD 10.0 (1000000000)".to_string()
+                ),
+                ("NT_NETBSD_PAX".to_string(), "MPROTECT | ASLR".to_string()),
+            ]
+        );
+    }
+
+    /// A note of another OS is not decoded with that OS's meaning of its type.
+    #[test]
+    fn foreign_notes_are_not_decoded() {
+        let loader = with_notes(
+            super::ELFOSABI_OPENBSD,
+            &[
+                ("OpenBSD", 1, &0u32.to_le_bytes()),
+                ("FreeBSD", 1, &1_400_097u32.to_le_bytes()),
+            ],
+        );
+        assert_eq!(
+            described(&loader),
+            [
+                ("NT_OPENBSD_IDENT".to_string(), "0x0".to_string()),
+                (
+                    "type 1".to_string(),
+                    "foreign note, not decoded for a OpenBSD binary".to_string()
+                ),
+            ]
+        );
+    }
+
+    /// A fuzzed sh_offset near the end of the address space used to overflow
+    /// the note walk instead of ending it.
+    #[test]
+    fn note_at_the_end_of_the_address_space() {
+        let loader = with_note_section(u64::MAX - 8, 64);
+        assert!(loader.notes().is_empty());
+    }
+}
@@ -0,0 +1,92 @@
+use crate::output;
+use crate::section::{SHF_ALLOC, SHT_NOBITS, SHT_REL, SHT_RELA};
+use crate::symbol::SHN_UNDEF;
+use crate::{display_line, Loader};
+
+const ET_REL: u16 = 1;
+
+impl Loader {
+    pub fn is_relocatable(&self) -> bool {
+        self.raw_e_type() == ET_REL
+    }
+
+    /// Relocation counts keyed by the section they patch (the sh_info of each table).
+    pub fn relocation_counts(&self) -> Vec<(String, usize)> {
+        let sections = self.section_headers();
+        sections
+            .iter()
+            .filter(|section| section.sh_type == SHT_REL || section.sh_type == SHT_RELA)
+            .map(|table| {
+                let target = sections
+                    .get(table.sh_info as usize)
+                    .map(|target| target.name.clone())
+                    .unwrap_or_else(|| table.name.clone());
+                (target, self.relocations_in(table, &sections).len())
+            })
+            .collect()
+    }
+
+    /// Names of the symbols this object references but does not define.
+    pub fn undefined_symbols(&self) -> Vec<String> {
+        self.static_symbols()
+            .into_iter()
+            .filter(|symbol| symbol.st_shndx == SHN_UNDEF && !symbol.name.is_empty())
+            .map(|symbol| symbol.name)
+            .collect()
+    }
+}
+
+pub fn display_obj_summary(path: &str, loader: &Loader) {
+    output::begin(path);
+    if !loader.is_relocatable() {
+        display_line("File Type", "Not a relocatable object (ET_REL), skipped");
+        output::end();
+        return;
+    }
+
+    let sections = loader.section_headers();
+    let allocated: Vec<_> = sections
+        .iter()
+        .filter(|section| section.sh_flags & SHF_ALLOC != 0)
+        .collect();
+    let total: u64 = allocated.iter().map(|section| section.sh_size).sum();
+    display_line(
+        "Allocated Sections",
+        &format!("{} ({} bytes)", allocated.len(), total),
+    );
+    for section in &allocated {
+        let note = if section.sh_type == SHT_NOBITS {
+            " (no file data)"
+        } else {
+            ""
+        };
+        output::item(
+            1,
+            &format!("{:<32} {:>10} bytes{}", section.name, section.sh_size, note),
+        );
+    }
+
+    let counts = loader.relocation_counts();
+    let relocations: usize = counts.iter().map(|(_, count)| count).sum();
+    display_line("Relocations", &relocations.to_string());
+    for (target, count) in &counts {
+        output::item(1, &format!("{:<32} {:>10}", target, count));
+    }
+
+    let undefined = loader.undefined_symbols();
+    display_line("Undefined Symbols", &undefined.len().to_string());
+    for name in &undefined {
+        output::item(1, name);
+    }
+
+    let groups = loader.section_groups();
+    let comdat: Vec<_> = groups.iter().filter(|group| group.is_comdat()).collect();
+    display_line("COMDAT Groups", &comdat.len().to_string());
+    for group in &comdat {
+        output::item(
+            1,
+            &format!("{} ({} section(s))", group.signature, group.members.len()),
+        );
+    }
+    output::end();
+}
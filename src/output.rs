@@ -0,0 +1,556 @@
+use std::cell::RefCell;
+use std::mem;
+use std::rc::Rc;
+
+use crate::buildinfo;
+use crate::i18n;
+use crate::timings::{self, Phase};
+
+/// One line of a report.
+#[derive(Clone)]
+pub enum Entry {
+    /// A `key = value` line.
+    Field { key: String, value: String },
+    /// A detail line, nested `depth` levels under the preceding field or item.
+    Item { depth: usize, text: String },
+}
+
+/// What the commands hand to the output sink.
+#[derive(Clone)]
+pub enum Record {
+    /// A titled report, normally one per input file.
+    Report { title: String, entries: Vec<Entry> },
+    /// The same fields for several files side by side, as in the default header view.
+    Comparison {
+        columns: Vec<String>,
+        rows: Vec<(String, Vec<String>)>,
+    },
+    /// A note that belongs to no report, such as an input that is not ELF.
+    Message(String),
+}
+
+/// Renders records in one output format. Commands never print report data
+/// themselves; they go through `begin`, `field`, `item` and `end` below, so a new
+/// format only needs a new sink.
+pub trait OutputSink {
+    fn write(&mut self, record: Record);
+    /// Called once after the last record.
+    fn finish(&mut self) {}
+}
+
+pub const FORMATS: &[&str] = &["table", "json", "yaml", "ndjson", "quiet"];
+
+/// The aligned text layout the tool has always printed.
+struct TableSink;
+
+impl OutputSink for TableSink {
+    fn write(&mut self, record: Record) {
+        match record {
+            Record::Report { title, entries } => {
+                if !title.is_empty() {
+                    println!("{}", title);
+                }
+                for entry in entries {
+                    match entry {
+                        Entry::Field { key, value } => {
+                            println!("{} = {}", i18n::pad(i18n::label(&key), 50), value)
+                        }
+                        Entry::Item { depth, text } => {
+                            println!("{}{}", "    ".repeat(depth), text)
+                        }
+                    }
+                }
+                println!();
+            }
+            Record::Comparison { columns, rows } => {
+                print!("{}", i18n::center(i18n::label("File"), 53));
+                for column in &columns {
+                    print!("{:^30}", column);
+                }
+                println!();
+                for (key, values) in rows {
+                    print!("{} = ", i18n::pad(i18n::label(&key), 50));
+                    for value in values {
+                        // Long values still keep a space before the next column.
+                        print!("{:<29} ", value);
+                    }
+                    println!();
+                }
+            }
+            Record::Message(text) => println!("{}", text),
+        }
+    }
+}
+
+/// A JSON value; only what the records need.
+enum Json {
+    Number(u64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(&'static str, Json)>),
+}
+
+fn quote(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+impl Json {
+    /// Plain decimal counts become numbers; everything else stays a string.
+    fn value(text: &str) -> Json {
+        match text.parse::<u64>() {
+            Ok(number) if number.to_string() == text => Json::Number(number),
+            _ => Json::String(text.to_string()),
+        }
+    }
+
+    fn to_json(&self) -> String {
+        match self {
+            Json::Number(number) => number.to_string(),
+            Json::String(text) => quote(text),
+            Json::Array(items) => {
+                let items: Vec<String> = items.iter().map(Json::to_json).collect();
+                format!("[{}]", items.join(","))
+            }
+            Json::Object(members) => {
+                let members: Vec<String> = members
+                    .iter()
+                    .map(|(key, value)| format!("{}:{}", quote(key), value.to_json()))
+                    .collect();
+                format!("{{{}}}", members.join(","))
+            }
+        }
+    }
+
+    /// Block-style YAML. `indent` is the column nested blocks start at.
+    fn to_yaml(&self, indent: usize) -> String {
+        let pad = " ".repeat(indent);
+        match self {
+            Json::Number(_) | Json::String(_) => self.to_json(),
+            Json::Array(items) if items.is_empty() => "[]".to_string(),
+            Json::Object(members) if members.is_empty() => "{}".to_string(),
+            Json::Array(items) => items
+                .iter()
+                .map(|item| format!("\n{}- {}", pad, item.to_yaml(indent + 2).trim_start()))
+                .collect(),
+            Json::Object(members) => members
+                .iter()
+                .map(|(key, value)| match value {
+                    Json::Number(_) | Json::String(_) => {
+                        format!("\n{}{}: {}", pad, key, value.to_json())
+                    }
+                    _ => {
+                        // Empty containers stay on the key's line, as `[]` or `{}`.
+                        let nested = value.to_yaml(indent + 2);
+                        let separator = if nested.starts_with('\n') { "" } else { " " };
+                        format!("\n{}{}:{}{}", pad, key, separator, nested)
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+struct Detail {
+    text: String,
+    details: Vec<Detail>,
+}
+
+impl Detail {
+    fn to_json(&self) -> Json {
+        let mut members = vec![("text", Json::String(self.text.clone()))];
+        if !self.details.is_empty() {
+            members.push(("details", details_json(&self.details)));
+        }
+        Json::Object(members)
+    }
+}
+
+fn details_json(details: &[Detail]) -> Json {
+    Json::Array(details.iter().map(Detail::to_json).collect())
+}
+
+/// Appends a detail `depth` levels below the last one in `details`.
+fn nest(details: &mut Vec<Detail>, depth: usize, text: String) {
+    match details.last_mut() {
+        Some(parent) if depth > 1 => nest(&mut parent.details, depth - 1, text),
+        _ => details.push(Detail {
+            text,
+            details: Vec::new(),
+        }),
+    }
+}
+
+fn report_json(title: &str, entries: Vec<Entry>) -> Json {
+    let mut fields: Vec<(String, String, Vec<Detail>)> = Vec::new();
+    let mut details = Vec::new();
+    for entry in entries {
+        match entry {
+            Entry::Field { key, value } => fields.push((key, value, Vec::new())),
+            Entry::Item { depth, text } => match fields.last_mut() {
+                Some((_, _, field_details)) if depth > 0 => nest(field_details, depth, text),
+                _ => nest(&mut details, depth.max(1), text),
+            },
+        }
+    }
+    let mut members = vec![("title", Json::String(title.to_string()))];
+    if !fields.is_empty() {
+        let fields = fields
+            .into_iter()
+            .map(|(key, value, details)| {
+                let mut members = vec![("key", Json::String(key)), ("value", Json::value(&value))];
+                if !details.is_empty() {
+                    members.push(("details", details_json(&details)));
+                }
+                Json::Object(members)
+            })
+            .collect();
+        members.push(("fields", Json::Array(fields)));
+    }
+    if !details.is_empty() {
+        members.push(("details", details_json(&details)));
+    }
+    Json::Object(members)
+}
+
+/// The build that wrote a record, kept in every JSON document.
+fn tool_json() -> Json {
+    Json::Object(vec![
+        ("name", Json::String(buildinfo::NAME.to_string())),
+        ("version", Json::String(buildinfo::VERSION.to_string())),
+        ("git", Json::String(buildinfo::GIT_HASH.to_string())),
+        ("target", Json::String(buildinfo::TARGET.to_string())),
+        (
+            "features",
+            Json::Array(
+                buildinfo::features()
+                    .into_iter()
+                    .map(|feature| Json::String(feature.to_string()))
+                    .collect(),
+            ),
+        ),
+    ])
+}
+
+/// The JSON documents a record turns into: a comparison becomes one report per
+/// file. Each one ends with the `tool` that wrote it.
+fn record_json(record: Record) -> Vec<Json> {
+    let documents = match record {
+        Record::Report { title, entries } => vec![report_json(&title, entries)],
+        Record::Comparison { columns, rows } => columns
+            .iter()
+            .enumerate()
+            .map(|(column, title)| {
+                let entries = rows
+                    .iter()
+                    .map(|(key, values)| Entry::Field {
+                        key: key.clone(),
+                        value: values[column].trim().to_string(),
+                    })
+                    .collect();
+                report_json(title, entries)
+            })
+            .collect(),
+        Record::Message(text) => vec![Json::Object(vec![("message", Json::String(text))])],
+    };
+    documents
+        .into_iter()
+        .map(|document| match document {
+            Json::Object(mut members) => {
+                members.push(("tool", tool_json()));
+                Json::Object(members)
+            }
+            other => other,
+        })
+        .collect()
+}
+
+/// One JSON array holding every record, printed when the run finishes.
+#[derive(Default)]
+struct JsonSink {
+    records: Vec<Json>,
+    /// Where `capture_json` wants the document instead of stdout.
+    captured: Option<Rc<RefCell<String>>>,
+}
+
+impl OutputSink for JsonSink {
+    fn write(&mut self, record: Record) {
+        self.records.extend(record_json(record));
+    }
+
+    fn finish(&mut self) {
+        let records: Vec<String> = mem::take(&mut self.records)
+            .iter()
+            .map(Json::to_json)
+            .collect();
+        let document = format!("[{}]", records.join(",\n"));
+        match &self.captured {
+            Some(captured) => *captured.borrow_mut() = document,
+            None => println!("{}", document),
+        }
+    }
+}
+
+/// One JSON object per line, written as soon as each record is complete.
+struct NdjsonSink;
+
+impl OutputSink for NdjsonSink {
+    fn write(&mut self, record: Record) {
+        for value in record_json(record) {
+            println!("{}", value.to_json());
+        }
+    }
+}
+
+/// A YAML sequence with one item per record.
+struct YamlSink;
+
+impl OutputSink for YamlSink {
+    fn write(&mut self, record: Record) {
+        for value in record_json(record) {
+            println!("- {}", value.to_yaml(2).trim_start());
+        }
+    }
+}
+
+/// Keeps the records instead of rendering them, for `collect`.
+#[derive(Default)]
+struct CollectSink {
+    records: Rc<RefCell<Vec<Record>>>,
+}
+
+impl OutputSink for CollectSink {
+    fn write(&mut self, record: Record) {
+        self.records.borrow_mut().push(record);
+    }
+}
+
+/// Prints nothing; for scripts that only look at the exit status.
+struct QuietSink;
+
+impl OutputSink for QuietSink {
+    fn write(&mut self, _record: Record) {}
+}
+
+thread_local! {
+    static SINK: RefCell<Box<dyn OutputSink>> = RefCell::new(Box::new(TableSink));
+    static CURRENT: RefCell<Option<(String, Vec<Entry>)>> = const { RefCell::new(None) };
+}
+
+/// Selects the sink by name, one of `FORMATS`.
+pub fn set_format(format: &str) {
+    let sink: Box<dyn OutputSink> = match format {
+        "json" => Box::<JsonSink>::default(),
+        "yaml" => Box::new(YamlSink),
+        "ndjson" => Box::new(NdjsonSink),
+        "quiet" => Box::new(QuietSink),
+        _ => Box::new(TableSink),
+    };
+    SINK.with(|current| *current.borrow_mut() = sink);
+}
+
+fn write(record: Record) {
+    timings::measure(Phase::Output, || {
+        SINK.with(|sink| sink.borrow_mut().write(record))
+    });
+}
+
+/// Starts a report; an empty title prints no heading line in the table layout.
+pub fn begin(title: &str) {
+    end();
+    CURRENT.with(|current| *current.borrow_mut() = Some((title.to_string(), Vec::new())));
+}
+
+fn push(entry: Entry) {
+    CURRENT.with(|current| {
+        current
+            .borrow_mut()
+            .get_or_insert_with(|| (String::new(), Vec::new()))
+            .1
+            .push(entry)
+    });
+}
+
+pub fn field(key: &str, value: &str) {
+    push(Entry::Field {
+        key: key.to_string(),
+        value: value.to_string(),
+    });
+}
+
+pub fn item(depth: usize, text: &str) {
+    push(Entry::Item {
+        depth,
+        text: text.to_string(),
+    });
+}
+
+/// Closes the current report and hands it to the sink.
+pub fn end() {
+    if let Some((title, entries)) = CURRENT.with(|current| current.borrow_mut().take()) {
+        write(Record::Report { title, entries });
+    }
+}
+
+pub fn comparison(columns: Vec<String>, rows: Vec<(String, Vec<String>)>) {
+    end();
+    write(Record::Comparison { columns, rows });
+}
+
+pub fn message(text: &str) {
+    end();
+    write(Record::Message(text.to_string()));
+}
+
+/// Flushes the last report and lets the sink write anything it buffered.
+pub fn finish() {
+    end();
+    SINK.with(|sink| sink.borrow_mut().finish());
+}
+
+/// Runs `f` with a JSON sink on this thread and returns the document it wrote
+/// instead of printing it; the previous sink is restored afterwards.
+pub fn capture_json(f: impl FnOnce()) -> String {
+    let captured = Rc::new(RefCell::new(String::new()));
+    let sink = JsonSink {
+        records: Vec::new(),
+        captured: Some(Rc::clone(&captured)),
+    };
+    let previous = SINK.with(|current| current.replace(Box::new(sink)));
+    f();
+    finish();
+    SINK.with(|current| *current.borrow_mut() = previous);
+    captured.take()
+}
+
+/// Runs `f` and returns the records it produced instead of writing them, so they
+/// can be kept and handed to `replay` later.
+pub fn collect(f: impl FnOnce()) -> Vec<Record> {
+    end();
+    let sink = CollectSink::default();
+    let records = Rc::clone(&sink.records);
+    let previous = SINK.with(|current| current.replace(Box::new(sink)));
+    f();
+    end();
+    SINK.with(|current| *current.borrow_mut() = previous);
+    records.take()
+}
+
+/// Writes records from `collect` to the current sink.
+pub fn replay(records: &[Record]) {
+    end();
+    for record in records {
+        write(record.clone());
+    }
+}
+
+/// Escapes tabs, newlines and backslashes so a value fits in one tab separated field.
+pub(crate) fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+}
+
+pub(crate) fn unescape(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            plain.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => plain.push('\t'),
+            Some('n') => plain.push('\n'),
+            Some(other) => plain.push(other),
+            None => plain.push('\\'),
+        }
+    }
+    plain
+}
+
+fn encode_line(tag: &str, fields: &[&str]) -> String {
+    let mut line = tag.to_string();
+    for field in fields {
+        line.push('\t');
+        line.push_str(&escape(field));
+    }
+    line
+}
+
+/// Records as lines of tab separated fields, one line per report entry or
+/// comparison row; `decode` reads them back.
+pub fn encode(records: &[Record]) -> Vec<String> {
+    let mut lines = Vec::new();
+    for record in records {
+        match record {
+            Record::Report { title, entries } => {
+                lines.push(encode_line("report", &[title]));
+                for entry in entries {
+                    lines.push(match entry {
+                        Entry::Field { key, value } => encode_line("field", &[key, value]),
+                        Entry::Item { depth, text } => {
+                            encode_line("item", &[&depth.to_string(), text])
+                        }
+                    });
+                }
+            }
+            Record::Comparison { columns, rows } => {
+                let columns: Vec<&str> = columns.iter().map(String::as_str).collect();
+                lines.push(encode_line("comparison", &columns));
+                for (key, values) in rows {
+                    let mut fields = vec![key.as_str()];
+                    fields.extend(values.iter().map(String::as_str));
+                    lines.push(encode_line("row", &fields));
+                }
+            }
+            Record::Message(text) => lines.push(encode_line("message", &[text])),
+        }
+    }
+    lines
+}
+
+/// Reads back the lines `encode` wrote; `None` if they are malformed.
+pub fn decode<'a>(lines: impl IntoIterator<Item = &'a str>) -> Option<Vec<Record>> {
+    let mut records = Vec::new();
+    for line in lines {
+        let mut fields = line.split('\t');
+        let tag = fields.next()?;
+        let mut fields = fields.map(unescape);
+        match (tag, records.last_mut()) {
+            ("report", _) => records.push(Record::Report {
+                title: fields.next()?,
+                entries: Vec::new(),
+            }),
+            ("field", Some(Record::Report { entries, .. })) => entries.push(Entry::Field {
+                key: fields.next()?,
+                value: fields.next()?,
+            }),
+            ("item", Some(Record::Report { entries, .. })) => entries.push(Entry::Item {
+                depth: fields.next()?.parse().ok()?,
+                text: fields.next()?,
+            }),
+            ("comparison", _) => records.push(Record::Comparison {
+                columns: fields.collect(),
+                rows: Vec::new(),
+            }),
+            ("row", Some(Record::Comparison { rows, .. })) => {
+                rows.push((fields.next()?, fields.collect()))
+            }
+            ("message", _) => records.push(Record::Message(fields.next()?)),
+            _ => return None,
+        }
+    }
+    Some(records)
+}
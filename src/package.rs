@@ -0,0 +1,274 @@
+use log::info;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+use crate::aggregate::display_aggregate;
+use crate::archive::{self, Member};
+use crate::Loader;
+
+const AR_MAGIC: &[u8] = b"!<arch>\n";
+const AR_HEADER_LEN: usize = 60;
+const RPM_LEAD_MAGIC: &[u8] = &[0xed, 0xab, 0xee, 0xdb];
+const RPM_LEAD_LEN: usize = 96;
+const RPM_HEADER_MAGIC: &[u8] = &[0x8e, 0xad, 0xe8, 0x01];
+
+const RPMTAG_NAME: u32 = 1000;
+const RPMTAG_VERSION: u32 = 1001;
+const RPMTAG_RELEASE: u32 = 1002;
+const RPMTAG_ARCH: u32 = 1022;
+const RPM_STRING_TYPE: u32 = 6;
+
+#[derive(Clone, Copy)]
+enum Format {
+    Deb,
+    Rpm,
+}
+
+/// Recognises a package by its first bytes. Only regular files are looked at, so
+/// that FIFOs and devices are left for the usual input handling.
+fn format(path: &str) -> Option<Format> {
+    if !Path::new(path).is_file() {
+        return None;
+    }
+    let mut start = Vec::new();
+    File::open(path)
+        .and_then(|file| file.take(21).read_to_end(&mut start))
+        .ok()?;
+    if start.starts_with(AR_MAGIC) && start[AR_MAGIC.len()..].starts_with(b"debian-binary") {
+        Some(Format::Deb)
+    } else if start.starts_with(RPM_LEAD_MAGIC) {
+        Some(Format::Rpm)
+    } else {
+        None
+    }
+}
+
+/// Whether `path` is a Debian or RPM package rather than an ELF file.
+pub fn is_package(path: &str) -> bool {
+    format(path).is_some()
+}
+
+/// The members of an ar archive, by name.
+fn ar_members(bytes: &[u8]) -> Result<Vec<(String, &[u8])>, String> {
+    let mut members = Vec::new();
+    let mut offset = AR_MAGIC.len();
+    while offset < bytes.len() {
+        let header = bytes
+            .get(offset..offset + AR_HEADER_LEN)
+            .ok_or("truncated ar header")?;
+        if &header[58..60] != b"`\n" {
+            return Err(format!("bad ar header at offset {:#x}", offset));
+        }
+        let name = String::from_utf8_lossy(&header[..16])
+            .trim_end()
+            .trim_end_matches('/')
+            .to_string();
+        let size: usize = std::str::from_utf8(&header[48..58])
+            .ok()
+            .and_then(|size| size.trim().parse().ok())
+            .ok_or_else(|| format!("{}: bad ar member size", name))?;
+        let start = offset + AR_HEADER_LEN;
+        let data = bytes
+            .get(start..start + size)
+            .ok_or_else(|| format!("{} is truncated", name))?;
+        members.push((name, data));
+        // Members are aligned to two bytes.
+        offset = start + size + size % 2;
+    }
+    Ok(members)
+}
+
+/// `name version (architecture)` from the `control` file of a control tarball.
+fn deb_name(control_tar: &[u8]) -> io::Result<Option<String>> {
+    let mut archive = tar::Archive::new(archive::decompressed(control_tar)?);
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if archive::normalize(&String::from_utf8_lossy(&entry.path_bytes())) != "control" {
+            continue;
+        }
+        let mut control = String::new();
+        entry.read_to_string(&mut control)?;
+        let field = |key: &str| {
+            control.lines().find_map(|line| {
+                let (name, value) = line.split_once(':')?;
+                (name == key).then(|| value.trim().to_string())
+            })
+        };
+        return Ok(field("Package").map(|package| {
+            let mut name = package;
+            if let Some(version) = field("Version") {
+                name = format!("{} {}", name, version);
+            }
+            if let Some(arch) = field("Architecture") {
+                name = format!("{} ({})", name, arch);
+            }
+            name
+        }));
+    }
+    Ok(None)
+}
+
+/// A package's display name and ELF files.
+struct Package {
+    name: Option<String>,
+    files: BTreeMap<String, Loader>,
+}
+
+/// Collects the ELF files `walk` reports, resolving hard links between them.
+fn collect_elf(
+    walk: impl FnOnce(&mut dyn FnMut(String, Member)) -> io::Result<()>,
+) -> io::Result<BTreeMap<String, Loader>> {
+    let mut files = BTreeMap::new();
+    walk(&mut |path, member| match member {
+        Member::Elf(bytes) => {
+            files.insert(path, Loader::from_bytes(bytes));
+        }
+        Member::Link(target) => {
+            if let Some(link) = files.get(&target).and_then(|loader| loader.at(0)) {
+                files.insert(path, link);
+            }
+        }
+        Member::Other => {}
+    })?;
+    Ok(files)
+}
+
+fn read_deb(bytes: &[u8]) -> Result<Package, String> {
+    let members = ar_members(bytes)?;
+    let member = |prefix: &str| {
+        members
+            .iter()
+            .find(|(name, _)| name.starts_with(prefix))
+            .ok_or_else(|| format!("no {}* member", prefix))
+    };
+    let (control, control_tar) = member("control.tar")?;
+    let name = deb_name(control_tar).map_err(|error| format!("{}: {}", control, error))?;
+    let (data, data_tar) = member("data.tar")?;
+    let files = collect_elf(|visit| archive::walk_tar(archive::decompressed(data_tar)?, visit))
+        .map_err(|error| format!("{}: {}", data, error))?;
+    Ok(Package { name, files })
+}
+
+fn be32(bytes: &[u8], offset: usize) -> Option<u32> {
+    let bytes = bytes.get(offset..offset + 4)?;
+    Some(u32::from_be_bytes(bytes.try_into().ok()?))
+}
+
+/// Reads the RPM header structure at `offset`: its string tags, and where it ends.
+fn rpm_header(bytes: &[u8], offset: usize) -> Result<(BTreeMap<u32, String>, usize), String> {
+    if bytes.get(offset..offset + 4) != Some(RPM_HEADER_MAGIC) {
+        return Err(format!("no RPM header at offset {:#x}", offset));
+    }
+    let truncated = || "truncated RPM header".to_string();
+    let count = be32(bytes, offset + 8).ok_or_else(truncated)? as usize;
+    let store_len = be32(bytes, offset + 12).ok_or_else(truncated)? as usize;
+    let store = offset + 16 + count * 16;
+    let end = store + store_len;
+    let store = bytes.get(store..end).ok_or_else(truncated)?;
+    let mut strings = BTreeMap::new();
+    for index in 0..count {
+        let entry = offset + 16 + index * 16;
+        let (Some(tag), Some(kind), Some(at)) = (
+            be32(bytes, entry),
+            be32(bytes, entry + 4),
+            be32(bytes, entry + 8),
+        ) else {
+            return Err(truncated());
+        };
+        if kind != RPM_STRING_TYPE {
+            continue;
+        }
+        if let Some(value) = store.get(at as usize..) {
+            let value = value.split(|&byte| byte == 0).next().unwrap_or_default();
+            strings.insert(tag, String::from_utf8_lossy(value).into_owned());
+        }
+    }
+    Ok((strings, end))
+}
+
+fn read_rpm(bytes: &[u8]) -> Result<Package, String> {
+    let (_, signature_end) = rpm_header(bytes, RPM_LEAD_LEN)?;
+    // The signature is padded to eight bytes; the main header is not.
+    let (tags, header_end) = rpm_header(bytes, signature_end.next_multiple_of(8))?;
+    let name = tags.get(&RPMTAG_NAME).map(|name| {
+        let mut name = name.clone();
+        if let (Some(version), Some(release)) =
+            (tags.get(&RPMTAG_VERSION), tags.get(&RPMTAG_RELEASE))
+        {
+            name = format!("{} {}-{}", name, version, release);
+        }
+        if let Some(arch) = tags.get(&RPMTAG_ARCH) {
+            name = format!("{} ({})", name, arch);
+        }
+        name
+    });
+    let payload = bytes.get(header_end..).unwrap_or_default();
+    // bzip2 and legacy lzma payloads, mostly from old distributions.
+    if !archive::is_compressed(payload) && !payload.starts_with(b"07070") {
+        return Err("unsupported payload compression".to_string());
+    }
+    let files = collect_elf(|visit| archive::walk_cpio(archive::decompressed(payload)?, visit))
+        .map_err(|error| format!("payload: {}", error))?;
+    Ok(Package { name, files })
+}
+
+/// A package file opened by [`open_packages`], not yet unpacked.
+pub struct OpenPackage {
+    path: String,
+    format: Format,
+    file: Loader,
+}
+
+/// Opens every Debian or RPM package in `paths`, before `--sandbox` takes
+/// the filesystem away. Returns the packages that could not be opened in
+/// `failures`.
+pub fn open_packages(paths: &[String], failures: &mut Vec<(String, String)>) -> Vec<OpenPackage> {
+    let mut packages = Vec::new();
+    for path in paths {
+        let Some(format) = format(path) else {
+            continue;
+        };
+        match Loader::open(path) {
+            Ok(file) => packages.push(OpenPackage {
+                path: path.clone(),
+                format,
+                file,
+            }),
+            Err(error) => failures.push((path.clone(), error.to_string())),
+        }
+    }
+    packages
+}
+
+/// Reports every package as one aggregated hardening and dependency report
+/// over the ELF files it installs. The payload is unpacked in memory, so this
+/// can run inside the sandbox. Returns the packages that could not be read.
+pub fn scan_packages(packages: Vec<OpenPackage>) -> Vec<(String, String)> {
+    let mut failures = Vec::new();
+    for OpenPackage { path, format, file } in packages {
+        let result = match format {
+            Format::Deb => read_deb(file.file_bytes()),
+            Format::Rpm => read_rpm(file.file_bytes()),
+        };
+        match result {
+            Ok(package) => {
+                let files: Vec<(String, Loader)> = package
+                    .files
+                    .into_iter()
+                    .filter(|(_, loader)| loader.header_truncation().is_none())
+                    .map(|(file, loader)| (format!("/{}", file), loader))
+                    .collect();
+                info!("{}: {} ELF file(s)", path, files.len());
+                let title = match package.name {
+                    Some(name) => format!("{}: {}", path, name),
+                    None => path.clone(),
+                };
+                display_aggregate(&title, &files);
+            }
+            Err(error) => failures.push((path, error)),
+        }
+    }
+    failures
+}
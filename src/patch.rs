@@ -0,0 +1,423 @@
+//! Writing patched files back, for the subcommands that change files such as
+//! `sign`, and every other file written. The new contents go to a temporary
+//! file next to the destination, which gets the original's owner, mode and
+//! extended attributes and is then renamed over the destination, so that a
+//! reader never sees half a file and an interrupted run leaves the original as
+//! it was. A symbolic link is followed to the file it names, and a file with
+//! other hard links is not replaced, as the other links would keep the old
+//! contents.
+//!
+//! With `--journal`, the bytes a patch replaced are kept in a JSON file too,
+//! and `unpatch` puts them back.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use log::warn;
+#[cfg(feature = "signing")]
+use serde_json::json;
+use serde_json::Value;
+
+use crate::baseline::key;
+use crate::checksum::sha256;
+use crate::cli::Options;
+use crate::ima::{XATTR_EVM, XATTR_IMA};
+use crate::note::from_hex;
+#[cfg(feature = "signing")]
+use crate::note::hex_string;
+use crate::{display_line, output, xattr};
+
+/// Changed runs closer than this are kept as one range of the journal.
+#[cfg(feature = "signing")]
+const JOURNAL_GAP: usize = 8;
+
+/// Where a patched file goes.
+pub enum Destination {
+    /// Over the file it was read from.
+    InPlace,
+    /// To this path, leaving the original alone.
+    Output(PathBuf),
+}
+
+impl Destination {
+    /// `--in-place` or `--output FILE`; one of them is required, and
+    /// `--output` names a single file so takes a single input.
+    pub fn from_options(options: &Options, inputs: usize) -> Result<Destination, String> {
+        match (options.in_place, &options.output) {
+            (true, Some(_)) => Err("--in-place and --output cannot be combined".to_string()),
+            (true, None) => Ok(Destination::InPlace),
+            (false, Some(_)) if inputs > 1 => {
+                Err("--output takes one input; use --in-place for several".to_string())
+            }
+            (false, Some(output)) => Ok(Destination::Output(PathBuf::from(output))),
+            (false, None) => Err("give --in-place or --output FILE for the result".to_string()),
+        }
+    }
+}
+
+/// Gives `file`, the temporary file for `path`, the owner, mode and
+/// extended attributes of `source`. The owner is kept only where the user may set it; the mode is
+/// set after it, as changing the owner clears set-user-ID bits. The IMA and
+/// EVM attributes are left behind, since they vouch for the old contents.
+fn copy_metadata(source: &Path, file: &File, temporary: &Path, path: &Path) -> Result<(), String> {
+    let metadata = fs::metadata(source).map_err(|error| error.to_string())?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{fchown, MetadataExt};
+        if let Err(error) = fchown(file, Some(metadata.uid()), Some(metadata.gid())) {
+            warn!("{}: owner not kept: {}", path.display(), error);
+        }
+    }
+    file.set_permissions(metadata.permissions())
+        .map_err(|error| error.to_string())?;
+    for name in xattr::list(source)? {
+        if name == XATTR_IMA || name == XATTR_EVM {
+            warn!(
+                "{}: {} dropped, it is for the old contents",
+                path.display(),
+                name
+            );
+            continue;
+        }
+        let copied = xattr::read(source, &name).and_then(|value| match value {
+            Some(value) => xattr::write(temporary, &name, &value),
+            None => Ok(()),
+        });
+        if let Err(error) = copied {
+            warn!("{}: {} not kept: {}", path.display(), name, error);
+        }
+    }
+    Ok(())
+}
+
+/// Writes `bytes` read from `source` to `destination`, returning the path
+/// written.
+pub fn write_patched(
+    source: &Path,
+    destination: &Destination,
+    bytes: &[u8],
+) -> Result<PathBuf, String> {
+    let path = match destination {
+        Destination::InPlace => source,
+        Destination::Output(path) => path,
+    };
+    replace(path, Some(source), bytes)
+}
+
+/// Writes `bytes` to `path`, keeping the metadata of the file it replaces,
+/// returning the path written.
+pub fn write_atomic(path: impl AsRef<Path>, bytes: impl AsRef<[u8]>) -> Result<PathBuf, String> {
+    let path = path.as_ref();
+    let existing = fs::symlink_metadata(path).is_ok().then_some(path);
+    replace(path, existing, bytes.as_ref())
+}
+
+/// The file `path` names, past any symbolic links, refusing one with other
+/// hard links. A path that does not exist yet is its own.
+fn resolve(path: &Path) -> Result<PathBuf, String> {
+    if fs::symlink_metadata(path).is_err() {
+        return Ok(path.to_path_buf());
+    }
+    let resolved =
+        fs::canonicalize(path).map_err(|error| format!("{}: {}", path.display(), error))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        let links = fs::metadata(&resolved)
+            .map_err(|error| format!("{}: {}", resolved.display(), error))?
+            .nlink();
+        if links > 1 {
+            return Err(format!(
+                "{}: has {} hard links, which would keep the old contents; not replacing it",
+                resolved.display(),
+                links
+            ));
+        }
+    }
+    Ok(resolved)
+}
+
+/// Writes `bytes` over `path` by way of a temporary file, with the metadata
+/// of `source` where given.
+fn replace(path: &Path, source: Option<&Path>, bytes: &[u8]) -> Result<PathBuf, String> {
+    let path = resolve(path)?;
+    let name = path
+        .file_name()
+        .ok_or_else(|| format!("{}: not a file name", path.display()))?;
+    let temporary =
+        path.with_file_name(format!(".{}.{}.tmp", name.to_string_lossy(), process::id()));
+    let written = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&temporary)
+        .map_err(|error| error.to_string())
+        .and_then(|mut file| {
+            file.write_all(bytes).map_err(|error| error.to_string())?;
+            if let Some(source) = source {
+                copy_metadata(source, &file, &temporary, &path)?;
+            }
+            file.sync_all().map_err(|error| error.to_string())
+        })
+        .and_then(|()| fs::rename(&temporary, &path).map_err(|error| error.to_string()));
+    if let Err(error) = written {
+        let _ = fs::remove_file(&temporary);
+        return Err(format!("{}: {}", path.display(), error));
+    }
+    Ok(path)
+}
+
+/// The offsets where `original` has bytes `patched` does not: the changed
+/// runs, merged when close, and whatever `original` has past the end of
+/// `patched`.
+#[cfg(feature = "signing")]
+fn changed_ranges(original: &[u8], patched: &[u8]) -> Vec<(usize, usize)> {
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    let common = original.len().min(patched.len());
+    for offset in (0..common).filter(|&at| original[at] != patched[at]) {
+        match ranges.last_mut() {
+            Some((_, end)) if offset <= *end + JOURNAL_GAP => *end = offset + 1,
+            _ => ranges.push((offset, offset + 1)),
+        }
+    }
+    if original.len() > common {
+        match ranges.last_mut() {
+            Some((_, end)) if common <= *end + JOURNAL_GAP => *end = original.len(),
+            _ => ranges.push((common, original.len())),
+        }
+    }
+    ranges
+}
+
+/// The undo records of the files patched in one run, for `--journal`.
+#[cfg(feature = "signing")]
+#[derive(Default)]
+pub struct Journal {
+    files: Vec<Value>,
+}
+
+#[cfg(feature = "signing")]
+impl Journal {
+    /// Records how to get `original` back from `patched`, written to `path`.
+    pub fn record(&mut self, path: &Path, original: &[u8], patched: &[u8]) {
+        let ranges: Vec<Value> = changed_ranges(original, patched)
+            .into_iter()
+            .map(|(start, end)| {
+                json!({ "offset": start, "bytes": hex_string(&original[start..end]) })
+            })
+            .collect();
+        self.files.push(json!({
+            "path": path.display().to_string(),
+            "size": original.len(),
+            "sha256": sha256(original),
+            "patched_size": patched.len(),
+            "patched_sha256": sha256(patched),
+            "ranges": ranges,
+        }));
+    }
+
+    pub fn write(&self, path: &str) -> Result<(), String> {
+        let journal = json!({ "files": self.files });
+        write_atomic(path, format!("{:#}\n", journal))?;
+        output::message(&format!(
+            "Wrote the undo journal of {} file(s) to {}",
+            self.files.len(),
+            path
+        ));
+        Ok(())
+    }
+}
+
+/// The contents before the patch, from the patched ones and their journal
+/// entry, checking both digests on the way. The original can be no longer
+/// than the patched contents and the bytes the journal gives back.
+fn restore(entry: &Value, patched: &[u8]) -> Result<Vec<u8>, String> {
+    let field = |name: &str| entry.get(name).and_then(Value::as_str).unwrap_or("");
+    if sha256(patched) != field("patched_sha256") {
+        return Err("changed since it was patched, not restoring".to_string());
+    }
+    let mut ranges = Vec::new();
+    for range in entry
+        .get("ranges")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+    {
+        let offset = range.get("offset").and_then(Value::as_u64);
+        let bytes = range
+            .get("bytes")
+            .and_then(Value::as_str)
+            .and_then(from_hex);
+        let Some((offset, bytes)) = offset.zip(bytes) else {
+            return Err("the journal has a malformed range".to_string());
+        };
+        ranges.push((offset as usize, bytes));
+    }
+    let size = entry
+        .get("size")
+        .and_then(Value::as_u64)
+        .ok_or("the journal entry has no size")? as usize;
+    let limit = ranges.iter().fold(patched.len(), |limit, (_, bytes)| {
+        limit.saturating_add(bytes.len())
+    });
+    if size > limit {
+        return Err(format!(
+            "the journal gives a size of {} bytes, more than the {} it can restore",
+            size, limit
+        ));
+    }
+    let mut original = patched[..size.min(patched.len())].to_vec();
+    original.resize(size, 0);
+    for (offset, bytes) in ranges {
+        let Some(target) = offset
+            .checked_add(bytes.len())
+            .and_then(|end| original.get_mut(offset..end))
+        else {
+            return Err("the journal has a malformed range".to_string());
+        };
+        target.copy_from_slice(&bytes);
+    }
+    if sha256(&original) != field("sha256") {
+        return Err("the journal does not give back the original contents".to_string());
+    }
+    Ok(original)
+}
+
+/// `unpatch --journal FILE`: restores the files of the journal, or those of
+/// them in `paths`, each at the path it was written to or at `--output`.
+pub fn unpatch(options: &Options) -> Vec<(String, String)> {
+    let Some(journal) = &options.journal else {
+        return vec![("unpatch".to_string(), "no --journal".to_string())];
+    };
+    let entries = match fs::read_to_string(journal)
+        .map_err(|error| error.to_string())
+        .and_then(|text| serde_json::from_str::<Value>(&text).map_err(|error| error.to_string()))
+    {
+        Ok(parsed) => match parsed.get("files") {
+            Some(Value::Array(files)) => files.clone(),
+            _ => {
+                return vec![(
+                    journal.clone(),
+                    "not a journal: expected an array under `files`".to_string(),
+                )]
+            }
+        },
+        Err(error) => return vec![(journal.clone(), error)],
+    };
+    let selected: Vec<&Value> = entries
+        .iter()
+        .filter(|entry| {
+            let path = entry.get("path").and_then(Value::as_str).unwrap_or("");
+            options.paths.is_empty()
+                || options
+                    .paths
+                    .iter()
+                    .any(|wanted| wanted == path || key(wanted) == key(path))
+        })
+        .collect();
+    let destination = match Destination::from_options(options, selected.len()) {
+        Ok(destination) => destination,
+        Err(error) => return vec![("unpatch".to_string(), error)],
+    };
+    let mut failures: Vec<(String, String)> = options
+        .paths
+        .iter()
+        .filter(|wanted| {
+            !selected.iter().any(|entry| {
+                let path = entry.get("path").and_then(Value::as_str).unwrap_or("");
+                *wanted == path || key(wanted) == key(path)
+            })
+        })
+        .map(|wanted| (wanted.clone(), "not in the journal".to_string()))
+        .collect();
+    for entry in selected {
+        let path = Path::new(entry.get("path").and_then(Value::as_str).unwrap_or(""));
+        let label = path.display().to_string();
+        let restored = fs::read(path)
+            .map_err(|error| error.to_string())
+            .and_then(|patched| restore(entry, &patched))
+            .and_then(|original| write_patched(path, &destination, &original));
+        match restored {
+            Ok(written) => {
+                output::begin(&label);
+                let ranges = entry.get("ranges").and_then(Value::as_array);
+                display_line("Restored Ranges", &ranges.map_or(0, Vec::len).to_string());
+                display_line("Output", &written.display().to_string());
+                output::end();
+            }
+            Err(error) => failures.push((label, error)),
+        }
+    }
+    failures
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::process;
+
+    use serde_json::json;
+
+    use super::{restore, write_patched, Destination};
+    use crate::checksum::sha256;
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!(
+            "elf-header-parser-patch-{}-{}",
+            process::id(),
+            name
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn in_place_through_a_symlink_patches_its_target() {
+        let dir = scratch("symlink");
+        fs::write(dir.join("prog"), b"old").unwrap();
+        std::os::unix::fs::symlink("prog", dir.join("link")).unwrap();
+
+        let written = write_patched(&dir.join("link"), &Destination::InPlace, b"new").unwrap();
+        let link = fs::symlink_metadata(dir.join("link")).unwrap();
+        let contents = fs::read(dir.join("prog")).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(link.file_type().is_symlink());
+        assert_eq!(contents, b"new");
+        assert_eq!(written.file_name().unwrap(), "prog");
+    }
+
+    #[test]
+    fn hard_linked_file_is_not_replaced() {
+        let dir = scratch("hardlink");
+        fs::write(dir.join("prog"), b"old").unwrap();
+        fs::hard_link(dir.join("prog"), dir.join("other")).unwrap();
+
+        let written = write_patched(&dir.join("prog"), &Destination::InPlace, b"new");
+        let contents = fs::read(dir.join("other")).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(written.unwrap_err().contains("2 hard links"));
+        assert_eq!(contents, b"old");
+    }
+
+    /// A size no range accounts for is refused before anything is allocated.
+    #[test]
+    fn journal_size_beyond_its_ranges_is_refused() {
+        let patched = b"patched";
+        let entry = json!({
+            "size": 1u64 << 50,
+            "patched_sha256": sha256(patched),
+            "ranges": [{ "offset": 7, "bytes": "0000" }],
+        });
+        let error = restore(&entry, patched).unwrap_err();
+        assert!(
+            error.contains("more than the 9 it can restore"),
+            "{}",
+            error
+        );
+    }
+}
@@ -0,0 +1,170 @@
+//! PE/COFF headers, read as far as telling what a file that starts with `MZ`
+//! is: EFI stub kernels are PE images, some of them with an ELF image inside.
+
+/// `MZ`, and where the DOS header keeps the offset of the PE signature.
+const DOS_MAGIC: &[u8] = b"MZ";
+const DOS_E_LFANEW: usize = 0x3c;
+const PE_SIGNATURE: &[u8] = b"PE\0\0";
+pub const COFF_HEADER_SIZE: usize = 20;
+const SECTION_HEADER_SIZE: usize = 40;
+/// Where the optional header of PE32 and PE32+ alike keeps `Subsystem`.
+pub const OPTIONAL_SUBSYSTEM: usize = 68;
+
+/// The magics of the kernel images that carry an EFI stub, at their offsets.
+const LINUX_EFI_ZBOOT: (usize, &[u8]) = (4, b"zimg");
+/// Where EFI zboot images name their compression.
+const LINUX_EFI_ZBOOT_COMPRESSION: usize = 0x18;
+const LINUX_BZIMAGE: (usize, &[u8]) = (0x202, b"HdrS");
+const LINUX_ARM64_IMAGE: (usize, &[u8]) = (0x38, b"ARM\x64");
+const LINUX_RISCV_IMAGE: (usize, &[u8]) = (0x34, b"RSC\x05");
+/// The section a unified kernel image keeps its kernel in.
+const UKI_KERNEL_SECTION: &str = ".linux";
+
+pub struct PeImage {
+    pub machine: u16,
+    /// `Subsystem` of the optional header, for images that have one.
+    pub subsystem: Option<u16>,
+    /// The section names.
+    pub sections: Vec<String>,
+}
+
+pub fn le_u16(bytes: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(
+        bytes.get(offset..offset + 2)?.try_into().ok()?,
+    ))
+}
+
+pub fn le_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        bytes.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+fn has_magic(bytes: &[u8], (offset, magic): (usize, &[u8])) -> bool {
+    bytes.get(offset..offset + magic.len()) == Some(magic)
+}
+
+/// Where the COFF header of `bytes` starts, when it starts with a DOS header
+/// pointing at a PE signature.
+pub fn coff_offset(bytes: &[u8]) -> Option<usize> {
+    if !bytes.starts_with(DOS_MAGIC) {
+        return None;
+    }
+    let pe = le_u32(bytes, DOS_E_LFANEW)? as usize;
+    if bytes.get(pe..pe.checked_add(PE_SIGNATURE.len())?)? != PE_SIGNATURE {
+        return None;
+    }
+    Some(pe + PE_SIGNATURE.len())
+}
+
+/// The PE headers of `bytes`, when it starts with a DOS header pointing at a
+/// PE signature.
+pub fn parse(bytes: &[u8]) -> Option<PeImage> {
+    let coff = coff_offset(bytes)?;
+    let machine = le_u16(bytes, coff)?;
+    let section_count = le_u16(bytes, coff + 2)? as usize;
+    let optional_size = le_u16(bytes, coff + 16)? as usize;
+    let optional = coff + COFF_HEADER_SIZE;
+    let subsystem = if optional_size > OPTIONAL_SUBSYSTEM {
+        le_u16(bytes, optional + OPTIONAL_SUBSYSTEM)
+    } else {
+        None
+    };
+    let table = optional + optional_size;
+    let sections = (0..section_count)
+        .map_while(|index| {
+            let header = bytes.get(table + index * SECTION_HEADER_SIZE..)?;
+            let name = header.get(..8)?;
+            let name = name.split(|&byte| byte == 0).next().unwrap_or_default();
+            Some(String::from_utf8_lossy(name).into_owned())
+        })
+        .collect();
+    Some(PeImage {
+        machine,
+        subsystem,
+        sections,
+    })
+}
+
+/// The name of an optional header `Subsystem`.
+pub fn subsystem_name(subsystem: u16) -> &'static str {
+    match subsystem {
+        1 => "native",
+        2 => "Windows GUI",
+        3 => "Windows console",
+        5 => "OS/2 console",
+        7 => "POSIX console",
+        9 => "Windows CE GUI",
+        10 => "EFI application",
+        11 => "EFI boot service driver",
+        12 => "EFI runtime driver",
+        13 => "EFI ROM image",
+        14 => "Xbox",
+        16 => "Windows boot application",
+        _ => "unknown",
+    }
+}
+
+impl PeImage {
+    pub fn machine_name(&self) -> String {
+        match self.machine {
+            0x014c => "i386".to_string(),
+            0x8664 => "x86-64".to_string(),
+            0x01c0 | 0x01c2 | 0x01c4 => "ARM".to_string(),
+            0xaa64 => "arm64".to_string(),
+            0x5032 => "RISC-V 32".to_string(),
+            0x5064 => "RISC-V 64".to_string(),
+            0x6264 => "LoongArch64".to_string(),
+            0x0ebc => "EFI byte code".to_string(),
+            other => format!("machine {:#06x}", other),
+        }
+    }
+
+    fn kind(&self) -> &'static str {
+        match self.subsystem {
+            Some(subsystem @ 10..=13) => subsystem_name(subsystem),
+            _ => "executable",
+        }
+    }
+
+    /// The kind of EFI stub kernel `bytes` is, from the magic each kind has
+    /// past the DOS header, or `None` for other PE images.
+    pub fn kernel_kind(&self, bytes: &[u8]) -> Option<String> {
+        if self
+            .sections
+            .iter()
+            .any(|section| section == UKI_KERNEL_SECTION)
+        {
+            return Some("unified kernel image".to_string());
+        }
+        if has_magic(bytes, LINUX_EFI_ZBOOT) {
+            let compression = bytes
+                .get(LINUX_EFI_ZBOOT_COMPRESSION..LINUX_EFI_ZBOOT_COMPRESSION + 32)
+                .and_then(|name| name.split(|&byte| byte == 0).next())
+                .map(String::from_utf8_lossy)
+                .filter(|name| !name.is_empty());
+            return Some(match compression {
+                Some(compression) => format!("EFI zboot kernel, {} compressed", compression),
+                None => "EFI zboot kernel".to_string(),
+            });
+        }
+        [
+            (LINUX_BZIMAGE, "x86 bzImage"),
+            (LINUX_ARM64_IMAGE, "arm64 Image"),
+            (LINUX_RISCV_IMAGE, "RISC-V Image"),
+        ]
+        .into_iter()
+        .find(|(magic, _)| has_magic(bytes, *magic))
+        .map(|(_, kind)| format!("{} with an EFI stub", kind))
+    }
+
+    /// What the image is as a phrase, e.g. `PE/COFF EFI application for
+    /// x86-64 (x86 bzImage with an EFI stub)`.
+    pub fn describe(&self, bytes: &[u8]) -> String {
+        let mut description = format!("PE/COFF {} for {}", self.kind(), self.machine_name());
+        if let Some(kernel) = self.kernel_kind(bytes) {
+            description.push_str(&format!(" ({})", kernel));
+        }
+        description
+    }
+}
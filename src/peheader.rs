@@ -0,0 +1,210 @@
+//! The PE/COFF header, built with the `pe` feature, so that Windows artifacts
+//! in a release tree get a report next to the ELF ones: the machine, the
+//! subsystem, the entry point and the hardening `DllCharacteristics` asks the
+//! loader for.
+
+use crate::normalized::{Kind, NormalizedReport};
+use crate::pe::{self, le_u16, le_u32, COFF_HEADER_SIZE, OPTIONAL_SUBSYSTEM};
+use crate::{display_line, output};
+
+const PE32_MAGIC: u16 = 0x10b;
+const PE32_PLUS_MAGIC: u16 = 0x20b;
+
+/// Where the optional header keeps the fields read here.
+const OPTIONAL_ENTRY_POINT: usize = 16;
+const OPTIONAL_IMAGE_BASE_PE32: usize = 28;
+const OPTIONAL_IMAGE_BASE_PE32_PLUS: usize = 24;
+const OPTIONAL_DLL_CHARACTERISTICS: usize = 70;
+
+const IMAGE_FILE_RELOCS_STRIPPED: u16 = 0x0001;
+const IMAGE_FILE_DLL: u16 = 0x2000;
+
+const IMAGE_DLLCHARACTERISTICS_HIGH_ENTROPY_VA: u16 = 0x0020;
+const IMAGE_DLLCHARACTERISTICS_DYNAMIC_BASE: u16 = 0x0040;
+const IMAGE_DLLCHARACTERISTICS_NX_COMPAT: u16 = 0x0100;
+const IMAGE_DLLCHARACTERISTICS_NO_SEH: u16 = 0x0400;
+const IMAGE_DLLCHARACTERISTICS_GUARD_CF: u16 = 0x4000;
+
+const CHARACTERISTICS: &[(u16, &str)] = &[
+    (0x0001, "RELOCS_STRIPPED"),
+    (0x0002, "EXECUTABLE_IMAGE"),
+    (0x0004, "LINE_NUMS_STRIPPED"),
+    (0x0008, "LOCAL_SYMS_STRIPPED"),
+    (0x0020, "LARGE_ADDRESS_AWARE"),
+    (0x0100, "32BIT_MACHINE"),
+    (0x0200, "DEBUG_STRIPPED"),
+    (0x1000, "SYSTEM"),
+    (0x2000, "DLL"),
+];
+
+const DLL_CHARACTERISTICS: &[(u16, &str)] = &[
+    (0x0020, "HIGH_ENTROPY_VA"),
+    (0x0040, "DYNAMIC_BASE"),
+    (0x0080, "FORCE_INTEGRITY"),
+    (0x0100, "NX_COMPAT"),
+    (0x0200, "NO_ISOLATION"),
+    (0x0400, "NO_SEH"),
+    (0x0800, "NO_BIND"),
+    (0x1000, "APPCONTAINER"),
+    (0x2000, "WDM_DRIVER"),
+    (0x4000, "GUARD_CF"),
+    (0x8000, "TERMINAL_SERVER_AWARE"),
+];
+
+/// The COFF and optional header fields the report shows.
+pub struct PeHeader {
+    pub machine: String,
+    pub section_count: u16,
+    pub characteristics: u16,
+    pub pe32_plus: bool,
+    pub entry_point: u32,
+    pub image_base: u64,
+    pub subsystem: u16,
+    pub dll_characteristics: u16,
+}
+
+impl PeHeader {
+    fn has(&self, dll_characteristic: u16) -> bool {
+        self.dll_characteristics & dll_characteristic != 0
+    }
+
+    /// The loader randomises the base only of images that ask for it and
+    /// still have their base relocations.
+    pub fn aslr(&self) -> bool {
+        self.has(IMAGE_DLLCHARACTERISTICS_DYNAMIC_BASE)
+            && self.characteristics & IMAGE_FILE_RELOCS_STRIPPED == 0
+    }
+
+    fn is_dll(&self) -> bool {
+        self.characteristics & IMAGE_FILE_DLL != 0
+    }
+
+    /// The header in the shape `--normalized` gives every format. Stack
+    /// cookies are in the load config directory, which is not read.
+    pub fn normalized(&self) -> NormalizedReport {
+        let kind = if self.is_dll() {
+            Kind::SharedLibrary
+        } else {
+            Kind::Executable
+        };
+        let mut report = NormalizedReport::new("PE", self.machine.clone(), kind);
+        report.bits = Some(if self.pe32_plus { 64 } else { 32 });
+        report.big_endian = Some(false);
+        report.entry = (self.entry_point != 0)
+            .then(|| self.image_base.wrapping_add(u64::from(self.entry_point)));
+        report.nx = Some(self.has(IMAGE_DLLCHARACTERISTICS_NX_COMPAT));
+        report.aslr = Some(self.aslr());
+        report.cfi = Some(self.has(IMAGE_DLLCHARACTERISTICS_GUARD_CF));
+        report
+    }
+}
+
+pub fn parse(bytes: &[u8]) -> Option<PeHeader> {
+    let image = pe::parse(bytes)?;
+    let coff = pe::coff_offset(bytes)?;
+    let optional = coff + COFF_HEADER_SIZE;
+    let pe32_plus = match le_u16(bytes, optional)? {
+        PE32_MAGIC => false,
+        PE32_PLUS_MAGIC => true,
+        _ => return None,
+    };
+    let image_base = if pe32_plus {
+        let low = le_u32(bytes, optional + OPTIONAL_IMAGE_BASE_PE32_PLUS)?;
+        let high = le_u32(bytes, optional + OPTIONAL_IMAGE_BASE_PE32_PLUS + 4)?;
+        u64::from(high) << 32 | u64::from(low)
+    } else {
+        u64::from(le_u32(bytes, optional + OPTIONAL_IMAGE_BASE_PE32)?)
+    };
+    Some(PeHeader {
+        machine: image.machine_name(),
+        section_count: image.sections.len() as u16,
+        characteristics: le_u16(bytes, coff + 18)?,
+        pe32_plus,
+        entry_point: le_u32(bytes, optional + OPTIONAL_ENTRY_POINT)?,
+        image_base,
+        subsystem: le_u16(bytes, optional + OPTIONAL_SUBSYSTEM)?,
+        dll_characteristics: le_u16(bytes, optional + OPTIONAL_DLL_CHARACTERISTICS)?,
+    })
+}
+
+fn flag_names(value: u16, names: &[(u16, &str)]) -> String {
+    let set: Vec<&str> = names
+        .iter()
+        .filter(|(bit, _)| value & bit != 0)
+        .map(|(_, name)| *name)
+        .collect();
+    if set.is_empty() {
+        format!("{:#x}", value)
+    } else {
+        format!("{:#x} ({})", value, set.join(", "))
+    }
+}
+
+fn enabled(on: bool) -> &'static str {
+    if on {
+        "Enabled"
+    } else {
+        "Disabled"
+    }
+}
+
+/// The header of a PE image, then its hardening in the terms `--checksec`
+/// uses for ELF files.
+pub fn display_pe(path: &str, header: &PeHeader) {
+    output::begin(path);
+    display_line("Format", if header.pe32_plus { "PE32+" } else { "PE32" });
+    display_line(
+        "File Type",
+        if header.is_dll() {
+            "DLL"
+        } else {
+            "Executable image"
+        },
+    );
+    display_line("Machine Type", &header.machine);
+    display_line(
+        "Subsystem",
+        &format!(
+            "{} ({})",
+            pe::subsystem_name(header.subsystem),
+            header.subsystem
+        ),
+    );
+    display_line("Entry Point", &format!("{:#x} (RVA)", header.entry_point));
+    display_line("Image Base", &format!("{:#x}", header.image_base));
+    display_line("Sections", &header.section_count.to_string());
+    display_line(
+        "Characteristics",
+        &flag_names(header.characteristics, CHARACTERISTICS),
+    );
+    display_line(
+        "DLL Characteristics",
+        &flag_names(header.dll_characteristics, DLL_CHARACTERISTICS),
+    );
+    display_line(
+        "NX",
+        enabled(header.has(IMAGE_DLLCHARACTERISTICS_NX_COMPAT)),
+    );
+    display_line("ASLR", enabled(header.aslr()));
+    display_line(
+        "High Entropy ASLR",
+        enabled(
+            header.aslr()
+                && header.pe32_plus
+                && header.has(IMAGE_DLLCHARACTERISTICS_HIGH_ENTROPY_VA),
+        ),
+    );
+    display_line(
+        "Control Flow Guard",
+        enabled(header.has(IMAGE_DLLCHARACTERISTICS_GUARD_CF)),
+    );
+    display_line(
+        "SEH",
+        if header.has(IMAGE_DLLCHARACTERISTICS_NO_SEH) {
+            "None"
+        } else {
+            "Allowed"
+        },
+    );
+    output::end();
+}
@@ -0,0 +1,185 @@
+use log::{debug, info};
+use std::fs;
+
+use crate::output;
+use crate::segment::PT_LOAD;
+use crate::{display_line, Loader};
+
+/// One line of `/proc/PID/maps`.
+pub(crate) struct Mapping {
+    pub start: u64,
+    pub end: u64,
+    pub perms: String,
+    pub offset: u64,
+    /// The file behind the mapping; empty for anonymous memory.
+    pub path: String,
+}
+
+/// Parses a `start-end perms offset dev inode [path]` line. Only the first three
+/// fields are required, so a line cut short in a crash log still works.
+pub(crate) fn parse_mapping(line: &str) -> Option<Mapping> {
+    let mut fields = line.split_whitespace();
+    let (start, end) = fields.next()?.split_once('-')?;
+    let perms = fields.next()?.to_string();
+    let offset = u64::from_str_radix(fields.next()?, 16).ok()?;
+    // The path is the rest of the line after dev and inode, spaces included.
+    let path = line
+        .splitn(6, ' ')
+        .nth(5)
+        .map(|path| path.trim_start().to_string())
+        .unwrap_or_default();
+    Some(Mapping {
+        start: u64::from_str_radix(start, 16).ok()?,
+        end: u64::from_str_radix(end, 16).ok()?,
+        perms,
+        offset,
+        path,
+    })
+}
+
+/// A file mapped into the process, with its mappings in address order.
+struct Module {
+    path: String,
+    mappings: Vec<Mapping>,
+}
+
+/// Groups the mappings backed by a file by that file. Anonymous memory, the
+/// stack and `[vdso]` have no path.
+fn parse_maps(maps: &str) -> Vec<Module> {
+    let mut modules: Vec<Module> = Vec::new();
+    for mapping in maps.lines().filter_map(parse_mapping) {
+        if !mapping.path.starts_with('/') {
+            continue;
+        }
+        match modules
+            .iter_mut()
+            .find(|module| module.path == mapping.path)
+        {
+            Some(module) => module.mappings.push(mapping),
+            None => modules.push(Module {
+                path: mapping.path.clone(),
+                mappings: vec![mapping],
+            }),
+        }
+    }
+    modules
+}
+
+/// The load bias implied by one mapping of the file: the difference between the
+/// addresses the module runs at and the virtual addresses in its file.
+///
+/// A mapping starts at its segment's `p_offset` rounded down to a page, which
+/// is at most `p_align`, and as offset and address are congruent modulo the
+/// page size the rounding cancels out. When several segments could start
+/// there the one whose `p_offset` is nearest wins.
+pub(crate) fn mapping_bias(loader: &Loader, mapping: &Mapping) -> Option<u64> {
+    let segment = loader
+        .program_headers()
+        .into_iter()
+        .filter(|header| header.p_type == PT_LOAD)
+        .filter(|header| {
+            let align = header.p_align.max(1);
+            header.p_offset - header.p_offset % align <= mapping.offset
+                && mapping.offset <= header.p_offset.saturating_add(header.p_filesz)
+        })
+        .min_by_key(|header| header.p_offset.abs_diff(mapping.offset))?;
+    Some(
+        mapping
+            .start
+            .wrapping_sub(mapping.offset)
+            .wrapping_sub(segment.p_vaddr.wrapping_sub(segment.p_offset)),
+    )
+}
+
+/// The load bias of a module, from its lowest mapping.
+fn load_bias(loader: &Loader, module: &Module) -> Option<u64> {
+    let first = module.mappings.iter().min_by_key(|mapping| mapping.start)?;
+    mapping_bias(loader, first)
+}
+
+fn display_module(label: &str, loader: &Loader, module: &Module) {
+    let bias = load_bias(loader, module);
+    output::begin(label);
+    display_line("File Type", loader.get_e_type());
+    display_line("Machine Type", &loader.machine_name());
+    display_line("Entry Point", &format!("{:#x}", loader.raw_e_entry()));
+    match bias {
+        Some(bias) => {
+            display_line("Load Bias", &format!("{:#x}", bias));
+            if loader.raw_e_entry() != 0 {
+                display_line(
+                    "Runtime Entry Point",
+                    &format!("{:#x}", loader.raw_e_entry().wrapping_add(bias)),
+                );
+            }
+        }
+        None => display_line("Load Bias", "unknown (no PT_LOAD segment)"),
+    }
+    display_line("Mappings", &module.mappings.len().to_string());
+    for mapping in &module.mappings {
+        let mut line = format!(
+            "{:#x}-{:#x} {} offset {:#x}",
+            mapping.start, mapping.end, mapping.perms, mapping.offset
+        );
+        if let Some(bias) = bias {
+            line.push_str(&format!(
+                ", vaddr {:#x}-{:#x}",
+                mapping.start.wrapping_sub(bias),
+                mapping.end.wrapping_sub(bias)
+            ));
+        }
+        output::item(1, &line);
+    }
+    output::end();
+}
+
+/// `--pid`: reports the executable of a running process and every file it has
+/// mapped, with the load bias that turns the file's virtual addresses into the
+/// process's. Files are opened through `/proc/PID/root`, so processes in other
+/// mount namespaces are read correctly. Returns the modules that could not be read.
+pub fn display_process(pid: u32) -> Vec<(String, String)> {
+    let proc = format!("/proc/{}", pid);
+    let maps = match fs::read_to_string(format!("{}/maps", proc)) {
+        Ok(maps) => maps,
+        Err(error) => return vec![(format!("pid {}", pid), error.to_string())],
+    };
+    let mut modules = parse_maps(&maps);
+    // The executable first, then the libraries in address order.
+    let exe = fs::read_link(format!("{}/exe", proc))
+        .map(|exe| exe.display().to_string())
+        .unwrap_or_default();
+    if let Some(index) = modules.iter().position(|module| module.path == exe) {
+        let module = modules.remove(index);
+        modules.insert(0, module);
+    }
+    info!("pid {}: {} mapped file(s)", pid, modules.len());
+
+    let mut failures = Vec::new();
+    for module in &modules {
+        let label = format!("{}: {}", pid, module.path);
+        // The executable is read through exe, which works even after it was deleted.
+        let opened = if module.path == exe {
+            Loader::open(&format!("{}/exe", proc))
+        } else {
+            Loader::open(&format!("{}/root{}", proc, module.path))
+        };
+        let loader = match opened {
+            Ok(loader) => loader,
+            Err(error) => {
+                failures.push((label, error.to_string()));
+                continue;
+            }
+        };
+        // Mapped data files such as locale archives are not ELF.
+        if !loader.is_elf() {
+            debug!("{}: skipped, no ELF magic", label);
+            continue;
+        }
+        if let Some(problem) = loader.header_truncation() {
+            failures.push((label, problem));
+            continue;
+        }
+        display_module(&label, &loader, module);
+    }
+    failures
+}
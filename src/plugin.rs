@@ -0,0 +1,328 @@
+//! Checks loaded at run time with `--plugin`, built with the `plugins` feature,
+//! so that custom rules can ship without forking the crate.
+//!
+//! A plugin is handed the parsed file as a JSON document (see [`crate::model::parsed_elf`]) and
+//! answers with a JSON array of findings, each
+//! `{"severity": "error"|"warning"|"note", "check": "...", "message": "..."}`.
+//!
+//! A shared library exports
+//! `char *elfhp_plugin_analyze(const char *elf_json)` and, if the string it
+//! returns has to be released, `void elfhp_plugin_free(char *findings)`.
+//!
+//! A WASM module exports its `memory`, `alloc(len: i32) -> i32` to reserve room
+//! for the document, and `analyze(ptr: i32, len: i32) -> i64`, which returns
+//! where the findings are as `ptr << 32 | len`. It gets no imports, and it is
+//! stopped after a number of instructions that grows with the document.
+
+use std::ffi::{c_char, CStr, CString};
+use std::fs;
+use std::path::Path;
+
+use serde_json::Value;
+
+use crate::finding::{self, Finding, Severity};
+use crate::model::parsed_elf;
+use crate::output;
+use crate::{display_line, Loader};
+
+/// The instructions a WASM plugin may run per document, plus
+/// `WASM_FUEL_PER_BYTE` for each of its bytes, so that a plugin stuck in a loop
+/// fails instead of hanging the run.
+const WASM_FUEL: u64 = 1 << 26;
+const WASM_FUEL_PER_BYTE: u64 = 1000;
+/// The most linear memory a WASM plugin may have; growing past it traps.
+const WASM_MEMORY: usize = 256 << 20;
+
+type AnalyzeFn = unsafe extern "C" fn(*const c_char) -> *mut c_char;
+type FreeFn = unsafe extern "C" fn(*mut c_char);
+
+enum Plugin {
+    Native(libloading::Library),
+    Wasm {
+        store: Box<wasmi::Store<wasmi::StoreLimits>>,
+        instance: wasmi::Instance,
+    },
+}
+
+impl Plugin {
+    /// Loads a `.wasm` module or, for any other extension, a shared library.
+    fn load(path: &str) -> Result<Plugin, String> {
+        if path.ends_with(".wasm") {
+            let bytes = fs::read(path).map_err(|error| error.to_string())?;
+            let mut config = wasmi::Config::default();
+            config.consume_fuel(true);
+            let engine = wasmi::Engine::new(&config);
+            let module = wasmi::Module::new(&engine, &bytes).map_err(|error| error.to_string())?;
+            let limits = wasmi::StoreLimitsBuilder::new()
+                .memory_size(WASM_MEMORY)
+                .trap_on_grow_failure(true)
+                .build();
+            let mut store = wasmi::Store::new(&engine, limits);
+            store.limiter(|limits| limits);
+            // The start function gets the budget of an empty document.
+            store
+                .set_fuel(WASM_FUEL)
+                .map_err(|error| error.to_string())?;
+            let instance = wasmi::Linker::<wasmi::StoreLimits>::new(&engine)
+                .instantiate(&mut store, &module)
+                .and_then(|instance| instance.start(&mut store))
+                .map_err(|error| error.to_string())?;
+            return Ok(Plugin::Wasm {
+                store: Box::new(store),
+                instance,
+            });
+        }
+        // SAFETY: loading a library runs its initialisers; plugins are trusted
+        // like any other code the user chooses to run.
+        let library =
+            unsafe { libloading::Library::new(path) }.map_err(|error| error.to_string())?;
+        // SAFETY: only the presence of the symbol is checked here.
+        unsafe { library.get::<AnalyzeFn>(b"elfhp_plugin_analyze\0") }
+            .map_err(|error| error.to_string())?;
+        Ok(Plugin::Native(library))
+    }
+
+    /// Runs the plugin on one document and returns its answer.
+    fn analyze(&mut self, document: &str) -> Result<String, String> {
+        match self {
+            Plugin::Native(library) => {
+                let input = CString::new(document).map_err(|error| error.to_string())?;
+                // SAFETY: the signature is the one the module documentation asks
+                // plugins to export, and `load` checked the symbol exists.
+                unsafe {
+                    let analyze = library
+                        .get::<AnalyzeFn>(b"elfhp_plugin_analyze\0")
+                        .map_err(|error| error.to_string())?;
+                    let answer = analyze(input.as_ptr());
+                    if answer.is_null() {
+                        return Err("the plugin returned no findings".to_string());
+                    }
+                    let text = CStr::from_ptr(answer).to_string_lossy().into_owned();
+                    if let Ok(free) = library.get::<FreeFn>(b"elfhp_plugin_free\0") {
+                        free(answer);
+                    }
+                    Ok(text)
+                }
+            }
+            Plugin::Wasm { store, instance } => {
+                let store: &mut wasmi::Store<wasmi::StoreLimits> = store;
+                let fuel = WASM_FUEL + WASM_FUEL_PER_BYTE * document.len() as u64;
+                store.set_fuel(fuel).map_err(|error| error.to_string())?;
+                let error = |error: wasmi::Error| match error.as_trap_code() {
+                    Some(wasmi::core::TrapCode::OutOfFuel) => {
+                        format!("stopped after {} instructions", fuel)
+                    }
+                    Some(wasmi::core::TrapCode::GrowthOperationLimited) => {
+                        format!("stopped growing its memory past {} bytes", WASM_MEMORY)
+                    }
+                    _ => error.to_string(),
+                };
+                let memory = instance
+                    .get_memory(&*store, "memory")
+                    .ok_or("the module exports no memory")?;
+                let alloc = instance
+                    .get_typed_func::<i32, i32>(&*store, "alloc")
+                    .map_err(error)?;
+                let analyze = instance
+                    .get_typed_func::<(i32, i32), i64>(&*store, "analyze")
+                    .map_err(error)?;
+                let length = document.len() as i32;
+                let pointer = alloc.call(&mut *store, length).map_err(error)?;
+                memory
+                    .write(&mut *store, pointer as u32 as usize, document.as_bytes())
+                    .map_err(|error| error.to_string())?;
+                let answer = analyze
+                    .call(&mut *store, (pointer, length))
+                    .map_err(error)? as u64;
+                // The answer is read in place, so a length past the end of the
+                // memory is refused before anything is allocated for it.
+                let start = (answer >> 32) as usize;
+                let text = start
+                    .checked_add((answer & 0xffff_ffff) as usize)
+                    .and_then(|end| memory.data(&*store).get(start..end))
+                    .ok_or("the findings lie outside the module's memory")?;
+                Ok(String::from_utf8_lossy(text).into_owned())
+            }
+        }
+    }
+}
+
+/// Reads a plugin's answer, naming its checks after the plugin.
+fn parse_findings(plugin: &str, answer: &str) -> Result<Vec<Finding>, String> {
+    let value: Value = serde_json::from_str(answer).map_err(|error| error.to_string())?;
+    let entries = value
+        .as_array()
+        .ok_or("the findings are not a JSON array")?;
+    entries
+        .iter()
+        .map(|entry| {
+            let field = |name: &str| entry.get(name).and_then(Value::as_str);
+            let severity = field("severity")
+                .and_then(Severity::parse)
+                .ok_or("a finding has no valid severity")?;
+            Ok(Finding {
+                severity,
+                check: format!("{}/{}", plugin, field("check").unwrap_or("unnamed")),
+                message: field("message").unwrap_or_default().to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Runs every plugin over every target and returns the findings per target.
+/// Plugins that cannot be loaded or fail are added to `errors`.
+fn run_plugins(
+    targets: &[(String, Loader)],
+    plugins: &[String],
+    errors: &mut Vec<(String, String)>,
+) -> Vec<(String, Vec<Finding>)> {
+    let mut loaded = Vec::new();
+    for path in plugins {
+        match Plugin::load(path) {
+            Ok(plugin) => {
+                let name = Path::new(path)
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| path.clone());
+                loaded.push((path, name, plugin));
+            }
+            Err(error) => errors.push((path.clone(), error)),
+        }
+    }
+    let mut results = Vec::new();
+    for (label, loader) in targets {
+        let document = parsed_elf(label, loader).to_string();
+        let mut findings = Vec::new();
+        for (path, name, plugin) in &mut loaded {
+            match plugin
+                .analyze(&document)
+                .and_then(|answer| parse_findings(name, &answer))
+            {
+                Ok(found) => findings.extend(found),
+                Err(error) => errors.push((path.to_string(), format!("{}: {}", label, error))),
+            }
+        }
+        results.push((label.clone(), findings));
+    }
+    results
+}
+
+/// `--plugin FILE...`: the findings of the plugins for each target. Files with
+/// error findings are returned as failures, as are plugins that do not work.
+pub fn display_plugins(targets: &[(String, Loader)], plugins: &[String]) -> Vec<(String, String)> {
+    let mut failures = Vec::new();
+    let results = run_plugins(targets, plugins, &mut failures);
+    for (label, findings) in &results {
+        // Plugins do not declare their checks, only those with findings are known.
+        finding::record(label, std::iter::empty::<String>(), findings);
+        output::begin(label);
+        display_line("Findings", &findings.len().to_string());
+        for finding in findings {
+            output::item(1, &finding.describe());
+        }
+        output::end();
+        let errors = finding::errors(findings);
+        if errors > 0 {
+            failures.push((label.clone(), format!("{} plugin error(s)", errors)));
+        }
+    }
+    failures
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::process;
+
+    use super::Plugin;
+
+    /// A module exporting one page of `memory`, an `alloc` that always
+    /// answers 0 and an `analyze` with the given body, in the binary format.
+    fn module(analyze: &[u8]) -> Vec<u8> {
+        fn section(id: u8, content: &[u8]) -> Vec<u8> {
+            let mut section = vec![id, content.len() as u8];
+            section.extend_from_slice(content);
+            section
+        }
+        fn export(name: &str, kind: u8, index: u8) -> Vec<u8> {
+            let mut export = vec![name.len() as u8];
+            export.extend_from_slice(name.as_bytes());
+            export.extend_from_slice(&[kind, index]);
+            export
+        }
+        let alloc = [0x00, 0x41, 0x00, 0x0b];
+        let mut exports = vec![3];
+        exports.extend(export("memory", 2, 0));
+        exports.extend(export("alloc", 0, 0));
+        exports.extend(export("analyze", 0, 1));
+        let mut code = vec![2, alloc.len() as u8];
+        code.extend_from_slice(&alloc);
+        code.push(analyze.len() as u8);
+        code.extend_from_slice(analyze);
+
+        let mut module = b"\0asm\x01\0\0\0".to_vec();
+        // (i32) -> i32 and (i32, i32) -> i64.
+        module.extend(section(
+            1,
+            &[2, 0x60, 1, 0x7f, 1, 0x7f, 0x60, 2, 0x7f, 0x7f, 1, 0x7e],
+        ));
+        module.extend(section(3, &[2, 0, 1]));
+        module.extend(section(5, &[1, 0, 1]));
+        module.extend(section(7, &exports));
+        module.extend(section(10, &code));
+        module
+    }
+
+    fn analyze_with(name: &str, analyze: &[u8]) -> Result<String, String> {
+        let path = env::temp_dir().join(format!(
+            "elf-header-parser-plugin-{}-{}.wasm",
+            process::id(),
+            name
+        ));
+        fs::write(&path, module(analyze)).unwrap();
+        let result =
+            Plugin::load(&path.display().to_string()).and_then(|mut plugin| plugin.analyze("{}"));
+        fs::remove_file(&path).unwrap();
+        result
+    }
+
+    #[test]
+    fn endless_loop_runs_out_of_fuel() {
+        // loop br 0 end; i64.const 0
+        let error = analyze_with(
+            "loop",
+            &[0x00, 0x03, 0x40, 0x0c, 0x00, 0x0b, 0x42, 0x00, 0x0b],
+        )
+        .unwrap_err();
+        assert!(error.contains("instructions"), "{}", error);
+    }
+
+    #[test]
+    fn memory_growth_is_capped() {
+        // i32.const 0xffff; memory.grow; drop; i64.const 0: 4 GiB of memory.
+        let error = analyze_with(
+            "grow",
+            &[
+                0x00, 0x41, 0xff, 0xff, 0x03, 0x40, 0x00, 0x1a, 0x42, 0x00, 0x0b,
+            ],
+        )
+        .unwrap_err();
+        assert!(error.contains("growing its memory"), "{}", error);
+    }
+
+    #[test]
+    fn answer_longer_than_the_memory() {
+        // i64.const 0xffff_ffff: 4 GiB of findings at address 0.
+        let error =
+            analyze_with("long", &[0x00, 0x42, 0xff, 0xff, 0xff, 0xff, 0x0f, 0x0b]).unwrap_err();
+        assert!(error.contains("outside the module's memory"), "{}", error);
+    }
+
+    #[test]
+    fn answer_in_memory() {
+        // i64.const 2: the two bytes of the document, `{}`, at address 0.
+        let answer = analyze_with("echo", &[0x00, 0x42, 0x02, 0x0b]).unwrap();
+        assert_eq!(answer, "{}");
+    }
+}
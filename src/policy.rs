@@ -0,0 +1,334 @@
+use std::fs;
+
+use serde_json::{json, Map, Value};
+
+use crate::checksec::RelroLevel;
+use crate::exports::glob_match;
+use crate::finding::{self, Finding, Severity};
+use crate::output;
+use crate::segment::{
+    PT_DYNAMIC, PT_GNU_EH_FRAME, PT_GNU_RELRO, PT_GNU_STACK, PT_INTERP, PT_LOAD, PT_NOTE,
+};
+use crate::{display_line, Loader};
+
+/// The facts about a file rules can compare, as listed by [`facts`].
+const FIELDS: &[&str] = &[
+    "class",
+    "endian",
+    "type",
+    "machine",
+    "machine_name",
+    "osabi",
+    "entry",
+    "flags",
+    "size",
+    "sections",
+    "segments",
+    "needed",
+    "soname",
+    "pie",
+    "nx",
+    "relro",
+    "bind_now",
+    "stack_canary",
+    "textrel",
+];
+
+/// The segment types `segment` accepts by name, with or without `PT_`.
+const SEGMENT_TYPES: &[(&str, u32)] = &[
+    ("LOAD", PT_LOAD),
+    ("DYNAMIC", PT_DYNAMIC),
+    ("INTERP", PT_INTERP),
+    ("NOTE", PT_NOTE),
+    ("GNU_EH_FRAME", PT_GNU_EH_FRAME),
+    ("GNU_STACK", PT_GNU_STACK),
+    ("GNU_RELRO", PT_GNU_RELRO),
+];
+
+enum Comparison {
+    Equals(Value),
+    NotEquals(Value),
+    OneOf(Vec<Value>),
+    Min(f64),
+    Max(f64),
+}
+
+enum Check {
+    Field {
+        field: String,
+        comparisons: Vec<Comparison>,
+    },
+    RequireSection(String),
+    ForbidSection(String),
+    MaxSegmentSize {
+        p_type: Option<u32>,
+        max: u64,
+    },
+    RequireNote(String),
+}
+
+/// One rule of a policy file.
+pub struct Rule {
+    id: String,
+    severity: Severity,
+    check: Check,
+}
+
+/// Reads one `[[rule]]` table (or YAML mapping) of a policy file.
+fn parse_rule(index: usize, rule: &Value) -> Result<Rule, String> {
+    let text = |name: &str| rule.get(name).and_then(Value::as_str);
+    let number = |name: &str| -> Result<Option<f64>, String> {
+        match rule.get(name) {
+            None => Ok(None),
+            Some(value) => value
+                .as_f64()
+                .map(Some)
+                .ok_or_else(|| format!("rule {}: {} is not a number", index + 1, name)),
+        }
+    };
+    let id = text("id")
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("rule-{}", index + 1));
+    let severity = match text("severity") {
+        None => Severity::Error,
+        Some(severity) => Severity::parse(severity)
+            .ok_or_else(|| format!("{}: unknown severity {}", id, severity))?,
+    };
+    let check = if let Some(field) = text("field") {
+        if !FIELDS.contains(&field) {
+            return Err(format!("{}: unknown field {}", id, field));
+        }
+        let mut comparisons = Vec::new();
+        if let Some(value) = rule.get("equals") {
+            comparisons.push(Comparison::Equals(value.clone()));
+        }
+        if let Some(value) = rule.get("not_equals") {
+            comparisons.push(Comparison::NotEquals(value.clone()));
+        }
+        if let Some(values) = rule.get("one_of") {
+            let values = values
+                .as_array()
+                .ok_or_else(|| format!("{}: one_of is not a list", id))?;
+            comparisons.push(Comparison::OneOf(values.clone()));
+        }
+        comparisons.extend(number("min")?.map(Comparison::Min));
+        comparisons.extend(number("max")?.map(Comparison::Max));
+        if comparisons.is_empty() {
+            return Err(format!(
+                "{}: field needs equals, not_equals, one_of, min or max",
+                id
+            ));
+        }
+        Check::Field {
+            field: field.to_string(),
+            comparisons,
+        }
+    } else if let Some(pattern) = text("require_section") {
+        Check::RequireSection(pattern.to_string())
+    } else if let Some(pattern) = text("forbid_section") {
+        Check::ForbidSection(pattern.to_string())
+    } else if let Some(max) = number("max_segment_size")? {
+        let p_type = match text("segment") {
+            None => None,
+            Some(name) => Some(
+                SEGMENT_TYPES
+                    .iter()
+                    .find(|(known, _)| *known == name.trim_start_matches("PT_"))
+                    .map(|&(_, p_type)| p_type)
+                    .ok_or_else(|| format!("{}: unknown segment type {}", id, name))?,
+            ),
+        };
+        Check::MaxSegmentSize {
+            p_type,
+            max: max as u64,
+        }
+    } else if let Some(note) = text("require_note") {
+        Check::RequireNote(note.to_string())
+    } else {
+        return Err(format!(
+            "{}: expected field, require_section, forbid_section, max_segment_size or require_note",
+            id
+        ));
+    };
+    Ok(Rule {
+        id,
+        severity,
+        check,
+    })
+}
+
+/// Reads the rules of a policy file, YAML when it is named `.yaml` or `.yml`
+/// and TOML otherwise. Both hold a list of rules under `rule`.
+pub fn load_rules(path: &str) -> Result<Vec<Rule>, String> {
+    let text = fs::read_to_string(path).map_err(|error| error.to_string())?;
+    let document: Value = if path.ends_with(".yaml") || path.ends_with(".yml") {
+        serde_yaml::from_str(&text).map_err(|error| error.to_string())?
+    } else {
+        toml::from_str(&text).map_err(|error| error.to_string())?
+    };
+    let rules = document
+        .get("rule")
+        .and_then(Value::as_array)
+        .ok_or("no rules: expected a list of rules under `rule`")?;
+    rules
+        .iter()
+        .enumerate()
+        .map(|(index, rule)| parse_rule(index, rule))
+        .collect()
+}
+
+/// The values `field` rules compare against.
+fn facts(loader: &Loader) -> Map<String, Value> {
+    let relro = match loader.relro_report().level {
+        RelroLevel::None => "none",
+        RelroLevel::Partial => "partial",
+        RelroLevel::Full => "full",
+    };
+    let facts = json!({
+        "class": if loader.is_64bit() { 64 } else { 32 },
+        "endian": if loader.is_big_endian() { "big" } else { "little" },
+        "type": loader.raw_e_type(),
+        "machine": loader.raw_e_machine(),
+        "machine_name": loader.machine_name(),
+        "osabi": loader.raw_ei_osabi(),
+        "entry": loader.raw_e_entry(),
+        "flags": loader.raw_e_flags(),
+        "size": loader.bytes().len(),
+        "sections": loader.section_headers().len(),
+        "segments": loader.program_headers().len(),
+        "needed": loader.needed_libraries().len(),
+        "soname": loader.soname(),
+        "pie": loader.is_pie(),
+        "nx": loader.has_nx(),
+        "relro": relro,
+        "bind_now": loader.is_bind_now(),
+        "stack_canary": loader.has_stack_canary(),
+        "textrel": loader.has_textrel_flag() || !loader.text_relocations().is_empty(),
+    });
+    match facts {
+        Value::Object(facts) => facts,
+        _ => Map::new(),
+    }
+}
+
+/// Why a value fails a comparison, if it does.
+fn compare(field: &str, value: &Value, comparison: &Comparison) -> Option<String> {
+    match comparison {
+        Comparison::Equals(expected) if value != expected => {
+            Some(format!("{} is {}, expected {}", field, value, expected))
+        }
+        Comparison::NotEquals(unwanted) if value == unwanted => {
+            Some(format!("{} is {}", field, value))
+        }
+        Comparison::OneOf(allowed) if !allowed.contains(value) => Some(format!(
+            "{} is {}, expected one of {}",
+            field,
+            value,
+            Value::from(allowed.clone())
+        )),
+        Comparison::Min(min) if value.as_f64().is_none_or(|value| value < *min) => {
+            Some(format!("{} is {}, below the minimum {}", field, value, min))
+        }
+        Comparison::Max(max) if value.as_f64().is_none_or(|value| value > *max) => {
+            Some(format!("{} is {}, above the maximum {}", field, value, max))
+        }
+        _ => None,
+    }
+}
+
+/// The violations of one rule by a file.
+fn violations(rule: &Rule, loader: &Loader, facts: &Map<String, Value>) -> Vec<String> {
+    match &rule.check {
+        Check::Field { field, comparisons } => {
+            let value = facts.get(field).cloned().unwrap_or(Value::Null);
+            comparisons
+                .iter()
+                .filter_map(|comparison| compare(field, &value, comparison))
+                .collect()
+        }
+        Check::RequireSection(pattern) => {
+            let present = loader
+                .section_headers()
+                .iter()
+                .any(|section| glob_match(pattern, &section.name));
+            if present {
+                Vec::new()
+            } else {
+                vec![format!("no section matches {}", pattern)]
+            }
+        }
+        Check::ForbidSection(pattern) => loader
+            .section_headers()
+            .iter()
+            .filter(|section| section.index != 0 && glob_match(pattern, &section.name))
+            .map(|section| format!("section {} is present", section.name))
+            .collect(),
+        Check::MaxSegmentSize { p_type, max } => loader
+            .program_headers()
+            .iter()
+            .filter(|segment| p_type.is_none_or(|p_type| segment.p_type == p_type))
+            .filter(|segment| segment.p_memsz > *max)
+            .map(|segment| {
+                format!(
+                    "segment at {:#x} is {} bytes, above the maximum {}",
+                    segment.p_vaddr, segment.p_memsz, max
+                )
+            })
+            .collect(),
+        Check::RequireNote(name) => {
+            let notes = loader.notes();
+            let os = loader.note_os(&notes);
+            let present = notes
+                .iter()
+                .any(|note| note.owner == *name || loader.describe_note(note, os).0 == *name);
+            if present {
+                Vec::new()
+            } else {
+                vec![format!("no {} note", name)]
+            }
+        }
+    }
+}
+
+/// Evaluates every rule against one file.
+pub fn evaluate(rules: &[Rule], loader: &Loader) -> Vec<Finding> {
+    let facts = facts(loader);
+    rules
+        .iter()
+        .flat_map(|rule| {
+            violations(rule, loader, &facts)
+                .into_iter()
+                .map(|message| Finding {
+                    severity: rule.severity,
+                    check: format!("policy/{}", rule.id),
+                    message,
+                })
+        })
+        .collect()
+}
+
+/// `policy RULES FILE...`: the violations of the rules by each file. Files
+/// violating rules of error severity are returned as failures.
+pub fn display_policy(targets: &[(String, Loader)], rules: &[Rule]) -> Vec<(String, String)> {
+    let mut failures = Vec::new();
+    for (label, loader) in targets {
+        let findings = evaluate(rules, loader);
+        finding::record(
+            label,
+            rules.iter().map(|rule| format!("policy/{}", rule.id)),
+            &findings,
+        );
+        output::begin(label);
+        display_line("Rules", &rules.len().to_string());
+        display_line("Violations", &findings.len().to_string());
+        for finding in &findings {
+            output::item(1, &finding.describe());
+        }
+        output::end();
+        let errors = finding::errors(&findings);
+        if errors > 0 {
+            failures.push((label.clone(), format!("{} policy violation(s)", errors)));
+        }
+    }
+    failures
+}
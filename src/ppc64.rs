@@ -0,0 +1,220 @@
+use crate::arch::{ArchExt, TextModel};
+use crate::attributes::AttributeValue;
+use crate::output;
+use crate::symbol::{SHN_UNDEF, STT_FUNC};
+use crate::{display_line, Loader};
+
+const EM_PPC64: u16 = 21;
+
+const EF_PPC64_ABI: u32 = 0x3;
+
+const DT_PPC64_GLINK: u64 = 0x7000_0000;
+const DT_PPC64_OPD: u64 = 0x7000_0001;
+const DT_PPC64_OPDSZ: u64 = 0x7000_0002;
+const DT_PPC64_OPT: u64 = 0x7000_0003;
+
+/// The ELFv1 function descriptor: entry address, TOC pointer and environment pointer.
+const FUNCTION_DESCRIPTOR_SIZE: u64 = 24;
+/// r2 points 0x8000 bytes into the TOC so signed 16-bit offsets reach 64 KB of it.
+const TOC_BIAS: u64 = 0x8000;
+/// st_other bits holding the distance from the global to the local entry point.
+const STO_PPC64_LOCAL_MASK: u8 = 0xe0;
+const STO_PPC64_LOCAL_SHIFT: u8 = 5;
+
+fn abi_version(flags: u32, big_endian: bool) -> &'static str {
+    match flags & EF_PPC64_ABI {
+        1 => "ELFv1 (function descriptors)",
+        2 => "ELFv2 (direct entry, local entry points)",
+        _ if big_endian => "unspecified, ELFv1 assumed for big-endian",
+        _ => "unspecified, ELFv2 assumed for little-endian",
+    }
+}
+
+fn is_elfv1(flags: u32, big_endian: bool) -> bool {
+    match flags & EF_PPC64_ABI {
+        1 => true,
+        2 => false,
+        _ => big_endian,
+    }
+}
+
+/// Bytes between a function's global and local entry points, from its st_other.
+/// 0 means the two coincide; 1 means the function does not need r2 at all.
+fn local_entry_offset(st_other: u8) -> Option<u64> {
+    match (st_other & STO_PPC64_LOCAL_MASK) >> STO_PPC64_LOCAL_SHIFT {
+        0 | 1 | 7 => None,
+        value => Some(1 << value),
+    }
+}
+
+pub struct Ppc64Info {
+    pub elfv1: bool,
+    /// The value r2 holds: `.TOC.` if the symbol is defined, else `.got` plus the bias.
+    pub toc_base: Option<u64>,
+    pub descriptors: u64,
+    /// With ELFv1, the code address and TOC found in the descriptor e_entry points to.
+    pub entry_descriptor: Option<(u64, u64)>,
+    /// With ELFv2, functions whose local entry point sits past the global one.
+    pub local_entries: Vec<(String, u64)>,
+    pub functions: usize,
+    pub dynamic: Vec<(String, String)>,
+}
+
+impl Loader {
+    pub fn ppc64_info(&self) -> Ppc64Info {
+        let flags = self.raw_e_flags();
+        let elfv1 = is_elfv1(flags, self.is_big_endian());
+        let mut symbols = self.static_symbols();
+        if symbols.is_empty() {
+            symbols = self.dynamic_symbols();
+        }
+        let toc_base = symbols
+            .iter()
+            .find(|symbol| symbol.name == ".TOC." && symbol.st_shndx != SHN_UNDEF)
+            .map(|symbol| symbol.st_value)
+            .or_else(|| {
+                self.section_by_name(".got")
+                    .map(|got| got.sh_addr + TOC_BIAS)
+            });
+        let opd = self.section_by_name(".opd");
+        let descriptors = opd
+            .as_ref()
+            .map_or(0, |opd| opd.sh_size / FUNCTION_DESCRIPTOR_SIZE);
+        let entry = self.raw_e_entry();
+        let entry_descriptor = match &opd {
+            Some(opd) if elfv1 && entry != 0 && opd.contains_addr(entry) => {
+                let offset = (opd.sh_offset + entry - opd.sh_addr) as usize;
+                self.read_u64(offset).zip(self.read_u64(offset + 8))
+            }
+            _ => None,
+        };
+        let functions: Vec<_> = symbols
+            .iter()
+            .filter(|symbol| symbol.kind() == STT_FUNC)
+            .collect();
+        let local_entries = functions
+            .iter()
+            .filter_map(|symbol| {
+                local_entry_offset(symbol.st_other).map(|offset| (symbol.name.clone(), offset))
+            })
+            .collect();
+
+        let mut dynamic = Vec::new();
+        for entry in self.dynamic_entries() {
+            let name = match entry.d_tag {
+                DT_PPC64_GLINK => "DT_PPC64_GLINK",
+                DT_PPC64_OPD => "DT_PPC64_OPD",
+                DT_PPC64_OPDSZ => "DT_PPC64_OPDSZ",
+                DT_PPC64_OPT => "DT_PPC64_OPT",
+                _ => continue,
+            };
+            dynamic.push((name.to_string(), format!("{:#x}", entry.d_val)));
+        }
+        Ppc64Info {
+            elfv1,
+            toc_base,
+            descriptors,
+            entry_descriptor,
+            local_entries,
+            functions: functions.len(),
+            dynamic,
+        }
+    }
+}
+
+pub struct Ppc64;
+
+impl ArchExt for Ppc64 {
+    fn machines(&self) -> &'static [u16] {
+        &[EM_PPC64]
+    }
+
+    fn describe_flags(&self, loader: &Loader, flags: u32) -> Vec<String> {
+        vec![abi_version(flags, loader.is_big_endian()).to_string()]
+    }
+
+    /// The NOP is `ori 0, 0, 0`, stored in the file's byte order.
+    fn text_model(&self, loader: &Loader) -> Option<TextModel> {
+        Some(TextModel {
+            average_length: 4.0,
+            alignment: 4,
+            padding: if loader.is_big_endian() {
+                &[&[0x60, 0x00, 0x00, 0x00]]
+            } else {
+                &[&[0x00, 0x00, 0x00, 0x60]]
+            },
+        })
+    }
+
+    fn display_details(&self, loader: &Loader, _flags: u32, _attributes: &[(u64, AttributeValue)]) {
+        let info = loader.ppc64_info();
+        match info.toc_base {
+            Some(toc) => display_line("TOC Base (r2)", &format!("{:#x}", toc)),
+            None => display_line("TOC Base (r2)", "Not found"),
+        }
+        if info.elfv1 {
+            display_line("Function Descriptors (.opd)", &info.descriptors.to_string());
+            if let Some((code, toc)) = info.entry_descriptor {
+                display_line(
+                    "Entry Point Descriptor",
+                    &format!("code {:#x}, TOC {:#x}", code, toc),
+                );
+            }
+        } else {
+            display_line(
+                "Functions With Local Entry Points",
+                &format!("{} of {}", info.local_entries.len(), info.functions),
+            );
+            for (name, offset) in &info.local_entries {
+                output::item(1, &format!("{:<32} global + {}", name, offset));
+            }
+        }
+        for (name, value) in &info.dynamic {
+            display_line(name, value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+
+    use super::{abi_version, is_elfv1, local_entry_offset};
+    use crate::Loader;
+
+    /// Without an ABI in e_flags the byte order decides, as the linkers do.
+    #[test]
+    fn abi_from_flags_and_byte_order() {
+        assert_eq!(abi_version(1, false), "ELFv1 (function descriptors)");
+        assert_eq!(
+            abi_version(2, true),
+            "ELFv2 (direct entry, local entry points)"
+        );
+        assert!(is_elfv1(0, true));
+        assert!(!is_elfv1(0, false));
+        assert_eq!(
+            abi_version(0, false),
+            "unspecified, ELFv2 assumed for little-endian"
+        );
+    }
+
+    #[test]
+    fn local_entry_offsets() {
+        assert_eq!(local_entry_offset(0), None);
+        // 1: no r2 needed; 7: reserved.
+        assert_eq!(local_entry_offset(1 << 5), None);
+        assert_eq!(local_entry_offset(7 << 5), None);
+        assert_eq!(local_entry_offset(3 << 5), Some(8));
+        assert_eq!(local_entry_offset(6 << 5), Some(64));
+    }
+
+    #[test]
+    fn elfv1_object_has_a_descriptor() {
+        let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/ppc64.o");
+        let info = Loader::from_bytes(fs::read(fixture).unwrap()).ppc64_info();
+        assert!(info.elfv1);
+        assert_eq!(info.descriptors, 1);
+        assert!(info.local_entries.is_empty());
+    }
+}
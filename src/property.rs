@@ -0,0 +1,87 @@
+use crate::arch::arch_ext;
+use crate::note::{hex_string, NT_GNU_PROPERTY_TYPE_0};
+use crate::Loader;
+
+const GNU_PROPERTY_STACK_SIZE: u32 = 1;
+const GNU_PROPERTY_NO_COPY_ON_PROTECTED: u32 = 2;
+const GNU_PROPERTY_1_NEEDED: u32 = 0xb000_8000;
+/// Property types from here up are processor-specific.
+const GNU_PROPERTY_LOPROC: u32 = 0xc000_0000;
+
+const GNU_PROPERTY_1_NEEDED_INDIRECT_EXTERN_ACCESS: u32 = 1;
+
+/// One entry of an NT_GNU_PROPERTY_TYPE_0 note.
+pub struct Property {
+    pub pr_type: u32,
+    pub data: Vec<u8>,
+}
+
+impl Loader {
+    /// The properties of an NT_GNU_PROPERTY_TYPE_0 descriptor. Each is a type, a
+    /// size and the data, padded to the address size.
+    pub fn properties_in(&self, desc: &[u8]) -> Vec<Property> {
+        let align = if self.is_64bit() { 8 } else { 4 };
+        let mut properties = Vec::new();
+        let mut offset = 0;
+        while let (Some(pr_type), Some(size)) =
+            (self.desc_u32(desc, offset), self.desc_u32(desc, offset + 4))
+        {
+            let start = offset + 8;
+            let Some(data) = desc.get(start..start + size as usize) else {
+                break;
+            };
+            properties.push(Property {
+                pr_type,
+                data: data.to_vec(),
+            });
+            offset = (start + size as usize).div_ceil(align) * align;
+        }
+        properties
+    }
+
+    /// The GNU properties of the file: ISA levels, CET and BTI markings and so on.
+    pub fn gnu_properties(&self) -> Vec<Property> {
+        match self.find_note("GNU", NT_GNU_PROPERTY_TYPE_0) {
+            Some(note) => self.properties_in(&note.desc),
+            None => Vec::new(),
+        }
+    }
+
+    /// The 32-bit value of the property of type `pr_type`, if the file has one.
+    pub fn property_u32(&self, pr_type: u32) -> Option<u32> {
+        self.gnu_properties()
+            .into_iter()
+            .find(|property| property.pr_type == pr_type)
+            .and_then(|property| self.desc_u32(&property.data, 0))
+    }
+
+    /// Renders a property the way readelf does, e.g. `x86 ISA needed: x86-64-v2`.
+    pub fn describe_property(&self, property: &Property) -> String {
+        let value = self.desc_u32(&property.data, 0);
+        let described = match (property.pr_type, value) {
+            (GNU_PROPERTY_STACK_SIZE, _) => self
+                .desc_word(&property.data, 0)
+                .map(|size| format!("stack size: {:#x}", size)),
+            (GNU_PROPERTY_NO_COPY_ON_PROTECTED, _) => Some("no copy on protected".to_string()),
+            (GNU_PROPERTY_1_NEEDED, Some(value)) => Some(
+                if value & GNU_PROPERTY_1_NEEDED_INDIRECT_EXTERN_ACCESS != 0 {
+                    "1_needed: indirect external access".to_string()
+                } else {
+                    format!("1_needed: {:#x}", value)
+                },
+            ),
+            (pr_type, Some(value)) if pr_type >= GNU_PROPERTY_LOPROC => {
+                arch_ext(self.raw_e_machine())
+                    .and_then(|arch| arch.describe_property(pr_type, value))
+            }
+            _ => None,
+        };
+        described.unwrap_or_else(|| {
+            format!(
+                "type {:#x}: {}",
+                property.pr_type,
+                hex_string(&property.data)
+            )
+        })
+    }
+}
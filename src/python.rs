@@ -0,0 +1,229 @@
+//! Python module `elf_header_parser`, built with the `python` feature (see
+//! pyproject.toml for building a wheel with maturin).
+
+use pyo3::exceptions::{PyOSError, PyValueError};
+use pyo3::prelude::*;
+
+use crate::section::SectionHeader;
+use crate::symbol::Symbol as ElfSymbol;
+use crate::Loader;
+
+/// One section header.
+#[pyclass(frozen, get_all, module = "elf_header_parser")]
+pub struct Section {
+    index: usize,
+    name: String,
+    sh_type: u32,
+    sh_flags: u64,
+    sh_addr: u64,
+    sh_offset: u64,
+    sh_size: u64,
+    sh_link: u32,
+    sh_info: u32,
+    sh_addralign: u64,
+    sh_entsize: u64,
+}
+
+#[pymethods]
+impl Section {
+    fn __repr__(&self) -> String {
+        format!(
+            "Section({}, {:?}, offset={:#x}, size={:#x})",
+            self.index, self.name, self.sh_offset, self.sh_size
+        )
+    }
+}
+
+impl From<SectionHeader> for Section {
+    fn from(section: SectionHeader) -> Section {
+        Section {
+            index: section.index,
+            name: section.name,
+            sh_type: section.sh_type,
+            sh_flags: section.sh_flags,
+            sh_addr: section.sh_addr,
+            sh_offset: section.sh_offset,
+            sh_size: section.sh_size,
+            sh_link: section.sh_link,
+            sh_info: section.sh_info,
+            sh_addralign: section.sh_addralign,
+            sh_entsize: section.sh_entsize,
+        }
+    }
+}
+
+/// One symbol table entry.
+#[pyclass(frozen, get_all, module = "elf_header_parser")]
+pub struct Symbol {
+    name: String,
+    st_value: u64,
+    st_size: u64,
+    st_info: u8,
+    st_other: u8,
+    st_shndx: u16,
+}
+
+#[pymethods]
+impl Symbol {
+    /// STB_* binding from st_info.
+    #[getter]
+    fn bind(&self) -> u8 {
+        self.st_info >> 4
+    }
+
+    /// STT_* type from st_info.
+    #[getter]
+    fn kind(&self) -> u8 {
+        self.st_info & 0xf
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Symbol({:?}, value={:#x})", self.name, self.st_value)
+    }
+}
+
+impl From<ElfSymbol> for Symbol {
+    fn from(symbol: ElfSymbol) -> Symbol {
+        Symbol {
+            name: symbol.name,
+            st_value: symbol.st_value,
+            st_size: symbol.st_size,
+            st_info: symbol.st_info,
+            st_other: symbol.st_other,
+            st_shndx: symbol.st_shndx,
+        }
+    }
+}
+
+/// A mapped ELF file. Raises OSError if it cannot be read and ValueError if it
+/// is not ELF or its header is truncated.
+#[pyclass(frozen, module = "elf_header_parser")]
+pub struct ElfFile {
+    #[pyo3(get)]
+    path: String,
+    loader: Loader,
+}
+
+#[pymethods]
+impl ElfFile {
+    #[new]
+    fn new(path: String) -> PyResult<ElfFile> {
+        let loader = Loader::open(&path)
+            .map_err(|error| PyOSError::new_err(format!("{}: {}", path, error)))?;
+        if !loader.is_elf() {
+            return Err(PyValueError::new_err(format!(
+                "{} is not an ELF file",
+                path
+            )));
+        }
+        if let Some(problem) = loader.header_truncation() {
+            return Err(PyValueError::new_err(format!("{}: {}", path, problem)));
+        }
+        Ok(ElfFile { path, loader })
+    }
+
+    /// 32 or 64.
+    #[getter]
+    fn bits(&self) -> u8 {
+        if self.loader.is_64bit() {
+            64
+        } else {
+            32
+        }
+    }
+
+    /// "little" or "big", as for int.from_bytes.
+    #[getter]
+    fn byteorder(&self) -> &'static str {
+        if self.loader.is_big_endian() {
+            "big"
+        } else {
+            "little"
+        }
+    }
+
+    #[getter]
+    fn e_type(&self) -> u16 {
+        self.loader.raw_e_type()
+    }
+
+    #[getter]
+    fn e_machine(&self) -> u16 {
+        self.loader.raw_e_machine()
+    }
+
+    /// The e_machine name the command line tool prints, such as "AArch64".
+    #[getter]
+    fn machine_name(&self) -> String {
+        self.loader.machine_name()
+    }
+
+    #[getter]
+    fn e_entry(&self) -> u64 {
+        self.loader.get_e_entry()
+    }
+
+    #[getter]
+    fn e_flags(&self) -> u32 {
+        self.loader.get_e_flags()
+    }
+
+    #[getter]
+    fn e_phnum(&self) -> u32 {
+        self.loader.get_e_phnum()
+    }
+
+    #[getter]
+    fn e_shnum(&self) -> u32 {
+        self.loader.get_e_shnum()
+    }
+
+    /// DT_SONAME of a shared library, or None.
+    #[getter]
+    fn soname(&self) -> Option<String> {
+        self.loader.soname()
+    }
+
+    /// DT_NEEDED entries in order.
+    #[getter]
+    fn needed(&self) -> Vec<String> {
+        self.loader.needed_libraries()
+    }
+
+    /// All section headers, section 0 included.
+    fn sections(&self) -> Vec<Section> {
+        self.loader
+            .section_headers()
+            .into_iter()
+            .map(Section::from)
+            .collect()
+    }
+
+    /// The first section called `name`, or None.
+    fn section(&self, name: &str) -> Option<Section> {
+        self.loader.section_by_name(name).map(Section::from)
+    }
+
+    /// `.symtab`, or `.dynsym` with `dynamic=True`; empty if the table is missing.
+    #[pyo3(signature = (dynamic = false))]
+    fn symbols(&self, dynamic: bool) -> Vec<Symbol> {
+        let symbols = if dynamic {
+            self.loader.dynamic_symbols()
+        } else {
+            self.loader.static_symbols()
+        };
+        symbols.into_iter().map(Symbol::from).collect()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("ElfFile({:?})", self.path)
+    }
+}
+
+#[pymodule]
+fn elf_header_parser(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<ElfFile>()?;
+    module.add_class::<Section>()?;
+    module.add_class::<Symbol>()?;
+    Ok(())
+}
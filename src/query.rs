@@ -0,0 +1,605 @@
+//! `--query`: a small jq-like language over the document of
+//! [`parsed_elf`], for pulling a few values out of files without piping JSON
+//! through jq.
+//!
+//! A query is filters joined by `|`, each turning every value it is given
+//! into zero or more values:
+//!
+//! - `.`, `.name`, `."name"`, `.[N]` and `.[]`, also chained as `.sections[].name`
+//! - `select(COND)`, which passes its input on where `COND` holds
+//! - comparisons with `==`, `!=`, `<`, `<=`, `>`, `>=` and `contains`,
+//!   joined by `and` and `or`. Numbers compare with strings that spell a
+//!   number, such as `"0x6"`, and a number contains the bits of a number or
+//!   of `sh_flags` letters, such as `"AX"`
+//! - `length`, `keys` and `not`
+//! - string and number literals, `true`, `false` and `null`, and parentheses
+//!
+//! For example `.sections[] | select(.flags contains "X") | .name`.
+
+use std::cmp::Ordering;
+
+use serde_json::{Number, Value};
+
+use crate::model::{flag_bits, parsed_elf};
+use crate::{output, Loader};
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    /// `.` on its own, the identity or the start of `.[...]`.
+    Dot,
+    /// `.name` or `."name"`.
+    Field(String),
+    Word(String),
+    Text(String),
+    Number(Number),
+    Operator(&'static str),
+    Open,
+    Close,
+    OpenBracket,
+    CloseBracket,
+    Pipe,
+}
+
+#[derive(Clone, Copy)]
+enum Operator {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Contains,
+}
+
+enum Expr {
+    Identity,
+    Literal(Value),
+    Field(String),
+    Index(i64),
+    Iterate,
+    Pipe(Box<Expr>, Box<Expr>),
+    Select(Box<Expr>),
+    Compare(Box<Expr>, Operator, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Length,
+    Keys,
+    Not,
+}
+
+/// The operators made of symbols, longest first so `<=` is not read as `<`.
+const OPERATORS: &[&str] = &["==", "!=", "<=", ">=", "<", ">"];
+
+fn is_word_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_'
+}
+
+fn is_word(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// Reads a `"..."` literal starting at `chars[at]`, returning it and the
+/// index after the closing quote.
+fn string_literal(chars: &[char], mut at: usize) -> Result<(String, usize), String> {
+    let mut text = String::new();
+    at += 1;
+    loop {
+        match chars.get(at) {
+            None => return Err("unterminated string".to_string()),
+            Some('"') => return Ok((text, at + 1)),
+            Some('\\') => {
+                match chars.get(at + 1) {
+                    Some('n') => text.push('\n'),
+                    Some('t') => text.push('\t'),
+                    Some(&c @ ('"' | '\\')) => text.push(c),
+                    _ => return Err("unknown escape in string".to_string()),
+                }
+                at += 2;
+            }
+            Some(&c) => {
+                text.push(c);
+                at += 1;
+            }
+        }
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut at = 0;
+    while let Some(&c) = chars.get(at) {
+        let rest: String = chars[at..].iter().take(2).collect();
+        if c.is_whitespace() {
+            at += 1;
+        } else if c == '.' {
+            match chars.get(at + 1) {
+                Some(&next) if is_word_start(next) => {
+                    let end = (at + 1..chars.len())
+                        .find(|&end| !is_word(chars[end]))
+                        .unwrap_or(chars.len());
+                    tokens.push(Token::Field(chars[at + 1..end].iter().collect()));
+                    at = end;
+                }
+                Some('"') => {
+                    let (name, end) = string_literal(&chars, at + 1)?;
+                    tokens.push(Token::Field(name));
+                    at = end;
+                }
+                _ => {
+                    tokens.push(Token::Dot);
+                    at += 1;
+                }
+            }
+        } else if c == '"' {
+            let (text, end) = string_literal(&chars, at)?;
+            tokens.push(Token::Text(text));
+            at = end;
+        } else if c.is_ascii_digit()
+            || (c == '-' && chars.get(at + 1).is_some_and(char::is_ascii_digit))
+        {
+            let end = (at + 1..chars.len())
+                .find(|&end| !(chars[end].is_ascii_digit() || chars[end] == '.'))
+                .unwrap_or(chars.len());
+            let text: String = chars[at..end].iter().collect();
+            let number = serde_json::from_str::<Number>(&text)
+                .map_err(|_| format!("invalid number: {}", text))?;
+            tokens.push(Token::Number(number));
+            at = end;
+        } else if is_word_start(c) {
+            let end = (at..chars.len())
+                .find(|&end| !is_word(chars[end]))
+                .unwrap_or(chars.len());
+            tokens.push(Token::Word(chars[at..end].iter().collect()));
+            at = end;
+        } else if let Some(operator) = OPERATORS
+            .iter()
+            .find(|operator| rest.starts_with(**operator))
+        {
+            tokens.push(Token::Operator(operator));
+            at += operator.len();
+        } else {
+            tokens.push(match c {
+                '(' => Token::Open,
+                ')' => Token::Close,
+                '[' => Token::OpenBracket,
+                ']' => Token::CloseBracket,
+                '|' => Token::Pipe,
+                _ => return Err(format!("unexpected character: {}", c)),
+            });
+            at += 1;
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    at: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.at)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.at).cloned();
+        self.at += 1;
+        token
+    }
+
+    fn expect(&mut self, token: Token, what: &str) -> Result<(), String> {
+        match self.next() {
+            Some(next) if next == token => Ok(()),
+            _ => Err(format!("expected {}", what)),
+        }
+    }
+
+    fn word(&mut self, word: &str) -> bool {
+        if self.peek() == Some(&Token::Word(word.to_string())) {
+            self.at += 1;
+            return true;
+        }
+        false
+    }
+
+    fn pipeline(&mut self) -> Result<Expr, String> {
+        let mut expr = self.or()?;
+        while self.peek() == Some(&Token::Pipe) {
+            self.at += 1;
+            expr = Expr::Pipe(Box::new(expr), Box::new(self.or()?));
+        }
+        Ok(expr)
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut expr = self.and()?;
+        while self.word("or") {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut expr = self.comparison()?;
+        while self.word("and") {
+            expr = Expr::And(Box::new(expr), Box::new(self.comparison()?));
+        }
+        Ok(expr)
+    }
+
+    fn comparison(&mut self) -> Result<Expr, String> {
+        let left = self.postfix()?;
+        let operator = match self.peek() {
+            Some(Token::Operator("==")) => Operator::Equal,
+            Some(Token::Operator("!=")) => Operator::NotEqual,
+            Some(Token::Operator("<")) => Operator::Less,
+            Some(Token::Operator("<=")) => Operator::LessOrEqual,
+            Some(Token::Operator(">")) => Operator::Greater,
+            Some(Token::Operator(">=")) => Operator::GreaterOrEqual,
+            Some(Token::Word(word)) if word == "contains" => Operator::Contains,
+            _ => return Ok(left),
+        };
+        self.at += 1;
+        Ok(Expr::Compare(
+            Box::new(left),
+            operator,
+            Box::new(self.postfix()?),
+        ))
+    }
+
+    /// A term followed by any `.name`, `[N]` and `[]` applied to it.
+    fn postfix(&mut self) -> Result<Expr, String> {
+        let mut expr = self.term()?;
+        loop {
+            let next = match self.peek() {
+                Some(Token::Field(name)) => {
+                    let name = name.clone();
+                    self.at += 1;
+                    Expr::Field(name)
+                }
+                Some(Token::OpenBracket) => {
+                    self.at += 1;
+                    match self.next() {
+                        Some(Token::CloseBracket) => Expr::Iterate,
+                        Some(Token::Number(number)) => {
+                            self.expect(Token::CloseBracket, "]")?;
+                            Expr::Index(number.as_i64().ok_or("indexes are whole numbers")?)
+                        }
+                        Some(Token::Text(name)) => {
+                            self.expect(Token::CloseBracket, "]")?;
+                            Expr::Field(name)
+                        }
+                        _ => return Err("expected ], a number or a string after [".to_string()),
+                    }
+                }
+                _ => return Ok(expr),
+            };
+            expr = Expr::Pipe(Box::new(expr), Box::new(next));
+        }
+    }
+
+    fn term(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Some(Token::Dot) => Ok(Expr::Identity),
+            Some(Token::Field(name)) => Ok(Expr::Field(name)),
+            Some(Token::Text(text)) => Ok(Expr::Literal(Value::String(text))),
+            Some(Token::Number(number)) => Ok(Expr::Literal(Value::Number(number))),
+            Some(Token::Open) => {
+                let expr = self.pipeline()?;
+                self.expect(Token::Close, ")")?;
+                Ok(expr)
+            }
+            Some(Token::Word(word)) => match word.as_str() {
+                "true" => Ok(Expr::Literal(Value::Bool(true))),
+                "false" => Ok(Expr::Literal(Value::Bool(false))),
+                "null" => Ok(Expr::Literal(Value::Null)),
+                "length" => Ok(Expr::Length),
+                "keys" => Ok(Expr::Keys),
+                "not" => Ok(Expr::Not),
+                "select" => {
+                    self.expect(Token::Open, "( after select")?;
+                    let condition = self.pipeline()?;
+                    self.expect(Token::Close, ")")?;
+                    Ok(Expr::Select(Box::new(condition)))
+                }
+                _ => Err(format!("unknown function: {}", word)),
+            },
+            Some(token) => Err(format!("unexpected {:?}", token)),
+            None => Err("unexpected end of query".to_string()),
+        }
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn truthy(value: &Value) -> bool {
+    !matches!(value, Value::Null | Value::Bool(false))
+}
+
+/// The number `value` is or spells, in decimal or with `0x` in hexadecimal.
+fn number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(number) => number.as_f64(),
+        Value::String(text) => match text.strip_prefix("0x") {
+            Some(hex) => u64::from_str_radix(hex, 16).ok().map(|n| n as f64),
+            None => text.parse().ok(),
+        },
+        _ => None,
+    }
+}
+
+/// Numbers compared with each other or with a string that spells one.
+fn numbers(left: &Value, right: &Value) -> Option<(f64, f64)> {
+    if !(left.is_number() || right.is_number()) {
+        return None;
+    }
+    Some((number(left)?, number(right)?))
+}
+
+fn order(left: &Value, right: &Value) -> Result<Ordering, String> {
+    if let Some((left, right)) = numbers(left, right) {
+        return Ok(left.partial_cmp(&right).unwrap_or(Ordering::Equal));
+    }
+    match (left, right) {
+        (Value::String(left), Value::String(right)) => Ok(left.cmp(right)),
+        _ => Err(format!(
+            "cannot compare {} with {}",
+            type_name(left),
+            type_name(right)
+        )),
+    }
+}
+
+fn equal(left: &Value, right: &Value) -> bool {
+    match numbers(left, right) {
+        Some((left, right)) => left == right,
+        None => left == right,
+    }
+}
+
+/// The bits a number on the right of `contains` stands for: its own, those of
+/// a string that spells a number, or those of `sh_flags` letters.
+fn bits(value: &Value) -> Option<u64> {
+    match value {
+        Value::Number(number) => number.as_u64(),
+        Value::String(text) => match number(value) {
+            Some(number) if number >= 0.0 && number.fract() == 0.0 => Some(number as u64),
+            _ => flag_bits(text),
+        },
+        _ => None,
+    }
+}
+
+fn compare(left: &Value, operator: Operator, right: &Value) -> Result<bool, String> {
+    Ok(match operator {
+        Operator::Equal => equal(left, right),
+        Operator::NotEqual => !equal(left, right),
+        Operator::Less => order(left, right)? == Ordering::Less,
+        Operator::LessOrEqual => order(left, right)? != Ordering::Greater,
+        Operator::Greater => order(left, right)? == Ordering::Greater,
+        Operator::GreaterOrEqual => order(left, right)? != Ordering::Less,
+        Operator::Contains => contains(left, right).ok_or_else(|| {
+            format!(
+                "cannot check whether {} contains {}",
+                type_name(left),
+                type_name(right)
+            )
+        })?,
+    })
+}
+
+fn contains(left: &Value, right: &Value) -> Option<bool> {
+    Some(match (left, right) {
+        (Value::String(left), Value::String(right)) => left.contains(right.as_str()),
+        (Value::Array(items), _) => items.iter().any(|item| equal(item, right)),
+        (Value::Object(members), Value::String(key)) => members.contains_key(key),
+        (Value::Number(number), _) => {
+            let (number, bits) = number.as_u64().zip(bits(right))?;
+            number & bits == bits
+        }
+        _ => return None,
+    })
+}
+
+fn evaluate(expr: &Expr, input: &Value) -> Result<Vec<Value>, String> {
+    Ok(match expr {
+        Expr::Identity => vec![input.clone()],
+        Expr::Literal(value) => vec![value.clone()],
+        Expr::Field(name) => match input {
+            Value::Object(members) => vec![members.get(name).cloned().unwrap_or(Value::Null)],
+            Value::Null => vec![Value::Null],
+            _ => return Err(format!("cannot take .{} of {}", name, type_name(input))),
+        },
+        Expr::Index(index) => match input {
+            Value::Array(items) => {
+                let at = if *index < 0 {
+                    items.len().checked_sub(index.unsigned_abs() as usize)
+                } else {
+                    Some(*index as usize)
+                };
+                vec![at
+                    .and_then(|at| items.get(at))
+                    .cloned()
+                    .unwrap_or(Value::Null)]
+            }
+            Value::Null => vec![Value::Null],
+            _ => return Err(format!("cannot index {} with a number", type_name(input))),
+        },
+        Expr::Iterate => match input {
+            Value::Array(items) => items.clone(),
+            Value::Object(members) => members.values().cloned().collect(),
+            _ => return Err(format!("cannot iterate over {}", type_name(input))),
+        },
+        Expr::Pipe(left, right) => {
+            let mut values = Vec::new();
+            for value in evaluate(left, input)? {
+                values.extend(evaluate(right, &value)?);
+            }
+            values
+        }
+        Expr::Select(condition) => {
+            let mut values = Vec::new();
+            for value in evaluate(condition, input)? {
+                if truthy(&value) {
+                    values.push(input.clone());
+                }
+            }
+            values
+        }
+        Expr::Compare(left, operator, right) => {
+            let mut values = Vec::new();
+            for right in evaluate(right, input)? {
+                for left in evaluate(left, input)? {
+                    values.push(Value::Bool(compare(&left, *operator, &right)?));
+                }
+            }
+            values
+        }
+        Expr::And(left, right) | Expr::Or(left, right) => {
+            let or = matches!(expr, Expr::Or(..));
+            let mut values = Vec::new();
+            for left in evaluate(left, input)? {
+                if truthy(&left) == or {
+                    values.push(Value::Bool(or));
+                    continue;
+                }
+                for right in evaluate(right, input)? {
+                    values.push(Value::Bool(truthy(&right)));
+                }
+            }
+            values
+        }
+        Expr::Length => vec![match input {
+            Value::Null => Value::from(0),
+            Value::String(text) => Value::from(text.chars().count()),
+            Value::Array(items) => Value::from(items.len()),
+            Value::Object(members) => Value::from(members.len()),
+            Value::Number(number) => Value::from(number.as_f64().map_or(0.0, f64::abs)),
+            Value::Bool(_) => return Err("boolean has no length".to_string()),
+        }],
+        Expr::Keys => vec![match input {
+            Value::Object(members) => {
+                let mut keys: Vec<&String> = members.keys().collect();
+                keys.sort();
+                Value::from(keys.into_iter().cloned().collect::<Vec<String>>())
+            }
+            Value::Array(items) => Value::from((0..items.len()).collect::<Vec<usize>>()),
+            _ => return Err(format!("{} has no keys", type_name(input))),
+        }],
+        Expr::Not => vec![Value::Bool(!truthy(input))],
+    })
+}
+
+/// A parsed `--query`.
+pub struct Query {
+    expr: Expr,
+}
+
+impl Query {
+    pub fn parse(source: &str) -> Result<Query, String> {
+        let error = |message: String| format!("invalid query: {}", message);
+        let mut parser = Parser {
+            tokens: tokenize(source).map_err(error)?,
+            at: 0,
+        };
+        let expr = parser.pipeline().map_err(error)?;
+        if let Some(token) = parser.peek() {
+            return Err(error(format!("unexpected {:?}", token)));
+        }
+        Ok(Query { expr })
+    }
+
+    pub fn run(&self, document: &Value) -> Result<Vec<Value>, String> {
+        evaluate(&self.expr, document)
+    }
+}
+
+/// `--query`: the values the query gives for each target, one per line;
+/// strings are printed as they are and everything else as JSON. Files the
+/// query fails on are returned.
+pub fn display_query(targets: &[(String, Loader)], query: &Query) -> Vec<(String, String)> {
+    let mut failures = Vec::new();
+    for (label, loader) in targets {
+        match query.run(&parsed_elf(label, loader)) {
+            Ok(values) => {
+                output::begin(label);
+                for value in values {
+                    match value {
+                        Value::String(text) => output::item(0, &text),
+                        value => output::item(0, &value.to_string()),
+                    }
+                }
+                output::end();
+            }
+            Err(error) => failures.push((label.clone(), error)),
+        }
+    }
+    failures
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use serde_json::{json, Value};
+
+    use super::Query;
+    use crate::model::parsed_elf;
+    use crate::Loader;
+
+    fn run(query: &str) -> Result<Vec<Value>, String> {
+        let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/x86_64.o");
+        let label = fixture.display().to_string();
+        let loader = Loader::open(&label).unwrap();
+        Query::parse(query)?.run(&parsed_elf(&label, &loader))
+    }
+
+    #[test]
+    fn flags_contain_their_letters() {
+        let names = run(r#".sections[] | select(.flags contains "X") | .name"#).unwrap();
+        assert_eq!(names, [json!(".text"), json!(".text.inline_fn")]);
+        let letters = run(r#".sections[] | select(.flag_letters contains "X") | .name"#);
+        assert_eq!(letters.unwrap(), names);
+        let bits = run(".sections[] | select(.flags contains 6) | .name").unwrap();
+        assert_eq!(bits, names);
+    }
+
+    #[test]
+    fn numbers_compare_with_strings_that_spell_them() {
+        let names = run(r#".sections[] | select(.type == "0x1" and .size > 0) | .name"#).unwrap();
+        assert!(names.contains(&json!(".text")));
+        assert_eq!(run(".sections | length > 1").unwrap(), [json!(true)]);
+    }
+
+    #[test]
+    fn query_errors() {
+        for (query, error) in [
+            (r#".name == "x"#, "unterminated string"),
+            (".sections[] | frobnicate", "unknown function: frobnicate"),
+            (".sections[1.5]", "indexes are whole numbers"),
+            ("select(.name", "expected )"),
+            (". )", "unexpected Close"),
+            (".a ~ 1", "unexpected character: ~"),
+        ] {
+            let message = Query::parse(query).err().unwrap();
+            assert_eq!(message, format!("invalid query: {}", error), "{}", query);
+        }
+        let error = run(r#".entry contains "Q""#).unwrap_err();
+        assert_eq!(error, "cannot check whether number contains string");
+        assert_eq!(
+            run(".sections[0].name[]").unwrap_err(),
+            "cannot iterate over string"
+        );
+    }
+}
@@ -0,0 +1,176 @@
+use log::{debug, info};
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::ops::Range;
+
+use crate::Loader;
+
+/// The largest ELF header (ELF64).
+pub const HEADER_LEN: usize = 64;
+
+/// Rounds of table reads: section 0 can change both counts once, so the tables
+/// are settled by the third.
+const MAX_ROUNDS: usize = 4;
+
+/// The pieces read so far laid out at their file offsets. The gaps stay zero;
+/// `vec![0; n]` only asks the allocator for zeroed pages, so untouched parts of a
+/// large file cost address space rather than memory.
+fn assemble(pieces: &[(usize, Vec<u8>)]) -> Vec<u8> {
+    let len = pieces
+        .iter()
+        .map(|(start, bytes)| start + bytes.len())
+        .max()
+        .unwrap_or(0);
+    let mut image = vec![0; len];
+    for (start, bytes) in pieces {
+        image[*start..start + bytes.len()].copy_from_slice(bytes);
+    }
+    image
+}
+
+/// Builds an image holding only the ELF header and the program and section header
+/// tables, fetched through `read_at`, which returns the bytes of a range or fewer
+/// at the end of the file. This is all the header report needs, so network
+/// filesystems and large debug binaries are read a few kilobytes at a time.
+/// A first read that returns more than asked for is taken as the whole file.
+pub fn read_headers(
+    label: &str,
+    mut read_at: impl FnMut(Range<usize>) -> io::Result<Vec<u8>>,
+) -> io::Result<Loader> {
+    let header = read_at(0..HEADER_LEN)?;
+    if header.len() > HEADER_LEN {
+        return Ok(Loader::from_bytes(header));
+    }
+    let mut pieces = vec![(0, header)];
+    let mut requested = Vec::new();
+    let mut loader = Loader::from_bytes(assemble(&pieces));
+    for _ in 0..MAX_ROUNDS {
+        if loader.header_truncation().is_some() {
+            break;
+        }
+        let wanted: Vec<Range<usize>> = [loader.program_table(), loader.section_table()]
+            .into_iter()
+            .filter(|table| !table.is_empty() && !requested.contains(table))
+            .collect();
+        if wanted.is_empty() {
+            break;
+        }
+        for table in wanted {
+            requested.push(table.clone());
+            // A table past the end of the file is the report's to point out.
+            match read_at(table.clone()) {
+                Ok(bytes) if !bytes.is_empty() => pieces.push((table.start, bytes)),
+                Ok(_) => debug!("{}: {:#x?} is past the end of the file", label, table),
+                Err(error) => debug!("{}: cannot read {:#x?}: {}", label, table, error),
+            }
+        }
+        loader = Loader::from_bytes(assemble(&pieces));
+    }
+    info!(
+        "{}: read {} bytes of headers",
+        label,
+        pieces.iter().map(|(_, bytes)| bytes.len()).sum::<usize>()
+    );
+    Ok(loader)
+}
+
+/// The bytes of `range` in `file`, fewer at the end of the file.
+pub fn read_range(file: &mut File, range: Range<usize>) -> io::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    file.seek(SeekFrom::Start(range.start as u64))?;
+    file.take(range.len() as u64).read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// `read_headers` over a local file.
+pub fn open_headers(path: &str) -> io::Result<Loader> {
+    let mut file = File::open(path)?;
+    read_headers(path, |range| read_range(&mut file, range))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::ops::Range;
+    use std::path::Path;
+
+    use super::{read_headers, HEADER_LEN};
+    use crate::Loader;
+
+    fn fixture(name: &str) -> Vec<u8> {
+        fs::read(
+            Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("tests/fixtures")
+                .join(name),
+        )
+        .unwrap()
+    }
+
+    /// Reads the headers of `bytes` and returns the loader with the ranges asked for.
+    fn read_recorded(bytes: &[u8]) -> (Loader, Vec<Range<usize>>) {
+        let mut requested = Vec::new();
+        let loader = read_headers("test", |range| {
+            requested.push(range.clone());
+            let end = range.end.min(bytes.len());
+            Ok(bytes[range.start.min(end)..end].to_vec())
+        })
+        .unwrap();
+        (loader, requested)
+    }
+
+    fn set_u16(bytes: &mut [u8], at: usize, value: u16) {
+        bytes[at..at + 2].copy_from_slice(&value.to_le_bytes());
+    }
+
+    fn set_u64(bytes: &mut [u8], at: usize, value: u64) {
+        bytes[at..at + 8].copy_from_slice(&value.to_le_bytes());
+    }
+
+    #[test]
+    fn only_the_header_and_tables_are_read() {
+        let bytes = fixture("x86_64-shared");
+        let whole = Loader::from_bytes(bytes.clone());
+        let (loader, requested) = read_recorded(&bytes);
+        assert_eq!(
+            requested,
+            [0..HEADER_LEN, whole.program_table(), whole.section_table()]
+        );
+        assert_eq!(loader.phnum(), whole.phnum());
+        assert_eq!(loader.shnum(), whole.shnum());
+    }
+
+    /// With `e_shnum` 0 the count is in section 0, so the table is read in two
+    /// rounds: section 0 first, then the rest.
+    #[test]
+    fn section_zero_holding_the_count_takes_another_round() {
+        let mut bytes = fixture("x86_64.o");
+        let whole = Loader::from_bytes(bytes.clone());
+        let (shoff, shentsize, shnum) = (whole.shoff() as usize, whole.shentsize(), whole.shnum());
+        set_u16(&mut bytes, 0x3c, 0);
+        set_u64(&mut bytes, shoff + 32, shnum as u64);
+
+        let (loader, requested) = read_recorded(&bytes);
+        assert_eq!(
+            requested,
+            [
+                0..HEADER_LEN,
+                shoff..shoff + shentsize,
+                shoff..shoff + shnum * shentsize
+            ]
+        );
+        assert_eq!(loader.shnum(), shnum);
+    }
+
+    #[test]
+    fn table_past_the_end_of_the_file() {
+        let mut bytes = fixture("x86_64.o");
+        let shoff = bytes.len() + 0x1000;
+        set_u64(&mut bytes, 0x28, shoff as u64);
+
+        let (loader, requested) = read_recorded(&bytes);
+        let table = loader.section_table();
+        assert_eq!(table.start, shoff);
+        assert_eq!(requested, [0..HEADER_LEN, table]);
+        assert_eq!(loader.bytes().len(), HEADER_LEN);
+    }
+}
@@ -0,0 +1,132 @@
+//! The ELF64 header, program header and section header as `#[repr(C)]`
+//! structs, borrowed straight from the mapped file instead of being assembled
+//! field by field. Only files whose class is ELF64 and whose byte order is the
+//! host's can be viewed this way; the accessors return `None` for the others,
+//! and for tables that are truncated, misaligned or have another entry size.
+
+#![allow(non_camel_case_types)]
+
+use std::mem;
+use std::slice;
+
+use crate::Loader;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct Elf64_Ehdr {
+    pub e_ident: [u8; 16],
+    pub e_type: u16,
+    pub e_machine: u16,
+    pub e_version: u32,
+    pub e_entry: u64,
+    pub e_phoff: u64,
+    pub e_shoff: u64,
+    pub e_flags: u32,
+    pub e_ehsize: u16,
+    pub e_phentsize: u16,
+    pub e_phnum: u16,
+    pub e_shentsize: u16,
+    pub e_shnum: u16,
+    pub e_shstrndx: u16,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct Elf64_Phdr {
+    pub p_type: u32,
+    pub p_flags: u32,
+    pub p_offset: u64,
+    pub p_vaddr: u64,
+    pub p_paddr: u64,
+    pub p_filesz: u64,
+    pub p_memsz: u64,
+    pub p_align: u64,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct Elf64_Shdr {
+    pub sh_name: u32,
+    pub sh_type: u32,
+    pub sh_flags: u64,
+    pub sh_addr: u64,
+    pub sh_offset: u64,
+    pub sh_size: u64,
+    pub sh_link: u32,
+    pub sh_info: u32,
+    pub sh_addralign: u64,
+    pub sh_entsize: u64,
+}
+
+// The sizes the ELF64 ABI gives them, which also shows they have no padding.
+const _: () = assert!(mem::size_of::<Elf64_Ehdr>() == 64);
+const _: () = assert!(mem::size_of::<Elf64_Phdr>() == 56);
+const _: () = assert!(mem::size_of::<Elf64_Shdr>() == 64);
+
+/// Structs made of integers only, without padding, so that any bytes are a
+/// valid value.
+///
+/// # Safety
+///
+/// Implementors must be `#[repr(C)]`, padding-free and valid for every bit pattern.
+unsafe trait Plain: Sized {}
+
+// SAFETY: all three are repr(C) integers laid out without padding, see above.
+unsafe impl Plain for Elf64_Ehdr {}
+unsafe impl Plain for Elf64_Phdr {}
+unsafe impl Plain for Elf64_Shdr {}
+
+/// `count` values of `T` at the start of `bytes`, when they are all there and
+/// aligned for `T`.
+fn cast_slice<T: Plain>(bytes: &[u8], count: usize) -> Option<&[T]> {
+    let bytes = bytes.get(..count.checked_mul(mem::size_of::<T>())?)?;
+    if bytes.as_ptr().align_offset(mem::align_of::<T>()) != 0 {
+        return None;
+    }
+    // SAFETY: the bytes are in bounds and aligned, and T takes any bit pattern.
+    Some(unsafe { slice::from_raw_parts(bytes.as_ptr().cast::<T>(), count) })
+}
+
+impl Loader {
+    /// Whether the file's fields can be read in place, being ELF64 in the
+    /// host's byte order.
+    fn is_native_elf64(&self) -> bool {
+        self.is_elf() && self.is_64bit() && self.is_big_endian() == cfg!(target_endian = "big")
+    }
+
+    /// The ELF header in place, for a complete ELF64 header in host byte order.
+    pub fn raw_ehdr(&self) -> Option<&Elf64_Ehdr> {
+        if !self.is_native_elf64() {
+            return None;
+        }
+        cast_slice(self.bytes(), 1).map(|header| &header[0])
+    }
+
+    /// The program header table in place, `PN_XNUM` resolved, when its entries
+    /// are `Elf64_Phdr` sized.
+    pub fn raw_phdrs(&self) -> Option<&[Elf64_Phdr]> {
+        let header = self.raw_ehdr()?;
+        if header.e_phoff == 0 {
+            return Some(&[]);
+        }
+        if usize::from(header.e_phentsize) != mem::size_of::<Elf64_Phdr>() {
+            return None;
+        }
+        let table = self.program_table();
+        cast_slice(self.bytes().get(table.start..)?, self.phnum())
+    }
+
+    /// The section header table in place, an `e_shnum` of 0 resolved, when
+    /// its entries are `Elf64_Shdr` sized.
+    pub fn raw_shdrs(&self) -> Option<&[Elf64_Shdr]> {
+        let header = self.raw_ehdr()?;
+        if header.e_shoff == 0 {
+            return Some(&[]);
+        }
+        if usize::from(header.e_shentsize) != mem::size_of::<Elf64_Shdr>() {
+            return None;
+        }
+        let table = self.section_table();
+        cast_slice(self.bytes().get(table.start..)?, self.shnum())
+    }
+}
@@ -0,0 +1,101 @@
+use crate::arch::arch_ext;
+use crate::section::{SectionHeader, SHT_REL, SHT_RELA};
+use crate::Loader;
+
+#[derive(Clone, Debug)]
+pub struct Relocation {
+    /// Name of the SHT_REL/SHT_RELA section this entry came from.
+    pub section: String,
+    pub r_offset: u64,
+    pub r_type: u32,
+    pub r_sym: u32,
+    pub r_addend: i64,
+    pub symbol: String,
+}
+
+impl Loader {
+    fn read_relocation(&self, base: usize, with_addend: bool) -> Option<(u64, u32, u32, i64)> {
+        if self.is_64bit() {
+            let r_offset = self.read_u64(base)?;
+            let r_info = self.read_u64(base + 8)?;
+            let r_addend = if with_addend {
+                self.read_u64(base + 16)? as i64
+            } else {
+                0
+            };
+            Some((
+                r_offset,
+                (r_info & 0xffff_ffff) as u32,
+                (r_info >> 32) as u32,
+                r_addend,
+            ))
+        } else {
+            let r_offset = self.read_u32(base)?.into();
+            let r_info = self.read_u32(base + 4)?;
+            let r_addend = if with_addend {
+                self.read_u32(base + 8)? as i32 as i64
+            } else {
+                0
+            };
+            Some((r_offset, r_info & 0xff, r_info >> 8, r_addend))
+        }
+    }
+
+    /// Returns the entries of one SHT_REL/SHT_RELA section with symbol names resolved.
+    pub fn relocations_in(
+        &self,
+        table: &SectionHeader,
+        sections: &[SectionHeader],
+    ) -> Vec<Relocation> {
+        let with_addend = table.sh_type == SHT_RELA;
+        let default_size = match (self.is_64bit(), with_addend) {
+            (true, true) => 24,
+            (true, false) => 16,
+            (false, true) => 12,
+            (false, false) => 8,
+        };
+        let entry_size = if table.sh_entsize != 0 {
+            table.sh_entsize as usize
+        } else {
+            default_size
+        };
+        let symbols = sections
+            .get(table.sh_link as usize)
+            .map(|symtab| self.symbols_in(symtab, sections))
+            .unwrap_or_default();
+
+        (0..table.sh_size as usize / entry_size)
+            .map_while(|index| {
+                self.read_relocation(table.sh_offset as usize + index * entry_size, with_addend)
+            })
+            .map(|(r_offset, r_type, r_sym, r_addend)| Relocation {
+                section: table.name.clone(),
+                r_offset,
+                r_type,
+                r_sym,
+                r_addend,
+                symbol: symbols
+                    .get(r_sym as usize)
+                    .map(|symbol| symbol.name.clone())
+                    .unwrap_or_default(),
+            })
+            .collect()
+    }
+
+    /// Returns every relocation found in the SHT_REL and SHT_RELA sections of the file.
+    pub fn relocations(&self) -> Vec<Relocation> {
+        let sections = self.section_headers();
+        sections
+            .iter()
+            .filter(|section| section.sh_type == SHT_REL || section.sh_type == SHT_RELA)
+            .flat_map(|table| self.relocations_in(table, &sections))
+            .collect()
+    }
+
+    pub fn relocation_type_name(&self, r_type: u32) -> String {
+        arch_ext(self.raw_e_machine())
+            .and_then(|arch| arch.relocation_name(r_type))
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("R_{}", r_type))
+    }
+}
@@ -0,0 +1,137 @@
+use log::info;
+use std::fs;
+use std::io;
+use std::ops::Range;
+use std::process::Command;
+
+use crate::ranged;
+use crate::scratch::PrivateDir;
+use crate::Loader;
+
+/// Whether the input names a file on another machine rather than a local path.
+pub fn is_url(path: &str) -> bool {
+    ["http://", "https://", "ssh://"]
+        .iter()
+        .any(|scheme| path.starts_with(scheme))
+}
+
+/// Runs an external tool and returns its stdout, or its stderr as the error.
+pub(crate) fn run_tool(command: &mut Command) -> Result<Vec<u8>, String> {
+    let program = command.get_program().to_string_lossy().into_owned();
+    let output = command
+        .output()
+        .map_err(|error| format!("cannot run {}: {}", program, error))?;
+    if !output.status.success() {
+        return Err(format!(
+            "{} failed: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(output.stdout)
+}
+
+/// Downloads `range` of the file, or all of it, with curl.
+pub(crate) fn curl(url: &str, range: Option<Range<usize>>) -> Result<Vec<u8>, String> {
+    let mut curl = Command::new("curl");
+    curl.args(["--fail", "--silent", "--show-error", "--location"]);
+    if let Some(range) = range {
+        curl.args(["--range", &format!("{}-{}", range.start, range.end - 1)]);
+    }
+    let bytes = run_tool(curl.arg("--").arg(url))?;
+    info!("{}: downloaded {} bytes", url, bytes.len());
+    Ok(bytes)
+}
+
+/// Downloads over HTTP, fetching only the ELF header and header tables with range
+/// requests when that is all the report needs. Servers that ignore ranges send
+/// the whole file, which works just as well.
+fn fetch_http(url: &str, header_only: bool) -> Result<Loader, String> {
+    if !header_only {
+        return curl(url, None).map(Loader::from_bytes);
+    }
+    ranged::read_headers(url, |range| {
+        curl(url, Some(range)).map_err(io::Error::other)
+    })
+    .map_err(|error| error.to_string())
+}
+
+/// `ssh://[user@]host[:port]/path` as the sftp destination and port. A user or
+/// host starting with `-` would be read as an ssh option, such as
+/// `-oProxyCommand=`, so those are refused along with ports that are not numbers.
+fn sftp_target(url: &str) -> Option<(String, Option<&str>)> {
+    let rest = url.strip_prefix("ssh://")?;
+    let (authority, path) = rest.split_at(rest.find('/')?);
+    let (login, port) = match authority.rsplit_once(':') {
+        Some((login, port)) => (login, Some(port)),
+        None => (authority, None),
+    };
+    let host = login.rsplit_once('@').map_or(login, |(_, host)| host);
+    if host.is_empty() || login.starts_with('-') || host.starts_with('-') || path.len() < 2 {
+        return None;
+    }
+    if port.is_some_and(|port| port.is_empty() || !port.bytes().all(|byte| byte.is_ascii_digit())) {
+        return None;
+    }
+    Some((format!("{}:{}", login, path), port))
+}
+
+/// Copies the file with sftp into a private temporary directory and reads it
+/// back. sftp cannot write to a pipe without echoing its own messages into it.
+fn fetch_sftp(url: &str) -> Result<Vec<u8>, String> {
+    let (target, port) = sftp_target(url).ok_or("expected ssh://[user@]host[:port]/path")?;
+    let dir = PrivateDir::create("sftp").map_err(|error| error.to_string())?;
+    let local = dir.path().join("download");
+    let mut sftp = Command::new("sftp");
+    // Batch mode fails instead of prompting for a password.
+    sftp.args(["-q", "-o", "BatchMode=yes"]);
+    if let Some(port) = port {
+        sftp.args(["-P", port]);
+    }
+    let result = run_tool(sftp.arg("--").arg(&target).arg(&local))
+        .and_then(|_| fs::read(&local).map_err(|error| error.to_string()));
+    if let Ok(bytes) = &result {
+        info!("{}: downloaded {} bytes", url, bytes.len());
+    }
+    result
+}
+
+/// Reads the file behind an `http(s)://` or `ssh://` URL into memory.
+pub fn fetch(url: &str, header_only: bool) -> Result<Loader, String> {
+    if url.starts_with("ssh://") {
+        return fetch_sftp(url).map(Loader::from_bytes);
+    }
+    fetch_http(url, header_only)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::sftp_target;
+
+    #[test]
+    fn sftp_targets() {
+        assert_eq!(
+            sftp_target("ssh://build@ci:2222/srv/app"),
+            Some(("build@ci:/srv/app".to_string(), Some("2222")))
+        );
+        assert_eq!(
+            sftp_target("ssh://ci/srv/app"),
+            Some(("ci:/srv/app".to_string(), None))
+        );
+    }
+
+    /// Anything sftp would take for an option is refused before it runs.
+    #[test]
+    fn option_injection() {
+        for url in [
+            "ssh://-oProxyCommand=touch${IFS}pwned/x",
+            "ssh://-oProxyCommand=id@ci/x",
+            "ssh://build@-oProxyCommand=id/x",
+            "ssh://ci:-oProxyCommand=id/x",
+            "ssh://ci:22x/x",
+            "ssh://ci:/x",
+        ] {
+            assert_eq!(sftp_target(url), None, "{}", url);
+        }
+    }
+}
@@ -0,0 +1,200 @@
+use std::collections::BTreeMap;
+
+use crate::output;
+use crate::symbol::{Symbol, SHN_COMMON, SHN_UNDEF, STB_GLOBAL, STB_WEAK};
+use crate::{display_line, Loader};
+
+/// Symbols the static linker defines itself, so references to them are never unresolved.
+const LINKER_DEFINED: &[&str] = &["_GLOBAL_OFFSET_TABLE_", "_DYNAMIC", "__ehdr_start"];
+
+/// How a definition takes part in symbol resolution.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Strength {
+    Weak,
+    Common,
+    Strong,
+}
+
+impl Strength {
+    fn of(symbol: &Symbol) -> Strength {
+        if symbol.st_shndx == SHN_COMMON {
+            Strength::Common
+        } else if symbol.bind() == STB_WEAK {
+            Strength::Weak
+        } else {
+            Strength::Strong
+        }
+    }
+
+    fn name(&self) -> &str {
+        match self {
+            Strength::Weak => "weak",
+            Strength::Common => "common",
+            Strength::Strong => "strong",
+        }
+    }
+}
+
+pub struct Definition {
+    pub object: String,
+    pub strength: Strength,
+    pub size: u64,
+}
+
+impl Definition {
+    fn describe(&self) -> String {
+        match self.strength {
+            Strength::Common => format!("common ({} bytes) in {}", self.size, self.object),
+            strength => format!("{} in {}", strength.name(), self.object),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct SymbolCandidates {
+    pub definitions: Vec<Definition>,
+    /// Objects referencing the symbol without defining it, and whether the reference is weak.
+    pub references: Vec<(String, bool)>,
+}
+
+impl SymbolCandidates {
+    /// Follows the static linker: a strong definition beats common and weak ones, the
+    /// largest common beats weak ones, and otherwise the first definition seen wins.
+    /// `None` means there is nothing to pick, or several strong definitions collide.
+    pub fn winner(&self) -> Option<&Definition> {
+        let strong: Vec<_> = self
+            .definitions
+            .iter()
+            .filter(|definition| definition.strength == Strength::Strong)
+            .collect();
+        match strong.len() {
+            1 => return Some(strong[0]),
+            0 => {}
+            _ => return None,
+        }
+        let common = self
+            .definitions
+            .iter()
+            .filter(|definition| definition.strength == Strength::Common)
+            .reduce(|best, next| if next.size > best.size { next } else { best });
+        common.or_else(|| self.definitions.first())
+    }
+
+    pub fn is_conflict(&self) -> bool {
+        self.definitions
+            .iter()
+            .filter(|definition| definition.strength == Strength::Strong)
+            .count()
+            > 1
+    }
+}
+
+/// Collects every global and weak symbol of the given relocatable objects by name.
+pub fn collect_candidates(targets: &[&(String, Loader)]) -> BTreeMap<String, SymbolCandidates> {
+    let mut candidates: BTreeMap<String, SymbolCandidates> = BTreeMap::new();
+    for (label, loader) in targets {
+        for symbol in loader.static_symbols() {
+            if symbol.name.is_empty() || !matches!(symbol.bind(), STB_GLOBAL | STB_WEAK) {
+                continue;
+            }
+            let entry = candidates.entry(symbol.name.clone()).or_default();
+            if symbol.st_shndx == SHN_UNDEF {
+                entry
+                    .references
+                    .push((label.clone(), symbol.bind() == STB_WEAK));
+            } else {
+                entry.definitions.push(Definition {
+                    object: label.clone(),
+                    strength: Strength::of(&symbol),
+                    size: symbol.st_size,
+                });
+            }
+        }
+    }
+    candidates
+}
+
+pub fn display_resolution(targets: &[(String, Loader)]) {
+    let (objects, skipped): (Vec<_>, Vec<_>) = targets
+        .iter()
+        .partition(|(_, loader)| loader.is_relocatable());
+    for (label, _) in &skipped {
+        output::message(&format!(
+            "{}: not a relocatable object (ET_REL), skipped",
+            label
+        ));
+    }
+    let candidates = collect_candidates(&objects);
+
+    output::begin(&format!(
+        "Symbol resolution across {} object(s)",
+        objects.len()
+    ));
+    let defined = candidates
+        .values()
+        .filter(|candidate| !candidate.definitions.is_empty())
+        .count();
+    display_line("Defined Global Symbols", &defined.to_string());
+
+    let conflicts: Vec<_> = candidates
+        .iter()
+        .filter(|(_, candidate)| candidate.is_conflict())
+        .collect();
+    display_line("Multiple Definitions", &conflicts.len().to_string());
+    for (name, candidate) in &conflicts {
+        let objects: Vec<&str> = candidate
+            .definitions
+            .iter()
+            .filter(|definition| definition.strength == Strength::Strong)
+            .map(|definition| definition.object.as_str())
+            .collect();
+        output::item(1, &format!("{}: strong in {}", name, objects.join(", ")));
+    }
+
+    let overridden: Vec<_> = candidates
+        .iter()
+        .filter(|(_, candidate)| candidate.definitions.len() > 1 && !candidate.is_conflict())
+        .collect();
+    display_line(
+        "Resolved Between Definitions",
+        &overridden.len().to_string(),
+    );
+    for (name, candidate) in &overridden {
+        let Some(winner) = candidate.winner() else {
+            continue;
+        };
+        output::item(1, &format!("{}: {} wins", name, winner.describe()));
+        for loser in candidate
+            .definitions
+            .iter()
+            .filter(|definition| !std::ptr::eq(*definition, winner))
+        {
+            output::item(2, &format!("over {}", loser.describe()));
+        }
+    }
+
+    let unresolved: Vec<_> = candidates
+        .iter()
+        .filter(|(name, candidate)| {
+            candidate.definitions.is_empty() && !LINKER_DEFINED.contains(&name.as_str())
+        })
+        .collect();
+    display_line("Undefined In All Objects", &unresolved.len().to_string());
+    for (name, candidate) in &unresolved {
+        let objects: Vec<&str> = candidate
+            .references
+            .iter()
+            .map(|(object, _)| object.as_str())
+            .collect();
+        let note = if candidate.references.iter().all(|(_, weak)| *weak) {
+            " (weak, resolves to 0 if no library defines it)"
+        } else {
+            ""
+        };
+        output::item(
+            1,
+            &format!("{}: referenced by {}{}", name, objects.join(", "), note),
+        );
+    }
+    output::end();
+}
@@ -0,0 +1,266 @@
+use crate::arch::{bit_name, ArchExt, AttributeSection, TextModel};
+use crate::attributes::AttributeValue;
+use crate::output;
+use crate::{display_line, Loader};
+
+const EM_RISCV: u16 = 243;
+
+const EF_RISCV_RVC: u32 = 0x1;
+const EF_RISCV_FLOAT_ABI: u32 = 0x6;
+const EF_RISCV_FLOAT_ABI_SINGLE: u32 = 0x2;
+const EF_RISCV_FLOAT_ABI_DOUBLE: u32 = 0x4;
+const EF_RISCV_FLOAT_ABI_QUAD: u32 = 0x6;
+const EF_RISCV_RVE: u32 = 0x8;
+const EF_RISCV_TSO: u32 = 0x10;
+
+const RISCV_ATTRIBUTES_SECTION: &str = ".riscv.attributes";
+
+const TAG_RISCV_ARCH: u64 = 5;
+
+/// In the `riscv` vendor subsection odd tags are strings and even tags numbers.
+fn is_text_attribute(tag: u64) -> bool {
+    tag % 2 == 1
+}
+
+fn describe_flags(flags: u32) -> Vec<String> {
+    let mut described = vec![match flags & EF_RISCV_FLOAT_ABI {
+        EF_RISCV_FLOAT_ABI_SINGLE => "single-float ABI",
+        EF_RISCV_FLOAT_ABI_DOUBLE => "double-float ABI",
+        EF_RISCV_FLOAT_ABI_QUAD => "quad-float ABI",
+        _ => "soft-float ABI",
+    }
+    .to_string()];
+    if flags & EF_RISCV_RVC != 0 {
+        described.push("compressed instructions (RVC)".to_string());
+    }
+    if flags & EF_RISCV_RVE != 0 {
+        described.push("RV32E/RV64E (16 integer registers)".to_string());
+    }
+    if flags & EF_RISCV_TSO != 0 {
+        described.push("TSO memory model".to_string());
+    }
+    described
+}
+
+fn describe_attribute(tag: u64, value: &AttributeValue) -> (String, String) {
+    let number = match value {
+        AttributeValue::Text(text) if tag == TAG_RISCV_ARCH => {
+            return ("Tag_RISCV_arch".to_string(), format!("\"{}\"", text));
+        }
+        AttributeValue::Text(text) => return (format!("Tag_{}", tag), format!("\"{}\"", text)),
+        AttributeValue::Number(number) => *number,
+    };
+    let (name, value) = match tag {
+        4 => ("Tag_RISCV_stack_align", format!("{}-byte", number)),
+        6 => (
+            "Tag_RISCV_unaligned_access",
+            match number {
+                0 => "No unaligned access".to_string(),
+                _ => "Unaligned access".to_string(),
+            },
+        ),
+        8 => ("Tag_RISCV_priv_spec", number.to_string()),
+        10 => ("Tag_RISCV_priv_spec_minor", number.to_string()),
+        12 => ("Tag_RISCV_priv_spec_revision", number.to_string()),
+        14 => (
+            "Tag_RISCV_atomic_abi",
+            match number {
+                1 => "A6C".to_string(),
+                2 => "A6S".to_string(),
+                3 => "A7".to_string(),
+                _ => "Unknown".to_string(),
+            },
+        ),
+        16 => (
+            "Tag_RISCV_x3_reg_usage",
+            match number {
+                1 => "gp".to_string(),
+                2 => "shadow call stack".to_string(),
+                3 => "temporary".to_string(),
+                _ => "Unknown".to_string(),
+            },
+        ),
+        _ => return (format!("Tag_{}", tag), number.to_string()),
+    };
+    (name.to_string(), value)
+}
+
+/// Splits an ISA string such as `rv64i2p1_m2p0_a2p1_zicsr2p0` into its base ISA and
+/// extension names, dropping the version numbers.
+fn split_arch(arch: &str) -> (String, Vec<String>) {
+    let strip_version = |part: &str| {
+        part.trim_end_matches(|c: char| c.is_ascii_digit() || c == 'p')
+            .to_string()
+    };
+    let mut parts = arch.split('_');
+    let first = parts.next().unwrap_or("");
+    let base = strip_version(first);
+    // The base is rv32/rv64 plus i, e or g; single-letter extensions may follow it
+    // directly in strings written by hand (rv64imac), so split those out too.
+    let (base, mut extensions) = match base.char_indices().nth(5) {
+        Some((index, _)) if base.starts_with("rv") => (
+            base[..index].to_string(),
+            base[index..].chars().map(String::from).collect(),
+        ),
+        _ => (base, Vec::new()),
+    };
+    extensions.extend(parts.map(strip_version).filter(|part| !part.is_empty()));
+    (base, extensions)
+}
+
+/// Mismatches between the float ABI / RVC bits in e_flags and the ISA string, which
+/// usually mean objects from differently configured toolchains were linked together.
+fn check_consistency(flags: u32, base: &str, extensions: &[String]) -> Vec<String> {
+    let has = |extension: &str| {
+        extensions.iter().any(|e| e == extension)
+            || (base.ends_with('g') && "imafd".contains(extension))
+    };
+    let mut problems = Vec::new();
+    let needed = match flags & EF_RISCV_FLOAT_ABI {
+        EF_RISCV_FLOAT_ABI_SINGLE => Some("f"),
+        EF_RISCV_FLOAT_ABI_DOUBLE => Some("d"),
+        EF_RISCV_FLOAT_ABI_QUAD => Some("q"),
+        _ => None,
+    };
+    if let Some(extension) = needed {
+        if !has(extension) {
+            problems.push(format!(
+                "e_flags selects a hardware float ABI but the ISA string lacks the '{}' extension",
+                extension
+            ));
+        }
+    }
+    if flags & EF_RISCV_RVC != 0 && !has("c") && !extensions.iter().any(|e| e.starts_with("zc")) {
+        problems.push("e_flags has RVC set but the ISA string lacks the 'c' extension".to_string());
+    }
+    problems
+}
+
+/// AT_HWCAP on RISC-V has one bit per single-letter extension, `a` first.
+const RISCV_HWCAP: [&str; 26] = [
+    "a", "b", "c", "d", "e", "f", "g", "h", "i", "j", "k", "l", "m", "n", "o", "p", "q", "r", "s",
+    "t", "u", "v", "w", "x", "y", "z",
+];
+
+pub struct Riscv;
+
+impl ArchExt for Riscv {
+    fn machines(&self) -> &'static [u16] {
+        &[EM_RISCV]
+    }
+
+    fn hwcap_name(&self, bit: u32) -> Option<&'static str> {
+        bit_name(&RISCV_HWCAP, bit)
+    }
+
+    fn describe_flags(&self, _loader: &Loader, flags: u32) -> Vec<String> {
+        describe_flags(flags)
+    }
+
+    /// With the C extension about half the instructions take 2 bytes instead of 4.
+    fn text_model(&self, loader: &Loader) -> Option<TextModel> {
+        Some(if loader.raw_e_flags() & EF_RISCV_RVC != 0 {
+            TextModel {
+                average_length: 3.0,
+                alignment: 2,
+                padding: &[&[0x13, 0x00, 0x00, 0x00], &[0x01, 0x00]],
+            }
+        } else {
+            TextModel {
+                average_length: 4.0,
+                alignment: 4,
+                padding: &[&[0x13, 0x00, 0x00, 0x00]],
+            }
+        })
+    }
+
+    fn relocation_name(&self, r_type: u32) -> Option<&'static str> {
+        match r_type {
+            1 => Some("R_RISCV_32"),
+            2 => Some("R_RISCV_64"),
+            3 => Some("R_RISCV_RELATIVE"),
+            4 => Some("R_RISCV_COPY"),
+            5 => Some("R_RISCV_JUMP_SLOT"),
+            10 => Some("R_RISCV_TLS_TPREL32"),
+            11 => Some("R_RISCV_TLS_TPREL64"),
+            _ => None,
+        }
+    }
+
+    fn attribute_section(&self) -> Option<AttributeSection> {
+        Some(AttributeSection {
+            name: RISCV_ATTRIBUTES_SECTION,
+            is_text: is_text_attribute,
+            describe: describe_attribute,
+        })
+    }
+
+    fn display_details(&self, _loader: &Loader, flags: u32, attributes: &[(u64, AttributeValue)]) {
+        let arch = attributes.iter().find_map(|(_, value)| match value {
+            AttributeValue::Text(text) if text.starts_with("rv") => Some(text.clone()),
+            _ => None,
+        });
+        if let Some(arch) = arch {
+            let (base, extensions) = split_arch(&arch);
+            display_line("Base ISA", &base);
+            display_line("Extensions", &extensions.join(", "));
+            for problem in check_consistency(flags, &base, &extensions) {
+                output::item(1, &format!("warning: {}", problem));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_consistency, describe_flags, split_arch};
+
+    #[test]
+    fn flags() {
+        assert_eq!(
+            describe_flags(0x5),
+            ["double-float ABI", "compressed instructions (RVC)"]
+        );
+        assert_eq!(
+            describe_flags(0x18),
+            [
+                "soft-float ABI",
+                "RV32E/RV64E (16 integer registers)",
+                "TSO memory model"
+            ]
+        );
+    }
+
+    #[test]
+    fn arch_strings() {
+        assert_eq!(
+            split_arch("rv64i2p1_m2p0_a2p1_zicsr2p0"),
+            (
+                "rv64i".to_string(),
+                vec!["m".into(), "a".into(), "zicsr".into()]
+            )
+        );
+        assert_eq!(
+            split_arch("rv32imac"),
+            (
+                "rv32i".to_string(),
+                vec!["m".into(), "a".into(), "c".into()]
+            )
+        );
+    }
+
+    /// The double-float ABI needs `d`, which `g` includes; RVC needs `c`.
+    #[test]
+    fn flags_against_the_arch_string() {
+        let (base, extensions) = split_arch("rv64gc");
+        assert!(check_consistency(0x5, &base, &extensions).is_empty());
+        let (base, extensions) = split_arch("rv64imaf");
+        assert_eq!(
+            check_consistency(0x5, &base, &extensions),
+            [
+                "e_flags selects a hardware float ABI but the ISA string lacks the 'd' extension",
+                "e_flags has RVC set but the ISA string lacks the 'c' extension",
+            ]
+        );
+    }
+}
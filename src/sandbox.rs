@@ -0,0 +1,371 @@
+use std::ffi::CString;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+
+use log::info;
+
+/// `landlock_create_ruleset` flag asking for the ABI version instead.
+const LANDLOCK_CREATE_RULESET_VERSION: u32 = 1 << 0;
+const LANDLOCK_RULE_PATH_BENEATH: u32 = 1;
+
+/// The filesystem rights of each Landlock ABI, by the version adding them.
+const LANDLOCK_ACCESS_FS_V1: u64 = (1 << 13) - 1;
+const LANDLOCK_ACCESS_FS_REFER: u64 = 1 << 13;
+const LANDLOCK_ACCESS_FS_TRUNCATE: u64 = 1 << 14;
+const LANDLOCK_ACCESS_FS_IOCTL_DEV: u64 = 1 << 15;
+const LANDLOCK_ACCESS_FS_WRITE_FILE: u64 = 1 << 1;
+const LANDLOCK_ACCESS_FS_REMOVE_FILE: u64 = 1 << 5;
+const LANDLOCK_ACCESS_FS_READ_FILE: u64 = 1 << 2;
+const LANDLOCK_ACCESS_FS_MAKE_DIR: u64 = 1 << 7;
+const LANDLOCK_ACCESS_FS_MAKE_REG: u64 = 1 << 8;
+/// TCP bind and connect, handled from ABI 4.
+const LANDLOCK_ACCESS_NET_TCP: u64 = (1 << 0) | (1 << 1);
+/// Abstract UNIX sockets and signals to processes outside the sandbox, from ABI 6.
+const LANDLOCK_SCOPE_ALL: u64 = (1 << 0) | (1 << 1);
+
+#[repr(C)]
+struct RulesetAttr {
+    handled_access_fs: u64,
+    handled_access_net: u64,
+    scoped: u64,
+}
+
+#[repr(C, packed)]
+struct PathBeneathAttr {
+    allowed_access: u64,
+    parent_fd: i32,
+}
+
+fn last_error() -> String {
+    std::io::Error::last_os_error().to_string()
+}
+
+/// Lets the ruleset grant `allowed_access` beneath `path`, a file or a directory.
+fn allow(ruleset: libc::c_int, path: &Path, allowed_access: u64) -> Result<(), String> {
+    let name = CString::new(path.as_os_str().as_bytes()).map_err(|error| error.to_string())?;
+    // SAFETY: name is a NUL-terminated path.
+    let parent_fd = unsafe { libc::open(name.as_ptr(), libc::O_PATH | libc::O_CLOEXEC) };
+    if parent_fd < 0 {
+        return Err(format!("{}: {}", path.display(), last_error()));
+    }
+    let rule = PathBeneathAttr {
+        allowed_access,
+        parent_fd,
+    };
+    // SAFETY: rule is a valid landlock_path_beneath_attr for the call.
+    let added = unsafe {
+        libc::syscall(
+            libc::SYS_landlock_add_rule,
+            ruleset,
+            LANDLOCK_RULE_PATH_BENEATH,
+            &rule as *const PathBeneathAttr,
+            0u32,
+        )
+    };
+    // SAFETY: parent_fd was opened above and is not used again.
+    unsafe { libc::close(parent_fd) };
+    if added < 0 {
+        return Err(format!("landlock_add_rule: {}", last_error()));
+    }
+    Ok(())
+}
+
+/// Landlock for the rest of the run: no filesystem access but reading the
+/// `readable` files and creating and writing files in the `writable`
+/// directories, and no TCP. Returns the ABI version applied, or `None` when
+/// the kernel has no Landlock.
+fn landlock(readable: &[&Path], writable: &[&Path]) -> Result<Option<i64>, String> {
+    // SAFETY: a NULL attribute with the version flag only queries the ABI.
+    let abi = unsafe {
+        libc::syscall(
+            libc::SYS_landlock_create_ruleset,
+            std::ptr::null::<RulesetAttr>(),
+            0usize,
+            LANDLOCK_CREATE_RULESET_VERSION,
+        )
+    };
+    if abi < 1 {
+        return Ok(None);
+    }
+    let mut handled_access_fs = LANDLOCK_ACCESS_FS_V1;
+    if abi >= 2 {
+        handled_access_fs |= LANDLOCK_ACCESS_FS_REFER;
+    }
+    if abi >= 3 {
+        handled_access_fs |= LANDLOCK_ACCESS_FS_TRUNCATE;
+    }
+    if abi >= 5 {
+        handled_access_fs |= LANDLOCK_ACCESS_FS_IOCTL_DEV;
+    }
+    let attr = RulesetAttr {
+        handled_access_fs,
+        handled_access_net: if abi >= 4 { LANDLOCK_ACCESS_NET_TCP } else { 0 },
+        scoped: if abi >= 6 { LANDLOCK_SCOPE_ALL } else { 0 },
+    };
+    // Older kernels take the leading fields they know of.
+    let size = match abi {
+        1..=3 => 8,
+        4 | 5 => 16,
+        _ => std::mem::size_of::<RulesetAttr>(),
+    };
+    // SAFETY: attr outlives the call and size covers only its initialized fields.
+    let ruleset = unsafe {
+        libc::syscall(
+            libc::SYS_landlock_create_ruleset,
+            &attr as *const RulesetAttr,
+            size,
+            0u32,
+        )
+    } as libc::c_int;
+    if ruleset < 0 {
+        return Err(format!("landlock_create_ruleset: {}", last_error()));
+    }
+
+    let truncate = if abi >= 3 {
+        LANDLOCK_ACCESS_FS_TRUNCATE
+    } else {
+        0
+    };
+    for path in readable {
+        allow(ruleset, path, LANDLOCK_ACCESS_FS_READ_FILE)?;
+    }
+    for dir in writable {
+        // Files are written next to their destination and renamed over it.
+        let access = LANDLOCK_ACCESS_FS_WRITE_FILE
+            | LANDLOCK_ACCESS_FS_REMOVE_FILE
+            | LANDLOCK_ACCESS_FS_MAKE_REG
+            | LANDLOCK_ACCESS_FS_MAKE_DIR
+            | truncate;
+        allow(ruleset, dir, access)?;
+    }
+
+    // SAFETY: ruleset is the descriptor created above.
+    let restricted = unsafe { libc::syscall(libc::SYS_landlock_restrict_self, ruleset, 0u32) };
+    // SAFETY: the ruleset is enforced or failed, either way its descriptor is done.
+    unsafe { libc::close(ruleset) };
+    if restricted < 0 {
+        return Err(format!("landlock_restrict_self: {}", last_error()));
+    }
+    Ok(Some(abi))
+}
+
+/// The seccomp filter, for the architectures whose system call numbers and
+/// `AUDIT_ARCH_*` it knows.
+#[cfg(any(
+    target_arch = "x86_64",
+    target_arch = "aarch64",
+    target_arch = "riscv64"
+))]
+mod filter {
+    /// The `AUDIT_ARCH_*` of the architecture built for.
+    #[cfg(target_arch = "x86_64")]
+    const AUDIT_ARCH: u32 = 0xc000_003e;
+    #[cfg(target_arch = "aarch64")]
+    const AUDIT_ARCH: u32 = 0xc000_00b7;
+    #[cfg(target_arch = "riscv64")]
+    const AUDIT_ARCH: u32 = 0xc000_00f3;
+
+    /// Where `seccomp_data` keeps the system call number and the architecture.
+    const SECCOMP_DATA_NR: u32 = 0;
+    const SECCOMP_DATA_ARCH: u32 = 4;
+
+    /// What a parser has no use for once its inputs are open: new sockets,
+    /// running programs and reaching into other processes.
+    const DENIED_SYSCALLS: &[libc::c_long] = &[
+        libc::SYS_socket,
+        libc::SYS_socketpair,
+        libc::SYS_connect,
+        libc::SYS_bind,
+        libc::SYS_listen,
+        libc::SYS_accept,
+        libc::SYS_accept4,
+        libc::SYS_execve,
+        libc::SYS_execveat,
+        libc::SYS_ptrace,
+        libc::SYS_process_vm_readv,
+        libc::SYS_process_vm_writev,
+        libc::SYS_kill,
+        libc::SYS_tkill,
+        libc::SYS_tgkill,
+        libc::SYS_pidfd_send_signal,
+    ];
+
+    /// The x32 ABI shares the x86-64 audit arch and sets this bit in the call number.
+    const X32_SYSCALL_BIT: u32 = 0x4000_0000;
+
+    fn statement(code: u32, k: u32) -> libc::sock_filter {
+        jump(code, k, 0, 0)
+    }
+
+    fn jump(code: u32, k: u32, jt: u8, jf: u8) -> libc::sock_filter {
+        libc::sock_filter {
+            code: code as u16,
+            jt,
+            jf,
+            k,
+        }
+    }
+
+    /// A seccomp filter failing [`DENIED_SYSCALLS`] with `EPERM` and killing the
+    /// process on any other architecture's calls.
+    fn program() -> Vec<libc::sock_filter> {
+        use libc::{
+            BPF_ABS, BPF_JEQ, BPF_JGE, BPF_JMP, BPF_K, BPF_LD, BPF_RET, BPF_W, SECCOMP_RET_ALLOW,
+            SECCOMP_RET_ERRNO, SECCOMP_RET_KILL_PROCESS,
+        };
+
+        let denied = DENIED_SYSCALLS.len() as u8;
+        let mut filter = vec![
+            statement(BPF_LD | BPF_W | BPF_ABS, SECCOMP_DATA_ARCH),
+            jump(BPF_JMP | BPF_JEQ | BPF_K, AUDIT_ARCH, 1, 0),
+            statement(BPF_RET | BPF_K, SECCOMP_RET_KILL_PROCESS),
+            statement(BPF_LD | BPF_W | BPF_ABS, SECCOMP_DATA_NR),
+            // Each test jumps over the ones after it and the ALLOW to the ERRNO.
+            jump(BPF_JMP | BPF_JGE | BPF_K, X32_SYSCALL_BIT, denied + 1, 0),
+        ];
+        for (index, &syscall) in DENIED_SYSCALLS.iter().enumerate() {
+            let skip = denied - index as u8;
+            filter.push(jump(BPF_JMP | BPF_JEQ | BPF_K, syscall as u32, skip, 0));
+        }
+        filter.push(statement(BPF_RET | BPF_K, SECCOMP_RET_ALLOW));
+        filter.push(statement(
+            BPF_RET | BPF_K,
+            SECCOMP_RET_ERRNO | libc::EPERM as u32,
+        ));
+        filter
+    }
+
+    /// Installs [`program`] for this process and the ones it starts.
+    pub(super) fn install() -> Result<(), String> {
+        let mut filter = program();
+        let program = libc::sock_fprog {
+            len: filter.len() as u16,
+            filter: filter.as_mut_ptr(),
+        };
+        // SAFETY: program points at the filter, which lives across the call.
+        let installed = unsafe {
+            libc::prctl(
+                libc::PR_SET_SECCOMP,
+                libc::SECCOMP_MODE_FILTER,
+                &program as *const libc::sock_fprog,
+            )
+        };
+        if installed < 0 {
+            return Err(format!("seccomp: {}", super::last_error()));
+        }
+        Ok(())
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use libc::{
+            BPF_ABS, BPF_JEQ, BPF_JGE, BPF_JMP, BPF_K, BPF_LD, BPF_RET, BPF_W, SECCOMP_RET_ALLOW,
+            SECCOMP_RET_ERRNO, SECCOMP_RET_KILL_PROCESS,
+        };
+
+        use super::{program, AUDIT_ARCH, DENIED_SYSCALLS, SECCOMP_DATA_ARCH, SECCOMP_DATA_NR};
+
+        /// Runs the filter over one call the way the kernel does and returns
+        /// the action; it only knows the instructions `program` uses.
+        fn verdict(arch: u32, nr: u32) -> u32 {
+            let filter = program();
+            let (mut pc, mut accumulator) = (0, 0);
+            loop {
+                let op = filter[pc];
+                let code = u32::from(op.code);
+                pc += 1;
+                if code == BPF_LD | BPF_W | BPF_ABS {
+                    accumulator = match op.k {
+                        SECCOMP_DATA_ARCH => arch,
+                        SECCOMP_DATA_NR => nr,
+                        k => panic!("load of seccomp_data at {}", k),
+                    };
+                } else if code == BPF_JMP | BPF_JEQ | BPF_K || code == BPF_JMP | BPF_JGE | BPF_K {
+                    let taken = if code & 0xf0 == BPF_JEQ {
+                        accumulator == op.k
+                    } else {
+                        accumulator >= op.k
+                    };
+                    pc += usize::from(if taken { op.jt } else { op.jf });
+                } else if code == BPF_RET | BPF_K {
+                    return op.k;
+                } else {
+                    panic!("unexpected instruction {:#x}", code);
+                }
+            }
+        }
+
+        #[test]
+        fn filter_length() {
+            // Architecture check, x32 check, one test per call, two returns.
+            assert_eq!(program().len(), 5 + DENIED_SYSCALLS.len() + 2);
+        }
+
+        #[test]
+        fn denied_calls_fail_with_eperm() {
+            let errno = SECCOMP_RET_ERRNO | libc::EPERM as u32;
+            for &syscall in DENIED_SYSCALLS {
+                assert_eq!(verdict(AUDIT_ARCH, syscall as u32), errno, "{}", syscall);
+            }
+            assert_eq!(verdict(AUDIT_ARCH, 0x4000_0000 | 1), errno);
+            assert_eq!(
+                verdict(AUDIT_ARCH, libc::SYS_read as u32),
+                SECCOMP_RET_ALLOW
+            );
+            assert_eq!(
+                verdict(AUDIT_ARCH, libc::SYS_getpid as u32),
+                SECCOMP_RET_ALLOW
+            );
+        }
+
+        #[test]
+        fn other_architectures_are_killed() {
+            assert_eq!(
+                verdict(AUDIT_ARCH ^ 1, libc::SYS_read as u32),
+                SECCOMP_RET_KILL_PROCESS
+            );
+        }
+    }
+}
+
+#[cfg(any(
+    target_arch = "x86_64",
+    target_arch = "aarch64",
+    target_arch = "riscv64"
+))]
+fn seccomp() -> Result<bool, String> {
+    filter::install().map(|()| true)
+}
+
+#[cfg(not(any(
+    target_arch = "x86_64",
+    target_arch = "aarch64",
+    target_arch = "riscv64"
+)))]
+fn seccomp() -> Result<bool, String> {
+    Ok(false)
+}
+
+/// `--sandbox`: once the inputs are open, gives up opening other files, TCP
+/// and new sockets, running programs and touching other processes, keeping
+/// only reading the `readable` files and writing into the `writable`
+/// directories. Fails when the kernel supports neither Landlock nor seccomp.
+pub fn enter(readable: &[&Path], writable: &[&Path]) -> Result<(), String> {
+    // SAFETY: PR_SET_NO_NEW_PRIVS takes plain integers.
+    if unsafe { libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) } < 0 {
+        return Err(format!("PR_SET_NO_NEW_PRIVS: {}", last_error()));
+    }
+    let landlock = landlock(readable, writable)?;
+    let seccomp = seccomp()?;
+    match (landlock, seccomp) {
+        (Some(abi), true) => info!("sandbox: Landlock ABI {} and seccomp", abi),
+        (Some(abi), false) => info!("sandbox: Landlock ABI {}", abi),
+        (None, true) => eprintln!(
+            "warning: the kernel has no Landlock, --sandbox restricts system calls but not files"
+        ),
+        (None, false) => {
+            return Err("--sandbox needs Landlock or seccomp, and neither is available".to_string())
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,65 @@
+use serde_json::{json, Value};
+
+use crate::buildinfo;
+use crate::finding::Checked;
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// `--report sarif=FILE`: the findings as a SARIF 2.1.0 log with one run, which
+/// code-scanning dashboards such as GitHub's and GitLab's ingest. Every check
+/// becomes a rule and every finding a result located at its file.
+pub fn sarif(checked: &[Checked]) -> String {
+    let mut rules: Vec<&str> = Vec::new();
+    for check in checked.iter().flat_map(|checked| &checked.checks) {
+        if !rules.contains(&check.as_str()) {
+            rules.push(check);
+        }
+    }
+    let results: Vec<Value> = checked
+        .iter()
+        .flat_map(|checked| {
+            checked
+                .findings
+                .iter()
+                .map(move |finding| (&checked.path, finding))
+        })
+        .map(|(path, finding)| {
+            let index = rules.iter().position(|rule| *rule == finding.check);
+            json!({
+                "ruleId": finding.check,
+                "ruleIndex": index,
+                "level": finding.severity.as_str(),
+                "message": { "text": finding.message },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": path },
+                    },
+                }],
+            })
+        })
+        .collect();
+    let rules: Vec<Value> = rules
+        .iter()
+        .map(|rule| json!({ "id": rule, "shortDescription": { "text": rule } }))
+        .collect();
+    let log = json!({
+        "$schema": SARIF_SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": buildinfo::NAME,
+                    "version": buildinfo::VERSION,
+                    "properties": {
+                        "git": buildinfo::GIT_HASH,
+                        "target": buildinfo::TARGET,
+                        "features": buildinfo::features(),
+                    },
+                    "rules": rules,
+                },
+            },
+            "results": results,
+        }],
+    });
+    format!("{:#}\n", log)
+}
@@ -0,0 +1,88 @@
+//! Scratch space under the temporary directory that no other user can get
+//! at. Names there are easy to guess, so each one is created fresh, failing
+//! if something is already in the way, and readable by the owner alone;
+//! otherwise another user could plant a symbolic link and have a write go
+//! wherever it points.
+
+use std::env;
+use std::fs::{self, DirBuilder};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Names tried before giving up, each taken by someone else.
+const ATTEMPTS: usize = 16;
+
+/// A directory only this user can use, removed with everything in it when
+/// dropped.
+pub struct PrivateDir {
+    path: PathBuf,
+}
+
+impl PrivateDir {
+    /// Creates `elf-header-parser-<purpose>-...` under the temporary
+    /// directory, with a name no one has used yet.
+    pub fn create(purpose: &str) -> io::Result<PrivateDir> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let mut builder = DirBuilder::new();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::DirBuilderExt;
+            builder.mode(0o700);
+        }
+        for _ in 0..ATTEMPTS {
+            let nanos = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.subsec_nanos());
+            let path = env::temp_dir().join(format!(
+                "elf-header-parser-{}-{}-{}-{:08x}",
+                purpose,
+                process::id(),
+                COUNTER.fetch_add(1, Ordering::Relaxed),
+                nanos
+            ));
+            match builder.create(&path) {
+                Ok(()) => return Ok(PrivateDir { path }),
+                Err(error) if error.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(error) => return Err(error),
+            }
+        }
+        Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            "no unused name for a temporary directory",
+        ))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for PrivateDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+
+    use super::PrivateDir;
+
+    #[test]
+    fn private_dirs_are_new_and_removed() {
+        let first = PrivateDir::create("test").unwrap();
+        let second = PrivateDir::create("test").unwrap();
+        assert_ne!(first.path(), second.path());
+        let mode = fs::metadata(first.path()).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o700);
+        let path = first.path().to_path_buf();
+        fs::write(path.join("file"), b"x").unwrap();
+        drop(first);
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,256 @@
+use log::{debug, trace};
+use std::ops::Range;
+
+use crate::limits;
+use crate::timings::{self, Phase};
+use crate::Loader;
+
+pub const SHT_SYMTAB: u32 = 2;
+pub const SHT_RELA: u32 = 4;
+pub const SHT_NOTE: u32 = 7;
+pub const SHT_NOBITS: u32 = 8;
+pub const SHT_REL: u32 = 9;
+pub const SHT_DYNSYM: u32 = 11;
+pub const SHT_GROUP: u32 = 17;
+pub const SHT_GNU_VERDEF: u32 = 0x6fff_fffd;
+pub const SHT_GNU_VERNEED: u32 = 0x6fff_fffe;
+pub const SHT_GNU_VERSYM: u32 = 0x6fff_ffff;
+
+pub const SHF_WRITE: u64 = 0x1;
+pub const SHF_ALLOC: u64 = 0x2;
+pub const SHF_EXECINSTR: u64 = 0x4;
+
+const SHN_UNDEF: u16 = 0;
+/// e_shstrndx value saying the real index did not fit and is in section 0.
+const SHN_XINDEX: usize = 0xffff;
+
+// Offsets of the section header related fields in the ELF header, per class.
+pub(crate) const ELF32_E_SHOFF: usize = 32;
+pub(crate) const ELF64_E_SHOFF: usize = 40;
+const ELF32_E_SHENTSIZE: usize = 46;
+const ELF64_E_SHENTSIZE: usize = 58;
+pub(crate) const ELF32_E_SHNUM: usize = 48;
+pub(crate) const ELF64_E_SHNUM: usize = 60;
+const ELF32_E_SHSTRNDX: usize = 50;
+const ELF64_E_SHSTRNDX: usize = 62;
+
+#[derive(Clone, Debug)]
+pub struct SectionHeader {
+    pub index: usize,
+    pub name: String,
+    pub sh_name: u32,
+    pub sh_type: u32,
+    pub sh_flags: u64,
+    pub sh_addr: u64,
+    pub sh_offset: u64,
+    pub sh_size: u64,
+    pub sh_link: u32,
+    pub sh_info: u32,
+    pub sh_addralign: u64,
+    pub sh_entsize: u64,
+}
+
+impl SectionHeader {
+    pub fn contains_addr(&self, addr: u64) -> bool {
+        self.sh_flags & SHF_ALLOC != 0
+            && addr >= self.sh_addr
+            && addr < self.sh_addr.saturating_add(self.sh_size)
+    }
+
+    /// Whether the section's contents in the file cover `offset`.
+    pub fn contains_offset(&self, offset: u64) -> bool {
+        self.sh_type != SHT_NOBITS
+            && offset >= self.sh_offset
+            && offset < self.sh_offset.saturating_add(self.sh_size)
+    }
+
+    pub fn end_addr(&self) -> u64 {
+        self.sh_addr.saturating_add(self.sh_size)
+    }
+}
+
+impl Loader {
+    pub(crate) fn shoff(&self) -> u64 {
+        if self.is_64bit() {
+            self.read_u64(ELF64_E_SHOFF).unwrap_or(0)
+        } else {
+            self.read_u32(ELF32_E_SHOFF).map(u64::from).unwrap_or(0)
+        }
+    }
+
+    pub(crate) fn shentsize(&self) -> usize {
+        let offset = if self.is_64bit() {
+            ELF64_E_SHENTSIZE
+        } else {
+            ELF32_E_SHENTSIZE
+        };
+        self.read_u16(offset).unwrap_or(0) as usize
+    }
+
+    /// Section 0, which holds the real counts when the header fields overflow.
+    pub(crate) fn initial_section(&self) -> Option<SectionHeader> {
+        if self.shoff() == 0 {
+            return None;
+        }
+        self.read_section_header(0)
+    }
+
+    pub(crate) fn shnum(&self) -> usize {
+        let offset = if self.is_64bit() {
+            ELF64_E_SHNUM
+        } else {
+            ELF32_E_SHNUM
+        };
+        let shnum = self.read_u16(offset).unwrap_or(0) as usize;
+        if shnum == 0 {
+            if let Some(initial) = self
+                .initial_section()
+                .filter(|initial| initial.sh_size != 0)
+            {
+                debug!(
+                    "e_shnum is 0, reading the real count from section 0: {}",
+                    initial.sh_size
+                );
+                return initial.sh_size as usize;
+            }
+        }
+        shnum
+    }
+
+    pub(crate) fn shstrndx(&self) -> usize {
+        let offset = if self.is_64bit() {
+            ELF64_E_SHSTRNDX
+        } else {
+            ELF32_E_SHSTRNDX
+        };
+        let shstrndx = self.read_u16(offset).unwrap_or(SHN_UNDEF) as usize;
+        if shstrndx == SHN_XINDEX {
+            if let Some(initial) = self.initial_section() {
+                debug!(
+                    "e_shstrndx is SHN_XINDEX, reading the real index from section 0 sh_link: {}",
+                    initial.sh_link
+                );
+                return initial.sh_link as usize;
+            }
+        }
+        shstrndx
+    }
+
+    /// File offset just past the section header table.
+    pub(crate) fn section_table_end(&self) -> usize {
+        (self.shoff() as usize).saturating_add(self.shnum() * self.shentsize())
+    }
+
+    /// Where the section header table lies in the file. Section 0 is included
+    /// even when `e_shnum` is 0, since it then holds the real count.
+    pub(crate) fn section_table(&self) -> Range<usize> {
+        let start = self.shoff() as usize;
+        let count = if start == 0 { 0 } else { self.shnum().max(1) };
+        start..start.saturating_add(count * self.shentsize())
+    }
+
+    pub(crate) fn read_section_header(&self, index: usize) -> Option<SectionHeader> {
+        let base = (self.shoff() as usize).checked_add(index.checked_mul(self.shentsize())?)?;
+        if self.is_64bit() {
+            Some(SectionHeader {
+                index,
+                name: String::new(),
+                sh_name: self.read_u32(base)?,
+                sh_type: self.read_u32(base + 4)?,
+                sh_flags: self.read_u64(base + 8)?,
+                sh_addr: self.read_u64(base + 16)?,
+                sh_offset: self.read_u64(base + 24)?,
+                sh_size: self.read_u64(base + 32)?,
+                sh_link: self.read_u32(base + 40)?,
+                sh_info: self.read_u32(base + 44)?,
+                sh_addralign: self.read_u64(base + 48)?,
+                sh_entsize: self.read_u64(base + 56)?,
+            })
+        } else {
+            Some(SectionHeader {
+                index,
+                name: String::new(),
+                sh_name: self.read_u32(base)?,
+                sh_type: self.read_u32(base + 4)?,
+                sh_flags: self.read_u32(base + 8)?.into(),
+                sh_addr: self.read_u32(base + 12)?.into(),
+                sh_offset: self.read_u32(base + 16)?.into(),
+                sh_size: self.read_u32(base + 20)?.into(),
+                sh_link: self.read_u32(base + 24)?,
+                sh_info: self.read_u32(base + 28)?,
+                sh_addralign: self.read_u32(base + 32)?.into(),
+                sh_entsize: self.read_u32(base + 36)?.into(),
+            })
+        }
+    }
+
+    /// Returns all section headers with their names resolved from the section header string table.
+    pub fn section_headers(&self) -> Vec<SectionHeader> {
+        timings::measure(Phase::Sections, || self.read_section_table())
+    }
+
+    fn read_section_table(&self) -> Vec<SectionHeader> {
+        if self.shoff() == 0 {
+            trace!("e_shoff is 0, no section header table");
+            return Vec::new();
+        }
+        if self.shentsize() == 0 {
+            debug!("e_shentsize is 0, ignoring the section header table");
+            return Vec::new();
+        }
+        let max_sections = limits::get().max_sections;
+        let mut shnum = self.shnum();
+        if shnum > max_sections {
+            debug!(
+                "reading {} of {} section headers, --max-sections is {}",
+                max_sections, shnum, max_sections
+            );
+            shnum = max_sections;
+        }
+        let mut sections: Vec<SectionHeader> = (0..shnum)
+            .map_while(|index| self.read_section_header(index))
+            .collect();
+        if sections.len() < shnum {
+            debug!(
+                "section header table at {:#x} is truncated: read {} of {} entries",
+                self.shoff(),
+                sections.len(),
+                shnum
+            );
+        }
+
+        let strtab_offset = sections
+            .get(self.shstrndx())
+            .map(|strtab| strtab.sh_offset as usize);
+        match strtab_offset {
+            Some(strtab_offset) => {
+                for section in sections.iter_mut() {
+                    if let Some(name) = self.read_cstr(strtab_offset + section.sh_name as usize) {
+                        section.name = name.to_string();
+                    }
+                }
+            }
+            None => debug!(
+                "e_shstrndx {} is not a section, section names are unavailable",
+                self.shstrndx()
+            ),
+        }
+        sections
+    }
+
+    pub fn section_by_name(&self, name: &str) -> Option<SectionHeader> {
+        self.section_headers()
+            .into_iter()
+            .find(|section| section.name == name)
+    }
+
+    /// Returns the file contents backing a section, or an empty slice for SHT_NOBITS.
+    pub fn section_data(&self, section: &SectionHeader) -> &[u8] {
+        if section.sh_type == SHT_NOBITS {
+            return &[];
+        }
+        let start = section.sh_offset as usize;
+        let end = start.saturating_add(section.sh_size as usize);
+        self.bytes().get(start..end).unwrap_or(&[])
+    }
+}
@@ -0,0 +1,185 @@
+use log::{debug, trace};
+use std::ops::Range;
+
+use crate::Loader;
+
+pub const PT_LOAD: u32 = 1;
+pub const PT_DYNAMIC: u32 = 2;
+pub const PT_INTERP: u32 = 3;
+pub const PT_NOTE: u32 = 4;
+pub const PT_GNU_EH_FRAME: u32 = 0x6474_e550;
+pub const PT_GNU_STACK: u32 = 0x6474_e551;
+pub const PT_GNU_RELRO: u32 = 0x6474_e552;
+
+pub const PF_X: u32 = 0x1;
+pub const PF_W: u32 = 0x2;
+pub const PF_R: u32 = 0x4;
+
+// Offsets of the program header related fields in the ELF header, per class.
+const ELF32_E_PHOFF: usize = 28;
+const ELF64_E_PHOFF: usize = 32;
+const ELF32_E_PHENTSIZE: usize = 42;
+const ELF64_E_PHENTSIZE: usize = 54;
+const ELF32_E_PHNUM: usize = 44;
+const ELF64_E_PHNUM: usize = 56;
+
+/// e_phnum value saying the real count did not fit and is in section 0.
+const PN_XNUM: usize = 0xffff;
+
+#[derive(Clone, Debug)]
+pub struct ProgramHeader {
+    pub p_type: u32,
+    pub p_flags: u32,
+    pub p_offset: u64,
+    pub p_vaddr: u64,
+    pub p_paddr: u64,
+    pub p_filesz: u64,
+    pub p_memsz: u64,
+    pub p_align: u64,
+}
+
+impl ProgramHeader {
+    pub fn end_vaddr(&self) -> u64 {
+        self.p_vaddr.saturating_add(self.p_memsz)
+    }
+
+    /// The segment's permissions in the `r-x` notation of /proc/PID/maps.
+    pub fn permissions(&self) -> String {
+        [(PF_R, 'r'), (PF_W, 'w'), (PF_X, 'x')]
+            .iter()
+            .map(|&(flag, letter)| {
+                if self.p_flags & flag != 0 {
+                    letter
+                } else {
+                    '-'
+                }
+            })
+            .collect()
+    }
+}
+
+impl Loader {
+    pub(crate) fn phoff(&self) -> u64 {
+        if self.is_64bit() {
+            self.read_u64(ELF64_E_PHOFF).unwrap_or(0)
+        } else {
+            self.read_u32(ELF32_E_PHOFF).map(u64::from).unwrap_or(0)
+        }
+    }
+
+    pub(crate) fn phentsize(&self) -> usize {
+        let offset = if self.is_64bit() {
+            ELF64_E_PHENTSIZE
+        } else {
+            ELF32_E_PHENTSIZE
+        };
+        self.read_u16(offset).unwrap_or(0) as usize
+    }
+
+    /// e_phnum as the header holds it, `PN_XNUM` included.
+    pub(crate) fn raw_e_phnum(&self) -> usize {
+        let offset = if self.is_64bit() {
+            ELF64_E_PHNUM
+        } else {
+            ELF32_E_PHNUM
+        };
+        self.read_u16(offset).unwrap_or(0) as usize
+    }
+
+    pub(crate) fn phnum(&self) -> usize {
+        let phnum = self.raw_e_phnum();
+        if phnum == PN_XNUM {
+            if let Some(initial) = self.initial_section() {
+                debug!(
+                    "e_phnum is PN_XNUM, reading the real count from section 0 sh_info: {}",
+                    initial.sh_info
+                );
+                return initial.sh_info as usize;
+            }
+        }
+        phnum
+    }
+
+    /// Where the program header table lies in the file.
+    pub(crate) fn program_table(&self) -> Range<usize> {
+        let start = self.phoff() as usize;
+        start..start.saturating_add(self.phnum() * self.phentsize())
+    }
+
+    /// File offset just past the program header table.
+    pub(crate) fn program_table_end(&self) -> usize {
+        self.program_table().end
+    }
+
+    fn read_program_header(&self, index: usize) -> Option<ProgramHeader> {
+        let base = (self.phoff() as usize).checked_add(index.checked_mul(self.phentsize())?)?;
+        // Elf64_Phdr moves p_flags right after p_type, Elf32_Phdr keeps it near the end.
+        if self.is_64bit() {
+            Some(ProgramHeader {
+                p_type: self.read_u32(base)?,
+                p_flags: self.read_u32(base + 4)?,
+                p_offset: self.read_u64(base + 8)?,
+                p_vaddr: self.read_u64(base + 16)?,
+                p_paddr: self.read_u64(base + 24)?,
+                p_filesz: self.read_u64(base + 32)?,
+                p_memsz: self.read_u64(base + 40)?,
+                p_align: self.read_u64(base + 48)?,
+            })
+        } else {
+            Some(ProgramHeader {
+                p_type: self.read_u32(base)?,
+                p_offset: self.read_u32(base + 4)?.into(),
+                p_vaddr: self.read_u32(base + 8)?.into(),
+                p_paddr: self.read_u32(base + 12)?.into(),
+                p_filesz: self.read_u32(base + 16)?.into(),
+                p_memsz: self.read_u32(base + 20)?.into(),
+                p_flags: self.read_u32(base + 24)?,
+                p_align: self.read_u32(base + 28)?.into(),
+            })
+        }
+    }
+
+    pub fn program_headers(&self) -> Vec<ProgramHeader> {
+        if self.phoff() == 0 {
+            trace!("e_phoff is 0, no program header table");
+            return Vec::new();
+        }
+        if self.phentsize() == 0 {
+            debug!("e_phentsize is 0, ignoring the program header table");
+            return Vec::new();
+        }
+        let phnum = self.phnum();
+        let headers: Vec<ProgramHeader> = (0..phnum)
+            .map_while(|index| self.read_program_header(index))
+            .collect();
+        if headers.len() < phnum {
+            debug!(
+                "program header table at {:#x} is truncated: read {} of {} entries",
+                self.phoff(),
+                headers.len(),
+                phnum
+            );
+        }
+        headers
+    }
+
+    pub fn program_header_by_type(&self, p_type: u32) -> Option<ProgramHeader> {
+        self.program_headers()
+            .into_iter()
+            .find(|header| header.p_type == p_type)
+    }
+
+    /// Translates a virtual address into a file offset using the PT_LOAD segments.
+    pub fn vaddr_to_offset(&self, vaddr: u64) -> Option<usize> {
+        let offset = self
+            .program_headers()
+            .iter()
+            .filter(|header| header.p_type == PT_LOAD)
+            .find(|header| vaddr >= header.p_vaddr && vaddr < header.p_vaddr + header.p_filesz)
+            .map(|header| (vaddr - header.p_vaddr + header.p_offset) as usize);
+        if offset.is_none() {
+            trace!("address {:#x} is not backed by any PT_LOAD segment", vaddr);
+        }
+        offset
+    }
+}
@@ -0,0 +1,210 @@
+//! `selftest`: writes synthetic ELF files with the benchmarks' generator into
+//! a temporary directory, reads them back and checks that every report sees
+//! what was written, so that a distribution package or a build for an unusual
+//! platform can be checked without fixtures.
+
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+
+use crate::baseline;
+use crate::model::parsed_elf;
+use crate::scratch::PrivateDir;
+use crate::synthetic::{self, Spec};
+use crate::{display_line, output, Loader};
+
+const ET_REL: u16 = 1;
+const ET_EXEC: u16 = 2;
+const EM_X86_64: u16 = 62;
+/// Where the generator loads executables and puts their entry point.
+const ENTRY: u64 = 0x400000 + 64 + 56;
+/// The sections the generator writes besides the code: the null section,
+/// `.data`, `.symtab`, `.strtab` and `.shstrtab`.
+const EXTRA_SECTIONS: usize = 5;
+
+/// The files written, by name.
+const CASES: &[(&str, Spec)] = &[
+    (
+        "object",
+        Spec {
+            executable: false,
+            sections: 16,
+            symbols: 1000,
+            padding: 1 << 16,
+        },
+    ),
+    (
+        "executable",
+        Spec {
+            executable: true,
+            sections: 16,
+            symbols: 1000,
+            padding: 1 << 16,
+        },
+    ),
+    // From 0xff00 sections the counts are kept in section 0.
+    (
+        "extended-numbering",
+        Spec {
+            executable: false,
+            sections: 0xff00,
+            symbols: 100,
+            padding: 0,
+        },
+    ),
+    (
+        "empty",
+        Spec {
+            executable: false,
+            sections: 0,
+            symbols: 0,
+            padding: 0,
+        },
+    ),
+];
+
+/// The checks of one file, as (name, what was expected, what was read).
+fn checks(spec: &Spec, label: &str, loader: &Loader) -> Vec<(&'static str, String, String)> {
+    let symbols = loader.static_symbols();
+    let undefined = symbols
+        .iter()
+        .skip(1)
+        .filter(|symbol| symbol.st_shndx == 0)
+        .count();
+    let expected_undefined = if spec.sections == 0 {
+        spec.symbols
+    } else {
+        spec.symbols / 64
+    };
+    let sections = loader.section_headers();
+    let mut checks = vec![
+        ("ELF", true.to_string(), loader.is_elf().to_string()),
+        (
+            "Type",
+            (if spec.executable { ET_EXEC } else { ET_REL }).to_string(),
+            loader.raw_e_type().to_string(),
+        ),
+        (
+            "Machine",
+            EM_X86_64.to_string(),
+            loader.raw_e_machine().to_string(),
+        ),
+        (
+            "Entry Point",
+            format!(
+                "{:#x}",
+                if spec.executable && spec.sections > 0 {
+                    ENTRY
+                } else {
+                    0
+                }
+            ),
+            format!("{:#x}", loader.raw_e_entry()),
+        ),
+        (
+            "Sections",
+            (spec.sections + EXTRA_SECTIONS).to_string(),
+            sections.len().to_string(),
+        ),
+        (
+            "Section Names",
+            ".data .shstrtab".to_string(),
+            format!(
+                "{} {}",
+                sections
+                    .get(spec.sections + 1)
+                    .map_or("", |section| section.name.as_str()),
+                sections.last().map_or("", |section| section.name.as_str())
+            ),
+        ),
+        (
+            "Segments",
+            usize::from(spec.executable).to_string(),
+            loader.program_headers().len().to_string(),
+        ),
+        (
+            "Symbols",
+            (spec.symbols + 1).to_string(),
+            symbols.len().to_string(),
+        ),
+        (
+            "Undefined Symbols",
+            expected_undefined.to_string(),
+            undefined.to_string(),
+        ),
+    ];
+    let document = parsed_elf(label, loader);
+    checks.push((
+        "Parsed Document",
+        (spec.sections + EXTRA_SECTIONS).to_string(),
+        document["sections"]
+            .as_array()
+            .map_or(0, Vec::len)
+            .to_string(),
+    ));
+    // The rest only has to get through the file.
+    loader.relro_report();
+    loader.has_nx();
+    loader.debug_facts();
+    baseline::entry(loader);
+    checks
+}
+
+/// Writes the file of one case, reads it back and prints its checks. Returns
+/// why it failed, if it did; a panic is a failure too.
+fn run_case(dir: &Path, name: &str, spec: &Spec) -> Option<String> {
+    let path = dir.join(name);
+    if let Err(error) = synthetic::write_elf(&path, spec) {
+        return Some(format!("cannot write {}: {}", path.display(), error));
+    }
+    let loader = match Loader::open(&path.to_string_lossy()) {
+        Ok(loader) => loader,
+        Err(error) => return Some(format!("cannot read {}: {}", path.display(), error)),
+    };
+    let checks = panic::catch_unwind(AssertUnwindSafe(|| checks(spec, name, &loader)));
+    output::begin(name);
+    let result = match checks {
+        Ok(checks) => {
+            let mut failed = 0;
+            for (check, expected, actual) in checks {
+                if expected == actual {
+                    display_line(check, "Pass");
+                } else {
+                    display_line(
+                        check,
+                        &format!("Fail: expected {}, read {}", expected, actual),
+                    );
+                    failed += 1;
+                }
+            }
+            (failed > 0).then(|| format!("{} check(s) failed", failed))
+        }
+        Err(_) => {
+            display_line("Result", "Panicked");
+            Some("the reports panicked".to_string())
+        }
+    };
+    output::end();
+    result
+}
+
+/// `selftest`: every case in a fresh private temporary directory, removed
+/// afterwards.
+pub fn selftest() -> Vec<(String, String)> {
+    let dir = match PrivateDir::create("selftest") {
+        Ok(dir) => dir,
+        Err(error) => return vec![("selftest".to_string(), error.to_string())],
+    };
+    let failures: Vec<(String, String)> = CASES
+        .iter()
+        .filter_map(|(name, spec)| {
+            run_case(dir.path(), name, spec).map(|reason| (format!("selftest/{}", name), reason))
+        })
+        .collect();
+    drop(dir);
+    output::message(&format!(
+        "{} of {} self-test file(s) passed",
+        CASES.len() - failures.len(),
+        CASES.len()
+    ));
+    failures
+}
@@ -0,0 +1,447 @@
+use log::info;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::{Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::cli::Options;
+use crate::filetype;
+use crate::output;
+use crate::scratch::PrivateDir;
+use crate::{open_inputs, report, Loader};
+
+/// Uploads larger than this are refused with 413 before any of the body is read.
+const MAX_UPLOAD: u64 = 256 << 20;
+/// The longest request or header line; longer ones are refused with 431.
+const MAX_LINE: u64 = 8192;
+/// The most header lines a request may have.
+const MAX_HEADERS: usize = 100;
+/// A client that stops sending for this long is dropped, so it cannot hold a thread.
+const READ_TIMEOUT: Duration = Duration::from_secs(30);
+/// The time to send the request line and headers, however they trickle in.
+const HEAD_DEADLINE: Duration = Duration::from_secs(30);
+/// How long, and how much, the rest of a refused request is read before the
+/// connection is closed.
+const LINGER: Duration = Duration::from_secs(1);
+const MAX_LINGER: u64 = 1 << 20;
+/// The slowest an upload may arrive on average, in bytes per second, on top
+/// of `HEAD_DEADLINE`.
+const MIN_UPLOAD_RATE: u64 = 256 << 10;
+/// Connections answered at once; further ones wait in the listen backlog.
+const MAX_CONNECTIONS: usize = 8;
+
+/// Counts the connections being answered, so that the accept loop can wait
+/// for a free slot.
+#[derive(Default)]
+struct Slots {
+    busy: Mutex<usize>,
+    freed: Condvar,
+}
+
+impl Slots {
+    /// Waits until fewer than `MAX_CONNECTIONS` are busy and takes a slot.
+    fn take(&self) {
+        let mut busy = self
+            .busy
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        while *busy >= MAX_CONNECTIONS {
+            busy = self
+                .freed
+                .wait(busy)
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+        }
+        *busy += 1;
+    }
+
+    fn release(&self) {
+        *self
+            .busy
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) -= 1;
+        self.freed.notify_one();
+    }
+}
+
+/// Gives the slot back when the connection's thread ends, even by a panic.
+struct Slot<'a>(&'a Slots);
+
+impl Drop for Slot<'_> {
+    fn drop(&mut self) {
+        self.0.release();
+    }
+}
+
+struct Request {
+    method: String,
+    path: String,
+    query: Vec<(String, String)>,
+    /// The body, in a file already unlinked, so that uploads do not sit in
+    /// memory.
+    body: Option<File>,
+}
+
+/// The connection, read with a deadline for the whole request on top of the
+/// timeout for each read.
+struct Deadline {
+    stream: TcpStream,
+    until: Instant,
+}
+
+impl Read for Deadline {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        let left = self.until.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            return Err(io::ErrorKind::TimedOut.into());
+        }
+        self.stream.set_read_timeout(Some(left.min(READ_TIMEOUT)))?;
+        self.stream.read(buffer)
+    }
+}
+
+impl Request {
+    fn param(&self, name: &str) -> Option<&str> {
+        self.query
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+struct Response {
+    status: u16,
+    content_type: &'static str,
+    body: String,
+}
+
+impl Response {
+    fn json(status: u16, body: String) -> Response {
+        Response {
+            status,
+            content_type: "application/json",
+            body,
+        }
+    }
+
+    /// An error in the same shape as a report: an array with one message record.
+    fn error(status: u16, text: &str) -> Response {
+        Response::json(status, output::capture_json(|| output::message(text)))
+    }
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        408 => "Request Timeout",
+        411 => "Length Required",
+        413 => "Payload Too Large",
+        422 => "Unprocessable Entity",
+        431 => "Request Header Fields Too Large",
+        _ => "Internal Server Error",
+    }
+}
+
+/// Decodes `%XX` escapes and `+` in one query component.
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        match bytes[index] {
+            b'+' => decoded.push(b' '),
+            b'%' => {
+                let hex = bytes
+                    .get(index + 1..index + 3)
+                    .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+                match hex {
+                    Some(byte) => {
+                        decoded.push(byte);
+                        index += 2;
+                    }
+                    None => decoded.push(b'%'),
+                }
+            }
+            byte => decoded.push(byte),
+        }
+        index += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn parse_query(query: &str) -> Vec<(String, String)> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (percent_decode(key), percent_decode(value))
+        })
+        .collect()
+}
+
+/// The response to a failed read of `what`.
+fn read_error(error: io::Error, what: &str) -> Response {
+    match error.kind() {
+        io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock => {
+            Response::error(408, &format!("{} did not arrive in time", what))
+        }
+        _ => Response::error(400, &format!("cannot read {}", what)),
+    }
+}
+
+/// Reads one line of at most `MAX_LINE` bytes into `line`, replacing what it
+/// held.
+fn read_line(
+    reader: &mut BufReader<Deadline>,
+    line: &mut String,
+    what: &str,
+) -> Result<(), Response> {
+    line.clear();
+    reader
+        .take(MAX_LINE)
+        .read_line(line)
+        .map_err(|error| read_error(error, what))?;
+    if line.len() as u64 == MAX_LINE && !line.ends_with('\n') {
+        return Err(Response::error(
+            431,
+            &format!("{} is longer than {} bytes", what, MAX_LINE),
+        ));
+    }
+    Ok(())
+}
+
+/// Reads one HTTP/1.1 request. A body needs Content-Length; chunked uploads are
+/// refused rather than decoded. An error is the response to send instead.
+fn read_request(stream: &mut TcpStream) -> Result<Request, Response> {
+    let mut reader = BufReader::new(Deadline {
+        stream: stream
+            .try_clone()
+            .map_err(|_| Response::error(500, "cannot read the request"))?,
+        until: Instant::now() + HEAD_DEADLINE,
+    });
+    let bad = |text: &str| Response::error(400, text);
+
+    let mut line = String::new();
+    read_line(&mut reader, &mut line, "the request line")?;
+    let mut words = line.split_whitespace();
+    let (Some(method), Some(target)) = (words.next(), words.next()) else {
+        return Err(bad("malformed request line"));
+    };
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let mut request = Request {
+        method: method.to_string(),
+        path: path.to_string(),
+        query: parse_query(query),
+        body: None,
+    };
+
+    let mut length = 0;
+    let mut expect_continue = false;
+    for count in 0.. {
+        read_line(&mut reader, &mut line, "a request header")?;
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if count == MAX_HEADERS {
+            return Err(Response::error(
+                431,
+                &format!("more than {} request headers", MAX_HEADERS),
+            ));
+        }
+        let Some((name, value)) = header.split_once(':') else {
+            return Err(bad("malformed header"));
+        };
+        let value = value.trim();
+        match name.to_ascii_lowercase().as_str() {
+            "content-length" => {
+                length = value.parse().map_err(|_| bad("invalid Content-Length"))?;
+            }
+            "transfer-encoding" if !value.eq_ignore_ascii_case("identity") => {
+                return Err(Response::error(
+                    411,
+                    "chunked uploads are not supported, send Content-Length",
+                ));
+            }
+            "expect" => expect_continue = value.eq_ignore_ascii_case("100-continue"),
+            _ => {}
+        }
+    }
+
+    if length > MAX_UPLOAD {
+        return Err(Response::error(
+            413,
+            &format!("upload is larger than {} bytes", MAX_UPLOAD),
+        ));
+    }
+    if length > 0 {
+        // curl waits for this before it sends anything larger than 1 KiB.
+        if expect_continue {
+            let _ = stream.write_all(b"HTTP/1.1 100 Continue\r\n\r\n");
+        }
+        reader.get_mut().until += Duration::from_secs(length / MIN_UPLOAD_RATE);
+        // The body goes to a file as it arrives, so a Content-Length alone
+        // reserves nothing and an upload is not held in memory.
+        let (mut file, dir) = PrivateDir::create("upload")
+            .and_then(|dir| Ok((File::create_new(dir.path().join("body"))?, dir)))
+            .map_err(|_| Response::error(500, "cannot store the upload"))?;
+        drop(dir);
+        let copied = io::copy(&mut reader.take(length), &mut file)
+            .map_err(|error| read_error(error, "the request body"))?;
+        if copied != length {
+            return Err(bad("request body is shorter than Content-Length"));
+        }
+        request.body = Some(file);
+    }
+    Ok(request)
+}
+
+/// Runs the report chosen on the command line over `inputs` and returns it as
+/// JSON, with any failures appended as message records.
+fn analyze(inputs: Vec<(String, Loader)>, options: &Options) -> Response {
+    let mut failures = Vec::new();
+    let body = output::capture_json(|| {
+        report(inputs, options, &mut failures);
+        for (label, problem) in &failures {
+            output::message(&format!("{}: {}", label, problem));
+        }
+    });
+    Response::json(if failures.is_empty() { 200 } else { 422 }, body)
+}
+
+/// The canonical form of `path`, if it lies under one of the shared directories.
+fn shared_path(path: &str, roots: &[PathBuf]) -> Option<String> {
+    let resolved = fs::canonicalize(path).ok()?;
+    roots
+        .iter()
+        .any(|root| resolved.starts_with(root))
+        .then(|| resolved.display().to_string())
+}
+
+fn respond_to(request: Request, options: &Options, roots: &[PathBuf]) -> Response {
+    match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/health") => Response {
+            status: 200,
+            content_type: "text/plain",
+            body: "ok\n".to_string(),
+        },
+        ("POST", "/analyze") => {
+            let label = request.param("name").unwrap_or("upload").to_string();
+            let loader = match &request.body {
+                Some(body) => match Loader::from_file(body) {
+                    Ok(loader) => loader,
+                    Err(_) => return Response::error(500, "cannot read the upload"),
+                },
+                None => Loader::from_bytes(Vec::new()),
+            };
+            if !loader.is_elf() && options.offset.is_none() && !options.carve {
+                let problem = filetype::not_elf(loader.bytes());
+                return Response::error(422, &format!("{} is {}", label, problem));
+            }
+            analyze(vec![(label, loader)], options)
+        }
+        ("GET", "/analyze") => {
+            let Some(path) = request.param("path") else {
+                return Response::error(400, "GET /analyze needs ?path=, or POST the file");
+            };
+            let Some(resolved) = shared_path(path, roots) else {
+                return Response::error(
+                    403,
+                    &format!("{}: not a file under a directory given to serve", path),
+                );
+            };
+            let mut failures = Vec::new();
+            let inputs = open_inputs(&[resolved], options.header_only(), &mut failures);
+            if let Some((_, problem)) = failures.first() {
+                return Response::error(422, &format!("{}: {}", path, problem));
+            }
+            analyze(inputs, options)
+        }
+        (_, "/analyze") | (_, "/health") => Response::error(405, "method not allowed"),
+        _ => Response::error(404, &format!("no endpoint {}", request.path)),
+    }
+}
+
+fn handle(mut stream: TcpStream, options: &Options, roots: &[PathBuf]) {
+    let (response, unread) = match read_request(&mut stream) {
+        Ok(request) => {
+            info!("{} {}", request.method, request.path);
+            (respond_to(request, options, roots), false)
+        }
+        Err(response) => (response, true),
+    };
+    let head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        response.status,
+        reason(response.status),
+        response.content_type,
+        response.body.len()
+    );
+    let _ = stream
+        .write_all(head.as_bytes())
+        .and_then(|()| stream.write_all(response.body.as_bytes()));
+    // Closing with part of the request unread resets the connection, which
+    // can cost the client the response; a little more is read and dropped.
+    if unread {
+        let _ = stream.shutdown(Shutdown::Write);
+        let _ = stream.set_read_timeout(Some(LINGER));
+        let _ = io::copy(&mut (&stream).take(MAX_LINGER), &mut io::sink());
+    }
+}
+
+/// Listens on the address in the first path and answers every connection on its
+/// own thread, up to `MAX_CONNECTIONS` at once, with the report the other options
+/// select, as JSON. Files are either POSTed to `/analyze` or named with
+/// `GET /analyze?path=`, which only reaches files under the directories given
+/// after the address. Returns only if it cannot start.
+pub fn serve(options: &Options) -> Vec<(String, String)> {
+    let Some(address) = options.paths.first() else {
+        return vec![(
+            "serve".to_string(),
+            "needs an address to listen on, such as 127.0.0.1:8080".to_string(),
+        )];
+    };
+    let mut roots = Vec::new();
+    for dir in &options.paths[1..] {
+        match fs::canonicalize(dir) {
+            Ok(root) if root.is_dir() => roots.push(root),
+            Ok(_) => return vec![(dir.clone(), "is not a directory".to_string())],
+            Err(error) => return vec![(dir.clone(), error.to_string())],
+        }
+    }
+    let listener = match TcpListener::bind(address) {
+        Ok(listener) => listener,
+        Err(error) => return vec![(address.clone(), error.to_string())],
+    };
+    eprintln!(
+        "serving on http://{}",
+        listener
+            .local_addr()
+            .map_or(address.clone(), |local| local.to_string())
+    );
+    let slots = Slots::default();
+    thread::scope(|scope| {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    slots.take();
+                    let slot = Slot(&slots);
+                    let roots = &roots;
+                    scope.spawn(move || {
+                        let _slot = slot;
+                        handle(stream, options, roots)
+                    });
+                }
+                Err(error) => info!("accept failed: {}", error),
+            }
+        }
+    });
+    Vec::new()
+}
@@ -0,0 +1,458 @@
+//! `sign` and `verify --public-key`, built with the `signing` feature: an
+//! Ed25519 signature over what a file loads, kept in a `.note.elfhp.sig`
+//! section of its own. The note is not allocated, so it is left out of the
+//! digest it signs, and signing a signed file again replaces the note.
+
+use std::fs;
+use std::path::Path;
+
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use sha2::{Digest, Sha256};
+
+use crate::note::{from_hex, hex_string};
+use crate::patch::{write_patched, Destination, Journal};
+use crate::section::{
+    SectionHeader, ELF32_E_SHNUM, ELF32_E_SHOFF, ELF64_E_SHNUM, ELF64_E_SHOFF, SHF_ALLOC, SHT_NOTE,
+};
+use crate::segment::PT_LOAD;
+use crate::{display_line, output, Loader};
+
+const SIGNATURE_SECTION: &str = ".note.elfhp.sig";
+const NOTE_NAME: &[u8] = b"elfhp\0";
+const NT_ELFHP_SIGNATURE: u32 = 1;
+/// Prefixed to the digested contents, so that a signature made here cannot
+/// stand for any other message signed with the same key.
+const DOMAIN: &[u8] = b"elf-header-parser signature v3\0";
+
+const KEY_SIZE: usize = 32;
+const SIGNATURE_SIZE: usize = 64;
+/// The note header, the name padded to 4 bytes, then the public key and the
+/// signature.
+const NOTE_SIZE: usize = 12 + 8 + KEY_SIZE + SIGNATURE_SIZE;
+
+/// Section indexes from here on are reserved, so files with this many
+/// sections keep their count elsewhere and are not rewritten.
+const SHN_LORESERVE: u16 = 0xff00;
+
+/// Offsets of `sh_name`, `sh_type`, `sh_offset`, `sh_size` and
+/// `sh_addralign` in a section header, per class.
+const ELF32_SH_FIELDS: [usize; 5] = [0, 4, 16, 20, 32];
+const ELF64_SH_FIELDS: [usize; 5] = [0, 4, 24, 32, 48];
+
+/// Reads a key file: 32 raw bytes, or 64 hexadecimal digits such as
+/// `openssl rand -hex 32` prints.
+pub fn read_key(path: &str) -> Result<[u8; KEY_SIZE], String> {
+    let bytes = fs::read(path).map_err(|error| error.to_string())?;
+    if let Ok(raw) = bytes.as_slice().try_into() {
+        return Ok(raw);
+    }
+    let text = String::from_utf8_lossy(&bytes);
+    let text = text.trim();
+    if text.len() != 2 * KEY_SIZE {
+        return Err(format!(
+            "expected {} bytes or {} hexadecimal digits",
+            KEY_SIZE,
+            2 * KEY_SIZE
+        ));
+    }
+    from_hex(text)
+        .and_then(|key| key.try_into().ok())
+        .ok_or_else(|| "not a hexadecimal key".to_string())
+}
+
+/// Hashes the bytes at `offset`, `size` long, or those of them the file has,
+/// reading the ones of the ELF header from `header` instead of the file.
+fn update_range(hasher: &mut Sha256, bytes: &[u8], header: &[u8], offset: u64, size: u64) {
+    let start = offset.min(bytes.len() as u64) as usize;
+    let end = (offset.saturating_add(size).min(bytes.len() as u64) as usize).max(start);
+    let split = header.len().clamp(start, end);
+    if start < split {
+        hasher.update(&header[start..split]);
+    }
+    hasher.update(&bytes[split..end]);
+}
+
+/// SHA-256 over what the file loads: the ELF header but for the fields that
+/// locate the section header table, the program header table and the
+/// contents of each PT_LOAD segment, then the name, type, flags, address,
+/// size and contents of each allocated section in section order. Sections
+/// that are not allocated are left out, so stripping debug info or adding the
+/// signature note keeps the digest.
+fn digest(loader: &Loader) -> [u8; 32] {
+    let bytes = loader.bytes();
+    let (size, e_shoff, word, e_shnum) = if loader.is_64bit() {
+        (64, ELF64_E_SHOFF, 8, ELF64_E_SHNUM)
+    } else {
+        (52, ELF32_E_SHOFF, 4, ELF32_E_SHNUM)
+    };
+    let mut header = bytes[..size.min(bytes.len())].to_vec();
+    header.resize(size, 0);
+    // e_shoff, then e_shentsize, e_shnum and e_shstrndx at the end.
+    header[e_shoff..e_shoff + word].fill(0);
+    header[e_shnum - 2..].fill(0);
+
+    let mut hasher = Sha256::new();
+    hasher.update(DOMAIN);
+    hasher.update(&header);
+    let phdrs = loader.phnum() as u64 * loader.phentsize() as u64;
+    update_range(&mut hasher, bytes, &header, loader.phoff(), phdrs);
+    for segment in loader.program_headers() {
+        if segment.p_type == PT_LOAD {
+            hasher.update(segment.p_offset.to_le_bytes());
+            hasher.update(segment.p_filesz.to_le_bytes());
+            let (offset, size) = (segment.p_offset, segment.p_filesz);
+            update_range(&mut hasher, bytes, &header, offset, size);
+        }
+    }
+    for section in loader.section_headers() {
+        if section.sh_flags & SHF_ALLOC == 0 {
+            continue;
+        }
+        hasher.update(section.name.as_bytes());
+        hasher.update([0]);
+        hasher.update(section.sh_type.to_le_bytes());
+        hasher.update(section.sh_flags.to_le_bytes());
+        hasher.update(section.sh_addr.to_le_bytes());
+        hasher.update(section.sh_size.to_le_bytes());
+        hasher.update(loader.section_data(&section));
+    }
+    hasher.finalize().into()
+}
+
+/// The bytes of a file being rewritten, written in its class and byte order.
+struct Image {
+    bytes: Vec<u8>,
+    is_64bit: bool,
+    big_endian: bool,
+}
+
+impl Image {
+    fn put(&mut self, offset: usize, value: u64, size: usize) {
+        let raw = if self.big_endian {
+            value.to_be_bytes()[8 - size..].to_vec()
+        } else {
+            value.to_le_bytes()[..size].to_vec()
+        };
+        self.bytes[offset..offset + size].copy_from_slice(&raw);
+    }
+
+    /// A field that is 4 or 8 bytes by class.
+    fn put_word(&mut self, offset: usize, value: u64) {
+        self.put(offset, value, if self.is_64bit { 8 } else { 4 });
+    }
+
+    fn align(&mut self, alignment: usize) -> usize {
+        let padded = self.bytes.len().next_multiple_of(alignment);
+        self.bytes.resize(padded, 0);
+        padded
+    }
+
+    /// Sets `sh_name`, `sh_type`, `sh_offset`, `sh_size` and `sh_addralign`
+    /// of the section header at `header`, where given.
+    fn put_section(&mut self, header: usize, fields: [Option<u64>; 5]) {
+        let offsets = if self.is_64bit {
+            ELF64_SH_FIELDS
+        } else {
+            ELF32_SH_FIELDS
+        };
+        for (index, (offset, value)) in offsets.into_iter().zip(fields).enumerate() {
+            let Some(value) = value else { continue };
+            if index < 2 {
+                self.put(header + offset, value, 4);
+            } else {
+                self.put_word(header + offset, value);
+            }
+        }
+    }
+}
+
+fn note(loader: &Loader, key: &SigningKey, digest: &[u8; 32]) -> Vec<u8> {
+    let mut image = Image {
+        bytes: vec![0; NOTE_SIZE],
+        is_64bit: loader.is_64bit(),
+        big_endian: loader.is_big_endian(),
+    };
+    image.put(0, NOTE_NAME.len() as u64, 4);
+    image.put(4, (KEY_SIZE + SIGNATURE_SIZE) as u64, 4);
+    image.put(8, u64::from(NT_ELFHP_SIGNATURE), 4);
+    image.bytes[12..12 + NOTE_NAME.len()].copy_from_slice(NOTE_NAME);
+    image.bytes[20..20 + KEY_SIZE].copy_from_slice(key.verifying_key().as_bytes());
+    image.bytes[20 + KEY_SIZE..].copy_from_slice(&key.sign(digest).to_bytes());
+    image.bytes
+}
+
+/// The public key and signature of the signature note, or why there is none.
+fn read_note(loader: &Loader) -> Result<([u8; KEY_SIZE], [u8; SIGNATURE_SIZE]), String> {
+    let section = loader
+        .section_by_name(SIGNATURE_SECTION)
+        .ok_or_else(|| "not signed".to_string())?;
+    let data = loader.section_data(&section);
+    let at = section.sh_offset as usize;
+    let well_formed = data.len() == NOTE_SIZE
+        && at
+            .checked_add(NOTE_SIZE)
+            .is_some_and(|end| end <= loader.bytes().len())
+        && loader.read_u32(at) == Some(NOTE_NAME.len() as u32)
+        && loader.read_u32(at + 4) == Some((KEY_SIZE + SIGNATURE_SIZE) as u32)
+        && loader.read_u32(at + 8) == Some(NT_ELFHP_SIGNATURE)
+        && data[12..12 + NOTE_NAME.len()] == *NOTE_NAME;
+    if !well_formed {
+        return Err(format!("{} is malformed", SIGNATURE_SECTION));
+    }
+    let key = data[20..20 + KEY_SIZE].try_into().unwrap();
+    let signature = data[20 + KEY_SIZE..].try_into().unwrap();
+    Ok((key, signature))
+}
+
+/// The file signed with `key` and its digest. The signature note is replaced
+/// in place when there is one and added after everything else otherwise: the
+/// note, a copy of the section name table with the note's name, and a copy of
+/// the section header table with the note's header.
+fn signed(loader: &Loader, key: &SigningKey) -> Result<(Vec<u8>, [u8; 32]), String> {
+    let digest = digest(loader);
+    let note = note(loader, key, &digest);
+    let sections = loader.section_headers();
+    let mut image = Image {
+        bytes: loader.bytes().to_vec(),
+        is_64bit: loader.is_64bit(),
+        big_endian: loader.is_big_endian(),
+    };
+    if let Some(section) = sections.iter().find(|s| s.name == SIGNATURE_SECTION) {
+        if section.sh_size as usize != NOTE_SIZE {
+            return Err(format!("{} is malformed", SIGNATURE_SECTION));
+        }
+        let at = section.sh_offset as usize;
+        at.checked_add(NOTE_SIZE)
+            .and_then(|end| image.bytes.get_mut(at..end))
+            .ok_or_else(|| format!("{} is past the end of the file", SIGNATURE_SECTION))?
+            .copy_from_slice(&note);
+        return Ok((image.bytes, digest));
+    }
+
+    let (e_shoff, e_shnum) = if loader.is_64bit() {
+        (ELF64_E_SHOFF, ELF64_E_SHNUM)
+    } else {
+        (ELF32_E_SHOFF, ELF32_E_SHNUM)
+    };
+    let shnum = loader.read_u16(e_shnum).unwrap_or(0);
+    let shstrndx = loader.shstrndx();
+    if sections.is_empty() || shnum == 0 || shnum + 1 >= SHN_LORESERVE {
+        return Err("no section header table the note can be added to".to_string());
+    }
+    let names: &SectionHeader = sections
+        .get(shstrndx)
+        .filter(|_| shstrndx != 0 && shstrndx < usize::from(SHN_LORESERVE))
+        .ok_or_else(|| "no section name table".to_string())?;
+    let shentsize = loader.shentsize();
+    let table = loader.shoff() as usize;
+    let headers = loader
+        .bytes()
+        .get(table..table + usize::from(shnum) * shentsize)
+        .ok_or_else(|| "the section header table is past the end of the file".to_string())?
+        .to_vec();
+
+    let note_offset = image.align(4);
+    image.bytes.extend_from_slice(&note);
+    let names_offset = image.bytes.len();
+    let name = loader.section_data(names).len() as u64;
+    image.bytes.extend_from_slice(loader.section_data(names));
+    image.bytes.extend_from_slice(SIGNATURE_SECTION.as_bytes());
+    image.bytes.push(0);
+    let names_size = image.bytes.len() - names_offset;
+    let table_offset = image.align(if loader.is_64bit() { 8 } else { 4 });
+    image.bytes.extend_from_slice(&headers);
+    image.bytes.resize(image.bytes.len() + shentsize, 0);
+
+    image.put_section(
+        table_offset + shstrndx * shentsize,
+        [
+            None,
+            None,
+            Some(names_offset as u64),
+            Some(names_size as u64),
+            None,
+        ],
+    );
+    image.put_section(
+        table_offset + usize::from(shnum) * shentsize,
+        [
+            Some(name),
+            Some(u64::from(SHT_NOTE)),
+            Some(note_offset as u64),
+            Some(NOTE_SIZE as u64),
+            Some(4),
+        ],
+    );
+    image.put_word(e_shoff, table_offset as u64);
+    image.put(e_shnum, u64::from(shnum + 1), 2);
+    Ok((image.bytes, digest))
+}
+
+/// `sign --key KEY`: signs every target and writes it back in place.
+pub fn display_sign(
+    targets: &[(String, Loader)],
+    secret: &[u8; KEY_SIZE],
+    destination: &Destination,
+    journal: &mut Journal,
+) -> Vec<(String, String)> {
+    let key = SigningKey::from_bytes(secret);
+    let mut failures = Vec::new();
+    for (label, loader) in targets {
+        if loader.bytes().len() != loader.file_bytes().len() || !Path::new(label).is_file() {
+            failures.push((label.clone(), "only whole files can be signed".to_string()));
+            continue;
+        }
+        let written = signed(loader, &key).and_then(|(bytes, digest)| {
+            let path = write_patched(Path::new(label), destination, &bytes)?;
+            journal.record(&path, loader.bytes(), &bytes);
+            Ok((path, digest))
+        });
+        let (path, digest) = match written {
+            Ok(written) => written,
+            Err(error) => {
+                failures.push((label.clone(), error));
+                continue;
+            }
+        };
+        output::begin(label);
+        display_line("Digest", &hex_string(&digest));
+        display_line("Public Key", &hex_string(key.verifying_key().as_bytes()));
+        display_line("Signature", &format!("Written to {}", SIGNATURE_SECTION));
+        display_line("Output", &path.display().to_string());
+        output::end();
+    }
+    failures
+}
+
+/// `verify --public-key KEY`: checks the signature note of every target,
+/// failing the files unsigned, signed with another key or changed since.
+pub fn display_verify(
+    targets: &[(String, Loader)],
+    public: &[u8; KEY_SIZE],
+) -> Vec<(String, String)> {
+    let mut failures = Vec::new();
+    let trusted = match VerifyingKey::from_bytes(public) {
+        Ok(key) => key,
+        Err(_) => return vec![("--public-key".to_string(), "not an Ed25519 key".to_string())],
+    };
+    for (label, loader) in targets {
+        output::begin(label);
+        let digest = digest(loader);
+        display_line("Digest", &hex_string(&digest));
+        let problem = match read_note(loader) {
+            Err(problem) => Some(problem),
+            Ok((key, _)) if key != *trusted.as_bytes() => {
+                display_line("Public Key", &hex_string(&key));
+                Some("signed with another key".to_string())
+            }
+            Ok((key, signature)) => {
+                display_line("Public Key", &hex_string(&key));
+                trusted
+                    .verify_strict(&digest, &Signature::from_bytes(&signature))
+                    .err()
+                    .map(|_| "the signature does not match the contents".to_string())
+            }
+        };
+        display_line(
+            "Signature",
+            if problem.is_none() {
+                "Valid"
+            } else {
+                "Invalid"
+            },
+        );
+        if let Some(problem) = &problem {
+            output::item(1, problem);
+        }
+        output::end();
+        if let Some(problem) = problem {
+            failures.push((label.clone(), problem));
+        }
+    }
+    failures
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+
+    use ed25519_dalek::SigningKey;
+
+    use super::{display_verify, signed, SIGNATURE_SECTION};
+    use crate::output;
+    use crate::Loader;
+
+    const SECRET: [u8; 32] = [7; 32];
+
+    fn signed_fixture() -> Vec<u8> {
+        let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/x86_64-shared");
+        let loader = Loader::from_bytes(fs::read(fixture).unwrap());
+        signed(&loader, &SigningKey::from_bytes(&SECRET)).unwrap().0
+    }
+
+    fn verify(bytes: Vec<u8>) -> Vec<(String, String)> {
+        let public = *SigningKey::from_bytes(&SECRET).verifying_key().as_bytes();
+        let targets = [("signed".to_string(), Loader::from_bytes(bytes))];
+        let mut failures = Vec::new();
+        output::collect(|| failures = display_verify(&targets, &public));
+        failures
+    }
+
+    #[test]
+    fn signed_file_verifies() {
+        assert!(verify(signed_fixture()).is_empty());
+    }
+
+    /// Making a segment writable touches neither a section nor the ELF header.
+    #[test]
+    fn changed_segment_flags_fail_verification() {
+        let mut elf = signed_fixture();
+        let phoff = u64::from_le_bytes(elf[32..40].try_into().unwrap()) as usize;
+        elf[phoff + 4] |= 2;
+        let failures = verify(elf);
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].1, "the signature does not match the contents");
+    }
+
+    fn section_header(elf: &[u8], name: &str) -> usize {
+        let loader = Loader::from_bytes(elf.to_vec());
+        let index = loader
+            .section_headers()
+            .iter()
+            .position(|section| section.name == name)
+            .unwrap();
+        let shoff = u64::from_le_bytes(elf[40..48].try_into().unwrap()) as usize;
+        shoff + index * 64
+    }
+
+    /// Sections that are not loaded, such as debug info, are not signed.
+    #[test]
+    fn changed_unallocated_section_keeps_the_signature() {
+        let mut elf = signed_fixture();
+        let comment = section_header(&elf, ".symtab");
+        let offset = u64::from_le_bytes(elf[comment + 24..comment + 32].try_into().unwrap());
+        elf[offset as usize] ^= 0xff;
+        assert!(verify(elf).is_empty());
+    }
+
+    #[test]
+    fn signing_again_replaces_the_note() {
+        let loader = Loader::from_bytes(signed_fixture());
+        let again = signed(&loader, &SigningKey::from_bytes(&SECRET)).unwrap().0;
+        assert_eq!(again.len(), loader.bytes().len());
+        assert!(verify(again).is_empty());
+    }
+
+    /// A note said to lie past the end of the address space is refused, not
+    /// added up to an overflow.
+    #[test]
+    fn note_offset_out_of_range() {
+        let mut elf = signed_fixture();
+        let note = section_header(&elf, SIGNATURE_SECTION);
+        elf[note + 24..note + 32].fill(0xff);
+        let loader = Loader::from_bytes(elf);
+        let error = signed(&loader, &SigningKey::from_bytes(&SECRET)).unwrap_err();
+        assert!(error.contains("past the end of the file"), "{}", error);
+    }
+}
@@ -0,0 +1,117 @@
+use crate::config;
+use crate::exports::glob_match;
+use crate::output;
+use crate::section::{SectionHeader, SHF_ALLOC, SHF_EXECINSTR, SHF_WRITE, SHT_NOBITS};
+use crate::{display_line, Loader};
+
+/// What a section counts as in `--size`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SectionClass {
+    Code,
+    Rodata,
+    Data,
+    Bss,
+    Other,
+}
+
+impl SectionClass {
+    pub const ALL: [SectionClass; 5] = [
+        SectionClass::Code,
+        SectionClass::Rodata,
+        SectionClass::Data,
+        SectionClass::Bss,
+        SectionClass::Other,
+    ];
+
+    pub fn parse(name: &str) -> Option<SectionClass> {
+        Self::ALL.into_iter().find(|class| class.as_str() == name)
+    }
+
+    /// The name `config.toml` uses.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SectionClass::Code => "code",
+            SectionClass::Rodata => "rodata",
+            SectionClass::Data => "data",
+            SectionClass::Bss => "bss",
+            SectionClass::Other => "other",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            SectionClass::Code => "Code",
+            SectionClass::Rodata => "Read-only Data",
+            SectionClass::Data => "Data",
+            SectionClass::Bss => "BSS",
+            SectionClass::Other => "Other",
+        }
+    }
+}
+
+/// The class of a section: the first `[[section]]` of `config.toml` whose
+/// pattern matches its name, or else its flags and type. Sections that do not
+/// take memory at run time have none unless the configuration gives them one.
+pub fn classify(section: &SectionHeader, rules: &[(String, SectionClass)]) -> Option<SectionClass> {
+    if let Some((_, class)) = rules
+        .iter()
+        .find(|(pattern, _)| glob_match(pattern, &section.name))
+    {
+        return Some(*class);
+    }
+    if section.sh_flags & SHF_ALLOC == 0 {
+        return None;
+    }
+    Some(if section.sh_type == SHT_NOBITS {
+        SectionClass::Bss
+    } else if section.sh_flags & SHF_EXECINSTR != 0 {
+        SectionClass::Code
+    } else if section.sh_flags & SHF_WRITE != 0 {
+        SectionClass::Data
+    } else {
+        SectionClass::Rodata
+    })
+}
+
+/// `--size`: the bytes of code, read-only data, data and bss, like `size`,
+/// then the classified sections by address as a memory map.
+pub fn display_size(path: &str, loader: &Loader) {
+    let rules = config::get().sections;
+    let mut sections: Vec<(SectionHeader, SectionClass)> = loader
+        .section_headers()
+        .into_iter()
+        .filter(|section| section.index != 0)
+        .filter_map(|section| {
+            let class = classify(&section, &rules)?;
+            Some((section, class))
+        })
+        .collect();
+    sections.sort_by_key(|(section, _)| (section.sh_addr, section.index));
+
+    output::begin(path);
+    let mut total = 0;
+    for class in SectionClass::ALL {
+        let size: u64 = sections
+            .iter()
+            .filter(|(_, of)| *of == class)
+            .map(|(section, _)| section.sh_size)
+            .sum();
+        total += size;
+        display_line(class.label(), &format!("{} bytes", size));
+    }
+    display_line("Total", &format!("{} bytes", total));
+    display_line("Memory Map", &format!("{} section(s)", sections.len()));
+    for (section, class) in &sections {
+        output::item(
+            1,
+            &format!(
+                "{:#010x} {:>10}  {:<6}  {}",
+                section.sh_addr,
+                section.sh_size,
+                class.as_str(),
+                section.name
+            ),
+        );
+    }
+    output::end();
+}
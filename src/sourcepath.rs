@@ -0,0 +1,137 @@
+use std::collections::BTreeSet;
+
+use crate::dwarf::{DW_AT_COMP_DIR, DW_AT_NAME, DW_UT_COMPILE, DW_UT_SKELETON};
+use crate::finding::{self, Finding, Severity};
+use crate::output;
+use crate::{display_line, Loader};
+
+/// The check behind the `--report` findings of `--source-paths`.
+const HOME_PATH: &str = "source-paths/home";
+
+/// Where home directories live, so that paths below them name a user.
+const HOME_ROOTS: &[&str] = &["/home/", "/Users/", "/export/home/"];
+
+/// The source paths recorded by one compile unit.
+pub struct SourcePath {
+    pub comp_dir: Option<String>,
+    pub name: String,
+}
+
+impl SourcePath {
+    /// The file as the compiler saw it: `name`, under `comp_dir` when relative.
+    pub fn full_path(&self) -> String {
+        match &self.comp_dir {
+            Some(dir) if !self.name.starts_with('/') && !is_windows_absolute(&self.name) => {
+                format!("{}/{}", dir.trim_end_matches('/'), self.name)
+            }
+            _ => self.name.clone(),
+        }
+    }
+}
+
+fn is_windows_absolute(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    bytes.len() > 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':'
+}
+
+/// The home directory a path lies in, such as `/home/alice`, `/root` or
+/// `C:\Users\alice`.
+pub fn home_directory(path: &str) -> Option<String> {
+    if path == "/root" || path.starts_with("/root/") {
+        return Some("/root".to_string());
+    }
+    for root in HOME_ROOTS {
+        if let Some(rest) = path.strip_prefix(root) {
+            let user = rest.split('/').next().filter(|user| !user.is_empty())?;
+            return Some(format!("{}{}", root, user));
+        }
+    }
+    if is_windows_absolute(path) && path[2..].to_ascii_lowercase().starts_with("\\users\\") {
+        let user = path[9..]
+            .split('\\')
+            .next()
+            .filter(|user| !user.is_empty())?;
+        return Some(format!("{}{}", &path[..9], user));
+    }
+    None
+}
+
+impl Loader {
+    /// `DW_AT_name` and `DW_AT_comp_dir` of every compile unit, skeletons of
+    /// split DWARF included.
+    pub fn source_paths(&self) -> Vec<SourcePath> {
+        self.units()
+            .into_iter()
+            .filter(|unit| matches!(unit.unit_type, DW_UT_COMPILE | DW_UT_SKELETON))
+            .filter_map(|unit| {
+                Some(SourcePath {
+                    comp_dir: unit.text(DW_AT_COMP_DIR).map(str::to_string),
+                    name: unit.text(DW_AT_NAME)?.to_string(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// `--source-paths`: the compilation directories and source files baked into
+/// the debug info, warning about paths that reveal a home directory and
+/// suggesting the prefix maps that would make them reproducible.
+pub fn display_source_paths(path: &str, loader: &Loader) {
+    let sources = loader.source_paths();
+    let directories: BTreeSet<&str> = sources
+        .iter()
+        .filter_map(|source| source.comp_dir.as_deref())
+        .collect();
+    let files: BTreeSet<String> = sources.iter().map(SourcePath::full_path).collect();
+    output::begin(path);
+    display_line("Compile Units", &sources.len().to_string());
+    display_line("Compilation Directories", &directories.len().to_string());
+    for directory in &directories {
+        output::item(1, directory);
+    }
+    display_line("Source Files", &files.len().to_string());
+    for file in &files {
+        output::item(1, file);
+    }
+
+    let leaks: Vec<&String> = files
+        .iter()
+        .filter(|file| home_directory(file).is_some())
+        .collect();
+    display_line("Home Directory Paths", &leaks.len().to_string());
+    // One map per leaking compilation directory, and the home directory itself
+    // for files named by absolute paths outside them.
+    let mut remaps = BTreeSet::new();
+    for file in &leaks {
+        let prefix = directories
+            .iter()
+            .filter(|directory| home_directory(directory).is_some())
+            .find(|directory| file.starts_with(*directory))
+            .map(|directory| directory.to_string())
+            .or_else(|| home_directory(file));
+        remaps.extend(prefix);
+    }
+    for prefix in &remaps {
+        output::item(
+            1,
+            &format!(
+                "-ffile-prefix-map={}=.  (rustc: --remap-path-prefix {}=.)",
+                prefix, prefix
+            ),
+        );
+    }
+    output::end();
+
+    let warnings: Vec<Finding> = remaps
+        .iter()
+        .map(|prefix| Finding {
+            severity: Severity::Warning,
+            check: HOME_PATH.to_string(),
+            message: format!(
+                "debug info names paths under {}; build with -ffile-prefix-map={}=.",
+                prefix, prefix
+            ),
+        })
+        .collect();
+    finding::record(path, [HOME_PATH], &warnings);
+}
@@ -0,0 +1,134 @@
+use crate::attributes::read_uleb128;
+use crate::output;
+use crate::section::{SectionHeader, SHT_REL, SHT_RELA, SHT_SYMTAB};
+use crate::symbol::{STT_FUNC, STT_SECTION};
+use crate::{display_line, Loader};
+
+const STACK_SIZES_SECTION: &str = ".stack_sizes";
+const ET_REL: u16 = 1;
+
+pub struct StackSize {
+    pub function: String,
+    pub address: u64,
+    pub size: u64,
+}
+
+impl Loader {
+    /// In a relocatable object the addresses in .stack_sizes are zero and a relocation
+    /// names the function, often as a section symbol plus an offset into that section.
+    fn stack_size_relocation_targets(
+        &self,
+        section: &SectionHeader,
+        sections: &[SectionHeader],
+    ) -> Vec<(u64, String)> {
+        let Some(symtab) = sections.iter().find(|s| s.sh_type == SHT_SYMTAB) else {
+            return Vec::new();
+        };
+        let symbols = self.symbols_in(symtab, sections);
+        sections
+            .iter()
+            .filter(|table| table.sh_type == SHT_REL || table.sh_type == SHT_RELA)
+            .filter(|table| table.sh_info as usize == section.index)
+            .flat_map(|table| self.relocations_in(table, sections))
+            .filter_map(|reloc| {
+                let symbol = symbols.get(reloc.r_sym as usize)?;
+                let name = if symbol.kind() == STT_SECTION {
+                    symbols
+                        .iter()
+                        .find(|f| {
+                            f.kind() == STT_FUNC
+                                && f.st_shndx == symbol.st_shndx
+                                && f.st_value == reloc.r_addend as u64
+                        })?
+                        .name
+                        .clone()
+                } else {
+                    symbol.name.clone()
+                };
+                Some((reloc.r_offset, name))
+            })
+            .collect()
+    }
+
+    /// Reads every `.stack_sizes` section LLVM emits with `-fstack-size-section`: a list
+    /// of function addresses, each followed by the ULEB128 stack size of that function.
+    pub fn stack_sizes(&self) -> Vec<StackSize> {
+        let sections = self.section_headers();
+        let functions: Vec<_> = self
+            .static_symbols()
+            .into_iter()
+            .filter(|symbol| symbol.kind() == STT_FUNC)
+            .collect();
+        let mut sizes = Vec::new();
+        for section in sections.iter().filter(|s| s.name == STACK_SIZES_SECTION) {
+            let targets = if self.raw_e_type() == ET_REL {
+                self.stack_size_relocation_targets(section, &sections)
+            } else {
+                Vec::new()
+            };
+            let data = self.section_data(section);
+            let mut offset = 0;
+            while offset + self.addr_size() <= data.len() {
+                let Some(address) = self.read_addr(section.sh_offset as usize + offset) else {
+                    break;
+                };
+                let entry_offset = offset as u64;
+                offset += self.addr_size();
+                let Some((size, length)) = read_uleb128(&data[offset..]) else {
+                    break;
+                };
+                offset += length;
+                let function = targets
+                    .iter()
+                    .find(|(r_offset, _)| *r_offset == entry_offset)
+                    .map(|(_, name)| name.clone())
+                    .or_else(|| {
+                        functions
+                            .iter()
+                            .find(|symbol| symbol.st_value == address)
+                            .map(|symbol| symbol.name.clone())
+                    })
+                    .unwrap_or_else(|| format!("{:#x}", address));
+                sizes.push(StackSize {
+                    function,
+                    address,
+                    size,
+                });
+            }
+        }
+        sizes.sort_by(|a, b| b.size.cmp(&a.size).then(a.address.cmp(&b.address)));
+        sizes
+    }
+}
+
+/// Prints per-function stack usage, largest first, and returns how many functions
+/// exceed `max_stack`.
+pub fn display_stack_sizes(path: &str, loader: &Loader, max_stack: Option<u64>) -> usize {
+    let sizes = loader.stack_sizes();
+    output::begin(path);
+    if sizes.is_empty() {
+        display_line("Stack Sizes", "No .stack_sizes section");
+        output::end();
+        return 0;
+    }
+    display_line("Functions With Stack Sizes", &sizes.len().to_string());
+    let over = match max_stack {
+        Some(limit) => sizes.iter().filter(|entry| entry.size > limit).count(),
+        None => 0,
+    };
+    if let Some(limit) = max_stack {
+        display_line(&format!("Over {} bytes", limit), &over.to_string());
+    }
+    for entry in &sizes {
+        let mark = match max_stack {
+            Some(limit) if entry.size > limit => "  (over limit)",
+            _ => "",
+        };
+        output::item(
+            1,
+            &format!("{:>8} bytes  {}{}", entry.size, entry.function, mark),
+        );
+    }
+    output::end();
+    over
+}
@@ -0,0 +1,151 @@
+use crate::grep::locate;
+use crate::output;
+use crate::section::{SHF_ALLOC, SHF_WRITE};
+use crate::segment::PF_W;
+use crate::symbol::{Symbol, SHN_UNDEF, STT_FILE, STT_SECTION};
+use crate::{display_line, Loader};
+
+/// Shortest run of printable characters reported, as `strings` does by default.
+const MIN_LENGTH: usize = 4;
+
+/// First of the reserved section indices such as SHN_ABS and SHN_COMMON.
+const SHN_LORESERVE: u16 = 0xff00;
+
+/// A run of printable ASCII characters in the file.
+struct Found<'a> {
+    offset: u64,
+    text: &'a str,
+}
+
+fn is_printable(byte: u8) -> bool {
+    byte == b'\t' || (0x20..0x7f).contains(&byte)
+}
+
+/// The runs of at least MIN_LENGTH printable characters, like `strings -a`.
+fn printable_runs(bytes: &[u8]) -> Vec<Found<'_>> {
+    let mut found = Vec::new();
+    let mut start = 0;
+    // A NUL past the end closes the last run.
+    for (index, &byte) in bytes.iter().chain([0].iter()).enumerate() {
+        if is_printable(byte) {
+            continue;
+        }
+        if index - start >= MIN_LENGTH {
+            // Only ASCII is kept, so the run is valid UTF-8.
+            if let Ok(text) = std::str::from_utf8(&bytes[start..index]) {
+                found.push(Found {
+                    offset: start as u64,
+                    text,
+                });
+            }
+        }
+        start = index + 1;
+    }
+    found
+}
+
+/// Symbols that can name a string's location, sorted by (section, value). In
+/// relocatable objects values are offsets into their section, elsewhere they are
+/// addresses and the section is left out of the key.
+fn anchors(loader: &Loader) -> Vec<(usize, Symbol)> {
+    let mut symbols = loader.static_symbols();
+    if symbols.is_empty() {
+        symbols = loader.dynamic_symbols();
+    }
+    let relocatable = loader.is_relocatable();
+    let mut anchors: Vec<(usize, Symbol)> = symbols
+        .into_iter()
+        .filter(|symbol| {
+            !symbol.name.is_empty()
+                && symbol.st_shndx != SHN_UNDEF
+                && symbol.st_shndx < SHN_LORESERVE
+                && !matches!(symbol.kind(), STT_SECTION | STT_FILE)
+        })
+        .map(|symbol| {
+            let key = if relocatable {
+                symbol.st_shndx as usize
+            } else {
+                0
+            };
+            (key, symbol)
+        })
+        .collect();
+    anchors.sort_by_key(|(key, symbol)| (*key, symbol.st_value));
+    anchors
+}
+
+/// The closest symbol at or before `position`, as `name+0x10`.
+fn nearest(anchors: &[(usize, Symbol)], key: usize, position: u64) -> Option<String> {
+    let index =
+        anchors.partition_point(|(other, symbol)| (*other, symbol.st_value) <= (key, position));
+    let (other, symbol) = &anchors[index.checked_sub(1)?];
+    if *other != key {
+        return None;
+    }
+    Some(match position - symbol.st_value {
+        0 => symbol.name.clone(),
+        delta => format!("{}+{:#x}", symbol.name, delta),
+    })
+}
+
+/// `--strings-scan`: the printable strings of the whole file, each with its
+/// offset, the section it lies in, whether it is mapped read-only (`ro`) or
+/// writable (`rw`) or not loaded at all (`-`), and the nearest symbol before it.
+pub fn display_strings(path: &str, loader: &Loader) {
+    let sections = loader.section_headers();
+    let segments = loader.program_headers();
+    let anchors = anchors(loader);
+    let relocatable = loader.is_relocatable();
+    let found = printable_runs(loader.bytes());
+    let mut lines = Vec::with_capacity(found.len());
+    let (mut read_only, mut writable) = (0, 0);
+    for string in &found {
+        let (section, segment) = locate(&sections, &segments, string.offset);
+        // Relocatable objects have no segments; their sections' flags tell instead.
+        let writable_flag = match (segment, section) {
+            (Some(segment), _) => Some(segment.p_flags & PF_W != 0),
+            (None, Some(section)) if relocatable && section.sh_flags & SHF_ALLOC != 0 => {
+                Some(section.sh_flags & SHF_WRITE != 0)
+            }
+            _ => None,
+        };
+        let memory = match writable_flag {
+            Some(true) => {
+                writable += 1;
+                "rw"
+            }
+            Some(false) => {
+                read_only += 1;
+                "ro"
+            }
+            None => "-",
+        };
+        let symbol = match (section, segment) {
+            (Some(section), _) if relocatable => {
+                nearest(&anchors, section.index, string.offset - section.sh_offset)
+            }
+            (_, Some(segment)) if !relocatable => nearest(
+                &anchors,
+                0,
+                segment.p_vaddr + (string.offset - segment.p_offset),
+            ),
+            _ => None,
+        };
+        lines.push(format!(
+            "{:#010x} {:<20} {:<2} {:<32} {}",
+            string.offset,
+            section.map_or("-", |section| section.name.as_str()),
+            memory,
+            symbol.as_deref().unwrap_or("-"),
+            string.text
+        ));
+    }
+    output::begin(path);
+    display_line("Strings", &found.len().to_string());
+    display_line("Read-Only", &read_only.to_string());
+    display_line("Writable", &writable.to_string());
+    for line in &lines {
+        output::item(1, line);
+    }
+    output::end();
+}
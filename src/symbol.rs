@@ -0,0 +1,152 @@
+use log::debug;
+
+use crate::limits;
+use crate::section::{SectionHeader, SHT_DYNSYM, SHT_SYMTAB};
+use crate::timings::{self, Phase};
+use crate::Loader;
+
+pub const STB_LOCAL: u8 = 0;
+pub const STB_GLOBAL: u8 = 1;
+pub const STB_WEAK: u8 = 2;
+
+pub const STT_FUNC: u8 = 2;
+pub const STT_SECTION: u8 = 3;
+pub const STT_FILE: u8 = 4;
+
+pub const SHN_UNDEF: u16 = 0;
+/// Tentative definition (uninitialised C global) the linker allocates in .bss.
+pub const SHN_COMMON: u16 = 0xfff2;
+
+#[derive(Clone, Debug)]
+pub struct Symbol {
+    pub name: String,
+    pub st_value: u64,
+    pub st_size: u64,
+    pub st_info: u8,
+    pub st_other: u8,
+    pub st_shndx: u16,
+}
+
+impl Symbol {
+    pub fn bind(&self) -> u8 {
+        self.st_info >> 4
+    }
+
+    pub fn kind(&self) -> u8 {
+        self.st_info & 0xf
+    }
+}
+
+impl Loader {
+    fn read_symbol(&self, base: usize, strtab_offset: usize) -> Option<Symbol> {
+        // Elf64_Sym groups the one-byte fields before st_value, Elf32_Sym puts them after.
+        let (st_name, st_value, st_size, st_info, st_other, st_shndx) = if self.is_64bit() {
+            (
+                self.read_u32(base)?,
+                self.read_u64(base + 8)?,
+                self.read_u64(base + 16)?,
+                self.read_u8(base + 4)?,
+                self.read_u8(base + 5)?,
+                self.read_u16(base + 6)?,
+            )
+        } else {
+            (
+                self.read_u32(base)?,
+                self.read_u32(base + 4)?.into(),
+                self.read_u32(base + 8)?.into(),
+                self.read_u8(base + 12)?,
+                self.read_u8(base + 13)?,
+                self.read_u16(base + 14)?,
+            )
+        };
+        let name = self
+            .read_cstr(strtab_offset + st_name as usize)
+            .unwrap_or("")
+            .to_string();
+        Some(Symbol {
+            name,
+            st_value,
+            st_size,
+            st_info,
+            st_other,
+            st_shndx,
+        })
+    }
+
+    /// Returns the symbols of a SHT_SYMTAB or SHT_DYNSYM section in table order.
+    pub fn symbols_in(&self, table: &SectionHeader, sections: &[SectionHeader]) -> Vec<Symbol> {
+        timings::measure(Phase::Symbols, || self.read_symbol_table(table, sections))
+    }
+
+    fn read_symbol_table(&self, table: &SectionHeader, sections: &[SectionHeader]) -> Vec<Symbol> {
+        let Some(strtab) = sections.get(table.sh_link as usize) else {
+            debug!(
+                "{}: sh_link {} is not a section, cannot name its symbols",
+                table.name, table.sh_link
+            );
+            return Vec::new();
+        };
+        let entry_size = if table.sh_entsize != 0 {
+            table.sh_entsize as usize
+        } else if self.is_64bit() {
+            24
+        } else {
+            16
+        };
+        let mut count = table.sh_size as usize / entry_size;
+        let max_symbols = limits::get().max_symbols;
+        if count > max_symbols {
+            debug!(
+                "{}: reading {} of {} symbols, --max-symbols is {}",
+                table.name, max_symbols, count, max_symbols
+            );
+            count = max_symbols;
+        }
+        let symbols: Vec<Symbol> = (0..count)
+            .map_while(|index| {
+                self.read_symbol(
+                    table.sh_offset as usize + index * entry_size,
+                    strtab.sh_offset as usize,
+                )
+            })
+            .collect();
+        if symbols.len() < count {
+            debug!(
+                "{}: symbol table is truncated: read {} of {} entries",
+                table.name,
+                symbols.len(),
+                count
+            );
+        }
+        symbols
+    }
+
+    pub fn dynamic_symbols(&self) -> Vec<Symbol> {
+        self.symbols_of_type(SHT_DYNSYM)
+    }
+
+    /// The symbol table, with the symbols of an attached debug file merged in.
+    pub fn static_symbols(&self) -> Vec<Symbol> {
+        let mut symbols = self.symbols_of_type(SHT_SYMTAB);
+        if let Some(debug) = self.debug_file() {
+            for symbol in debug.symbols_of_type(SHT_SYMTAB) {
+                if !symbols
+                    .iter()
+                    .any(|known| known.name == symbol.name && known.st_value == symbol.st_value)
+                {
+                    symbols.push(symbol);
+                }
+            }
+        }
+        symbols
+    }
+
+    pub(crate) fn symbols_of_type(&self, sh_type: u32) -> Vec<Symbol> {
+        let sections = self.section_headers();
+        sections
+            .iter()
+            .find(|section| section.sh_type == sh_type)
+            .map(|table| self.symbols_in(table, &sections))
+            .unwrap_or_default()
+    }
+}
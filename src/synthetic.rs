@@ -0,0 +1,303 @@
+//! Writes synthetic x86-64 ELF files of any size for the benchmarks and
+//! `selftest`: a header, `sections` small code sections, one large data
+//! section of `padding` bytes, and a symbol table of `symbols` function
+//! symbols (every 64th one undefined).
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+const ET_REL: u16 = 1;
+const ET_EXEC: u16 = 2;
+const EM_X86_64: u16 = 62;
+const PT_LOAD: u32 = 1;
+
+const SHT_PROGBITS: u32 = 1;
+const SHT_SYMTAB: u32 = 2;
+const SHT_STRTAB: u32 = 3;
+const SHF_WRITE: u64 = 1;
+const SHF_ALLOC: u64 = 2;
+const SHF_EXECINSTR: u64 = 4;
+
+const SHN_LORESERVE: usize = 0xff00;
+const SHN_XINDEX: u16 = 0xffff;
+
+const EHDR_SIZE: u64 = 64;
+const PHDR_SIZE: u64 = 56;
+const SHDR_SIZE: u64 = 64;
+const SYM_SIZE: u64 = 24;
+const FUNCTION_SIZE: u64 = 16;
+const LOAD_ADDRESS: u64 = 0x400000;
+
+pub struct Spec {
+    /// ET_EXEC with one PT_LOAD covering the file, instead of ET_REL.
+    pub executable: bool,
+    pub sections: usize,
+    pub symbols: usize,
+    /// Size of the `.data` section, which is what makes the file large.
+    pub padding: u64,
+}
+
+struct Section {
+    name: u32,
+    sh_type: u32,
+    flags: u64,
+    offset: u64,
+    size: u64,
+    link: u32,
+    info: u32,
+    align: u64,
+    entsize: u64,
+}
+
+fn align(offset: u64, alignment: u64) -> u64 {
+    offset.div_ceil(alignment) * alignment
+}
+
+/// Appends `name` to a string table and returns its offset.
+fn add_string(table: &mut Vec<u8>, name: &str) -> u32 {
+    let offset = table.len() as u32;
+    table.extend_from_slice(name.as_bytes());
+    table.push(0);
+    offset
+}
+
+struct Writer {
+    out: BufWriter<File>,
+    offset: u64,
+}
+
+impl Writer {
+    fn bytes(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.offset += bytes.len() as u64;
+        self.out.write_all(bytes)
+    }
+
+    fn pad_to(&mut self, offset: u64) -> io::Result<()> {
+        let zeros = vec![0; (offset - self.offset) as usize];
+        self.bytes(&zeros)
+    }
+
+    /// Fills `size` bytes with non-zero data, so the file is not sparse and
+    /// anything that reads it really has to page it in.
+    fn fill(&mut self, size: u64) -> io::Result<()> {
+        let chunk = vec![0x5a; 1 << 20];
+        let mut left = size;
+        while left > 0 {
+            let len = left.min(chunk.len() as u64);
+            self.bytes(&chunk[..len as usize])?;
+            left -= len;
+        }
+        Ok(())
+    }
+}
+
+/// Writes the file described by `spec` to `path`. Section counts from 0xff00 up
+/// use extended numbering (the real count in section 0), like large objects do.
+pub fn write_elf(path: &Path, spec: &Spec) -> io::Result<()> {
+    let code_start = if spec.executable {
+        EHDR_SIZE + PHDR_SIZE
+    } else {
+        EHDR_SIZE
+    };
+    let data_index = spec.sections + 1;
+    let symtab_index = data_index + 1;
+    let strtab_index = symtab_index + 1;
+    let shstrtab_index = strtab_index + 1;
+    let shnum = shstrtab_index + 1;
+
+    let mut shstrtab = vec![0];
+    let mut sections = Vec::with_capacity(shnum);
+    let mut offset = code_start;
+    for index in 0..spec.sections {
+        sections.push(Section {
+            name: add_string(&mut shstrtab, &format!(".text.f{}", index)),
+            sh_type: SHT_PROGBITS,
+            flags: SHF_ALLOC | SHF_EXECINSTR,
+            offset,
+            size: FUNCTION_SIZE,
+            link: 0,
+            info: 0,
+            align: FUNCTION_SIZE,
+            entsize: 0,
+        });
+        offset += FUNCTION_SIZE;
+    }
+    let data_offset = align(offset, 4096);
+    sections.push(Section {
+        name: add_string(&mut shstrtab, ".data"),
+        sh_type: SHT_PROGBITS,
+        flags: SHF_ALLOC | SHF_WRITE,
+        offset: data_offset,
+        size: spec.padding,
+        link: 0,
+        info: 0,
+        align: 4096,
+        entsize: 0,
+    });
+
+    // Symbols only point at sections below SHN_LORESERVE, so no SHT_SYMTAB_SHNDX is needed.
+    let defining_sections = spec.sections.min(SHN_LORESERVE - 1);
+    let mut strtab = vec![0];
+    let mut symtab = vec![0; SYM_SIZE as usize];
+    for index in 0..spec.symbols {
+        let name = add_string(&mut strtab, &format!("fn_{}", index));
+        let (info, shndx, value, size) = if index % 64 == 63 || defining_sections == 0 {
+            (0x10, 0u16, 0u64, 0u64)
+        } else {
+            let section = index % defining_sections;
+            let value = if spec.executable {
+                LOAD_ADDRESS + code_start + section as u64 * FUNCTION_SIZE
+            } else {
+                0
+            };
+            (0x12, section as u16 + 1, value, FUNCTION_SIZE)
+        };
+        symtab.extend_from_slice(&name.to_le_bytes());
+        symtab.push(info);
+        symtab.push(0);
+        symtab.extend_from_slice(&shndx.to_le_bytes());
+        symtab.extend_from_slice(&value.to_le_bytes());
+        symtab.extend_from_slice(&size.to_le_bytes());
+    }
+
+    let symtab_offset = align(data_offset + spec.padding, 8);
+    let strtab_offset = symtab_offset + symtab.len() as u64;
+    sections.push(Section {
+        name: add_string(&mut shstrtab, ".symtab"),
+        sh_type: SHT_SYMTAB,
+        flags: 0,
+        offset: symtab_offset,
+        size: symtab.len() as u64,
+        link: strtab_index as u32,
+        info: 1,
+        align: 8,
+        entsize: SYM_SIZE,
+    });
+    sections.push(Section {
+        name: add_string(&mut shstrtab, ".strtab"),
+        sh_type: SHT_STRTAB,
+        flags: 0,
+        offset: strtab_offset,
+        size: strtab.len() as u64,
+        link: 0,
+        info: 0,
+        align: 1,
+        entsize: 0,
+    });
+    let shstrtab_offset = strtab_offset + strtab.len() as u64;
+    let name = add_string(&mut shstrtab, ".shstrtab");
+    sections.push(Section {
+        name,
+        sh_type: SHT_STRTAB,
+        flags: 0,
+        offset: shstrtab_offset,
+        size: shstrtab.len() as u64,
+        link: 0,
+        info: 0,
+        align: 1,
+        entsize: 0,
+    });
+    let shoff = align(shstrtab_offset + shstrtab.len() as u64, 8);
+    let file_size = shoff + shnum as u64 * SHDR_SIZE;
+
+    let mut writer = Writer {
+        out: BufWriter::new(File::create(path)?),
+        offset: 0,
+    };
+
+    let e_type = if spec.executable { ET_EXEC } else { ET_REL };
+    let entry = if spec.executable && spec.sections > 0 {
+        LOAD_ADDRESS + code_start
+    } else {
+        0
+    };
+    let (phoff, phnum) = if spec.executable {
+        (EHDR_SIZE, 1u16)
+    } else {
+        (0, 0)
+    };
+    let e_shnum = if shnum < SHN_LORESERVE {
+        shnum as u16
+    } else {
+        0
+    };
+    let e_shstrndx = if shstrtab_index < SHN_LORESERVE {
+        shstrtab_index as u16
+    } else {
+        SHN_XINDEX
+    };
+    let mut header = vec![0x7f, b'E', b'L', b'F', 2, 1, 1];
+    header.resize(16, 0);
+    header.extend_from_slice(&e_type.to_le_bytes());
+    header.extend_from_slice(&EM_X86_64.to_le_bytes());
+    header.extend_from_slice(&1u32.to_le_bytes());
+    header.extend_from_slice(&entry.to_le_bytes());
+    header.extend_from_slice(&phoff.to_le_bytes());
+    header.extend_from_slice(&shoff.to_le_bytes());
+    header.extend_from_slice(&0u32.to_le_bytes());
+    header.extend_from_slice(&(EHDR_SIZE as u16).to_le_bytes());
+    header.extend_from_slice(&(PHDR_SIZE as u16).to_le_bytes());
+    header.extend_from_slice(&phnum.to_le_bytes());
+    header.extend_from_slice(&(SHDR_SIZE as u16).to_le_bytes());
+    header.extend_from_slice(&e_shnum.to_le_bytes());
+    header.extend_from_slice(&e_shstrndx.to_le_bytes());
+    writer.bytes(&header)?;
+
+    if spec.executable {
+        let mut phdr = Vec::new();
+        phdr.extend_from_slice(&PT_LOAD.to_le_bytes());
+        phdr.extend_from_slice(&7u32.to_le_bytes());
+        phdr.extend_from_slice(&0u64.to_le_bytes());
+        phdr.extend_from_slice(&LOAD_ADDRESS.to_le_bytes());
+        phdr.extend_from_slice(&LOAD_ADDRESS.to_le_bytes());
+        phdr.extend_from_slice(&file_size.to_le_bytes());
+        phdr.extend_from_slice(&file_size.to_le_bytes());
+        phdr.extend_from_slice(&4096u64.to_le_bytes());
+        writer.bytes(&phdr)?;
+    }
+
+    // Every function is a `ret` followed by `int3` padding.
+    let mut function = [0xcc; FUNCTION_SIZE as usize];
+    function[0] = 0xc3;
+    for _ in 0..spec.sections {
+        writer.bytes(&function)?;
+    }
+    writer.pad_to(data_offset)?;
+    writer.fill(spec.padding)?;
+    writer.pad_to(symtab_offset)?;
+    writer.bytes(&symtab)?;
+    writer.bytes(&strtab)?;
+    writer.bytes(&shstrtab)?;
+    writer.pad_to(shoff)?;
+
+    // Section 0 carries the real count and string table index when they do not fit.
+    let mut initial = vec![0; SHDR_SIZE as usize];
+    if e_shnum == 0 {
+        initial[32..40].copy_from_slice(&(shnum as u64).to_le_bytes());
+    }
+    if e_shstrndx == SHN_XINDEX {
+        initial[40..44].copy_from_slice(&(shstrtab_index as u32).to_le_bytes());
+    }
+    writer.bytes(&initial)?;
+    for section in &sections {
+        let mut shdr = Vec::with_capacity(SHDR_SIZE as usize);
+        shdr.extend_from_slice(&section.name.to_le_bytes());
+        shdr.extend_from_slice(&section.sh_type.to_le_bytes());
+        shdr.extend_from_slice(&section.flags.to_le_bytes());
+        let address = if spec.executable && section.flags & SHF_ALLOC != 0 {
+            LOAD_ADDRESS + section.offset
+        } else {
+            0
+        };
+        shdr.extend_from_slice(&address.to_le_bytes());
+        shdr.extend_from_slice(&section.offset.to_le_bytes());
+        shdr.extend_from_slice(&section.size.to_le_bytes());
+        shdr.extend_from_slice(&section.link.to_le_bytes());
+        shdr.extend_from_slice(&section.info.to_le_bytes());
+        shdr.extend_from_slice(&section.align.to_le_bytes());
+        shdr.extend_from_slice(&section.entsize.to_le_bytes());
+        writer.bytes(&shdr)?;
+    }
+    writer.out.flush()
+}
@@ -0,0 +1,43 @@
+//! `--template FILE`: renders the parsed files through a minijinja (Jinja2
+//! syntax) template, for reports in HTML, Markdown or whatever else a team
+//! wants without changes to the crate.
+//!
+//! The template gets `files`, the document of [`parsed_elf`] for every input
+//! in order. Templates named `*.html`, `*.htm` or `*.xml` escape what they
+//! print.
+
+use std::fs;
+use std::path::Path;
+
+use minijinja::{context, Environment, Value};
+
+use crate::model::parsed_elf;
+use crate::{output, Loader};
+
+/// Renders `path` over every target, failing the template if it cannot be
+/// read, parsed or rendered.
+pub fn display_template(targets: &[(String, Loader)], path: &str) -> Vec<(String, String)> {
+    let source = match fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(error) => return vec![(path.to_string(), error.to_string())],
+    };
+    let name = Path::new(path)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.to_string());
+    let files: Vec<serde_json::Value> = targets
+        .iter()
+        .map(|(label, loader)| parsed_elf(label, loader))
+        .collect();
+    let environment = Environment::new();
+    let rendered = environment
+        .template_from_named_str(&name, &source)
+        .and_then(|template| template.render(context! { files => Value::from_serialize(&files) }));
+    match rendered {
+        Ok(text) => {
+            output::message(text.strip_suffix('\n').unwrap_or(&text));
+            Vec::new()
+        }
+        Err(error) => vec![(path.to_string(), error.to_string())],
+    }
+}
@@ -0,0 +1,126 @@
+use crate::dynamic::{DF_TEXTREL, DT_FLAGS, DT_TEXTREL};
+use crate::output;
+use crate::section::SHF_ALLOC;
+use crate::segment::{PF_W, PT_LOAD};
+use crate::symbol::{STB_LOCAL, STT_FILE, STT_FUNC};
+use crate::{display_line, Loader};
+
+/// A dynamic relocation that patches a read-only (usually executable) segment.
+pub struct TextRelocation {
+    pub address: u64,
+    pub r_type: String,
+    pub symbol: String,
+    pub section: String,
+    /// The function containing the patched address, if the symbol table knows it.
+    pub function: Option<String>,
+    /// The source file (STT_FILE) the containing function was compiled from.
+    pub origin: Option<String>,
+}
+
+impl Loader {
+    /// Whether the dynamic section announces text relocations (DT_TEXTREL or DF_TEXTREL).
+    pub fn has_textrel_flag(&self) -> bool {
+        self.dynamic_value(DT_TEXTREL).is_some()
+            || self.dynamic_value(DT_FLAGS).unwrap_or(0) & DF_TEXTREL != 0
+    }
+
+    fn is_read_only_addr(&self, addr: u64) -> bool {
+        self.program_headers()
+            .iter()
+            .filter(|header| header.p_type == PT_LOAD)
+            .find(|header| addr >= header.p_vaddr && addr < header.end_vaddr())
+            .is_some_and(|header| header.p_flags & PF_W == 0)
+    }
+
+    pub fn text_relocations(&self) -> Vec<TextRelocation> {
+        let sections = self.section_headers();
+        // Only relocations the dynamic linker applies at load time matter here.
+        let dynamic_tables: Vec<_> = sections
+            .iter()
+            .filter(|section| section.sh_flags & SHF_ALLOC != 0)
+            .map(|section| section.name.clone())
+            .collect();
+        let symbols = self.static_symbols();
+
+        self.relocations()
+            .into_iter()
+            .filter(|reloc| dynamic_tables.contains(&reloc.section))
+            .filter(|reloc| self.is_read_only_addr(reloc.r_offset))
+            .map(|reloc| {
+                let section = sections
+                    .iter()
+                    .find(|section| section.contains_addr(reloc.r_offset))
+                    .map(|section| section.name.clone())
+                    .unwrap_or_default();
+                let function_index = symbols.iter().position(|symbol| {
+                    symbol.kind() == STT_FUNC
+                        && reloc.r_offset >= symbol.st_value
+                        && reloc.r_offset < symbol.st_value.saturating_add(symbol.st_size.max(1))
+                });
+                // Local symbols follow the STT_FILE entry of the object they came from.
+                let origin = function_index.and_then(|index| {
+                    symbols[..index]
+                        .iter()
+                        .rev()
+                        .find(|symbol| symbol.kind() == STT_FILE)
+                        .filter(|_| symbols[index].bind() == STB_LOCAL)
+                        .map(|symbol| symbol.name.clone())
+                });
+                TextRelocation {
+                    address: reloc.r_offset,
+                    r_type: self.relocation_type_name(reloc.r_type),
+                    symbol: reloc.symbol,
+                    section,
+                    function: function_index.map(|index| symbols[index].name.clone()),
+                    origin,
+                }
+            })
+            .collect()
+    }
+}
+
+pub fn display_textrel(path: &str, loader: &Loader) {
+    let relocations = loader.text_relocations();
+    output::begin(path);
+    display_line(
+        "DT_TEXTREL",
+        if loader.has_textrel_flag() {
+            "Present"
+        } else {
+            "Not present"
+        },
+    );
+    display_line("Text Relocations", &relocations.len().to_string());
+    for reloc in &relocations {
+        let symbol = if reloc.symbol.is_empty() {
+            "-"
+        } else {
+            &reloc.symbol
+        };
+        output::item(
+            1,
+            &format!(
+                "{:#x} {:<24} {:<20} {}",
+                reloc.address, reloc.r_type, symbol, reloc.section
+            ),
+        );
+        match (&reloc.function, &reloc.origin) {
+            (Some(function), Some(origin)) => {
+                output::item(2, &format!("in {} (from {})", function, origin))
+            }
+            (Some(function), None) => output::item(2, &format!("in {}", function)),
+            _ => {}
+        }
+    }
+    if !relocations.is_empty() {
+        output::item(
+            1,
+            "Text relocations keep these pages private per process and are rejected by",
+        );
+        output::item(
+            1,
+            "hardened kernels and Android; rebuild the listed objects with -fPIC.",
+        );
+    }
+    output::end();
+}
@@ -0,0 +1,158 @@
+use crate::arch::{arch_ext, TextModel};
+use crate::output;
+use crate::section::{SHF_EXECINSTR, SHT_NOBITS};
+use crate::segment::{PF_X, PT_LOAD};
+use crate::{display_line, Loader};
+
+/// Shortest run of padding counted, so that single NOP-like bytes inside other
+/// instructions are not mistaken for padding.
+const MIN_SLED: usize = 4;
+
+/// Byte values listed in the histogram.
+const TOP_BYTES: usize = 8;
+
+impl Loader {
+    /// The code of the file with the address it is loaded at: its executable
+    /// sections, or the executable PT_LOAD segments when it has no sections.
+    fn code_regions(&self) -> Vec<(u64, &[u8])> {
+        let sections: Vec<_> = self
+            .section_headers()
+            .into_iter()
+            .filter(|section| section.sh_flags & SHF_EXECINSTR != 0)
+            .filter(|section| section.sh_type != SHT_NOBITS)
+            .map(|section| (section.sh_addr, self.section_data(&section)))
+            .collect();
+        if !sections.is_empty() {
+            return sections;
+        }
+        self.program_headers()
+            .into_iter()
+            .filter(|segment| segment.p_type == PT_LOAD && segment.p_flags & PF_X != 0)
+            .filter_map(|segment| {
+                let start = segment.p_offset as usize;
+                let end = start.saturating_add(segment.p_filesz as usize);
+                Some((segment.p_vaddr, self.bytes().get(start..end)?))
+            })
+            .collect()
+    }
+}
+
+/// The bytes of `code` in runs of at least MIN_SLED bytes of the model's
+/// padding encodings, looked for at instruction boundaries.
+fn padding_bytes(model: &TextModel, code: &[u8]) -> u64 {
+    let (mut total, mut run) = (0, 0);
+    let mut offset = 0;
+    while offset < code.len() {
+        let found = model
+            .padding
+            .iter()
+            .filter(|padding| code[offset..].starts_with(padding))
+            .map(|padding| padding.len())
+            .max();
+        match found {
+            Some(length) => {
+                run += length;
+                offset += length;
+                continue;
+            }
+            None => {
+                if run >= MIN_SLED {
+                    total += run as u64;
+                }
+                run = 0;
+            }
+        }
+        offset += model.alignment;
+    }
+    if run >= MIN_SLED {
+        total += run as u64;
+    }
+    total
+}
+
+/// Decodes the code when the disassembler is built in and knows the machine.
+#[cfg(feature = "disassembler")]
+fn decode(loader: &Loader, regions: &[(u64, &[u8])]) -> Option<(u64, u64)> {
+    let mut totals = (0, 0);
+    for (address, code) in regions {
+        let (instructions, padding) = crate::x86::decode(loader, *address, code)?;
+        totals = (totals.0 + instructions, totals.1 + padding);
+    }
+    Some(totals)
+}
+
+#[cfg(not(feature = "disassembler"))]
+fn decode(_loader: &Loader, _regions: &[(u64, &[u8])]) -> Option<(u64, u64)> {
+    None
+}
+
+fn percent(part: u64, whole: u64) -> String {
+    if whole == 0 {
+        return "0.00%".to_string();
+    }
+    format!("{:.2}%", part as f64 * 100.0 / whole as f64)
+}
+
+/// `--text-stats`: the size of the code, how many instructions it holds, how
+/// much of it is NOP and INT3 padding, and its most common byte values. The
+/// instruction count is decoded with the `disassembler` feature on x86, and
+/// otherwise estimated from the family's average instruction length.
+pub fn display_text_stats(path: &str, loader: &Loader) {
+    let regions = loader.code_regions();
+    let size: u64 = regions.iter().map(|(_, code)| code.len() as u64).sum();
+    let model = arch_ext(loader.raw_e_machine()).and_then(|arch| arch.text_model(loader));
+    output::begin(path);
+    display_line("Text Size", &size.to_string());
+    match (decode(loader, &regions), &model) {
+        (Some((instructions, padding)), _) => {
+            display_line("Instructions", &format!("{} (decoded)", instructions));
+            display_line(
+                "Padding",
+                &format!("{} ({})", padding, percent(padding, size)),
+            );
+        }
+        (None, Some(model)) => {
+            let instructions = (size as f64 / model.average_length).round() as u64;
+            display_line(
+                "Instructions",
+                &format!(
+                    "~{} (estimated at {} bytes each)",
+                    instructions, model.average_length
+                ),
+            );
+            let padding = regions
+                .iter()
+                .map(|(_, code)| padding_bytes(model, code))
+                .sum();
+            display_line(
+                "Padding",
+                &format!("{} ({})", padding, percent(padding, size)),
+            );
+        }
+        (None, None) => {
+            display_line("Instructions", "Unknown");
+            display_line("Padding", "Unknown");
+        }
+    }
+    let mut histogram = [0u64; 256];
+    for (_, code) in &regions {
+        for &byte in *code {
+            histogram[byte as usize] += 1;
+        }
+    }
+    let mut common: Vec<(usize, u64)> = histogram
+        .iter()
+        .copied()
+        .enumerate()
+        .filter(|&(_, count)| count > 0)
+        .collect();
+    common.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    display_line("Distinct Bytes", &common.len().to_string());
+    for (byte, count) in common.iter().take(TOP_BYTES) {
+        output::item(
+            1,
+            &format!("{:#04x} {:>10} {:>7}", byte, count, percent(*count, size)),
+        );
+    }
+    output::end();
+}
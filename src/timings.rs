@@ -0,0 +1,144 @@
+use std::cell::RefCell;
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Copy)]
+pub enum Phase {
+    Mmap,
+    Header,
+    Sections,
+    Symbols,
+    Output,
+}
+
+const PHASE_NAMES: [&str; 5] = ["mmap", "header", "sections", "symbols", "output"];
+
+/// Where the time went for one input file.
+struct Row {
+    file: String,
+    phases: [Duration; 5],
+    total: Duration,
+}
+
+#[derive(Default)]
+struct Timings {
+    started: Option<Instant>,
+    rows: Vec<Row>,
+    current: Option<usize>,
+    /// Phases being measured: which one, when it started, and the time spent in
+    /// phases nested inside it, which is not counted twice.
+    open: Vec<(Phase, Instant, Duration)>,
+}
+
+impl Timings {
+    fn row(&mut self, file: &str) -> usize {
+        match self.rows.iter().position(|row| row.file == file) {
+            Some(index) => index,
+            None => {
+                self.rows.push(Row {
+                    file: file.to_string(),
+                    phases: Default::default(),
+                    total: Duration::ZERO,
+                });
+                self.rows.len() - 1
+            }
+        }
+    }
+}
+
+thread_local! {
+    static TIMINGS: RefCell<Timings> = RefCell::new(Timings::default());
+}
+
+fn enabled() -> bool {
+    TIMINGS.with(|timings| timings.borrow().started.is_some())
+}
+
+pub fn enable() {
+    TIMINGS.with(|timings| timings.borrow_mut().started = Some(Instant::now()));
+}
+
+/// Runs `f` and charges the time to `phase` of the file being processed.
+pub fn measure<T>(phase: Phase, f: impl FnOnce() -> T) -> T {
+    if !enabled() {
+        return f();
+    }
+    TIMINGS.with(|timings| {
+        timings
+            .borrow_mut()
+            .open
+            .push((phase, Instant::now(), Duration::ZERO))
+    });
+    let result = f();
+    TIMINGS.with(|timings| {
+        let mut timings = timings.borrow_mut();
+        let Some((phase, start, nested)) = timings.open.pop() else {
+            return;
+        };
+        let elapsed = start.elapsed();
+        if let Some(parent) = timings.open.last_mut() {
+            parent.2 += elapsed;
+        }
+        let row = match timings.current {
+            Some(row) => row,
+            None => timings.row("(not tied to a file)"),
+        };
+        timings.rows[row].phases[phase as usize] += elapsed.saturating_sub(nested);
+    });
+    result
+}
+
+/// Runs `f` with everything it measures charged to `file`, adding its wall time
+/// to the file's total.
+pub fn for_file<T>(file: &str, f: impl FnOnce() -> T) -> T {
+    if !enabled() {
+        return f();
+    }
+    let (row, previous) = TIMINGS.with(|timings| {
+        let mut timings = timings.borrow_mut();
+        let row = timings.row(file);
+        (row, timings.current.replace(row))
+    });
+    let start = Instant::now();
+    let result = f();
+    let elapsed = start.elapsed();
+    TIMINGS.with(|timings| {
+        let mut timings = timings.borrow_mut();
+        timings.rows[row].total += elapsed;
+        timings.current = previous;
+    });
+    result
+}
+
+fn millis(duration: Duration) -> String {
+    format!("{:.3}", duration.as_secs_f64() * 1000.0)
+}
+
+/// Prints the per-file and overall timings to stderr, keeping stdout for the report.
+pub fn report() {
+    TIMINGS.with(|timings| {
+        let timings = timings.borrow();
+        let Some(started) = timings.started else {
+            return;
+        };
+        eprintln!();
+        eprint!("{:<40}", "Timings (ms)");
+        for name in PHASE_NAMES {
+            eprint!(" {:>9}", name);
+        }
+        eprintln!(" {:>9}", "total");
+        let mut sums = [Duration::ZERO; 5];
+        for row in &timings.rows {
+            eprint!("{:<40}", row.file);
+            for (sum, phase) in sums.iter_mut().zip(row.phases) {
+                *sum += phase;
+                eprint!(" {:>9}", millis(phase));
+            }
+            eprintln!(" {:>9}", millis(row.total));
+        }
+        eprint!("{:<40}", "Overall");
+        for sum in sums {
+            eprint!(" {:>9}", millis(sum));
+        }
+        eprintln!(" {:>9}", millis(started.elapsed()));
+    });
+}
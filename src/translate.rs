@@ -0,0 +1,148 @@
+use log::warn;
+use std::path::Path;
+
+use crate::cli::{parse_number, Options};
+use crate::debuginfo;
+use crate::output;
+use crate::pid::{mapping_bias, parse_mapping};
+use crate::symbol::{Symbol, SHN_UNDEF, STT_FILE, STT_SECTION};
+use crate::{display_line, Loader};
+
+/// The defined symbols an address can be named after: the symbol table, or the
+/// dynamic one in stripped files.
+fn named_symbols(loader: &Loader) -> Vec<Symbol> {
+    let mut symbols = loader.static_symbols();
+    if symbols.is_empty() {
+        symbols = loader.dynamic_symbols();
+    }
+    symbols.retain(|symbol| {
+        symbol.st_shndx != SHN_UNDEF
+            && !symbol.name.is_empty()
+            && symbol.kind() != STT_SECTION
+            && symbol.kind() != STT_FILE
+    });
+    symbols
+}
+
+/// `name+0x10` for the symbol containing `vaddr`, or for the closest one below it
+/// when no symbol's size covers it.
+fn describe_symbol(symbols: &[Symbol], vaddr: u64) -> String {
+    let containing = symbols.iter().find(|symbol| {
+        symbol.st_size != 0
+            && vaddr >= symbol.st_value
+            && vaddr < symbol.st_value.saturating_add(symbol.st_size)
+    });
+    if let Some(symbol) = containing {
+        return format!("symbol {}+{:#x}", symbol.name, vaddr - symbol.st_value);
+    }
+    match symbols
+        .iter()
+        .filter(|symbol| symbol.st_value <= vaddr)
+        .max_by_key(|symbol| symbol.st_value)
+    {
+        Some(symbol) => format!(
+            "nearest symbol below: {}+{:#x}",
+            symbol.name,
+            vaddr - symbol.st_value
+        ),
+        None => "no symbol".to_string(),
+    }
+}
+
+/// Prints the file address, section and symbol of every runtime address, and
+/// returns how many of them the file does not map.
+fn display_translation(label: &str, loader: &Loader, addresses: &[u64], bias: u64) -> usize {
+    let sections = loader.section_headers();
+    let symbols = named_symbols(loader);
+    let mut outside = 0;
+    output::begin(label);
+    display_line("Load Bias", &format!("{:#x}", bias));
+    for address in addresses {
+        let vaddr = address.wrapping_sub(bias);
+        display_line(&format!("{:#x}", address), &format!("{:#x}", vaddr));
+        let section = sections.iter().find(|section| section.contains_addr(vaddr));
+        let offset = loader.vaddr_to_offset(vaddr);
+        if section.is_none() && offset.is_none() {
+            output::item(1, "outside the file's segments and sections");
+            outside += 1;
+            continue;
+        }
+        let mut location = match section {
+            Some(section) => format!("section {}", section.name),
+            None => "no section".to_string(),
+        };
+        if let Some(offset) = offset {
+            location.push_str(&format!(", file offset {:#x}", offset));
+        }
+        output::item(1, &location);
+        output::item(1, &describe_symbol(&symbols, vaddr));
+    }
+    output::end();
+    outside
+}
+
+/// `translate FILE ADDRESS...`: turns runtime addresses of a module into
+/// addresses in FILE using `--bias`, or the bias implied by a `/proc/PID/maps`
+/// line given with `--maps`, and names their section and symbol. Returns the
+/// inputs that could not be used.
+pub fn translate(options: &Options) -> Vec<(String, String)> {
+    let Some((path, arguments)) = options.paths.split_first() else {
+        return vec![(
+            "translate".to_string(),
+            "expects a FILE and runtime addresses".to_string(),
+        )];
+    };
+    let mut failures = Vec::new();
+    let mut addresses = Vec::new();
+    for argument in arguments {
+        match parse_number(argument) {
+            Some(address) => addresses.push(address as u64),
+            None => failures.push((argument.clone(), "not an address".to_string())),
+        }
+    }
+    let loader = match Loader::open(path) {
+        Ok(loader) if options.with_debug => debuginfo::attach_debug_file(path, loader),
+        Ok(loader) => loader,
+        Err(error) => {
+            failures.push((path.clone(), error.to_string()));
+            return failures;
+        }
+    };
+    if let Some(problem) = loader.header_truncation() {
+        failures.push((path.clone(), problem));
+        return failures;
+    }
+
+    let bias = match (&options.maps_line, options.bias) {
+        (Some(line), _) => {
+            let Some(mapping) = parse_mapping(line) else {
+                failures.push((line.clone(), "not a /proc/PID/maps line".to_string()));
+                return failures;
+            };
+            let file_name =
+                |path: &str| Path::new(path).file_name().map(|name| name.to_os_string());
+            if !mapping.path.is_empty() && file_name(&mapping.path) != file_name(path) {
+                warn!("{}: the maps line is for {}", path, mapping.path);
+            }
+            match mapping_bias(&loader, &mapping) {
+                Some(bias) => bias,
+                None => {
+                    failures.push((
+                        path.clone(),
+                        format!("no PT_LOAD segment maps file offset {:#x}", mapping.offset),
+                    ));
+                    return failures;
+                }
+            }
+        }
+        (None, bias) => bias.unwrap_or(0),
+    };
+    let outside = display_translation(path, &loader, &addresses, bias);
+    if outside > 0 {
+        failures.push((
+            path.clone(),
+            format!("{} address(es) outside the file", outside),
+        ));
+    }
+    failures
+}
@@ -0,0 +1,135 @@
+use std::collections::HashMap;
+
+use crate::output;
+use crate::symbol::{SHN_UNDEF, STB_GLOBAL, STB_WEAK};
+use crate::version::SymbolVersion;
+use crate::{display_line, Loader};
+
+/// A dynamic symbol the application imports, with the outcome of looking it up.
+pub struct Import {
+    pub name: String,
+    pub version: Option<SymbolVersion>,
+    pub weak: bool,
+    /// The library that would provide the symbol.
+    pub provider: Option<String>,
+    /// Versions the listed libraries define the symbol under, when none matched.
+    pub other_versions: Vec<String>,
+}
+
+impl Import {
+    fn display_name(&self) -> String {
+        match &self.version {
+            Some(version) => format!("{}@{}", self.name, version.name),
+            None => self.name.clone(),
+        }
+    }
+}
+
+struct Export {
+    library: String,
+    version: Option<SymbolVersion>,
+    /// Whether the library versions its symbols at all (has .gnu.version_d).
+    versioned_library: bool,
+}
+
+impl Export {
+    /// An unversioned reference binds to the default version; a versioned one needs
+    /// that exact version, unless the library does not use symbol versioning.
+    fn satisfies(&self, required: &Option<SymbolVersion>) -> bool {
+        match (required, &self.version) {
+            (None, None) => true,
+            (None, Some(version)) => !version.hidden,
+            (Some(_), None) => !self.versioned_library,
+            (Some(required), Some(version)) => required.name == version.name,
+        }
+    }
+}
+
+fn exports(libraries: &[(String, Loader)]) -> HashMap<String, Vec<Export>> {
+    let mut exports: HashMap<String, Vec<Export>> = HashMap::new();
+    for (label, library) in libraries {
+        let versions = library.dynamic_symbol_versions();
+        let versioned_library = !library.version_definitions().is_empty();
+        for (index, symbol) in library.dynamic_symbols().into_iter().enumerate() {
+            if symbol.st_shndx == SHN_UNDEF || !matches!(symbol.bind(), STB_GLOBAL | STB_WEAK) {
+                continue;
+            }
+            exports.entry(symbol.name).or_default().push(Export {
+                library: label.clone(),
+                version: versions.get(index).cloned().flatten(),
+                versioned_library,
+            });
+        }
+    }
+    exports
+}
+
+/// Looks up every undefined dynamic symbol of `app` in `libraries`, in the order given.
+pub fn resolve_imports(app: &Loader, libraries: &[(String, Loader)]) -> Vec<Import> {
+    let exports = exports(libraries);
+    let versions = app.dynamic_symbol_versions();
+    app.dynamic_symbols()
+        .into_iter()
+        .enumerate()
+        .filter(|(_, symbol)| symbol.st_shndx == SHN_UNDEF && !symbol.name.is_empty())
+        .map(|(index, symbol)| {
+            let version = versions.get(index).cloned().flatten();
+            let candidates = exports.get(&symbol.name).map(Vec::as_slice).unwrap_or(&[]);
+            let provider = candidates
+                .iter()
+                .find(|export| export.satisfies(&version))
+                .map(|export| export.library.clone());
+            let other_versions = if provider.is_some() {
+                Vec::new()
+            } else {
+                candidates
+                    .iter()
+                    .map(|export| match &export.version {
+                        Some(version) => format!("{} in {}", version.name, export.library),
+                        None => format!("unversioned in {}", export.library),
+                    })
+                    .collect()
+            };
+            Import {
+                weak: symbol.bind() == STB_WEAK,
+                name: symbol.name,
+                version,
+                provider,
+                other_versions,
+            }
+        })
+        .collect()
+}
+
+/// Prints the imports of `app` that the libraries cannot satisfy and returns how many
+/// of them would make the dynamic linker fail. Weak imports may stay unresolved.
+pub fn display_resolve_undefs(path: &str, app: &Loader, libraries: &[(String, Loader)]) -> usize {
+    let imports = resolve_imports(app, libraries);
+    let (resolved, unresolved): (Vec<_>, Vec<_>) =
+        imports.iter().partition(|import| import.provider.is_some());
+    let (optional, missing): (Vec<_>, Vec<_>) =
+        unresolved.into_iter().partition(|import| import.weak);
+
+    output::begin(path);
+    display_line("Undefined Dynamic Symbols", &imports.len().to_string());
+    display_line("Resolved", &resolved.len().to_string());
+    display_line("Unresolved", &missing.len().to_string());
+    for import in &missing {
+        let expected = import
+            .version
+            .as_ref()
+            .and_then(|version| version.file.as_deref())
+            .map(|file| format!(" (expected from {})", file))
+            .unwrap_or_default();
+        output::item(1, &format!("{}{}", import.display_name(), expected));
+        for other in &import.other_versions {
+            output::item(2, &format!("only defined as {}", other));
+        }
+    }
+    display_line("Unresolved Weak (allowed)", &optional.len().to_string());
+    for import in &optional {
+        output::item(1, &import.display_name());
+    }
+    output::end();
+    missing.len()
+}
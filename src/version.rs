@@ -0,0 +1,148 @@
+use std::collections::HashMap;
+
+use crate::section::{SectionHeader, SHT_GNU_VERDEF, SHT_GNU_VERNEED, SHT_GNU_VERSYM};
+use crate::Loader;
+
+/// Set in a .gnu.version entry when the definition is not the default version
+/// (`foo@VER` rather than `foo@@VER`).
+pub const VERSYM_HIDDEN: u16 = 0x8000;
+/// .gnu.version indices 0 and 1 mean "local" and "global, unversioned".
+pub const VER_NDX_GLOBAL: u16 = 1;
+
+/// A version a dynamic symbol is bound to, as named by verdef or verneed.
+#[derive(Clone, Debug)]
+pub struct SymbolVersion {
+    pub name: String,
+    /// For a required version, the library (DT_NEEDED name) expected to provide it.
+    pub file: Option<String>,
+    pub hidden: bool,
+}
+
+impl Loader {
+    fn version_section(&self, sh_type: u32) -> Option<(SectionHeader, usize)> {
+        let sections = self.section_headers();
+        let section = sections
+            .iter()
+            .find(|section| section.sh_type == sh_type)?
+            .clone();
+        let strtab = sections.get(section.sh_link as usize)?.sh_offset as usize;
+        Some((section, strtab))
+    }
+
+    /// The raw .gnu.version entries, one per .dynsym entry.
+    pub fn version_indices(&self) -> Vec<u16> {
+        let Some((section, _)) = self.version_section(SHT_GNU_VERSYM) else {
+            return Vec::new();
+        };
+        (0..section.sh_size as usize / 2)
+            .map_while(|index| self.read_u16(section.sh_offset as usize + index * 2))
+            .collect()
+    }
+
+    /// Version names defined by this object (.gnu.version_d), keyed by vd_ndx.
+    pub fn version_definitions(&self) -> HashMap<u16, String> {
+        let mut definitions = HashMap::new();
+        let Some((section, strtab)) = self.version_section(SHT_GNU_VERDEF) else {
+            return definitions;
+        };
+        let mut offset = section.sh_offset as usize;
+        // Elf_Verdef: vd_version, vd_flags, vd_ndx, vd_cnt (u16), vd_hash, vd_aux, vd_next (u32).
+        for _ in 0..section.sh_info {
+            let (Some(vd_ndx), Some(vd_aux), Some(vd_next)) = (
+                self.read_u16(offset + 4),
+                self.read_u32(offset + 12),
+                self.read_u32(offset + 16),
+            ) else {
+                break;
+            };
+            // The first Elf_Verdaux names the version itself; later ones name its parents.
+            if let Some(name) = self
+                .read_u32(offset + vd_aux as usize)
+                .and_then(|vda_name| self.read_cstr(strtab + vda_name as usize))
+            {
+                definitions.insert(vd_ndx, name.to_string());
+            }
+            if vd_next == 0 {
+                break;
+            }
+            offset += vd_next as usize;
+        }
+        definitions
+    }
+
+    /// Versions this object requires (.gnu.version_r), keyed by vna_other, with the
+    /// library each one is expected from.
+    pub fn version_needs(&self) -> HashMap<u16, (String, String)> {
+        let mut needs = HashMap::new();
+        let Some((section, strtab)) = self.version_section(SHT_GNU_VERNEED) else {
+            return needs;
+        };
+        let mut offset = section.sh_offset as usize;
+        // Elf_Verneed: vn_version, vn_cnt (u16), vn_file, vn_aux, vn_next (u32).
+        for _ in 0..section.sh_info {
+            let (Some(vn_cnt), Some(vn_file), Some(vn_aux), Some(vn_next)) = (
+                self.read_u16(offset + 2),
+                self.read_u32(offset + 4),
+                self.read_u32(offset + 8),
+                self.read_u32(offset + 12),
+            ) else {
+                break;
+            };
+            let file = self
+                .read_cstr(strtab + vn_file as usize)
+                .unwrap_or("")
+                .to_string();
+            // Elf_Vernaux: vna_hash (u32), vna_flags, vna_other (u16), vna_name, vna_next (u32).
+            let mut aux = offset + vn_aux as usize;
+            for _ in 0..vn_cnt {
+                let (Some(vna_other), Some(vna_name), Some(vna_next)) = (
+                    self.read_u16(aux + 6),
+                    self.read_u32(aux + 8),
+                    self.read_u32(aux + 12),
+                ) else {
+                    break;
+                };
+                if let Some(name) = self.read_cstr(strtab + vna_name as usize) {
+                    needs.insert(vna_other, (file.clone(), name.to_string()));
+                }
+                if vna_next == 0 {
+                    break;
+                }
+                aux += vna_next as usize;
+            }
+            if vn_next == 0 {
+                break;
+            }
+            offset += vn_next as usize;
+        }
+        needs
+    }
+
+    /// The version of every .dynsym entry, in table order. Unversioned and local
+    /// entries map to `None`.
+    pub fn dynamic_symbol_versions(&self) -> Vec<Option<SymbolVersion>> {
+        let definitions = self.version_definitions();
+        let needs = self.version_needs();
+        self.version_indices()
+            .into_iter()
+            .map(|raw| {
+                let index = raw & !VERSYM_HIDDEN;
+                if index <= VER_NDX_GLOBAL {
+                    return None;
+                }
+                if let Some((file, name)) = needs.get(&index) {
+                    return Some(SymbolVersion {
+                        name: name.clone(),
+                        file: Some(file.clone()),
+                        hidden: false,
+                    });
+                }
+                definitions.get(&index).map(|name| SymbolVersion {
+                    name: name.clone(),
+                    file: None,
+                    hidden: raw & VERSYM_HIDDEN != 0,
+                })
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,193 @@
+//! JavaScript bindings, built with the `wasm` feature for wasm32-unknown-unknown:
+//! `wasm-pack build --target web -- --features wasm`. The file comes in as a
+//! `Uint8Array`, since a page has no filesystem to map; 64-bit fields come out
+//! as `BigInt`.
+
+use wasm_bindgen::prelude::*;
+
+use crate::section::SectionHeader;
+use crate::symbol::Symbol as ElfSymbol;
+use crate::Loader;
+
+/// One section header.
+#[wasm_bindgen(getter_with_clone)]
+pub struct Section {
+    pub index: u32,
+    pub name: String,
+    pub sh_type: u32,
+    pub sh_flags: u64,
+    pub sh_addr: u64,
+    pub sh_offset: u64,
+    pub sh_size: u64,
+    pub sh_link: u32,
+    pub sh_info: u32,
+    pub sh_addralign: u64,
+    pub sh_entsize: u64,
+}
+
+impl From<SectionHeader> for Section {
+    fn from(section: SectionHeader) -> Section {
+        Section {
+            index: section.index as u32,
+            name: section.name,
+            sh_type: section.sh_type,
+            sh_flags: section.sh_flags,
+            sh_addr: section.sh_addr,
+            sh_offset: section.sh_offset,
+            sh_size: section.sh_size,
+            sh_link: section.sh_link,
+            sh_info: section.sh_info,
+            sh_addralign: section.sh_addralign,
+            sh_entsize: section.sh_entsize,
+        }
+    }
+}
+
+/// One symbol table entry.
+#[wasm_bindgen(getter_with_clone)]
+pub struct Symbol {
+    pub name: String,
+    pub st_value: u64,
+    pub st_size: u64,
+    pub st_info: u8,
+    pub st_other: u8,
+    pub st_shndx: u16,
+}
+
+#[wasm_bindgen]
+impl Symbol {
+    /// STB_* binding from st_info.
+    #[wasm_bindgen(getter)]
+    pub fn bind(&self) -> u8 {
+        self.st_info >> 4
+    }
+
+    /// STT_* type from st_info.
+    #[wasm_bindgen(getter)]
+    pub fn kind(&self) -> u8 {
+        self.st_info & 0xf
+    }
+}
+
+impl From<ElfSymbol> for Symbol {
+    fn from(symbol: ElfSymbol) -> Symbol {
+        Symbol {
+            name: symbol.name,
+            st_value: symbol.st_value,
+            st_size: symbol.st_size,
+            st_info: symbol.st_info,
+            st_other: symbol.st_other,
+            st_shndx: symbol.st_shndx,
+        }
+    }
+}
+
+/// An ELF file held in memory.
+#[wasm_bindgen]
+pub struct ElfFile {
+    loader: Loader,
+}
+
+#[wasm_bindgen]
+impl ElfFile {
+    /// Copies `bytes` and checks the header. Throws if they are not ELF or the
+    /// header is truncated.
+    #[wasm_bindgen(constructor)]
+    pub fn new(bytes: &[u8]) -> Result<ElfFile, JsError> {
+        let loader = Loader::from_bytes(bytes.to_vec());
+        if !loader.is_elf() {
+            return Err(JsError::new("not an ELF file"));
+        }
+        if let Some(problem) = loader.header_truncation() {
+            return Err(JsError::new(&problem));
+        }
+        Ok(ElfFile { loader })
+    }
+
+    /// 32 or 64.
+    #[wasm_bindgen(getter)]
+    pub fn bits(&self) -> u8 {
+        if self.loader.is_64bit() {
+            64
+        } else {
+            32
+        }
+    }
+
+    #[wasm_bindgen(getter, js_name = bigEndian)]
+    pub fn big_endian(&self) -> bool {
+        self.loader.is_big_endian()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn e_type(&self) -> u16 {
+        self.loader.raw_e_type()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn e_machine(&self) -> u16 {
+        self.loader.raw_e_machine()
+    }
+
+    /// The e_machine name the command line tool prints, such as "AArch64".
+    #[wasm_bindgen(getter, js_name = machineName)]
+    pub fn machine_name(&self) -> String {
+        self.loader.machine_name()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn e_entry(&self) -> u64 {
+        self.loader.get_e_entry()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn e_flags(&self) -> u32 {
+        self.loader.get_e_flags()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn e_phnum(&self) -> u32 {
+        self.loader.get_e_phnum()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn e_shnum(&self) -> u32 {
+        self.loader.get_e_shnum()
+    }
+
+    /// DT_SONAME of a shared library, or undefined.
+    #[wasm_bindgen(getter)]
+    pub fn soname(&self) -> Option<String> {
+        self.loader.soname()
+    }
+
+    /// DT_NEEDED entries in order.
+    #[wasm_bindgen(getter)]
+    pub fn needed(&self) -> Vec<String> {
+        self.loader.needed_libraries()
+    }
+
+    /// All section headers, section 0 included.
+    pub fn sections(&self) -> Vec<Section> {
+        self.loader
+            .section_headers()
+            .into_iter()
+            .map(Section::from)
+            .collect()
+    }
+
+    /// The first section called `name`, or undefined.
+    pub fn section(&self, name: &str) -> Option<Section> {
+        self.loader.section_by_name(name).map(Section::from)
+    }
+
+    /// `.symtab`, or `.dynsym` when `dynamic` is true; empty if the table is missing.
+    pub fn symbols(&self, dynamic: bool) -> Vec<Symbol> {
+        let symbols = if dynamic {
+            self.loader.dynamic_symbols()
+        } else {
+            self.loader.static_symbols()
+        };
+        symbols.into_iter().map(Symbol::from).collect()
+    }
+}
@@ -0,0 +1,159 @@
+//! A short summary of WebAssembly modules given as inputs, built with the
+//! `wasm-summary` feature, so that a mix of ELF and WASM build outputs can be
+//! looked at in one run.
+
+use crate::attributes::read_uleb128;
+use crate::normalized::{Kind, NormalizedReport};
+use crate::{display_line, output};
+
+const WASM_MAGIC: &[u8] = b"\0asm";
+/// The version of core modules; components have another one.
+const WASM_MODULE_VERSION: u32 = 1;
+
+const SECTION_CUSTOM: u8 = 0;
+const SECTION_IMPORT: u8 = 2;
+const SECTION_FUNCTION: u8 = 3;
+const SECTION_EXPORT: u8 = 7;
+
+const SECTION_NAMES: &[&str] = &[
+    "custom",
+    "type",
+    "import",
+    "function",
+    "table",
+    "memory",
+    "global",
+    "export",
+    "start",
+    "element",
+    "code",
+    "data",
+    "datacount",
+    "tag",
+];
+
+/// One section of a module, with the file offset of its contents.
+struct Section<'a> {
+    id: u8,
+    offset: usize,
+    contents: &'a [u8],
+}
+
+impl Section<'_> {
+    fn name(&self) -> String {
+        if self.id == SECTION_CUSTOM {
+            let custom = read_uleb128(self.contents).and_then(|(len, taken)| {
+                self.contents.get(taken..taken.checked_add(len as usize)?)
+            });
+            return match custom {
+                Some(name) => format!("custom \"{}\"", String::from_utf8_lossy(name)),
+                None => "custom".to_string(),
+            };
+        }
+        SECTION_NAMES
+            .get(usize::from(self.id))
+            .map_or_else(|| format!("section {}", self.id), |name| name.to_string())
+    }
+
+    /// The entry count vector sections start with.
+    fn count(&self) -> Option<u64> {
+        read_uleb128(self.contents).map(|(count, _)| count)
+    }
+}
+
+pub fn is_wasm(bytes: &[u8]) -> bool {
+    bytes.starts_with(WASM_MAGIC)
+}
+
+/// A module in the shape `--normalized` gives every format: 32-bit and
+/// little-endian by definition, and with no hardening to speak of since the
+/// runtime sandboxes it.
+pub fn normalized() -> NormalizedReport {
+    let mut report = NormalizedReport::new("WebAssembly", "wasm32".to_string(), Kind::Module);
+    report.bits = Some(32);
+    report.big_endian = Some(false);
+    report
+}
+
+/// The sections after the header, and whether they run past the end of the
+/// file.
+fn sections(bytes: &[u8]) -> (Vec<Section<'_>>, bool) {
+    let mut sections = Vec::new();
+    let mut at = 8;
+    while at < bytes.len() {
+        let id = bytes[at];
+        let Some((size, taken)) = bytes.get(at + 1..).and_then(read_uleb128) else {
+            return (sections, true);
+        };
+        let offset = at + 1 + taken;
+        let Some(contents) = offset
+            .checked_add(size as usize)
+            .and_then(|end| bytes.get(offset..end))
+        else {
+            return (sections, true);
+        };
+        sections.push(Section {
+            id,
+            offset,
+            contents,
+        });
+        at = offset + contents.len();
+    }
+    (sections, false)
+}
+
+fn count_of(sections: &[Section], id: u8) -> u64 {
+    sections
+        .iter()
+        .filter(|section| section.id == id)
+        .filter_map(Section::count)
+        .sum()
+}
+
+/// The version, the sections and the import, export and function counts of a
+/// WebAssembly module.
+pub fn display_wasm_summary(path: &str, bytes: &[u8]) {
+    output::begin(path);
+    let version = bytes
+        .get(4..8)
+        .and_then(|version| version.try_into().ok())
+        .map(u32::from_le_bytes);
+    match version {
+        Some(WASM_MODULE_VERSION) => display_line("Format", "WebAssembly module"),
+        Some(_) => display_line("Format", "WebAssembly component"),
+        None => display_line("Format", "WebAssembly, truncated header"),
+    }
+    let Some(version) = version else {
+        output::end();
+        return;
+    };
+    display_line("Version", &version.to_string());
+    if version != WASM_MODULE_VERSION {
+        // Components number their sections differently.
+        output::end();
+        return;
+    }
+    let (sections, truncated) = sections(bytes);
+    display_line("Sections", &sections.len().to_string());
+    for section in &sections {
+        output::item(
+            1,
+            &format!(
+                "{:>#10x} {:>10} bytes  {}",
+                section.offset,
+                section.contents.len(),
+                section.name()
+            ),
+        );
+    }
+    if truncated {
+        output::item(1, "the last section runs past the end of the file");
+    }
+    display_line("Imports", &count_of(&sections, SECTION_IMPORT).to_string());
+    display_line("Exports", &count_of(&sections, SECTION_EXPORT).to_string());
+    display_line(
+        "Functions",
+        &count_of(&sections, SECTION_FUNCTION).to_string(),
+    );
+    output::end();
+}
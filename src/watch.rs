@@ -0,0 +1,194 @@
+use std::env;
+use std::fs;
+use std::process::Command;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use crate::fingerprint::Fingerprint;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// How many changed regions of a file the change line names.
+const LISTED_REGIONS: usize = 8;
+/// The largest table of the line diff, in cells of 4 bytes: 64 MiB. Outputs
+/// that differ in more lines than this allows are printed whole instead.
+const MAX_DIFF_CELLS: usize = 1 << 24;
+
+/// What identifies one version of a watched file: its modification time and size.
+type Stamp = Option<(SystemTime, u64)>;
+
+fn stamp(path: &str) -> Stamp {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// Runs this binary again without the watch flags and captures what it prints, so a
+/// half-written file that makes the parser bail out cannot take the watcher down with it.
+fn render(args: &[String]) -> String {
+    let Ok(executable) = env::current_exe() else {
+        return "cannot locate the running executable\n".to_string();
+    };
+    match Command::new(executable).args(args).output() {
+        Ok(output) => {
+            let mut text = String::from_utf8_lossy(&output.stdout).to_string();
+            if !output.status.success() {
+                text.push_str(&format!("(exited with {})\n", output.status));
+            }
+            text
+        }
+        Err(error) => format!("could not run the parser: {}\n", error),
+    }
+}
+
+/// Line diff based on the longest common subsequence of the two outputs, or
+/// `None` when the lines between their common start and end are too many to
+/// compare in `MAX_DIFF_CELLS`.
+fn diff_lines(old: &str, new: &str) -> Option<Vec<String>> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    let prefix = old
+        .iter()
+        .zip(&new)
+        .take_while(|(old, new)| old == new)
+        .count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(old, new)| old == new)
+        .count();
+    let old = &old[prefix..old.len() - suffix];
+    let new = &new[prefix..new.len() - suffix];
+    if (old.len() + 1).saturating_mul(new.len() + 1) > MAX_DIFF_CELLS {
+        return None;
+    }
+    let mut lcs = vec![vec![0u32; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+    let (mut i, mut j) = (0, 0);
+    let mut changes = Vec::new();
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            changes.push(format!("- {}", old[i]));
+            i += 1;
+        } else {
+            changes.push(format!("+ {}", new[j]));
+            j += 1;
+        }
+    }
+    Some(changes)
+}
+
+/// What changed in `path`, e.g. `ls: .dynsym, .text`, with up to
+/// `LISTED_REGIONS` regions.
+fn describe_change(path: &str, regions: &[String]) -> String {
+    let mut listed = regions[..regions.len().min(LISTED_REGIONS)].join(", ");
+    if regions.len() > LISTED_REGIONS {
+        listed.push_str(&format!(" and {} more", regions.len() - LISTED_REGIONS));
+    }
+    format!("{}: {}", path, listed)
+}
+
+/// Prints the output for `args` and then again (or only what changed, with `diff`)
+/// every time one of `paths` changes on disk. A change that leaves the regions the
+/// report reads as they were, such as a relink that only moves code while the
+/// default header table is shown (`header_only`), prints nothing. Never returns.
+pub fn watch(paths: &[String], args: &[String], diff: bool, header_only: bool) {
+    let mut stamps: Vec<Stamp> = paths.iter().map(|path| stamp(path)).collect();
+    let mut fingerprints: Vec<Option<Fingerprint>> = paths
+        .iter()
+        .map(|path| Fingerprint::of(path).ok())
+        .collect();
+    let mut previous = render(args);
+    print!("{}", previous);
+    loop {
+        thread::sleep(POLL_INTERVAL);
+        let current: Vec<Stamp> = paths.iter().map(|path| stamp(path)).collect();
+        if current == stamps {
+            continue;
+        }
+        let mut changes = Vec::new();
+        for (index, path) in paths.iter().enumerate() {
+            if current[index] == stamps[index] {
+                continue;
+            }
+            let fingerprint = Fingerprint::of(path).ok();
+            match (&fingerprints[index], &fingerprint) {
+                (Some(old), Some(new)) => {
+                    let regions = new.changed_since(old, header_only);
+                    if !regions.is_empty() {
+                        changes.push(describe_change(path, &regions));
+                    }
+                }
+                _ => changes.push(path.clone()),
+            }
+            fingerprints[index] = fingerprint;
+        }
+        stamps = current;
+        if changes.is_empty() {
+            continue;
+        }
+        let output = render(args);
+        println!("--- change detected in {} ---", changes.join("; "));
+        match diff.then(|| diff_lines(&previous, &output)) {
+            Some(Some(changes)) => {
+                if changes.is_empty() {
+                    println!("(output unchanged)");
+                }
+                for change in changes {
+                    println!("{}", change);
+                }
+            }
+            Some(None) => {
+                println!("(too many changed lines to compare, the whole output follows)");
+                print!("{}", output);
+            }
+            None => print!("{}", output),
+        }
+        previous = output;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::diff_lines;
+
+    #[test]
+    fn changed_lines() {
+        let old = "header\na\nb\nc\nfooter\n";
+        let new = "header\na\nB\nc\nd\nfooter\n";
+        assert_eq!(
+            diff_lines(old, new).unwrap(),
+            ["- b", "+ B", "+ d"].map(String::from)
+        );
+        assert!(diff_lines(old, old).unwrap().is_empty());
+        assert_eq!(diff_lines("", "a\n").unwrap(), ["+ a"].map(String::from));
+    }
+
+    /// A long output with one line changed is compared only around it.
+    #[test]
+    fn long_output_with_a_small_change() {
+        let old: String = (0..200_000).map(|line| format!("{}\n", line)).collect();
+        let new = old.replace("\n100000\n", "\nchanged\n");
+        assert_eq!(
+            diff_lines(&old, &new).unwrap(),
+            ["- 100000", "+ changed"].map(String::from)
+        );
+    }
+
+    #[test]
+    fn outputs_too_different_to_compare() {
+        let old: String = (0..10_000).map(|line| format!("old {}\n", line)).collect();
+        let new: String = (0..10_000).map(|line| format!("new {}\n", line)).collect();
+        assert!(diff_lines(&old, &new).is_none());
+    }
+}
@@ -0,0 +1,251 @@
+use crate::arch::{bit_name, ArchExt, TextModel};
+use crate::attributes::AttributeValue;
+use crate::output;
+use crate::{display_line, Loader};
+
+pub(crate) const EM_386: u16 = 3;
+pub(crate) const EM_X86_64: u16 = 62;
+
+/// AT_HWCAP on x86 is the EDX word of CPUID leaf 1.
+const X86_HWCAP: [&str; 32] = [
+    "fpu", "vme", "de", "pse", "tsc", "msr", "pae", "mce", "cx8", "apic", "", "sep", "mtrr", "pge",
+    "mca", "cmov", "pat", "pse36", "pn", "clflush", "", "dts", "acpi", "mmx", "fxsr", "sse",
+    "sse2", "ss", "ht", "tm", "ia64", "pbe",
+];
+
+/// The NOPs of the Intel optimization manual, with the `cs` prefixed and
+/// doubled-prefix forms GCC and binutils emit, and INT3, which LLVM and the Go
+/// linker pad with.
+const X86_PADDING: &[&[u8]] = &[
+    &[
+        0x66, 0x66, 0x2e, 0x0f, 0x1f, 0x84, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ],
+    &[0x66, 0x2e, 0x0f, 0x1f, 0x84, 0x00, 0x00, 0x00, 0x00, 0x00],
+    &[0x66, 0x0f, 0x1f, 0x84, 0x00, 0x00, 0x00, 0x00, 0x00],
+    &[0x0f, 0x1f, 0x84, 0x00, 0x00, 0x00, 0x00, 0x00],
+    &[0x0f, 0x1f, 0x80, 0x00, 0x00, 0x00, 0x00],
+    &[0x66, 0x0f, 0x1f, 0x44, 0x00, 0x00],
+    &[0x0f, 0x1f, 0x44, 0x00, 0x00],
+    &[0x0f, 0x1f, 0x40, 0x00],
+    &[0x0f, 0x1f, 0x00],
+    &[0x66, 0x90],
+    &[0x90],
+    &[0xcc],
+];
+
+pub(crate) const GNU_PROPERTY_X86_FEATURE_1_AND: u32 = 0xc000_0002;
+const GNU_PROPERTY_X86_ISA_1_NEEDED: u32 = 0xc000_8002;
+const GNU_PROPERTY_X86_ISA_1_USED: u32 = 0xc001_0002;
+
+pub(crate) const GNU_PROPERTY_X86_FEATURE_1_IBT: u32 = 1;
+pub(crate) const GNU_PROPERTY_X86_FEATURE_1_SHSTK: u32 = 2;
+
+/// The micro-architecture levels of the x86-64 psABI, one bit each in the ISA
+/// properties, with the CPU features each adds to the one before.
+const ISA_LEVELS: [(&str, &str); 4] = [
+    (
+        "x86-64-baseline",
+        "CMOV, CX8, FPU, FXSR, MMX, OSFXSR, SCE, SSE, SSE2",
+    ),
+    (
+        "x86-64-v2",
+        "CMPXCHG16B, LAHF-SAHF, POPCNT, SSE3, SSE4.1, SSE4.2, SSSE3",
+    ),
+    (
+        "x86-64-v3",
+        "AVX, AVX2, BMI1, BMI2, F16C, FMA, LZCNT, MOVBE, OSXSAVE",
+    ),
+    (
+        "x86-64-v4",
+        "AVX512F, AVX512BW, AVX512CD, AVX512DQ, AVX512VL",
+    ),
+];
+
+fn isa_level_names(value: u32) -> String {
+    let mut names: Vec<String> = (0..32)
+        .filter(|bit| value & (1 << bit) != 0)
+        .map(|bit| match ISA_LEVELS.get(bit) {
+            Some((name, _)) => name.to_string(),
+            None => format!("<unknown: {:x}>", 1u32 << bit),
+        })
+        .collect();
+    if names.is_empty() {
+        names.push("<None>".to_string());
+    }
+    names.join(", ")
+}
+
+/// The highest level set in an ISA property value.
+fn highest_level(value: u32) -> Option<usize> {
+    (0..ISA_LEVELS.len())
+        .rev()
+        .find(|bit| value & (1 << bit) != 0)
+}
+
+fn describe_property(pr_type: u32, value: u32) -> Option<String> {
+    match pr_type {
+        GNU_PROPERTY_X86_ISA_1_NEEDED => {
+            Some(format!("x86 ISA needed: {}", isa_level_names(value)))
+        }
+        GNU_PROPERTY_X86_ISA_1_USED => Some(format!("x86 ISA used: {}", isa_level_names(value))),
+        GNU_PROPERTY_X86_FEATURE_1_AND => {
+            let mut features = Vec::new();
+            if value & GNU_PROPERTY_X86_FEATURE_1_IBT != 0 {
+                features.push("IBT");
+            }
+            if value & GNU_PROPERTY_X86_FEATURE_1_SHSTK != 0 {
+                features.push("SHSTK");
+            }
+            Some(format!("x86 feature: {}", features.join(", ")))
+        }
+        _ => None,
+    }
+}
+
+/// The CPU level the code needs, from the ISA properties. glibc refuses to load
+/// a binary whose ISA_1_NEEDED level the CPU lacks, while a level that is only
+/// in ISA_1_USED shows up as SIGILL when the code runs.
+fn display_isa_level(loader: &Loader) {
+    let needed = loader.property_u32(GNU_PROPERTY_X86_ISA_1_NEEDED);
+    let used = loader.property_u32(GNU_PROPERTY_X86_ISA_1_USED);
+    let show = |value: Option<u32>| value.map_or("None".to_string(), isa_level_names);
+    display_line("ISA Level Needed", &show(needed));
+    display_line("ISA Level Used", &show(used));
+    let needed_level = needed.and_then(highest_level);
+    let used_level = used.and_then(highest_level);
+    let Some(level) = needed_level.max(used_level) else {
+        display_line("Minimum CPU", "Unknown (no ISA level property)");
+        return;
+    };
+    display_line("Minimum CPU", ISA_LEVELS[level].0);
+    for (name, features) in &ISA_LEVELS[..=level] {
+        output::item(1, &format!("{:<16} {}", name, features));
+    }
+    if used_level > needed_level {
+        output::item(
+            1,
+            "warning: the code uses more than it is marked as needing, so older CPUs \
+             get SIGILL instead of a loader error",
+        );
+    }
+}
+
+/// Decodes x86 code placed at `address` and returns how many instructions it
+/// holds and how many of its bytes are NOPs or INT3, or `None` when the file is
+/// not for x86.
+#[cfg(feature = "disassembler")]
+pub fn decode(loader: &Loader, address: u64, code: &[u8]) -> Option<(u64, u64)> {
+    use iced_x86::{Decoder, DecoderOptions, Mnemonic};
+
+    let bitness = match loader.raw_e_machine() {
+        EM_386 => 32,
+        EM_X86_64 => 64,
+        _ => return None,
+    };
+    let (mut instructions, mut padding) = (0, 0);
+    for instruction in Decoder::with_ip(bitness, code, address, DecoderOptions::NONE) {
+        instructions += 1;
+        if matches!(instruction.mnemonic(), Mnemonic::Nop | Mnemonic::Int3) {
+            padding += instruction.len() as u64;
+        }
+    }
+    Some((instructions, padding))
+}
+
+pub struct I386;
+
+impl ArchExt for I386 {
+    fn machines(&self) -> &'static [u16] {
+        &[EM_386]
+    }
+
+    fn hwcap_name(&self, bit: u32) -> Option<&'static str> {
+        bit_name(&X86_HWCAP, bit)
+    }
+
+    fn describe_property(&self, pr_type: u32, value: u32) -> Option<String> {
+        describe_property(pr_type, value)
+    }
+
+    fn display_details(&self, loader: &Loader, _flags: u32, _attributes: &[(u64, AttributeValue)]) {
+        display_isa_level(loader);
+    }
+
+    fn text_model(&self, _loader: &Loader) -> Option<TextModel> {
+        Some(TextModel {
+            average_length: 3.0,
+            alignment: 1,
+            padding: X86_PADDING,
+        })
+    }
+
+    fn relocation_name(&self, r_type: u32) -> Option<&'static str> {
+        match r_type {
+            0 => Some("R_386_NONE"),
+            1 => Some("R_386_32"),
+            2 => Some("R_386_PC32"),
+            5 => Some("R_386_COPY"),
+            6 => Some("R_386_GLOB_DAT"),
+            7 => Some("R_386_JMP_SLOT"),
+            8 => Some("R_386_RELATIVE"),
+            14 => Some("R_386_TLS_TPOFF"),
+            37 => Some("R_386_TLS_TPOFF32"),
+            42 => Some("R_386_IRELATIVE"),
+            _ => None,
+        }
+    }
+}
+
+pub struct X86_64;
+
+impl ArchExt for X86_64 {
+    fn machines(&self) -> &'static [u16] {
+        &[EM_X86_64]
+    }
+
+    fn hwcap_name(&self, bit: u32) -> Option<&'static str> {
+        bit_name(&X86_HWCAP, bit)
+    }
+
+    fn describe_property(&self, pr_type: u32, value: u32) -> Option<String> {
+        describe_property(pr_type, value)
+    }
+
+    fn display_details(&self, loader: &Loader, _flags: u32, _attributes: &[(u64, AttributeValue)]) {
+        display_isa_level(loader);
+    }
+
+    /// REX prefixes and 32-bit displacements make x86-64 code a byte longer on
+    /// average than i386 code.
+    fn text_model(&self, _loader: &Loader) -> Option<TextModel> {
+        Some(TextModel {
+            average_length: 4.0,
+            alignment: 1,
+            padding: X86_PADDING,
+        })
+    }
+
+    fn relocation_name(&self, r_type: u32) -> Option<&'static str> {
+        match r_type {
+            0 => Some("R_X86_64_NONE"),
+            1 => Some("R_X86_64_64"),
+            2 => Some("R_X86_64_PC32"),
+            4 => Some("R_X86_64_PLT32"),
+            5 => Some("R_X86_64_COPY"),
+            6 => Some("R_X86_64_GLOB_DAT"),
+            7 => Some("R_X86_64_JUMP_SLOT"),
+            8 => Some("R_X86_64_RELATIVE"),
+            9 => Some("R_X86_64_GOTPCREL"),
+            10 => Some("R_X86_64_32"),
+            11 => Some("R_X86_64_32S"),
+            16 => Some("R_X86_64_DTPMOD64"),
+            17 => Some("R_X86_64_DTPOFF64"),
+            18 => Some("R_X86_64_TPOFF64"),
+            23 => Some("R_X86_64_TPOFF32"),
+            37 => Some("R_X86_64_IRELATIVE"),
+            41 => Some("R_X86_64_GOTPCRELX"),
+            42 => Some("R_X86_64_REX_GOTPCRELX"),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,128 @@
+//! Extended attributes of files, read for `--ima` and carried over when a
+//! patched file replaces the original. Linux only; elsewhere files have none.
+
+use std::path::Path;
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::ffi::CString;
+    use std::io;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+
+    fn c_path(path: &Path) -> Result<CString, String> {
+        CString::new(path.as_os_str().as_bytes()).map_err(|error| error.to_string())
+    }
+
+    /// Calls `call` with an empty buffer for the size, then with one that
+    /// size.
+    fn sized(call: impl Fn(&mut [u8]) -> isize) -> io::Result<Vec<u8>> {
+        let size = call(&mut []);
+        if size < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let mut value = vec![0u8; size as usize];
+        let size = call(&mut value);
+        if size < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        value.truncate(size as usize);
+        Ok(value)
+    }
+
+    fn absent(error: &io::Error) -> bool {
+        matches!(error.raw_os_error(), Some(libc::ENODATA | libc::ENOTSUP))
+    }
+
+    pub fn read(path: &Path, name: &str) -> Result<Option<Vec<u8>>, String> {
+        let path = c_path(path)?;
+        let name = CString::new(name).map_err(|error| error.to_string())?;
+        let value = sized(|value| {
+            // SAFETY: path and name are NUL-terminated and value is writable
+            // for its length; an empty value only asks for the size.
+            unsafe {
+                libc::getxattr(
+                    path.as_ptr(),
+                    name.as_ptr(),
+                    value.as_mut_ptr().cast(),
+                    value.len(),
+                )
+            }
+        });
+        match value {
+            Ok(value) => Ok(Some(value)),
+            Err(error) if absent(&error) => Ok(None),
+            Err(error) => Err(error.to_string()),
+        }
+    }
+
+    pub fn list(path: &Path) -> Result<Vec<String>, String> {
+        let path = c_path(path)?;
+        let names = sized(|names| {
+            // SAFETY: path is NUL-terminated and names is writable for its
+            // length; an empty one only asks for the size.
+            unsafe { libc::listxattr(path.as_ptr(), names.as_mut_ptr().cast(), names.len()) }
+        });
+        match names {
+            Ok(names) => Ok(names
+                .split(|&byte| byte == 0)
+                .filter(|name| !name.is_empty())
+                .map(|name| String::from_utf8_lossy(name).into_owned())
+                .collect()),
+            Err(error) if absent(&error) => Ok(Vec::new()),
+            Err(error) => Err(error.to_string()),
+        }
+    }
+
+    pub fn write(path: &Path, name: &str, value: &[u8]) -> Result<(), String> {
+        let path = c_path(path)?;
+        let name = CString::new(name).map_err(|error| error.to_string())?;
+        // SAFETY: path and name are NUL-terminated and value is readable for
+        // its length.
+        let result = unsafe {
+            libc::setxattr(
+                path.as_ptr(),
+                name.as_ptr(),
+                value.as_ptr().cast(),
+                value.len(),
+                0,
+            )
+        };
+        if result < 0 {
+            return Err(io::Error::last_os_error().to_string());
+        }
+        Ok(())
+    }
+}
+
+/// The value of the attribute `name` of `path`, or `None` when it has none.
+pub fn read(path: &Path, name: &str) -> Result<Option<Vec<u8>>, String> {
+    #[cfg(target_os = "linux")]
+    return linux::read(path, name);
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = (path, name);
+        Err("extended attributes are only read on Linux".to_string())
+    }
+}
+
+/// The names of the attributes of `path`.
+pub fn list(path: &Path) -> Result<Vec<String>, String> {
+    #[cfg(target_os = "linux")]
+    return linux::list(path);
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = path;
+        Ok(Vec::new())
+    }
+}
+
+pub fn write(path: &Path, name: &str, value: &[u8]) -> Result<(), String> {
+    #[cfg(target_os = "linux")]
+    return linux::write(path, name, value);
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = (path, name, value);
+        Err("extended attributes are only written on Linux".to_string())
+    }
+}
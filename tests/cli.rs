@@ -0,0 +1,424 @@
+//! The command line as scripts see it: exit statuses for option mistakes,
+//! unparsable inputs and exceeded limits, and what reaches standard output.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command, Output};
+
+fn fixture_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures")
+}
+
+fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_elf-header-parser"))
+        .args(args)
+        .current_dir(fixture_dir())
+        .env("XDG_CONFIG_HOME", fixture_dir())
+        .env_remove("ELFHP_FORMAT")
+        .env_remove("ELFHP_STRICT")
+        .output()
+        .expect("cannot run elf-header-parser")
+}
+
+/// Each mode is a report of its own, so a second one would be dropped, and
+/// with it a gate such as `--max-stack`.
+#[test]
+fn two_report_modes_are_a_usage_error() {
+    let output = run(&[
+        "--stack-sizes",
+        "--max-stack",
+        "1",
+        "--checksec",
+        "x86_64-shared",
+    ]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("--checksec cannot be combined with --stack-sizes"));
+}
+
+/// Runs with `args` followed by a scratch file holding `bytes`.
+fn run_on(name: &str, bytes: &[u8], args: &[&str]) -> Output {
+    let path = env::temp_dir().join(format!("elf-header-parser-cli-{}-{}", process::id(), name));
+    fs::write(&path, bytes).unwrap();
+    let output = run(&[args, &[path.to_str().unwrap()]].concat());
+    fs::remove_file(&path).unwrap();
+    output
+}
+
+/// Runs on a scratch file holding `bytes` and returns the exit status.
+fn status_for(name: &str, bytes: &[u8]) -> Option<i32> {
+    run_on(name, bytes, &[]).status.code()
+}
+
+#[test]
+fn file_that_is_not_elf_is_a_parse_error() {
+    let text = b"#!/bin/sh\necho this is a shell script, not ELF\n";
+    assert_eq!(status_for("script", text), Some(3));
+}
+
+#[test]
+fn truncated_header_is_a_parse_error() {
+    let elf = fs::read(fixture_dir().join("x86_64.o")).unwrap();
+    assert_eq!(status_for("truncated", &elf[..40]), Some(3));
+}
+
+#[test]
+fn file_smaller_than_e_ident_is_a_parse_error() {
+    assert_eq!(status_for("tiny", b"\x7fELF"), Some(3));
+}
+
+#[test]
+fn readable_elf_file_succeeds() {
+    assert_eq!(run(&["x86_64.o"]).status.code(), Some(0));
+}
+
+/// The summary counts the files processed, not the words on the command line.
+#[test]
+fn summary_counts_the_files_processed() {
+    let output = run(&["manifest", "verify", "no-such-manifest.json", "x86_64.o"]);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("1 of 1 file(s) failed"));
+}
+
+/// Baselines and manifests name the build that wrote them, as reports do.
+#[test]
+fn written_json_files_name_their_build() {
+    let dir = env::temp_dir().join(format!("elf-header-parser-cli-{}-tool", process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let baseline = dir.join("baseline.json");
+    let manifest = dir.join("manifest.json");
+    run(&["--write-baseline", baseline.to_str().unwrap(), "x86_64.o"]);
+    run(&["manifest", "create", manifest.to_str().unwrap(), "x86_64.o"]);
+    let written = [fs::read_to_string(&baseline), fs::read_to_string(&manifest)];
+    fs::remove_dir_all(&dir).unwrap();
+
+    for text in written {
+        let text = text.unwrap();
+        assert!(text.contains("\"tool\": {"), "{}", text);
+        assert!(text.contains(&format!("\"version\": \"{}\"", env!("CARGO_PKG_VERSION"))));
+    }
+}
+
+/// Listings and graphs go through the output format like every report.
+#[test]
+fn listings_follow_the_output_format() {
+    let machines = run(&["--list-machines", "--format", "json"]);
+    let text = String::from_utf8_lossy(&machines.stdout);
+    assert!(text.starts_with('['), "{}", text);
+    assert!(text.contains("EM_X86_64"));
+
+    let graph = run(&[
+        "call-graph",
+        "--graph",
+        "dot",
+        "--format",
+        "json",
+        "x86_64.o",
+    ]);
+    let text = String::from_utf8_lossy(&graph.stdout);
+    assert!(text.starts_with('['), "{}", text);
+    assert!(text.contains("{\"text\":\"digraph {\"}"), "{}", text);
+}
+
+/// Asserts that a limit failed the file as a parse error and said why.
+fn assert_limits_exceeded(output: &Output, limit: &str) {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(3), "{}", stderr);
+    assert!(stderr.contains("limits exceeded"), "{}", stderr);
+    assert!(stderr.contains(limit), "{}", stderr);
+}
+
+#[test]
+fn too_many_sections_exceed_the_limits() {
+    let output = run(&["--max-sections", "3", "--checksec", "x86_64.o"]);
+    assert_limits_exceeded(&output, "more than --max-sections 3");
+}
+
+#[test]
+fn too_many_symbols_exceed_the_limits() {
+    let output = run(&["--max-symbols", "3", "--checksec", "x86_64-shared"]);
+    assert_limits_exceeded(&output, ".dynsym has 7 symbols, more than --max-symbols 3");
+}
+
+/// x86_64.o with its empty `.note.GNU-stack` turned into a note section
+/// holding a GNU build ID of 16 bytes.
+fn with_build_id_note() -> Vec<u8> {
+    let mut elf = fs::read(fixture_dir().join("x86_64.o")).unwrap();
+    let offset = elf.len().next_multiple_of(4);
+    elf.resize(offset, 0);
+    for word in [4u32, 16, 3] {
+        elf.extend_from_slice(&word.to_le_bytes());
+    }
+    elf.extend_from_slice(b"GNU\0");
+    elf.extend_from_slice(&[0xab; 16]);
+    let shoff = u64::from_le_bytes(elf[0x28..0x30].try_into().unwrap()) as usize;
+    let header = shoff + 7 * 64;
+    elf[header + 4..header + 8].copy_from_slice(&7u32.to_le_bytes());
+    elf[header + 24..header + 32].copy_from_slice(&(offset as u64).to_le_bytes());
+    elf[header + 32..header + 40].copy_from_slice(&32u64.to_le_bytes());
+    elf[header + 48..header + 56].copy_from_slice(&4u64.to_le_bytes());
+    elf
+}
+
+#[test]
+fn large_note_exceeds_the_limits() {
+    let elf = with_build_id_note();
+    assert_eq!(run_on("note", &elf, &["--checksec"]).status.code(), Some(0));
+    let output = run_on("large-note", &elf, &["--max-note-size", "8", "--checksec"]);
+    assert_limits_exceeded(&output, "20 bytes, more than --max-note-size 8");
+}
+
+/// x86_64.o behind 100 bytes of something else, as in a self-extracting archive.
+fn behind_prefix() -> Vec<u8> {
+    let mut blob = b"#!/bin/sh\n".repeat(10);
+    blob.extend(fs::read(fixture_dir().join("x86_64.o")).unwrap());
+    blob
+}
+
+#[test]
+fn offset_auto_finds_a_concatenated_image() {
+    let output = run_on("bundle", &behind_prefix(), &["--offset", "auto"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(0));
+    assert!(
+        stdout.contains("contains 1 embedded ELF image(s)"),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("0x64        944 bytes  concatenated image"),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("-bundle@0x64"), "{}", stdout);
+}
+
+#[test]
+fn carve_extracts_the_image() {
+    let dir = env::temp_dir().join(format!("elf-header-parser-cli-{}-carve", process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let output = run_on(
+        "carved",
+        &behind_prefix(),
+        &["--carve", "--extract", dir.to_str().unwrap()],
+    );
+    let extracted: Vec<_> = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| fs::read(entry.unwrap().path()).unwrap())
+        .collect();
+    fs::remove_dir_all(&dir).unwrap();
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(0));
+    assert!(stdout.contains("1 ELF image(s) found"), "{}", stdout);
+    assert!(
+        stdout.contains("ELF64 LSB Relocatable file, AMD64"),
+        "{}",
+        stdout
+    );
+    assert_eq!(
+        extracted,
+        [fs::read(fixture_dir().join("x86_64.o")).unwrap()]
+    );
+}
+
+/// `--help` stays short; `--help-full` adds the environment and examples, and
+/// `gen-man` renders the same table as a roff man page.
+#[test]
+fn full_help_and_man_page() {
+    let help = String::from_utf8_lossy(&run(&["--help"]).stdout).into_owned();
+    let full = String::from_utf8_lossy(&run(&["--help-full"]).stdout).into_owned();
+    let man = run(&["gen-man"]);
+    let man_text = String::from_utf8_lossy(&man.stdout);
+
+    assert!(!help.contains("Environment:") && !help.contains("gen-man"));
+    assert!(full.contains("Environment:") && full.contains("Examples:"));
+    assert!(full.contains("ELFHP_FORMAT"));
+    assert_eq!(man.status.code(), Some(0));
+    assert!(
+        man_text.starts_with(".TH ELF-HEADER-PARSER 1 "),
+        "{}",
+        man_text
+    );
+    assert!(man_text.contains(".SH ENVIRONMENT"), "{}", man_text);
+    assert!(man_text.contains("\\fB\\-\\-checksec\\fR"));
+}
+
+/// Special files are skipped without being opened, so a FIFO cannot block the
+/// run, and the other inputs are still reported.
+#[cfg(unix)]
+#[test]
+fn special_files_are_skipped() {
+    let fifo = env::temp_dir().join(format!("elf-header-parser-cli-{}-fifo", process::id()));
+    let status = Command::new("mkfifo").arg(&fifo).status().unwrap();
+    assert!(status.success());
+    let output = run(&[fifo.to_str().unwrap(), "x86_64.o", "/dev/null", "."]);
+    fs::remove_file(&fifo).unwrap();
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(3), "{}", stderr);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("x86_64.o"));
+    for skipped in [
+        "fifo: skipped, is a FIFO",
+        "/dev/null: skipped, is a character device",
+        ".: skipped, is a directory",
+    ] {
+        assert!(stderr.contains(skipped), "{}", stderr);
+    }
+    assert!(stderr.contains("3 of 4 file(s) failed"), "{}", stderr);
+}
+
+/// Inputs too short for an ELF header say how much is there and what is missing.
+#[test]
+fn short_inputs_are_diagnosed() {
+    let elf = fs::read(fixture_dir().join("x86_64.o")).unwrap();
+    for (name, bytes, message) in [
+        ("empty", &b""[..], "file too small to be ELF (0 bytes)"),
+        (
+            "magic",
+            &b"\x7fELF"[..],
+            "file too small to be ELF (4 bytes)",
+        ),
+        (
+            "cut",
+            &elf[..40],
+            "truncated ELF header (40 bytes), cannot read e_shoff at 0x28..0x30",
+        ),
+    ] {
+        let output = run_on(name, bytes, &[]);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert_eq!(output.status.code(), Some(3), "{}", stderr);
+        assert!(stderr.contains(message), "{}", stderr);
+    }
+}
+
+/// A header size other than the class's is a warning, an error with `--strict`.
+#[test]
+fn header_size_deviation_warns() {
+    let mut elf = fs::read(fixture_dir().join("x86_64.o")).unwrap();
+    elf[0x34] = 72;
+    let message = "e_ehsize is 72, but an ELF64 header is 64 bytes";
+    for (args, status, severity) in [
+        (&[][..], 0, "warning"),
+        (&["--fail-on", "warning"][..], 4, "warning"),
+        (&["--strict"][..], 3, "error"),
+    ] {
+        let output = run_on("ehsize", &elf, args);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert_eq!(output.status.code(), Some(status), "{:?}: {}", args, stderr);
+        assert!(stderr.contains(&format!("{}: ", severity)), "{}", stderr);
+        assert!(stderr.contains(message), "{}", stderr);
+    }
+}
+
+/// `linkage` classifies every file under a directory and counts each kind.
+#[test]
+fn linkage_classifies_a_directory() {
+    let dir = env::temp_dir().join(format!("elf-header-parser-cli-{}-linkage", process::id()));
+    fs::create_dir_all(dir.join("lib")).unwrap();
+    for (fixture, copy) in [
+        ("x86_64-static", "static"),
+        ("x86_64-static-pie", "static-pie"),
+        ("x86_64-dynamic", "dynamic"),
+        ("x86_64-shared", "lib/libfixture.so.1"),
+    ] {
+        fs::copy(fixture_dir().join(fixture), dir.join(copy)).unwrap();
+    }
+    let output = run(&["linkage", dir.to_str().unwrap()]);
+    fs::remove_dir_all(&dir).unwrap();
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(0), "{}", stdout);
+    let kinds: Vec<(&str, &str)> = stdout
+        .lines()
+        .skip(1)
+        .take_while(|line| !line.is_empty())
+        .filter_map(|line| line.split_once(char::is_whitespace))
+        .map(|(kind, path)| (kind, path.trim().rsplit('/').next().unwrap()))
+        .collect();
+    assert_eq!(
+        kinds,
+        [
+            ("dynamic", "dynamic"),
+            ("dynamic", "libfixture.so.1"),
+            ("static", "static"),
+            ("static-pie", "static-pie"),
+        ]
+    );
+    assert!(
+        stdout.contains(&format!("{:<50} = 1", "Static PIE")),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains(&format!("{:<50} = 2", "Dynamic")),
+        "{}",
+        stdout
+    );
+}
+
+/// x86_64.o relabelled as LoongArch, whose e_flags `--arch-info` decodes.
+#[test]
+fn loongarch_flags_in_arch_info() {
+    let mut elf = fs::read(fixture_dir().join("x86_64.o")).unwrap();
+    elf[0x12..0x14].copy_from_slice(&258u16.to_le_bytes());
+    elf[0x30..0x34].copy_from_slice(&0x43u32.to_le_bytes());
+    let output = run_on("loongarch", &elf, &["--arch-info"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(0), "{}", stdout);
+    for line in ["= LoongArch", "lp64d ABI (double-float)", "object ABI v1"] {
+        assert!(stdout.contains(line), "{}", stdout);
+    }
+}
+
+/// `-v` traces each file, `-vv` also the parsing decisions, such as taking the
+/// section count from section 0; without either, stderr stays quiet.
+#[test]
+fn verbosity_levels() {
+    let mut elf = fs::read(fixture_dir().join("x86_64.o")).unwrap();
+    let shoff = u64::from_le_bytes(elf[0x28..0x30].try_into().unwrap()) as usize;
+    elf[0x3c..0x3e].copy_from_slice(&0u16.to_le_bytes());
+    elf[shoff + 32..shoff + 40].copy_from_slice(&9u64.to_le_bytes());
+    let stderr = |args: &[&str]| {
+        let output = run_on("extended", &elf, args);
+        assert_eq!(output.status.code(), Some(0));
+        String::from_utf8_lossy(&output.stderr).into_owned()
+    };
+
+    let decision = "debug: e_shnum is 0, reading the real count from section 0: 9";
+    assert_eq!(stderr(&[]), "");
+    let verbose = stderr(&["-v"]);
+    assert!(
+        verbose.contains("ELF64 LSB Relocatable file, AMD64"),
+        "{}",
+        verbose
+    );
+    assert!(!verbose.contains(decision), "{}", verbose);
+    assert!(stderr(&["-vv"]).contains(decision));
+}
+
+/// `--timings` adds a table with a row per file and the overall time, on
+/// stderr so that the report itself is unchanged.
+#[test]
+fn timings_table() {
+    let plain = run(&["x86_64.o", "x86_64-shared"]);
+    let timed = run(&["--timings", "x86_64.o", "x86_64-shared"]);
+    assert_eq!(timed.status.code(), Some(0));
+    assert_eq!(timed.stdout, plain.stdout);
+
+    let stderr = String::from_utf8_lossy(&timed.stderr);
+    let header = stderr
+        .lines()
+        .find(|line| line.starts_with("Timings (ms)"))
+        .unwrap_or_else(|| panic!("no timings in {}", stderr));
+    for phase in ["mmap", "header", "sections", "symbols", "output", "total"] {
+        assert!(header.contains(phase), "{}", header);
+    }
+    for row in ["x86_64.o ", "x86_64-shared ", "Overall "] {
+        assert!(
+            stderr.lines().any(|line| line.starts_with(row)),
+            "{}",
+            stderr
+        );
+    }
+}
@@ -0,0 +1,67 @@
+//! The C interface, called the way a C program would.
+
+#![cfg(feature = "ffi")]
+
+use std::ffi::{CStr, CString};
+use std::mem::MaybeUninit;
+use std::path::Path;
+
+use elf_header_parser::ffi::*;
+
+fn fixture(name: &str) -> CString {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name);
+    CString::new(path.to_str().unwrap()).unwrap()
+}
+
+#[test]
+fn reads_header_sections_and_symbols() {
+    unsafe {
+        let file = elfhp_open(fixture("mips.o").as_ptr());
+        assert!(!file.is_null());
+
+        let mut header = MaybeUninit::<ElfhpHeader>::uninit();
+        assert!(elfhp_header(file, header.as_mut_ptr()));
+        let header = header.assume_init();
+        assert_eq!((header.ei_class, header.ei_data), (1, 2));
+        assert_eq!((header.e_type, header.e_machine), (1, 8));
+        assert_eq!((header.e_ehsize, header.e_shnum), (52, 9));
+
+        let sections = elfhp_sections_iter(file);
+        elfhp_close(file);
+        let mut section = MaybeUninit::<ElfhpSection>::uninit();
+        let mut names = Vec::new();
+        while elfhp_sections_next(sections, section.as_mut_ptr()) {
+            let section = section.assume_init_ref();
+            names.push(CStr::from_ptr(section.name).to_str().unwrap().to_string());
+        }
+        elfhp_sections_iter_free(sections);
+        assert_eq!(names.len(), 9);
+        assert_eq!(names[2], ".text");
+
+        let file = elfhp_open(fixture("mips.o").as_ptr());
+        let symbols = elfhp_symbols_iter(file, false);
+        let mut symbol = MaybeUninit::<ElfhpSymbol>::uninit();
+        let mut undefined = Vec::new();
+        while elfhp_symbols_next(symbols, symbol.as_mut_ptr()) {
+            let symbol = symbol.assume_init_ref();
+            let name = CStr::from_ptr(symbol.name).to_str().unwrap();
+            if symbol.st_shndx == 0 && !name.is_empty() {
+                undefined.push(name.to_string());
+            }
+        }
+        elfhp_symbols_iter_free(symbols);
+        elfhp_close(file);
+        assert_eq!(undefined, ["helper"]);
+    }
+}
+
+#[test]
+fn reports_why_a_file_cannot_be_opened() {
+    unsafe {
+        assert!(elfhp_open(fixture("generate.sh").as_ptr()).is_null());
+        let error = CStr::from_ptr(elfhp_last_error()).to_str().unwrap();
+        assert!(error.ends_with("is not an ELF file"), "{}", error);
+    }
+}
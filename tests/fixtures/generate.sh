@@ -0,0 +1,215 @@
+#!/bin/sh
+# Regenerates the checked-in fixtures and the readelf output recorded next to
+# them. Needs llvm-mc (any recent LLVM), GNU ld and GNU readelf; the tests
+# themselves only read the files.
+set -eu
+cd "$(dirname "$0")"
+
+# A function calling an undefined one, initialised data, a common symbol and an
+# inline function in a COMDAT group.
+cat > x86_64.s <<'ASM'
+    .text
+    .globl  compute
+    .type   compute, @function
+compute:
+    call    helper
+    ret
+    .size   compute, .-compute
+    .section .text.inline_fn,"axG",@progbits,inline_fn,comdat
+    .weak   inline_fn
+    .type   inline_fn, @function
+inline_fn:
+    ret
+    .data
+    .globl  counter
+counter:
+    .long   1
+    .comm   shared_buffer, 64, 16
+    .section .note.GNU-stack,"",@progbits
+ASM
+llvm-mc -triple=x86_64-linux-gnu -filetype=obj x86_64.s -o x86_64.o
+
+cat > i386.s <<'ASM'
+    .text
+    .globl  compute
+compute:
+    call    helper
+    ret
+    .data
+    .globl  counter
+counter:
+    .long   1
+    .section .note.GNU-stack,"",@progbits
+ASM
+llvm-mc -triple=i386-linux-gnu -filetype=obj i386.s -o i386.o
+
+cat > aarch64.s <<'ASM'
+    .text
+    .globl  compute
+compute:
+    bl      helper
+    ret
+    .data
+    .globl  counter
+counter:
+    .quad   1
+ASM
+llvm-mc -triple=aarch64-linux-gnu -filetype=obj aarch64.s -o aarch64.o
+llvm-mc -triple=aarch64_be-linux-gnu -filetype=obj aarch64.s -o aarch64_be.o
+
+cat > armv7.s <<'ASM'
+    .cpu    cortex-a9
+    .fpu    neon
+    .eabi_attribute 20, 1
+    .eabi_attribute 28, 1
+    .text
+    .globl  compute
+compute:
+    bl      helper
+    bx      lr
+ASM
+llvm-mc -triple=armv7-linux-gnueabihf -filetype=obj armv7.s -o armv7.o
+
+cat > mips.s <<'ASM'
+    .text
+    .globl  compute
+compute:
+    jal     helper
+    nop
+    jr      $ra
+    nop
+ASM
+llvm-mc -triple=mips-linux-gnu -filetype=obj mips.s -o mips.o
+llvm-mc -triple=mips64el-linux-gnuabi64 -filetype=obj mips.s -o mips64el.o
+
+cat > ppc64le.s <<'ASM'
+    .abiversion 2
+    .text
+    .globl  compute
+    .type   compute, @function
+compute:
+0:  addis   2, 12, .TOC.-0b@ha
+    addi    2, 2, .TOC.-0b@l
+    .localentry compute, .-compute
+    bl      helper
+    nop
+    blr
+ASM
+llvm-mc -triple=powerpc64le-linux-gnu -filetype=obj ppc64le.s -o ppc64le.o
+
+cat > ppc64.s <<'ASM'
+    .abiversion 1
+    .section .opd,"aw"
+    .align  3
+    .globl  compute
+compute:
+    .quad   .L.compute, .TOC.@tocbase, 0
+    .text
+.L.compute:
+    bl      helper
+    nop
+    blr
+ASM
+llvm-mc -triple=powerpc64-linux-gnu -filetype=obj ppc64.s -o ppc64.o
+
+cat > riscv64.s <<'ASM'
+    .attribute arch, "rv64i2p0_m2p0_a2p0_c2p0"
+    .attribute stack_align, 16
+    .text
+    .globl  compute
+compute:
+    call    helper
+    ret
+ASM
+llvm-mc -triple=riscv64-linux-gnu -mattr=+m,+a,+c -filetype=obj riscv64.s -o riscv64.o
+
+cat > s390x.s <<'ASM'
+    .text
+    .globl  compute
+compute:
+    brasl   %r14, helper
+    br      %r14
+ASM
+llvm-mc -triple=s390x-linux-gnu -filetype=obj s390x.s -o s390x.o
+
+# Two functions and the .stack_sizes records clang -fstack-size-section writes.
+cat > stack.s <<'ASM'
+    .text
+    .globl  small
+    .type   small, @function
+small:
+    ret
+    .size   small, .-small
+    .globl  large
+    .type   large, @function
+large:
+    sub     $4096, %rsp
+    add     $4096, %rsp
+    ret
+    .size   large, .-large
+    .section .stack_sizes,"o",@progbits,.text
+    .quad   small
+    .uleb128 8
+    .quad   large
+    .uleb128 4104
+    .section .note.GNU-stack,"",@progbits
+ASM
+llvm-mc -triple=x86_64-linux-gnu -filetype=obj stack.s -o x86_64-stack.o
+
+# Linked files, for program headers and the dynamic section.
+cat > start.s <<'ASM'
+    .text
+    .globl  _start
+_start:
+    call    compute
+    hlt
+    .globl  helper
+helper:
+    ret
+    .section .note.GNU-stack,"",@progbits
+ASM
+llvm-mc -triple=x86_64-linux-gnu -filetype=obj start.s -o start.o
+ld -m elf_x86_64 --build-id=none -z noseparate-code -z max-page-size=4096 \
+    -z norelro -o x86_64-static start.o x86_64.o
+ld -m elf_x86_64 --build-id=none -z noseparate-code -z max-page-size=4096 \
+    -z relro -z now -shared -soname libfixture.so.1 -o x86_64-shared start.o x86_64.o
+ld -m elf_x86_64 --build-id=none -z noseparate-code -z max-page-size=4096 \
+    -z relro -z now -pie --no-dynamic-linker -o x86_64-static-pie start.o x86_64.o
+# Code loading an absolute address, which needs a text relocation once shared.
+cat > textrel.s <<'ASM'
+    .text
+    .globl  address
+address:
+    movabs  $counter, %rax
+    ret
+    .data
+    .globl  counter
+counter:
+    .long   1
+    .section .note.GNU-stack,"",@progbits
+ASM
+llvm-mc -triple=x86_64-linux-gnu -filetype=obj textrel.s -o textrel.o
+ld -m elf_x86_64 --build-id=none -z noseparate-code -z max-page-size=4096 \
+    -z notext -shared -soname libtextrel.so -o x86_64-textrel textrel.o
+# An executable importing compute from x86_64-shared.
+cat > dynamic.s <<'ASM'
+    .text
+    .globl  _start
+_start:
+    call    compute@PLT
+    hlt
+    .section .note.GNU-stack,"",@progbits
+ASM
+llvm-mc -triple=x86_64-linux-gnu -filetype=obj dynamic.s -o dynamic.o
+ld -m elf_x86_64 --build-id=none -z noseparate-code -z max-page-size=4096 \
+    -z relro -z now -pie -dynamic-linker /lib64/ld-linux-x86-64.so.2 \
+    -o x86_64-dynamic dynamic.o x86_64-shared
+llvm-mc -triple=i386-linux-gnu -filetype=obj start.s -o start32.o
+ld -m elf_i386 --build-id=none -z noseparate-code -z max-page-size=4096 \
+    -o i386-static start32.o i386.o
+rm -f ./*.s start.o start32.o textrel.o dynamic.o
+
+for fixture in *.o x86_64-static x86_64-shared x86_64-static-pie x86_64-textrel \
+    x86_64-dynamic i386-static; do
+    LC_ALL=C readelf -h -S -s -W "$fixture" > "$fixture.readelf"
+done
@@ -0,0 +1,129 @@
+//! `serve`, run as a child process and talked to over TCP.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{Shutdown, TcpStream};
+use std::path::Path;
+use std::process::{Child, ChildStderr, Command, Stdio};
+
+/// A running `serve`, killed when dropped.
+struct Server {
+    child: Child,
+    address: String,
+    /// Kept open so that the server's log lines have somewhere to go.
+    _stderr: BufReader<ChildStderr>,
+}
+
+impl Server {
+    fn start(options: &[&str]) -> Server {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_elf-header-parser"))
+            .args(["serve", "127.0.0.1:0"])
+            .arg(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures"))
+            .args(options)
+            .env_remove("ELFHP_FORMAT")
+            .stderr(Stdio::piped())
+            .spawn()
+            .expect("cannot run elf-header-parser serve");
+        let mut stderr = BufReader::new(child.stderr.take().unwrap());
+        let mut line = String::new();
+        stderr.read_line(&mut line).unwrap();
+        let address = line
+            .trim()
+            .strip_prefix("serving on http://")
+            .unwrap_or_else(|| panic!("unexpected first line: {}", line))
+            .to_string();
+        Server {
+            child,
+            address,
+            _stderr: stderr,
+        }
+    }
+
+    /// Sends `request` as it is and returns the status code and the body.
+    fn send(&self, request: &[u8]) -> (u16, String) {
+        let mut stream = TcpStream::connect(&self.address).unwrap();
+        stream.write_all(request).unwrap();
+        stream.shutdown(Shutdown::Write).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let status = response
+            .split_whitespace()
+            .nth(1)
+            .and_then(|status| status.parse().ok())
+            .unwrap_or(0);
+        let body = response
+            .split_once("\r\n\r\n")
+            .map_or(String::new(), |(_, body)| body.to_string());
+        (status, body)
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// A Content-Length just under the limit with a few bytes behind it is
+/// answered as a short body, without waiting for or reserving the rest.
+#[test]
+fn short_body_under_a_large_content_length() {
+    let server = Server::start(&[]);
+    let (status, body) =
+        server.send(b"POST /analyze HTTP/1.1\r\nContent-Length: 268435455\r\n\r\n\x7fELF");
+    assert_eq!(status, 400, "{}", body);
+    assert!(body.contains("shorter than Content-Length"), "{}", body);
+    assert_eq!(server.send(b"GET /health HTTP/1.1\r\n\r\n").0, 200);
+}
+
+/// The limits of the command line hold on the connection threads as well.
+#[test]
+fn limits_apply_to_every_connection() {
+    let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/x86_64-shared");
+    let request = format!("GET /analyze?path={} HTTP/1.1\r\n\r\n", fixture.display());
+    let server = Server::start(&["--checksec", "--max-sections", "2"]);
+    let (status, body) = server.send(request.as_bytes());
+    assert_eq!(status, 422, "{}", body);
+    assert!(body.contains("limits exceeded"), "{}", body);
+}
+
+/// A header line that never ends is cut off at 8 KiB, not read into memory
+/// until it does.
+#[test]
+fn oversized_header_line() {
+    let server = Server::start(&[]);
+    let mut request = b"GET /health HTTP/1.1\r\nX-Padding: ".to_vec();
+    request.resize(request.len() + 64 * 1024, b'a');
+    let (status, body) = server.send(&request);
+    assert_eq!(status, 431, "{}", body);
+    assert!(body.contains("longer than 8192 bytes"), "{}", body);
+}
+
+#[test]
+fn too_many_headers() {
+    let server = Server::start(&[]);
+    let mut request = b"GET /health HTTP/1.1\r\n".to_vec();
+    for index in 0..200 {
+        request.extend_from_slice(format!("X-Header-{}: 1\r\n", index).as_bytes());
+    }
+    request.extend_from_slice(b"\r\n");
+    let (status, body) = server.send(&request);
+    assert_eq!(status, 431, "{}", body);
+}
+
+/// Uploads are analysed from the file they are stored in.
+#[test]
+fn uploaded_file_is_analysed() {
+    let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/x86_64.o");
+    let elf = std::fs::read(fixture).unwrap();
+    let mut request = format!(
+        "POST /analyze?name=x86_64.o HTTP/1.1\r\nContent-Length: {}\r\n\r\n",
+        elf.len()
+    )
+    .into_bytes();
+    request.extend_from_slice(&elf);
+    let server = Server::start(&[]);
+    let (status, body) = server.send(&request);
+    assert_eq!(status, 200, "{}", body);
+    assert!(body.contains("x86_64.o"), "{}", body);
+}
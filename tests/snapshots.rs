@@ -0,0 +1,428 @@
+//! Runs the binary on the small fixtures in `tests/fixtures` and compares its
+//! output with the snapshots in `tests/snapshots`, and its header and symbol
+//! values with the `readelf` output recorded next to each fixture.
+//!
+//! After an intended output change, `UPDATE_SNAPSHOTS=1 cargo test` rewrites the
+//! snapshots; review the diff before committing it. `tests/fixtures/generate.sh`
+//! rebuilds the fixtures and their readelf output.
+
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const OBJECTS: &[&str] = &[
+    "aarch64.o",
+    "aarch64_be.o",
+    "armv7.o",
+    "i386.o",
+    "mips.o",
+    "mips64el.o",
+    "ppc64.o",
+    "ppc64le.o",
+    "riscv64.o",
+    "s390x.o",
+    "x86_64.o",
+    "x86_64-stack.o",
+];
+
+const LINKED: &[&str] = &[
+    "i386-static",
+    "x86_64-shared",
+    "x86_64-static",
+    "x86_64-static-pie",
+    "x86_64-textrel",
+    "x86_64-dynamic",
+];
+
+/// Snapshots taken for every fixture, as (name, arguments before the file).
+const EVERY_FIXTURE: &[(&str, &[&str])] = &[("header", &[]), ("arch-info", &["--arch-info"])];
+
+/// Snapshots for one fixture only.
+const SINGLE: &[(&str, &str, &[&str])] = &[
+    ("x86_64.o", "groups", &["--groups"]),
+    ("x86_64.o", "json", &["--format", "json"]),
+    ("x86_64-shared", "functions", &["--functions"]),
+    ("x86_64.o", "strings-scan", &["--strings-scan"]),
+    ("ppc64le.o", "text-stats", &["--text-stats"]),
+    ("x86_64-shared", "policy", &["policy", "policy.toml"]),
+    (
+        "x86_64-shared",
+        "baseline",
+        &["--baseline", "baseline.json"],
+    ),
+    ("i386.o", "explain", &["explain", "e_shstrndx"]),
+    ("x86_64-shared", "debug-info", &["--debug-info"]),
+    ("x86_64-shared", "brief", &["--brief"]),
+    ("x86_64-static", "classify", &["--classify"]),
+    ("x86_64-shared", "mitigations", &["--mitigations"]),
+    ("x86_64-shared", "normalized", &["--normalized"]),
+    (
+        "x86_64-shared",
+        "query",
+        &[
+            "--query",
+            ".sections[] | select(.flag_letters contains \"X\") | .name",
+        ],
+    ),
+    (
+        "x86_64-static",
+        "manifest",
+        &["manifest", "verify", "manifest.json"],
+    ),
+    (
+        "x86_64-shared",
+        "size",
+        &["--config", "sections.toml", "--size"],
+    ),
+    (
+        "x86_64-static",
+        "verify",
+        &[
+            "verify",
+            "--golden",
+            "x86_64-static",
+            "--mask",
+            "0x400018+8",
+        ],
+    ),
+    ("x86_64-shared", "checksec", &["--checksec"]),
+    (
+        "x86_64-shared",
+        "checksec-ja",
+        &["--lang", "ja", "--checksec"],
+    ),
+    ("x86_64-static", "checksec", &["--checksec"]),
+    ("i386-static", "checksec", &["--checksec"]),
+    ("x86_64-static-pie", "checksec", &["--checksec"]),
+    ("x86_64-textrel", "textrel", &["--textrel"]),
+    ("x86_64-shared", "textrel", &["--textrel"]),
+    ("x86_64-textrel", "android", &["--android"]),
+    (
+        "x86_64.o",
+        "resolve-preview",
+        &["resolve-preview", "x86_64.o"],
+    ),
+    // x86_64-dynamic against the library its import comes from, which goes
+    // last where the fixture is put.
+    (
+        "x86_64-shared",
+        "resolve-undefs",
+        &["resolve-undefs", "x86_64-dynamic", "--with"],
+    ),
+    (
+        "x86_64-shared",
+        "lint-exports",
+        &[
+            "lint-exports",
+            "--allow-export",
+            "comp*",
+            "--version-script",
+            "required",
+        ],
+    ),
+    ("x86_64-shared", "dlopen", &["--dlopen"]),
+    ("x86_64-dynamic", "dlopen", &["--dlopen"]),
+    ("x86_64-textrel", "dlopen", &["--dlopen"]),
+    (
+        "x86_64-stack.o",
+        "stack-sizes",
+        &["--stack-sizes", "--max-stack", "1024"],
+    ),
+];
+
+fn fixture_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures")
+}
+
+fn snapshot_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/snapshots")
+}
+
+/// Runs the binary from the fixture directory, so reports name the bare file.
+/// Returns stdout, followed by stderr and the exit status when there is anything
+/// to say about them.
+fn run(args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_elf-header-parser"))
+        .args(args)
+        .current_dir(fixture_dir())
+        // Keeps the user's config.toml out of the reports.
+        .env("XDG_CONFIG_HOME", fixture_dir())
+        // And the defaults of the environment, such as ELFHP_FORMAT.
+        .env_remove("ELFHP_FORMAT")
+        .env_remove("ELFHP_COLOR")
+        .env_remove("ELFHP_STRICT")
+        .env_remove("DEBUGINFOD_URLS")
+        .output()
+        .expect("cannot run elf-header-parser");
+    let mut text = without_tool(&String::from_utf8_lossy(&output.stdout));
+    if !output.stderr.is_empty() {
+        text.push_str("--- stderr\n");
+        text.push_str(&String::from_utf8_lossy(&output.stderr));
+    }
+    if !output.status.success() {
+        text.push_str(&format!("--- {}\n", output.status));
+    }
+    text
+}
+
+/// Drops the `"tool"` member of the JSON records, which names the commit the
+/// binary was built from and so changes with every commit.
+fn without_tool(text: &str) -> String {
+    let mut rest = text;
+    let mut kept = String::new();
+    while let Some(start) = rest.find(",\"tool\":{") {
+        kept.push_str(&rest[..start]);
+        // The member holds no objects, so the first brace closes it.
+        rest = &rest[start..];
+        rest = &rest[rest.find('}').map_or(rest.len(), |end| end + 1)..];
+    }
+    kept.push_str(rest);
+    kept
+}
+
+/// Every snapshot case: the snapshot file name and the full argument list.
+fn cases() -> Vec<(String, Vec<&'static str>)> {
+    let mut cases = Vec::new();
+    for fixture in OBJECTS.iter().chain(LINKED) {
+        for (mode, args) in EVERY_FIXTURE {
+            let mut args = args.to_vec();
+            args.push(fixture);
+            cases.push((format!("{}.{}", fixture, mode), args));
+        }
+    }
+    for fixture in OBJECTS {
+        cases.push((
+            format!("{}.obj-summary", fixture),
+            vec!["obj-summary", fixture],
+        ));
+    }
+    for (fixture, mode, args) in SINGLE {
+        let mut args = args.to_vec();
+        args.push(fixture);
+        cases.push((format!("{}.{}", fixture, mode), args));
+    }
+    cases
+}
+
+/// Where the two texts first differ, for the failure message.
+fn first_difference(expected: &str, actual: &str) -> String {
+    let mut expected_lines = expected.lines();
+    let mut actual_lines = actual.lines();
+    let mut line = 1;
+    loop {
+        match (expected_lines.next(), actual_lines.next()) {
+            (Some(want), Some(got)) if want == got => line += 1,
+            (want, got) => {
+                return format!(
+                    "line {}:\n    expected: {}\n    actual:   {}",
+                    line,
+                    want.unwrap_or("<end of output>"),
+                    got.unwrap_or("<end of output>")
+                )
+            }
+        }
+    }
+}
+
+#[test]
+fn output_matches_snapshots() {
+    let update = env::var_os("UPDATE_SNAPSHOTS").is_some();
+    let mut failures = Vec::new();
+    for (name, args) in cases() {
+        let path = snapshot_dir().join(format!("{}.txt", name));
+        let actual = run(&args);
+        if update {
+            fs::write(&path, &actual).expect("cannot write a snapshot");
+            continue;
+        }
+        match fs::read_to_string(&path) {
+            Ok(expected) if expected == actual => {}
+            Ok(expected) => failures.push(format!(
+                "{} ({}) differs at {}",
+                name,
+                args.join(" "),
+                first_difference(&expected, &actual)
+            )),
+            Err(_) => failures.push(format!("{} has no snapshot", name)),
+        }
+    }
+    assert!(
+        failures.is_empty(),
+        "{}\n\nrun with UPDATE_SNAPSHOTS=1 to accept the new output",
+        failures.join("\n")
+    );
+}
+
+/// The `Key: value` lines of the "ELF Header:" part of recorded readelf output.
+fn readelf_header(readelf: &str) -> HashMap<&str, &str> {
+    readelf
+        .lines()
+        .skip_while(|line| !line.starts_with("ELF Header:"))
+        .skip(1)
+        .take_while(|line| line.starts_with("  "))
+        .filter_map(|line| line.split_once(':'))
+        .map(|(key, value)| (key.trim(), value.trim()))
+        .collect()
+}
+
+/// The `Key = value` lines of a report.
+fn report_fields(report: &str) -> HashMap<String, String> {
+    report
+        .lines()
+        .filter_map(|line| line.split_once(" = "))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .collect()
+}
+
+/// The leading number of a value such as `0x1, abiv1`, `64 (bytes)` or `0x350`.
+fn number(value: &str) -> Option<u64> {
+    let token = value.split([' ', ',']).next()?;
+    match token.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => token.parse().ok(),
+    }
+}
+
+/// readelf's header lines and the report rows that must hold the same number.
+const NUMERIC_FIELDS: &[(&str, &str)] = &[
+    ("Entry point address", "Entry Point"),
+    ("Start of program headers", "Program Header Offset"),
+    ("Start of section headers", "Section Header Offset"),
+    ("Flags", "Flags"),
+    ("Size of this header", "Header's Size"),
+    ("Size of program headers", "Per Program Header's Size"),
+    ("Number of program headers", "Program Header's Number"),
+    ("Size of section headers", "Per Section Header's Size"),
+    ("Number of section headers", "Section Header's Number"),
+    ("Section header string table index", "Entry Index"),
+];
+
+fn readelf_output(fixture: &str) -> String {
+    fs::read_to_string(fixture_dir().join(format!("{}.readelf", fixture)))
+        .expect("missing recorded readelf output")
+}
+
+#[test]
+fn header_matches_readelf() {
+    let mut failures = Vec::new();
+    for fixture in OBJECTS.iter().chain(LINKED) {
+        let readelf = readelf_output(fixture);
+        let expected = readelf_header(&readelf);
+        let actual = report_fields(&run(&[fixture]));
+        let mut check = |what: &str, want: String, got: Option<&String>| {
+            if got != Some(&want) {
+                failures.push(format!(
+                    "{}: {}: readelf says {}, report says {:?}",
+                    fixture, what, want, got
+                ));
+            }
+        };
+
+        let class = match expected["Class"] {
+            "ELF64" => "64bit architecture",
+            _ => "32bit architecture",
+        };
+        check("class", class.to_string(), actual.get("Architecture"));
+        let data = if expected["Data"].contains("big endian") {
+            "Big endian"
+        } else {
+            "Little endian"
+        };
+        check("data", data.to_string(), actual.get("Endian"));
+        let file_type = match expected["Type"].split(' ').next() {
+            Some("REL") => "Relocatable file",
+            Some("EXEC") => "Executable file",
+            Some("DYN") => "Shared object file",
+            other => panic!("{}: unexpected type {:?}", fixture, other),
+        };
+        check("type", file_type.to_string(), actual.get("File Type"));
+
+        for (readelf_key, report_key) in NUMERIC_FIELDS {
+            let want = number(expected[readelf_key]).expect("unparsable readelf value");
+            let got = actual.get(*report_key).and_then(|value| number(value));
+            if got != Some(want) {
+                failures.push(format!(
+                    "{}: {}: readelf says {:#x}, report says {:?}",
+                    fixture, readelf_key, want, got
+                ));
+            }
+        }
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+/// The rows of the "Section Headers:" table as whitespace separated fields,
+/// without section 0.
+fn readelf_sections(readelf: &str) -> Vec<Vec<&str>> {
+    readelf
+        .lines()
+        .skip_while(|line| !line.starts_with("Section Headers:"))
+        .filter_map(|line| line.trim_start().strip_prefix('['))
+        .filter_map(|line| line.split_once(']'))
+        .filter(|(index, _)| index.trim() != "Nr" && index.trim() != "0")
+        .map(|(_, fields)| fields.split_whitespace().collect())
+        .collect()
+}
+
+/// Names of the undefined symbols in the recorded `.symtab`.
+fn readelf_undefined(readelf: &str) -> Vec<&str> {
+    readelf
+        .lines()
+        .skip_while(|line| !line.starts_with("Symbol table '.symtab'"))
+        .map(|line| line.split_whitespace().collect::<Vec<_>>())
+        .filter(|fields| fields.len() == 8 && fields[6] == "UND")
+        .map(|fields| fields[7])
+        .collect()
+}
+
+#[test]
+fn object_summary_matches_readelf() {
+    let mut failures = Vec::new();
+    for fixture in OBJECTS {
+        let readelf = readelf_output(fixture);
+        // Name, type, address, offset, size, entry size, flags, link, info,
+        // alignment: a section without flags has one field less.
+        let allocated = readelf_sections(&readelf)
+            .iter()
+            .filter(|fields| fields.len() == 10 && fields[6].contains('A'))
+            .count();
+        let undefined = readelf_undefined(&readelf);
+
+        let report = run(&["obj-summary", fixture]);
+        let fields = report_fields(&report);
+        let got = fields
+            .get("Allocated Sections")
+            .and_then(|value| number(value));
+        if got != Some(allocated as u64) {
+            failures.push(format!(
+                "{}: readelf lists {} allocated sections, report says {:?}",
+                fixture, allocated, got
+            ));
+        }
+        let got = fields
+            .get("Undefined Symbols")
+            .and_then(|value| number(value));
+        if got != Some(undefined.len() as u64) {
+            failures.push(format!(
+                "{}: readelf lists {} undefined symbols, report says {:?}",
+                fixture,
+                undefined.len(),
+                got
+            ));
+        }
+        for name in undefined {
+            if !report.lines().any(|line| line.trim() == name) {
+                failures.push(format!("{}: undefined symbol {} not listed", fixture, name));
+            }
+        }
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+#[test]
+fn selftest_passes() {
+    let output = run(&["selftest"]);
+    assert!(!output.contains("--- "), "{}", output);
+}